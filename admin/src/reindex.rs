@@ -3,13 +3,17 @@ use serde_json::Value;
 use std::process::ExitCode;
 
 use reqwest::StatusCode;
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector};
 use trustification_common::tls::ClientConfig;
+use trustification_infrastructure::endpoint::{self, Endpoint};
+use url::Url;
 
 /// Reindex
 #[derive(clap::Subcommand, Debug)]
 pub enum Reindex {
     Status(ReindexStatus),
     Start(ReindexStart),
+    Sbom(ReindexSbom),
 }
 
 impl Reindex {
@@ -17,6 +21,7 @@ impl Reindex {
         match self {
             Self::Start(run) => run.run().await,
             Self::Status(run) => run.run().await,
+            Self::Sbom(run) => run.run().await,
         }
     }
 }
@@ -78,3 +83,77 @@ impl ReindexStatus {
         Ok(ExitCode::SUCCESS)
     }
 }
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Replay the events of stored SBOMs through Bombastic, for an indexer to catch up",
+    args_conflicts_with_subcommands = true
+)]
+pub struct ReindexSbom {
+    /// URL of the Bombastic instance
+    #[arg(short = 'u', long = "url", default_value_t = endpoint::Bombastic::url())]
+    pub url: Url,
+
+    /// Only SBOMs with a key greater than or equal to this one
+    #[arg(long = "from")]
+    pub from: Option<String>,
+
+    /// Only SBOMs with a key less than this one
+    #[arg(long = "to")]
+    pub to: Option<String>,
+
+    /// Only SBOMs stored at or after this time, in RFC 3339 format
+    #[arg(long = "since")]
+    pub since: Option<String>,
+
+    /// Only SBOMs stored before this time, in RFC 3339 format
+    #[arg(long = "until")]
+    pub until: Option<String>,
+
+    /// OIDC parameters
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    /// Development mode
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+}
+
+impl ReindexSbom {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let client = self.client.build_client()?;
+        let provider = self.oidc.clone().into_provider_or_devmode(self.devmode).await?;
+
+        let query: Vec<_> = [
+            ("from", &self.from),
+            ("to", &self.to),
+            ("since", &self.since),
+            ("until", &self.until),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| (name, value)))
+        .collect();
+
+        let response = client
+            .post(self.url.join("api/v1/admin/reindex")?)
+            .query(&query)
+            .inject_token(provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if status != StatusCode::ACCEPTED {
+            println!("Error starting reindexing: {status}: {body}");
+            return Ok(ExitCode::FAILURE);
+        }
+
+        let status: Value = serde_json::from_str(&body)?;
+        println!("{}", to_colored_json_auto(&status)?);
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -5,7 +5,7 @@ use utoipa::IntoParams;
 
 use crate::Apply;
 
-#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, IntoParams)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize, IntoParams)]
 pub struct SearchOptions {
     /// Return index "explain" output
     #[serde(default)]
@@ -15,18 +15,64 @@ pub struct SearchOptions {
     pub metadata: bool,
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Comma separated list of facets to compute bucket counts for
+    #[serde(default, with = "comma_separated", skip_serializing_if = "Vec::is_empty")]
+    #[param(value_type = Option<String>)]
+    pub facets: Vec<String>,
+    /// Sort order of the results, in the form of `<field>:<asc|desc>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Highlight matched terms in result snippets using `<em>` markers
+    #[serde(default)]
+    pub highlight: bool,
+    /// Iterate the results using a cursor, instead of an offset. An empty value starts the iteration, following pages
+    /// are requested using the cursor returned with the previous page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Collapse results sharing the value of a field, like the identifier of an advisory, to the highest ranking one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_by: Option<String>,
 }
 
 const fn default_summaries() -> bool {
     true
 }
 
+/// Parameters of a date histogram query.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HistogramParams {
+    /// Search query string
+    #[serde(default)]
+    pub q: String,
+    /// Date the documents are bucketed by, the default date of the index if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Length of the intervals, like `7d`
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    /// Shift of the intervals from the epoch, like `4d` for weeks starting on Mondays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<String>,
+    /// Facet to split the intervals by, like `severity`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facet: Option<String>,
+}
+
+fn default_interval() -> String {
+    "7d".to_string()
+}
+
 impl Default for SearchOptions {
     fn default() -> Self {
         Self {
             explain: false,
             metadata: false,
             summaries: true,
+            facets: Vec::new(),
+            sort: None,
+            highlight: false,
+            cursor: None,
+            dedup_by: None,
         }
     }
 }
@@ -47,6 +93,53 @@ impl Apply<SearchOptions> for reqwest::RequestBuilder {
             self = self.query(&[("summaries", "true")]);
         }
 
+        if !options.facets.is_empty() {
+            self = self.query(&[("facets", options.facets.join(","))]);
+        }
+
+        if let Some(sort) = &options.sort {
+            self = self.query(&[("sort", sort)]);
+        }
+
+        if options.highlight {
+            self = self.query(&[("highlight", "true")]);
+        }
+
+        if let Some(cursor) = &options.cursor {
+            self = self.query(&[("cursor", cursor)]);
+        }
+
+        if let Some(dedup_by) = &options.dedup_by {
+            self = self.query(&[("dedup_by", dedup_by)]);
+        }
+
         self
     }
 }
+
+/// (De)serialize a list of values from a single, comma separated, string.
+///
+/// This is required for query parameters, which don't support sequences.
+pub mod comma_separated {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &[String], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.join(","))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Ok(value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+}
@@ -1,9 +1,137 @@
+use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 
+/// Bucket counts, by facet name.
+pub type Facets = BTreeMap<String, Vec<FacetCount>>;
+
+/// The number of matching documents for a single facet value.
+#[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: u64,
+}
+
+/// The number of matching documents for a single facet value, split by the values of another facet.
+#[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct FacetPivot {
+    pub value: String,
+    pub count: u64,
+    /// Bucket counts of the facet the value is split by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<FacetCount>,
+}
+
+/// The number of matching documents of a single interval of a histogram.
+#[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HistogramBucket {
+    /// Start of the interval, in RFC 3339 format
+    pub start: String,
+    pub count: u64,
+    /// Bucket counts of the facet the histogram is split by, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<FacetCount>,
+}
+
+/// Statistics of the values of a numeric field, over all matching documents having a value.
+#[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct NumericStats {
+    /// Number of values
+    pub count: u64,
+    pub sum: f64,
+    /// Smallest value, if there are any values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Largest value, if there are any values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Average of the values, if there are any values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg: Option<f64>,
+}
+
+/// The result of an aggregation over the matching documents.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationResult {
+    /// Bucket counts of a facet
+    Terms(Vec<FacetCount>),
+    /// Bucket counts of the intervals of a date field
+    DateHistogram(Vec<HistogramBucket>),
+    /// Statistics of a numeric field
+    Stats(NumericStats),
+}
+
+/// The type of the values of a search field.
+#[derive(utoipa::ToSchema, Copy, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchFieldType {
+    Text,
+    /// Dates, compared using `<`, `>` and ranges like `2023-01-01..2023-12-31`
+    Date,
+    /// Numbers, compared using `<`, `>` and ranges like `7..10`
+    Number,
+    Boolean,
+    /// Predicates, matched by `is:<name>` and negated by `-is:<name>`
+    Predicate,
+}
+
+/// A field which query terms can be qualified with, like `<name>:<value>`.
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct SearchField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: SearchFieldType,
+    /// Whether terms without a qualifier search this field
+    #[serde(default)]
+    pub default: bool,
+    /// Whether results can be sorted by this field, using `sort:<name>`
+    #[serde(default)]
+    pub sortable: bool,
+    /// An example query using the field
+    pub example: String,
+}
+
+impl SearchField {
+    pub fn new(name: impl Into<String>, r#type: SearchFieldType, example: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            r#type,
+            default: false,
+            sortable: false,
+            example: example.into(),
+        }
+    }
+
+    /// A predicate, with its example derived from its name.
+    pub fn predicate(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let example = format!("is:{name}");
+        Self::new(name, SearchFieldType::Predicate, example)
+    }
+
+    /// Search the field for terms without a qualifier.
+    pub fn with_default(mut self) -> Self {
+        self.default = true;
+        self
+    }
+
+    /// Allow sorting results by the field.
+    pub fn with_sortable(mut self) -> Self {
+        self.sortable = true;
+        self
+    }
+}
+
 #[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct SearchResult<T> {
     pub result: T,
     pub total: Option<usize>,
+    /// Bucket counts of the requested facets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub facets: BTreeMap<String, Vec<FacetCount>>,
+    /// Cursor for retrieving the next page, if results were requested using a cursor and more are available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
 
 impl<T> SearchResult<T> {
@@ -14,6 +142,8 @@ impl<T> SearchResult<T> {
         SearchResult {
             result: f(self.result),
             total: self.total,
+            facets: self.facets,
+            cursor: self.cursor,
         }
     }
 }
@@ -37,12 +167,19 @@ impl<T> From<(T, usize)> for SearchResult<T> {
         Self {
             result,
             total: Some(total),
+            facets: Default::default(),
+            cursor: None,
         }
     }
 }
 
 impl<T> From<T> for SearchResult<T> {
     fn from(result: T) -> Self {
-        Self { result, total: None }
+        Self {
+            result,
+            total: None,
+            facets: Default::default(),
+            cursor: None,
+        }
     }
 }
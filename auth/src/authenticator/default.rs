@@ -14,6 +14,9 @@ pub const DEFAULT_SCOPE_MAPPINGS: &[(&str, &[&str])] = &[
     ("read:document", &["read.sbom", "read.vex"]),
     ("update:document", &["update.sbom", "update.vex"]),
     ("delete:document", &["delete.sbom", "delete.vex"]),
+    ("admin:document", &["admin.sbom"]),
+    ("manage:webhook", &["manage.webhook"]),
+    ("manage:product", &["manage.product"]),
 ];
 
 /// A convenience function to get the default scopes in an allocated form.
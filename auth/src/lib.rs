@@ -17,6 +17,9 @@ pub enum Permission {
     UpdateSbom,
     #[serde(rename = "delete.sbom")]
     DeleteSbom,
+    /// Administration of SBOMs, like deleting all SBOMs matching a query
+    #[serde(rename = "admin.sbom")]
+    AdminSbom,
 
     #[serde(rename = "create.vex")]
     CreateVex,
@@ -32,6 +35,12 @@ pub enum Permission {
 
     #[serde(rename = "create.vulnerability")]
     IngestVulnerability,
+
+    #[serde(rename = "manage.webhook")]
+    ManageWebhooks,
+    /// Registration of products, and the SBOMs they are made of
+    #[serde(rename = "manage.product")]
+    ManageProducts,
 }
 
 impl AsRef<str> for Permission {
@@ -41,6 +50,7 @@ impl AsRef<str> for Permission {
             Self::ReadSbom => "read.sbom",
             Self::UpdateSbom => "update.sbom",
             Self::DeleteSbom => "delete.sbom",
+            Self::AdminSbom => "admin.sbom",
 
             Self::CreateVex => "create.vex",
             Self::ReadVex => "read.vex",
@@ -50,6 +60,9 @@ impl AsRef<str> for Permission {
             Self::ReadCve => "read.cve",
 
             Self::IngestVulnerability => "create.vulnerability",
+
+            Self::ManageWebhooks => "manage.webhook",
+            Self::ManageProducts => "manage.product",
         }
     }
 }
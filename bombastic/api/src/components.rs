@@ -0,0 +1,81 @@
+//! The components of recently listed SBOMs, so that paging through the components of an SBOM doesn't parse it for
+//! every page.
+//!
+//! Components are cached by the ETag of the stored SBOM, a replaced SBOM gets parsed again.
+
+use bombastic_model::components::SbomComponent;
+use std::{collections::VecDeque, sync::Arc, sync::Mutex};
+
+/// Number of SBOMs to keep the components of
+const CAPACITY: usize = 16;
+
+struct Entry {
+    id: String,
+    etag: String,
+    components: Arc<Vec<SbomComponent>>,
+}
+
+#[derive(Default)]
+pub struct ComponentCache {
+    entries: Mutex<VecDeque<Entry>>,
+}
+
+impl ComponentCache {
+    /// Get the components of an SBOM, if they were cached for the stored version of the SBOM.
+    pub fn get(&self, id: &str, etag: &str) -> Option<Arc<Vec<SbomComponent>>> {
+        let mut entries = self.entries.lock().unwrap();
+        let index = entries.iter().position(|entry| entry.id == id && entry.etag == etag)?;
+        // keep the recently used entries
+        let entry = entries.remove(index)?;
+        let components = entry.components.clone();
+        entries.push_front(entry);
+        Some(components)
+    }
+
+    /// Cache the components of a version of an SBOM, evicting the least recently used SBOM if the cache is full.
+    pub fn insert(&self, id: &str, etag: &str, components: Arc<Vec<SbomComponent>>) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| entry.id != id);
+        entries.truncate(CAPACITY - 1);
+        entries.push_front(Entry {
+            id: id.to_string(),
+            etag: etag.to_string(),
+            components,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components(name: &str) -> Arc<Vec<SbomComponent>> {
+        Arc::new(vec![SbomComponent {
+            reference: name.to_string(),
+            name: name.to_string(),
+            version: None,
+            purl: None,
+            licenses: vec![],
+            hashes: Default::default(),
+        }])
+    }
+
+    #[test]
+    fn test_cache() {
+        let cache = ComponentCache::default();
+        cache.insert("a", "1", components("a"));
+        assert_eq!(cache.get("a", "1"), Some(components("a")));
+        // a replaced SBOM has another ETag
+        assert_eq!(cache.get("a", "2"), None);
+        cache.insert("a", "2", components("b"));
+        assert_eq!(cache.get("a", "1"), None);
+        assert_eq!(cache.get("a", "2"), Some(components("b")));
+
+        for i in 0..CAPACITY {
+            cache.insert(&i.to_string(), "1", components("c"));
+        }
+        assert_eq!(cache.get("a", "2"), None);
+        assert!(cache.get("0", "1").is_some());
+        assert_eq!(cache.entries.lock().unwrap().len(), CAPACITY);
+    }
+}
@@ -1,7 +1,7 @@
 use actix_web::web;
 use bytesize::ByteSize;
 use prometheus::Registry;
-use std::{net::TcpListener, process::ExitCode, sync::Arc, time::Duration};
+use std::{fmt, net::TcpListener, process::ExitCode, sync::Arc, time::Duration};
 use tokio::task::block_in_place;
 use trustification_auth::{
     auth::AuthConfigArguments,
@@ -9,7 +9,7 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
-use trustification_index::{IndexConfig, IndexStore};
+use trustification_index::{require_unsharded, IndexConfig, IndexStore};
 use trustification_infrastructure::{
     app::http::BinaryByteSize,
     app::http::{HttpServerBuilder, HttpServerConfig},
@@ -19,8 +19,24 @@ use trustification_infrastructure::{
 };
 use trustification_storage::{Storage, StorageConfig};
 
+use crate::{
+    components::ComponentCache, reindex::Reindexer, retention::Retention, signature::SignatureVerifier,
+    vex::VexForwarder,
+};
+
+mod components;
+mod reindex;
+mod retention;
 mod sbom;
 mod server;
+mod signature;
+mod stats;
+mod vex;
+
+pub use reindex::ReindexConfig;
+pub use retention::RetentionConfig;
+pub use signature::SignatureConfig;
+pub use vex::VexConfig;
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the api server", args_conflicts_with_subcommands = true)]
@@ -34,6 +50,18 @@ pub struct Run {
     #[command(flatten)]
     pub storage: StorageConfig,
 
+    #[command(flatten)]
+    pub signature: SignatureConfig,
+
+    #[command(flatten)]
+    pub retention: RetentionConfig,
+
+    #[command(flatten)]
+    pub reindex: ReindexConfig,
+
+    #[command(flatten)]
+    pub vex: VexConfig,
+
     #[command(flatten)]
     pub infra: InfrastructureConfig,
 
@@ -49,12 +77,53 @@ pub struct Run {
     /// Request limit for publish requests
     #[arg(long, default_value_t = ByteSize::mib(64).into())]
     pub publish_limit: BinaryByteSize,
+
+    /// Limit for published SBOMs once decompressed, guarding against documents compressing with a very high ratio
+    #[arg(long, default_value_t = ByteSize::mib(256).into())]
+    pub decompressed_limit: BinaryByteSize,
+
+    /// Handling of published SBOMs which are exact duplicates of a stored SBOM
+    #[arg(long, env = "DEDUPLICATION", default_value_t = Deduplication::Disabled)]
+    pub deduplication: Deduplication,
+
+    /// Number of previous revisions kept of a replaced SBOM
+    #[arg(long, env = "KEEP_REVISIONS", default_value_t = 5)]
+    pub keep_revisions: usize,
+}
+
+/// Handling of published SBOMs which are exact duplicates of a stored SBOM, by their canonical digest.
+#[derive(clap::ValueEnum, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Deduplication {
+    /// Store all SBOMs
+    #[default]
+    #[clap(name = "disabled")]
+    Disabled,
+    /// Reject duplicates, with a conflict
+    #[clap(name = "reject")]
+    Reject,
+    /// Don't store duplicates, but return the identifier of the stored SBOM
+    #[clap(name = "link")]
+    Link,
+}
+
+impl fmt::Display for Deduplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deduplication::Disabled => write!(f, "disabled"),
+            Deduplication::Reject => write!(f, "reject"),
+            Deduplication::Link => write!(f, "link"),
+        }
+    }
 }
 
 impl Run {
     pub async fn run(self, listener: Option<TcpListener>) -> anyhow::Result<ExitCode> {
         let index = self.index;
         let storage = self.storage;
+        let signature = SignatureVerifier::try_from(self.signature)?;
+        let retention = self.retention;
+        let reindex = self.reindex;
+        let vex = self.vex;
 
         let (authn, authz) = self.auth.split(self.devmode)?.unzip();
         let authenticator: Option<Arc<Authenticator>> = Authenticator::from_config(authn).await?.map(Arc::new);
@@ -71,6 +140,9 @@ impl Run {
 
         let tracing = self.infra.tracing;
         let publish_limit = self.publish_limit.as_u64() as usize;
+        let decompressed_limit = self.decompressed_limit.as_u64() as usize;
+        let deduplication = self.deduplication;
+        let keep_revisions = self.keep_revisions;
 
         Infrastructure::from(self.infra)
             .run(
@@ -86,6 +158,8 @@ impl Run {
                         .liveness
                         .register("available.index", available_check)
                         .await;
+                    let reindexer = Reindexer::new(reindex, context.metrics.registry()).await?;
+                    let vex = VexForwarder::new(vex, self.devmode, context.metrics.registry()).await?;
                     let state = Self::configure(
                         index,
                         storage,
@@ -93,8 +167,18 @@ impl Run {
                         available_probe,
                         context.metrics.registry(),
                         self.devmode,
+                        publish_limit,
+                        decompressed_limit,
+                        deduplication,
+                        keep_revisions,
+                        signature,
+                        reindexer,
+                        vex,
                     )?;
 
+                    let retention = Retention::new(retention, context.metrics.registry())?;
+                    tokio::task::spawn(retention.run(state.clone()));
+
                     let mut http = HttpServerBuilder::try_from(self.http)?
                         .tracing(tracing)
                         .metrics(context.metrics.registry().clone(), "bombastic_api")
@@ -120,6 +204,7 @@ impl Run {
         Ok(ExitCode::SUCCESS)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn configure(
         index_config: IndexConfig,
         storage: StorageConfig,
@@ -127,15 +212,29 @@ impl Run {
         available_probe: Probe,
         registry: &Registry,
         devmode: bool,
+        publish_limit: usize,
+        decompressed_limit: usize,
+        deduplication: Deduplication,
+        keep_revisions: usize,
+        signature: SignatureVerifier,
+        reindexer: Reindexer,
+        vex: Option<VexForwarder>,
     ) -> anyhow::Result<Arc<AppState>> {
-        let sbom_index =
-            block_in_place(|| IndexStore::new(&storage, &index_config, bombastic_index::sbom::Index::new(), registry))?;
+        require_unsharded(&index_config, "bombastic")?;
+        let sbom_index = block_in_place(|| {
+            IndexStore::new(
+                &storage,
+                &index_config,
+                bombastic_index::sbom::Index::with_tokenizers(&index_config.tokenizers),
+                registry,
+            )
+        })?;
 
         let package_index = block_in_place(|| {
             IndexStore::new(
                 &storage,
                 &index_config,
-                bombastic_index::packages::Index::new(),
+                bombastic_index::packages::Index::with_tokenizers(&index_config.tokenizers),
                 registry,
             )
         })?;
@@ -146,6 +245,14 @@ impl Run {
             storage,
             sbom_index,
             package_index,
+            publish_limit,
+            decompressed_limit,
+            deduplication,
+            keep_revisions,
+            signature,
+            reindexer,
+            vex,
+            components: ComponentCache::default(),
         });
 
         let sinker = state.clone();
@@ -199,10 +306,24 @@ pub struct AppState {
     storage: Storage,
     sbom_index: SbomIndex,
     package_index: PackageIndex,
+    publish_limit: usize,
+    decompressed_limit: usize,
+    deduplication: Deduplication,
+    keep_revisions: usize,
+    signature: SignatureVerifier,
+    reindexer: Reindexer,
+    vex: Option<VexForwarder>,
+    components: ComponentCache,
 }
 
 pub(crate) type SharedState = Arc<AppState>;
 
+/// Name of this instance, telling it apart from the other instances sharing the storage
+pub(crate) fn instance_name() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "bombastic-api".to_string());
+    format!("{host}-{}", std::process::id())
+}
+
 impl AppState {
     async fn sync_index(&self) -> Result<(), anyhow::Error> {
         let storage = &self.storage;
@@ -210,4 +331,26 @@ impl AppState {
         self.package_index.sync(storage).await?;
         Ok(())
     }
+
+    /// Delete a stored SBOM, along with its labels and revisions, the record of its digest, and the advisory of its
+    /// embedded vulnerabilities.
+    async fn delete_sbom(&self, id: &str) -> Result<(), trustification_storage::Error> {
+        // the digest is only known from the data, and only recorded when deduplicating
+        let digest = match self.deduplication {
+            Deduplication::Disabled => None,
+            _ => match self.storage.get_data(id).await {
+                Ok(data) => bombastic_model::digest::canonical_digest(&data).ok(),
+                Err(trustification_storage::Error::NotFound) => None,
+                Err(e) => return Err(e),
+            },
+        };
+        self.storage.delete(id).await?;
+        if let Some(digest) = digest {
+            self.storage.delete_digest(&digest, id).await?;
+        }
+        if let Some(vex) = &self.vex {
+            vex.retract(id).await;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+//! Reindexing of all stored SBOMs, triggered by an administrator.
+//!
+//! The API doesn't index SBOMs itself: for each stored SBOM, it publishes an event of storing it on the topic of
+//! stored documents, just like the storage notifies about a published SBOM. The indexer then fetches and indexes the
+//! SBOM again, which rebuilds the index after changes of its schema, without restarting the indexer. The events are
+//! marked as replaying stored SBOMs, so that consumers of indexed SBOMs don't notify about them again.
+//!
+//! Reindexing can be limited to a range of keys, or to the SBOMs stored within a time window. That replays the events
+//! a lagging indexer missed, or which a rebuilt indexer needs, without it having to access the storage directly.
+//!
+//! The progress is stored along with the SBOMs, so that all instances report the same status, and only one of them
+//! reindexes at a time. Reindexing which made no progress for a while was abandoned by a stopped instance, and may be
+//! started again.
+
+use crate::SharedState;
+use bombastic_model::prelude::{ReindexSelection, ReindexState, ReindexStatus};
+use prometheus::{opts, register_int_counter_with_registry, IntCounter, Registry};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use trustification_event_bus::{EventBus, EventBusConfig};
+use trustification_storage::Error as StorageError;
+
+/// Log the progress every this many published events
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// Name of the status holding the progress of reindexing
+const STATUS: &str = "bombastic-reindex";
+
+/// Time to wait for other instances starting reindexing at the same time, before checking who got to do it
+const SETTLE: Duration = Duration::from_secs(5);
+
+/// Interval of storing the progress while publishing events
+const HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Time without progress after which reindexing is considered abandoned
+const ABANDONED: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(next_help_heading = "Reindex")]
+pub struct ReindexConfig {
+    /// Topic of stored SBOMs, which the indexer consumes
+    #[arg(long = "stored-topic", default_value = "sbom-stored")]
+    pub stored_topic: String,
+
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+}
+
+/// The progress of reindexing, as stored by the instance publishing the events
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SharedStatus {
+    holder: String,
+    /// Unix timestamp of the last time the holder stored the progress
+    updated: i64,
+    #[serde(flatten)]
+    status: ReindexStatus,
+}
+
+impl SharedStatus {
+    /// Check if running reindexing was abandoned by its holder, as it made no progress for a while
+    fn is_abandoned(&self, now: OffsetDateTime) -> bool {
+        self.status.state == ReindexState::Running && self.updated + ABANDONED.as_secs() as i64 <= now.unix_timestamp()
+    }
+
+    /// The progress of reindexing, failed if it was abandoned
+    fn into_status(self, now: OffsetDateTime) -> ReindexStatus {
+        match self.is_abandoned(now) {
+            true => ReindexStatus {
+                state: ReindexState::Failed,
+                error: Some(format!("Reindexing was abandoned by instance {}", self.holder)),
+                ..self.status
+            },
+            false => self.status,
+        }
+    }
+}
+
+pub struct Reindexer {
+    bus: EventBus,
+    stored_topic: String,
+    /// Name of this instance, holding the status while it publishes events
+    instance: String,
+    published_total: IntCounter,
+}
+
+impl Reindexer {
+    pub async fn new(config: ReindexConfig, registry: &Registry) -> anyhow::Result<Self> {
+        let published_total = register_int_counter_with_registry!(
+            opts!(
+                "bombastic_reindex_published_total",
+                "Total number of events published for reindexing SBOMs"
+            ),
+            registry
+        )?;
+        Ok(Self {
+            bus: config.bus.create(registry).await?,
+            stored_topic: config.stored_topic,
+            instance: crate::instance_name(),
+            published_total,
+        })
+    }
+
+    /// The progress of the current, or the last, reindexing, by any instance
+    pub async fn status(&self, state: &SharedState) -> Result<ReindexStatus, StorageError> {
+        Ok(self
+            .load(state)
+            .await?
+            .map(|shared| shared.into_status(OffsetDateTime::now_utc()))
+            .unwrap_or_default())
+    }
+
+    /// Start reindexing the selected stored SBOMs in the background, returning `None` if reindexing is already running.
+    pub async fn start(
+        state: &SharedState,
+        selection: ReindexSelection,
+    ) -> Result<Option<ReindexStatus>, StorageError> {
+        let reindexer = &state.reindexer;
+        let now = OffsetDateTime::now_utc();
+        if let Some(current) = reindexer.load(state).await? {
+            if current.status.state == ReindexState::Running && !current.is_abandoned(now) {
+                return Ok(None);
+            }
+        }
+
+        let mut shared = SharedStatus {
+            holder: reindexer.instance.clone(),
+            updated: now.unix_timestamp(),
+            status: ReindexStatus {
+                state: ReindexState::Running,
+                selection,
+                ..Default::default()
+            },
+        };
+        reindexer.store(state, &shared).await?;
+        // of instances starting reindexing at the same time, the last one storing the status publishes the events
+        tokio::time::sleep(SETTLE).await;
+        if reindexer
+            .load(state)
+            .await?
+            .map_or(true, |current| current.holder != reindexer.instance)
+        {
+            return Ok(None);
+        }
+
+        let status = shared.status.clone();
+        let state = state.clone();
+        tokio::task::spawn(async move {
+            let reindexer = &state.reindexer;
+            match reindexer.publish(&state, &mut shared).await {
+                Ok(published) => {
+                    log::info!("Published events for reindexing {published} SBOMs");
+                    shared.status.state = ReindexState::Completed;
+                }
+                Err(e) => {
+                    log::warn!("Unable to reindex SBOMs: {e}");
+                    shared.status.state = ReindexState::Failed;
+                    shared.status.error = Some(e.to_string());
+                }
+            }
+            if let Err(e) = reindexer.update(&state, &mut shared).await {
+                log::warn!("Unable to store the result of reindexing: {e}");
+            }
+        });
+        Ok(Some(status))
+    }
+
+    /// Publish an event for each selected stored SBOM, returning the number of events published.
+    async fn publish(&self, state: &SharedState, shared: &mut SharedStatus) -> anyhow::Result<usize> {
+        let selection = shared.status.selection.clone();
+        let keys: Vec<_> = state
+            .storage
+            .list_keys()
+            .await?
+            .into_iter()
+            .filter(|(key, last_modified)| selection.matches(key, OffsetDateTime::parse(last_modified, &Rfc3339).ok()))
+            .collect();
+        log::info!("Reindexing {} SBOMs", keys.len());
+        shared.status.total = keys.len();
+        self.update(state, shared).await?;
+
+        let mut updated = Instant::now();
+        for (published, (key, _)) in keys.iter().enumerate() {
+            let event = serde_json::to_vec(&state.storage.reindex_event(key))?;
+            self.bus.send(&self.stored_topic, &event).await?;
+            self.published_total.inc();
+            shared.status.published = published + 1;
+            if updated.elapsed() >= HEARTBEAT {
+                self.update(state, shared).await?;
+                updated = Instant::now();
+            }
+            if (published + 1) % PROGRESS_INTERVAL == 0 {
+                log::info!(
+                    "Published events for reindexing {} of {} SBOMs",
+                    published + 1,
+                    keys.len()
+                );
+            }
+        }
+        Ok(keys.len())
+    }
+
+    /// Store the progress of this instance, failing if another instance took over reindexing in the meantime.
+    async fn update(&self, state: &SharedState, shared: &mut SharedStatus) -> anyhow::Result<()> {
+        if let Some(current) = self.load(state).await? {
+            if current.holder != self.instance {
+                anyhow::bail!("Reindexing was taken over by instance {}", current.holder);
+            }
+        }
+        shared.updated = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(self.store(state, shared).await?)
+    }
+
+    /// The stored progress, an unreadable status is considered missing
+    async fn load(&self, state: &SharedState) -> Result<Option<SharedStatus>, StorageError> {
+        Ok(state
+            .storage
+            .get_status(STATUS)
+            .await?
+            .and_then(|status| serde_json::from_value(status).ok()))
+    }
+
+    async fn store(&self, state: &SharedState, shared: &SharedStatus) -> Result<(), StorageError> {
+        let status = serde_json::to_value(shared).map_err(|_| StorageError::Internal)?;
+        state.storage.put_status(STATUS, &status).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abandoned() {
+        let now = OffsetDateTime::parse("2024-01-31T00:00:00Z", &Rfc3339).unwrap();
+        let shared = |state: ReindexState, updated: OffsetDateTime| SharedStatus {
+            holder: "bombastic-api-1".to_string(),
+            updated: updated.unix_timestamp(),
+            status: ReindexStatus {
+                state,
+                ..Default::default()
+            },
+        };
+
+        assert!(!shared(ReindexState::Running, now - HEARTBEAT).is_abandoned(now));
+        assert!(shared(ReindexState::Running, now - ABANDONED).is_abandoned(now));
+        // only running reindexing is abandoned
+        assert!(!shared(ReindexState::Completed, now - ABANDONED).is_abandoned(now));
+
+        let status = shared(ReindexState::Running, now - ABANDONED).into_status(now);
+        assert_eq!(status.state, ReindexState::Failed);
+        assert_eq!(
+            status.error.as_deref(),
+            Some("Reindexing was abandoned by instance bombastic-api-1")
+        );
+
+        // the progress is stored along with the status of the model
+        let stored = serde_json::to_value(shared(ReindexState::Running, now)).unwrap();
+        assert_eq!(stored["state"], "running");
+        assert_eq!(stored["holder"], "bombastic-api-1");
+        assert_eq!(
+            serde_json::from_value::<SharedStatus>(stored).unwrap(),
+            shared(ReindexState::Running, now)
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Retention of stored SBOMs, deleting SBOMs once they reached the age of a rule.
+//!
+//! Rules apply to all SBOMs, or to SBOMs with a label, and are evaluated in the order they were configured: the first
+//! rule matching an SBOM decides on its time to live. SBOMs not matching any rule are kept. The age of an SBOM is the
+//! time since it was last stored.
+//!
+//! Expired SBOMs are deleted from the storage, just like deleting them through the API: the indexer removes them from
+//! the index when it receives the storage event, and emits the event on the topic for deleted documents.
+//!
+//! Only a single instance applies the rules at a time, the one holding the lease stored along with the SBOMs. The lease
+//! is renewed by its holder on every evaluation, and taken over by another instance once it expired.
+
+use crate::SharedState;
+use prometheus::{opts, register_int_counter_with_registry, IntCounter, Registry};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr, time::Duration};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use trustification_storage::Labels;
+
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(next_help_heading = "Retention")]
+pub struct RetentionConfig {
+    /// Retention rules of stored SBOMs, in the form of `<selector>:<ttl>`, like `source=ci:90d`. The selector is either
+    /// `*` for all SBOMs, a label key, or a label key and value. The first matching rule applies.
+    #[arg(long = "retention-rule", env = "RETENTION_RULES", value_delimiter = ',')]
+    pub rules: Vec<RetentionRule>,
+
+    /// Interval of evaluating the retention rules
+    #[arg(long = "retention-interval", env = "RETENTION_INTERVAL", default_value = "1h")]
+    pub interval: humantime::Duration,
+}
+
+/// The SBOMs a rule applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selector {
+    All,
+    Label { key: String, value: Option<String> },
+}
+
+impl Selector {
+    fn matches(&self, labels: &Labels) -> bool {
+        match self {
+            Self::All => true,
+            Self::Label { key, value: None } => labels.contains_key(key),
+            Self::Label {
+                key,
+                value: Some(value),
+            } => labels.get(key) == Some(value),
+        }
+    }
+}
+
+/// A rule deleting the SBOMs it selects once they reached its time to live
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetentionRule {
+    pub selector: Selector,
+    pub ttl: Duration,
+}
+
+impl FromStr for RetentionRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, ttl) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("missing time to live of retention rule: '{s}'"))?;
+        let ttl = humantime::parse_duration(ttl).map_err(|e| format!("invalid time to live '{ttl}': {e}"))?;
+        let selector = match selector.split_once('=') {
+            _ if selector == "*" => Selector::All,
+            _ if selector.is_empty() => return Err(format!("missing selector of retention rule: '{s}'")),
+            Some((key, value)) => Selector::Label {
+                key: key.to_string(),
+                value: Some(value.to_string()),
+            },
+            None => Selector::Label {
+                key: selector.to_string(),
+                value: None,
+            },
+        };
+        Ok(Self { selector, ttl })
+    }
+}
+
+impl fmt::Display for RetentionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.selector {
+            Selector::All => write!(f, "*")?,
+            Selector::Label { key, value: None } => write!(f, "{key}")?,
+            Selector::Label {
+                key,
+                value: Some(value),
+            } => write!(f, "{key}={value}")?,
+        }
+        write!(f, ":{}", humantime::format_duration(self.ttl))
+    }
+}
+
+/// Name of the status holding the lease of the instance applying the rules
+const LEASE: &str = "bombastic-retention";
+
+/// Time to wait for other instances acquiring the lease at the same time, before checking who got it
+const SETTLE: Duration = Duration::from_secs(5);
+
+/// The lease of the instance applying the rules
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Lease {
+    holder: String,
+    /// Unix timestamp of the expiration of the lease
+    expires: i64,
+}
+
+pub struct Retention {
+    rules: Vec<RetentionRule>,
+    interval: Duration,
+    /// Name of this instance, holding the lease
+    instance: String,
+    deleted_total: IntCounter,
+}
+
+impl Retention {
+    pub fn new(config: RetentionConfig, registry: &Registry) -> anyhow::Result<Self> {
+        let deleted_total = register_int_counter_with_registry!(
+            opts!(
+                "bombastic_retention_deleted_total",
+                "Total number of SBOMs deleted by retention rules"
+            ),
+            registry
+        )?;
+        Ok(Self {
+            rules: config.rules,
+            interval: config.interval.into(),
+            instance: crate::instance_name(),
+            deleted_total,
+        })
+    }
+
+    /// Evaluate the rules periodically, as long as the process runs. The task isn't stopped by dropping its handle.
+    pub async fn run(self, state: SharedState) {
+        if self.rules.is_empty() {
+            return;
+        }
+        let rules: Vec<_> = self.rules.iter().map(ToString::to_string).collect();
+        log::info!("Retention of SBOMs by the rules: {}", rules.join(", "));
+
+        loop {
+            match self.acquire(&state, OffsetDateTime::now_utc()).await {
+                Ok(true) => match self.apply(&state, OffsetDateTime::now_utc()).await {
+                    Ok(0) => {}
+                    Ok(deleted) => log::info!("Deleted {deleted} expired SBOMs"),
+                    Err(e) => log::warn!("Unable to apply retention rules: {e}"),
+                },
+                Ok(false) => log::debug!("Retention rules are applied by another instance"),
+                Err(e) => log::warn!("Unable to acquire the retention lease: {e}"),
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    /// Acquire or renew the lease of applying the rules, returning whether this instance holds it.
+    async fn acquire(&self, state: &SharedState, now: OffsetDateTime) -> anyhow::Result<bool> {
+        let current = self.lease(state).await?;
+        if !self.may_acquire(current.as_ref(), now) {
+            return Ok(false);
+        }
+
+        let lease = Lease {
+            holder: self.instance.clone(),
+            // the lease outlives a missed evaluation
+            expires: (now + 2 * self.interval).unix_timestamp(),
+        };
+        state.storage.put_status(LEASE, &serde_json::to_value(&lease)?).await?;
+        // of instances acquiring the lease at the same time, the last one storing it holds it
+        tokio::time::sleep(SETTLE).await;
+        Ok(self
+            .lease(state)
+            .await?
+            .is_some_and(|lease| lease.holder == self.instance))
+    }
+
+    /// The stored lease, an unreadable lease is considered expired
+    async fn lease(&self, state: &SharedState) -> anyhow::Result<Option<Lease>> {
+        Ok(state
+            .storage
+            .get_status(LEASE)
+            .await?
+            .and_then(|lease| serde_json::from_value(lease).ok()))
+    }
+
+    /// Check if this instance may acquire the lease, which it holds already, or which expired
+    fn may_acquire(&self, lease: Option<&Lease>, now: OffsetDateTime) -> bool {
+        lease.map_or(true, |lease| {
+            lease.holder == self.instance || lease.expires <= now.unix_timestamp()
+        })
+    }
+
+    /// Delete all SBOMs which expired at a time, returning the number of SBOMs deleted.
+    async fn apply(&self, state: &SharedState, now: OffsetDateTime) -> anyhow::Result<usize> {
+        // no rule applies to SBOMs younger than the shortest time to live, so skip fetching their labels
+        let Some(min_ttl) = self.rules.iter().map(|rule| rule.ttl).min() else {
+            return Ok(0);
+        };
+
+        let mut deleted = 0;
+        for (key, last_modified) in state.storage.list_keys().await? {
+            let Some(age) = age(&last_modified, now) else {
+                log::debug!("Skipping retention of {key}, unknown modification time: {last_modified}");
+                continue;
+            };
+            if age < min_ttl {
+                continue;
+            }
+
+            let labels = state.storage.get_labels(&key).await?;
+            if self.is_expired(&labels, age) {
+                log::info!("Deleting expired SBOM {key}, last modified {last_modified}");
+                state.delete_sbom(&key).await?;
+                self.deleted_total.inc();
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Check if an SBOM of an age expired, by the first rule selecting it
+    fn is_expired(&self, labels: &Labels, age: Duration) -> bool {
+        self.rules
+            .iter()
+            .find(|rule| rule.selector.matches(labels))
+            .is_some_and(|rule| age >= rule.ttl)
+    }
+}
+
+fn age(last_modified: &str, now: OffsetDateTime) -> Option<Duration> {
+    let last_modified = OffsetDateTime::parse(last_modified, &Rfc3339).ok()?;
+    (now - last_modified).try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retention(rules: &[&str]) -> Retention {
+        Retention {
+            rules: rules.iter().map(|rule| rule.parse().unwrap()).collect(),
+            interval: Duration::from_secs(3600),
+            instance: "bombastic-api-1".to_string(),
+            deleted_total: IntCounter::new("deleted", "deleted").unwrap(),
+        }
+    }
+
+    fn labels(labels: &[(&str, &str)]) -> Labels {
+        labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: RetentionRule = "source=ci:90d".parse().unwrap();
+        assert_eq!(
+            rule.selector,
+            Selector::Label {
+                key: "source".to_string(),
+                value: Some("ci".to_string())
+            }
+        );
+        assert_eq!(rule.ttl, 90 * DAY);
+        assert_eq!(rule.to_string().parse::<RetentionRule>(), Ok(rule));
+
+        assert_eq!("*:1y".parse::<RetentionRule>().unwrap().selector, Selector::All);
+        assert!("source=ci".parse::<RetentionRule>().is_err());
+        assert!(":90d".parse::<RetentionRule>().is_err());
+        assert!("source:ninety days".parse::<RetentionRule>().is_err());
+    }
+
+    #[test]
+    fn test_first_rule_applies() {
+        let retention = retention(&["source=ci:90d", "temporary:1d", "*:365d"]);
+
+        let ci = labels(&[("source", "ci"), ("temporary", "true")]);
+        assert!(!retention.is_expired(&ci, 30 * DAY));
+        assert!(retention.is_expired(&ci, 90 * DAY));
+
+        let temporary = labels(&[("temporary", "true")]);
+        assert!(retention.is_expired(&temporary, 2 * DAY));
+
+        assert!(!retention.is_expired(&labels(&[]), 200 * DAY));
+        assert!(retention.is_expired(&labels(&[]), 400 * DAY));
+
+        // without a global rule, SBOMs not selected are kept
+        let retention = self::retention(&["source=ci:90d"]);
+        assert!(!retention.is_expired(&labels(&[("source", "release")]), 400 * DAY));
+    }
+
+    #[test]
+    fn test_may_acquire() {
+        let retention = retention(&["*:1d"]);
+        let now = OffsetDateTime::parse("2024-01-31T00:00:00Z", &Rfc3339).unwrap();
+        let lease = |holder: &str, expires: OffsetDateTime| Lease {
+            holder: holder.to_string(),
+            expires: expires.unix_timestamp(),
+        };
+
+        assert!(retention.may_acquire(None, now));
+        assert!(retention.may_acquire(Some(&lease("bombastic-api-1", now + DAY)), now));
+        assert!(!retention.may_acquire(Some(&lease("bombastic-api-2", now + DAY)), now));
+        assert!(retention.may_acquire(Some(&lease("bombastic-api-2", now)), now));
+    }
+
+    #[test]
+    fn test_age() {
+        let now = OffsetDateTime::parse("2024-01-31T00:00:00Z", &Rfc3339).unwrap();
+        assert_eq!(age("2024-01-01T00:00:00.000Z", now), Some(30 * DAY));
+        // modified in the future, by a skewed clock
+        assert_eq!(age("2024-02-01T00:00:00.000Z", now), None);
+        assert_eq!(age("yesterday", now), None);
+    }
+}
@@ -1,7 +1,13 @@
+use std::future::Future;
 use std::io::{self};
 use std::sync::Arc;
 
-use crate::SharedState;
+use crate::{
+    reindex::Reindexer,
+    signature::{Signature, BUNDLE_HEADER, SIGNATURE_HEADER},
+    stats::{storage_stats, DocumentSize, SbomStats},
+    Deduplication, SharedState,
+};
 use actix_web::{
     delete,
     error::{self, PayloadError},
@@ -10,13 +16,21 @@ use actix_web::{
         header::{self, Accept, AcceptEncoding, ContentType, Encoding, HeaderValue, CONTENT_ENCODING},
         Method, StatusCode,
     },
-    web, HttpRequest, HttpResponse, Responder,
+    patch, post, put, web, HttpRequest, HttpResponse, Responder,
+};
+use bombastic_model::{
+    convert::{convert, Format},
+    digest,
+    merge::{merge, MergeRequest},
+    prelude::*,
+    protobuf,
+    signature::{self, Verification},
 };
-use bombastic_model::prelude::*;
 use derive_more::{Display, Error, From};
 use futures::TryStreamExt;
 use serde::Deserialize;
-use trustification_api::search::SearchOptions;
+use std::collections::{BTreeMap, BTreeSet};
+use trustification_api::search::{HistogramBucket, HistogramParams, SearchField, SearchOptions};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
@@ -25,13 +39,61 @@ use trustification_auth::{
 };
 use trustification_index::Error as IndexError;
 use trustification_infrastructure::new_auth;
-use trustification_storage::{Error as StorageError, S3Path};
+use trustification_storage::{Error as StorageError, Labels, S3Path, Upload};
 use utoipa::OpenApi;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(query_sbom, publish_sbom, search_sbom, delete_sbom, search_package),
-    components(schemas(SearchDocument, SearchResult, SearchPackageDocument, SearchPackageResult),)
+    paths(
+        query_sbom,
+        convert_sbom_format,
+        merge_sboms,
+        publish_sbom,
+        get_sbom_components,
+        get_sbom_stats,
+        get_sbom_versions,
+        get_sbom_version,
+        initiate_sbom_upload,
+        get_sbom_upload,
+        put_sbom_upload_part,
+        complete_sbom_upload,
+        abort_sbom_upload,
+        search_sbom,
+        search_sbom_fields,
+        sbom_histogram,
+        delete_sbom,
+        delete_sboms,
+        update_sbom_labels,
+        get_sbom_attestations,
+        put_sbom_attestations,
+        search_package,
+        start_reindex,
+        get_reindex
+    ),
+    components(schemas(
+        SearchDocument,
+        SearchResult,
+        SearchPackageDocument,
+        SearchPackageResult,
+        Problem,
+        Violation,
+        UploadSession,
+        UploadPart,
+        SbomComponent,
+        SbomComponentResult,
+        DeleteResult,
+        MergeRequest,
+        SbomStats,
+        DocumentSize,
+        ReindexStatus,
+        ReindexState,
+        ReindexSelection,
+        bombastic_model::quality::Quality,
+        trustification_api::search::FacetCount,
+        trustification_api::search::HistogramBucket,
+        trustification_api::search::SearchField,
+        trustification_api::search::SearchFieldType
+    ),)
 )]
 pub struct ApiDoc;
 
@@ -46,6 +108,8 @@ pub fn config(
             .wrap(new_auth!(auth))
             .service(query_sbom)
             .service(search_sbom)
+            .service(search_sbom_fields)
+            .service(sbom_histogram)
             .service(search_package)
             .service(
                 web::resource("/sbom")
@@ -53,8 +117,32 @@ pub fn config(
                     .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
                     .to(publish_sbom),
             )
+            .service(
+                web::resource("/sbom/convert")
+                    .app_data(web::PayloadConfig::new(publish_limit))
+                    .route(web::post().to(convert_sbom_format)),
+            )
+            .service(merge_sboms)
+            .service(get_sbom_components)
+            .service(get_sbom_stats)
+            .service(get_sbom_versions)
+            .service(get_sbom_version)
+            .service(initiate_sbom_upload)
+            .service(get_sbom_upload)
+            .service(complete_sbom_upload)
+            .service(
+                web::resource("/sbom/upload/{upload}/{part}")
+                    .app_data(web::PayloadConfig::new(publish_limit))
+                    .route(web::put().to(put_sbom_upload_part)),
+            )
+            .service(abort_sbom_upload)
             .service(delete_sbom)
-            .service(delete_sboms),
+            .service(delete_sboms)
+            .service(update_sbom_labels)
+            .service(get_sbom_attestations)
+            .service(put_sbom_attestations)
+            .service(start_reindex)
+            .service(get_reindex),
     )
     .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
 }
@@ -71,10 +159,42 @@ enum Error {
     InvalidContentType,
     #[display(fmt = "invalid encoding, see Accept-Encoding header")]
     InvalidContentEncoding,
+    #[display(fmt = "invalid label key: '{}'", "_0")]
+    #[from(ignore)]
+    InvalidLabel(#[error(not(source))] String),
+    #[display(fmt = "invalid attestation: {}", "_0")]
+    #[from(ignore)]
+    InvalidAttestation(#[error(not(source))] String),
+    #[display(fmt = "unable to parse SBOM: {}", "_0")]
+    #[from(ignore)]
+    UnparsableSbom(#[error(not(source))] String),
+    #[display(fmt = "duplicate of SBOM: '{}'", "_0")]
+    #[from(ignore)]
+    Duplicate(#[error(not(source))] String),
+    #[display(fmt = "invalid part number: {}", "_0")]
+    #[from(ignore)]
+    InvalidPartNumber(#[error(not(source))] u32),
+    #[display(fmt = "invalid signature header: {}", "_0")]
+    #[from(ignore)]
+    InvalidSignature(#[error(not(source))] String),
+    #[display(fmt = "missing id or query")]
+    MissingIdentifier,
+    #[display(fmt = "either id or query must be given, not both")]
+    AmbiguousIdentifier,
+    #[display(fmt = "no SBOMs to merge")]
+    NothingToMerge,
+    #[display(fmt = "reindexing is already running")]
+    ReindexRunning,
 }
 
 impl error::ResponseError for Error {
     fn error_response(&self) -> HttpResponse {
+        if let Self::Storage(StorageError::InvalidSbom(violations)) = self {
+            return HttpResponse::BadRequest()
+                .content_type("application/problem+json")
+                .json(Problem::invalid_sbom(violations.clone()));
+        }
+
         let mut res = HttpResponse::build(self.status_code());
         res.insert_header(ContentType::plaintext());
         match self {
@@ -98,8 +218,28 @@ impl error::ResponseError for Error {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
             Self::Storage(StorageError::InvalidContent) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::InvalidSbom(_)) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::IncompleteUpload(_)) => StatusCode::BAD_REQUEST,
+            Self::Storage(StorageError::TooLarge(_)) => StatusCode::PAYLOAD_TOO_LARGE,
             Self::InvalidContentType | Self::InvalidContentEncoding => StatusCode::BAD_REQUEST,
+            Self::InvalidLabel(_) | Self::InvalidAttestation(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidPartNumber(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidSignature(_) => StatusCode::BAD_REQUEST,
+            Self::MissingIdentifier | Self::AmbiguousIdentifier => StatusCode::BAD_REQUEST,
+            Self::NothingToMerge => StatusCode::BAD_REQUEST,
+            Self::UnparsableSbom(_) => StatusCode::BAD_REQUEST,
+            Self::Duplicate(_) => StatusCode::CONFLICT,
+            Self::ReindexRunning => StatusCode::CONFLICT,
             Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotFacetable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotHistogrammable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidInterval(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NoStatistics(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotDeduplicable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotSortable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidCursor) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::CursorExpired) => StatusCode::GONE,
+            Self::Index(IndexError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -115,7 +255,19 @@ struct IdentifierParams {
     id: String,
 }
 
+/// Parameters to fetch requests.
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    /// Identifier of SBOM
+    id: String,
+    /// Format to convert the SBOM to
+    format: Option<Format>,
+}
+
 /// Retrieve an SBOM using its identifier.
+///
+/// If a format is requested, the SBOM is converted into that format, carrying over its components and their
+/// dependencies only. SBOMs which already are in the requested format are returned as they are.
 #[utoipa::path(
     get,
     tag = "bombastic",
@@ -127,22 +279,31 @@ struct IdentifierParams {
     ),
     params(
         ("id" = String, Query, description = "Identifier of SBOM to fetch"),
+        ("format" = Option<String>, Query, description = "Format to convert the SBOM to (`cyclonedx-json` or `spdx-json`)"),
     )
 )]
 #[get("/sbom")]
 async fn query_sbom(
     state: web::Data<SharedState>,
-    params: web::Query<IdentifierParams>,
+    params: web::Query<QueryParams>,
     accept_encoding: web::Header<AcceptEncoding>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
     authorizer.require(&user, Permission::ReadSbom)?;
 
-    let key = params.into_inner().id;
+    let QueryParams { id: key, format } = params.into_inner();
+    let storage = &state.storage;
+    if let Some(format) = format {
+        log::trace!("Querying SBOM using id {}, converted to {:?}", key, format);
+        let data = storage.get_data(&key).await.map_err(Error::Storage)?;
+        return Ok(HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(convert_sbom(data, &key, format)?));
+    }
+
     let path: S3Path = S3Path::from_key(&key);
     log::trace!("Querying SBOM using id {}", key);
-    let storage = &state.storage;
     // determine the encoding of the stored object, if any
     let encoding = storage.get_head(path.clone()).await.ok().and_then(|head| {
         head.content_encoding
@@ -163,6 +324,104 @@ async fn query_sbom(
     }
 }
 
+/// Parameters to convert requests.
+#[derive(Debug, Deserialize)]
+struct ConvertParams {
+    /// Format to convert the SBOM to
+    format: Format,
+}
+
+/// Convert an SBOM into another format, without storing it.
+///
+/// The conversion carries over the components of the SBOM and their dependencies only. SBOMs which already are in the
+/// requested format are returned as they are.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/convert",
+    request_body(content = Value, description = "The SBOM to be converted", content_type = "application/json"),
+    responses(
+        (status = 200, description = "SBOM converted"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Invalid SBOM or format"),
+    ),
+    params(
+        ("format" = String, Query, description = "Format to convert the SBOM to (`cyclonedx-json` or `spdx-json`)"),
+    )
+)]
+async fn convert_sbom_format(
+    params: web::Query<ConvertParams>,
+    data: web::Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(convert_sbom(data.to_vec(), "sbom", params.format)?))
+}
+
+/// Merge stored SBOMs into an aggregate CycloneDX SBOM, without storing it.
+///
+/// The aggregate describes a component of the requested name and version, like a product, depending on the components
+/// the SBOMs describe. Components with the same package URL are merged, and the SBOMs each component came from are
+/// recorded as annotations. Like a conversion, the merge carries over the components of the SBOMs and their
+/// dependencies only.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/merge",
+    request_body = MergeRequest,
+    responses(
+        (status = 200, description = "SBOMs merged"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "SBOM not found"),
+        (status = BAD_REQUEST, description = "No SBOMs to merge, or an SBOM can't be parsed"),
+    ),
+)]
+#[post("/sbom/merge")]
+async fn merge_sboms(
+    state: web::Data<SharedState>,
+    request: web::Json<MergeRequest>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let MergeRequest { name, version, ids } = request.into_inner();
+    let ids: BTreeSet<_> = ids.into_iter().collect();
+    if ids.is_empty() {
+        return Err(Error::NothingToMerge.into());
+    }
+
+    let mut sboms = Vec::with_capacity(ids.len());
+    for id in ids {
+        let data = state.storage.get_data(&id).await.map_err(Error::Storage)?;
+        let sbom = SBOM::parse(&data).map_err(|e| Error::UnparsableSbom(format!("{id}: {e}")))?;
+        sboms.push((id, sbom));
+    }
+    log::info!("Merging {} SBOMs into '{name}'", sboms.len());
+
+    let merged = merge(
+        &name,
+        version.as_deref(),
+        sboms.iter().map(|(id, sbom)| (id.as_str(), sbom)),
+    );
+    Ok(HttpResponse::Ok().json(merged))
+}
+
+/// Convert an SBOM, returning the data of SBOMs already in the format
+fn convert_sbom(data: Vec<u8>, id: &str, format: Format) -> Result<Vec<u8>, Error> {
+    let sbom = SBOM::parse(&data).map_err(|e| Error::UnparsableSbom(e.to_string()))?;
+    match convert(&sbom, id, format) {
+        Some(converted) => serde_json::to_vec(&converted).map_err(|e| Error::UnparsableSbom(e.to_string())),
+        None => Ok(data),
+    }
+}
+
 /// Parameters for search query.
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
@@ -183,6 +442,21 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Comma separated list of facets to compute bucket counts for
+    #[serde(default, with = "trustification_api::search::comma_separated")]
+    pub facets: Vec<String>,
+    /// Sort order of the results, in the form of `<field>:<asc|desc>`
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Highlight matched terms in the snippets, using `<em>` markers
+    #[serde(default)]
+    pub highlight: bool,
+    /// Cursor to iterate the results with, instead of using an offset
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Collapse results sharing the value of a field to the highest ranking one
+    #[serde(default)]
+    pub dedup_by: Option<String>,
 }
 
 const fn default_offset() -> usize {
@@ -211,6 +485,11 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            sort: value.sort.clone(),
+            highlight: value.highlight,
+            cursor: value.cursor.clone(),
+            dedup_by: value.dedup_by.clone(),
         }
     }
 }
@@ -229,6 +508,11 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("facets" = Option<String>, Query, description = "Comma separated list of facets: supplier, classifier, license"),
+        ("sort" = Option<String>, Query, description = "Sort order, e.g. created:desc or name:asc"),
+        ("highlight" = Option<bool>, Query, description = "Highlight matched terms in snippets using <em> markers"),
+        ("cursor" = Option<String>, Query, description = "Iterate the results using a cursor: empty for the first page, or the cursor returned by the previous page"),
+        ("dedup_by" = Option<String>, Query, description = "Collapse results sharing the value of a field to the highest ranking one: uid, name"),
     )
 )]
 #[get("/sbom/search")]
@@ -244,15 +528,94 @@ async fn search_sbom(
 
     log::info!("Querying SBOM: '{}'", params.q);
 
-    let (result, total) = actix_web::web::block(move || {
-        state
-            .sbom_index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+    let (result, total, facets, cursor) = actix_web::web::block(move || {
+        let (result, total, cursor) = match &params.cursor {
+            Some(cursor) => state
+                .sbom_index
+                .search_cursor(&params.q, cursor, params.limit, (&params).into())?,
+            None => {
+                let (result, total) =
+                    state
+                        .sbom_index
+                        .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        let facets = state.sbom_index.facets(&params.q, &params.facets)?;
+        Ok::<_, IndexError>((result, total, facets, cursor))
     })
     .await?
     .map_err(Error::Index)?;
 
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
+}
+
+/// List the fields SBOM search queries can be qualified with.
+///
+/// Each field comes with its type and an example query, for building queries programmatically.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/search/fields",
+    responses(
+        (status = 200, description = "Search fields", body = Vec<SearchField>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/sbom/search/fields")]
+async fn search_sbom_fields(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.sbom_index.search_fields()))
+}
+
+/// Count the SBOMs matching a search query for each interval of time.
+///
+/// Queries can select ranges of dates, like `created>2024-01-01` or `created:[2024-01-01 TO 2024-06-30]`.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/histogram",
+    responses(
+        (status = 200, description = "Histogram computed", body = Vec<HistogramBucket>),
+        (status = BAD_REQUEST, description = "Bad query, field, interval or facet"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = Option<String>, Query, description = "Search query"),
+        ("field" = Option<String>, Query, description = "Date to bucket by: created (default) or indexed"),
+        ("interval" = Option<String>, Query, description = "Length of the intervals, defaults to 7d"),
+        ("offset" = Option<String>, Query, description = "Shift of the intervals from the epoch"),
+        ("facet" = Option<String>, Query, description = "Facet to split the intervals by: supplier, classifier, license, format or spec_version"),
+    )
+)]
+#[get("/sbom/histogram")]
+async fn sbom_histogram(
+    state: web::Data<SharedState>,
+    params: web::Query<HistogramParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+
+    log::debug!("Computing SBOM histogram: '{}'", params.q);
+
+    let buckets = web::block(move || state.sbom_index.histogram_for(&params, "created"))
+        .await?
+        .map_err(Error::Index)?;
+
+    Ok(HttpResponse::Ok().json(buckets))
 }
 
 /// Search for a package using a free form search query.
@@ -269,6 +632,7 @@ async fn search_sbom(
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("dedup_by" = Option<String>, Query, description = "Collapse results sharing the value of a field to the highest ranking one: name"),
     )
 )]
 #[get("/package/search")]
@@ -298,20 +662,39 @@ async fn search_package(
 
 /// Upload an SBOM with an identifier.
 ///
-/// Clients may split the transfer using multipart uploads. The only supported content type is JSON, but content encoding can be unset, bzip2 or zstd.
+/// The supported content types are JSON, and CycloneDX encoded as protobuf (`application/x.vnd.cyclonedx+protobuf`),
+/// which gets converted into JSON before storing it. Content encoding can be unset, bzip2 or zstd. Clients may split
+/// the transfer of SBOMs using multipart uploads.
+///
+/// If SBOMs get validated, an invalid SBOM is rejected with the details of the problem, listing all violations found.
+///
+/// If deduplication is enabled, an SBOM which is an exact duplicate of a stored SBOM, by the digest of its canonical
+/// JSON, is not stored again. Depending on the configuration, it is either rejected, or the identifier of the stored
+/// SBOM is returned.
+///
+/// The SBOM may be signed, passing either a detached signature in the `X-Signature` header, or a cosign bundle in the
+/// `X-Signature-Bundle` header, both base64 encoded. The signature is verified against the configured keys, or Fulcio
+/// and Rekor for keyless signatures. The result is stored as the reserved labels `signature.signed`,
+/// `signature.verified` and `signature.identity`, and can be searched with `is:signed` and `is:verified`. SBOMs failing
+/// the verification are still stored, as signed, but not verified.
 #[utoipa::path(
     put,
     tag = "bombastic",
     path = "/api/v1/sbom",
     request_body(content = Value, description = "The SBOM to be uploaded", content_type = "application/json"),
     responses(
-        (status = 200, description = "SBOM uploaded successfully"),
+        (status = 201, description = "SBOM uploaded successfully"),
+        (status = 200, description = "SBOM is a duplicate of the stored SBOM, with the identifier returned"),
         (status = 401, description = "User is not authenticated"),
         (status = 403, description = "User is not allowed to perform operation"),
-        (status = BAD_REQUEST, description = "Missing valid id or invalid content"),
+        (status = BAD_REQUEST, description = "Missing valid id or invalid content", body = Problem, content_type = "application/problem+json"),
+        (status = CONFLICT, description = "SBOM is a duplicate of a stored SBOM"),
+        (status = PAYLOAD_TOO_LARGE, description = "SBOM exceeds the size limit, as uploaded or once decompressed"),
     ),
     params(
         ("id" = String, Query, description = "Identifier assigned to the SBOM"),
+        ("x-signature" = Option<String>, Header, description = "Detached signature of the SBOM, base64 encoded"),
+        ("x-signature-bundle" = Option<String>, Header, description = "Cosign bundle of the signature of the SBOM, base64 encoded"),
     )
 )]
 async fn publish_sbom(
@@ -327,84 +710,1095 @@ async fn publish_sbom(
 
     let typ = verify_type(content_type)?;
     let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let is_protobuf = typ.essence_str() == protobuf::CONTENT_TYPE;
     let id = &params.id;
+    let signature = signature(&req)?;
     let payload = payload.map_err(|e| match e {
         PayloadError::Io(e) => StorageError::Io(e),
         _ => StorageError::Io(io::Error::new(io::ErrorKind::Other, e)),
     });
+
+    // uploads are stored the same way as assembled multipart uploads, which needs the whole document
+    let limit = state.publish_limit;
+    let body = payload
+        .try_fold(web::BytesMut::new(), |mut body, chunk| async move {
+            if body.len() + chunk.len() > limit {
+                return Err(StorageError::TooLarge(limit));
+            }
+            body.extend_from_slice(&chunk);
+            Ok(body)
+        })
+        .await
+        .map_err(Error::Storage)?
+        .freeze();
+    let typ = match is_protobuf {
+        true => protobuf::CONTENT_TYPE,
+        false => typ.as_ref(),
+    };
+    Ok(ingest(&state, id, typ, enc, body, signature).await?)
+}
+
+/// Store an SBOM, uploaded with a content type and an encoding, verifying its signature, converting it from protobuf,
+/// and checking for a duplicate before. The vulnerabilities embedded into the SBOM are forwarded once it's stored.
+async fn ingest(
+    state: &web::Data<SharedState>,
+    id: &str,
+    typ: &str,
+    enc: Option<&str>,
+    body: web::Bytes,
+    signature: Option<Signature>,
+) -> Result<HttpResponse, Error> {
+    let mut data = state
+        .storage
+        .decode(enc, body.clone(), state.decompressed_limit)
+        .await
+        .map_err(Error::Storage)?;
+    // the signature is over the document as uploaded
+    let verification = match &signature {
+        Some(signature) => state.signature.verify(&data, signature),
+        None => Verification::default(),
+    };
+    // protobuf SBOMs are stored as JSON, uncompressed before the storage compresses them
+    let (typ, enc, body) = match typ == protobuf::CONTENT_TYPE {
+        true => {
+            let json = protobuf::to_json(&data).map_err(|e| Error::UnparsableSbom(e.to_string()))?;
+            data = serde_json::to_vec(&json).map_err(|e| Error::UnparsableSbom(e.to_string()))?;
+            ("application/json", None, web::Bytes::from(data.clone()))
+        }
+        false => (typ, enc, body),
+    };
+    // documents which aren't JSON are left to the validation
+    let digest = match state.deduplication {
+        Deduplication::Disabled => None,
+        _ => digest::canonical_digest(&data).ok(),
+    };
+
+    if let Some(digest) = &digest {
+        if let Some(existing) = find_duplicate(state, digest).await.map_err(Error::Storage)? {
+            if existing != id && state.deduplication == Deduplication::Reject {
+                return Err(Error::Duplicate(existing));
+            }
+            if existing == id {
+                store_verification(state, id, verification).await?;
+            }
+            let msg = format!("SBOM already exists: id={existing}");
+            log::info!("{} ({digest}), not storing {id}", msg);
+            return Ok(HttpResponse::Ok().body(msg));
+        }
+    }
+
+    // the stored SBOM is only kept as a revision once the new one got accepted
+    let labels = verified_labels(state, id, verification).await?;
     let size = state
         .storage
-        .put_stream(id, typ.as_ref(), enc, payload)
+        .put_stream_archiving(
+            id,
+            typ,
+            enc,
+            futures::stream::once(async { Ok::<_, StorageError>(body) }),
+            state.keep_revisions,
+            labels.as_ref(),
+        )
         .await
         .map_err(Error::Storage)?;
+    if let Some(digest) = &digest {
+        state.storage.put_digest(digest, id).await.map_err(Error::Storage)?;
+    }
+    if state.vex.is_some() {
+        let (state, id) = (state.clone(), id.to_string());
+        tokio::task::spawn(async move {
+            if let Some(vex) = &state.vex {
+                vex.forward(&id, &data).await;
+            }
+        });
+    }
     let msg = format!("Successfully uploaded SBOM: id={id}, size={size}");
     log::info!("{}", msg);
     Ok(HttpResponse::Created().body(msg))
 }
 
-fn verify_type(content_type: Option<web::Header<ContentType>>) -> Result<ContentType, Error> {
-    if let Some(hdr) = content_type {
-        let ct = hdr.into_inner();
-        if ct == ContentType::json() {
-            return Ok(ct);
-        }
-    }
-    Err(Error::InvalidContentType)
+/// Parameters to list the components of an SBOM.
+#[derive(Debug, Deserialize)]
+struct ComponentParams {
+    /// Only return components with the term in their name or package URL, ignoring the case
+    #[serde(default)]
+    q: String,
+    /// Offset of components to return (for pagination)
+    #[serde(default = "default_offset")]
+    offset: usize,
+    /// Max number of components to return
+    #[serde(default = "default_limit")]
+    limit: usize,
 }
 
-// bzip2 prevents us from using the ContentEncoding enum
-fn verify_encoding(content_encoding: Option<&HeaderValue>) -> Result<Option<&str>, Error> {
-    match content_encoding {
-        Some(enc) => match enc.to_str() {
-            Ok(v) if ACCEPT_ENCODINGS.contains(&v) => Ok(Some(v)),
-            _ => Err(Error::InvalidContentEncoding),
-        },
-        None => Ok(None),
+/// Max number of components returned at once
+const MAX_COMPONENTS: usize = 1_000;
+
+/// List the components of an SBOM.
+///
+/// Returns the components as parsed from the SBOM, in the order of the document, without the need to retrieve the
+/// whole SBOM. At most 1000 components are returned at once.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/{id}/components",
+    responses(
+        (status = 200, description = "Components of the SBOM", body = SbomComponentResult),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "SBOM not found"),
+        (status = BAD_REQUEST, description = "SBOM can't be parsed"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+        ("q" = Option<String>, Query, description = "Term to match the name or package URL of the components with"),
+        ("offset" = Option<usize>, Query, description = "Offset of components to return"),
+        ("limit" = Option<usize>, Query, description = "Max number of components to return"),
+    )
+)]
+#[get("/sbom/{id}/components")]
+async fn get_sbom_components(
+    state: web::Data<SharedState>,
+    id: web::Path<String>,
+    params: web::Query<ComponentParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let components = sbom_components(&state, &id).await?;
+    let components: Vec<_> = components
+        .iter()
+        .filter(|component| params.q.is_empty() || component.matches(&params.q))
+        .collect();
+    let total = components.len();
+    let result = components
+        .into_iter()
+        .skip(params.offset)
+        .take(params.limit.min(MAX_COMPONENTS))
+        .cloned()
+        .collect();
+
+    Ok(HttpResponse::Ok().json(SbomComponentResult { total, result }))
+}
+
+/// The components of a stored SBOM, only parsing the SBOM if its components aren't cached yet.
+async fn sbom_components(state: &SharedState, id: &str) -> Result<Arc<Vec<SbomComponent>>, Error> {
+    let head = state
+        .storage
+        .get_head(S3Path::from_key(id))
+        .await
+        .map_err(Error::Storage)?;
+    if head.status == StatusCode::NOT_FOUND {
+        return Err(Error::Storage(StorageError::NotFound));
     }
+    if let Some(components) = head.e_tag.as_ref().and_then(|etag| state.components.get(id, etag)) {
+        return Ok(components);
+    }
+
+    let data = state.storage.get_data(id).await.map_err(Error::Storage)?;
+    let sbom = SBOM::parse(&data).map_err(|e| Error::UnparsableSbom(e.to_string()))?;
+    let components = Arc::new(sbom.components());
+    if let Some(etag) = &head.e_tag {
+        state.components.insert(id, etag, components.clone());
+    }
+    Ok(components)
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsParams {
+    /// Number of largest SBOMs to report
+    #[serde(default = "default_largest")]
+    largest: usize,
+}
+
+const fn default_largest() -> usize {
+    10
 }
 
-/// Delete an SBOM using its identifier.
+/// Report statistics of the stored SBOMs, for capacity monitoring.
+///
+/// Reports the number of SBOMs by format and version of the specification, the total size of the stored SBOMs, the
+/// largest SBOMs, and the number of SBOMs stored over the last 24 hours. Sizes are the sizes as stored, after
+/// compression.
 #[utoipa::path(
-    delete,
+    get,
     tag = "bombastic",
-    path = "/api/v1/sbom",
+    path = "/api/v1/sbom/stats",
     responses(
-        (status = 204, description = "SBOM either deleted or nonexistent"),
+        (status = 200, description = "Statistics computed", body = SbomStats),
         (status = 401, description = "User is not authenticated"),
         (status = 403, description = "User is not allowed to perform operation"),
-        (status = BAD_REQUEST, description = "Missing id"),
     ),
     params(
-        ("id" = String, Query, description = "Package URL or product identifier of SBOM to query"),
+        ("largest" = Option<usize>, Query, description = "Number of largest SBOMs to report, defaults to 10"),
     )
 )]
-#[delete("/sbom")]
-async fn delete_sbom(
+#[get("/sbom/stats")]
+async fn get_sbom_stats(
     state: web::Data<SharedState>,
-    params: web::Query<IdentifierParams>,
+    params: web::Query<StatsParams>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
-    authorizer.require(&user, Permission::DeleteSbom)?;
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-    let params = params.into_inner();
-    let id = &params.id;
-    log::trace!("Deleting SBOM using id {}", id);
-    state.storage.delete(id).await.map_err(Error::Storage)?;
+    let objects = state.storage.list_objects_info().await.map_err(Error::Storage)?;
+    let mut stats = storage_stats(objects, params.largest, time::OffsetDateTime::now_utc());
 
-    Ok(HttpResponse::NoContent().finish())
+    let index = state.clone();
+    stats.formats = web::block(move || index.sbom_index.pivot("", "format", "spec_version"))
+        .await?
+        .map_err(Error::Index)?;
+
+    Ok(HttpResponse::Ok().json(stats))
 }
 
-/// Delete all SBOMs
-#[delete("/sbom/all")]
-async fn delete_sboms(
+/// List the previous revisions of an SBOM, ordered from the oldest to the newest.
+///
+/// Whenever an SBOM gets replaced, the replaced SBOM is kept as a revision, numbered from 1. Only a limited number of
+/// the newest revisions is kept.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/{id}/versions",
+    responses(
+        (status = 200, description = "Revisions of the SBOM", body = Vec<String>),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+    )
+)]
+#[get("/sbom/{id}/versions")]
+async fn get_sbom_versions(
     state: web::Data<SharedState>,
+    id: web::Path<String>,
     authorizer: web::Data<Authorizer>,
     user: UserInformation,
 ) -> actix_web::Result<impl Responder> {
-    authorizer.require(&user, Permission::DeleteSbom)?;
+    authorizer.require(&user, Permission::ReadSbom)?;
 
-    state.storage.delete_all().await.map_err(Error::Storage)?;
+    let mut revisions = state.storage.list_revisions(&id).await.map_err(Error::Storage)?;
+    revisions.sort_by_key(|revision| revision.parse::<u64>().ok());
 
-    Ok(HttpResponse::NoContent().finish())
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+/// Retrieve a previous revision of an SBOM.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/{id}/versions/{revision}",
+    responses(
+        (status = 200, description = "SBOM revision found"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "SBOM revision not found"),
+    ),
+    params(
+        ("id" = String, Path, description = "Identifier of the SBOM"),
+        ("revision" = String, Path, description = "Name of the revision"),
+    )
+)]
+#[get("/sbom/{id}/versions/{revision}")]
+async fn get_sbom_version(
+    state: web::Data<SharedState>,
+    path: web::Path<(String, String)>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let (id, revision) = path.into_inner();
+    let data = state
+        .storage
+        .get_revision(&id, &revision)
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).body(data))
+}
+
+/// Get the signature passed along with an SBOM, if any.
+fn signature(req: &HttpRequest) -> Result<Option<Signature>, Error> {
+    let header = |name| {
+        req.headers()
+            .get(name)
+            .map(|value| value.to_str().map_err(|_| Error::InvalidSignature(name.to_string())))
+            .transpose()
+    };
+    if let Some(value) = header(BUNDLE_HEADER)? {
+        return Signature::from_bundle(value)
+            .map(Some)
+            .ok_or_else(|| Error::InvalidSignature(BUNDLE_HEADER.to_string()));
+    }
+    if let Some(value) = header(SIGNATURE_HEADER)? {
+        return Signature::from_detached(value)
+            .map(Some)
+            .ok_or_else(|| Error::InvalidSignature(SIGNATURE_HEADER.to_string()));
+    }
+    Ok(None)
+}
+
+/// Store the result of the signature verification as labels, dropping the result of a replaced SBOM.
+async fn store_verification(state: &SharedState, id: &str, verification: Verification) -> Result<(), Error> {
+    if let Some(labels) = verified_labels(state, id, verification).await? {
+        state.storage.put_labels(id, &labels).await?;
+    }
+    Ok(())
+}
+
+/// The labels of an SBOM with the result of verifying its signature, if they need to be updated.
+async fn verified_labels(state: &SharedState, id: &str, verification: Verification) -> Result<Option<Labels>, Error> {
+    let mut labels = state.storage.get_labels(id).await?;
+    let previous = labels.clone();
+    verification.apply(&mut labels);
+    if labels == previous {
+        return Ok(None);
+    }
+    log::debug!("Updating signature labels of SBOM {id}: {verification:?}");
+    Ok(Some(labels))
+}
+
+/// Find the stored SBOM with a canonical digest, which still has the digest.
+async fn find_duplicate(state: &SharedState, digest: &str) -> Result<Option<String>, StorageError> {
+    let Some(key) = state.storage.get_digest(digest).await? else {
+        return Ok(None);
+    };
+    // the SBOM may have been replaced or deleted since
+    let data = match state.storage.get_data(&key).await {
+        Ok(data) => data,
+        Err(StorageError::NotFound) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match digest::canonical_digest(&data) {
+        Ok(stored) if stored == digest => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+fn verify_type(content_type: Option<web::Header<ContentType>>) -> Result<ContentType, Error> {
+    if let Some(hdr) = content_type {
+        let ct = hdr.into_inner();
+        if ct == ContentType::json() || ct.essence_str() == protobuf::CONTENT_TYPE {
+            return Ok(ct);
+        }
+    }
+    Err(Error::InvalidContentType)
+}
+
+// bzip2 prevents us from using the ContentEncoding enum
+fn verify_encoding(content_encoding: Option<&HeaderValue>) -> Result<Option<&str>, Error> {
+    match content_encoding {
+        Some(enc) => match enc.to_str() {
+            Ok(v) if ACCEPT_ENCODINGS.contains(&v) => Ok(Some(v)),
+            _ => Err(Error::InvalidContentEncoding),
+        },
+        None => Ok(None),
+    }
+}
+
+/// the range of part numbers supported by multipart uploads of the storage
+const PART_NUMBERS: std::ops::RangeInclusive<u32> = 1..=10000;
+
+/// Get an upload in progress, which must have been initiated by the user, as others must not continue it.
+async fn owned_upload(state: &SharedState, id: &str, user: &UserInformation) -> Result<Upload, Error> {
+    let upload = state.storage.get_upload(id).await.map_err(Error::Storage)?;
+    match upload.is_owned_by(user.id()) {
+        true => Ok(upload),
+        false => Err(Error::Storage(StorageError::NotFound)),
+    }
+}
+
+fn upload_session(upload: Upload) -> UploadSession {
+    UploadSession {
+        missing: upload.missing_parts(),
+        parts: upload
+            .parts
+            .iter()
+            .map(|part| UploadPart {
+                number: part.number,
+                size: part.size,
+            })
+            .collect(),
+        upload: upload.id,
+        id: upload.key,
+    }
+}
+
+/// Initiate a resumable upload of an SBOM with an identifier.
+///
+/// The SBOM is transferred in parts, which can be retried independently, and is stored once the upload gets completed.
+/// The content type and encoding of the SBOM are the same as for uploading it at once.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload",
+    responses(
+        (status = 201, description = "Upload initiated", body = UploadSession),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Missing valid id, invalid type or encoding"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier assigned to the SBOM"),
+    )
+)]
+#[post("/sbom/upload")]
+async fn initiate_sbom_upload(
+    req: HttpRequest,
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    content_type: Option<web::Header<ContentType>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let typ = verify_type(content_type)?;
+    // the parts are stored as uploaded, protobuf SBOMs are converted once the upload gets completed
+    let typ = match typ.essence_str() == protobuf::CONTENT_TYPE {
+        true => protobuf::CONTENT_TYPE,
+        false => typ.as_ref(),
+    };
+    let enc = verify_encoding(req.headers().get(CONTENT_ENCODING))?;
+    let upload = state
+        .storage
+        .initiate_upload(&params.id, typ, enc, user.id())
+        .await
+        .map_err(Error::Storage)?;
+    log::info!("Initiated upload {} of SBOM {}", upload.id, upload.key);
+    Ok(HttpResponse::Created().json(upload_session(upload)))
+}
+
+/// Get an upload of an SBOM in progress, along with the parts transferred so far.
+///
+/// After a failed transfer, the parts listed as missing must be transferred again, before completing the upload.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{upload}",
+    responses(
+        (status = 200, description = "Upload in progress", body = UploadSession),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "Upload not found"),
+    ),
+    params(
+        ("upload" = String, Path, description = "Identifier of the upload"),
+    )
+)]
+#[get("/sbom/upload/{upload}")]
+async fn get_sbom_upload(
+    state: web::Data<SharedState>,
+    upload: web::Path<String>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let upload = owned_upload(&state, &upload, &user).await?;
+    Ok(HttpResponse::Ok().json(upload_session(upload)))
+}
+
+/// Transfer a part of an upload of an SBOM.
+///
+/// Parts are numbered from 1 to 10000, in the order of their data. All parts but the last one must have a size of at
+/// least 5 MiB. Transferring a part again replaces it.
+#[utoipa::path(
+    put,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{upload}/{part}",
+    request_body(content = Vec<u8>, description = "The data of the part", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Part transferred successfully", body = UploadPart),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "Upload not found"),
+        (status = BAD_REQUEST, description = "Invalid part number"),
+    ),
+    params(
+        ("upload" = String, Path, description = "Identifier of the upload"),
+        ("part" = u32, Path, description = "Number of the part"),
+    )
+)]
+async fn put_sbom_upload_part(
+    state: web::Data<SharedState>,
+    path: web::Path<(String, u32)>,
+    data: web::Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    let (upload, number) = path.into_inner();
+    if !PART_NUMBERS.contains(&number) {
+        return Err(Error::InvalidPartNumber(number).into());
+    }
+    owned_upload(&state, &upload, &user).await?;
+    let part = state
+        .storage
+        .put_upload_part(&upload, number, data.to_vec())
+        .await
+        .map_err(Error::Storage)?;
+    log::debug!("Transferred part {number} of upload {upload}, size={}", part.size);
+    Ok(HttpResponse::Ok().json(UploadPart {
+        number: part.number,
+        size: part.size,
+    }))
+}
+
+/// Complete an upload of an SBOM, storing it out of the parts transferred.
+///
+/// The parts must be numbered without gaps, starting with 1. The SBOM is stored like when uploading it at once, and
+/// the upload is dropped, also if the SBOM gets rejected.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{upload}/complete",
+    responses(
+        (status = 201, description = "SBOM uploaded successfully"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "Upload not found"),
+        (status = BAD_REQUEST, description = "Missing parts or invalid content", body = Problem, content_type = "application/problem+json"),
+    ),
+    params(
+        ("upload" = String, Path, description = "Identifier of the upload"),
+    )
+)]
+#[post("/sbom/upload/{upload}/complete")]
+async fn complete_sbom_upload(
+    state: web::Data<SharedState>,
+    upload: web::Path<String>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    owned_upload(&state, &upload, &user).await?;
+    // the parts are assembled apart from the stored SBOM, which is only replaced once the upload passed the pipeline
+    let (upload, data) = state.storage.complete_upload(&upload).await.map_err(Error::Storage)?;
+    // uploads in parts aren't signed
+    let result = ingest(
+        &state,
+        &upload.key,
+        &upload.content_type,
+        upload.encoding.as_deref(),
+        web::Bytes::from(data),
+        None,
+    )
+    .await;
+    // a completed upload can't be completed again
+    state.storage.delete_upload(&upload.id).await.map_err(Error::Storage)?;
+    Ok(result?)
+}
+
+/// Abort an upload of an SBOM, dropping all parts transferred so far.
+#[utoipa::path(
+    delete,
+    tag = "bombastic",
+    path = "/api/v1/sbom/upload/{upload}",
+    responses(
+        (status = 204, description = "Upload aborted"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "Upload not found"),
+    ),
+    params(
+        ("upload" = String, Path, description = "Identifier of the upload"),
+    )
+)]
+#[delete("/sbom/upload/{upload}")]
+async fn abort_sbom_upload(
+    state: web::Data<SharedState>,
+    upload: web::Path<String>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::CreateSbom)?;
+
+    owned_upload(&state, &upload, &user).await?;
+    state.storage.abort_upload(&upload).await.map_err(Error::Storage)?;
+    log::info!("Aborted upload {upload}");
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Parameters to delete requests.
+#[derive(Debug, Deserialize)]
+struct DeleteParams {
+    /// Identifier of the SBOM
+    id: Option<String>,
+    /// Search query of the SBOMs
+    query: Option<String>,
+    /// Only return the SBOMs matching the query, without deleting them
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// What a delete request deletes.
+#[derive(Debug, PartialEq, Eq)]
+enum DeleteTarget {
+    /// The SBOM of an identifier
+    Id(String),
+    /// All SBOMs matching a query
+    Query(String),
+}
+
+impl DeleteParams {
+    fn target(&self) -> Result<DeleteTarget, Error> {
+        match (&self.id, &self.query) {
+            (Some(_), Some(_)) => Err(Error::AmbiguousIdentifier),
+            (Some(id), None) => Ok(DeleteTarget::Id(id.clone())),
+            // an empty query matches all SBOMs, which must be deleted explicitly
+            (None, Some(query)) if !query.trim().is_empty() => Ok(DeleteTarget::Query(query.clone())),
+            (None, _) => Err(Error::MissingIdentifier),
+        }
+    }
+}
+
+/// Delete an SBOM using its identifier, or all SBOMs matching a query.
+///
+/// Deleting by a query requires the permission to administrate SBOMs, and responds with the identifiers of the SBOMs
+/// matching the query, and of the SBOMs deleted. With `dry_run`, the SBOMs are only matched, but not deleted. SBOMs
+/// which can't be deleted don't stop deleting the others, they are reported with their error, responding with an
+/// internal server error. The SBOMs get removed from the index once the indexer processed their deletion.
+#[utoipa::path(
+    delete,
+    tag = "bombastic",
+    path = "/api/v1/sbom",
+    responses(
+        (status = 204, description = "SBOM either deleted or nonexistent"),
+        (status = 200, description = "SBOMs matching the query deleted", body = DeleteResult),
+        (status = 500, description = "Some of the SBOMs matching the query not deleted", body = DeleteResult),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = BAD_REQUEST, description = "Missing id or query, both of them, or invalid query"),
+    ),
+    params(
+        ("id" = Option<String>, Query, description = "Package URL or product identifier of SBOM to query"),
+        ("query" = Option<String>, Query, description = "Search query of the SBOMs to delete, instead of an id"),
+        ("dry_run" = Option<bool>, Query, description = "Only return the SBOMs matching the query, without deleting them"),
+    )
+)]
+#[delete("/sbom")]
+async fn delete_sbom(
+    state: web::Data<SharedState>,
+    params: web::Query<DeleteParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    let params = params.into_inner();
+    let query = match params.target()? {
+        DeleteTarget::Id(id) => {
+            authorizer.require(&user, Permission::DeleteSbom)?;
+            log::trace!("Deleting SBOM using id {}", id);
+            state.delete_sbom(&id).await.map_err(Error::Storage)?;
+            return Ok(HttpResponse::NoContent().finish());
+        }
+        DeleteTarget::Query(query) => query,
+    };
+
+    authorizer.require(&user, Permission::AdminSbom)?;
+    let ids = find_sboms(&state, query.clone()).await?;
+    log::info!("Query '{query}' matches {} SBOMs", ids.len());
+    let result = DeleteResult {
+        ids,
+        dry_run: params.dry_run,
+        deleted: Vec::new(),
+        failed: BTreeMap::new(),
+    };
+
+    Ok(delete_matched(result, |id| {
+        let state = state.clone();
+        async move { state.delete_sbom(&id).await }
+    })
+    .await)
+}
+
+/// Delete the SBOMs matching a query, unless it's a dry run.
+///
+/// SBOMs which can't be deleted don't stop deleting the others, but make the response an internal server error.
+async fn delete_matched<F, Fut>(mut result: DeleteResult, delete: F) -> HttpResponse
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<(), StorageError>>,
+{
+    if result.dry_run {
+        return HttpResponse::Ok().json(result);
+    }
+
+    for id in &result.ids {
+        match delete(id.clone()).await {
+            Ok(()) => result.deleted.push(id.clone()),
+            Err(e) => {
+                log::warn!("Unable to delete SBOM {id}: {e}");
+                result.failed.insert(id.clone(), e.to_string());
+            }
+        }
+    }
+
+    match result.failed.is_empty() {
+        true => HttpResponse::Ok().json(result),
+        false => HttpResponse::InternalServerError().json(result),
+    }
+}
+
+/// Find the identifiers of all SBOMs matching a query, page by page.
+async fn find_sboms(state: &web::Data<SharedState>, query: String) -> actix_web::Result<Vec<String>> {
+    const PAGE_SIZE: usize = 1000;
+
+    let state = state.clone();
+    let ids = web::block(move || {
+        let mut ids = BTreeSet::new();
+        let mut offset = 0;
+        loop {
+            let (result, total) = state.sbom_index.search(&query, offset, PAGE_SIZE, Default::default())?;
+            let count = result.len();
+            ids.extend(result.into_iter().map(|hit| hit.document.id));
+            offset += count;
+            if count == 0 || offset >= total {
+                break;
+            }
+        }
+        Ok::<_, IndexError>(ids)
+    })
+    .await?
+    .map_err(Error::Index)?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Update the labels of an SBOM.
+///
+/// Labels with a value of `null` are removed, all other labels of the SBOM are kept. Label keys must not be empty and
+/// must not contain a `=`. The response contains all labels of the SBOM, after the update.
+#[utoipa::path(
+    patch,
+    tag = "bombastic",
+    path = "/api/v1/sbom/labels",
+    request_body = BTreeMap<String, Option<String>>,
+    responses(
+        (status = 200, description = "Labels updated successfully", body = BTreeMap<String, String>),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "SBOM not found"),
+        (status = BAD_REQUEST, description = "Missing id or invalid label"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of the SBOM to label"),
+    )
+)]
+#[patch("/sbom/labels")]
+async fn update_sbom_labels(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    web::Json(update): web::Json<BTreeMap<String, Option<String>>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    // the signature labels are only set by the verification
+    if let Some(key) = update
+        .keys()
+        .find(|key| !is_valid_label_key(key) || signature::is_reserved_label(key))
+    {
+        return Err(Error::InvalidLabel(key.clone()).into());
+    }
+
+    let id = &params.id;
+    // labels can only be assigned to existing SBOMs
+    let head = state
+        .storage
+        .get_head(S3Path::from_key(id))
+        .await
+        .map_err(Error::Storage)?;
+    if head.status == StatusCode::NOT_FOUND {
+        return Err(Error::Storage(StorageError::NotFound).into());
+    }
+
+    let mut labels: Labels = state.storage.get_labels(id).await.map_err(Error::Storage)?;
+    for (key, value) in update {
+        match value {
+            Some(value) => labels.insert(key, value),
+            None => labels.remove(&key),
+        };
+    }
+    state.storage.put_labels(id, &labels).await.map_err(Error::Storage)?;
+    log::debug!("Updated labels of SBOM {id}: {labels:?}");
+
+    Ok(HttpResponse::Ok().json(labels))
+}
+
+/// label keys are indexed as "key=value"
+fn is_valid_label_key(key: &str) -> bool {
+    !key.is_empty() && !key.contains('=')
+}
+
+/// Get the attestations attached to an SBOM.
+///
+/// Attestations are returned as DSSE envelopes, as they were attached.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/sbom/attestations",
+    responses(
+        (status = 200, description = "Attestations of the SBOM", body = Vec<serde_json::Value>),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of the SBOM"),
+    )
+)]
+#[get("/sbom/attestations")]
+async fn get_sbom_attestations(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let attestations = state
+        .storage
+        .get_attestations(&params.id)
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().json(attestations))
+}
+
+/// Attach attestations to an SBOM.
+///
+/// The request body is a list of in-toto attestations, wrapped in DSSE envelopes. It replaces all attestations which
+/// were attached to the SBOM before.
+#[utoipa::path(
+    put,
+    tag = "bombastic",
+    path = "/api/v1/sbom/attestations",
+    request_body = Vec<serde_json::Value>,
+    responses(
+        (status = 204, description = "Attestations attached successfully"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = NOT_FOUND, description = "SBOM not found"),
+        (status = BAD_REQUEST, description = "Missing id or invalid attestation"),
+    ),
+    params(
+        ("id" = String, Query, description = "Identifier of the SBOM"),
+    )
+)]
+#[put("/sbom/attestations")]
+async fn put_sbom_attestations(
+    state: web::Data<SharedState>,
+    params: web::Query<IdentifierParams>,
+    web::Json(attestations): web::Json<Vec<serde_json::Value>>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::UpdateSbom)?;
+
+    if let Some(n) = attestations.iter().position(|envelope| !is_dsse_envelope(envelope)) {
+        return Err(Error::InvalidAttestation(format!("entry {n} is not a DSSE envelope")).into());
+    }
+
+    let id = &params.id;
+    // attestations can only be attached to existing SBOMs
+    let head = state
+        .storage
+        .get_head(S3Path::from_key(id))
+        .await
+        .map_err(Error::Storage)?;
+    if head.status == StatusCode::NOT_FOUND {
+        return Err(Error::Storage(StorageError::NotFound).into());
+    }
+
+    state
+        .storage
+        .put_attestations(id, &attestations)
+        .await
+        .map_err(Error::Storage)?;
+    log::debug!("Attached {} attestations to SBOM {id}", attestations.len());
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// check for the mandatory fields of a DSSE envelope
+fn is_dsse_envelope(value: &serde_json::Value) -> bool {
+    value["payloadType"].is_string() && value["payload"].is_string() && value["signatures"].is_array()
+}
+
+/// Delete all SBOMs.
+///
+/// The SBOMs get removed from the index once the indexer processed their deletion.
+#[utoipa::path(
+    delete,
+    tag = "bombastic",
+    path = "/api/v1/sbom/all",
+    responses(
+        (status = 204, description = "All SBOMs deleted"),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+    ),
+)]
+#[delete("/sbom/all")]
+async fn delete_sboms(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::DeleteSbom)?;
+
+    // the forwarded advisories are only known by the SBOMs
+    let keys = match &state.vex {
+        Some(_) => state.storage.list_keys().await.map_err(Error::Storage)?,
+        None => vec![],
+    };
+    state.storage.delete_all().await.map_err(Error::Storage)?;
+    if let Some(vex) = &state.vex {
+        for (key, _) in keys {
+            vex.retract(&key).await;
+        }
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Reindex the stored SBOMs, all of them unless limited to a range of keys or a time window.
+///
+/// Publishes an event of storing each selected SBOM, which makes the indexer index it again, like after publishing it.
+/// Responds once reindexing started, with its progress, which is available from `GET` as well.
+#[utoipa::path(
+    post,
+    tag = "bombastic",
+    path = "/api/v1/admin/reindex",
+    responses(
+        (status = 202, description = "Reindexing started", body = ReindexStatus),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+        (status = 409, description = "Reindexing is already running"),
+    ),
+    params(ReindexSelection),
+)]
+#[post("/admin/reindex")]
+async fn start_reindex(
+    state: web::Data<SharedState>,
+    selection: web::Query<ReindexSelection>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::AdminSbom)?;
+
+    let status = Reindexer::start(&state, selection.into_inner())
+        .await
+        .map_err(Error::Storage)?
+        .ok_or(Error::ReindexRunning)?;
+    Ok(HttpResponse::Accepted().json(status))
+}
+
+/// Get the progress of the current, or the last, reindexing of all stored SBOMs, by any instance.
+#[utoipa::path(
+    get,
+    tag = "bombastic",
+    path = "/api/v1/admin/reindex",
+    responses(
+        (status = 200, description = "Progress of reindexing", body = ReindexStatus),
+        (status = 401, description = "User is not authenticated"),
+        (status = 403, description = "User is not allowed to perform operation"),
+    ),
+)]
+#[get("/admin/reindex")]
+async fn get_reindex(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::AdminSbom)?;
+
+    let status = state.reindexer.status(&state).await.map_err(Error::Storage)?;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use std::cell::RefCell;
+
+    fn params(id: Option<&str>, query: Option<&str>) -> DeleteParams {
+        DeleteParams {
+            id: id.map(ToString::to_string),
+            query: query.map(ToString::to_string),
+            dry_run: false,
+        }
+    }
+
+    fn matched(dry_run: bool) -> DeleteResult {
+        DeleteResult {
+            ids: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            dry_run,
+            deleted: Vec::new(),
+            failed: BTreeMap::new(),
+        }
+    }
+
+    async fn body(response: HttpResponse) -> DeleteResult {
+        serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_delete_target() {
+        assert_eq!(
+            params(Some("a"), None).target().unwrap(),
+            DeleteTarget::Id("a".to_string())
+        );
+        assert_eq!(
+            params(None, Some("ubi9")).target().unwrap(),
+            DeleteTarget::Query("ubi9".to_string())
+        );
+        assert!(matches!(
+            params(Some("a"), Some("ubi9")).target(),
+            Err(Error::AmbiguousIdentifier)
+        ));
+        // an empty query would match all SBOMs
+        assert!(matches!(
+            params(None, Some(" ")).target(),
+            Err(Error::MissingIdentifier)
+        ));
+        assert!(matches!(params(None, None).target(), Err(Error::MissingIdentifier)));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_matched_dry_run() {
+        let deleted = RefCell::new(vec![]);
+        let response = delete_matched(matched(true), |id| {
+            deleted.borrow_mut().push(id);
+            async { Ok(()) }
+        })
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(deleted.borrow().is_empty());
+        assert_eq!(body(response).await, matched(true));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_matched_partial_failure() {
+        let response = delete_matched(matched(false), |id| async move {
+            match id.as_str() {
+                "b" => Err(StorageError::Internal),
+                _ => Ok(()),
+            }
+        })
+        .await;
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let result = body(response).await;
+        assert_eq!(result.deleted, vec!["a".to_string(), "c".to_string()]);
+        assert_eq!(result.failed.keys().collect::<Vec<_>>(), vec!["b"]);
+    }
 }
@@ -0,0 +1,403 @@
+//! Verification of signatures of uploaded SBOMs, as created by [cosign](https://docs.sigstore.dev/signing/signing_with_blobs/).
+//!
+//! Signatures are passed along with the SBOM, either as a detached signature (`cosign sign-blob --key`), or as a
+//! bundle (`cosign sign-blob --bundle`). Detached signatures, and bundles without a certificate, are verified against
+//! the configured static keys. Keyless signatures are verified against the certificate of the bundle, which must be
+//! issued by the configured Fulcio roots, at the time of the entry in the Rekor transparency log, which in turn must be
+//! signed by the configured Rekor key. As Fulcio issues certificates to anyone, the identity and the OIDC issuer of the
+//! certificate must be allowed by the configuration as well.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bombastic_model::{digest::canonicalize, signature::Verification};
+use openssl::{
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{Id, PKey, PKeyRef, Public},
+    sign::Verifier,
+    stack::Stack,
+    x509::{
+        store::{X509Store, X509StoreBuilder},
+        verify::X509VerifyParam,
+        X509StoreContext, X509,
+    },
+};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// The header passing a detached signature, base64 encoded
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// The header passing a signature bundle, as JSON or base64 encoded JSON
+pub const BUNDLE_HEADER: &str = "x-signature-bundle";
+
+#[derive(Clone, Debug, Default, clap::Args)]
+#[command(next_help_heading = "Signature verification")]
+pub struct SignatureConfig {
+    /// Public keys (PEM) to verify signatures created with a static key
+    #[arg(long = "signature-key", env = "SIGNATURE_KEYS", value_delimiter = ',')]
+    pub keys: Vec<PathBuf>,
+
+    /// Root and intermediate certificates (PEM) of Fulcio, issuing the certificates of keyless signatures
+    #[arg(long = "fulcio-roots", env = "FULCIO_ROOTS")]
+    pub fulcio_roots: Option<PathBuf>,
+
+    /// Public key (PEM) of Rekor, signing the transparency log entries of keyless signatures
+    #[arg(long = "rekor-key", env = "REKOR_KEY")]
+    pub rekor_key: Option<PathBuf>,
+
+    /// Identities (email or URI of the certificate) allowed to create keyless signatures
+    #[arg(long = "signature-identity", env = "SIGNATURE_IDENTITIES", value_delimiter = ',')]
+    pub identities: Vec<String>,
+
+    /// OIDC issuers allowed to authenticate the identities of keyless signatures, like `https://accounts.google.com`
+    #[arg(long = "signature-issuer", env = "SIGNATURE_ISSUERS", value_delimiter = ',')]
+    pub issuers: Vec<String>,
+}
+
+/// A signature passed along with an SBOM
+#[derive(Clone, Debug, PartialEq)]
+pub enum Signature {
+    /// A detached signature
+    Detached(Vec<u8>),
+    /// A cosign bundle, with the signature, and optionally the signing certificate and the transparency log entry
+    Bundle(Value),
+}
+
+impl Signature {
+    /// Get the signature from the value of the [`SIGNATURE_HEADER`]
+    pub fn from_detached(value: &str) -> Option<Self> {
+        STANDARD.decode(value.trim()).ok().map(Self::Detached)
+    }
+
+    /// Get the signature from the value of the [`BUNDLE_HEADER`]
+    pub fn from_bundle(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let json = match value.starts_with('{') {
+            true => value.as_bytes().to_vec(),
+            false => STANDARD.decode(value).ok()?,
+        };
+        serde_json::from_slice(&json).ok().map(Self::Bundle)
+    }
+}
+
+pub struct SignatureVerifier {
+    keys: Vec<PKey<Public>>,
+    fulcio_roots: Option<Vec<X509>>,
+    rekor_key: Option<PKey<Public>>,
+    identities: Vec<String>,
+    issuers: Vec<String>,
+}
+
+impl TryFrom<SignatureConfig> for SignatureVerifier {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SignatureConfig) -> Result<Self, Self::Error> {
+        let keys = config
+            .keys
+            .iter()
+            .map(|path| Ok(PKey::public_key_from_pem(&std::fs::read(path)?)?))
+            .collect::<Result<_, anyhow::Error>>()?;
+        let fulcio_roots = match config.fulcio_roots {
+            Some(path) => Some(X509::stack_from_pem(&std::fs::read(path)?)?),
+            None => None,
+        };
+        let rekor_key = match config.rekor_key {
+            Some(path) => Some(PKey::public_key_from_pem(&std::fs::read(path)?)?),
+            None => None,
+        };
+        Ok(Self {
+            keys,
+            fulcio_roots,
+            rekor_key,
+            identities: config.identities,
+            issuers: config.issuers,
+        })
+    }
+}
+
+impl SignatureVerifier {
+    /// Verify the signature of an SBOM. Signatures which can't be verified are reported as signed, but not verified.
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> Verification {
+        let (verified, identity) = match signature {
+            Signature::Detached(signature) => (self.verify_with_keys(data, signature), None),
+            Signature::Bundle(bundle) => match self.verify_bundle(data, bundle) {
+                Ok(result) => result,
+                Err(e) => {
+                    log::info!("Unable to verify signature bundle: {e}");
+                    (false, None)
+                }
+            },
+        };
+        Verification {
+            signed: true,
+            verified,
+            identity,
+        }
+    }
+
+    fn verify_with_keys(&self, data: &[u8], signature: &[u8]) -> bool {
+        self.keys.iter().any(|key| verify(key, data, signature))
+    }
+
+    fn verify_bundle(&self, data: &[u8], bundle: &Value) -> Result<(bool, Option<String>), anyhow::Error> {
+        let encoded = bundle["base64Signature"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing signature"))?;
+        let signature = STANDARD.decode(encoded)?;
+
+        let Some(cert) = bundle["cert"].as_str() else {
+            return Ok((self.verify_with_keys(data, &signature), None));
+        };
+
+        // keyless signatures are only valid at the time they got logged
+        let (Some(roots), Some(time)) = (&self.fulcio_roots, self.verify_log_entry(data, encoded, bundle)?) else {
+            log::info!("Unable to verify keyless signature, without Fulcio roots or a verified log entry");
+            return Ok((false, None));
+        };
+
+        // cosign encodes the PEM of the certificate once more
+        let pem = STANDARD.decode(cert).unwrap_or_else(|_| cert.as_bytes().to_vec());
+        let cert = X509::from_pem(&pem)?;
+        if !verify_certificate(roots, &cert, time)? {
+            log::info!("Signing certificate is not issued by the Fulcio roots");
+            return Ok((false, None));
+        }
+
+        let (identity, issuer) = (identity(&cert), issuer(&cert));
+        if !self.is_allowed(identity.as_deref(), issuer.as_deref()) {
+            log::info!("Signing identity {identity:?} of issuer {issuer:?} is not allowed");
+            return Ok((false, None));
+        }
+
+        let key = cert.public_key()?;
+        Ok((verify(&key, data, &signature), identity))
+    }
+
+    /// Check if a keyless signature of an identity, authenticated by an OIDC issuer, is allowed.
+    fn is_allowed(&self, identity: Option<&str>, issuer: Option<&str>) -> bool {
+        let allows = |allowlist: &[String], value: Option<&str>| allowlist.iter().any(|v| Some(v.as_str()) == value);
+        allows(&self.identities, identity) && allows(&self.issuers, issuer)
+    }
+
+    /// Verify the transparency log entry of a bundle, returning the time it got logged, if it was verified.
+    fn verify_log_entry(&self, data: &[u8], signature: &str, bundle: &Value) -> Result<Option<i64>, anyhow::Error> {
+        let (Some(key), Some(entry)) = (&self.rekor_key, bundle.get("rekorBundle")) else {
+            return Ok(None);
+        };
+
+        let timestamp = entry["SignedEntryTimestamp"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing signed entry timestamp"))?;
+        let payload = serde_json::to_vec(&canonicalize(entry["Payload"].clone()))?;
+        if !verify(key, &payload, &STANDARD.decode(timestamp)?) {
+            log::info!("Log entry is not signed by the Rekor key");
+            return Ok(None);
+        }
+
+        // the entry must log the signature of the SBOM
+        let body = entry["Payload"]["body"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing body"))?;
+        let body: Value = serde_json::from_slice(&STANDARD.decode(body)?)?;
+        let digest = hex(&openssl::sha::sha256(data));
+        if body["spec"]["data"]["hash"]["value"].as_str() != Some(digest.as_str())
+            || body["spec"]["signature"]["content"].as_str() != Some(signature)
+        {
+            log::info!("Log entry doesn't match the SBOM or its signature");
+            return Ok(None);
+        }
+
+        Ok(entry["Payload"]["integratedTime"].as_i64())
+    }
+}
+
+fn verify(key: &PKeyRef<Public>, data: &[u8], signature: &[u8]) -> bool {
+    let verifier = match key.id() {
+        Id::ED25519 => Verifier::new_without_digest(key),
+        _ => Verifier::new(MessageDigest::sha256(), key),
+    };
+    verifier
+        .and_then(|mut verifier| verifier.verify_oneshot(signature, data))
+        .unwrap_or_default()
+}
+
+/// Verify a certificate was issued by one of the roots, and was valid at a time
+fn verify_certificate(roots: &[X509], cert: &X509, time: i64) -> Result<bool, anyhow::Error> {
+    let mut builder = X509StoreBuilder::new()?;
+    for root in roots {
+        builder.add_cert(root.clone())?;
+    }
+    let mut param = X509VerifyParam::new()?;
+    param.set_time(time as _);
+    builder.set_param(&param)?;
+    let store: X509Store = builder.build();
+
+    let chain = Stack::new()?;
+    let mut context = X509StoreContext::new()?;
+    Ok(context.init(&store, cert, &chain, |context| context.verify_cert())?)
+}
+
+/// The identity of the signer, the email or URI of the certificate
+fn identity(cert: &X509) -> Option<String> {
+    let names = cert.subject_alt_names()?;
+    let identity = names
+        .iter()
+        .find_map(|name| name.email().or_else(|| name.uri()).map(ToString::to_string));
+    identity.or_else(|| {
+        cert.subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .map(|name| name.to_string())
+    })
+}
+
+/// The DER encoded object identifiers of the Fulcio extensions of the OIDC issuer, as raw string
+/// (1.3.6.1.4.1.57264.1.1), and as UTF8String (1.3.6.1.4.1.57264.1.8)
+const ISSUER_V1: &[u8] = &[0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x01];
+const ISSUER_V2: &[u8] = &[0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x08];
+
+/// The OIDC issuer, which authenticated the identity of the certificate issued by Fulcio
+fn issuer(cert: &X509) -> Option<String> {
+    let der = cert.to_der().ok()?;
+    if let Some(value) = extension(&der, ISSUER_V2) {
+        // the value is a DER encoded UTF8String
+        return match tlv(value)? {
+            (0x0c, value, _) => String::from_utf8(value.to_vec()).ok(),
+            _ => None,
+        };
+    }
+    extension(&der, ISSUER_V1).and_then(|value| String::from_utf8(value.to_vec()).ok())
+}
+
+/// Find the value of the extension with a DER encoded object identifier in a DER encoded certificate.
+///
+/// An extension is encoded as the OID, optionally followed by the critical flag, followed by the value as OCTET STRING.
+fn extension<'a>(der: &'a [u8], oid: &[u8]) -> Option<&'a [u8]> {
+    let start = der.windows(oid.len()).position(|window| window == oid)? + oid.len();
+    let (tag, value) = match tlv(&der[start..])? {
+        // the critical flag is a BOOLEAN
+        (0x01, _, rest) => tlv(rest).map(|(tag, value, _)| (tag, value))?,
+        (tag, value, _) => (tag, value),
+    };
+    (tag == 0x04).then_some(value)
+}
+
+/// Split DER encoded data into the tag, the value and the remaining data
+fn tlv(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, der) = der.split_first()?;
+    let (&first, der) = der.split_first()?;
+    let (len, der) = match first {
+        0..=0x7f => (first as usize, der),
+        0x81..=0x84 => {
+            let n = (first & 0x7f) as usize;
+            let len = der.get(..n)?.iter().fold(0, |len, b| (len << 8) | *b as usize);
+            (len, &der[n..])
+        }
+        _ => return None,
+    };
+    let value = der.get(..len)?;
+    Some((tag, value, &der[len..]))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::{ec::EcGroup, ec::EcKey, pkey::Private, sign::Signer};
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn sign(key: &PKey<Private>, data: &[u8]) -> Vec<u8> {
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.sign_oneshot_to_vec(data).unwrap()
+    }
+
+    fn verifier(keys: &[&PKey<Private>]) -> SignatureVerifier {
+        SignatureVerifier {
+            keys: keys
+                .iter()
+                .map(|key| PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap())
+                .collect(),
+            fulcio_roots: None,
+            rekor_key: None,
+            identities: vec!["alice@example.com".to_string()],
+            issuers: vec!["https://accounts.example.com".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_detached() {
+        let (key, other) = (key(), key());
+        let data = br#"{"bomFormat": "CycloneDX"}"#;
+        let signature = Signature::from_detached(&STANDARD.encode(sign(&key, data))).unwrap();
+
+        let verification = verifier(&[&other, &key]).verify(data, &signature);
+        assert!(verification.signed);
+        assert!(verification.verified);
+
+        assert!(!verifier(&[&other]).verify(data, &signature).verified);
+        assert!(!verifier(&[&key]).verify(b"other", &signature).verified);
+    }
+
+    #[test]
+    fn test_bundle() {
+        let key = key();
+        let data = br#"{"bomFormat": "CycloneDX"}"#;
+        let bundle = serde_json::json!({ "base64Signature": STANDARD.encode(sign(&key, data)) }).to_string();
+
+        let signature = Signature::from_bundle(&bundle).unwrap();
+        assert_eq!(
+            Signature::from_bundle(&STANDARD.encode(&bundle)),
+            Some(signature.clone())
+        );
+        assert!(verifier(&[&key]).verify(data, &signature).verified);
+
+        // keyless signatures require Fulcio roots and the Rekor key
+        let bundle = serde_json::json!({
+            "base64Signature": STANDARD.encode(sign(&key, data)),
+            "cert": "not a certificate",
+        });
+        let verification = verifier(&[&key]).verify(data, &Signature::Bundle(bundle));
+        assert!(verification.signed);
+        assert!(!verification.verified);
+    }
+
+    #[test]
+    fn test_allowed() {
+        let verifier = verifier(&[]);
+        assert!(verifier.is_allowed(Some("alice@example.com"), Some("https://accounts.example.com")));
+        assert!(!verifier.is_allowed(Some("mallory@example.com"), Some("https://accounts.example.com")));
+        assert!(!verifier.is_allowed(Some("alice@example.com"), Some("https://accounts.example.org")));
+        assert!(!verifier.is_allowed(Some("alice@example.com"), None));
+        assert!(!verifier.is_allowed(None, Some("https://accounts.example.com")));
+    }
+
+    #[test]
+    fn test_issuer() {
+        let issuer = b"https://accounts.example.com";
+        // an extension with the issuer as raw string, and one marked critical with the issuer as UTF8String
+        let mut v1 = ISSUER_V1.to_vec();
+        v1.extend([0x04, issuer.len() as u8]);
+        v1.extend(issuer);
+        assert_eq!(extension(&v1, ISSUER_V1), Some(&issuer[..]));
+
+        let mut v2 = ISSUER_V2.to_vec();
+        v2.extend([0x01, 0x01, 0xff, 0x04, issuer.len() as u8 + 2, 0x0c, issuer.len() as u8]);
+        v2.extend(issuer);
+        let (tag, value, _) = tlv(extension(&v2, ISSUER_V2).unwrap()).unwrap();
+        assert_eq!((tag, value), (0x0c, &issuer[..]));
+
+        assert_eq!(extension(&v1, ISSUER_V2), None);
+        assert_eq!(
+            tlv(&[0x04, 0x81, 0x02, 0x01, 0x02, 0x03]),
+            Some((0x04, &[0x01, 0x02][..], &[0x03][..]))
+        );
+        assert_eq!(tlv(&[0x04, 0x05, 0x01]), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Statistics of the stored SBOMs, for capacity monitoring.
+//!
+//! Sizes and modification times are taken from the listing of the storage, so sizes are the sizes as stored, after
+//! compression. Replacing an SBOM counts as ingesting it again, as only the time it was last stored is known.
+
+use std::time::Duration;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use trustification_api::search::FacetPivot;
+use trustification_storage::ObjectInfo;
+
+/// The window the ingest rate is computed over
+const INGEST_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(utoipa::ToSchema, Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct SbomStats {
+    /// Number of stored SBOMs
+    pub documents: u64,
+    /// Total size of the stored SBOMs, in bytes
+    pub storage_bytes: u64,
+    /// Number of indexed SBOMs by format, split by the version of the specification
+    pub formats: Vec<FacetPivot>,
+    /// The largest stored SBOMs, largest first
+    pub largest: Vec<DocumentSize>,
+    /// Number of SBOMs stored in the last 24 hours
+    pub ingested_last_24h: u64,
+    /// Average number of SBOMs stored per hour, over the last 24 hours
+    pub ingest_rate_per_hour: f64,
+}
+
+#[derive(utoipa::ToSchema, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct DocumentSize {
+    pub id: String,
+    /// Size of the SBOM as stored, in bytes
+    pub size: u64,
+}
+
+/// Compute the statistics of the stored objects at a time, keeping the given number of largest documents.
+///
+/// The counts by format are taken from the index, and left empty.
+pub fn storage_stats(objects: Vec<ObjectInfo>, largest: usize, now: OffsetDateTime) -> SbomStats {
+    let since = now - INGEST_WINDOW;
+    let ingested = objects
+        .iter()
+        .filter_map(|object| OffsetDateTime::parse(&object.last_modified, &Rfc3339).ok())
+        .filter(|modified| *modified > since)
+        .count() as u64;
+
+    let mut sizes: Vec<_> = objects
+        .into_iter()
+        .map(|object| DocumentSize {
+            id: object.key,
+            size: object.size,
+        })
+        .collect();
+    sizes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.id.cmp(&b.id)));
+
+    SbomStats {
+        documents: sizes.len() as u64,
+        storage_bytes: sizes.iter().map(|document| document.size).sum(),
+        formats: Vec::new(),
+        largest: sizes.into_iter().take(largest).collect(),
+        ingested_last_24h: ingested,
+        ingest_rate_per_hour: ingested as f64 / (INGEST_WINDOW.as_secs() / 3600) as f64,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn object(key: &str, size: u64, last_modified: &str) -> ObjectInfo {
+        ObjectInfo {
+            key: key.to_string(),
+            size,
+            last_modified: last_modified.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_storage_stats() {
+        let objects = vec![
+            object("a", 100, "2023-06-01T11:00:00.000Z"),
+            object("b", 300, "2023-05-31T13:00:00.000Z"),
+            object("c", 200, "2023-05-30T12:00:00.000Z"),
+            object("d", 300, "unknown"),
+        ];
+        let now = OffsetDateTime::parse("2023-06-01T12:00:00Z", &Rfc3339).unwrap();
+        let stats = storage_stats(objects, 2, now);
+
+        assert_eq!(stats.documents, 4);
+        assert_eq!(stats.storage_bytes, 900);
+        assert_eq!(
+            stats.largest,
+            vec![
+                DocumentSize {
+                    id: "b".to_string(),
+                    size: 300
+                },
+                DocumentSize {
+                    id: "d".to_string(),
+                    size: 300
+                },
+            ]
+        );
+        assert_eq!(stats.ingested_last_24h, 2);
+        assert_eq!(stats.ingest_rate_per_hour, 2.0 / 24.0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Forwarding of the vulnerabilities embedded in CycloneDX SBOMs to vexination.
+//!
+//! The vulnerabilities are split off into a standalone CycloneDX VEX document, see [`bombastic_model::vex`], and
+//! published to vexination as the advisory of the SBOM, identified by the identifier of the SBOM with the prefix
+//! `sbom:`, which advisories of vendors don't use. So they are correlated with the packages like any other advisory.
+//! Replacing the SBOM replaces the advisory as well, and the advisory is deleted along with the SBOM, or once the SBOM
+//! is replaced by one without vulnerabilities.
+
+use bombastic_model::vex::extract_vex;
+use prometheus::{opts, register_int_counter_with_registry, IntCounter, Registry};
+use serde_json::Value;
+use std::sync::Arc;
+use trustification_auth::client::{OpenIdTokenProviderConfigArguments, TokenInjector, TokenProvider};
+use trustification_common::tls::ClientConfig;
+use url::Url;
+
+#[derive(Clone, Debug, clap::Args)]
+#[command(next_help_heading = "Embedded VEX")]
+pub struct VexConfig {
+    /// URL of the vexination API, which the vulnerabilities embedded in CycloneDX SBOMs are forwarded to.
+    ///
+    /// Embedded vulnerabilities are not forwarded if not set.
+    #[arg(long = "vexination-url", env = "VEXINATION_URL")]
+    pub vexination_url: Option<Url>,
+
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+}
+
+pub struct VexForwarder {
+    client: reqwest::Client,
+    url: Url,
+    provider: Arc<dyn TokenProvider>,
+    forwarded_total: IntCounter,
+    failed_total: IntCounter,
+}
+
+impl VexForwarder {
+    /// Create a forwarder, returning `None` if forwarding isn't configured.
+    pub async fn new(config: VexConfig, devmode: bool, registry: &Registry) -> anyhow::Result<Option<Self>> {
+        let Some(url) = config.vexination_url else {
+            return Ok(None);
+        };
+        let forwarded_total = register_int_counter_with_registry!(
+            opts!(
+                "bombastic_vex_forwarded_total",
+                "Total number of VEX documents extracted from SBOMs and forwarded"
+            ),
+            registry
+        )?;
+        let failed_total = register_int_counter_with_registry!(
+            opts!(
+                "bombastic_vex_failed_total",
+                "Total number of VEX documents extracted from SBOMs which failed to be forwarded"
+            ),
+            registry
+        )?;
+        Ok(Some(Self {
+            client: config.client.build_client()?,
+            url: url.join("api/v1/vex")?,
+            provider: config.oidc.into_provider_or_devmode(devmode).await?,
+            forwarded_total,
+            failed_total,
+        }))
+    }
+
+    /// Forward the vulnerabilities embedded in an SBOM, deleting the advisory of a replaced SBOM if there are none.
+    ///
+    /// Failing to forward them doesn't fail the upload of the SBOM, so errors are only logged.
+    pub async fn forward(&self, id: &str, data: &[u8]) {
+        // documents which aren't JSON are not CycloneDX SBOMs carrying vulnerabilities either
+        let Some(vex) = serde_json::from_slice::<Value>(data)
+            .ok()
+            .as_ref()
+            .and_then(extract_vex)
+        else {
+            self.retract(id).await;
+            return;
+        };
+        match self.publish(id, &vex).await {
+            Ok(()) => {
+                log::info!("Forwarded VEX embedded in SBOM: id={id}");
+                self.forwarded_total.inc();
+            }
+            Err(e) => {
+                log::warn!("Unable to forward VEX embedded in SBOM {id}: {e}");
+                self.failed_total.inc();
+            }
+        }
+    }
+
+    /// Delete the advisory of the vulnerabilities embedded in an SBOM, which is fine if there is none.
+    ///
+    /// Failing to delete it doesn't fail deleting the SBOM, so errors are only logged.
+    pub async fn retract(&self, id: &str) {
+        let advisory = advisory_id(id);
+        let result = async {
+            self.client
+                .delete(self.url.clone())
+                .query(&[("advisory", &advisory)])
+                .inject_token(self.provider.as_ref())
+                .await?
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok::<_, anyhow::Error>(())
+        };
+        if let Err(e) = result.await {
+            log::warn!("Unable to delete VEX embedded in SBOM {id}: {e}");
+        }
+    }
+
+    async fn publish(&self, id: &str, vex: &Value) -> anyhow::Result<()> {
+        self.client
+            .put(self.url.clone())
+            .query(&[("advisory", advisory_id(id))])
+            .inject_token(self.provider.as_ref())
+            .await?
+            .json(vex)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The identifier of the advisory of the vulnerabilities embedded in an SBOM
+fn advisory_id(id: &str) -> String {
+    format!("sbom:{id}")
+}
@@ -5,6 +5,8 @@ pub enum Command {
     Api(bombastic_api::Run),
     Indexer(bombastic_indexer::Run),
     Walker(bombastic_walker::Run),
+    RemoteWalker(bombastic_walker::remote::Run),
+    Oci(bombastic_walker::oci::Run),
 }
 
 impl Command {
@@ -13,6 +15,8 @@ impl Command {
             Self::Api(run) => run.run(None).await,
             Self::Indexer(run) => run.run().await,
             Self::Walker(run) => run.run().await,
+            Self::RemoteWalker(run) => run.run().await,
+            Self::Oci(run) => run.run().await,
         }
     }
 }
@@ -10,23 +10,27 @@ use spdx_rs::models::Algorithm;
 use time::OffsetDateTime;
 use trustification_api::search::SearchOptions;
 use trustification_index::{
-    boost, create_date_query, create_string_query, field2str,
+    boosts::boost_query,
+    create_date_query, create_purl_query, field2str,
     metadata::doc2metadata,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
         query::{AllQuery, BooleanQuery, Query, TermQuery, TermSetQuery},
-        schema::{Field, Schema, Term, FAST, STORED, STRING, TEXT},
+        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader,
     },
-    term2query, Document, Error as SearchError, SearchQuery,
+    term2query,
+    tokenizer::{FieldTokenizer, Tokenizer, Tokenizers},
+    Document, Error as SearchError, QueryContext, SearchQuery,
 };
 
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    tokenizers: Tokenizers,
 }
 
 pub struct Fields {
@@ -55,19 +59,27 @@ impl Default for Index {
 
 impl Index {
     pub fn new() -> Self {
+        Self::with_tokenizers(&[])
+    }
+
+    /// Create the index, with the tokenizers configured for its fields.
+    ///
+    /// The package URL names are raw by default, the descriptions simple.
+    pub fn with_tokenizers(configured: &[FieldTokenizer]) -> Self {
+        let mut tokenizers = Tokenizers::new(configured);
         let mut schema = Schema::builder();
         let fields = Fields {
-            indexed_timestamp: schema.add_date_field("indexed_timestamp", STORED),
+            indexed_timestamp: schema.add_date_field("indexed_timestamp", INDEXED | FAST | STORED),
             purl: schema.add_text_field("package_url", FAST | STRING | STORED),
             name: schema.add_text_field("package_name", FAST | STRING | STORED),
             version: schema.add_text_field("package_version", STRING | STORED),
-            desc: schema.add_text_field("package_desc", TEXT | STORED),
+            desc: tokenizers.add_text_field(&mut schema, "package_desc", Tokenizer::Simple, STORED),
             license: schema.add_text_field("package_license", TEXT | STORED),
             supplier: schema.add_text_field("package_supplier", STRING | STORED),
             classifier: schema.add_text_field("package_classifier", STRING | STORED),
             sha256: schema.add_text_field("package_sha256", STRING | STORED),
             purl_type: schema.add_text_field("package_url_type", STRING | STORED),
-            purl_name: schema.add_text_field("package_url_name", FAST | STRING | STORED),
+            purl_name: tokenizers.add_text_field(&mut schema, "package_url_name", Tokenizer::Raw, FAST | STORED),
             purl_namespace: schema.add_text_field("package_url_namespace", STRING | STORED),
             purl_version: schema.add_text_field("package_url_version", STRING | STORED),
             purl_qualifiers: schema.add_text_field("package_url_qualifiers", STRING | STORED),
@@ -76,6 +88,7 @@ impl Index {
         Self {
             schema: schema.build(),
             fields,
+            tokenizers,
         }
     }
 
@@ -113,7 +126,7 @@ impl Index {
         let mut package_id = "".to_string();
         for r in package.external_reference.iter() {
             if r.reference_type == "purl" {
-                let purl = r.reference_locator.clone();
+                let purl = trustification_purl::normalize(&r.reference_locator).into_owned();
                 package_id = purl.clone();
 
                 if let Ok(package) = packageurl::PackageUrl::from_str(&purl) {
@@ -201,7 +214,7 @@ impl Index {
         };
         let mut package_id = "".to_string();
         if let Some(purl) = &component.purl {
-            let purl = purl.to_string();
+            let purl = trustification_purl::normalize(&purl.to_string()).into_owned();
             package_id = purl.clone();
             document.add_text(fields.purl, &purl);
 
@@ -247,39 +260,57 @@ impl Index {
         }
     }
 
-    fn resource2query(&self, resource: &PackageInfo) -> Box<dyn Query> {
+    fn resource2query(&self, resource: &PackageInfo, context: &QueryContext) -> Box<dyn Query> {
         // const PACKAGE_WEIGHT: f32 = 1.5;
         const CREATED_WEIGHT: f32 = 1.25;
         match resource {
-            PackageInfo::Purl(value) => self.create_string_query(&[self.fields.purl], value),
+            PackageInfo::Purl(value) => boost_query(
+                "purl",
+                1.0,
+                create_purl_query(self.fields.purl, value, context),
+                context,
+            ),
 
             PackageInfo::Type(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.purl_type,
                 value,
             )])),
 
-            PackageInfo::Version(value) => {
-                self.create_string_query(&[self.fields.version, self.fields.purl_version], value)
-            }
+            PackageInfo::Version(value) => boost_query(
+                "version",
+                1.0,
+                self.create_string_query(&[self.fields.version, self.fields.purl_version], value, context),
+                context,
+            ),
 
-            PackageInfo::Description(value) => {
-                Box::new(TermSetQuery::new(vec![Term::from_field_text(self.fields.desc, value)]))
-            }
+            PackageInfo::Description(value) => boost_query(
+                "description",
+                1.0,
+                self.tokenizers.query(self.fields.desc, &Primary::Equal(value), context),
+                context,
+            ),
 
             PackageInfo::License(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.license,
                 value,
             )])),
 
-            PackageInfo::Supplier(primary) => self.create_string_query(&[self.fields.supplier], primary),
+            PackageInfo::Supplier(primary) => self.create_string_query(&[self.fields.supplier], primary, context),
 
-            PackageInfo::Created(ordered) => boost(
-                create_date_query(&self.schema, self.fields.indexed_timestamp, ordered),
+            PackageInfo::Created(ordered) => boost_query(
+                "created",
                 CREATED_WEIGHT,
+                create_date_query(&self.schema, self.fields.indexed_timestamp, ordered),
+                context,
             ),
 
-            PackageInfo::Name(value) => self.create_string_query(&[self.fields.purl_name], value),
-            PackageInfo::Namespace(value) => self.create_string_query(&[self.fields.purl_namespace], value),
+            PackageInfo::Name(value) => boost_query(
+                "name",
+                1.0,
+                self.create_string_query(&[self.fields.purl_name], value, context),
+                context,
+            ),
+            PackageInfo::Namespace(value) => self.create_string_query(&[self.fields.purl_namespace], value, context),
 
             PackageInfo::Qualifier(value) => {
                 let value = format!("{}={}", value.qualifier, value.expression);
@@ -291,8 +322,11 @@ impl Index {
         }
     }
 
-    fn create_string_query(&self, fields: &[Field], value: &Primary<'_>) -> Box<dyn Query> {
-        let queries: Vec<Box<dyn Query>> = fields.iter().map(|f| create_string_query(*f, value)).collect();
+    fn create_string_query(&self, fields: &[Field], value: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
+        let queries: Vec<Box<dyn Query>> = fields
+            .iter()
+            .map(|f| self.tokenizers.query(*f, value, context))
+            .collect();
         Box::new(BooleanQuery::union(queries))
     }
 }
@@ -300,7 +334,7 @@ impl Index {
 impl trustification_index::Index for Index {
     type MatchedDocument = SearchPackageHit;
 
-    fn prepare_query(&self, q: &str) -> Result<SearchQuery, SearchError> {
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, SearchError> {
         let mut query = PackageInfo::parse(q).map_err(|err| SearchError::QueryParser(err.to_string()))?;
         query.term = query.term.compact();
 
@@ -323,7 +357,7 @@ impl trustification_index::Index for Index {
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            term2query(&query.term, &|resource| self.resource2query(resource, context))
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -336,6 +370,7 @@ impl trustification_index::Index for Index {
         query: &dyn Query,
         offset: usize,
         limit: usize,
+        _context: &QueryContext,
     ) -> Result<(Vec<(f32, DocAddress)>, usize), SearchError> {
         let date_field = self.schema.get_field_name(self.fields.indexed_timestamp).to_string();
         let now = tantivy::DateTime::from_utc(OffsetDateTime::now_utc());
@@ -368,6 +403,20 @@ impl trustification_index::Index for Index {
         )?)
     }
 
+    fn histogram_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "created" => Some(self.fields.indexed_timestamp),
+            _ => None,
+        }
+    }
+
+    fn dedup_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "name" => Some(self.fields.name),
+            _ => None,
+        }
+    }
+
     fn process_hit(
         &self,
         doc_address: DocAddress,
@@ -499,7 +548,7 @@ impl trustification_index::WriteIndex for Index {
 
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
         let sha256 = sha256::digest(data);
-        SBOM::parse(data)
+        SBOM::parse_pruned(data)
             .map_err(|e| SearchError::DocParser(e.to_string()))
             .map(|doc| (doc, sha256))
     }
@@ -572,6 +621,7 @@ mod tests {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    ..Default::default()
                 },
             )
             .unwrap()
@@ -590,6 +640,13 @@ mod tests {
         assert_search(|index| {
             let result = search(&index, "purl:\"pkg:rpm/redhat/libdnf@0.67.0-3.el9?arch=aarch64\"");
             assert_eq!(result.0.len(), 1);
+
+            // package URLs are normalized
+            let result = search(
+                &index,
+                "purl:\"PKG:RPM/redhat/libdnf@0.67.0-3.el9?ARCH=aarch64&distro=\"",
+            );
+            assert_eq!(result.0.len(), 1);
         });
     }
 
@@ -1,6 +1,8 @@
 use core::str::FromStr;
 
 use bombastic_model::prelude::*;
+use bombastic_model::quality::Quality;
+use bombastic_model::signature::{Verification, SIGNED_LABEL, VERIFIED_LABEL};
 use cyclonedx_bom::models::{
     component::Classification,
     hash::HashAlgorithm,
@@ -9,11 +11,15 @@ use cyclonedx_bom::models::{
 use log::{debug, warn};
 use sikula::{mir::Direction, prelude::*};
 use spdx_rs::models::Algorithm;
+use strum::VariantNames;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    boost, create_boolean_query, create_date_query, create_string_query, field2str,
+    boosts::boost_query,
+    create_boolean_query, create_date_query, create_float_query, create_purl_query, field2str,
+    highlight::{highlight_values, snippet2html},
     metadata::doc2metadata,
+    search_field,
     tantivy::{
         self,
         collector::TopDocs,
@@ -25,12 +31,15 @@ use trustification_index::{
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Order, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Document, Error as SearchError, SearchQuery,
+    term2query,
+    tokenizer::{FieldTokenizer, Tokenizer, Tokenizers},
+    Document, Error as SearchError, Labels, QueryContext, SearchQuery,
 };
 
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    tokenizers: Tokenizers,
 }
 
 pub struct PackageFields {
@@ -51,8 +60,20 @@ pub struct PackageFields {
     purl_qualifiers_values: Field,
 }
 
+pub struct QualityFields {
+    /// the quality score, from 0 to 100
+    score: Field,
+    supplier: Field,
+    version: Field,
+    unique_id: Field,
+    timestamp: Field,
+    dependencies: Field,
+}
+
 pub struct DepFields {
     purl: Field,
+    /// SHA256 and SHA1 digests of all packages
+    digest: Field,
 }
 
 struct Fields {
@@ -66,8 +87,19 @@ struct Fields {
     sbom_created: Field,
     sbom_creators: Field,
     sbom_name: Field,
+    /// labels, as "key=value"
+    sbom_label: Field,
+    /// the keys of labels
+    sbom_label_key: Field,
+    /// the format, "spdx" or "cyclonedx"
+    sbom_format: Field,
+    /// the version of the specification, like "1.5" or "2.3"
+    sbom_spec_version: Field,
+    /// the purls of the packages of the formulation, describing how the SBOM subject was built
+    formulation_purl: Field,
     sbom: PackageFields,
     dep: DepFields,
+    quality: QualityFields,
 }
 
 impl Default for Index {
@@ -78,27 +110,40 @@ impl Default for Index {
 
 impl Index {
     pub fn new() -> Self {
+        Self::with_tokenizers(&[])
+    }
+
+    /// Create the index, with the tokenizers configured for its fields.
+    ///
+    /// The names, the package names and the package URL names are raw by default, the package descriptions simple.
+    pub fn with_tokenizers(configured: &[FieldTokenizer]) -> Self {
+        let mut tokenizers = Tokenizers::new(configured);
         let mut schema = Schema::builder();
         let fields = Fields {
-            indexed_timestamp: schema.add_date_field("indexed_timestamp", STORED),
+            indexed_timestamp: schema.add_date_field("indexed_timestamp", STORED | FAST),
             sbom_id: schema.add_text_field("sbom_id", STRING | FAST | STORED),
             sbom_uid: schema.add_text_field("sbom_uid", STRING | FAST | STORED),
             sbom_sha256: schema.add_text_field("sbom_sha256", STRING | STORED),
             sbom_created: schema.add_date_field("sbom_created", INDEXED | FAST | STORED),
             sbom_creators: schema.add_text_field("sbom_creators", STRING | STORED),
-            sbom_name: schema.add_text_field("sbom_name", STRING | FAST | STORED),
+            sbom_name: tokenizers.add_text_field(&mut schema, "sbom_name", Tokenizer::Raw, FAST | STORED),
+            sbom_label: schema.add_text_field("sbom_label", STRING | STORED),
+            sbom_label_key: schema.add_text_field("sbom_label_key", STRING),
+            sbom_format: schema.add_text_field("sbom_format", STRING | FAST),
+            sbom_spec_version: schema.add_text_field("sbom_spec_version", STRING | FAST | STORED),
+            formulation_purl: schema.add_text_field("formulation_purl", STRING | STORED),
             sbom: PackageFields {
-                name: schema.add_text_field("sbom_pkg_name", STRING | FAST | STORED),
+                name: tokenizers.add_text_field(&mut schema, "sbom_pkg_name", Tokenizer::Raw, FAST | STORED),
                 version: schema.add_text_field("sbom_pkg_version", STRING | STORED),
                 purl: schema.add_text_field("sbom_pkg_purl", STRING | FAST | STORED),
-                desc: schema.add_text_field("sbom_pkg_desc", TEXT | STORED),
-                license: schema.add_text_field("sbom_pkg_license", TEXT | STORED),
+                desc: tokenizers.add_text_field(&mut schema, "sbom_pkg_desc", Tokenizer::Simple, STORED),
+                license: schema.add_text_field("sbom_pkg_license", TEXT | FAST | STORED),
                 cpe: schema.add_text_field("sbom_pkg_cpe", STRING | FAST | STORED),
-                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | STORED),
-                classifier: schema.add_text_field("sbom_pkg_classifier", STRING),
+                supplier: schema.add_text_field("sbom_pkg_supplier", STRING | FAST | STORED),
+                classifier: schema.add_text_field("sbom_pkg_classifier", STRING | FAST),
                 sha256: schema.add_text_field("sbom_pkg_sha256", STRING | STORED),
                 purl_type: schema.add_text_field("sbom_pkg_purl_type", STRING),
-                purl_name: schema.add_text_field("sbom_pkg_purl_name", FAST | STRING),
+                purl_name: tokenizers.add_text_field(&mut schema, "sbom_pkg_purl_name", Tokenizer::Raw, FAST),
                 purl_namespace: schema.add_text_field("sbom_pkg_purl_namespace", STRING),
                 purl_version: schema.add_text_field("sbom_pkg_purl_version", STRING),
                 purl_qualifiers: schema.add_text_field("sbom_pkg_purl_qualifiers", STRING),
@@ -106,11 +151,21 @@ impl Index {
             },
             dep: DepFields {
                 purl: schema.add_text_field("package_purl", FAST | STRING | STORED),
+                digest: schema.add_text_field("package_digest", STRING),
+            },
+            quality: QualityFields {
+                score: schema.add_f64_field("sbom_quality_score", INDEXED | FAST | STORED),
+                supplier: schema.add_f64_field("sbom_quality_supplier", STORED),
+                version: schema.add_f64_field("sbom_quality_version", STORED),
+                unique_id: schema.add_f64_field("sbom_quality_unique_id", STORED),
+                timestamp: schema.add_f64_field("sbom_quality_timestamp", STORED),
+                dependencies: schema.add_f64_field("sbom_quality_dependencies", STORED),
             },
         };
         Self {
             schema: schema.build(),
             fields,
+            tokenizers,
         }
     }
 
@@ -146,6 +201,7 @@ impl Index {
         );
 
         for package in &bom.package_information {
+            Self::index_spdx_digests(&mut document, package, &self.fields.dep);
             if bom
                 .document_creation_information
                 .document_describes
@@ -165,12 +221,20 @@ impl Index {
     fn index_spdx_dep(document: &mut Document, package: &spdx_rs::models::PackageInformation, fields: &DepFields) {
         for r in package.external_reference.iter() {
             if r.reference_type == "purl" {
-                let purl = r.reference_locator.clone();
+                let purl = trustification_purl::normalize(&r.reference_locator).into_owned();
                 document.add_text(fields.purl, &purl);
             }
         }
     }
 
+    fn index_spdx_digests(document: &mut Document, package: &spdx_rs::models::PackageInformation, fields: &DepFields) {
+        for sum in package.package_checksum.iter() {
+            if matches!(sum.algorithm, Algorithm::SHA256 | Algorithm::SHA1) {
+                document.add_text(fields.digest, sum.value.to_lowercase());
+            }
+        }
+    }
+
     fn index_spdx_package(
         document: &mut Document,
         package: &spdx_rs::models::PackageInformation,
@@ -184,7 +248,7 @@ impl Index {
                 document.add_text(fields.cpe, &r.reference_locator);
             }
             if r.reference_type == "purl" {
-                let purl = r.reference_locator.clone();
+                let purl = trustification_purl::normalize(&r.reference_locator).into_owned();
                 document.add_text(fields.purl, &purl);
 
                 if let Ok(package) = packageurl::PackageUrl::from_str(&purl) {
@@ -227,12 +291,7 @@ impl Index {
         }
     }
 
-    fn index_cyclonedx(
-        &self,
-        id: &str,
-        bom: &cyclonedx_bom::prelude::Bom,
-        sha256: &str,
-    ) -> Result<Vec<(String, Document)>, SearchError> {
+    fn index_cyclonedx(&self, id: &str, bom: &CycloneDx, sha256: &str) -> Result<Vec<(String, Document)>, SearchError> {
         let mut documents: Vec<(String, Document)> = Vec::new();
         let mut document = doc!();
         document.add_text(self.fields.sbom_sha256, sha256);
@@ -257,26 +316,51 @@ impl Index {
 
             if let Some(component) = &metadata.component {
                 document.add_text(self.fields.sbom_name, component.name.to_string());
+                Self::index_cyclonedx_digests(&mut document, component, &self.fields.dep);
                 Self::index_cyclonedx_component(&mut document, component, &self.fields.sbom);
             }
         }
 
         if let Some(components) = &bom.components {
             for component in components.0.iter() {
+                Self::index_cyclonedx_digests(&mut document, component, &self.fields.dep);
                 Self::index_cyclonedx_dep(&mut document, component, &self.fields.dep);
             }
         }
+
+        for component in &bom.formulation {
+            if let Some(purl) = &component.purl {
+                document.add_text(
+                    self.fields.formulation_purl,
+                    trustification_purl::normalize(&purl.to_string()),
+                );
+            }
+        }
         documents.push((id.to_string(), document));
         Ok(documents)
     }
 
     fn index_cyclonedx_dep(document: &mut Document, component: &cyclonedx_bom::prelude::Component, fields: &DepFields) {
         if let Some(purl) = &component.purl {
-            let purl = purl.to_string();
+            let purl = trustification_purl::normalize(&purl.to_string()).into_owned();
             document.add_text(fields.purl, &purl);
         }
     }
 
+    fn index_cyclonedx_digests(
+        document: &mut Document,
+        component: &cyclonedx_bom::prelude::Component,
+        fields: &DepFields,
+    ) {
+        if let Some(hashes) = &component.hashes {
+            for hash in hashes.0.iter() {
+                if matches!(hash.alg, HashAlgorithm::SHA256 | HashAlgorithm::SHA1) {
+                    document.add_text(fields.digest, hash.content.0.to_lowercase());
+                }
+            }
+        }
+    }
+
     fn index_cyclonedx_component(
         document: &mut Document,
         component: &cyclonedx_bom::prelude::Component,
@@ -296,7 +380,7 @@ impl Index {
         };
 
         if let Some(purl) = &component.purl {
-            let purl = purl.to_string();
+            let purl = trustification_purl::normalize(&purl.to_string()).into_owned();
             document.add_text(fields.purl, &purl);
 
             if let Ok(package) = packageurl::PackageUrl::from_str(&purl) {
@@ -335,30 +419,40 @@ impl Index {
         document.add_text(fields.classifier, component.component_type.to_string());
     }
 
-    fn resource2query(&self, resource: &Packages) -> Box<dyn Query> {
+    fn resource2query(&self, resource: &Packages, context: &QueryContext) -> Box<dyn Query> {
         const PACKAGE_WEIGHT: f32 = 1.5;
         const CREATED_WEIGHT: f32 = 1.25;
         match resource {
-            Packages::Id(value) => Box::new(TermQuery::new(
-                Term::from_field_text(self.fields.sbom_id, value),
-                Default::default(),
-            )),
+            Packages::Id(value) => boost_query(
+                "id",
+                1.0,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.fields.sbom_id, value),
+                    Default::default(),
+                )),
+                context,
+            ),
             Packages::Uid(value) => Box::new(TermQuery::new(
                 Term::from_field_text(self.fields.sbom_uid, value),
                 Default::default(),
             )),
-            Packages::Package(primary) => boost(
-                self.create_string_query(
-                    &[
-                        self.fields.sbom_name,
-                        self.fields.sbom.name,
-                        self.fields.sbom.purl,
-                        self.fields.sbom.cpe,
-                        self.fields.sbom.purl_name,
-                    ],
-                    primary,
-                ),
+            Packages::Package(primary) => boost_query(
+                "package",
                 PACKAGE_WEIGHT,
+                Box::new(BooleanQuery::union(vec![
+                    self.create_string_query(
+                        &[
+                            self.fields.sbom_name,
+                            self.fields.sbom.name,
+                            self.fields.sbom.cpe,
+                            self.fields.sbom.purl_name,
+                        ],
+                        primary,
+                        context,
+                    ),
+                    create_purl_query(self.fields.sbom.purl, primary, context),
+                ])),
+                context,
             ),
 
             Packages::Type(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
@@ -371,19 +465,31 @@ impl Index {
                 value,
             )])),
 
-            Packages::Created(ordered) => boost(
-                create_date_query(&self.schema, self.fields.sbom_created, ordered),
+            Packages::Created(ordered) => boost_query(
+                "created",
                 CREATED_WEIGHT,
+                create_date_query(&self.schema, self.fields.sbom_created, ordered),
+                context,
             ),
 
-            Packages::Version(value) => {
-                self.create_string_query(&[self.fields.sbom.version, self.fields.sbom.purl_version], value)
-            }
+            Packages::Version(value) => boost_query(
+                "version",
+                1.0,
+                self.create_string_query(
+                    &[self.fields.sbom.version, self.fields.sbom.purl_version],
+                    value,
+                    context,
+                ),
+                context,
+            ),
 
-            Packages::Description(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
-                self.fields.sbom.desc,
-                value,
-            )])),
+            Packages::Description(value) => boost_query(
+                "description",
+                1.0,
+                self.tokenizers
+                    .query(self.fields.sbom.desc, &Primary::Equal(value), context),
+                context,
+            ),
 
             Packages::Digest(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.sbom.sha256,
@@ -395,19 +501,55 @@ impl Index {
                 value,
             )])),
 
-            Packages::Supplier(primary) => self.create_string_query(&[self.fields.sbom.supplier], primary),
+            Packages::Supplier(primary) => self.create_string_query(&[self.fields.sbom.supplier], primary, context),
 
             Packages::Qualifier(qualified) => {
                 let mut qs = Vec::new();
                 for qualifier in qualified.qualifier.0.iter() {
                     let exp = format!("{}={}", qualifier, qualified.expression);
-                    let q = self.create_string_query(&[self.fields.sbom.purl_qualifiers], &Primary::Equal(&exp));
+                    let q =
+                        self.create_string_query(&[self.fields.sbom.purl_qualifiers], &Primary::Equal(&exp), context);
                     qs.push(q);
                 }
                 Box::new(BooleanQuery::union(qs))
             }
 
-            Packages::Dependency(primary) => self.create_string_query(&[self.fields.dep.purl], primary),
+            Packages::Dependency(primary) => create_purl_query(self.fields.dep.purl, primary, context),
+
+            Packages::SpecVersion(value) => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.sbom_spec_version, value),
+                Default::default(),
+            )),
+
+            Packages::Formulation(primary) => create_purl_query(self.fields.formulation_purl, primary, context),
+
+            Packages::QualityScore(value) => create_float_query(&self.schema, [self.fields.quality.score], value),
+
+            Packages::Artifact(value) => {
+                // accept digests in the form of "<algorithm>:<digest>" too
+                let digest = value.rsplit_once(':').map(|(_, digest)| digest).unwrap_or(value);
+                Box::new(TermQuery::new(
+                    Term::from_field_text(self.fields.dep.digest, &digest.to_lowercase()),
+                    Default::default(),
+                ))
+            }
+
+            Packages::Signed => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.sbom_label, &format!("{SIGNED_LABEL}=true")),
+                Default::default(),
+            )),
+            Packages::Verified => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.sbom_label, &format!("{VERIFIED_LABEL}=true")),
+                Default::default(),
+            )),
+
+            Packages::Label(value) => {
+                let field = match value.contains('=') {
+                    true => self.fields.sbom_label,
+                    false => self.fields.sbom_label_key,
+                };
+                Box::new(TermQuery::new(Term::from_field_text(field, value), Default::default()))
+            }
 
             Packages::Application => self.match_classifiers(Classification::Application),
             Packages::Library => self.match_classifiers(Classification::Library),
@@ -417,15 +559,49 @@ impl Index {
             Packages::Device => self.match_classifiers(Classification::Device),
             Packages::Firmware => self.match_classifiers(Classification::Firmware),
             Packages::File => self.match_classifiers(Classification::File),
+            // classifications introduced after the model, since CycloneDX 1.5
+            Packages::MachineLearningModel => self.match_classifiers("machine-learning-model"),
+            Packages::Data => self.match_classifiers("data"),
+            Packages::CryptographicAsset => self.match_classifiers("cryptographic-asset"),
+            Packages::Platform => self.match_classifiers("platform"),
+            Packages::DeviceDriver => self.match_classifiers("device-driver"),
         }
     }
 
-    fn create_string_query(&self, fields: &[Field], value: &Primary<'_>) -> Box<dyn Query> {
-        let queries: Vec<Box<dyn Query>> = fields.iter().map(|f| create_string_query(*f, value)).collect();
+    /// Create a snippet from the description and the component fields, highlighting the matched terms.
+    fn highlight(&self, searcher: &Searcher, query: &dyn Query, doc: &Document) -> Result<String, SearchError> {
+        // maximum number of matched component values to include
+        const MAX_VALUES: usize = 5;
+
+        let description = SnippetGenerator::create(searcher, query, self.fields.sbom.desc)?.snippet_from_doc(doc);
+        let values = [
+            self.fields.sbom_name,
+            self.fields.sbom.name,
+            self.fields.sbom.purl,
+            self.fields.sbom.cpe,
+            self.fields.dep.purl,
+        ]
+        .into_iter()
+        .flat_map(|field| highlight_values(query, field, doc))
+        .take(MAX_VALUES);
+
+        let fragments: Vec<_> = std::iter::once(snippet2html(&description))
+            .filter(|fragment| !fragment.is_empty())
+            .chain(values)
+            .collect();
+
+        Ok(fragments.join(" … "))
+    }
+
+    fn create_string_query(&self, fields: &[Field], value: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
+        let queries: Vec<Box<dyn Query>> = fields
+            .iter()
+            .map(|f| self.tokenizers.query(*f, value, context))
+            .collect();
         Box::new(BooleanQuery::union(queries))
     }
 
-    fn match_classifiers(&self, classification: Classification) -> Box<dyn Query> {
+    fn match_classifiers(&self, classification: impl ToString) -> Box<dyn Query> {
         Box::new(BooleanQuery::union(vec![create_boolean_query(
             Occur::Should,
             Term::from_field_text(self.fields.sbom.classifier, &classification.to_string()),
@@ -436,7 +612,7 @@ impl Index {
 impl trustification_index::Index for Index {
     type MatchedDocument = SearchHit;
 
-    fn prepare_query(&self, q: &str) -> Result<SearchQuery, SearchError> {
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, SearchError> {
         let mut query = Packages::parse(q).map_err(|err| SearchError::QueryParser(err.to_string()))?;
         query.term = query.term.compact();
 
@@ -453,13 +629,21 @@ impl trustification_index::Index for Index {
                         sort_by.replace((self.fields.sbom_created, Order::Asc));
                     }
                 },
+                PackagesSortable::QualityScore => match f.direction {
+                    Direction::Descending => {
+                        sort_by.replace((self.fields.quality.score, Order::Desc));
+                    }
+                    Direction::Ascending => {
+                        sort_by.replace((self.fields.quality.score, Order::Asc));
+                    }
+                },
             }
         }
 
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            term2query(&query.term, &|resource| self.resource2query(resource, context))
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -472,6 +656,7 @@ impl trustification_index::Index for Index {
         query: &dyn Query,
         offset: usize,
         limit: usize,
+        _context: &QueryContext,
     ) -> Result<(Vec<(f32, DocAddress)>, usize), SearchError> {
         let date_field = self.schema.get_field_name(self.fields.sbom_created).to_string();
         let now = tantivy::DateTime::from_utc(OffsetDateTime::now_utc());
@@ -520,8 +705,12 @@ impl trustification_index::Index for Index {
             .map(ToString::to_string);
         let name = field2str(&self.schema, &doc, self.fields.sbom_name)?;
 
-        let snippet_generator = SnippetGenerator::create(searcher, query, self.fields.sbom.desc)?;
-        let snippet = snippet_generator.snippet_from_doc(&doc).to_html();
+        let snippet = match options.highlight {
+            true => self.highlight(searcher, query, &doc)?,
+            false => SnippetGenerator::create(searcher, query, self.fields.sbom.desc)?
+                .snippet_from_doc(&doc)
+                .to_html(),
+        };
 
         let file_sha256 = doc
             .get_first(self.fields.sbom_sha256)
@@ -558,6 +747,11 @@ impl trustification_index::Index for Index {
             .map(|s| s.as_text().unwrap_or("Unknown"))
             .unwrap_or("Unknown");
 
+        let spec_version = doc
+            .get_first(self.fields.sbom_spec_version)
+            .and_then(|s| s.as_text())
+            .map(ToString::to_string);
+
         let supplier = doc
             .get_first(self.fields.sbom.supplier)
             .map(|s| s.as_text().unwrap_or("Unknown"))
@@ -578,6 +772,27 @@ impl trustification_index::Index for Index {
             .unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
 
         let dependencies: u64 = doc.get_all(self.fields.dep.purl).count() as u64;
+
+        let labels = doc
+            .get_all(self.fields.sbom_label)
+            .filter_map(|s| s.as_text())
+            .filter_map(|s| s.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let verification = Verification::from_labels(&labels);
+
+        let quality_score = doc.get_first(self.fields.quality.score).and_then(|s| s.as_f64());
+        let quality = quality_score.map(|_| {
+            let element = |field| doc.get_first(field).and_then(|s| s.as_f64()).unwrap_or_default();
+            Quality {
+                supplier: element(self.fields.quality.supplier),
+                version: element(self.fields.quality.version),
+                unique_id: element(self.fields.quality.unique_id),
+                timestamp: element(self.fields.quality.timestamp),
+                dependencies: element(self.fields.quality.dependencies),
+            }
+        });
+
         let document = SearchDocument {
             id: id.to_string(),
             uid,
@@ -589,11 +804,17 @@ impl trustification_index::Index for Index {
             sha256: sha256.to_string(),
             license: license.to_string(),
             classifier: classifier.to_string(),
+            spec_version,
             supplier: supplier.to_string(),
             snippet,
             created,
             description: description.to_string(),
             dependencies,
+            labels,
+            signed: verification.signed,
+            verified: verification.verified,
+            quality_score,
+            quality,
         };
 
         let explanation: Option<serde_json::Value> = if options.explain {
@@ -617,6 +838,88 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn facet_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "supplier" => Some(self.fields.sbom.supplier),
+            "classifier" => Some(self.fields.sbom.classifier),
+            "license" => Some(self.fields.sbom.license),
+            "format" => Some(self.fields.sbom_format),
+            "spec_version" => Some(self.fields.sbom_spec_version),
+            _ => None,
+        }
+    }
+
+    fn histogram_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "created" => Some(self.fields.sbom_created),
+            "indexed" => Some(self.fields.indexed_timestamp),
+            _ => None,
+        }
+    }
+
+    fn stats_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "quality_score" => Some(self.fields.quality.score),
+            _ => None,
+        }
+    }
+
+    fn dedup_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "uid" => Some(self.fields.sbom_uid),
+            "name" => Some(self.fields.sbom_name),
+            _ => None,
+        }
+    }
+
+    fn sort_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "created" => Some(self.fields.sbom_created),
+            "indexed" => Some(self.fields.indexed_timestamp),
+            "name" => Some(self.fields.sbom_name),
+            "quality_score" => Some(self.fields.quality.score),
+            _ => None,
+        }
+    }
+
+    fn qualifiers(&self) -> &[&'static str] {
+        Packages::VARIANTS
+    }
+
+    fn qualifier_field(&self, name: &str) -> Option<SearchField> {
+        let field = |f, example| search_field(&self.schema, name, f, example);
+        Some(match name {
+            "id" => field(self.fields.sbom_id, "id:my-sbom").with_default(),
+            "uid" => field(
+                self.fields.sbom_uid,
+                "uid:\"urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79\"",
+            ),
+            "package" => field(self.fields.sbom_name, "package:openssl").with_default(),
+            "type" => field(self.fields.sbom.purl_type, "type:oci"),
+            "namespace" => field(self.fields.sbom.purl_namespace, "namespace:redhat"),
+            "version" => field(self.fields.sbom.version, "version:1.1.1k").with_default(),
+            "description" => field(self.fields.sbom.desc, "description:\"cryptography library\"").with_default(),
+            "created" => field(self.fields.sbom_created, "created:>2023-01-01").with_sortable(),
+            "digest" => field(
+                self.fields.sbom.sha256,
+                "digest:9b1a5c3fd5ee7a5c0af1c7e5379c5f9bbd1e1a4c5d5fb2324b2a8a3b3e56b3b2",
+            ),
+            "license" => field(self.fields.sbom.license, "license:Apache-2.0"),
+            "supplier" => field(self.fields.sbom.supplier, "supplier:\"Organization: Red Hat\""),
+            "qualifier" => field(self.fields.sbom.purl_qualifiers, "qualifier:arch:x86_64"),
+            "dependency" => field(self.fields.dep.purl, "dependency:\"pkg:npm/lodash@4.17.21\""),
+            "artifact" => field(
+                self.fields.dep.digest,
+                "artifact:\"sha256:9b1a5c3fd5ee7a5c0af1c7e5379c5f9bbd1e1a4c5d5fb2324b2a8a3b3e56b3b2\"",
+            ),
+            "label" => field(self.fields.sbom_label, "label:\"team=security\""),
+            "specVersion" => field(self.fields.sbom_spec_version, "specVersion:1.5"),
+            "formulation" => field(self.fields.formulation_purl, "formulation:\"pkg:generic/builder\""),
+            "qualityScore" => field(self.fields.quality.score, "qualityScore:>=80").with_sortable(),
+            _ => return None,
+        })
+    }
 }
 
 impl trustification_index::WriteIndex for Index {
@@ -627,17 +930,44 @@ impl trustification_index::WriteIndex for Index {
     }
 
     fn index_doc(&self, id: &str, (doc, sha256): &Self::Document) -> Result<Vec<(String, Document)>, SearchError> {
-        let doc = match doc {
+        let mut documents = match doc {
             SBOM::CycloneDX(bom) => self.index_cyclonedx(id, bom, sha256)?,
             SBOM::SPDX(bom) => self.index_spdx(id, bom, sha256)?,
         };
+        let quality = Quality::from(doc);
+        for (_, document) in &mut documents {
+            document.add_text(self.fields.sbom_format, doc.format());
+            document.add_text(self.fields.sbom_spec_version, doc.spec_version());
+            document.add_f64(self.fields.quality.score, quality.score());
+            document.add_f64(self.fields.quality.supplier, quality.supplier);
+            document.add_f64(self.fields.quality.version, quality.version);
+            document.add_f64(self.fields.quality.unique_id, quality.unique_id);
+            document.add_f64(self.fields.quality.timestamp, quality.timestamp);
+            document.add_f64(self.fields.quality.dependencies, quality.dependencies);
+        }
 
-        Ok(doc)
+        Ok(documents)
+    }
+
+    fn index_labeled_doc(
+        &self,
+        id: &str,
+        document: &Self::Document,
+        labels: &Labels,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
+        let mut documents = self.index_doc(id, document)?;
+        for (_, document) in &mut documents {
+            for (key, value) in labels {
+                document.add_text(self.fields.sbom_label, format!("{key}={value}"));
+                document.add_text(self.fields.sbom_label_key, key);
+            }
+        }
+        Ok(documents)
     }
 
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, SearchError> {
         let sha256 = sha256::digest(data);
-        SBOM::parse(data)
+        SBOM::parse_pruned(data)
             .map_err(|e| SearchError::DocParser(e.to_string()))
             .map(|doc| (doc, sha256))
     }
@@ -666,7 +996,8 @@ mod tests {
     use sbom_walker::Sbom;
     use std::path::Path;
     use time::format_description;
-    use trustification_index::{IndexStore, IndexWriter};
+    use trustification_api::search::{FacetCount, SearchFieldType};
+    use trustification_index::{boosts::Boosts, IndexStore, IndexWriter};
 
     use super::*;
 
@@ -687,12 +1018,18 @@ mod tests {
     }
 
     fn assert_search<F>(f: F)
+    where
+        F: FnOnce(IndexStore<Index>),
+    {
+        assert_search_index(Index::new(), f)
+    }
+
+    fn assert_search_index<F>(index: Index, f: F)
     where
         F: FnOnce(IndexStore<Index>),
     {
         let _ = env_logger::try_init();
 
-        let index = Index::new();
         let mut store = IndexStore::new_in_memory(index).unwrap();
         let mut writer = store.writer().unwrap();
 
@@ -715,6 +1052,7 @@ mod tests {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    ..Default::default()
                 },
             )
             .unwrap()
@@ -728,6 +1066,310 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_boosts() {
+        assert_search(|index| {
+            let q = "ubi9-container in:package";
+            let default = search(&index, q).0[0].score;
+
+            // replaces the default weight of 1.5
+            let boosts = Boosts::new(&["package=3".parse().unwrap()], Default::default());
+            let index = index.with_boosts(boosts);
+            let boosted = search(&index, q).0[0].score;
+            assert!((boosted / default - 2.0).abs() < 0.01, "{default} -> {boosted}");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_search_artifact() {
+        assert_search(|index| {
+            // digests of the "quarkus-arc" component
+            let result = search(
+                &index,
+                "artifact:a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc",
+            );
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.id, "my-sbom");
+
+            let result = search(&index, "artifact:\"sha1:E743499C22F07EDA63501CB6CCC3DC8E05ACC399\"");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "artifact:0000000000000000000000000000000000000000");
+            assert_eq!(result.0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_highlight() {
+        assert_search(|index| {
+            let purl = "pkg:rpm/redhat/glib2@2.68.4-5.el9?arch=aarch64";
+            let (result, _) = index
+                .search(
+                    &format!("dependency:\"{purl}\""),
+                    0,
+                    10,
+                    SearchOptions {
+                        highlight: true,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(result.len(), 1);
+            assert!(
+                result[0].document.snippet.contains(&format!("<em>{purl}</em>")),
+                "{}",
+                result[0].document.snippet
+            );
+
+            // without highlighting, there's no match in the description
+            let (result, _) = search(&index, &format!("dependency:\"{purl}\""));
+            assert_eq!(result[0].document.snippet, "");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_search_label() {
+        let _ = env_logger::try_init();
+
+        let index = Index::new();
+        let mut store = IndexStore::new_in_memory(index).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        let data = std::fs::read("../testdata/my-sbom.json").unwrap();
+        let labels = Labels::from([
+            ("team".to_string(), "platform".to_string()),
+            ("env".to_string(), "prod".to_string()),
+        ]);
+        writer
+            .add_labeled_document(store.index_as_mut(), "my-sbom", &data, &labels)
+            .unwrap();
+        let data = std::fs::read("../testdata/kmm-1.json").unwrap();
+        writer.add_document(store.index_as_mut(), "kmm-1", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "label:\"team=platform\"");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "my-sbom");
+        assert_eq!(result.0[0].document.labels, labels);
+
+        let result = search(&store, "label:env");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "label:\"team=other\"");
+        assert_eq!(result.0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_signed() {
+        let _ = env_logger::try_init();
+
+        let index = Index::new();
+        let mut store = IndexStore::new_in_memory(index).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        let mut labels = Labels::new();
+        Verification {
+            signed: true,
+            verified: false,
+            identity: None,
+        }
+        .apply(&mut labels);
+        let data = std::fs::read("../testdata/my-sbom.json").unwrap();
+        writer
+            .add_labeled_document(store.index_as_mut(), "my-sbom", &data, &labels)
+            .unwrap();
+        let mut labels = Labels::new();
+        Verification {
+            signed: true,
+            verified: true,
+            identity: Some("someone@example.com".to_string()),
+        }
+        .apply(&mut labels);
+        let data = std::fs::read("../testdata/kmm-1.json").unwrap();
+        writer
+            .add_labeled_document(store.index_as_mut(), "kmm-1", &data, &labels)
+            .unwrap();
+        let data = std::fs::read("../testdata/ubi9-sbom.json").unwrap();
+        writer.add_document(store.index_as_mut(), "ubi9", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "is:signed");
+        assert_eq!(result.0.len(), 2);
+
+        let result = search(&store, "is:verified");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "kmm-1");
+        assert!(result.0[0].document.signed);
+        assert!(result.0[0].document.verified);
+
+        let result = search(&store, "NOT is:signed");
+        assert_eq!(result.0.len(), 1);
+        assert!(!result.0[0].document.signed);
+    }
+
+    #[tokio::test]
+    async fn test_search_spec_version() {
+        assert_search(|index| {
+            let result = search(&index, "specVersion:2.2");
+            assert_eq!(result.0.len(), 2);
+
+            let result = search(&index, "specVersion:1.3");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.spec_version.as_deref(), Some("1.3"));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_search_quality_score() {
+        assert_search(|index| {
+            let result = search(&index, "qualityScore:>=0");
+            assert_eq!(result.0.len(), 3);
+            for hit in &result.0 {
+                let score = hit.document.quality_score.expect("scored at index time");
+                assert!((0.0..=100.0).contains(&score));
+                assert_eq!(hit.document.quality.as_ref().map(Quality::score), Some(score));
+            }
+
+            let result = search(&index, "qualityScore:>100");
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, "sort:qualityScore");
+            assert_eq!(result.0.len(), 3);
+            assert!(result
+                .0
+                .windows(2)
+                .all(|pair| pair[0].document.quality_score <= pair[1].document.quality_score));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cyclonedx_1_5() {
+        let _ = env_logger::try_init();
+
+        let index = Index::new();
+        let mut store = IndexStore::new_in_memory(index).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        // the SBOM walker doesn't understand CycloneDX 1.5 yet, so it's not validated by it
+        let data = std::fs::read("../testdata/cyclonedx-1.5.json").unwrap();
+        writer
+            .add_document(store.index_as_mut(), "cyclonedx-1.5", &data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "specVersion:1.5");
+        assert_eq!(result.0.len(), 1);
+        let document = &result.0[0].document;
+        assert_eq!(document.name, "sentiment-model");
+        assert_eq!(document.classifier, "machine-learning-model");
+        assert_eq!(document.spec_version.as_deref(), Some("1.5"));
+        assert_eq!(document.dependencies, 1);
+
+        assert_eq!(search(&store, "is:machineLearningModel").0.len(), 1);
+        assert_eq!(search(&store, "is:library").0.len(), 0);
+        assert_eq!(search(&store, "\"pkg:pypi/torch@2.1.2\" in:dependency").0.len(), 1);
+        assert_eq!(
+            search(&store, "formulation:\"pkg:pypi/transformers@4.36.2\"").0.len(),
+            1
+        );
+        assert_eq!(search(&store, "formulation:\"pkg:pypi/torch@2.1.2\"").0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_spdx_3_0() {
+        let _ = env_logger::try_init();
+
+        let index = Index::new();
+        let mut store = IndexStore::new_in_memory(index).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        // the SBOM walker doesn't understand SPDX 3.0 yet, so it's not validated by it
+        let data = std::fs::read("../testdata/spdx-3.0.json").unwrap();
+        writer.add_document(store.index_as_mut(), "spdx-3.0", &data).unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "specVersion:3.0.1");
+        assert_eq!(result.0.len(), 1);
+        let document = &result.0[0].document;
+        assert_eq!(
+            document.uid.as_deref(),
+            Some("https://example.com/spdx/example-app/document")
+        );
+        assert_eq!(document.name, "example-app");
+        assert_eq!(document.version, "1.2.3");
+        assert_eq!(document.supplier, "Organization: Example Inc.");
+        assert_eq!(document.license, "Apache-2.0");
+        assert_eq!(document.dependencies, 1);
+
+        assert_eq!(
+            search(&store, "\"pkg:rpm/redhat/openssl@3.0.7\" in:dependency").0.len(),
+            1
+        );
+        assert_eq!(search(&store, "example-app in:package").0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_facets() {
+        assert_search(|index| {
+            let facets = index.facets("", &["supplier".to_string()]).unwrap();
+            assert_eq!(
+                facets["supplier"],
+                vec![FacetCount {
+                    value: "Organization: Red Hat".to_string(),
+                    count: 2,
+                }]
+            );
+
+            assert!(index.facets("", &["name".to_string()]).is_err());
+        });
+    }
+
+    #[tokio::test]
+    async fn test_sort_option() {
+        assert_search(|index| {
+            let sorted = |sort: &str| {
+                index
+                    .search(
+                        "",
+                        0,
+                        10000,
+                        SearchOptions {
+                            sort: Some(sort.to_string()),
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap()
+                    .0
+            };
+
+            let result = sorted("name:asc");
+            assert_eq!(result.len(), 3);
+            let names: Vec<_> = result.iter().map(|hit| hit.document.name.clone()).collect();
+            let mut expected = names.clone();
+            expected.sort();
+            assert_eq!(names, expected);
+
+            let result = sorted("created:desc");
+            assert_eq!(result.len(), 3);
+            assert!(result
+                .windows(2)
+                .all(|pair| pair[0].document.created >= pair[1].document.created));
+
+            assert!(matches!(
+                index.search(
+                    "",
+                    0,
+                    10,
+                    SearchOptions {
+                        sort: Some("digest:asc".to_string()),
+                        ..Default::default()
+                    }
+                ),
+                Err(SearchError::NotSortable(_))
+            ));
+        });
+    }
+
     #[tokio::test]
     async fn test_search_package() {
         assert_search(|index| {
@@ -751,6 +1393,44 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_search_package_fuzzy() {
+        assert_search(|index| {
+            let result = search(&index, "package:ubi9-containr");
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, "package:~ubi9-containr");
+            assert_eq!(result.0.len(), 1);
+
+            // two edits require a larger distance
+            let result = search(&index, "package:~ubi9-contanre");
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, "package:~~ubi9-contanre");
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_search_package_ngram() {
+        let tokenizers: Vec<FieldTokenizer> = ["sbom_name=ngram", "sbom_pkg_name=ngram", "sbom_pkg_purl_name=ngram"]
+            .iter()
+            .map(|configured| configured.parse().unwrap())
+            .collect();
+
+        assert_search(|index| {
+            let result = search(&index, "package:i9-contai");
+            assert_eq!(result.0.len(), 0);
+        });
+        assert_search_index(Index::with_tokenizers(&tokenizers), |index| {
+            let result = search(&index, "package:i9-contai");
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "package:ubi9-container");
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
     #[tokio::test]
     async fn test_search_namespace() {
         assert_search(|index| {
@@ -877,6 +1557,7 @@ mod tests {
                         explain: false,
                         metadata: true,
                         summaries: true,
+                        ..Default::default()
                     },
                 )
                 .unwrap();
@@ -903,6 +1584,7 @@ mod tests {
                         explain: true,
                         metadata: false,
                         summaries: true,
+                        ..Default::default()
                     },
                 )
                 .unwrap();
@@ -914,4 +1596,23 @@ mod tests {
             );
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            assert!(fields.iter().any(|f| f.name == "created" && f.sortable));
+            assert!(fields
+                .iter()
+                .any(|f| f.name == "signed" && f.r#type == SearchFieldType::Predicate));
+            // all examples are valid queries, including the ones of qualifiers lacking a field, described as predicates
+            for field in fields {
+                assert!(
+                    index.search(&field.example, 0, 10, Default::default()).is_ok(),
+                    "{}",
+                    field.example
+                );
+            }
+        });
+    }
 }
@@ -7,7 +7,7 @@ use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
 use trustification_event_bus::EventBusConfig;
-use trustification_index::{IndexConfig, IndexStore, WriteIndex};
+use trustification_index::{require_unsharded, IndexConfig, IndexStore, WriteIndex};
 use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
 use trustification_infrastructure::health::checks::FailureRate;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
@@ -25,6 +25,9 @@ pub struct Run {
     #[arg(long = "failed-topic", default_value = "sbom-failed")]
     pub failed_topic: String,
 
+    #[arg(long = "deleted-topic", default_value = "sbom-deleted")]
+    pub deleted_topic: String,
+
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
@@ -56,13 +59,15 @@ impl Run {
                 "bombastic-indexer",
                 |_context| async { Ok(()) },
                 |context| async move {
-                    let sbom_index: Box<dyn WriteIndex<Document = (SBOM, String)>> = Box::new(sbom::Index::new());
+                    require_unsharded(&self.index, "bombastic")?;
+                    let sbom_index: Box<dyn WriteIndex<Document = (SBOM, String)>> =
+                        Box::new(sbom::Index::with_tokenizers(&self.index.tokenizers));
                     let sbom_store = block_in_place(|| {
                         IndexStore::new(&self.storage, &self.index, sbom_index, context.metrics.registry())
                     })?;
 
                     let package_index: Box<dyn WriteIndex<Document = (SBOM, String)>> =
-                        Box::new(packages::Index::new());
+                        Box::new(packages::Index::with_tokenizers(&self.index.tokenizers));
                     let package_store = block_in_place(|| {
                         IndexStore::new(&self.storage, &self.index, package_index, context.metrics.registry())
                     })?;
@@ -84,6 +89,7 @@ impl Run {
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: self.deleted_topic.as_str(),
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
@@ -0,0 +1,9 @@
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed=proto/bom-1.5.proto");
+    prost_reflect_build::Builder::new()
+        .descriptor_pool("crate::protobuf::DESCRIPTOR_POOL")
+        .compile_protos(&["proto/bom-1.5.proto"], &["proto"])?;
+    Ok(())
+}
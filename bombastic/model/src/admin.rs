@@ -0,0 +1,117 @@
+//! Administration of the stored SBOMs.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// The state of reindexing all stored SBOMs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReindexState {
+    /// Reindexing wasn't started yet
+    #[default]
+    Idle,
+    /// Events are being published for the stored SBOMs
+    Running,
+    /// Events were published for all stored SBOMs
+    Completed,
+    /// Publishing events failed, see the error
+    Failed,
+}
+
+/// The stored SBOMs to reindex, by a range of their keys and a window of the time they were stored.
+///
+/// All bounds are optional, the SBOMs matching all of the given bounds are reindexed. Without any bound, all stored
+/// SBOMs are reindexed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct ReindexSelection {
+    /// Only SBOMs with a key greater than or equal to this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Only SBOMs with a key less than this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Only SBOMs stored at or after this time, in RFC 3339 format
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[param(value_type = Option<String>)]
+    #[schema(value_type = Option<String>)]
+    pub since: Option<OffsetDateTime>,
+    /// Only SBOMs stored before this time, in RFC 3339 format
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[param(value_type = Option<String>)]
+    #[schema(value_type = Option<String>)]
+    pub until: Option<OffsetDateTime>,
+}
+
+impl ReindexSelection {
+    /// Check if an SBOM is selected, by its key and the time it was stored, if known.
+    ///
+    /// SBOMs of which the time they were stored is unknown are not selected by a time window.
+    pub fn matches(&self, key: &str, stored: Option<OffsetDateTime>) -> bool {
+        let in_window = match (self.since, self.until) {
+            (None, None) => true,
+            (since, until) => stored.is_some_and(|stored| {
+                since.map_or(true, |since| stored >= since) && until.map_or(true, |until| stored < until)
+            }),
+        };
+        in_window
+            && self.from.as_deref().map_or(true, |from| key >= from)
+            && self.to.as_deref().map_or(true, |to| key < to)
+    }
+}
+
+/// The progress of reindexing all stored SBOMs, by publishing an event for each of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReindexStatus {
+    pub state: ReindexState,
+    /// The stored SBOMs being reindexed
+    #[serde(default)]
+    pub selection: ReindexSelection,
+    /// Number of stored SBOMs to reindex, once they were listed
+    pub total: usize,
+    /// Number of SBOMs an event was published for
+    pub published: usize,
+    /// The error which made reindexing fail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_selection() {
+        let stored = Some(datetime!(2023-06-01 12:00 UTC));
+        assert!(ReindexSelection::default().matches("a", None));
+
+        let selection = ReindexSelection {
+            from: Some("b".to_string()),
+            to: Some("d".to_string()),
+            ..Default::default()
+        };
+        assert!(!selection.matches("a", stored));
+        assert!(selection.matches("b", stored));
+        assert!(selection.matches("cde", stored));
+        assert!(!selection.matches("d", stored));
+
+        let selection = ReindexSelection {
+            since: Some(datetime!(2023-06-01 00:00 UTC)),
+            until: Some(datetime!(2023-06-02 00:00 UTC)),
+            ..Default::default()
+        };
+        assert!(selection.matches("a", stored));
+        assert!(!selection.matches("a", Some(datetime!(2023-06-02 00:00 UTC))));
+        assert!(!selection.matches("a", Some(datetime!(2023-05-31 23:59 UTC))));
+        assert!(!selection.matches("a", None));
+    }
+}
@@ -0,0 +1,218 @@
+//! The components of SBOMs, as listed without the rest of the document.
+
+use std::collections::BTreeMap;
+
+/// A component of an SBOM, a package for SPDX documents.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, utoipa::ToSchema)]
+pub struct SbomComponent {
+    /// Document internal reference of the component, the SPDX identifier or the CycloneDX `bom-ref`
+    pub reference: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Package URL of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    /// Licenses of the component, as SPDX expressions or license names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<String>,
+    /// Hashes of the component, by their algorithm
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<String, String>,
+}
+
+impl SbomComponent {
+    /// Check if the name or the package URL contain a term, ignoring the case
+    pub fn matches(&self, term: &str) -> bool {
+        let term = term.to_lowercase();
+        self.name.to_lowercase().contains(&term)
+            || self
+                .purl
+                .as_ref()
+                .is_some_and(|purl| purl.to_lowercase().contains(&term))
+    }
+}
+
+/// A page of the components of an SBOM.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct SbomComponentResult {
+    /// Total number of matching components
+    pub total: usize,
+    /// Components matched, within the offset and limit requested
+    pub result: Vec<SbomComponent>,
+}
+
+/// The components of a CycloneDX SBOM, in the order of the document, starting with the component of the metadata.
+/// Nested components follow their parent.
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+pub(crate) fn walk(bom: &cyclonedx_bom::prelude::Bom) -> Vec<&cyclonedx_bom::prelude::Component> {
+    let mut result = Vec::new();
+    let mut todo: Vec<_> = bom
+        .metadata
+        .iter()
+        .flat_map(|metadata| metadata.component.iter())
+        .chain(bom.components.iter().flat_map(|c| c.0.iter()))
+        .rev()
+        .collect();
+    while let Some(component) = todo.pop() {
+        todo.extend(component.components.iter().flat_map(|c| c.0.iter()).rev());
+        result.push(component);
+    }
+    result
+}
+
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+mod parse {
+    use super::{walk, SbomComponent};
+    use crate::data::SBOM;
+    use cyclonedx_bom::models::license::{LicenseChoice, LicenseIdentifier};
+    use cyclonedx_bom::prelude::Bom;
+    use spdx_rs::models::{Algorithm, SPDX};
+
+    impl SBOM {
+        /// The components of the SBOM, in the order of the document. Nested CycloneDX components follow their parent.
+        pub fn components(&self) -> Vec<SbomComponent> {
+            match self {
+                Self::SPDX(spdx) => spdx_components(spdx),
+                Self::CycloneDX(bom) => cyclonedx_components(bom),
+            }
+        }
+    }
+
+    fn spdx_components(spdx: &SPDX) -> Vec<SbomComponent> {
+        spdx.package_information
+            .iter()
+            .map(|package| {
+                let mut licenses = Vec::new();
+                for license in [&package.declared_license, &package.concluded_license]
+                    .into_iter()
+                    .flatten()
+                {
+                    let license = license.to_string();
+                    if !matches!(license.as_str(), "" | "NONE" | "NOASSERTION") && !licenses.contains(&license) {
+                        licenses.push(license);
+                    }
+                }
+
+                SbomComponent {
+                    reference: package.package_spdx_identifier.clone(),
+                    name: package.package_name.clone(),
+                    version: package.package_version.clone(),
+                    purl: package
+                        .external_reference
+                        .iter()
+                        .find(|r| r.reference_type == "purl")
+                        .map(|r| r.reference_locator.clone()),
+                    licenses,
+                    hashes: package
+                        .package_checksum
+                        .iter()
+                        .map(|sum| (algorithm(&sum.algorithm), sum.value.clone()))
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// The name of a checksum algorithm, as in SPDX documents
+    fn algorithm(algorithm: &Algorithm) -> String {
+        match serde_json::to_value(algorithm) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => format!("{algorithm:?}"),
+        }
+    }
+
+    fn cyclonedx_components(bom: &Bom) -> Vec<SbomComponent> {
+        walk(bom)
+            .into_iter()
+            .map(|component| SbomComponent {
+                reference: component
+                    .bom_ref
+                    .clone()
+                    .or_else(|| component.purl.as_ref().map(|p| p.to_string()))
+                    .unwrap_or_else(|| component.name.to_string()),
+                name: component.name.to_string(),
+                version: component.version.as_ref().map(|v| v.to_string()),
+                purl: component.purl.as_ref().map(|p| p.to_string()),
+                licenses: component
+                    .licenses
+                    .iter()
+                    .flat_map(|l| l.0.iter())
+                    .map(|l| match l {
+                        LicenseChoice::License(l) => match &l.license_identifier {
+                            LicenseIdentifier::SpdxId(id) => id.to_string(),
+                            LicenseIdentifier::Name(name) => name.to_string(),
+                        },
+                        LicenseChoice::Expression(expr) => expr.to_string(),
+                    })
+                    .collect(),
+                hashes: component
+                    .hashes
+                    .iter()
+                    .flat_map(|h| h.0.iter())
+                    .map(|hash| (hash.alg.to_string(), hash.content.0.clone()))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "cyclonedx-bom", feature = "spdx-rs"))]
+mod tests {
+    use crate::data::SBOM;
+
+    #[test]
+    fn test_cyclonedx() {
+        let sbom = SBOM::parse(
+            br#"{
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.4",
+                "version": 1,
+                "metadata": {
+                    "component": { "type": "application", "bom-ref": "app", "name": "app", "version": "1.0" }
+                },
+                "components": [
+                    {
+                        "type": "library", "bom-ref": "lib", "name": "lib", "version": "2.0", "purl": "pkg:generic/lib@2.0",
+                        "licenses": [ { "license": { "id": "MIT" } } ],
+                        "hashes": [ { "alg": "SHA-256", "content": "a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc" } ],
+                        "components": [ { "type": "library", "name": "nested" } ]
+                    },
+                    { "type": "library", "bom-ref": "other", "name": "other" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let components = sbom.components();
+        let references: Vec<_> = components.iter().map(|c| c.reference.as_str()).collect();
+        assert_eq!(references, vec!["app", "lib", "nested", "other"]);
+
+        let lib = &components[1];
+        assert_eq!(lib.purl.as_deref(), Some("pkg:generic/lib@2.0"));
+        assert_eq!(lib.licenses, vec!["MIT".to_string()]);
+        assert_eq!(
+            lib.hashes.get("SHA-256").map(String::as_str),
+            Some("a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc")
+        );
+        assert!(lib.matches("GENERIC/LIB"));
+        assert!(!lib.matches("app"));
+    }
+
+    #[test]
+    fn test_spdx() {
+        let sbom = SBOM::parse(include_bytes!("../../testdata/syft.spdx.json")).unwrap();
+
+        let components = sbom.components();
+        assert_eq!(components.len(), 105);
+        let compute = components
+            .iter()
+            .find(|c| c.reference == "SPDXRef-Package-go-module-cloud.google.com-go-compute-36357a787dfd18c0")
+            .unwrap();
+        // the algorithms are named as in the document
+        assert_eq!(
+            compute.hashes.get("SHA256").map(String::as_str),
+            Some("dac326b7ca6b0a7ec33da1b83e687437722799cf1c4fc69ee64d4ce9527d5aa7")
+        );
+    }
+}
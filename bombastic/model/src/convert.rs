@@ -0,0 +1,354 @@
+//! Conversion of SBOMs between the CycloneDX and SPDX formats.
+//!
+//! SBOMs are converted through their dependency [`Graph`], so the conversion only carries over what the formats have
+//! in common:
+//!
+//! * the components, with their name, version, package URL, license (as SPDX expression) and SHA-256 hash
+//! * the dependencies between the components, and the components the SBOM describes
+//! * the creation time, and the serial number of CycloneDX SBOMs, as namespace of the SPDX document
+//!
+//! Everything else is lost, notably: other hashes, CPEs and external references, suppliers and authors, files and
+//! snippets, annotations, and relationships other than dependencies and containment, which are turned into
+//! dependencies. CycloneDX license names, which aren't SPDX identifiers, are dropped, as well as SPDX concluded
+//! licenses. Converted documents are SPDX 2.3 and CycloneDX 1.3.
+
+use crate::data::SBOM;
+use crate::graph::{Component, Graph};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use time::{macros::format_description, OffsetDateTime};
+
+/// Document formats an SBOM can be converted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Format {
+    #[serde(rename = "cyclonedx-json")]
+    CycloneDxJson,
+    #[serde(rename = "spdx-json")]
+    SpdxJson,
+}
+
+/// Convert an SBOM into the requested format, using the identifier of the SBOM as fallback for its name.
+///
+/// Returns `None` if the SBOM already is in the requested format.
+pub fn convert(sbom: &SBOM, id: &str, format: Format) -> Option<Value> {
+    match (sbom, format) {
+        (SBOM::SPDX(_), Format::SpdxJson) | (SBOM::CycloneDX(_), Format::CycloneDxJson) => None,
+        (SBOM::SPDX(spdx), Format::CycloneDxJson) => {
+            let created = spdx
+                .document_creation_information
+                .creation_info
+                .created
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string();
+            Some(to_cyclonedx(&Graph::from(sbom), created))
+        }
+        (SBOM::CycloneDX(bom), Format::SpdxJson) => {
+            let graph = Graph::from(sbom);
+            let name = graph
+                .roots
+                .first()
+                .and_then(|root| graph.components.get(root))
+                .map(|component| component.name.clone())
+                .unwrap_or_else(|| id.to_string());
+            let created = bom
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.timestamp.as_ref())
+                .map(|timestamp| timestamp.to_string())
+                .unwrap_or_else(now);
+            let namespace = match &bom.serial_number {
+                Some(serial) => serial.to_string().trim_start_matches("urn:uuid:").to_string(),
+                None => id.to_string(),
+            };
+            Some(to_spdx(
+                &graph,
+                &name,
+                created,
+                format!("https://trustification.io/spdx/{namespace}"),
+            ))
+        }
+    }
+}
+
+pub(crate) fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z"))
+        .unwrap_or_default()
+}
+
+fn to_cyclonedx(graph: &Graph, created: String) -> Value {
+    let root = graph.roots.first().filter(|root| graph.components.contains_key(*root));
+
+    let mut metadata = Map::new();
+    metadata.insert("timestamp".into(), created.into());
+    if let Some(root) = root {
+        metadata.insert("component".into(), cyclonedx_component(root, &graph.components[root]));
+    }
+
+    let components: Vec<_> = graph
+        .components
+        .iter()
+        .filter(|(id, _)| Some(*id) != root)
+        .map(|(id, component)| cyclonedx_component(id, component))
+        .collect();
+
+    let dependencies: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|(from, _)| graph.components.contains_key(*from))
+        .map(|(from, to)| {
+            let to: Vec<_> = to.iter().filter(|to| graph.components.contains_key(*to)).collect();
+            json!({ "ref": from, "dependsOn": to })
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.3",
+        "version": 1,
+        "metadata": metadata,
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+pub(crate) fn cyclonedx_component(id: &str, component: &Component) -> Value {
+    let mut result = Map::new();
+    result.insert("type".into(), "library".into());
+    result.insert("bom-ref".into(), id.into());
+    result.insert("name".into(), component.name.clone().into());
+    if let Some(version) = &component.version {
+        result.insert("version".into(), version.clone().into());
+    }
+    if let Some(purl) = &component.purl {
+        result.insert("purl".into(), purl.clone().into());
+    }
+    if let Some(license) = &component.license {
+        result.insert("licenses".into(), json!([{ "expression": license }]));
+    }
+    if let Some(sha256) = &component.sha256 {
+        result.insert("hashes".into(), json!([{ "alg": "SHA-256", "content": sha256 }]));
+    }
+    result.into()
+}
+
+fn to_spdx(graph: &Graph, name: &str, created: String, namespace: String) -> Value {
+    // CycloneDX references may contain characters which are not allowed in SPDX identifiers
+    let ids: BTreeMap<&str, String> = graph
+        .components
+        .keys()
+        .enumerate()
+        .map(|(n, id)| (id.as_str(), format!("SPDXRef-{n}")))
+        .collect();
+
+    let roots: Vec<_> = graph.roots.iter().filter_map(|root| ids.get(root.as_str())).collect();
+
+    let packages: Vec<_> = graph
+        .components
+        .iter()
+        .map(|(id, component)| spdx_package(&ids[id.as_str()], component))
+        .collect();
+
+    let mut relationships: Vec<_> = roots
+        .iter()
+        .map(|root| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": root,
+            })
+        })
+        .collect();
+    for (from, to) in &graph.edges {
+        let Some(from) = ids.get(from.as_str()) else {
+            continue;
+        };
+        relationships.extend(to.iter().filter_map(|to| ids.get(to.as_str())).map(|to| {
+            json!({
+                "spdxElementId": from,
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": to,
+            })
+        }));
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": created,
+            "creators": ["Tool: trustification"],
+        },
+        "documentDescribes": roots,
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+fn spdx_package(id: &str, component: &Component) -> Value {
+    let mut result = Map::new();
+    result.insert("SPDXID".into(), id.into());
+    result.insert("name".into(), component.name.clone().into());
+    if let Some(version) = &component.version {
+        result.insert("versionInfo".into(), version.clone().into());
+    }
+    result.insert("downloadLocation".into(), "NOASSERTION".into());
+    result.insert("filesAnalyzed".into(), false.into());
+    result.insert(
+        "licenseDeclared".into(),
+        component.license.as_deref().unwrap_or("NOASSERTION").into(),
+    );
+    result.insert("licenseConcluded".into(), "NOASSERTION".into());
+    result.insert("copyrightText".into(), "NOASSERTION".into());
+    if let Some(purl) = &component.purl {
+        result.insert(
+            "externalRefs".into(),
+            json!([{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": purl,
+            }]),
+        );
+    }
+    if let Some(sha256) = &component.sha256 {
+        result.insert(
+            "checksums".into(),
+            json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]),
+        );
+    }
+    result.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn graph() -> Graph {
+        let mut graph = Graph {
+            roots: vec!["pkg:rpm/redhat/a@1".to_string()],
+            ..Default::default()
+        };
+        for (id, name) in [("pkg:rpm/redhat/a@1", "a"), ("pkg:rpm/redhat/b@2", "b")] {
+            graph.components.insert(
+                id.to_string(),
+                Component {
+                    name: name.to_string(),
+                    purl: Some(id.to_string()),
+                    license: Some("MIT".to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+        graph.add_edge("pkg:rpm/redhat/a@1", "pkg:rpm/redhat/b@2");
+        graph
+    }
+
+    #[test]
+    fn test_to_spdx() {
+        let value = to_spdx(
+            &graph(),
+            "a",
+            "2023-01-01T00:00:00Z".to_string(),
+            "https://trustification.io/spdx/a".to_string(),
+        );
+        let sbom = SBOM::parse(&serde_json::to_vec(&value).unwrap()).unwrap();
+        let SBOM::SPDX(spdx) = sbom else {
+            panic!("must be SPDX");
+        };
+        assert_eq!(spdx.package_information.len(), 2);
+        assert_eq!(spdx.document_creation_information.document_describes, vec!["SPDXRef-0"]);
+        assert_eq!(spdx.relationships.len(), 2);
+    }
+
+    #[test]
+    fn test_to_cyclonedx() {
+        let value = to_cyclonedx(&graph(), "2023-01-01T00:00:00Z".to_string());
+        let sbom = SBOM::parse(&serde_json::to_vec(&value).unwrap()).unwrap();
+        let SBOM::CycloneDX(sbom) = sbom else {
+            panic!("must be CycloneDX");
+        };
+        let bom = sbom.bom;
+        assert_eq!(bom.components.map(|c| c.0.len()), Some(1));
+        assert_eq!(bom.dependencies.map(|d| d.0.len()), Some(1));
+    }
+
+    /// A component, by what the conversion keeps of it, apart from its license and hash.
+    type Key = (String, Option<String>, Option<String>);
+
+    fn key(component: &Component) -> Key {
+        (
+            component.name.clone(),
+            component.version.clone(),
+            component.purl.clone(),
+        )
+    }
+
+    /// The roots, components and dependencies of a graph, independent of the references of the components.
+    fn summary(graph: &Graph) -> (Vec<Key>, BTreeSet<Key>, BTreeSet<(Key, Key)>) {
+        let roots = graph
+            .roots
+            .iter()
+            .filter_map(|root| graph.components.get(root))
+            .map(key)
+            .collect();
+        let components = graph.components.values().map(key).collect();
+        let edges = graph
+            .edges
+            .iter()
+            .flat_map(|(from, to)| to.iter().map(move |to| (from, to)))
+            .filter_map(|(from, to)| Some((key(graph.components.get(from)?), key(graph.components.get(to)?))))
+            .collect();
+        (roots, components, edges)
+    }
+
+    /// Convert an SBOM, parsing the converted document again.
+    fn convert_fixture(data: &[u8], format: Format) -> (Graph, SBOM) {
+        let sbom = SBOM::parse(data).unwrap();
+        let converted = convert(&sbom, "fixture", format).unwrap();
+        let converted = SBOM::parse(&serde_json::to_vec(&converted).unwrap()).unwrap();
+        (Graph::from(&sbom), converted)
+    }
+
+    #[test]
+    fn test_convert_cyclonedx_fixture() {
+        let (original, converted) = convert_fixture(include_bytes!("../../testdata/my-sbom.json"), Format::SpdxJson);
+        assert!(matches!(converted, SBOM::SPDX(_)));
+
+        let (roots, components, edges) = summary(&original);
+        assert_eq!(roots.len(), 1);
+        assert!(components.iter().all(|(_, _, purl)| purl.is_some()));
+        assert!(!edges.is_empty());
+        assert_eq!(summary(&Graph::from(&converted)), (roots, components, edges));
+    }
+
+    #[test]
+    fn test_convert_spdx_fixture() {
+        let (original, converted) =
+            convert_fixture(include_bytes!("../../testdata/ubi8-valid.json"), Format::CycloneDxJson);
+        assert!(matches!(converted, SBOM::CycloneDX(_)));
+
+        let (roots, components, edges) = summary(&original);
+        assert_eq!(roots.len(), 1);
+        assert!(components.iter().all(|(_, _, purl)| purl.is_some()));
+        assert!(!edges.is_empty());
+        assert_eq!(summary(&Graph::from(&converted)), (roots, components, edges));
+    }
+
+    #[test]
+    fn test_convert_format() {
+        assert_eq!(
+            serde_json::from_value::<Format>(json!("spdx-json")).unwrap(),
+            Format::SpdxJson
+        );
+        assert!(serde_json::from_value::<Format>(json!("spdx-tag-value")).is_err());
+
+        // SBOMs already in the requested format aren't converted
+        let sbom = SBOM::parse(include_bytes!("../../testdata/syft.spdx.json")).unwrap();
+        assert!(convert(&sbom, "syft", Format::SpdxJson).is_none());
+        assert!(convert(&sbom, "syft", Format::CycloneDxJson).is_some());
+    }
+}
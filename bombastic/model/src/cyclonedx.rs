@@ -0,0 +1,542 @@
+//! Compatibility of CycloneDX SBOMs across the versions of the specification.
+//!
+//! The CycloneDX model understands SBOMs up to version 1.4. Newer versions, like 1.5 and 1.6, are converted into the
+//! form of the model: parts which changed their form are converted back, like the tools of the metadata, and parts
+//! the model doesn't cover are kept apart, like the lifecycles of the metadata or the model cards of machine learning
+//! models, so that no data of the SBOM is lost. The components of the formulation, describing how the SBOM subject
+//! was built, are kept in the form of the model too.
+
+use cyclonedx_bom::errors::JsonReadError;
+use cyclonedx_bom::prelude::{Bom, Component};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::ops::Deref;
+
+/// The newest version of the specification understood by the model
+pub const MODEL_VERSION: &str = "1.4";
+
+/// The versions of the specification which are converted into the form of the model
+const NEWER_VERSIONS: &[&str] = &["1.5", "1.6"];
+
+const BOM: &[&str] = &[
+    "$schema",
+    "bomFormat",
+    "specVersion",
+    "serialNumber",
+    "version",
+    "metadata",
+    "components",
+    "services",
+    "externalReferences",
+    "dependencies",
+    "compositions",
+    "vulnerabilities",
+    "signature",
+];
+const METADATA: &[&str] = &[
+    "timestamp",
+    "tools",
+    "authors",
+    "component",
+    "manufacture",
+    "supplier",
+    "licenses",
+    "properties",
+];
+const TOOL: &[&str] = &["vendor", "name", "version", "hashes", "externalReferences"];
+const COMPONENT: &[&str] = &[
+    "type",
+    "mime-type",
+    "bom-ref",
+    "supplier",
+    "author",
+    "publisher",
+    "group",
+    "name",
+    "version",
+    "description",
+    "scope",
+    "hashes",
+    "licenses",
+    "copyright",
+    "cpe",
+    "purl",
+    "swid",
+    "modified",
+    "pedigree",
+    "externalReferences",
+    "properties",
+    "components",
+    "evidence",
+    "releaseNotes",
+    "signature",
+];
+const PEDIGREE: &[&str] = &["ancestors", "descendants", "variants", "commits", "patches", "notes"];
+const EVIDENCE: &[&str] = &["licenses", "copyright"];
+const SERVICE: &[&str] = &[
+    "bom-ref",
+    "provider",
+    "group",
+    "name",
+    "version",
+    "description",
+    "endpoints",
+    "authenticated",
+    "x-trust-boundary",
+    "data",
+    "licenses",
+    "externalReferences",
+    "properties",
+    "services",
+    "releaseNotes",
+    "signature",
+];
+const DATA_FLOW: &[&str] = &["flow", "classification"];
+const LICENSE: &[&str] = &["id", "name", "text", "url"];
+const ORGANIZATIONAL_ENTITY: &[&str] = &["name", "url", "contact"];
+const ORGANIZATIONAL_CONTACT: &[&str] = &["name", "email", "phone"];
+const EXTERNAL_REFERENCE: &[&str] = &["url", "comment", "type", "hashes"];
+const DEPENDENCY: &[&str] = &["ref", "dependsOn"];
+const COMPOSITION: &[&str] = &["aggregate", "assemblies", "dependencies", "signature"];
+const VULNERABILITY: &[&str] = &[
+    "bom-ref",
+    "id",
+    "source",
+    "references",
+    "ratings",
+    "cwes",
+    "description",
+    "detail",
+    "recommendation",
+    "advisories",
+    "created",
+    "published",
+    "updated",
+    "credits",
+    "tools",
+    "analysis",
+    "affects",
+    "properties",
+];
+const ANALYSIS: &[&str] = &["state", "justification", "response", "detail"];
+
+/// A CycloneDX SBOM, along with the parts of newer versions of the specification the model doesn't cover.
+///
+/// It dereferences to the SBOM as understood by the model.
+#[derive(Debug)]
+pub struct CycloneDx {
+    pub bom: Bom,
+    /// The version of the specification the SBOM was written in, like `1.5`
+    pub spec_version: String,
+    /// The components of the formulation of the SBOM, describing how its subject was built, since CycloneDX 1.5
+    pub formulation: Vec<Component>,
+    /// The parts of the SBOM the model doesn't cover, or only in another form, by their JSON pointer, like
+    /// `/components/0/cryptoProperties`
+    pub unmodeled: BTreeMap<String, Value>,
+}
+
+impl Deref for CycloneDx {
+    type Target = Bom;
+
+    fn deref(&self) -> &Bom {
+        &self.bom
+    }
+}
+
+impl CycloneDx {
+    /// Parse a CycloneDX SBOM of any supported version.
+    pub fn parse(data: &[u8]) -> Result<Self, JsonReadError> {
+        match serde_json::from_slice(data) {
+            Ok(value) => Self::from_value(value),
+            // let the model report the error
+            Err(_) => Bom::parse_from_json(data).map(Self::from),
+        }
+    }
+
+    /// Parse a CycloneDX SBOM of any supported version from its JSON value.
+    pub fn from_value(mut value: Value) -> Result<Self, JsonReadError> {
+        let spec_version = value["specVersion"].as_str().unwrap_or(MODEL_VERSION).to_string();
+        let Conversion { formulation, unmodeled } = to_model(&mut value);
+        let bom = parse_value(&value)?;
+
+        let formulation = match formulation.is_empty() {
+            true => vec![],
+            false => {
+                // the model only parses components as part of an SBOM
+                let components = serde_json::json!({
+                    "bomFormat": "CycloneDX",
+                    "specVersion": MODEL_VERSION,
+                    "version": 1,
+                    "components": formulation,
+                });
+                parse_value(&components)?
+                    .components
+                    .map(|components| components.0)
+                    .unwrap_or_default()
+            }
+        };
+
+        Ok(Self {
+            bom,
+            spec_version,
+            formulation,
+            unmodeled,
+        })
+    }
+}
+
+/// Parse an SBOM in the form of the model.
+fn parse_value(value: &Value) -> Result<Bom, JsonReadError> {
+    Bom::parse_from_json(value.to_string().as_bytes())
+}
+
+impl From<Bom> for CycloneDx {
+    fn from(bom: Bom) -> Self {
+        Self {
+            bom,
+            spec_version: MODEL_VERSION.to_string(),
+            formulation: vec![],
+            unmodeled: BTreeMap::new(),
+        }
+    }
+}
+
+/// Check if the version of a CycloneDX SBOM is converted into the form of the model.
+pub fn is_newer(value: &Value) -> bool {
+    value["specVersion"]
+        .as_str()
+        .map(|version| NEWER_VERSIONS.contains(&version))
+        .unwrap_or(false)
+}
+
+/// The parts of a CycloneDX SBOM of a newer version which are not in the form of the model.
+#[derive(Debug, Default, PartialEq)]
+pub struct Conversion {
+    /// The components of the formulation, in the form of the model
+    pub formulation: Vec<Value>,
+    /// The members the model doesn't cover, or only in another form, by their JSON pointer, like
+    /// `/metadata/lifecycles`
+    pub unmodeled: BTreeMap<String, Value>,
+}
+
+/// Convert a CycloneDX SBOM of a newer version into the form of the model, returning the parts which are not in the
+/// form of the model. SBOMs of the versions understood by the model are left as they are.
+pub fn to_model(value: &mut Value) -> Conversion {
+    let mut conversion = Conversion::default();
+    if !is_newer(value) {
+        return conversion;
+    }
+
+    for formula in value["formulation"].as_array().into_iter().flatten() {
+        for component in formula["components"].as_array().into_iter().flatten() {
+            let mut component = component.clone();
+            // the formulation is kept as a whole, the components only need to be in the form of the model
+            Conversion::default().convert_component(&mut component, "");
+            conversion.formulation.push(component);
+        }
+    }
+
+    value["specVersion"] = Value::String(MODEL_VERSION.to_string());
+    conversion.retain(value, "", BOM);
+    if let Some(metadata) = value.get_mut("metadata") {
+        conversion.convert_metadata(metadata, "/metadata");
+    }
+    conversion.for_each(value, "", "components", Conversion::convert_component);
+    conversion.for_each(value, "", "services", Conversion::convert_service);
+    conversion.for_each(value, "", "externalReferences", |conversion, reference, path| {
+        conversion.retain(reference, path, EXTERNAL_REFERENCE)
+    });
+    conversion.for_each(value, "", "dependencies", |conversion, dependency, path| {
+        conversion.retain(dependency, path, DEPENDENCY)
+    });
+    conversion.for_each(value, "", "compositions", |conversion, composition, path| {
+        conversion.retain(composition, path, COMPOSITION)
+    });
+    conversion.for_each(value, "", "vulnerabilities", Conversion::convert_vulnerability);
+
+    conversion
+}
+
+impl Conversion {
+    /// Keep only the members of an object which are known, recording the others.
+    fn retain(&mut self, value: &mut Value, path: &str, known: &[&str]) {
+        if let Some(object) = value.as_object_mut() {
+            for (key, value) in std::mem::take(object) {
+                match known.contains(&key.as_str()) {
+                    true => {
+                        object.insert(key, value);
+                    }
+                    false => {
+                        self.unmodeled.insert(pointer(path, &key), value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convert the values of an array member of an object.
+    fn for_each(&mut self, value: &mut Value, path: &str, key: &str, f: impl Fn(&mut Self, &mut Value, &str)) {
+        if let Some(values) = value.get_mut(key).and_then(Value::as_array_mut) {
+            let path = pointer(path, key);
+            for (n, value) in values.iter_mut().enumerate() {
+                f(self, value, &format!("{path}/{n}"));
+            }
+        }
+    }
+
+    fn convert_metadata(&mut self, metadata: &mut Value, path: &str) {
+        // since 1.6, the manufacturer of the subject is no longer called the manufacture
+        if metadata.get("manufacture").is_none() {
+            if let Some(manufacturer) = metadata.get("manufacturer").cloned() {
+                metadata["manufacture"] = manufacturer;
+            }
+        }
+        self.retain(metadata, path, METADATA);
+
+        // since 1.5, tools are components and services
+        if let Some(Value::Object(tools)) = metadata.get("tools") {
+            self.unmodeled.insert(pointer(path, "tools"), metadata["tools"].clone());
+            let tools = convert_tools(tools);
+            metadata["tools"] = Value::Array(tools);
+        }
+        self.for_each(metadata, path, "tools", |conversion, tool, path| {
+            conversion.retain(tool, path, TOOL)
+        });
+        if let Some(component) = metadata.get_mut("component") {
+            self.convert_component(component, &pointer(path, "component"));
+        }
+        self.for_each(metadata, path, "authors", |conversion, author, path| {
+            conversion.retain(author, path, ORGANIZATIONAL_CONTACT)
+        });
+        for key in ["manufacture", "supplier"] {
+            if let Some(entity) = metadata.get_mut(key) {
+                self.convert_entity(entity, &pointer(path, key));
+            }
+        }
+        self.convert_licenses(metadata, path);
+    }
+
+    fn convert_component(&mut self, component: &mut Value, path: &str) {
+        self.retain(component, path, COMPONENT);
+        if let Some(supplier) = component.get_mut("supplier") {
+            self.convert_entity(supplier, &pointer(path, "supplier"));
+        }
+        self.convert_licenses(component, path);
+        self.for_each(component, path, "externalReferences", |conversion, reference, path| {
+            conversion.retain(reference, path, EXTERNAL_REFERENCE)
+        });
+        self.for_each(component, path, "components", Self::convert_component);
+        if let Some(pedigree) = component.get_mut("pedigree") {
+            let path = pointer(path, "pedigree");
+            self.retain(pedigree, &path, PEDIGREE);
+            for key in ["ancestors", "descendants", "variants"] {
+                self.for_each(pedigree, &path, key, Self::convert_component);
+            }
+        }
+        if let Some(evidence) = component.get_mut("evidence") {
+            let path = pointer(path, "evidence");
+            self.retain(evidence, &path, EVIDENCE);
+            self.convert_licenses(evidence, &path);
+        }
+    }
+
+    fn convert_service(&mut self, service: &mut Value, path: &str) {
+        self.retain(service, path, SERVICE);
+        if let Some(provider) = service.get_mut("provider") {
+            self.convert_entity(provider, &pointer(path, "provider"));
+        }
+        // since 1.5, data flows are described in more detail, and may lack the classification
+        if let Some(data) = service.get_mut("data").and_then(Value::as_array_mut) {
+            let original = data.clone();
+            data.retain(|flow| flow.get("flow").is_some() && flow.get("classification").is_some());
+            for object in data.iter_mut().filter_map(Value::as_object_mut) {
+                object.retain(|key, _| DATA_FLOW.contains(&key.as_str()));
+            }
+            if *data != original {
+                self.unmodeled.insert(pointer(path, "data"), Value::Array(original));
+            }
+        }
+        self.convert_licenses(service, path);
+        self.for_each(service, path, "externalReferences", |conversion, reference, path| {
+            conversion.retain(reference, path, EXTERNAL_REFERENCE)
+        });
+        self.for_each(service, path, "services", Self::convert_service);
+    }
+
+    fn convert_vulnerability(&mut self, vulnerability: &mut Value, path: &str) {
+        self.retain(vulnerability, path, VULNERABILITY);
+        if let Some(Value::Object(tools)) = vulnerability.get("tools") {
+            self.unmodeled
+                .insert(pointer(path, "tools"), vulnerability["tools"].clone());
+            let tools = convert_tools(tools);
+            vulnerability["tools"] = Value::Array(tools);
+        }
+        self.for_each(vulnerability, path, "tools", |conversion, tool, path| {
+            conversion.retain(tool, path, TOOL)
+        });
+        if let Some(analysis) = vulnerability.get_mut("analysis") {
+            self.retain(analysis, &pointer(path, "analysis"), ANALYSIS);
+        }
+    }
+
+    fn convert_entity(&mut self, entity: &mut Value, path: &str) {
+        self.retain(entity, path, ORGANIZATIONAL_ENTITY);
+        self.for_each(entity, path, "contact", |conversion, contact, path| {
+            conversion.retain(contact, path, ORGANIZATIONAL_CONTACT)
+        });
+    }
+
+    /// Convert the licenses of an object, which are either licenses or license expressions.
+    fn convert_licenses(&mut self, value: &mut Value, path: &str) {
+        self.for_each(value, path, "licenses", |conversion, choice, path| {
+            let known: &[&str] = match choice.get_mut("license") {
+                Some(license) => {
+                    conversion.retain(license, &pointer(path, "license"), LICENSE);
+                    &["license"]
+                }
+                None => &["expression"],
+            };
+            conversion.retain(choice, path, known);
+        });
+    }
+}
+
+/// Convert tools in the form of components and services into the form of tools.
+fn convert_tools(tools: &Map<String, Value>) -> Vec<Value> {
+    ["components", "services"]
+        .into_iter()
+        .flat_map(|key| tools.get(key).and_then(Value::as_array).into_iter().flatten())
+        .map(|tool| {
+            let vendor = tool["publisher"]
+                .as_str()
+                .or_else(|| tool["group"].as_str())
+                .or_else(|| tool["provider"]["name"].as_str())
+                .or_else(|| tool["supplier"]["name"].as_str());
+            let mut result = tool.clone();
+            if let (Some(vendor), Some(object)) = (vendor, result.as_object_mut()) {
+                object.insert("vendor".to_string(), Value::String(vendor.to_string()));
+            }
+            result
+        })
+        .collect()
+}
+
+/// The JSON pointer of a member of the object at a pointer.
+fn pointer(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn sbom() -> Value {
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79",
+            "version": 1,
+            "metadata": {
+                "timestamp": "2024-01-01T00:00:00Z",
+                "lifecycles": [{"phase": "build"}],
+                "manufacturer": {"name": "Example Inc."},
+                "tools": {
+                    "components": [{"type": "application", "publisher": "anchore", "name": "syft", "version": "0.100.0"}]
+                },
+                "component": {
+                    "type": "machine-learning-model",
+                    "bom-ref": "model",
+                    "name": "sentiment",
+                    "version": "1.0.0",
+                    "purl": "pkg:huggingface/example/sentiment@1.0.0",
+                    "modelCard": {"modelParameters": {"task": "text-classification"}},
+                    "licenses": [{"license": {"id": "Apache-2.0", "acknowledgement": "declared"}}]
+                }
+            },
+            "components": [
+                {
+                    "type": "cryptographic-asset",
+                    "name": "AES-128-GCM",
+                    "cryptoProperties": {"assetType": "algorithm"}
+                },
+                {
+                    "type": "library",
+                    "name": "openssl",
+                    "version": "3.0.7",
+                    "purl": "pkg:rpm/redhat/openssl@3.0.7",
+                    "omniborId": ["gitoid:blob:sha1:261eeb9e9f8b2b4b0d119366dda99c6fd7d35c64"]
+                }
+            ],
+            "formulation": [{
+                "bom-ref": "build",
+                "components": [{"type": "application", "name": "maven", "version": "3.9.6", "purl": "pkg:maven/org.apache.maven/maven-core@3.9.6"}]
+            }],
+            "annotations": [{"subjects": ["model"], "annotator": {}, "timestamp": "2024-01-01T00:00:00Z", "text": "reviewed"}]
+        })
+    }
+
+    #[test]
+    fn test_to_model() {
+        let mut value = sbom();
+        let Conversion { formulation, unmodeled } = to_model(&mut value);
+
+        assert_eq!(value["specVersion"], MODEL_VERSION);
+        assert!(value.get("formulation").is_none());
+        assert!(value.get("annotations").is_none());
+        assert!(value["metadata"].get("lifecycles").is_none());
+        assert_eq!(
+            value["metadata"]["tools"],
+            json!([{"vendor": "anchore", "name": "syft", "version": "0.100.0"}])
+        );
+        assert!(value["metadata"]["component"].get("modelCard").is_none());
+        assert_eq!(value["metadata"]["component"]["type"], "machine-learning-model");
+        assert_eq!(
+            value["metadata"]["component"]["licenses"],
+            json!([{"license": {"id": "Apache-2.0"}}])
+        );
+        assert!(value["components"][0].get("cryptoProperties").is_none());
+        assert!(value["components"][1].get("omniborId").is_none());
+        assert_eq!(formulation.len(), 1);
+        assert_eq!(formulation[0]["name"], "maven");
+
+        // the parts which are not in the form of the model are kept
+        assert_eq!(unmodeled["/metadata/lifecycles"], json!([{"phase": "build"}]));
+        assert_eq!(unmodeled["/metadata/tools"], sbom()["metadata"]["tools"]);
+        assert_eq!(
+            unmodeled["/metadata/component/modelCard"],
+            json!({"modelParameters": {"task": "text-classification"}})
+        );
+        assert_eq!(
+            unmodeled["/metadata/component/licenses/0/license/acknowledgement"],
+            "declared"
+        );
+        assert_eq!(
+            unmodeled["/components/0/cryptoProperties"],
+            json!({"assetType": "algorithm"})
+        );
+        assert_eq!(
+            unmodeled["/components/1/omniborId"],
+            sbom()["components"][1]["omniborId"]
+        );
+        assert_eq!(unmodeled["/formulation"], sbom()["formulation"]);
+        assert_eq!(unmodeled["/annotations"], sbom()["annotations"]);
+        assert_eq!(value["metadata"]["manufacture"], json!({"name": "Example Inc."}));
+        assert_eq!(unmodeled["/metadata/manufacturer"], json!({"name": "Example Inc."}));
+
+        // versions understood by the model are left as they are
+        let mut value = json!({"bomFormat": "CycloneDX", "specVersion": "1.4", "formulation": []});
+        assert_eq!(to_model(&mut value), Conversion::default());
+        assert!(value.get("formulation").is_some());
+    }
+
+    #[test]
+    fn test_parse() {
+        let sbom = CycloneDx::parse(&serde_json::to_vec(&sbom()).unwrap()).unwrap();
+        assert_eq!(sbom.spec_version, "1.5");
+        assert_eq!(sbom.components.as_ref().map(|components| components.0.len()), Some(2));
+        assert_eq!(sbom.formulation.len(), 1);
+        assert_eq!(sbom.formulation[0].name.to_string(), "maven");
+        assert_eq!(sbom.unmodeled["/metadata/lifecycles"], json!([{"phase": "build"}]));
+    }
+}
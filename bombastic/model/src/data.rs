@@ -3,7 +3,7 @@ use tracing::{info_span, instrument};
 
 pub enum SBOM {
     #[cfg(feature = "cyclonedx-bom")]
-    CycloneDX(cyclonedx_bom::prelude::Bom),
+    CycloneDX(crate::cyclonedx::CycloneDx),
     #[cfg(feature = "spdx-rs")]
     SPDX(spdx_rs::models::SPDX),
 }
@@ -50,7 +50,7 @@ impl SBOM {
 
         #[cfg(feature = "spdx-rs")]
         {
-            let result = info_span!("parse spdx").in_scope(|| serde_json::from_slice::<spdx_rs::models::SPDX>(data));
+            let result = info_span!("parse spdx").in_scope(|| crate::spdx3::parse(data));
             match result.map_err(|e| {
                 log::info!("Error parsing SPDX: {:?}", e);
                 e
@@ -64,7 +64,7 @@ impl SBOM {
 
         #[cfg(feature = "cyclonedx-bom")]
         {
-            let result = info_span!("parse cyclonedx").in_scope(|| cyclonedx_bom::prelude::Bom::parse_from_json(data));
+            let result = info_span!("parse cyclonedx").in_scope(|| crate::cyclonedx::CycloneDx::parse(data));
             match result.map_err(|e| {
                 log::info!("Error parsing CycloneDX: {:?}", e);
                 e
@@ -79,12 +79,78 @@ impl SBOM {
         Err(err)
     }
 
+    /// Parse an SBOM from its JSON value.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, Error> {
+        let mut err: Error = Default::default();
+
+        #[cfg(feature = "spdx-rs")]
+        {
+            use serde::Deserialize;
+
+            let result = match crate::spdx3::is_spdx3(&value) {
+                true => crate::spdx3::to_model(&value)
+                    .map_err(<serde_json::Error as serde::de::Error>::custom)
+                    .and_then(serde_json::from_value),
+                false => spdx_rs::models::SPDX::deserialize(&value),
+            };
+            match result {
+                Ok(spdx) => return Ok(SBOM::SPDX(spdx)),
+                Err(e) => {
+                    log::info!("Error parsing SPDX: {:?}", e);
+                    err.spdx = Some(e);
+                }
+            }
+        }
+
+        #[cfg(feature = "cyclonedx-bom")]
+        {
+            match crate::cyclonedx::CycloneDx::from_value(value) {
+                Ok(bom) => return Ok(SBOM::CycloneDX(bom)),
+                Err(e) => {
+                    log::info!("Error parsing CycloneDX: {:?}", e);
+                    err.cyclonedx = Some(e);
+                }
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Parse an SBOM for indexing, dropping parts known not to be indexed, see [`crate::prune`]. The document is still
+    /// read as a whole.
+    pub fn parse_pruned(data: &[u8]) -> Result<Self, Error> {
+        crate::prune::parse(data)
+    }
+
+    /// The format of the SBOM, either `spdx` or `cyclonedx`
+    pub fn format(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX(_) => "spdx",
+            #[cfg(feature = "cyclonedx-bom")]
+            Self::CycloneDX(_) => "cyclonedx",
+        }
+    }
+
+    /// The version of the specification the SBOM was written in, like `2.3` or `1.5`
+    pub fn spec_version(&self) -> String {
+        match self {
+            #[cfg(feature = "spdx-rs")]
+            Self::SPDX(sbom) => {
+                let version = &sbom.document_creation_information.spdx_version;
+                version.strip_prefix("SPDX-").unwrap_or(version).to_string()
+            }
+            #[cfg(feature = "cyclonedx-bom")]
+            Self::CycloneDX(sbom) => sbom.spec_version.clone(),
+        }
+    }
+
     pub fn type_str(&self) -> String {
         match self {
             #[cfg(feature = "spdx-rs")]
             Self::SPDX(sbom) => format!("SPDX/{}", sbom.document_creation_information.spdx_version),
             #[cfg(feature = "cyclonedx-bom")]
-            Self::CycloneDX(_) => "CycloneDX/1.3".to_string(),
+            Self::CycloneDX(sbom) => format!("CycloneDX/{}", sbom.spec_version),
         }
     }
 }
@@ -0,0 +1,56 @@
+//! Canonical digests of SBOMs, to detect exact duplicates.
+//!
+//! The digest is taken from the canonical form of the JSON document, with the keys of all objects sorted and without
+//! whitespace, so documents only differing in their formatting share a digest.
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// The algorithm of the digest, prefixing its hex encoded value
+pub const ALGORITHM: &str = "sha256";
+
+/// Compute the canonical digest of a JSON document, like `sha256:<hex>`.
+pub fn canonical_digest(data: &[u8]) -> Result<String, serde_json::Error> {
+    let value: Value = serde_json::from_slice(data)?;
+    let canonical = serde_json::to_vec(&canonicalize(value))?;
+    Ok(format!("{}:{}", ALGORITHM, hex::encode(Sha256::digest(canonical))))
+}
+
+/// Sort the keys of all objects, which may keep the order they were inserted in otherwise.
+pub fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_canonical_digest() {
+        let a = canonical_digest(br#"{"name": "app", "packages": [{"version": "1", "name": "lib"}]}"#).unwrap();
+        let b = canonical_digest(br#"{"packages":[{"name":"lib","version":"1"}],"name":"app"}"#).unwrap();
+        assert_eq!(a, b);
+        assert!(a.starts_with("sha256:"));
+        assert_eq!(a.len(), "sha256:".len() + 64);
+
+        // the order of arrays is significant
+        let c = canonical_digest(br#"{"name": "app", "packages": [{"name": "lib"}, {"name": "other"}]}"#).unwrap();
+        let d = canonical_digest(br#"{"name": "app", "packages": [{"name": "other"}, {"name": "lib"}]}"#).unwrap();
+        assert_ne!(c, d);
+
+        assert!(canonical_digest(b"not json").is_err());
+    }
+}
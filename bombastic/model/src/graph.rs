@@ -0,0 +1,266 @@
+//! The flat dependency graph of an SBOM, independent of its format.
+//!
+//! The graph only carries the information shared by all formats, which is the base for converting SBOMs between
+//! formats, see [`crate::convert`].
+
+use crate::data::SBOM;
+use cyclonedx_bom::models::{
+    hash::HashAlgorithm,
+    license::{LicenseChoice, LicenseIdentifier},
+};
+use spdx_rs::models::{Algorithm, RelationshipType, SPDX};
+use std::collections::{BTreeMap, HashSet};
+
+/// A component of the graph
+#[derive(Clone, Debug, Default)]
+pub struct Component {
+    pub name: String,
+    pub version: Option<String>,
+    pub purl: Option<String>,
+    /// The license, as SPDX expression
+    pub license: Option<String>,
+    pub sha256: Option<String>,
+}
+
+/// The flat dependency graph, by the document internal component references
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub roots: Vec<String>,
+    pub components: BTreeMap<String, Component>,
+    pub edges: BTreeMap<String, Vec<String>>,
+}
+
+impl Graph {
+    pub fn add_edge(&mut self, from: &str, to: &str) {
+        let deps = self.edges.entry(from.to_string()).or_default();
+        if !deps.iter().any(|dep| dep == to) {
+            deps.push(to.to_string());
+        }
+    }
+
+    pub fn from_spdx(spdx: &SPDX) -> Self {
+        let mut graph = Self::default();
+
+        for pi in &spdx.package_information {
+            graph.components.insert(
+                pi.package_spdx_identifier.clone(),
+                Component {
+                    name: pi.package_name.clone(),
+                    version: pi.package_version.clone(),
+                    purl: pi
+                        .external_reference
+                        .iter()
+                        .find(|er| er.reference_type == "purl")
+                        .map(|er| er.reference_locator.clone()),
+                    license: pi
+                        .declared_license
+                        .as_ref()
+                        .map(|license| license.to_string())
+                        .filter(|license| !matches!(license.as_str(), "" | "NONE" | "NOASSERTION")),
+                    sha256: pi
+                        .package_checksum
+                        .iter()
+                        .find(|sum| sum.algorithm == Algorithm::SHA256)
+                        .map(|sum| sum.value.clone()),
+                },
+            );
+        }
+
+        for rel in &spdx.relationships {
+            let (from, to) = (&rel.spdx_element_id, &rel.related_spdx_element);
+            match rel.relationship_type {
+                RelationshipType::DependsOn | RelationshipType::Contains => graph.add_edge(from, to),
+                RelationshipType::DependencyOf
+                | RelationshipType::ContainedBy
+                | RelationshipType::BuildDependencyOf
+                | RelationshipType::DevDependencyOf
+                | RelationshipType::OptionalDependencyOf
+                | RelationshipType::RuntimeDependencyOf => graph.add_edge(to, from),
+                _ => {}
+            }
+        }
+
+        graph.roots = spdx_roots(spdx, &graph);
+
+        graph
+    }
+
+    pub fn from_cyclonedx(bom: &cyclonedx_bom::prelude::Bom) -> Self {
+        let mut graph = Self::default();
+
+        let mut todo: Vec<_> = Vec::new();
+        if let Some(component) = bom.metadata.as_ref().and_then(|m| m.component.as_ref()) {
+            let id = bom_ref(component);
+            graph.roots.push(id);
+            todo.push(component);
+        }
+        todo.extend(bom.components.iter().flat_map(|c| c.0.iter()));
+
+        while let Some(component) = todo.pop() {
+            let id = bom_ref(component);
+            for child in component.components.iter().flat_map(|c| c.0.iter()) {
+                graph.add_edge(&id, &bom_ref(child));
+                todo.push(child);
+            }
+            graph.components.insert(
+                id,
+                Component {
+                    name: component.name.to_string(),
+                    version: component.version.as_ref().map(|v| v.to_string()),
+                    purl: component.purl.as_ref().map(|p| p.to_string()),
+                    license: license_expression(component),
+                    sha256: component
+                        .hashes
+                        .iter()
+                        .flat_map(|h| h.0.iter())
+                        .find(|hash| hash.alg == HashAlgorithm::SHA256)
+                        .map(|hash| hash.content.0.clone()),
+                },
+            );
+        }
+
+        for dep in bom.dependencies.iter().flat_map(|d| d.0.iter()) {
+            for to in &dep.dependencies {
+                graph.add_edge(&dep.dependency_ref, to);
+            }
+        }
+
+        graph
+    }
+}
+
+impl From<&SBOM> for Graph {
+    fn from(sbom: &SBOM) -> Self {
+        match sbom {
+            SBOM::SPDX(spdx) => Self::from_spdx(spdx),
+            SBOM::CycloneDX(bom) => Self::from_cyclonedx(bom),
+        }
+    }
+}
+
+/// The packages an SPDX document describes.
+///
+/// Those are declared by `DESCRIBES` relationships of the document, or by the older `documentDescribes` field. If the
+/// document declares neither, the packages no other package depends on are taken.
+fn spdx_roots(spdx: &SPDX, graph: &Graph) -> Vec<String> {
+    let document = &spdx.document_creation_information.spdx_identifier;
+
+    let mut roots = spdx.document_creation_information.document_describes.clone();
+    for rel in &spdx.relationships {
+        let root = match rel.relationship_type {
+            RelationshipType::Describes if &rel.spdx_element_id == document => &rel.related_spdx_element,
+            RelationshipType::DescribedBy if &rel.related_spdx_element == document => &rel.spdx_element_id,
+            _ => continue,
+        };
+        if !roots.contains(root) {
+            roots.push(root.clone());
+        }
+    }
+
+    if roots.is_empty() {
+        let dependencies: HashSet<&String> = graph.edges.values().flatten().collect();
+        roots = spdx
+            .package_information
+            .iter()
+            .map(|pi| &pi.package_spdx_identifier)
+            .filter(|id| !dependencies.contains(id))
+            .cloned()
+            .collect();
+    }
+
+    roots
+}
+
+/// the reference of a component, falling back to its package URL or name
+fn bom_ref(component: &cyclonedx_bom::prelude::Component) -> String {
+    component
+        .bom_ref
+        .clone()
+        .or_else(|| component.purl.as_ref().map(|p| p.to_string()))
+        .unwrap_or_else(|| component.name.to_string())
+}
+
+/// the licenses of a component, combined into a single SPDX expression
+fn license_expression(component: &cyclonedx_bom::prelude::Component) -> Option<String> {
+    let licenses: Vec<_> = component
+        .licenses
+        .iter()
+        .flat_map(|l| l.0.iter())
+        .filter_map(|l| match l {
+            LicenseChoice::License(l) => match &l.license_identifier {
+                LicenseIdentifier::SpdxId(id) => Some(id.to_string()),
+                LicenseIdentifier::Name(_) => None,
+            },
+            LicenseChoice::Expression(expr) => Some(expr.to_string()),
+        })
+        .collect();
+
+    match licenses.len() {
+        0 => None,
+        1 => licenses.into_iter().next(),
+        _ => Some(
+            licenses
+                .iter()
+                .map(|license| format!("({license})"))
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn spdx(describes: Value, relationships: Value) -> SPDX {
+        let package = |id: &str| {
+            json!({
+                "SPDXID": id,
+                "name": id,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "copyrightText": "NOASSERTION",
+            })
+        };
+        serde_json::from_value(json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "test",
+            "documentNamespace": "https://example.com/test",
+            "creationInfo": {
+                "created": "2023-01-01T00:00:00Z",
+                "creators": ["Tool: test"],
+            },
+            "documentDescribes": describes,
+            "packages": [package("SPDXRef-a"), package("SPDXRef-b"), package("SPDXRef-c")],
+            "relationships": relationships,
+        }))
+        .unwrap()
+    }
+
+    fn rel(from: &str, kind: &str, to: &str) -> Value {
+        json!({ "spdxElementId": from, "relationshipType": kind, "relatedSpdxElement": to })
+    }
+
+    #[test]
+    fn test_spdx_roots() {
+        let depends = || vec![rel("SPDXRef-a", "DEPENDS_ON", "SPDXRef-b")];
+
+        let mut relationships = depends();
+        relationships.push(rel("SPDXRef-DOCUMENT", "DESCRIBES", "SPDXRef-a"));
+        let graph = Graph::from_spdx(&spdx(json!([]), json!(relationships)));
+        assert_eq!(graph.roots, ["SPDXRef-a"]);
+
+        let mut relationships = depends();
+        relationships.push(rel("SPDXRef-c", "DESCRIBED_BY", "SPDXRef-DOCUMENT"));
+        let graph = Graph::from_spdx(&spdx(json!(["SPDXRef-a"]), json!(relationships)));
+        assert_eq!(graph.roots, ["SPDXRef-a", "SPDXRef-c"]);
+
+        // neither DESCRIBES nor documentDescribes, fall back to the packages not being a dependency
+        let graph = Graph::from_spdx(&spdx(json!([]), json!(depends())));
+        assert_eq!(graph.roots, ["SPDXRef-a", "SPDXRef-c"]);
+    }
+}
@@ -1,9 +1,37 @@
+pub mod admin;
+pub mod components;
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+pub mod convert;
+#[cfg(feature = "cyclonedx-bom")]
+pub mod cyclonedx;
 pub mod data;
+pub mod digest;
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+pub mod graph;
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+pub mod merge;
 pub mod packages;
+pub mod protobuf;
+pub mod prune;
+pub mod quality;
 pub mod search;
+pub mod signature;
+#[cfg(feature = "spdx-rs")]
+pub mod spdx3;
+pub mod upload;
+#[cfg(feature = "spdx-rs")]
+pub mod validation;
+pub mod vex;
 
 pub mod prelude {
+    pub use crate::admin::*;
+    pub use crate::components::*;
+    #[cfg(feature = "cyclonedx-bom")]
+    pub use crate::cyclonedx::CycloneDx;
     pub use crate::data::*;
     pub use crate::packages::*;
     pub use crate::search::*;
+    pub use crate::upload::*;
+    #[cfg(feature = "spdx-rs")]
+    pub use crate::validation::{Problem, Violation};
 }
@@ -0,0 +1,181 @@
+//! Merging of SBOMs into a single aggregate CycloneDX SBOM, like the SBOMs of the containers of a product.
+//!
+//! The SBOMs are merged through their dependency [`Graph`], so the aggregate carries the same parts of the SBOMs as a
+//! conversion does, see [`crate::convert`]. Components with the same package URL are merged into a single component,
+//! components without one are kept apart for each SBOM. The aggregate describes a new component, which depends on the
+//! components described by the merged SBOMs.
+//!
+//! The provenance of the components is recorded as annotations: one annotation for each merged SBOM, carrying its
+//! identifier and having the components of the SBOM as subjects.
+
+use crate::convert::{cyclonedx_component, now};
+use crate::data::SBOM;
+use crate::graph::{Component, Graph};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The reference of the component the aggregate describes
+const AGGREGATE_REF: &str = "aggregate";
+
+/// A request to merge stored SBOMs
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct MergeRequest {
+    /// Name of the component the aggregate SBOM describes, like the product
+    pub name: String,
+    /// Version of the component the aggregate SBOM describes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Identifiers of the SBOMs to merge
+    pub ids: Vec<String>,
+}
+
+/// Merge SBOMs, by their identifiers, into an aggregate CycloneDX SBOM describing a component of a name and version.
+pub fn merge<'a>(name: &str, version: Option<&str>, sboms: impl IntoIterator<Item = (&'a str, &'a SBOM)>) -> Value {
+    let mut merged = Graph {
+        roots: vec![AGGREGATE_REF.to_string()],
+        ..Default::default()
+    };
+    merged.components.insert(
+        AGGREGATE_REF.to_string(),
+        Component {
+            name: name.to_string(),
+            version: version.map(ToString::to_string),
+            ..Default::default()
+        },
+    );
+
+    let mut provenance: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for (id, sbom) in sboms {
+        let graph = Graph::from(sbom);
+        // document internal references are unique within their SBOM only
+        let reference = |r: &str| match graph.components.get(r).and_then(|c| c.purl.as_ref()) {
+            Some(purl) => purl.clone(),
+            None => format!("{id}#{r}"),
+        };
+
+        let subjects = provenance.entry(id).or_default();
+        for (r, component) in &graph.components {
+            let r = reference(r);
+            merged.components.entry(r.clone()).or_insert_with(|| component.clone());
+            subjects.insert(r);
+        }
+        for root in &graph.roots {
+            merged.add_edge(AGGREGATE_REF, &reference(root));
+        }
+        for (from, to) in &graph.edges {
+            for to in to {
+                merged.add_edge(&reference(from), &reference(to));
+            }
+        }
+    }
+
+    to_cyclonedx(&merged, &provenance)
+}
+
+fn to_cyclonedx(graph: &Graph, provenance: &BTreeMap<&str, BTreeSet<String>>) -> Value {
+    let timestamp = now();
+
+    let mut root = cyclonedx_component(AGGREGATE_REF, &graph.components[AGGREGATE_REF]);
+    root["type"] = "application".into();
+
+    let components: Vec<_> = graph
+        .components
+        .iter()
+        .filter(|(r, _)| *r != AGGREGATE_REF)
+        .map(|(r, component)| cyclonedx_component(r, component))
+        .collect();
+
+    // dependencies of the SBOMs may refer to elements which are not components, like SPDX files
+    let dependencies: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|(from, _)| graph.components.contains_key(*from))
+        .map(|(from, to)| {
+            let to: Vec<_> = to.iter().filter(|to| graph.components.contains_key(*to)).collect();
+            json!({ "ref": from, "dependsOn": to })
+        })
+        .collect();
+
+    let annotations: Vec<_> = provenance
+        .iter()
+        .map(|(id, subjects)| {
+            json!({
+                "subjects": subjects,
+                "annotator": { "component": { "type": "application", "name": "trustification" } },
+                "timestamp": timestamp,
+                "text": format!("Merged from SBOM '{id}'"),
+            })
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "component": root,
+        },
+        "components": components,
+        "dependencies": dependencies,
+        "annotations": annotations,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cyclonedx(name: &str, dependencies: &[(&str, &str)]) -> SBOM {
+        let purl = |name: &str| format!("pkg:cargo/{name}@1.0");
+        let mut components = BTreeSet::new();
+        for (from, to) in dependencies {
+            components.insert(*from);
+            components.insert(*to);
+        }
+        // the described component has no package URL
+        components.remove(name);
+        let value = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "version": 1,
+            "metadata": {
+                "component": { "type": "application", "name": name, "bom-ref": name }
+            },
+            "components": components
+                .iter()
+                .map(|c| json!({ "type": "library", "name": c, "version": "1.0", "bom-ref": c, "purl": purl(c) }))
+                .collect::<Vec<_>>(),
+            "dependencies": dependencies
+                .iter()
+                .map(|(from, to)| json!({ "ref": from, "dependsOn": [to] }))
+                .collect::<Vec<_>>(),
+        });
+        SBOM::parse(&serde_json::to_vec(&value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = cyclonedx("a", &[("a", "serde"), ("serde", "serde_derive")]);
+        let b = cyclonedx("b", &[("b", "serde")]);
+
+        let value = merge("product", Some("1"), [("sbom-a", &a), ("sbom-b", &b)]);
+        assert_eq!(value["metadata"]["component"]["name"], json!("product"));
+
+        let SBOM::CycloneDX(merged) = SBOM::parse(&serde_json::to_vec(&value).unwrap()).unwrap() else {
+            panic!("must be CycloneDX");
+        };
+        let graph = Graph::from_cyclonedx(&merged);
+        assert_eq!(graph.roots, vec![AGGREGATE_REF]);
+        // a and b have no package URL, the dependencies are shared
+        assert_eq!(graph.components.len(), 5);
+        assert_eq!(graph.edges[AGGREGATE_REF], vec!["sbom-a#a", "sbom-b#b"]);
+        assert_eq!(graph.edges["sbom-b#b"], vec!["pkg:cargo/serde@1.0"]);
+        assert_eq!(graph.edges["pkg:cargo/serde@1.0"], vec!["pkg:cargo/serde_derive@1.0"]);
+
+        let annotations = value["annotations"].as_array().unwrap();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[1]["text"], json!("Merged from SBOM 'sbom-b'"));
+        assert_eq!(annotations[1]["subjects"], json!(["pkg:cargo/serde@1.0", "sbom-b#b"]));
+    }
+}
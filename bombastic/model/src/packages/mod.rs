@@ -31,7 +31,7 @@ pub enum PackageInfo<'a> {
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchPackageDocument {
     /// Package name
     pub name: String,
@@ -58,7 +58,7 @@ pub struct SearchPackageDocument {
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchPackageHit {
     /// The document that was matched.
     pub document: SearchPackageDocument,
@@ -0,0 +1,492 @@
+//! Support for CycloneDX SBOMs encoded as protobuf.
+//!
+//! Some build tooling emits the protobuf encoding of CycloneDX for its size. Those SBOMs are converted into the JSON
+//! encoding, which the rest of the system understands. The types are generated from the protobuf schema of CycloneDX
+//! 1.5, which earlier versions are compatible with. The conversion walks the descriptors of the schema, so that every
+//! field is kept, mapping the names of fields and enum values to those of the JSON schema.
+
+use once_cell::sync::Lazy;
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, ReflectMessage, Value as ProtoValue,
+};
+use serde_json::{Map, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// The types of the protobuf schema of CycloneDX 1.5
+#[allow(clippy::all)]
+pub mod cyclonedx {
+    include!(concat!(env!("OUT_DIR"), "/cyclonedx.v1_5.rs"));
+}
+
+/// The content type of CycloneDX SBOMs encoded as protobuf
+pub const CONTENT_TYPE: &str = "application/x.vnd.cyclonedx+protobuf";
+
+static DESCRIPTOR_POOL: Lazy<DescriptorPool> = Lazy::new(|| {
+    DescriptorPool::decode(include_bytes!(concat!(env!("OUT_DIR"), "/file_descriptor_set.bin")).as_ref())
+        .expect("invalid descriptors of the CycloneDX protobuf schema")
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid CycloneDX protobuf: {}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Convert a CycloneDX SBOM encoded as protobuf into its JSON encoding.
+pub fn to_json(data: &[u8]) -> Result<Value, Error> {
+    let bom = cyclonedx::Bom::decode(data).map_err(|e| Error(e.to_string()))?;
+    let mut json = message(&bom.transcode_to_dynamic())?;
+    if let Value::Object(bom) = &mut json {
+        bom.insert("bomFormat".into(), "CycloneDX".into());
+    }
+    Ok(json)
+}
+
+const HASH_ALGORITHMS: &[&str] = &[
+    "",
+    "MD5",
+    "SHA-1",
+    "SHA-256",
+    "SHA-384",
+    "SHA-512",
+    "SHA3-256",
+    "SHA3-384",
+    "SHA3-512",
+    "BLAKE2b-256",
+    "BLAKE2b-384",
+    "BLAKE2b-512",
+    "BLAKE3",
+];
+
+const SCORE_METHODS: &[&str] = &["", "CVSSv2", "CVSSv3", "CVSSv31", "OWASP", "other", "CVSSv4", "SSVC"];
+
+/// Enums of which the JSON values keep the underscores of the protobuf values, instead of using hyphens
+const UNDERSCORED: &[&str] = &[
+    "cyclonedx.v1_5.Aggregate",
+    "cyclonedx.v1_5.ImpactAnalysisJustification",
+    "cyclonedx.v1_5.ImpactAnalysisState",
+    "cyclonedx.v1_5.VulnerabilityResponse",
+];
+
+/// Choices which are the value of their message in the JSON encoding, instead of a field of it
+const INLINE: &[(&str, &str)] = &[
+    ("cyclonedx.v1_5.ModelCard.ModelParameters.Datasets", "dataset"),
+    ("cyclonedx.v1_5.EnvironmentVars", "property"),
+    ("cyclonedx.v1_5.EnvironmentVars", "value"),
+];
+
+/// Values of fields which are required in the JSON encoding, but may be left unset in protobuf
+const DEFAULTS: &[(&str, &str, &str)] = &[
+    ("cyclonedx.v1_5.Bom", "specVersion", "1.5"),
+    // the classification is unset for components of unknown type, which CycloneDX 1.3 knows no type for
+    ("cyclonedx.v1_5.Component", "type", "library"),
+];
+
+/// Convert a message into its JSON encoding.
+fn message(message: &DynamicMessage) -> Result<Value, Error> {
+    let descriptor = message.descriptor();
+    let name = descriptor.full_name();
+    if name == "google.protobuf.Timestamp" {
+        return timestamp(message);
+    }
+
+    let mut result = Map::new();
+    for field in descriptor.fields() {
+        let value = message.get_field(&field);
+        let present = match &*value {
+            ProtoValue::List(values) => !values.is_empty(),
+            // fields without presence are the required fields of the JSON encoding
+            _ => !field.supports_presence() || message.has_field(&field),
+        };
+        if !present {
+            continue;
+        }
+        let Some(value) = convert_field(name, &field, &value)? else {
+            continue;
+        };
+        if INLINE.contains(&(name, field.name())) {
+            return Ok(value);
+        }
+        result.insert(json_name(name, &field).into(), value);
+    }
+    for (message, field, value) in DEFAULTS {
+        if *message == name && result.get(*field).map_or(true, |current| current == "") {
+            result.insert((*field).into(), (*value).into());
+        }
+    }
+    Ok(result.into())
+}
+
+/// The name of a field in the JSON encoding, which mostly is the camel case of its name.
+fn json_name<'a>(message: &str, field: &'a FieldDescriptor) -> &'a str {
+    match (message, field.name()) {
+        (_, "bom_ref") => "bom-ref",
+        (_, "mime_type") => "mime-type",
+        (_, "x_trust_boundary") => "x-trust-boundary",
+        ("cyclonedx.v1_5.AttachedText" | "cyclonedx.v1_5.Hash", "value") => "content",
+        ("cyclonedx.v1_5.DataClassification", "value") => "classification",
+        ("cyclonedx.v1_5.Dependency", "dependencies") => "dependsOn",
+        _ => field.json_name(),
+    }
+}
+
+fn convert_field(message: &str, field: &FieldDescriptor, value: &ProtoValue) -> Result<Option<Value>, Error> {
+    match (message, field.name()) {
+        ("cyclonedx.v1_5.Dependency", "dependencies") => Ok(Some(references(value))),
+        ("cyclonedx.v1_5.Metadata" | "cyclonedx.v1_5.Vulnerability", "tools") => tools(value).map(Some),
+        _ => convert(&field.kind(), value),
+    }
+}
+
+fn convert(kind: &Kind, value: &ProtoValue) -> Result<Option<Value>, Error> {
+    Ok(match value {
+        ProtoValue::Bool(value) => Some((*value).into()),
+        ProtoValue::I32(value) => Some((*value).into()),
+        ProtoValue::I64(value) => Some((*value).into()),
+        ProtoValue::U32(value) => Some((*value).into()),
+        ProtoValue::U64(value) => Some((*value).into()),
+        // the shortest decimal of the value, like 0.8 instead of 0.800000011920929
+        ProtoValue::F32(value) => number(value.to_string().parse().unwrap_or_default()),
+        ProtoValue::F64(value) => number(*value),
+        ProtoValue::String(value) => Some(value.as_str().into()),
+        ProtoValue::EnumNumber(number) => match kind {
+            Kind::Enum(descriptor) => enum_name(descriptor, *number).map(Into::into),
+            _ => None,
+        },
+        ProtoValue::Message(value) => Some(message(value)?),
+        ProtoValue::List(values) => {
+            let mut result = Vec::with_capacity(values.len());
+            for value in values {
+                result.extend(convert(kind, value)?);
+            }
+            Some(result.into())
+        }
+        // the schema has none of those
+        ProtoValue::Bytes(_) | ProtoValue::Map(_) => return Err(Error("unsupported field type".into())),
+    })
+}
+
+fn number(value: f64) -> Option<Value> {
+    serde_json::Number::from_f64(value).map(Value::Number)
+}
+
+/// The value of an enum in the JSON encoding, which mostly is the lower case of its name without its prefix.
+fn enum_name(descriptor: &EnumDescriptor, number: i32) -> Option<String> {
+    // values unknown to this version of the schema are dropped
+    let value = descriptor.get_value(number)?;
+    let table = match descriptor.full_name() {
+        "cyclonedx.v1_5.HashAlg" => Some(HASH_ALGORITHMS),
+        "cyclonedx.v1_5.ScoreMethod" => Some(SCORE_METHODS),
+        _ => None,
+    };
+    if let Some(table) = table {
+        return table
+            .get(number as usize)
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string);
+    }
+
+    let name = value.name()[prefix(descriptor)..].to_lowercase();
+    Some(match descriptor.full_name() {
+        _ if name == "null" || name == "unspecified" => return None,
+        "cyclonedx.v1_5.VulnerabilityAffectedStatus" if name == "not_affected" => "unaffected".into(),
+        full_name if UNDERSCORED.contains(&full_name) => name,
+        _ => name.replace('_', "-"),
+    })
+}
+
+/// The length of the prefix which all values of an enum share, like `CLASSIFICATION_`.
+fn prefix(descriptor: &EnumDescriptor) -> usize {
+    let mut values = descriptor.values();
+    let Some(first) = values.next() else {
+        return 0;
+    };
+    let first = first.name().to_string();
+    let common = values.fold(first.len(), |len, value| {
+        first
+            .bytes()
+            .zip(value.name().bytes())
+            .take(len)
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+    first[..common].rfind('_').map_or(0, |index| index + 1)
+}
+
+/// The references of nested dependencies, as dependencies don't nest in the JSON encoding.
+fn references(value: &ProtoValue) -> Value {
+    value
+        .as_list()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(ProtoValue::as_message)
+        .filter_map(|dependency| {
+            dependency
+                .get_field_by_name("ref")
+                .and_then(|reference| reference.as_str().map(Value::from))
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Convert tools, which are a list of tools in the JSON encoding before CycloneDX 1.5, and the components and services
+/// used as tools since. Tools of the list are kept as components if both are set.
+fn tools(value: &ProtoValue) -> Result<Value, Error> {
+    let mut tools = Vec::new();
+    for tool in value.as_list().unwrap_or_default() {
+        tools.extend(tool.as_message().map(message).transpose()?);
+    }
+    if !tools
+        .iter()
+        .any(|tool| tool.get("components").is_some() || tool.get("services").is_some())
+    {
+        return Ok(tools.into());
+    }
+
+    let (mut components, mut services) = (Vec::new(), Vec::new());
+    for mut tool in tools {
+        let Some(tool) = tool.as_object_mut() else {
+            continue;
+        };
+        if let Some(Value::Array(values)) = tool.remove("components") {
+            components.extend(values);
+        }
+        if let Some(Value::Array(values)) = tool.remove("services") {
+            services.extend(values);
+        }
+        if let Some(name) = tool.remove("name") {
+            let mut component = Map::new();
+            component.insert("type".into(), "application".into());
+            component.extend(tool.remove("vendor").map(|vendor| ("publisher".into(), vendor)));
+            component.insert("name".into(), name);
+            for field in ["version", "hashes", "externalReferences"] {
+                component.extend(tool.remove(field).map(|value| (field.into(), value)));
+            }
+            components.push(component.into());
+        }
+    }
+    let mut result = Map::new();
+    if !components.is_empty() {
+        result.insert("components".into(), components.into());
+    }
+    if !services.is_empty() {
+        result.insert("services".into(), services.into());
+    }
+    Ok(result.into())
+}
+
+fn timestamp(message: &DynamicMessage) -> Result<Value, Error> {
+    let timestamp: prost_types::Timestamp = message.transcode_to().map_err(|e| Error(e.to_string()))?;
+    let nanos = i128::from(timestamp.seconds) * 1_000_000_000 + i128::from(timestamp.nanos);
+    let timestamp = OffsetDateTime::from_unix_timestamp_nanos(nanos)
+        .map_err(|_| Error(format!("timestamp out of range: {}", timestamp.seconds)))?;
+    timestamp
+        .format(&Rfc3339)
+        .map(Into::into)
+        .map_err(|e| Error(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::cyclonedx::*;
+    use super::*;
+    use serde_json::json;
+
+    const SERDE: &str = "pkg:cargo/serde@1.0.188";
+
+    #[test]
+    fn test_to_json() {
+        let bom = Bom {
+            spec_version: "1.5".into(),
+            version: Some(1),
+            serial_number: Some("urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79".into()),
+            metadata: Some(Metadata {
+                timestamp: Some(prost_types::Timestamp {
+                    seconds: 1685620800,
+                    nanos: 0,
+                }),
+                tools: vec![Tool {
+                    components: vec![Component {
+                        r#type: Classification::Application.into(),
+                        name: "syft".into(),
+                        version: Some("0.98.0".into()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                component: Some(Component {
+                    r#type: Classification::Application.into(),
+                    bom_ref: Some("app".into()),
+                    name: "app".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            components: vec![Component {
+                r#type: Classification::Library.into(),
+                bom_ref: Some(SERDE.into()),
+                name: "serde".into(),
+                version: Some("1.0.188".into()),
+                hashes: vec![Hash {
+                    alg: HashAlg::Sha256.into(),
+                    value: "abcd".into(),
+                }],
+                licenses: vec![LicenseChoice {
+                    choice: Some(license_choice::Choice::Expression("Apache-2.0 OR MIT".into())),
+                }],
+                purl: Some(SERDE.into()),
+                pedigree: Some(Pedigree {
+                    notes: Some("patched".into()),
+                    ..Default::default()
+                }),
+                evidence: Some(Evidence {
+                    occurrences: vec![EvidenceOccurrences {
+                        bom_ref: None,
+                        location: "Cargo.lock".into(),
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            services: vec![Service {
+                name: "api".into(),
+                endpoints: vec!["https://example.com/api".into()],
+                x_trust_boundary: Some(true),
+                ..Default::default()
+            }],
+            dependencies: vec![Dependency {
+                r#ref: "app".into(),
+                dependencies: vec![Dependency {
+                    r#ref: SERDE.into(),
+                    dependencies: vec![],
+                }],
+            }],
+            compositions: vec![Composition {
+                aggregate: Aggregate::IncompleteFirstPartyOnly.into(),
+                assemblies: vec!["app".into()],
+                ..Default::default()
+            }],
+            vulnerabilities: vec![Vulnerability {
+                id: Some("CVE-2023-1234".into()),
+                ratings: vec![VulnerabilityRating {
+                    score: Some(7.5),
+                    severity: Some(Severity::High.into()),
+                    method: Some(ScoreMethod::Cvssv31.into()),
+                    ..Default::default()
+                }],
+                cwes: vec![79],
+                analysis: Some(VulnerabilityAnalysis {
+                    state: Some(ImpactAnalysisState::NotAffected.into()),
+                    justification: Some(ImpactAnalysisJustification::CodeNotReachable.into()),
+                    ..Default::default()
+                }),
+                affects: vec![VulnerabilityAffects {
+                    r#ref: SERDE.into(),
+                    versions: vec![VulnerabilityAffectedVersions {
+                        choice: Some(vulnerability_affected_versions::Choice::Version("1.0.188".into())),
+                        status: Some(VulnerabilityAffectedStatus::NotAffected.into()),
+                    }],
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_json(&bom.encode_to_vec()).unwrap(),
+            json!({
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "version": 1,
+                "serialNumber": "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79",
+                "metadata": {
+                    "timestamp": "2023-06-01T12:00:00Z",
+                    "tools": {
+                        "components": [{ "type": "application", "name": "syft", "version": "0.98.0" }]
+                    },
+                    "component": { "type": "application", "bom-ref": "app", "name": "app" }
+                },
+                "components": [{
+                    "type": "library",
+                    "bom-ref": SERDE,
+                    "name": "serde",
+                    "version": "1.0.188",
+                    "hashes": [{ "alg": "SHA-256", "content": "abcd" }],
+                    "licenses": [{ "expression": "Apache-2.0 OR MIT" }],
+                    "purl": SERDE,
+                    "pedigree": { "notes": "patched" },
+                    "evidence": { "occurrences": [{ "location": "Cargo.lock" }] }
+                }],
+                "services": [{
+                    "name": "api",
+                    "endpoints": ["https://example.com/api"],
+                    "x-trust-boundary": true
+                }],
+                "dependencies": [{ "ref": "app", "dependsOn": [SERDE] }],
+                "compositions": [{ "aggregate": "incomplete_first_party_only", "assemblies": ["app"] }],
+                "vulnerabilities": [{
+                    "id": "CVE-2023-1234",
+                    "ratings": [{ "score": 7.5, "severity": "high", "method": "CVSSv31" }],
+                    "cwes": [79],
+                    "analysis": { "state": "not_affected", "justification": "code_not_reachable" },
+                    "affects": [{ "ref": SERDE, "versions": [{ "version": "1.0.188", "status": "unaffected" }] }]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let bom = Bom {
+            metadata: Some(Metadata {
+                tools: vec![Tool {
+                    vendor: Some("anchore".into()),
+                    name: Some("syft".into()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            components: vec![Component {
+                name: "unknown".into(),
+                external_references: vec![ExternalReference {
+                    url: "https://example.com".into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_json(&bom.encode_to_vec()).unwrap(),
+            json!({
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "metadata": {
+                    "tools": [{ "vendor": "anchore", "name": "syft" }]
+                },
+                "components": [{
+                    "type": "library",
+                    "name": "unknown",
+                    "externalReferences": [{ "type": "other", "url": "https://example.com" }]
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        // truncated length delimited field
+        assert!(to_json(&[0x0a, 0x05, b'1']).is_err());
+        // a string in place of the metadata
+        assert!(to_json(b"\x22\x08metadata").is_err());
+        // JSON isn't protobuf
+        assert!(to_json(br#"{"bomFormat":"CycloneDX"}"#).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Pruning of SBOMs for indexing, dropping the parts known not to be indexed before parsing the rest into the model.
+//!
+//! Parsing an SBOM into the model takes several times the memory of the document itself, most of it spent on parts
+//! the index doesn't look at, like the files of container images. So the parts listed in `PRUNED` are skipped while
+//! deserializing the document, without materializing them, and only the rest is converted into the model.
+//!
+//! This is no streaming parser: the document itself is still read into memory as a whole, only the memory of the
+//! model is reduced. Parts not listed are kept, whether they are indexed or not.
+
+use crate::data::{Error, SBOM};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+use std::collections::HashSet;
+use std::fmt;
+use tracing::instrument;
+
+/// Keys of the parts of SPDX and CycloneDX documents which are not indexed, at any level of the document
+const PRUNED: &[&str] = &[
+    // SPDX
+    "files",
+    "snippets",
+    "hasFiles",
+    "hasExtractedLicensingInfos",
+    "annotations",
+    // CycloneDX
+    "evidence",
+    "signature",
+    "compositions",
+    "releaseNotes",
+];
+
+/// Parse an SBOM, dropping the parts listed in `PRUNED`.
+///
+/// Relationships of SPDX documents to the dropped files are dropped as well, only relationships between the
+/// document and its packages are kept.
+#[instrument(skip_all, fields(data_len={data.len()}), err)]
+pub fn parse(data: &[u8]) -> Result<SBOM, Error> {
+    let mut deserializer = serde_json::Deserializer::from_slice(data);
+    let value = Pruned(PRUNED)
+        .deserialize(&mut deserializer)
+        .and_then(|value| deserializer.end().map(|_| value));
+
+    match value {
+        Ok(value) => SBOM::from_value(prune_relationships(value)),
+        // let the models report the error
+        Err(_) => SBOM::parse(data),
+    }
+}
+
+/// Drop relationships of SPDX documents which don't relate packages, or the document itself.
+fn prune_relationships(mut value: Value) -> Value {
+    let Some(packages) = value["packages"].as_array() else {
+        return value;
+    };
+    let mut ids: HashSet<String> = packages
+        .iter()
+        .filter_map(|package| package["SPDXID"].as_str())
+        .map(ToString::to_string)
+        .collect();
+    ids.extend(value["SPDXID"].as_str().map(ToString::to_string));
+
+    // references to external documents, or to no element at all, are kept
+    let kept = |element: &Value| {
+        element
+            .as_str()
+            .map(|id| ids.contains(id) || !id.starts_with("SPDXRef-"))
+            .unwrap_or(true)
+    };
+    if let Some(relationships) = value.get_mut("relationships").and_then(Value::as_array_mut) {
+        relationships.retain(|rel| kept(&rel["spdxElementId"]) && kept(&rel["relatedSpdxElement"]));
+    }
+    value
+}
+
+/// A JSON value, skipping the values of some keys while deserializing.
+#[derive(Clone, Copy)]
+struct Pruned<'a>(&'a [&'a str]);
+
+impl<'de> DeserializeSeed<'de> for Pruned<'_> {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Pruned<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(v).map(Value::Number).unwrap_or(Value::Null))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element_seed(self)? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if self.0.contains(&key.as_str()) {
+                map.next_value::<IgnoredAny>()?;
+            } else {
+                values.insert(key, map.next_value_seed(self)?);
+            }
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+#[cfg(all(test, feature = "spdx-rs", feature = "cyclonedx-bom"))]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_prune_spdx() {
+        let data = json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": "image",
+            "documentNamespace": "https://example.com/image",
+            "creationInfo": {
+                "created": "2023-05-04T12:00:00Z",
+                "creators": ["Tool: test"]
+            },
+            "documentDescribes": ["SPDXRef-image"],
+            "packages": [{
+                "SPDXID": "SPDXRef-image",
+                "name": "image",
+                "downloadLocation": "NOASSERTION",
+                "hasFiles": ["SPDXRef-file"],
+                "annotations": [{
+                    "annotationDate": "2023-05-04T12:00:00Z",
+                    "annotationType": "OTHER",
+                    "annotator": "Tool: test",
+                    "comment": "annotated"
+                }]
+            }],
+            "files": [{
+                "SPDXID": "SPDXRef-file",
+                "fileName": "/usr/bin/true",
+                "checksums": []
+            }],
+            "relationships": [
+                {
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": "SPDXRef-image"
+                },
+                {
+                    "spdxElementId": "SPDXRef-image",
+                    "relationshipType": "CONTAINS",
+                    "relatedSpdxElement": "SPDXRef-file"
+                }
+            ]
+        });
+
+        let data = serde_json::to_vec(&data).unwrap();
+        let SBOM::SPDX(spdx) = parse(&data).unwrap() else {
+            panic!("not parsed as SPDX");
+        };
+        assert_eq!(spdx.package_information.len(), 1);
+        assert!(spdx.package_information[0].files.is_empty());
+        assert!(spdx.file_information.is_empty());
+        assert_eq!(spdx.relationships.len(), 1);
+        assert_eq!(spdx.relationships[0].related_spdx_element, "SPDXRef-image");
+    }
+
+    #[test]
+    fn test_prune_cyclonedx() {
+        let data = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "version": 1,
+            "components": [{
+                "type": "library",
+                "name": "foo",
+                "version": "1.0",
+                "purl": "pkg:cargo/foo@1.0",
+                "evidence": {
+                    "copyright": [{ "text": "Copyright foo" }]
+                }
+            }]
+        });
+
+        let data = serde_json::to_vec(&data).unwrap();
+        let SBOM::CycloneDX(bom) = parse(&data).unwrap() else {
+            panic!("not parsed as CycloneDX");
+        };
+        let components = &bom.components.as_ref().unwrap().0;
+        assert_eq!(components.len(), 1);
+        assert!(components[0].evidence.is_none());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse(b"{ \"packages\": [").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Scoring the quality of SBOMs, by the minimum elements of an SBOM as defined by the
+//! [NTIA](https://www.ntia.gov/files/ntia/publications/sbom_minimum_elements_report.pdf).
+//!
+//! Every element is scored from 0 to 1, as the share of the components which provide the element. The quality score
+//! is the average of all elements, from 0 to 100.
+
+use serde::{Deserialize, Serialize};
+
+/// The quality of an SBOM, by its minimum elements
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Quality {
+    /// Share of the components with a supplier
+    pub supplier: f64,
+    /// Share of the components with a version
+    pub version: f64,
+    /// Share of the components with a unique identifier, like a package URL or CPE
+    pub unique_id: f64,
+    /// Whether the SBOM has a timestamp of its creation
+    pub timestamp: f64,
+    /// Share of the components with their dependency relationships
+    pub dependencies: f64,
+}
+
+impl Quality {
+    /// The quality score, from 0 to 100
+    pub fn score(&self) -> f64 {
+        let elements = [
+            self.supplier,
+            self.version,
+            self.unique_id,
+            self.timestamp,
+            self.dependencies,
+        ];
+        (elements.iter().sum::<f64>() / elements.len() as f64 * 100.0).round()
+    }
+}
+
+#[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+mod scoring {
+    use super::Quality;
+    use crate::{components::walk, data::SBOM, graph::Graph};
+    use cyclonedx_bom::prelude::Bom;
+    use spdx_rs::models::SPDX;
+    use std::collections::BTreeSet;
+
+    impl Quality {
+        fn from_spdx(spdx: &SPDX) -> Self {
+            let packages = &spdx.package_information;
+            Self {
+                supplier: share(packages, |p| p.package_supplier.as_deref().is_some_and(is_present)),
+                version: share(packages, |p| p.package_version.as_deref().is_some_and(is_present)),
+                unique_id: share(packages, |p| {
+                    p.external_reference.iter().any(|r| {
+                        matches!(r.reference_type.as_str(), "purl" | "cpe22Type" | "cpe23Type" | "swid")
+                            && is_present(&r.reference_locator)
+                    })
+                }),
+                // the creation time is required by the specification
+                timestamp: 1.0,
+                dependencies: dependencies(&Graph::from_spdx(spdx)),
+            }
+        }
+
+        fn from_cyclonedx(bom: &Bom) -> Self {
+            let components = walk(bom);
+
+            Self {
+                supplier: share(&components, |c| {
+                    c.supplier
+                        .as_ref()
+                        .and_then(|supplier| supplier.name.as_ref())
+                        .is_some_and(|name| is_present(&name.to_string()))
+                        || c.publisher
+                            .as_ref()
+                            .is_some_and(|publisher| is_present(&publisher.to_string()))
+                }),
+                version: share(&components, |c| {
+                    c.version.as_ref().is_some_and(|v| is_present(&v.to_string()))
+                }),
+                unique_id: share(&components, |c| c.purl.is_some() || c.cpe.is_some() || c.swid.is_some()),
+                timestamp: match bom.metadata.as_ref().and_then(|metadata| metadata.timestamp.as_ref()) {
+                    Some(_) => 1.0,
+                    None => 0.0,
+                },
+                dependencies: dependencies(&Graph::from_cyclonedx(bom)),
+            }
+        }
+    }
+
+    impl From<&SBOM> for Quality {
+        fn from(sbom: &SBOM) -> Self {
+            match sbom {
+                SBOM::SPDX(spdx) => Self::from_spdx(spdx),
+                SBOM::CycloneDX(bom) => Self::from_cyclonedx(bom),
+            }
+        }
+    }
+
+    /// the share of the items matching a predicate, all elements of an empty SBOM are missing
+    fn share<T>(items: &[T], f: impl Fn(&T) -> bool) -> f64 {
+        match items.len() {
+            0 => 0.0,
+            len => items.iter().filter(|item| f(item)).count() as f64 / len as f64,
+        }
+    }
+
+    fn is_present(value: &str) -> bool {
+        !matches!(value.trim(), "" | "NONE" | "NOASSERTION")
+    }
+
+    /// the share of the components taking part in a dependency relationship, a single component has none to declare
+    fn dependencies(graph: &Graph) -> f64 {
+        if graph.components.len() == 1 {
+            return 1.0;
+        }
+        let related: BTreeSet<&str> = graph
+            .edges
+            .iter()
+            .flat_map(|(from, to)| std::iter::once(from).chain(to.iter()))
+            .map(String::as_str)
+            .collect();
+        let components: Vec<_> = graph.components.keys().collect();
+        share(&components, |id| related.contains(id.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let quality = Quality {
+            supplier: 1.0,
+            version: 1.0,
+            unique_id: 0.5,
+            timestamp: 1.0,
+            dependencies: 0.0,
+        };
+        assert_eq!(quality.score(), 70.0);
+        assert_eq!(Quality::default().score(), 0.0);
+    }
+
+    #[cfg(all(feature = "cyclonedx-bom", feature = "spdx-rs"))]
+    #[test]
+    fn test_cyclonedx() {
+        let sbom = crate::data::SBOM::parse(
+            br#"{
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.4",
+                "version": 1,
+                "metadata": {
+                    "timestamp": "2023-03-30T12:00:00Z",
+                    "component": { "type": "application", "bom-ref": "app", "name": "app", "version": "1.0", "purl": "pkg:generic/app@1.0", "supplier": { "name": "Example Inc." } }
+                },
+                "components": [
+                    { "type": "library", "bom-ref": "lib", "name": "lib", "version": "2.0", "purl": "pkg:generic/lib@2.0" },
+                    { "type": "library", "bom-ref": "other", "name": "other" }
+                ],
+                "dependencies": [
+                    { "ref": "app", "dependsOn": ["lib"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let quality = Quality::from(&sbom);
+        assert_eq!(quality.supplier, 1.0 / 3.0);
+        assert_eq!(quality.version, 2.0 / 3.0);
+        assert_eq!(quality.unique_id, 2.0 / 3.0);
+        assert_eq!(quality.timestamp, 1.0);
+        assert_eq!(quality.dependencies, 2.0 / 3.0);
+        assert_eq!(quality.score(), 67.0);
+    }
+}
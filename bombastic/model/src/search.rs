@@ -1,7 +1,11 @@
+use crate::quality::Quality;
 use serde_json::Value;
 use sikula::prelude::*;
+use std::collections::BTreeMap;
+use trustification_api::search::FacetCount;
 
-#[derive(Clone, Debug, PartialEq, Search)]
+#[derive(Clone, Debug, PartialEq, Search, strum::VariantNames)]
+#[strum(serialize_all = "camelCase")]
 pub enum Packages<'a> {
     /// Search by SBOM id (the storage ID)
     #[search(default)]
@@ -42,6 +46,54 @@ pub enum Packages<'a> {
     Qualifier(Qualified<'a, &'a str>),
     #[search(scope)]
     Dependency(Primary<'a>),
+    /// Search by the SHA256 or SHA1 digest of any package contained in the SBOM.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// artifact:a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc
+    /// artifact:"sha256:a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc"
+    /// ```
+    Artifact(&'a str),
+    /// Search by labels assigned to the SBOM, either by key and value, or by key only.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// label:"team=platform"
+    /// label:env
+    /// ```
+    Label(&'a str),
+    /// Search by the version of the specification the SBOM was written in, like `1.5` for CycloneDX or `2.3` for SPDX.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// specVersion:1.5
+    /// ```
+    SpecVersion(&'a str),
+    /// Search by the package URL of the components of the formulation of an SBOM, describing how its subject was
+    /// built.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// formulation:"pkg:maven/org.apache.maven/maven-core@3.9.6"
+    /// ```
+    #[search(scope)]
+    Formulation(Primary<'a>),
+    /// Search for SBOMs uploaded with a signature, `is:signed`, or with a verified signature, `is:verified`.
+    Signed,
+    Verified,
+    /// Search by the quality score of the SBOM, from 0 to 100, by the minimum elements of an SBOM.
+    ///
+    /// Example queries:
+    ///
+    /// ```ignore
+    /// qualityScore:>=80
+    /// ```
+    #[search(sort)]
+    QualityScore(PartialOrdered<f64>),
     Application,
     Library,
     Framework,
@@ -50,10 +102,15 @@ pub enum Packages<'a> {
     Device,
     Firmware,
     File,
+    MachineLearningModel,
+    Data,
+    CryptographicAsset,
+    Platform,
+    DeviceDriver,
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchDocument {
     /// SBOM (storage) identifier
     pub id: String,
@@ -77,6 +134,9 @@ pub struct SearchDocument {
     pub supplier: String,
     /// SBOM classifier
     pub classifier: String,
+    /// The version of the specification the SBOM was written in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<String>,
     /// SBOM description
     pub description: String,
     /// Snippet highlighting part of description that matched
@@ -86,10 +146,25 @@ pub struct SearchDocument {
     pub created: time::OffsetDateTime,
     /// Number of dependencies with package names that matched
     pub dependencies: u64,
+    /// Labels assigned to the SBOM
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Whether the SBOM was uploaded with a signature
+    #[serde(default)]
+    pub signed: bool,
+    /// Whether the signature of the SBOM was verified
+    #[serde(default)]
+    pub verified: bool,
+    /// The quality score of the SBOM, from 0 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<f64>,
+    /// The scores of the minimum elements of the SBOM, making up the quality score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchHit {
     /// The document that was matched.
     pub document: SearchDocument,
@@ -110,4 +185,25 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Bucket counts of the requested facets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub facets: BTreeMap<String, Vec<FacetCount>>,
+    /// Cursor for retrieving the next page, if results were requested using a cursor and more are available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// The SBOMs matching a query, which were deleted, unless it was a dry run.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct DeleteResult {
+    /// Identifiers of the SBOMs matching the query
+    pub ids: Vec<String>,
+    /// Whether the SBOMs were only matched, but not deleted
+    pub dry_run: bool,
+    /// Identifiers of the SBOMs deleted
+    #[serde(default)]
+    pub deleted: Vec<String>,
+    /// Errors deleting SBOMs, by the identifier of the SBOM
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed: BTreeMap<String, String>,
 }
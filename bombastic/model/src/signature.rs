@@ -0,0 +1,76 @@
+//! The result of verifying the signature of an SBOM, which is kept as labels of the SBOM.
+//!
+//! Labels with the [`LABEL_PREFIX`] are reserved, they can only be assigned by verifying a signature.
+
+use std::collections::BTreeMap;
+
+/// The prefix of all labels of the signature
+pub const LABEL_PREFIX: &str = "signature.";
+/// Whether the SBOM was uploaded with a signature
+pub const SIGNED_LABEL: &str = "signature.signed";
+/// Whether the signature was verified
+pub const VERIFIED_LABEL: &str = "signature.verified";
+/// The identity of the signer, the subject of the signing certificate
+pub const IDENTITY_LABEL: &str = "signature.identity";
+
+/// Check if a label is reserved for the result of the verification.
+pub fn is_reserved_label(key: &str) -> bool {
+    key.starts_with(LABEL_PREFIX)
+}
+
+/// The result of verifying the signature of an SBOM.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Verification {
+    pub signed: bool,
+    pub verified: bool,
+    pub identity: Option<String>,
+}
+
+impl Verification {
+    /// Replace the labels of the result of a former verification with the labels of this one.
+    pub fn apply(&self, labels: &mut BTreeMap<String, String>) {
+        labels.retain(|key, _| !is_reserved_label(key));
+        if self.signed {
+            labels.insert(SIGNED_LABEL.to_string(), true.to_string());
+            labels.insert(VERIFIED_LABEL.to_string(), self.verified.to_string());
+            if let Some(identity) = &self.identity {
+                labels.insert(IDENTITY_LABEL.to_string(), identity.clone());
+            }
+        }
+    }
+
+    /// Get the result of the verification from the labels of an SBOM.
+    pub fn from_labels(labels: &BTreeMap<String, String>) -> Self {
+        let flag = |key| labels.get(key).map(|value| value == "true").unwrap_or_default();
+        Self {
+            signed: flag(SIGNED_LABEL),
+            verified: flag(VERIFIED_LABEL),
+            identity: labels.get(IDENTITY_LABEL).cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let mut labels = BTreeMap::from([
+            ("team".to_string(), "platform".to_string()),
+            (IDENTITY_LABEL.to_string(), "old@example.com".to_string()),
+        ]);
+        let verification = Verification {
+            signed: true,
+            verified: true,
+            identity: None,
+        };
+        verification.apply(&mut labels);
+        assert_eq!(labels.len(), 3);
+        assert_eq!(Verification::from_labels(&labels), verification);
+
+        Verification::default().apply(&mut labels);
+        assert_eq!(labels.keys().collect::<Vec<_>>(), vec!["team"]);
+        assert_eq!(Verification::from_labels(&labels), Verification::default());
+    }
+}
@@ -0,0 +1,441 @@
+//! Support for SPDX 3.0 documents, serialized as JSON-LD.
+//!
+//! The SPDX model understands documents of version 2.x. Documents of version 3.0 are a graph of elements instead,
+//! related by relationships. So they are converted into the form of version 2.3, covering the creation information,
+//! the packages, and the relationships between them, and keeping the version they were written in. Elements which
+//! have no counterpart in the model, like files, snippets or AI and dataset profiles, are dropped.
+
+use serde_json::{json, Map, Value};
+use spdx_rs::models::SPDX;
+use std::collections::HashMap;
+
+/// The identifier of the document, in the form of the model
+const DOCUMENT_ID: &str = "SPDXRef-DOCUMENT";
+
+/// The data license of all SPDX documents
+const DATA_LICENSE: &str = "CC0-1.0";
+
+/// Relationship types of SPDX 3.0, along with the type of the model describing the same relationship
+const RELATIONSHIPS: &[(&str, &str)] = &[
+    ("describes", "DESCRIBES"),
+    ("contains", "CONTAINS"),
+    ("dependsOn", "DEPENDS_ON"),
+    ("hasDynamicLink", "DYNAMIC_LINK"),
+    ("hasStaticLink", "STATIC_LINK"),
+    ("ancestorOf", "ANCESTOR_OF"),
+    ("descendantOf", "DESCENDANT_OF"),
+    ("variantOf", "VARIANT_OF"),
+    ("generates", "GENERATES"),
+    ("hasDistributionArtifact", "DISTRIBUTION_ARTIFACT"),
+    ("hasPrerequisite", "HAS_PREREQUISITE"),
+    ("other", "OTHER"),
+];
+
+/// Relationship types of SPDX 3.0, along with the type of the model describing the relationship the other way round
+const INVERSE_RELATIONSHIPS: &[(&str, &str)] = &[
+    ("hasOptionalComponent", "OPTIONAL_COMPONENT_OF"),
+    ("hasOptionalDependency", "OPTIONAL_DEPENDENCY_OF"),
+    ("hasProvidedDependency", "PROVIDED_DEPENDENCY_OF"),
+    ("hasTest", "TEST_OF"),
+    ("hasDocumentation", "DOCUMENTATION_OF"),
+    ("hasExample", "EXAMPLE_OF"),
+    ("hasDataFile", "DATA_FILE_OF"),
+    ("hasDependencyManifest", "DEPENDENCY_MANIFEST_OF"),
+    ("patchedBy", "PATCH_FOR"),
+];
+
+/// Hash algorithms of SPDX 3.0, along with the algorithm of the model
+const ALGORITHMS: &[(&str, &str)] = &[
+    ("md5", "MD5"),
+    ("sha1", "SHA1"),
+    ("sha224", "SHA224"),
+    ("sha256", "SHA256"),
+    ("sha384", "SHA384"),
+    ("sha512", "SHA512"),
+    ("sha3_256", "SHA3-256"),
+    ("sha3_384", "SHA3-384"),
+    ("sha3_512", "SHA3-512"),
+    ("blake2b256", "BLAKE2b-256"),
+    ("blake2b384", "BLAKE2b-384"),
+    ("blake2b512", "BLAKE2b-512"),
+    ("blake3", "BLAKE3"),
+];
+
+/// Parse an SPDX document of version 2.x or 3.0 into the model.
+pub fn parse(data: &[u8]) -> Result<SPDX, serde_json::Error> {
+    let err = match serde_json::from_slice::<SPDX>(data) {
+        Ok(spdx) => return Ok(spdx),
+        Err(err) => err,
+    };
+
+    match serde_json::from_slice::<Value>(data) {
+        Ok(value) if is_spdx3(&value) => {
+            let value = to_model(&value).map_err(<serde_json::Error as serde::de::Error>::custom)?;
+            serde_json::from_value(value)
+        }
+        // report the error of the model
+        _ => Err(err),
+    }
+}
+
+/// Check if a document is an SPDX 3.0 document, by its JSON-LD context.
+pub fn is_spdx3(value: &Value) -> bool {
+    let is_context = |context: &Value| {
+        context
+            .as_str()
+            .map(|context| context.contains("spdx.org/rdf/3."))
+            .unwrap_or(false)
+    };
+    match &value["@context"] {
+        Value::Array(contexts) => contexts.iter().any(is_context),
+        context => is_context(context),
+    }
+}
+
+/// The elements of an SPDX 3.0 document
+struct Graph<'a> {
+    elements: Vec<&'a Value>,
+    by_id: HashMap<&'a str, &'a Value>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(value: &'a Value) -> Self {
+        let elements: Vec<_> = value["@graph"].as_array().into_iter().flatten().collect();
+        let by_id = elements
+            .iter()
+            .filter_map(|element| id(element).map(|id| (id, *element)))
+            .collect();
+        Self { elements, by_id }
+    }
+
+    fn of_type<'g>(&'g self, types: &'g [&'g str]) -> impl Iterator<Item = &'a Value> + 'g {
+        self.elements
+            .iter()
+            .copied()
+            .filter(move |element| ty(element).map(|ty| types.contains(&ty)).unwrap_or(false))
+    }
+
+    /// Resolve a reference to an element, or the element itself if it's inlined.
+    fn resolve(&self, value: &'a Value) -> Option<&'a Value> {
+        match value {
+            Value::String(id) => self.by_id.get(id.as_str()).copied(),
+            Value::Object(_) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn id(element: &Value) -> Option<&str> {
+    element
+        .get("spdxId")
+        .or_else(|| element.get("@id"))
+        .and_then(Value::as_str)
+}
+
+fn ty(element: &Value) -> Option<&str> {
+    element
+        .get("type")
+        .or_else(|| element.get("@type"))
+        .and_then(Value::as_str)
+}
+
+fn ids(value: &Value) -> impl Iterator<Item = &str> {
+    let values = match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .filter_map(|value| value.as_str().or_else(|| id(value)))
+}
+
+const PACKAGE: &[&str] = &["software_Package", "Package"];
+const DOCUMENT: &[&str] = &["SpdxDocument"];
+const SBOM: &[&str] = &["software_Sbom", "Sbom"];
+
+/// Convert an SPDX 3.0 document into the form of the model.
+pub fn to_model(value: &Value) -> Result<Value, String> {
+    let graph = Graph::new(value);
+    let document = graph.of_type(DOCUMENT).next();
+    let sboms: Vec<_> = graph.of_type(SBOM).collect();
+
+    let creation_info = document
+        .or_else(|| sboms.first().copied())
+        .and_then(|element| element.get("creationInfo"))
+        .and_then(|info| graph.resolve(info))
+        .or_else(|| graph.of_type(&["CreationInfo"]).next())
+        .ok_or("The document has no creation information")?;
+    let spec_version = creation_info["specVersion"].as_str().unwrap_or("3.0");
+    let created = creation_info["created"]
+        .as_str()
+        .ok_or("The creation information has no creation time")?;
+    let creators: Vec<_> = ids(&creation_info["createdBy"])
+        .chain(ids(&creation_info["createdUsing"]))
+        .filter_map(|id| graph.by_id.get(id).copied())
+        .filter_map(agent_name)
+        .collect();
+
+    let packages: HashMap<&str, &Value> = graph
+        .of_type(PACKAGE)
+        .filter_map(|package| id(package).map(|id| (id, package)))
+        .collect();
+
+    // the document, and the SBOMs it contains, are both the document of the model
+    let documents: Vec<&str> = document
+        .into_iter()
+        .chain(sboms.iter().copied())
+        .filter_map(id)
+        .collect();
+    let to_model_id = |id: &str| match documents.contains(&id) {
+        true => Some(DOCUMENT_ID.to_string()),
+        false => packages.contains_key(id).then(|| id.to_string()),
+    };
+
+    let mut relationships = vec![];
+    let mut licenses = HashMap::<(&str, &str), String>::new();
+    for relationship in graph.of_type(&["Relationship"]) {
+        let (Some(kind), Some(from)) = (
+            relationship["relationshipType"].as_str(),
+            relationship["from"].as_str().or_else(|| id(&relationship["from"])),
+        ) else {
+            continue;
+        };
+
+        if matches!(kind, "hasDeclaredLicense" | "hasConcludedLicense") {
+            let expression = ids(&relationship["to"])
+                .filter_map(|id| graph.by_id.get(id).copied())
+                .find_map(license_expression);
+            if let Some(expression) = expression {
+                licenses.insert((from, kind), expression);
+            }
+            continue;
+        }
+
+        for to in ids(&relationship["to"]) {
+            let converted = if let Some((_, model_kind)) = RELATIONSHIPS.iter().find(|(name, _)| *name == kind) {
+                (to_model_id(from), *model_kind, to_model_id(to))
+            } else if let Some((_, model_kind)) = INVERSE_RELATIONSHIPS.iter().find(|(name, _)| *name == kind) {
+                (to_model_id(to), *model_kind, to_model_id(from))
+            } else {
+                continue;
+            };
+            if let (Some(from), model_kind, Some(to)) = converted {
+                relationships.push(json!({
+                    "spdxElementId": from,
+                    "relationshipType": model_kind,
+                    "relatedSpdxElement": to,
+                }));
+            }
+        }
+    }
+
+    // the subjects are the root elements of the SBOMs, or of the document if there are no SBOMs
+    let mut describes: Vec<String> = match sboms.is_empty() {
+        true => document.into_iter().collect::<Vec<_>>(),
+        false => sboms.clone(),
+    }
+    .into_iter()
+    .flat_map(|element| ids(&element["rootElement"]))
+    .filter(|id| packages.contains_key(id))
+    .map(ToString::to_string)
+    .collect();
+    for relationship in &relationships {
+        if relationship["spdxElementId"] == DOCUMENT_ID && relationship["relationshipType"] == "DESCRIBES" {
+            if let Some(id) = relationship["relatedSpdxElement"].as_str() {
+                if !describes.iter().any(|described| described == id) {
+                    describes.push(id.to_string());
+                }
+            }
+        }
+    }
+
+    let mut packages: Vec<(&str, &Value)> = packages.into_iter().collect();
+    packages.sort_by_key(|(id, _)| *id);
+    let packages: Vec<Value> = packages
+        .into_iter()
+        .map(|(id, package)| to_model_package(&graph, id, package, &licenses))
+        .collect();
+
+    let document = document.or_else(|| sboms.first().copied());
+    Ok(json!({
+        "spdxVersion": format!("SPDX-{spec_version}"),
+        "dataLicense": DATA_LICENSE,
+        "SPDXID": DOCUMENT_ID,
+        "name": document.and_then(|document| document["name"].as_str()).unwrap_or_default(),
+        "documentNamespace": document.and_then(id).unwrap_or_default(),
+        "creationInfo": {
+            "created": created,
+            "creators": creators,
+        },
+        "documentDescribes": describes,
+        "packages": packages,
+        "relationships": relationships,
+    }))
+}
+
+fn to_model_package(graph: &Graph, id: &str, package: &Value, licenses: &HashMap<(&str, &str), String>) -> Value {
+    let mut result = Map::new();
+    let mut set = |key: &str, value: Option<&str>| {
+        if let Some(value) = value {
+            result.insert(key.to_string(), Value::String(value.to_string()));
+        }
+    };
+
+    set("SPDXID", Some(id));
+    set("name", Some(package["name"].as_str().unwrap_or_default()));
+    set("versionInfo", package["software_packageVersion"].as_str());
+    set(
+        "downloadLocation",
+        Some(package["software_downloadLocation"].as_str().unwrap_or("NOASSERTION")),
+    );
+    set("homepage", package["software_homePage"].as_str());
+    set("description", package["description"].as_str());
+    set("summary", package["summary"].as_str());
+    set("copyrightText", package["software_copyrightText"].as_str());
+    let supplier = package
+        .get("suppliedBy")
+        .and_then(|supplier| graph.resolve(supplier))
+        .and_then(agent_name);
+    set("supplier", supplier.as_deref());
+    set(
+        "licenseDeclared",
+        licenses.get(&(id, "hasDeclaredLicense")).map(String::as_str),
+    );
+    set(
+        "licenseConcluded",
+        licenses.get(&(id, "hasConcludedLicense")).map(String::as_str),
+    );
+    result.insert("filesAnalyzed".to_string(), Value::Bool(false));
+
+    let checksums: Vec<_> = package["verifiedUsing"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|hash| {
+            let algorithm = hash["algorithm"].as_str()?;
+            let (_, algorithm) = ALGORITHMS.iter().find(|(name, _)| *name == algorithm)?;
+            Some(json!({"algorithm": algorithm, "checksumValue": hash["hashValue"].as_str()?}))
+        })
+        .collect();
+    result.insert("checksums".to_string(), Value::Array(checksums));
+
+    let mut references = vec![];
+    let mut purl = package["software_packageUrl"].as_str();
+    for identifier in package["externalIdentifier"].as_array().into_iter().flatten() {
+        let Some(value) = identifier["identifier"].as_str() else {
+            continue;
+        };
+        match identifier["externalIdentifierType"].as_str() {
+            Some("cpe22") => references.push(json!({
+                "referenceCategory": "SECURITY",
+                "referenceType": "cpe22Type",
+                "referenceLocator": value,
+            })),
+            Some("cpe23") => references.push(json!({
+                "referenceCategory": "SECURITY",
+                "referenceType": "cpe23Type",
+                "referenceLocator": value,
+            })),
+            Some("packageUrl") => {
+                purl = purl.or(Some(value));
+            }
+            _ => {}
+        }
+    }
+    if let Some(purl) = purl {
+        references.push(json!({
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": purl,
+        }));
+    }
+    result.insert("externalRefs".to_string(), Value::Array(references));
+
+    Value::Object(result)
+}
+
+/// The name of an agent, in the form of the creators and suppliers of the model, like `Organization: Red Hat`.
+fn agent_name(agent: &Value) -> Option<String> {
+    let name = agent["name"].as_str()?;
+    let kind = match ty(agent) {
+        Some("Person") => "Person",
+        Some("Organization") => "Organization",
+        _ => "Tool",
+    };
+    Some(format!("{kind}: {name}"))
+}
+
+/// The license expression of a license element, or `NOASSERTION` if it isn't valid for the model.
+fn license_expression(license: &Value) -> Option<String> {
+    let expression = license["simplelicensing_licenseExpression"]
+        .as_str()
+        .or_else(|| license["licenseExpression"].as_str())
+        .or_else(|| match ty(license) {
+            Some("expandedlicensing_ListedLicense") => license["name"].as_str(),
+            _ => None,
+        })?;
+    match spdx_expression::SpdxExpression::parse(expression) {
+        Ok(_) => Some(expression.to_string()),
+        Err(err) => {
+            log::info!("Replacing invalid SPDX license expression with NOASSERTION: {err}");
+            Some("NOASSERTION".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let data = std::fs::read("../testdata/spdx-3.0.json").unwrap();
+        let spdx = parse(&data).unwrap();
+
+        let info = &spdx.document_creation_information;
+        assert_eq!(info.spdx_version, "SPDX-3.0.1");
+        assert_eq!(info.document_name, "example-app");
+        assert_eq!(
+            info.spdx_document_namespace,
+            "https://example.com/spdx/example-app/document"
+        );
+        assert_eq!(
+            info.document_describes,
+            vec!["https://example.com/spdx/example-app/app"]
+        );
+        assert_eq!(
+            info.creation_info.creators,
+            vec!["Organization: Example Inc.", "Tool: example-sbom-generator"]
+        );
+
+        assert_eq!(spdx.package_information.len(), 2);
+        let app = &spdx.package_information[0];
+        assert_eq!(app.package_name, "example-app");
+        assert_eq!(app.package_version.as_deref(), Some("1.2.3"));
+        assert_eq!(app.package_supplier.as_deref(), Some("Organization: Example Inc."));
+        assert_eq!(app.package_checksum.len(), 1);
+        assert_eq!(
+            app.declared_license.as_ref().map(ToString::to_string).as_deref(),
+            Some("Apache-2.0")
+        );
+        let references: Vec<_> = app
+            .external_reference
+            .iter()
+            .map(|reference| reference.reference_type.as_str())
+            .collect();
+        assert_eq!(references, vec!["cpe23Type", "purl"]);
+
+        assert_eq!(spdx.relationships.len(), 2);
+    }
+
+    #[test]
+    fn test_is_spdx3() {
+        assert!(is_spdx3(
+            &json!({"@context": "https://spdx.org/rdf/3.0.1/spdx-context.jsonld"})
+        ));
+        assert!(is_spdx3(
+            &json!({"@context": ["https://spdx.org/rdf/3.0.0/spdx-context.jsonld", {"ex": "https://example.com/"}]})
+        ));
+        assert!(!is_spdx3(&json!({"spdxVersion": "SPDX-2.3"})));
+    }
+}
@@ -0,0 +1,23 @@
+//! Resumable uploads of SBOMs, transferred in parts.
+
+/// An upload of an SBOM in progress, which is stored once all parts were transferred and the upload got completed.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct UploadSession {
+    /// Identifier of the upload, to transfer the parts with
+    pub upload: String,
+    /// Identifier assigned to the uploaded SBOM
+    pub id: String,
+    /// Parts transferred so far, ordered by their number
+    pub parts: Vec<UploadPart>,
+    /// Numbers of the parts missing in between the parts transferred so far
+    pub missing: Vec<u32>,
+}
+
+/// A part of an upload, which was transferred.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct UploadPart {
+    /// Number of the part, starting with 1
+    pub number: u32,
+    /// Size of the part in bytes
+    pub size: usize,
+}
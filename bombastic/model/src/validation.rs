@@ -0,0 +1,612 @@
+//! Validation of SBOMs, reporting every violation of the specification found in a document.
+//!
+//! The models stop at the first error they run into, which is hard to act on for the producer of the document. So
+//! the document is walked as JSON first, checking the parts of the specification the models rely on, and only
+//! deserialized into the model if no violations were found.
+
+use crate::data::SBOM;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// The type of the problem of an SBOM failing the validation
+pub const INVALID_SBOM: &str = "https://trustification.dev/problems/invalid-sbom";
+
+const CYCLONEDX_VERSIONS: &[&str] = &["1.3", "1.4", "1.5", "1.6"];
+
+const CYCLONEDX_COMPONENT_TYPES: &[&str] = &[
+    "application",
+    "framework",
+    "library",
+    "container",
+    "operating-system",
+    "device",
+    "firmware",
+    "file",
+    // since 1.5
+    "platform",
+    "device-driver",
+    "machine-learning-model",
+    "data",
+    // since 1.6
+    "cryptographic-asset",
+];
+
+const CYCLONEDX_HASH_ALGORITHMS: &[&str] = &[
+    "MD5",
+    "SHA-1",
+    "SHA-256",
+    "SHA-384",
+    "SHA-512",
+    "SHA3-256",
+    "SHA3-384",
+    "SHA3-512",
+    "BLAKE2b-256",
+    "BLAKE2b-384",
+    "BLAKE2b-512",
+    "BLAKE3",
+];
+
+/// A violation of the specification of an SBOM.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Violation {
+    /// The location of the violation in the document, as a JSON pointer, like `/components/3/name`
+    pub path: String,
+    /// The description of the violation
+    pub message: String,
+    /// A reference to the part of the specification which is violated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec: Option<String>,
+}
+
+impl Violation {
+    pub fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            spec: None,
+        }
+    }
+
+    /// Set the reference to the part of the specification which is violated.
+    pub fn spec(mut self, spec: impl Into<String>) -> Self {
+        self.spec = Some(spec.into());
+        self
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// The details of a problem, as of [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807), along with the violations
+/// which caused it.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Problem {
+    /// A URI identifying the type of the problem
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// A short summary of the type of the problem
+    pub title: String,
+    /// The HTTP status code of the response
+    pub status: u16,
+    /// An explanation of this occurrence of the problem
+    pub detail: String,
+    /// The violations found in the document
+    pub violations: Vec<Violation>,
+}
+
+impl Problem {
+    pub fn invalid_sbom(violations: Vec<Violation>) -> Self {
+        Self {
+            kind: INVALID_SBOM.to_string(),
+            title: "Invalid SBOM".to_string(),
+            status: 400,
+            detail: format!("The SBOM violates its specification in {} places", violations.len()),
+            violations,
+        }
+    }
+}
+
+/// Validate an SBOM, returning all violations found, which are none if it's valid.
+pub fn validate(data: &[u8]) -> Vec<Violation> {
+    let value: Value = match serde_json::from_slice(data) {
+        Ok(value) => value,
+        Err(err) => return vec![Violation::new("", format!("Malformed JSON: {err}"))],
+    };
+
+    let mut validator = Validator::default();
+    if value.get("bomFormat").is_some() {
+        validator.cyclonedx(&value);
+    } else if value.get("spdxVersion").is_some() {
+        validator.spdx(&value);
+    } else if crate::spdx3::is_spdx3(&value) {
+        validator.spdx3(&value);
+    } else {
+        validator.violation(
+            "",
+            "Unknown document format, expected a CycloneDX or SPDX JSON document",
+            None,
+        );
+    }
+
+    // the validation doesn't cover the whole specification, so let the model report what's left
+    if validator.violations.is_empty() {
+        if let Err(err) = SBOM::parse(data) {
+            validator.violation("", err.to_string(), None);
+        }
+    }
+
+    validator.violations
+}
+
+#[derive(Default)]
+struct Validator {
+    violations: Vec<Violation>,
+    /// the base URL of the references to the specification
+    spec: String,
+}
+
+impl Validator {
+    fn violation(&mut self, path: &str, message: impl Into<String>, anchor: Option<&str>) {
+        let violation = Violation::new(path, message);
+        self.violations.push(match anchor {
+            Some(anchor) => violation.spec(format!("{}{anchor}", self.spec)),
+            None => violation,
+        });
+    }
+
+    /// Check that a member is a string, if it's required or present, returning it.
+    fn string<'v>(&mut self, value: &'v Value, path: &str, key: &str, required: bool, anchor: &str) -> Option<&'v str> {
+        match value.get(key) {
+            Some(Value::String(s)) => Some(s),
+            None | Some(Value::Null) if !required => None,
+            None => {
+                self.violation(path, format!("The required property '{key}' is missing"), Some(anchor));
+                None
+            }
+            Some(_) => {
+                self.violation(
+                    &format!("{path}/{key}"),
+                    format!("The property '{key}' must be a string"),
+                    Some(anchor),
+                );
+                None
+            }
+        }
+    }
+
+    /// Get the items of an array member, if present, reporting a violation if it isn't an array.
+    fn array<'v>(&mut self, value: &'v Value, path: &str, key: &str, anchor: &str) -> &'v [Value] {
+        match value.get(key) {
+            Some(Value::Array(items)) => items,
+            None | Some(Value::Null) => &[],
+            Some(_) => {
+                self.violation(
+                    &format!("{path}/{key}"),
+                    format!("The property '{key}' must be an array"),
+                    Some(anchor),
+                );
+                &[]
+            }
+        }
+    }
+
+    fn cyclonedx(&mut self, value: &Value) {
+        let version = value["specVersion"].as_str().unwrap_or("1.4");
+        let version = match CYCLONEDX_VERSIONS.contains(&version) {
+            true => version,
+            false => "1.4",
+        };
+        self.spec = format!("https://cyclonedx.org/docs/{version}/json/#");
+
+        if value["bomFormat"] != "CycloneDX" {
+            self.violation("/bomFormat", "The BOM format must be 'CycloneDX'", Some("bomFormat"));
+        }
+        if let Some(version) = self.string(value, "", "specVersion", true, "specVersion") {
+            if !CYCLONEDX_VERSIONS.contains(&version) {
+                self.violation(
+                    "/specVersion",
+                    format!(
+                        "Unsupported specification version '{version}', expected one of {}",
+                        CYCLONEDX_VERSIONS.join(", ")
+                    ),
+                    Some("specVersion"),
+                );
+            }
+        }
+        if let Some(serial) = self.string(value, "", "serialNumber", false, "serialNumber") {
+            if !serial.strip_prefix("urn:uuid:").map(is_uuid).unwrap_or(false) {
+                self.violation(
+                    "/serialNumber",
+                    format!("Invalid serial number '{serial}', expected a UUID URN (urn:uuid:<uuid>)"),
+                    Some("serialNumber"),
+                );
+            }
+        }
+        match value.get("version") {
+            None => {}
+            Some(version) if version.as_u64().map(|version| version >= 1).unwrap_or(false) => {}
+            Some(_) => self.violation(
+                "/version",
+                "The version must be an integer of at least 1",
+                Some("version"),
+            ),
+        }
+
+        let mut refs = HashSet::new();
+        if let Some(component) = value["metadata"].get("component") {
+            self.cyclonedx_component(component, "/metadata/component", "metadata_component", &mut refs);
+        }
+        self.cyclonedx_components(value, "", &mut refs);
+
+        for (n, service) in self.array(value, "", "services", "services").iter().enumerate() {
+            self.string(service, &format!("/services/{n}"), "name", true, "services_items_name");
+        }
+        for (n, dependency) in self.array(value, "", "dependencies", "dependencies").iter().enumerate() {
+            let path = format!("/dependencies/{n}");
+            self.string(dependency, &path, "ref", true, "dependencies_items_ref");
+            for (m, depends_on) in self
+                .array(dependency, &path, "dependsOn", "dependencies_items_dependsOn")
+                .iter()
+                .enumerate()
+            {
+                if !depends_on.is_string() {
+                    self.violation(
+                        &format!("{path}/dependsOn/{m}"),
+                        "The reference of a dependency must be a string",
+                        Some("dependencies_items_dependsOn"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn cyclonedx_components<'v>(&mut self, value: &'v Value, path: &str, refs: &mut HashSet<&'v str>) {
+        for (n, component) in self.array(value, path, "components", "components").iter().enumerate() {
+            self.cyclonedx_component(component, &format!("{path}/components/{n}"), "components", refs);
+        }
+    }
+
+    fn cyclonedx_component<'v>(&mut self, component: &'v Value, path: &str, anchor: &str, refs: &mut HashSet<&'v str>) {
+        if !component.is_object() {
+            self.violation(path, "A component must be an object", Some(anchor));
+            return;
+        }
+
+        if let Some(kind) = self.string(component, path, "type", true, &format!("{anchor}_items_type")) {
+            if !CYCLONEDX_COMPONENT_TYPES.contains(&kind) {
+                self.violation(
+                    &format!("{path}/type"),
+                    format!("Unknown component type '{kind}'"),
+                    Some(&format!("{anchor}_items_type")),
+                );
+            }
+        }
+        self.string(component, path, "name", true, &format!("{anchor}_items_name"));
+        if let Some(bom_ref) = self.string(component, path, "bom-ref", false, &format!("{anchor}_items_bom-ref")) {
+            if !refs.insert(bom_ref) {
+                self.violation(
+                    &format!("{path}/bom-ref"),
+                    format!("The BOM reference '{bom_ref}' is not unique"),
+                    Some(&format!("{anchor}_items_bom-ref")),
+                );
+            }
+        }
+        if let Some(purl) = self.string(component, path, "purl", false, &format!("{anchor}_items_purl")) {
+            if !purl.starts_with("pkg:") {
+                self.violation(
+                    &format!("{path}/purl"),
+                    format!("Invalid package URL '{purl}', expected it to start with 'pkg:'"),
+                    Some(&format!("{anchor}_items_purl")),
+                );
+            }
+        }
+
+        let hashes = format!("{anchor}_items_hashes");
+        for (n, hash) in self.array(component, path, "hashes", &hashes).iter().enumerate() {
+            let path = format!("{path}/hashes/{n}");
+            if let Some(alg) = self.string(hash, &path, "alg", true, &format!("{hashes}_items_alg")) {
+                if !CYCLONEDX_HASH_ALGORITHMS.contains(&alg) {
+                    self.violation(
+                        &format!("{path}/alg"),
+                        format!("Unknown hash algorithm '{alg}'"),
+                        Some(&format!("{hashes}_items_alg")),
+                    );
+                }
+            }
+            if let Some(content) = self.string(hash, &path, "content", true, &format!("{hashes}_items_content")) {
+                if !content.chars().all(|c| c.is_ascii_hexdigit()) {
+                    self.violation(
+                        &format!("{path}/content"),
+                        "The content of a hash must be hexadecimal",
+                        Some(&format!("{hashes}_items_content")),
+                    );
+                }
+            }
+        }
+
+        let licenses = format!("{anchor}_items_licenses");
+        for (n, license) in self.array(component, path, "licenses", &licenses).iter().enumerate() {
+            let path = format!("{path}/licenses/{n}");
+            match (license.get("license"), license.get("expression")) {
+                (Some(license), None) => {
+                    if license.get("id").is_none() && license.get("name").is_none() {
+                        self.violation(
+                            &format!("{path}/license"),
+                            "A license must have either an 'id' or a 'name'",
+                            Some(&licenses),
+                        );
+                    }
+                }
+                (None, Some(Value::String(_))) => {}
+                _ => self.violation(&path, "Expected either a 'license' or an 'expression'", Some(&licenses)),
+            }
+        }
+
+        self.cyclonedx_components(component, path, refs);
+    }
+
+    fn spdx(&mut self, value: &Value) {
+        self.spec = "https://spdx.github.io/spdx-spec/v2.3/".to_string();
+        const DOCUMENT: &str = "document-creation-information/";
+        const PACKAGE: &str = "package-information/";
+
+        if let Some(version) = self.string(value, "", "spdxVersion", true, DOCUMENT) {
+            if !version.starts_with("SPDX-2.") {
+                self.violation(
+                    "/spdxVersion",
+                    format!("Unsupported specification version '{version}', expected SPDX-2.x"),
+                    Some(&format!("{DOCUMENT}#61-spdx-version-field")),
+                );
+            }
+        }
+        if let Some(license) = self.string(value, "", "dataLicense", true, DOCUMENT) {
+            if license != "CC0-1.0" {
+                self.violation(
+                    "/dataLicense",
+                    "The data license must be 'CC0-1.0'",
+                    Some(&format!("{DOCUMENT}#62-data-license-field")),
+                );
+            }
+        }
+        if let Some(id) = self.string(value, "", "SPDXID", true, DOCUMENT) {
+            if id != "SPDXRef-DOCUMENT" {
+                self.violation(
+                    "/SPDXID",
+                    "The identifier of the document must be 'SPDXRef-DOCUMENT'",
+                    Some(&format!("{DOCUMENT}#63-spdx-identifier-field")),
+                );
+            }
+        }
+        self.string(value, "", "name", true, &format!("{DOCUMENT}#64-document-name-field"));
+        self.string(
+            value,
+            "",
+            "documentNamespace",
+            true,
+            &format!("{DOCUMENT}#65-spdx-document-namespace-field"),
+        );
+        match value.get("creationInfo") {
+            Some(info) => {
+                self.string(
+                    info,
+                    "/creationInfo",
+                    "created",
+                    true,
+                    &format!("{DOCUMENT}#69-created-field"),
+                );
+                if self.array(info, "/creationInfo", "creators", DOCUMENT).is_empty() {
+                    self.violation(
+                        "/creationInfo/creators",
+                        "The document must have at least one creator",
+                        Some(&format!("{DOCUMENT}#68-creator-field")),
+                    );
+                }
+            }
+            None => self.violation("", "The required property 'creationInfo' is missing", Some(DOCUMENT)),
+        }
+
+        let mut ids = HashSet::new();
+        for (n, package) in self.array(value, "", "packages", PACKAGE).iter().enumerate() {
+            let path = format!("/packages/{n}");
+            self.string(
+                package,
+                &path,
+                "name",
+                true,
+                &format!("{PACKAGE}#71-package-name-field"),
+            );
+            let anchor = format!("{PACKAGE}#72-package-spdx-identifier-field");
+            if let Some(id) = self.string(package, &path, "SPDXID", true, &anchor) {
+                if !id.starts_with("SPDXRef-") {
+                    self.violation(
+                        &format!("{path}/SPDXID"),
+                        format!("Invalid identifier '{id}', expected it to start with 'SPDXRef-'"),
+                        Some(&anchor),
+                    );
+                } else if !ids.insert(id) {
+                    self.violation(
+                        &format!("{path}/SPDXID"),
+                        format!("The identifier '{id}' is not unique"),
+                        Some(&anchor),
+                    );
+                }
+            }
+            self.string(
+                package,
+                &path,
+                "downloadLocation",
+                true,
+                &format!("{PACKAGE}#77-package-download-location-field"),
+            );
+            for (key, anchor) in [
+                ("licenseConcluded", "#713-concluded-license-field"),
+                ("licenseDeclared", "#715-declared-license-field"),
+            ] {
+                let anchor = format!("{PACKAGE}{anchor}");
+                if let Some(expression) = self.string(package, &path, key, false, &anchor) {
+                    if let Err(err) = spdx_expression::SpdxExpression::parse(expression) {
+                        self.violation(
+                            &format!("{path}/{key}"),
+                            format!("Invalid license expression '{expression}': {err}"),
+                            Some(&anchor),
+                        );
+                    }
+                }
+            }
+            let anchor = format!("{PACKAGE}#710-package-checksum-field");
+            for (m, checksum) in self.array(package, &path, "checksums", &anchor).iter().enumerate() {
+                let path = format!("{path}/checksums/{m}");
+                self.string(checksum, &path, "algorithm", true, &anchor);
+                self.string(checksum, &path, "checksumValue", true, &anchor);
+            }
+        }
+
+        const RELATIONSHIPS: &str = "relationships-between-SPDX-elements/";
+        for (n, relationship) in self.array(value, "", "relationships", RELATIONSHIPS).iter().enumerate() {
+            let path = format!("/relationships/{n}");
+            for key in ["spdxElementId", "relationshipType", "relatedSpdxElement"] {
+                self.string(relationship, &path, key, true, RELATIONSHIPS);
+            }
+        }
+    }
+
+    fn spdx3(&mut self, value: &Value) {
+        self.spec = "https://spdx.github.io/spdx-spec/v3.0.1/".to_string();
+
+        let Some(elements) = value["@graph"].as_array() else {
+            self.violation("", "The required property '@graph' is missing", Some("serializations/"));
+            return;
+        };
+        for (n, element) in elements.iter().enumerate() {
+            if element.get("type").or_else(|| element.get("@type")).is_none() {
+                self.violation(
+                    &format!("/@graph/{n}"),
+                    "The element has no type",
+                    Some("model/Core/Classes/Element/"),
+                );
+            }
+        }
+        if let Err(err) = crate::spdx3::to_model(value) {
+            self.violation("/@graph", err, Some("model/Core/Classes/CreationInfo/"));
+        }
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(n, c)| match n {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(value: Value) -> Vec<String> {
+        validate(&serde_json::to_vec(&value).unwrap())
+            .into_iter()
+            .map(|violation| violation.path)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        for file in ["my-sbom.json", "ubi9-sbom.json", "cyclonedx-1.5.json", "spdx-3.0.json"] {
+            let data = std::fs::read(format!("../testdata/{file}")).unwrap();
+            assert_eq!(validate(&data), vec![], "{file}");
+        }
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let violations = paths(json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "serialNumber": "42",
+            "version": 0,
+            "components": [
+                {"type": "library", "name": "a", "bom-ref": "a"},
+                {"type": "toy", "bom-ref": "a", "hashes": [{"alg": "SHA-256", "content": "xyz"}]},
+                {"type": "library", "name": "b", "licenses": [{}], "components": [{"type": "file"}]},
+            ],
+            "dependencies": [{"dependsOn": ["a", 1]}],
+        }));
+        assert_eq!(
+            violations,
+            vec![
+                "/serialNumber",
+                "/version",
+                "/components/1/type",
+                "/components/1",
+                "/components/1/bom-ref",
+                "/components/1/hashes/0/content",
+                "/components/2/licenses/0",
+                "/components/2/components/0",
+                "/dependencies/0",
+                "/dependencies/0/dependsOn/1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spdx() {
+        let violations = validate(
+            &serde_json::to_vec(&json!({
+                "spdxVersion": "SPDX-2.3",
+                "dataLicense": "CC0-1.0",
+                "SPDXID": "SPDXRef-DOCUMENT",
+                "name": "example",
+                "creationInfo": {"created": "2023-01-01T00:00:00Z", "creators": []},
+                "packages": [
+                    {"SPDXID": "SPDXRef-a", "name": "a", "downloadLocation": "NOASSERTION", "licenseDeclared": "Apache 2"},
+                    {"SPDXID": "SPDXRef-a", "downloadLocation": "NOASSERTION"},
+                ],
+                "relationships": [{"spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES"}],
+            }))
+            .unwrap(),
+        );
+        let paths: Vec<_> = violations.iter().map(|violation| violation.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "",
+                "/creationInfo/creators",
+                "/packages/0/licenseDeclared",
+                "/packages/1",
+                "/packages/1/SPDXID",
+                "/relationships/0",
+            ]
+        );
+        assert_eq!(
+            violations[0].spec.as_deref(),
+            Some(
+                "https://spdx.github.io/spdx-spec/v2.3/document-creation-information/#65-spdx-document-namespace-field"
+            )
+        );
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(paths(json!({"hello": "world"})), vec![""]);
+        assert_eq!(validate(b"{").len(), 1);
+    }
+
+    #[test]
+    fn test_problem() {
+        let problem = Problem::invalid_sbom(vec![Violation::new("/name", "missing")]);
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["type"], INVALID_SBOM);
+        assert_eq!(json["status"], 400);
+        assert_eq!(json["violations"][0]["path"], "/name");
+    }
+}
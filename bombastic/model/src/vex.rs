@@ -0,0 +1,141 @@
+//! Extraction of the vulnerabilities embedded in CycloneDX SBOMs.
+//!
+//! Since version 1.4, a CycloneDX SBOM can carry vulnerabilities, along with the analysis of their impact on its
+//! components, which turns it into a VEX or VDR document as well. Those are split off into a standalone CycloneDX
+//! VEX document, which carries the vulnerabilities, the components they affect and the metadata of the SBOM, so that
+//! it can be ingested like any other VEX document.
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Extract the vulnerabilities embedded in a CycloneDX SBOM as a standalone CycloneDX VEX document.
+///
+/// Returns `None` if the document isn't a CycloneDX SBOM, or doesn't carry any vulnerabilities.
+pub fn extract_vex(sbom: &Value) -> Option<Value> {
+    if sbom["bomFormat"] != "CycloneDX" {
+        return None;
+    }
+    let vulnerabilities = sbom["vulnerabilities"].as_array().filter(|v| !v.is_empty())?;
+
+    let references: BTreeSet<&str> = vulnerabilities
+        .iter()
+        .flat_map(|vulnerability| vulnerability["affects"].as_array().into_iter().flatten())
+        .filter_map(|affects| affects["ref"].as_str())
+        // a BOM-Link refers to a component of this, or another, SBOM by the fragment
+        .map(|r| r.rsplit_once('#').map(|(_, r)| r).unwrap_or(r))
+        .collect();
+
+    let mut components = Vec::new();
+    collect_components(
+        std::iter::once(&sbom["metadata"]["component"]).chain(sbom["components"].as_array().into_iter().flatten()),
+        &references,
+        &mut components,
+    );
+
+    let mut metadata = Map::new();
+    let timestamp = match &sbom["metadata"]["timestamp"] {
+        Value::String(timestamp) => timestamp.clone(),
+        _ => OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+    };
+    metadata.insert("timestamp".into(), timestamp.into());
+    for key in ["component", "supplier", "manufacture", "authors"] {
+        let mut value = sbom["metadata"][key].clone();
+        if let Some(component) = value.as_object_mut() {
+            component.remove("components");
+        }
+        if !value.is_null() {
+            metadata.insert(key.into(), value);
+        }
+    }
+
+    let mut vex = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": sbom["specVersion"],
+        "version": sbom["version"],
+        "metadata": metadata,
+        "components": components,
+        "vulnerabilities": vulnerabilities,
+    });
+    if let Some(serial) = sbom["serialNumber"].as_str() {
+        vex["serialNumber"] = serial.into();
+    }
+    Some(vex)
+}
+
+/// Collect the components, and their nested components, which are referenced, without their nested components.
+fn collect_components<'a>(
+    components: impl IntoIterator<Item = &'a Value>,
+    references: &BTreeSet<&str>,
+    result: &mut Vec<Value>,
+) {
+    for component in components {
+        if let Some(r) = component["bom-ref"].as_str() {
+            if references.contains(r) {
+                let mut component = component.clone();
+                if let Some(component) = component.as_object_mut() {
+                    component.remove("components");
+                }
+                result.push(component);
+            }
+        }
+        collect_components(
+            component["components"].as_array().into_iter().flatten(),
+            references,
+            result,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extract_vex() {
+        let sbom = json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79",
+            "version": 1,
+            "metadata": {
+                "timestamp": "2023-06-01T12:00:00Z",
+                "component": { "type": "application", "bom-ref": "app", "name": "app" }
+            },
+            "components": [
+                {
+                    "type": "library",
+                    "bom-ref": "pkg:npm/express@4.18.2",
+                    "name": "express",
+                    "components": [
+                        { "type": "library", "bom-ref": "pkg:npm/qs@6.11.0", "name": "qs" }
+                    ]
+                },
+                { "type": "library", "bom-ref": "pkg:npm/lodash@4.17.20", "name": "lodash" }
+            ],
+            "vulnerabilities": [{
+                "id": "CVE-2022-24999",
+                "analysis": { "state": "exploitable" },
+                "affects": [{ "ref": "urn:cdx:3e671687-395b-41f5-a30f-a58921a69b79/1#pkg:npm/qs@6.11.0" }]
+            }]
+        });
+
+        let vex = extract_vex(&sbom).unwrap();
+        assert_eq!(vex["serialNumber"], sbom["serialNumber"]);
+        assert_eq!(vex["metadata"]["timestamp"], json!("2023-06-01T12:00:00Z"));
+        assert_eq!(vex["metadata"]["component"]["name"], json!("app"));
+        assert_eq!(
+            vex["components"],
+            json!([{ "type": "library", "bom-ref": "pkg:npm/qs@6.11.0", "name": "qs" }])
+        );
+        assert_eq!(vex["vulnerabilities"], sbom["vulnerabilities"]);
+    }
+
+    #[test]
+    fn test_extract_no_vulnerabilities() {
+        let sbom = json!({ "bomFormat": "CycloneDX", "specVersion": "1.5", "vulnerabilities": [] });
+        assert!(extract_vex(&sbom).is_none());
+        let sbom = json!({ "spdxVersion": "SPDX-2.3", "vulnerabilities": [{ "id": "CVE-2022-24999" }] });
+        assert!(extract_vex(&sbom).is_none());
+    }
+}
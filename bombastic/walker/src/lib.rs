@@ -15,9 +15,13 @@ use trustification_infrastructure::{
 use url::Url;
 use walker_common::sender::provider::TokenProvider;
 
+pub mod oci;
+pub mod remote;
+
 mod processing;
 mod report;
 mod scanner;
+mod upload;
 
 const DEVMODE_SOURCE: &str = "https://access.redhat.com/security/data/sbom/beta/";
 const DEVMODE_KEY: &str =
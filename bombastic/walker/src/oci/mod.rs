@@ -0,0 +1,291 @@
+//! Ingestion of the SBOMs attached to images in OCI registries.
+//!
+//! The SBOMs of an image are discovered through the referrers API of the registry, and through the tags cosign uses
+//! for attaching SBOMs (`sha256-<digest>.sbom`) and attestations (`sha256-<digest>.att`) to images, for registries
+//! which don't support the referrers API. SBOMs attested as in-toto statements with an SPDX or CycloneDX predicate
+//! are ingested as well, without verifying the signature of the attestation.
+//!
+//! Each SBOM is stored for each image it is attached to, identified by the digests of the image and of its blob, like
+//! `sha256-<image>.sha256-<blob>`. It carries the labels `image.repository`, `image.digest` and `image.reference`,
+//! so that the SBOMs of an image can be looked up by its digest, like `label:"image.digest=sha256:..."`. Manifests
+//! and blobs are verified against their digest.
+
+use crate::{oci::reference::Reference, upload::Uploader};
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{arg, command, Args};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use trustification_auth::client::OpenIdTokenProviderConfigArguments;
+use trustification_common::{
+    oci::{Credentials, Registry},
+    tls::ClientConfig,
+};
+use trustification_infrastructure::{
+    endpoint::{self, Endpoint},
+    Infrastructure, InfrastructureConfig,
+};
+use url::Url;
+
+mod reference;
+
+/// Media types of manifests an image reference can resolve to.
+const MANIFEST_TYPES: &[&str] = &[
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+/// Media types of SBOM layers.
+const SBOM_TYPES: &[&str] = &[
+    "application/spdx+json",
+    "text/spdx+json",
+    "application/vnd.cyclonedx+json",
+];
+
+/// Media type of the layers of attestations.
+const DSSE_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+
+/// Artifact types of referrers which may carry SBOMs.
+const ARTIFACT_TYPES: &[&str] = &[
+    "application/spdx+json",
+    "text/spdx+json",
+    "application/vnd.cyclonedx+json",
+    "application/vnd.dev.cosign.artifact.sbom.v1+json",
+    "application/vnd.in-toto+json",
+    DSSE_TYPE,
+];
+
+/// Predicate types of in-toto statements attesting an SBOM, the type may be followed by a version.
+const SBOM_PREDICATE_TYPES: &[&str] = &["https://spdx.dev/Document", "https://cyclonedx.org/bom"];
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Ingest the SBOMs attached to images in OCI registries",
+    args_conflicts_with_subcommands = true,
+    rename_all_env = "SCREAMING_SNAKE_CASE"
+)]
+pub struct Run {
+    /// Apply reasonable settings for local development. Do not use in production!
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// Bombastic
+    #[arg(long = "sink", env, default_value_t = endpoint::Bombastic::url())]
+    pub sink: Url,
+
+    /// Reference of an image to ingest the SBOMs of, like `quay.io/org/image:tag` or `image@sha256:...`.
+    #[arg(long = "image", required = true)]
+    pub images: Vec<String>,
+
+    /// Username for the registry. Anonymous access is used if not set.
+    #[arg(long, env, requires = "registry_password")]
+    pub registry_username: Option<String>,
+
+    /// Password, or token, for the registry.
+    #[arg(long, env, requires = "registry_username")]
+    pub registry_password: Option<String>,
+
+    /// Access the registry using plain HTTP. Do not use in production!
+    #[arg(long = "plain-http", default_value_t = false)]
+    pub plain_http: bool,
+
+    /// OIDC client
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        Infrastructure::from(self.infra)
+            .run(
+                "bombastic-oci-ingestion",
+                |_context| async { Ok(()) },
+                |_| async move {
+                    let client = self.client.build_client()?;
+                    let uploader = Uploader::new(
+                        client.clone(),
+                        self.oidc.into_provider_or_devmode(self.devmode).await?,
+                        &self.sink,
+                    )?;
+                    let credentials = match (self.registry_username, self.registry_password) {
+                        (Some(username), Some(password)) => Some(Credentials { username, password }),
+                        _ => None,
+                    };
+
+                    let mut failed = 0;
+                    for image in &self.images {
+                        let reference = Reference::parse(image)?;
+                        let registry =
+                            Registry::new(client.clone(), reference.host(), &reference.repository, self.plain_http)?
+                                .with_credentials(credentials.clone());
+                        match ingest(registry, image, &reference, &uploader).await {
+                            Ok(0) => log::warn!("No SBOMs found for image {image}"),
+                            Ok(n) => log::info!("Ingested {n} SBOMs for image {image}"),
+                            Err(err) => {
+                                log::warn!("Failed to ingest the SBOMs of image {image}: {err}");
+                                failed += 1;
+                            }
+                        }
+                    }
+                    if failed > 0 {
+                        bail!("Failed to ingest the SBOMs of {failed} images");
+                    }
+
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Ingest the SBOMs attached to an image, returning the number of SBOMs ingested.
+async fn ingest(
+    mut registry: Registry,
+    image: &str,
+    reference: &Reference,
+    uploader: &Uploader,
+) -> anyhow::Result<usize> {
+    let digest = match (&reference.digest, &reference.tag) {
+        (Some(digest), _) => digest.clone(),
+        (None, Some(tag)) => {
+            registry
+                .manifest(tag, MANIFEST_TYPES)
+                .await?
+                .ok_or_else(|| anyhow!("image not found"))?
+                .0
+        }
+        (None, None) => bail!("image reference without tag or digest"),
+    };
+    log::debug!("Discovering SBOMs of {image}: digest={digest}");
+
+    let mut artifacts = Vec::new();
+    if let Some(index) = registry.referrers(&digest).await? {
+        for descriptor in index["manifests"].as_array().into_iter().flatten() {
+            let artifact_type = descriptor["artifactType"].as_str().unwrap_or_default();
+            if let (true, Some(digest)) = (ARTIFACT_TYPES.contains(&artifact_type), descriptor["digest"].as_str()) {
+                artifacts.extend(registry.manifest(digest, MANIFEST_TYPES).await?);
+            }
+        }
+    }
+    let tag = digest.replace(':', "-");
+    for suffix in ["sbom", "att"] {
+        artifacts.extend(registry.manifest(&format!("{tag}.{suffix}"), MANIFEST_TYPES).await?);
+    }
+
+    let name = reference.name();
+    let labels = BTreeMap::from([
+        ("source", "oci"),
+        ("image.repository", name.as_str()),
+        ("image.digest", digest.as_str()),
+        ("image.reference", image),
+    ]);
+    let mut ingested = 0;
+    for (_, manifest) in artifacts {
+        for layer in manifest["layers"].as_array().into_iter().flatten() {
+            let (Some(media_type), Some(layer_digest)) = (layer["mediaType"].as_str(), layer["digest"].as_str()) else {
+                continue;
+            };
+            if !SBOM_TYPES.contains(&media_type) && media_type != DSSE_TYPE {
+                continue;
+            }
+            let blob = registry
+                .blob(layer_digest)
+                .await?
+                .ok_or_else(|| anyhow!("blob {layer_digest} not found"))?;
+            let sbom = match media_type {
+                DSSE_TYPE => match attested_sbom(&blob)? {
+                    Some(sbom) => sbom,
+                    None => continue,
+                },
+                _ => blob,
+            };
+            uploader
+                .upload(&sbom_id(&digest, layer_digest), sbom, None, &labels)
+                .await?;
+            ingested += 1;
+        }
+    }
+    Ok(ingested)
+}
+
+/// The identifier of an SBOM attached to an image, by the digests of the image and of the blob of the SBOM
+fn sbom_id(image_digest: &str, blob_digest: &str) -> String {
+    format!("{}.{}", image_digest.replace(':', "-"), blob_digest.replace(':', "-"))
+}
+
+/// Extract the SBOM from a DSSE envelope, returning `None` if it's an attestation of something else.
+fn attested_sbom(envelope: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    let envelope: Value = serde_json::from_slice(envelope)?;
+    if envelope["payloadType"] != "application/vnd.in-toto+json" {
+        return Ok(None);
+    }
+    let payload = envelope["payload"]
+        .as_str()
+        .ok_or_else(|| anyhow!("missing payload in attestation"))?;
+    let statement: Value = serde_json::from_slice(&STANDARD.decode(payload)?)?;
+    let predicate_type = statement["predicateType"].as_str().unwrap_or_default();
+    if !SBOM_PREDICATE_TYPES.iter().any(|t| predicate_type.starts_with(t)) {
+        return Ok(None);
+    }
+    Ok(match &statement["predicate"] {
+        Value::Null => None,
+        // some tools attest the SBOM serialized as string
+        Value::String(sbom) => Some(sbom.as_bytes().to_vec()),
+        sbom => Some(serde_json::to_vec(sbom)?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn envelope(statement: Value) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "payloadType": "application/vnd.in-toto+json",
+            "payload": STANDARD.encode(serde_json::to_vec(&statement).unwrap()),
+            "signatures": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sbom_id() {
+        assert_eq!(sbom_id("sha256:1234", "sha256:abcd"), "sha256-1234.sha256-abcd");
+        // the same SBOM attached to another image
+        assert_ne!(
+            sbom_id("sha256:1234", "sha256:abcd"),
+            sbom_id("sha256:5678", "sha256:abcd")
+        );
+    }
+
+    #[test]
+    fn test_attested_sbom() {
+        let sbom = json!({ "spdxVersion": "SPDX-2.3", "SPDXID": "SPDXRef-DOCUMENT" });
+        let attestation = envelope(json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "predicateType": "https://spdx.dev/Document",
+            "predicate": sbom,
+        }));
+        let extracted = attested_sbom(&attestation).unwrap().unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&extracted).unwrap(), sbom);
+
+        let attestation = envelope(json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "predicateType": "https://slsa.dev/provenance/v0.2",
+            "predicate": {},
+        }));
+        assert!(attested_sbom(&attestation).unwrap().is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::bail;
+
+/// The registry of image references without one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// A reference to an image, like `quay.io/org/image:tag` or `nginx@sha256:...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl Reference {
+    pub fn parse(reference: &str) -> anyhow::Result<Self> {
+        let (rest, digest) = match reference.split_once('@') {
+            Some((rest, digest)) => (rest, Some(digest.to_string())),
+            None => (reference, None),
+        };
+        // a colon after the last slash separates the tag, otherwise it's the port of the registry
+        let (name, tag) = match rest.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, Some(tag.to_string())),
+            _ => (rest, None),
+        };
+        let (registry, repository) = match name.split_once('/') {
+            Some((registry, repository))
+                if registry.contains('.') || registry.contains(':') || registry == "localhost" =>
+            {
+                (registry.to_string(), repository.to_string())
+            }
+            _ => (DEFAULT_REGISTRY.to_string(), name.to_string()),
+        };
+        let repository = match registry == DEFAULT_REGISTRY && !repository.contains('/') {
+            true => format!("library/{repository}"),
+            false => repository,
+        };
+
+        if repository.is_empty() {
+            bail!("missing repository in image reference '{reference}'");
+        }
+        if let Some(digest) = &digest {
+            if !digest.contains(':') {
+                bail!("invalid digest in image reference '{reference}'");
+            }
+        }
+        let tag = match (tag, &digest) {
+            (None, None) => Some("latest".to_string()),
+            (tag, _) => tag,
+        };
+
+        Ok(Self {
+            registry,
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    /// The host serving the registry API.
+    pub fn host(&self) -> &str {
+        match self.registry.as_str() {
+            DEFAULT_REGISTRY => "registry-1.docker.io",
+            registry => registry,
+        }
+    }
+
+    /// The repository, including the registry.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.registry, self.repository)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        let reference = Reference::parse("nginx").unwrap();
+        assert_eq!(reference.name(), "docker.io/library/nginx");
+        assert_eq!(reference.host(), "registry-1.docker.io");
+        assert_eq!(reference.tag.as_deref(), Some("latest"));
+
+        let reference = Reference::parse("quay.io/org/image:1.2").unwrap();
+        assert_eq!(reference.host(), "quay.io");
+        assert_eq!(reference.repository, "org/image");
+        assert_eq!(reference.tag.as_deref(), Some("1.2"));
+        assert_eq!(reference.digest, None);
+
+        let reference = Reference::parse("localhost:5000/image@sha256:abcd").unwrap();
+        assert_eq!(reference.registry, "localhost:5000");
+        assert_eq!(reference.repository, "image");
+        assert_eq!(reference.tag, None);
+        assert_eq!(reference.digest.as_deref(), Some("sha256:abcd"));
+
+        assert!(Reference::parse("quay.io/").is_err());
+        assert!(Reference::parse("image@abcd").is_err());
+    }
+}
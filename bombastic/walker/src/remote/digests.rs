@@ -0,0 +1,47 @@
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The digests of the documents uploaded so far, by their location.
+///
+/// If a file is configured, the digests are read from it at startup, and stored to it at the end of each run, so that
+/// unchanged documents are not uploaded again after a restart.
+pub struct Digests {
+    file: Option<PathBuf>,
+    digests: BTreeMap<String, String>,
+}
+
+impl Digests {
+    pub fn load(file: Option<PathBuf>) -> anyhow::Result<Self> {
+        let digests = match &file {
+            Some(path) if path.exists() => {
+                let data = std::fs::read(path).with_context(|| format!("failed to read digest file {path:?}"))?;
+                serde_json::from_slice(&data).with_context(|| format!("failed to parse digest file {path:?}"))?
+            }
+            _ => BTreeMap::new(),
+        };
+        Ok(Self { file, digests })
+    }
+
+    /// Check if the document at a location has changed since it was uploaded.
+    pub fn changed(&self, location: &str, digest: &str) -> bool {
+        self.digests.get(location).map(String::as_str) != Some(digest)
+    }
+
+    pub fn insert(&mut self, location: String, digest: String) {
+        self.digests.insert(location, digest);
+    }
+
+    /// Drop the digests of the documents which are no longer published.
+    pub fn retain(&mut self, f: impl Fn(&str) -> bool) {
+        self.digests.retain(|location, _| f(location));
+    }
+
+    pub fn store(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.file {
+            std::fs::write(path, serde_json::to_vec(&self.digests)?)
+                .with_context(|| format!("failed to store digest file {path:?}"))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+//! Walker for remote repositories of published SBOMs.
+//!
+//! Other than the SBOM walker, which follows the Red Hat style of publishing SBOMs, this walker crawls a plain HTTP
+//! index file, or all objects of an S3 bucket. New and changed documents are detected by their digest, which is taken
+//! from the index or the ETag of the object, or computed from the content if the repository doesn't publish one.
+//! Documents are identified by their path relative to the index or the prefix, and uploaded with the labels `source`
+//! and `source.location`, recording where they were crawled from.
+
+use crate::{
+    remote::{
+        digests::Digests,
+        source::{content_encoding, Source},
+    },
+    upload::Uploader,
+};
+use anyhow::bail;
+use clap::{arg, command, Args};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use trustification_auth::client::OpenIdTokenProviderConfigArguments;
+use trustification_common::tls::ClientConfig;
+use trustification_common_walker::report::{handle_report, Phase, Report, ReportBuilder, Severity};
+use trustification_infrastructure::{
+    endpoint::{self, Endpoint},
+    Infrastructure, InfrastructureConfig,
+};
+use trustification_storage::StorageConfig;
+use url::Url;
+
+mod digests;
+mod source;
+
+#[derive(Args, Debug)]
+#[command(
+    about = "Run the walker for remote SBOM repositories",
+    args_conflicts_with_subcommands = true,
+    rename_all_env = "SCREAMING_SNAKE_CASE"
+)]
+pub struct Run {
+    /// Apply reasonable settings for local development. Do not use in production!
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// Long-running mode. The repository will be scanned for changes every interval.
+    #[arg(long = "scan-interval")]
+    pub scan_interval: Option<humantime::Duration>,
+
+    /// Bombastic
+    #[arg(long = "sink", env, default_value_t = endpoint::Bombastic::url())]
+    pub sink: Url,
+
+    /// URL of the index file, listing the SBOMs of the repository. Conflicts with an S3 bucket.
+    #[arg(long, env)]
+    pub source: Option<Url>,
+
+    /// Prefix of the SBOM objects, when crawling an S3 bucket.
+    #[arg(long, env, default_value = "")]
+    pub prefix: String,
+
+    /// Name of the repository, recorded as `source` label. Defaults to the index URL or the bucket name.
+    #[arg(long, env)]
+    pub source_name: Option<String>,
+
+    /// The S3 bucket to crawl, instead of an index file.
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    /// A file to read/store the digests of the uploaded SBOMs to at the end of each run.
+    #[arg(long = "digest-file")]
+    pub digest_file: Option<PathBuf>,
+
+    /// OIDC client
+    #[command(flatten)]
+    pub oidc: OpenIdTokenProviderConfigArguments,
+
+    #[command(flatten)]
+    pub client: ClientConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        Infrastructure::from(self.infra)
+            .run(
+                "bombastic-remote-walker",
+                |_context| async { Ok(()) },
+                |_| async move {
+                    let client = self.client.build_client()?;
+                    let (source, name) = match (self.source, self.storage.bucket.clone()) {
+                        (Some(_), Some(_)) => {
+                            bail!("Only one of --source <url> and --storage-bucket <name> can be used")
+                        }
+                        (None, None) => {
+                            bail!("Missing source. Provide either --source <url> or --storage-bucket <name>")
+                        }
+                        (Some(index), None) => {
+                            let name = index.to_string();
+                            (
+                                Source::Http {
+                                    index,
+                                    client: client.clone(),
+                                },
+                                name,
+                            )
+                        }
+                        (None, Some(bucket)) => (
+                            Source::S3 {
+                                bucket: Box::new(self.storage.try_into()?),
+                                prefix: self.prefix,
+                            },
+                            bucket,
+                        ),
+                    };
+
+                    let mut walker = RemoteWalker {
+                        source,
+                        source_name: self.source_name.unwrap_or(name),
+                        uploader: Uploader::new(
+                            client,
+                            self.oidc.into_provider_or_devmode(self.devmode).await?,
+                            &self.sink,
+                        )?,
+                        digests: Digests::load(self.digest_file)?,
+                    };
+
+                    if let Some(interval) = self.scan_interval {
+                        walker.run(interval.into()).await?;
+                    } else {
+                        let (report, result) = walker.run_once().await;
+                        handle_report(report).await?;
+                        result?;
+                    }
+
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+struct RemoteWalker {
+    source: Source,
+    source_name: String,
+    uploader: Uploader,
+    digests: Digests,
+}
+
+impl RemoteWalker {
+    async fn run(&mut self, interval: Duration) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            let (report, result) = self.run_once().await;
+            if let Err(err) = result {
+                log::warn!("Failed to perform sync: {err}");
+            }
+            handle_report(report).await?;
+            interval.tick().await;
+        }
+    }
+
+    /// Crawl the repository once, uploading all new and changed documents.
+    ///
+    /// Failing to process a single document is recorded in the report, and doesn't fail the run. The digests are
+    /// stored at the end of the run, failing documents will be retried on the next run.
+    async fn run_once(&mut self) -> (Report, anyhow::Result<()>) {
+        let mut report = ReportBuilder::new();
+
+        let entries = match self.source.list().await {
+            Ok(entries) => entries,
+            Err(err) => return (report.build(), Err(err)),
+        };
+
+        let (mut uploaded, mut unchanged, mut failed) = (0, 0, 0);
+        for entry in &entries {
+            report.tick();
+            // we can skip fetching the document if the repository publishes its digest
+            if let Some(digest) = &entry.digest {
+                if !self.digests.changed(&entry.location, digest) {
+                    unchanged += 1;
+                    continue;
+                }
+            }
+
+            let data = match self.source.fetch(&entry.location).await {
+                Ok(data) => data,
+                Err(err) => {
+                    report.add_error(Phase::Retrieval, &entry.location, Severity::Error, err.to_string());
+                    failed += 1;
+                    continue;
+                }
+            };
+            let digest = match &entry.digest {
+                Some(digest) => digest.clone(),
+                None => hex::encode(Sha256::digest(&data)),
+            };
+            if !self.digests.changed(&entry.location, &digest) {
+                unchanged += 1;
+                continue;
+            }
+
+            let labels = BTreeMap::from([
+                ("source", self.source_name.as_str()),
+                ("source.location", entry.location.as_str()),
+            ]);
+            let result = self
+                .uploader
+                .upload(
+                    &self.source.document_id(&entry.location),
+                    data,
+                    content_encoding(&entry.location),
+                    &labels,
+                )
+                .await;
+            match result {
+                Ok(()) => {
+                    log::info!("Uploaded SBOM: {}", entry.location);
+                    self.digests.insert(entry.location.clone(), digest);
+                    uploaded += 1;
+                }
+                Err(err) => {
+                    report.add_error(Phase::Upload, &entry.location, Severity::Error, err.to_string());
+                    failed += 1;
+                }
+            }
+        }
+
+        log::info!(
+            "Crawled {}: {uploaded} new or changed, {unchanged} unchanged, {failed} failed",
+            self.source_name
+        );
+
+        let published: BTreeSet<_> = entries.iter().map(|entry| entry.location.as_str()).collect();
+        self.digests.retain(|location| published.contains(location));
+        let result = self.digests.store();
+
+        (report.build(), result)
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{anyhow, bail};
+use s3::Bucket;
+use url::Url;
+
+/// A document published by a remote repository.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Location of the document, a URL or the key of an object.
+    pub location: String,
+    /// Digest of the document, if the repository publishes it.
+    pub digest: Option<String>,
+}
+
+/// A remote repository of published SBOMs.
+pub enum Source {
+    /// An index file, listing the documents one per line.
+    Http { index: Url, client: reqwest::Client },
+    /// All objects of a bucket, having a prefix.
+    S3 { bucket: Box<Bucket>, prefix: String },
+}
+
+impl Source {
+    /// The documents currently published by the repository.
+    pub async fn list(&self) -> anyhow::Result<Vec<Entry>> {
+        match self {
+            Self::Http { index, client } => {
+                let content = client
+                    .get(index.clone())
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await?;
+                parse_index(index, &content)
+            }
+            Self::S3 { bucket, prefix } => {
+                let mut entries = Vec::new();
+                for result in bucket.list(prefix.clone(), None).await? {
+                    entries.extend(
+                        result
+                            .contents
+                            .into_iter()
+                            // skip "directories"
+                            .filter(|object| !object.key.ends_with('/'))
+                            .map(|object| Entry {
+                                location: object.key,
+                                digest: object.e_tag.map(|tag| tag.trim_matches('"').to_string()),
+                            }),
+                    );
+                }
+                Ok(entries)
+            }
+        }
+    }
+
+    /// The identifier of a document in bombastic, see [`document_id`].
+    pub fn document_id(&self, location: &str) -> String {
+        match self {
+            // the directory of the index
+            Self::Http { index, .. } => match index.join(".") {
+                Ok(base) => document_id(base.as_str(), location),
+                Err(_) => document_id("", location),
+            },
+            Self::S3 { prefix, .. } => document_id(prefix, location),
+        }
+    }
+
+    /// Fetch a document, as published.
+    pub async fn fetch(&self, location: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Http { client, .. } => Ok(client
+                .get(location)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec()),
+            Self::S3 { bucket, .. } => {
+                let response = bucket.get_object(location).await?;
+                match response.status_code() {
+                    200 => Ok(response.bytes().to_vec()),
+                    code => bail!("unexpected status {code} fetching object"),
+                }
+            }
+        }
+    }
+}
+
+/// Parse an index file, resolving the locations relative to the index.
+///
+/// Each line of the index is either the path or URL of a document, or the digest and path of a document in the format
+/// of the `sha256sum` tool. Empty lines, and lines starting with `#`, are skipped.
+pub fn parse_index(index: &Url, content: &str) -> anyhow::Result<Vec<Entry>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (digest, path) = match line.split_once(char::is_whitespace) {
+                // binary mode entries prefix the path with '*'
+                Some((digest, path)) => (Some(digest.to_string()), path.trim_start().trim_start_matches('*')),
+                None => (None, line),
+            };
+            let location = index
+                .join(path)
+                .map_err(|err| anyhow!("invalid location in index '{path}': {err}"))?;
+            Ok(Entry {
+                location: location.to_string(),
+                digest,
+            })
+        })
+        .collect()
+}
+
+/// The identifier of a document in bombastic: its path relative to the base of the repository, without the file
+/// extensions, so that documents of the same name in different directories don't replace each other.
+///
+/// Documents outside of the base, like the ones on a mirror listed in the index, are identified by their URL without
+/// the scheme.
+pub fn document_id(base: &str, location: &str) -> String {
+    let path = match location.strip_prefix(base) {
+        Some(path) if !path.is_empty() => path,
+        _ => location.split_once("://").map_or(location, |(_, rest)| rest),
+    };
+    let path = path.trim_start_matches('/');
+    let path = path
+        .strip_suffix(".bz2")
+        .or_else(|| path.strip_suffix(".zst"))
+        .unwrap_or(path);
+    path.strip_suffix(".json").unwrap_or(path).to_string()
+}
+
+/// The content encoding of a document, derived from its file extension.
+pub fn content_encoding(location: &str) -> Option<&'static str> {
+    if location.ends_with(".bz2") {
+        Some("bzip2")
+    } else if location.ends_with(".zst") {
+        Some("zstd")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_index() {
+        let index = Url::parse("https://example.com/sboms/index.txt").unwrap();
+        let content = r#"
+# published SBOMs
+a.json
+openssl/openssl-3.0.7.spdx.json.bz2
+d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26  b.json
+d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26 *c.json.zst
+https://mirror.example.com/d.json
+"#;
+        let entries = parse_index(&index, content).unwrap();
+        let locations: Vec<_> = entries.iter().map(|e| e.location.as_str()).collect();
+        assert_eq!(
+            locations,
+            vec![
+                "https://example.com/sboms/a.json",
+                "https://example.com/sboms/openssl/openssl-3.0.7.spdx.json.bz2",
+                "https://example.com/sboms/b.json",
+                "https://example.com/sboms/c.json.zst",
+                "https://mirror.example.com/d.json",
+            ]
+        );
+        assert_eq!(entries[0].digest, None);
+        assert_eq!(
+            entries[2].digest.as_deref(),
+            Some("d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26")
+        );
+    }
+
+    #[test]
+    fn test_document_id() {
+        let base = "https://example.com/sboms/";
+        assert_eq!(
+            document_id(base, "https://example.com/sboms/openssl-3.0.7.spdx.json.bz2"),
+            "openssl-3.0.7.spdx"
+        );
+        // documents of the same name in different directories
+        assert_eq!(document_id(base, "https://example.com/sboms/a/x.json"), "a/x");
+        assert_eq!(document_id(base, "https://example.com/sboms/b/x.json"), "b/x");
+        assert_eq!(
+            document_id(base, "https://mirror.example.com/d.json"),
+            "mirror.example.com/d"
+        );
+
+        assert_eq!(document_id("sboms/", "sboms/ubi9.json"), "ubi9");
+        assert_eq!(document_id("", "sboms/ubi9.json"), "sboms/ubi9");
+        assert_eq!(document_id("", "ubi9"), "ubi9");
+        assert_eq!(content_encoding("ubi9.json.zst"), Some("zstd"));
+        assert_eq!(content_encoding("ubi9.json"), None);
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use trustification_auth::client::{TokenInjector, TokenProvider};
+use url::Url;
+
+/// Uploads SBOMs to bombastic, labelling them with where they were ingested from.
+pub struct Uploader {
+    client: reqwest::Client,
+    provider: Arc<dyn TokenProvider>,
+    target: Url,
+    labels: Url,
+}
+
+impl Uploader {
+    pub fn new(client: reqwest::Client, provider: Arc<dyn TokenProvider>, sink: &Url) -> anyhow::Result<Self> {
+        Ok(Self {
+            client,
+            provider,
+            target: sink.join("/api/v1/sbom")?,
+            labels: sink.join("/api/v1/sbom/labels")?,
+        })
+    }
+
+    /// Upload an SBOM, replacing an SBOM of the same identifier, and add labels to it.
+    pub async fn upload(
+        &self,
+        id: &str,
+        data: Vec<u8>,
+        encoding: Option<&str>,
+        labels: &BTreeMap<&str, &str>,
+    ) -> anyhow::Result<()> {
+        let mut request = self
+            .client
+            .put(self.target.clone())
+            .query(&[("id", id)])
+            .inject_token(self.provider.as_ref())
+            .await?
+            .body(data);
+        if let Some(encoding) = encoding {
+            request = request.header(reqwest::header::CONTENT_ENCODING, encoding);
+        }
+        request.send().await?.error_for_status()?;
+
+        let labels: BTreeMap<_, _> = labels.iter().map(|(key, value)| (*key, Some(*value))).collect();
+        self.client
+            .patch(self.labels.clone())
+            .query(&[("id", id)])
+            .inject_token(self.provider.as_ref())
+            .await?
+            .json(&labels)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
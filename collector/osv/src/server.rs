@@ -163,6 +163,15 @@ pub async fn collect_packages(
                                         }
                                     }
 
+                                    // store the OSV record as well, making it searchable by its own id, unless the
+                                    // vulnerability is stored as its CVE
+                                    if vulnerability_input_specs.is_empty() {
+                                        if let Err(err) = state.v11y_client.ingest_osv(&osv_vuln).await {
+                                            log::warn!("v11y error: {err}");
+                                            collected_v11y_errors.push(err);
+                                        }
+                                    }
+
                                     let v11y_vuln = v11y_client::Vulnerability::from(osv_vuln);
                                     if let Err(err) = state.v11y_client.ingest_vulnerability(&v11y_vuln).await {
                                         log::warn!("v11y error: {err}");
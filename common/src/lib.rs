@@ -1,6 +1,8 @@
 pub mod config;
 pub mod error;
 
+#[cfg(feature = "oci")]
+pub mod oci;
 #[cfg(feature = "tls")]
 pub mod reqwest;
 #[cfg(feature = "tls")]
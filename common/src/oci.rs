@@ -0,0 +1,276 @@
+//! A client for repositories of OCI registries, using the distribution API.
+
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{header, Response, StatusCode};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
+use url::Url;
+
+/// Credentials for a registry.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// A client for a repository of an OCI registry.
+///
+/// Requests are authenticated on demand, as requested by the registry, using either basic authentication or the token
+/// authentication of the distribution API. Without credentials, anonymous tokens are requested. Manifests fetched by
+/// digest, and blobs, are verified against their digest.
+pub struct Registry {
+    client: reqwest::Client,
+    base: Url,
+    credentials: Option<Credentials>,
+    /// The hosts which may issue tokens, any host if not restricted
+    realms: Option<Vec<String>>,
+    authorization: Option<String>,
+}
+
+impl Registry {
+    /// Create a client for a repository of a registry, like `quay.io` and `org/image`.
+    pub fn new(client: reqwest::Client, host: &str, repository: &str, plain_http: bool) -> anyhow::Result<Self> {
+        let scheme = if plain_http { "http" } else { "https" };
+        let base = Url::parse(&format!("{scheme}://{host}/v2/{repository}/"))?;
+        Ok(Self {
+            client,
+            base,
+            credentials: None,
+            realms: None,
+            authorization: None,
+        })
+    }
+
+    /// Authenticate with credentials, instead of requesting anonymous tokens.
+    pub fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Only request tokens from realms which are HTTPS URLs of one of the hosts, optionally with a port.
+    ///
+    /// The realm is chosen by the registry, so this keeps a registry from making the client send requests elsewhere.
+    pub fn with_allowed_realms(mut self, hosts: &[String]) -> Self {
+        self.realms = Some(hosts.to_vec());
+        self
+    }
+
+    /// Fetch a manifest by tag or digest, returning `None` if it doesn't exist.
+    ///
+    /// Returns the digest of the manifest, along with the manifest.
+    pub async fn manifest(&mut self, reference: &str, accept: &[&str]) -> anyhow::Result<Option<(String, Value)>> {
+        let Some(response) = self.get(&format!("manifests/{reference}"), accept).await? else {
+            return Ok(None);
+        };
+        let digest = response
+            .headers()
+            .get("Docker-Content-Digest")
+            .and_then(|digest| digest.to_str().ok())
+            .map(ToString::to_string);
+        let data = response.bytes().await?;
+        let digest = match digest {
+            // a manifest requested by digest must have that digest, whatever the registry claims
+            _ if reference.contains(':') => reference.to_string(),
+            Some(digest) => digest,
+            None => format!("sha256:{}", hex::encode(Sha256::digest(&data))),
+        };
+        verify_digest(&digest, &data)?;
+        Ok(Some((digest, serde_json::from_slice(&data)?)))
+    }
+
+    /// Fetch the index of the artifacts referring to a manifest, returning `None` if the registry doesn't support the
+    /// referrers API.
+    pub async fn referrers(&mut self, digest: &str) -> anyhow::Result<Option<Value>> {
+        let Some(response) = self
+            .get(
+                &format!("referrers/{digest}"),
+                &["application/vnd.oci.image.index.v1+json"],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(response.json().await?))
+    }
+
+    /// Fetch a blob by its digest, returning `None` if it doesn't exist.
+    pub async fn blob(&mut self, digest: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(response) = self.get(&format!("blobs/{digest}"), &[]).await? else {
+            return Ok(None);
+        };
+        let data = response.bytes().await?.to_vec();
+        verify_digest(digest, &data)?;
+        Ok(Some(data))
+    }
+
+    /// Send a GET request, returning `None` if the resource doesn't exist.
+    async fn get(&mut self, path: &str, accept: &[&str]) -> anyhow::Result<Option<Response>> {
+        let url = self.base.join(path)?;
+        let mut retried = false;
+        loop {
+            let mut request = self.client.get(url.clone());
+            if !accept.is_empty() {
+                request = request.header(header::ACCEPT, accept.join(", "));
+            }
+            if let Some(authorization) = &self.authorization {
+                request = request.header(header::AUTHORIZATION, authorization);
+            }
+            let response = request.send().await?;
+            match response.status() {
+                StatusCode::NOT_FOUND => return Ok(None),
+                StatusCode::UNAUTHORIZED if !retried => {
+                    let challenge = response
+                        .headers()
+                        .get(header::WWW_AUTHENTICATE)
+                        .and_then(|challenge| challenge.to_str().ok())
+                        .ok_or_else(|| anyhow!("registry requires authentication, but didn't send a challenge"))?
+                        .to_string();
+                    self.authorization = Some(self.authenticate(&challenge).await?);
+                    retried = true;
+                }
+                _ => return Ok(Some(response.error_for_status()?)),
+            }
+        }
+    }
+
+    /// Answer an authentication challenge, returning the value of the authorization header.
+    async fn authenticate(&self, challenge: &str) -> anyhow::Result<String> {
+        let (scheme, params) = parse_challenge(challenge);
+        if scheme.eq_ignore_ascii_case("basic") {
+            let Some(credentials) = &self.credentials else {
+                bail!("registry requires credentials");
+            };
+            return Ok(format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", credentials.username, credentials.password))
+            ));
+        }
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            bail!("unsupported authentication scheme of registry: {scheme}");
+        }
+
+        let realm = params
+            .get("realm")
+            .ok_or_else(|| anyhow!("missing realm in authentication challenge"))?;
+        let realm = self.realm(realm)?;
+        let query: Vec<_> = params.iter().filter(|(key, _)| *key != "realm").collect();
+        let mut request = self.client.get(realm).query(&query);
+        if let Some(credentials) = &self.credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.password));
+        }
+        let response: Value = request.send().await?.error_for_status()?.json().await?;
+        let token = response["token"]
+            .as_str()
+            .or_else(|| response["access_token"].as_str())
+            .ok_or_else(|| anyhow!("missing token in response of registry authentication"))?;
+        Ok(format!("Bearer {token}"))
+    }
+
+    /// Parse the realm of a challenge, failing if tokens may not be requested from it.
+    fn realm(&self, realm: &str) -> anyhow::Result<Url> {
+        let url = Url::parse(realm)?;
+        let Some(hosts) = &self.realms else {
+            return Ok(url);
+        };
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => String::new(),
+        };
+        match url.scheme() == "https" && hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(&host)) {
+            true => Ok(url),
+            false => bail!("registry authentication realm {realm} is not an allowed registry"),
+        }
+    }
+}
+
+/// Parse an authentication challenge, like `Bearer realm="https://auth",service="registry"`, into its scheme and its
+/// parameters, with lowercase keys.
+pub fn parse_challenge(challenge: &str) -> (&str, BTreeMap<String, String>) {
+    let (scheme, params) = challenge.trim().split_once(' ').unwrap_or((challenge.trim(), ""));
+    let mut result = BTreeMap::new();
+    let mut rest = params.trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let value = value.trim_start();
+        let (value, remainder) = match value.strip_prefix('"') {
+            // quoted values may contain commas, like a scope for multiple actions
+            Some(value) => value.split_once('"').unwrap_or((value, "")),
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        result.insert(key, value.to_string());
+        rest = remainder;
+    }
+    (scheme, result)
+}
+
+/// Check content against its digest, like `sha256:<hex>`.
+pub fn verify_digest(digest: &str, data: &[u8]) -> anyhow::Result<()> {
+    let (algorithm, expected) = digest
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid digest: {digest}"))?;
+    let actual = match algorithm {
+        "sha256" => hex::encode(Sha256::digest(data)),
+        "sha512" => hex::encode(Sha512::digest(data)),
+        _ => bail!("unsupported digest algorithm: {algorithm}"),
+    };
+    match actual.eq_ignore_ascii_case(expected) {
+        true => Ok(()),
+        false => bail!("content doesn't match its digest {digest}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenge() {
+        let (scheme, params) = parse_challenge(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/nginx:pull,push""#,
+        );
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(
+            params,
+            BTreeMap::from([
+                ("realm".to_string(), "https://auth.docker.io/token".to_string()),
+                ("service".to_string(), "registry.docker.io".to_string()),
+                ("scope".to_string(), "repository:library/nginx:pull,push".to_string()),
+            ])
+        );
+
+        let (scheme, params) = parse_challenge("bearer Realm=https://quay.io/v2/auth, service=quay.io");
+        assert_eq!(scheme, "bearer");
+        assert_eq!(params["realm"], "https://quay.io/v2/auth");
+        assert_eq!(params["service"], "quay.io");
+
+        let (scheme, params) = parse_challenge("Basic");
+        assert_eq!(scheme, "Basic");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_realm_allowed() {
+        let registry = Registry::new(reqwest::Client::new(), "quay.io", "org/app", false).unwrap();
+        assert!(registry.realm("https://169.254.169.254/latest/meta-data").is_ok());
+
+        let registry = registry.with_allowed_realms(&["quay.io".to_string()]);
+        assert!(registry.realm("https://quay.io/v2/auth").is_ok());
+        for realm in ["http://quay.io/v2/auth", "https://169.254.169.254/latest/meta-data"] {
+            let err = registry.realm(realm).unwrap_err();
+            assert!(err.to_string().contains("not an allowed registry"), "{realm}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_verify_digest() {
+        let digest = "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert!(verify_digest(digest, b"hello world").is_ok());
+        assert!(verify_digest(&digest.to_uppercase().replace("SHA256", "sha256"), b"hello world").is_ok());
+        assert!(verify_digest(digest, b"hello world!").is_err());
+        assert!(verify_digest("md5:5eb63bbbe01eeed093cb22bb8f5acdc3", b"hello world").is_err());
+        assert!(verify_digest("b94d27b9934d3e08", b"hello world").is_err());
+    }
+}
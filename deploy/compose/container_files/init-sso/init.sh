@@ -68,7 +68,7 @@ for i in read:document; do
 kcadm create client-scopes -r "${REALM}" -s "name=$i" -s protocol=openid-connect || true
 done
 
-for i in create:document delete:document; do
+for i in create:document delete:document admin:document manage:webhook manage:product; do
 kcadm create client-scopes -r "${REALM}" -s "name=$i" -s protocol=openid-connect || true
 ID=$(kcadm get client-scopes -r "${REALM}" --fields id,name --format csv --noquotes | grep ",${i}" | awk -F ',' '{print $1}')
 # add all scopes to the chicken-manager
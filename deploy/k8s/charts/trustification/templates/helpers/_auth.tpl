@@ -106,6 +106,9 @@ authentication:
           - "update.vex"
           - "delete.sbom"
           - "delete.vex"
+          - "admin.sbom"
+          - "manage.webhook"
+          - "manage.product"
 
     - clientId: {{ include "trustification.oidc.clientId" (dict "root" .root "clientId" "walker" ) }}
       issuerUrl: {{ include "trustification.oidc.issuerUrlForClient" (dict "root" .root "clientId" "walker" ) }}
@@ -134,6 +137,9 @@ authentication:
         "read:document": [ "read.sbom", "read.vex" ]
         "update:document": [ "update.sbom", "update.vex" ]
         "delete:document": [ "delete.sbom", "delete.vex" ]
+        "admin:document": [ "admin.sbom" ]
+        "manage:webhook": [ "manage.webhook" ]
+        "manage:product": [ "manage.product" ]
     - clientId: {{ include "trustification.oidc.clientId" (dict "root" .root "clientId" "walker" ) }}
       issuerUrl: {{ include "trustification.oidc.issuerUrlForClient" (dict "root" .root "clientId" "walker" ) }}
       scopeMappings: *keycloakScopeMappings
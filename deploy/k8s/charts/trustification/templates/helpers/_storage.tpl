@@ -21,6 +21,11 @@ Arguments (dict):
   value: {{ .storage.bucket | quote }}
 {{- end}}
 
+{{- with (.module.storage).compression }}
+- name: STORAGE_COMPRESSION
+  value: {{ . | quote }}
+{{- end }}
+
 {{- end }}
 
 {{- define "_trustification.storage.envVars" -}}
@@ -20,6 +20,14 @@ pub async fn run<M: Emitter + Send + Sync>(
                                 if data.event_type() == EventType::Put {
                                     if storage.is_index(data.key()) {
                                         log::trace!("It's an index event, ignoring");
+                                    } else if storage.is_labels(data.key())
+                                        || storage.is_attestations(data.key())
+                                        || storage.is_revisions(data.key())
+                                        || storage.is_status(data.key())
+                                        || storage.is_uploads(data.key())
+                                        || storage.is_digests(data.key())
+                                    {
+                                        log::trace!("It's a metadata event, ignoring");
                                     } else {
                                         match storage.get_for_event(&data, false).await {
                                             Ok(res) => {
@@ -0,0 +1,48 @@
+use crate::MAX_FACET_BUCKETS;
+use std::time::Duration;
+
+/// An aggregation over the documents matching a query, see [`crate::IndexStore::aggregate`].
+///
+/// Aggregations are computed from fast fields, which indexes opt into by exposing them through
+/// [`crate::Index::facet_field`], [`crate::Index::histogram_field`] and [`crate::Index::stats_field`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Count the documents of each value of a facet, limited to the most frequent values.
+    Terms { facet: String, size: u32 },
+    /// Count the documents of each interval of a date field, optionally split by the values of a facet.
+    ///
+    /// Intervals start at the epoch, shifted by the offset.
+    DateHistogram {
+        field: String,
+        interval: Duration,
+        offset: Duration,
+        facet: Option<String>,
+    },
+    /// Compute the count, sum, minimum, maximum and average of the values of a numeric field.
+    Stats { field: String },
+}
+
+impl Aggregation {
+    /// Count the documents of each value of a facet, using the maximum number of buckets.
+    pub fn terms(facet: impl Into<String>) -> Self {
+        Self::Terms {
+            facet: facet.into(),
+            size: MAX_FACET_BUCKETS,
+        }
+    }
+
+    /// Count the documents of each interval of a date field, with intervals starting at the epoch.
+    pub fn date_histogram(field: impl Into<String>, interval: Duration) -> Self {
+        Self::DateHistogram {
+            field: field.into(),
+            interval,
+            offset: Duration::ZERO,
+            facet: None,
+        }
+    }
+
+    /// Compute the statistics of a numeric field.
+    pub fn stats(field: impl Into<String>) -> Self {
+        Self::Stats { field: field.into() }
+    }
+}
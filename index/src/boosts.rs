@@ -0,0 +1,163 @@
+//! Configurable relevance boosting.
+//!
+//! Indexes weight the matches of some qualifiers over others, like matches of the `package` of an SBOM over matches
+//! of its `description`. Those default weights can be overridden by configured boosts, like `package=3`, see
+//! [`crate::IndexConfig::boosts`]. Boosts are applied while a query is prepared, through the [`QueryContext`] of the
+//! index, see [`boost_query`].
+//!
+//! Indexes ranking recent documents higher, like advisories, weight them using the configured [`Recency`], see
+//! [`QueryContext::recency`].
+
+use crate::QueryContext;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+use tantivy::query::{BoostQuery, Query};
+
+/// The boost of the matches of a qualifier, configured as `<qualifier>=<boost>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldBoost {
+    pub field: String,
+    pub boost: f32,
+}
+
+impl FromStr for FieldBoost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, boost) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <field>=<boost>, got '{s}'"))?;
+        let field = field.trim();
+        if field.is_empty() {
+            return Err(format!("missing field name in '{s}'"));
+        }
+        let boost = boost
+            .trim()
+            .parse::<f32>()
+            .map_err(|err| format!("invalid boost in '{s}': {err}"))?;
+        if !boost.is_finite() || boost < 0.0 {
+            return Err(format!("boost must not be negative in '{s}'"));
+        }
+        Ok(Self {
+            field: field.to_string(),
+            boost,
+        })
+    }
+}
+
+impl Display for FieldBoost {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.field, self.boost)
+    }
+}
+
+/// The boost of documents dated within a window before now.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Recency {
+    pub window: Duration,
+    /// Factor scores of recent documents get multiplied by, `1` disables boosting them
+    pub boost: f32,
+}
+
+impl Default for Recency {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(30 * 24 * 3600),
+            boost: 4.0,
+        }
+    }
+}
+
+/// The boosts of an index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Boosts {
+    /// Boosts by qualifier
+    fields: HashMap<String, f32>,
+    recency: Recency,
+}
+
+impl Boosts {
+    pub fn new(fields: &[FieldBoost], recency: Recency) -> Self {
+        Self {
+            fields: fields.iter().map(|boost| (boost.field.clone(), boost.boost)).collect(),
+            recency,
+        }
+    }
+
+    /// The boost of a qualifier, or the default weight of the index if none is configured.
+    pub fn get(&self, field: &str, default: f32) -> f32 {
+        self.fields.get(field).copied().unwrap_or(default)
+    }
+
+    pub fn recency(&self) -> Recency {
+        self.recency
+    }
+}
+
+/// Boost the query of a qualifier, by the boost of the context or else by the default weight.
+pub fn boost_query(field: &str, default: f32, query: Box<dyn Query>, context: &QueryContext) -> Box<dyn Query> {
+    let boost = context.boost(field, default);
+    if boost == 1.0 {
+        query
+    } else {
+        Box::new(BoostQuery::new(query, boost))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use tantivy::query::AllQuery;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "package=2.5".parse::<FieldBoost>(),
+            Ok(FieldBoost {
+                field: "package".to_string(),
+                boost: 2.5
+            })
+        );
+        assert!("package".parse::<FieldBoost>().is_err());
+        assert!("=2".parse::<FieldBoost>().is_err());
+        assert!("package=high".parse::<FieldBoost>().is_err());
+        assert!("package=-1".parse::<FieldBoost>().is_err());
+    }
+
+    #[test]
+    fn test_context() {
+        let boosts = Arc::new(Boosts::new(
+            &["title=3".parse().unwrap(), "description=1".parse().unwrap()],
+            Recency {
+                window: Duration::from_secs(3600),
+                boost: 2.0,
+            },
+        ));
+        let boost = |field, default, context: &QueryContext| {
+            format!("{:?}", boost_query(field, default, Box::new(AllQuery), context))
+        };
+
+        let context = QueryContext::default();
+        assert_eq!(
+            boost("title", 1.5, &context),
+            format!("{:?}", BoostQuery::new(Box::new(AllQuery), 1.5))
+        );
+        assert_eq!(context.recency(), Recency::default());
+
+        let context = QueryContext::default().with_boosts(Some(boosts));
+        assert_eq!(
+            boost("title", 1.5, &context),
+            format!("{:?}", BoostQuery::new(Box::new(AllQuery), 3.0))
+        );
+        // a boost of 1 leaves the query as it is
+        assert_eq!(boost("description", 1.2, &context), format!("{:?}", AllQuery));
+        assert_eq!(
+            boost("id", 1.4, &context),
+            format!("{:?}", BoostQuery::new(Box::new(AllQuery), 1.4))
+        );
+        assert_eq!(context.recency().boost, 2.0);
+    }
+}
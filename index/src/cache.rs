@@ -0,0 +1,133 @@
+//! Caching of search results.
+//!
+//! Identical searches, like the ones of the dashboard issued by every user, can be answered from a cache of the most
+//! recently used results, see [`crate::IndexConfig::search_cache_size`]. Results are cached along with the generation
+//! of the index they were found in, being the fingerprint of its segments. Once the index changes, like when new
+//! documents got committed or a newer snapshot got synced, its generation changes as well, invalidating all cached
+//! results.
+
+use parking_lot::Mutex;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use trustification_api::search::SearchOptions;
+
+/// The parameters of a search, which identical searches share.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct SearchKey {
+    pub(crate) q: String,
+    pub(crate) offset: usize,
+    pub(crate) limit: usize,
+    pub(crate) options: SearchOptions,
+}
+
+/// A cache of the most recently used search results of an index.
+///
+/// Results are stored type erased, as the type of the documents depends on the index, which is the same for all
+/// results of a cache.
+pub(crate) struct SearchCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// The generation of the index all entries are of
+    generation: u64,
+    /// Incremented on every use of an entry, for finding the least recently used one
+    tick: u64,
+    entries: HashMap<SearchKey, (u64, Arc<dyn Any + Send + Sync>)>,
+}
+
+impl Entries {
+    /// Switch to a generation of the index, dropping the entries of any other one.
+    fn switch(&mut self, generation: u64) {
+        if self.generation != generation {
+            self.generation = generation;
+            self.entries.clear();
+        }
+    }
+}
+
+impl SearchCache {
+    /// Create a cache of up to `capacity` results, `None` if the capacity is zero.
+    pub(crate) fn new(capacity: usize) -> Option<Self> {
+        (capacity > 0).then(|| Self {
+            capacity,
+            entries: Default::default(),
+        })
+    }
+
+    /// The cached result of a search, if it was searched in the same generation of the index.
+    pub(crate) fn get<T: Clone + 'static>(&self, key: &SearchKey, generation: u64) -> Option<T> {
+        let mut entries = self.entries.lock();
+        entries.switch(generation);
+        entries.tick += 1;
+        let tick = entries.tick;
+        let (used, value) = entries.entries.get_mut(key)?;
+        *used = tick;
+        value.downcast_ref::<T>().cloned()
+    }
+
+    /// Cache the result of a search, evicting the least recently used result if the cache is full.
+    pub(crate) fn insert<T: Send + Sync + 'static>(&self, key: SearchKey, generation: u64, value: T) {
+        let mut entries = self.entries.lock();
+        entries.switch(generation);
+        if entries.entries.len() >= self.capacity && !entries.entries.contains_key(&key) {
+            // a linear scan is fine for the small number of distinct searches being cached
+            let lru = entries
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(lru) = lru {
+                entries.entries.remove(&lru);
+            }
+        }
+        entries.tick += 1;
+        let tick = entries.tick;
+        entries.entries.insert(key, (tick, Arc::new(value)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(q: &str) -> SearchKey {
+        SearchKey {
+            q: q.to_string(),
+            offset: 0,
+            limit: 10,
+            options: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_lru() {
+        let cache = SearchCache::new(2).unwrap();
+        cache.insert(key("a"), 1, 1);
+        cache.insert(key("b"), 1, 2);
+        assert_eq!(cache.get::<i32>(&key("a"), 1), Some(1));
+        // evicts "b", as "a" got used more recently
+        cache.insert(key("c"), 1, 3);
+        assert_eq!(cache.get::<i32>(&key("b"), 1), None);
+        assert_eq!(cache.get::<i32>(&key("a"), 1), Some(1));
+        assert_eq!(cache.get::<i32>(&key("c"), 1), Some(3));
+
+        let paged = SearchKey { offset: 10, ..key("a") };
+        assert_eq!(cache.get::<i32>(&paged, 1), None);
+
+        assert!(SearchCache::new(0).is_none());
+    }
+
+    #[test]
+    fn test_generation() {
+        let cache = SearchCache::new(10).unwrap();
+        cache.insert(key("a"), 1, 1);
+        assert_eq!(cache.get::<i32>(&key("a"), 1), Some(1));
+        // a new generation of the index invalidates all results
+        assert_eq!(cache.get::<i32>(&key("a"), 2), None);
+        assert_eq!(cache.get::<i32>(&key("a"), 1), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! Cursor based pagination.
+//!
+//! A cursor iterates the documents matching a query in the order they are stored in the index, which doesn't require
+//! scoring or keeping a heap of `offset + limit` documents. To stay valid, a cursor records a fingerprint of the
+//! searched index, and expires once the index changes.
+
+use crate::Error;
+use sha2::{Digest, Sha256};
+use tantivy::{
+    collector::{Collector, SegmentCollector},
+    DocAddress, DocId, Score, Searcher, SegmentOrdinal, SegmentReader,
+};
+
+/// The decoded form of a cursor token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Cursor {
+    /// Fingerprint of the index the cursor was created for
+    pub(crate) fingerprint: u64,
+    /// The last document returned
+    pub(crate) after: DocAddress,
+}
+
+impl Cursor {
+    /// Decode a cursor token, an empty token starts a new iteration and decodes to `None`.
+    pub(crate) fn decode(token: &str) -> Result<Option<Self>, Error> {
+        if token.is_empty() {
+            return Ok(None);
+        }
+        if token.len() != 32 || !token.is_ascii() {
+            return Err(Error::InvalidCursor);
+        }
+
+        let fingerprint = u64::from_str_radix(&token[0..16], 16).map_err(|_| Error::InvalidCursor)?;
+        let segment_ord = u32::from_str_radix(&token[16..24], 16).map_err(|_| Error::InvalidCursor)?;
+        let doc_id = u32::from_str_radix(&token[24..32], 16).map_err(|_| Error::InvalidCursor)?;
+
+        Ok(Some(Self {
+            fingerprint,
+            after: DocAddress::new(segment_ord, doc_id),
+        }))
+    }
+
+    /// Encode the cursor into an opaque token.
+    pub(crate) fn encode(&self) -> String {
+        format!(
+            "{:016x}{:08x}{:08x}",
+            self.fingerprint, self.after.segment_ord, self.after.doc_id
+        )
+    }
+}
+
+/// Fingerprint the state of the index a searcher operates on.
+pub(crate) fn fingerprint(searcher: &Searcher) -> u64 {
+    let mut digest = Sha256::new();
+    for segment in searcher.segment_readers() {
+        digest.update(segment.segment_id().uuid_string());
+        digest.update(segment.num_deleted_docs().to_be_bytes());
+    }
+    let digest = digest.finalize();
+    let mut result = [0u8; 8];
+    result.copy_from_slice(&digest[0..8]);
+    u64::from_be_bytes(result)
+}
+
+/// Collect up to `limit` documents, following the document `after`, in the order of their address.
+pub(crate) struct CursorCollector {
+    pub(crate) after: Option<DocAddress>,
+    pub(crate) limit: usize,
+}
+
+impl Collector for CursorCollector {
+    type Fruit = Vec<DocAddress>;
+    type Child = CursorSegmentCollector;
+
+    fn for_segment(&self, segment_ord: SegmentOrdinal, _segment: &SegmentReader) -> tantivy::Result<Self::Child> {
+        let (after, remaining) = match self.after {
+            // segments up to the one of the cursor were already returned
+            Some(after) if after.segment_ord > segment_ord => (None, 0),
+            Some(after) if after.segment_ord == segment_ord => (Some(after.doc_id), self.limit),
+            _ => (None, self.limit),
+        };
+        Ok(CursorSegmentCollector {
+            segment_ord,
+            after,
+            remaining,
+            docs: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Vec<DocAddress>>) -> tantivy::Result<Self::Fruit> {
+        let mut docs: Vec<_> = segment_fruits.into_iter().flatten().collect();
+        docs.sort_unstable();
+        docs.truncate(self.limit);
+        Ok(docs)
+    }
+}
+
+pub(crate) struct CursorSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    after: Option<DocId>,
+    remaining: usize,
+    docs: Vec<DocAddress>,
+}
+
+impl SegmentCollector for CursorSegmentCollector {
+    type Fruit = Vec<DocAddress>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        if self.remaining == 0 || self.after.map(|after| doc <= after).unwrap_or(false) {
+            return;
+        }
+        self.docs.push(DocAddress::new(self.segment_ord, doc));
+        self.remaining -= 1;
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.docs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor {
+            fingerprint: 0x0123_4567_89ab_cdef,
+            after: DocAddress::new(3, 42),
+        };
+        let token = cursor.encode();
+        assert_eq!(token.len(), 32);
+        assert_eq!(Cursor::decode(&token).unwrap(), Some(cursor));
+    }
+
+    #[test]
+    fn test_cursor_invalid() {
+        assert_eq!(Cursor::decode("").unwrap(), None);
+        assert!(matches!(Cursor::decode("foo"), Err(Error::InvalidCursor)));
+        assert!(matches!(
+            Cursor::decode("zz23456789abcdef000000030000002a"),
+            Err(Error::InvalidCursor)
+        ));
+    }
+}
@@ -0,0 +1,189 @@
+//! Deduplication of search hits.
+//!
+//! Hits sharing the value of a (fast) string field, like several revisions of the same advisory, can be collapsed to
+//! the one ranking highest, see [`SearchOptions::dedup_by`](trustification_api::search::SearchOptions::dedup_by).
+//! Documents without a value are never collapsed. As the collapsed hits of a page depend on all hits ranking higher,
+//! the top documents are fetched from the start, in windows of growing size, until the page is filled, see [`dedup`].
+
+use crate::Error;
+use std::collections::HashSet;
+use tantivy::{
+    collector::{Collector, SegmentCollector},
+    columnar::StrColumn,
+    DocAddress, DocId, Score, Searcher, SegmentOrdinal, SegmentReader,
+};
+
+/// Collapse ranked documents sharing a key to the first one, returning the page of `limit` documents following
+/// `offset`.
+///
+/// The function `fetch` returns the top documents up to the size of the window, in the order of their rank.
+pub(crate) fn dedup<D>(
+    offset: usize,
+    limit: usize,
+    mut fetch: impl FnMut(usize) -> Result<Vec<D>, Error>,
+    key: impl Fn(&D) -> Result<Option<String>, Error>,
+) -> Result<Vec<D>, Error> {
+    let wanted = offset + limit;
+    let mut window = wanted;
+    loop {
+        let docs = fetch(window)?;
+        let exhausted = docs.len() < window;
+
+        let mut seen = HashSet::new();
+        let mut unique = Vec::with_capacity(wanted);
+        for doc in docs {
+            match key(&doc)? {
+                Some(key) if !seen.insert(key) => {}
+                _ => unique.push(doc),
+            }
+            if unique.len() == wanted {
+                break;
+            }
+        }
+
+        if unique.len() == wanted || exhausted {
+            return Ok(unique.into_iter().skip(offset).collect());
+        }
+        window = window.saturating_mul(2);
+    }
+}
+
+/// The keys of the documents of a searcher, being the first value of a field.
+pub(crate) struct Keys {
+    /// The column of each segment, `None` if no document of the segment has a value
+    columns: Vec<Option<StrColumn>>,
+}
+
+impl Keys {
+    pub(crate) fn new(searcher: &Searcher, field: &str) -> Result<Self, Error> {
+        let columns = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.fast_fields().str(field))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+
+    /// The key of a document, or `None` if it has no value.
+    pub(crate) fn get(&self, doc: DocAddress) -> Result<Option<String>, Error> {
+        let Some(Some(column)) = self.columns.get(doc.segment_ord as usize) else {
+            return Ok(None);
+        };
+        let Some(ord) = column.term_ords(doc.doc_id).next() else {
+            return Ok(None);
+        };
+        let mut value = String::new();
+        match column.ord_to_str(ord, &mut value).map_err(Error::Io)? {
+            true => Ok(Some(value)),
+            false => Ok(None),
+        }
+    }
+}
+
+/// The distinct keys of the matching documents, along with the number of matching documents without a key.
+#[derive(Debug, Default)]
+pub(crate) struct Distinct {
+    pub(crate) keys: HashSet<String>,
+    pub(crate) without: usize,
+}
+
+impl Distinct {
+    /// The number of documents left after collapsing the documents sharing a key.
+    pub(crate) fn count(&self) -> usize {
+        self.keys.len() + self.without
+    }
+
+    pub(crate) fn merge(&mut self, other: Distinct) {
+        self.keys.extend(other.keys);
+        self.without += other.without;
+    }
+}
+
+/// Collect the distinct keys of the matching documents, see [`Keys`].
+pub(crate) struct DistinctCollector {
+    pub(crate) field: String,
+}
+
+impl Collector for DistinctCollector {
+    type Fruit = Distinct;
+    type Child = DistinctSegmentCollector;
+
+    fn for_segment(&self, _segment_ord: SegmentOrdinal, segment: &SegmentReader) -> tantivy::Result<Self::Child> {
+        Ok(DistinctSegmentCollector {
+            column: segment.fast_fields().str(&self.field)?,
+            ords: HashSet::new(),
+            without: 0,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Distinct>) -> tantivy::Result<Self::Fruit> {
+        let mut result = Distinct::default();
+        for fruit in segment_fruits {
+            result.merge(fruit);
+        }
+        Ok(result)
+    }
+}
+
+pub(crate) struct DistinctSegmentCollector {
+    column: Option<StrColumn>,
+    /// Term ordinals are specific to the segment, and resolved to their values once the segment is collected
+    ords: HashSet<u64>,
+    without: usize,
+}
+
+impl SegmentCollector for DistinctSegmentCollector {
+    type Fruit = Distinct;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        match self.column.as_ref().and_then(|column| column.term_ords(doc).next()) {
+            Some(ord) => {
+                self.ords.insert(ord);
+            }
+            None => self.without += 1,
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let mut keys = HashSet::with_capacity(self.ords.len());
+        let mut without = self.without;
+        if let Some(column) = &self.column {
+            for ord in self.ords {
+                let mut value = String::new();
+                match column.ord_to_str(ord, &mut value) {
+                    Ok(true) => {
+                        keys.insert(value);
+                    }
+                    _ => without += 1,
+                }
+            }
+        }
+        Distinct { keys, without }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dedup() {
+        let docs = ["a", "b", "a", "c", "b", "d", "", ""];
+        let key = |doc: &&str| Ok((!doc.is_empty()).then(|| doc.to_string()));
+        let mut fetched = Vec::new();
+        let mut fetch = |window: usize| {
+            fetched.push(window);
+            Ok(docs.iter().take(window).copied().collect())
+        };
+
+        assert_eq!(dedup(0, 2, &mut fetch, key).unwrap(), ["a", "b"]);
+        assert_eq!(dedup(1, 2, &mut fetch, key).unwrap(), ["b", "c"]);
+        // documents without a key are never collapsed
+        assert_eq!(dedup(2, 10, &mut fetch, key).unwrap(), ["c", "d", "", ""]);
+        assert_eq!(fetched, [2, 3, 6, 12]);
+    }
+}
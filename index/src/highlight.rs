@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+use tantivy::query::Query;
+use tantivy::schema::Field;
+use tantivy::{Document, Snippet};
+
+const PREFIX: &str = "<em>";
+const POSTFIX: &str = "</em>";
+
+/// Render a snippet as HTML, marking the matched terms with `<em>`.
+pub fn snippet2html(snippet: &Snippet) -> String {
+    highlight(snippet.fragment(), snippet.highlighted())
+}
+
+/// Highlight the terms of a query in the values of an untokenized (`STRING`) field.
+///
+/// Returns the values containing any of the terms, rendered as HTML, in the order of the document.
+pub fn highlight_values(query: &dyn Query, field: Field, doc: &Document) -> Vec<String> {
+    let mut terms = BTreeSet::new();
+    query.query_terms(&mut |term, _| {
+        if term.field() == field {
+            if let Some(text) = term.as_str().filter(|text| !text.is_empty()) {
+                terms.insert(text.to_string());
+            }
+        }
+    });
+
+    if terms.is_empty() {
+        return vec![];
+    }
+
+    doc.get_all(field)
+        .filter_map(|value| value.as_text())
+        .filter_map(|value| {
+            let mut ranges: Vec<_> = terms
+                .iter()
+                .flat_map(|term| value.match_indices(term.as_str()))
+                .map(|(start, term)| start..start + term.len())
+                .collect();
+            (!ranges.is_empty()).then(|| {
+                ranges = merge(ranges);
+                highlight(value, &ranges)
+            })
+        })
+        .collect()
+}
+
+/// sort and merge overlapping ranges
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut result: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match result.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => result.push(range),
+        }
+    }
+    result
+}
+
+/// render text as HTML, marking the (sorted, non-overlapping) ranges
+fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut html = String::with_capacity(text.len());
+    let mut start = 0;
+    for range in ranges {
+        html.push_str(&escape(&text[start..range.start]));
+        html.push_str(PREFIX);
+        html.push_str(&escape(&text[range.clone()]));
+        html.push_str(POSTFIX);
+        start = range.end;
+    }
+    html.push_str(&escape(&text[start..]));
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#x27;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tantivy::query::{BooleanQuery, TermQuery};
+    use tantivy::schema::{Schema, STRING};
+    use tantivy::{doc, Term};
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(highlight("foo bar baz", &[4..7]), "foo <em>bar</em> baz");
+        assert_eq!(highlight("<foo> & bar", &[0..5]), "<em>&lt;foo&gt;</em> &amp; bar");
+        assert_eq!(highlight("foo", &[]), "foo");
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(merge(vec![4..6, 0..2, 1..3]), vec![0..3, 4..6]);
+    }
+
+    #[test]
+    fn test_highlight_values() {
+        let mut schema = Schema::builder();
+        let purl = schema.add_text_field("purl", STRING);
+        let other = schema.add_text_field("other", STRING);
+
+        let doc = doc!(
+            purl => "pkg:rpm/redhat/openssl@3",
+            purl => "pkg:rpm/redhat/glib2@2",
+            other => "openssl"
+        );
+        let queries: Vec<Box<dyn Query>> = vec![
+            Box::new(TermQuery::new(
+                Term::from_field_text(purl, "openssl"),
+                Default::default(),
+            )),
+            Box::new(TermQuery::new(
+                Term::from_field_text(other, "glib2"),
+                Default::default(),
+            )),
+        ];
+        let query = BooleanQuery::union(queries);
+
+        assert_eq!(
+            highlight_values(&query, purl, &doc),
+            vec!["pkg:rpm/redhat/<em>openssl</em>@3".to_string()]
+        );
+        assert!(highlight_values(&query, other, &doc).is_empty());
+    }
+}
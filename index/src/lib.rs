@@ -3,23 +3,42 @@
 //! This crate provides a wrapper around the tantivy index for the trustification project.
 //!
 
+pub mod boosts;
+pub mod highlight;
 pub mod metadata;
+pub mod patterns;
+pub mod ranges;
+pub mod synonyms;
+pub mod tokenizer;
 
+pub use aggregation::*;
+pub use shard::*;
 pub use sort::*;
 
+mod aggregation;
+mod cache;
+mod cursor;
+mod dedup;
 mod s3dir;
+mod shard;
 mod sort;
+mod timeout;
 
 // Re-export to align versions
 pub use tantivy;
 pub use tantivy::schema::Document;
+pub use trustification_storage::Labels;
 
+use boosts::{Boosts, Recency};
 use bytesize::ByteSize;
-use parking_lot::RwLock;
+use cache::{SearchCache, SearchKey};
+use parking_lot::{Mutex, RwLock};
+use patterns::{create_pattern_query, pattern_value};
 use prometheus::{
     histogram_opts, opts, register_histogram_with_registry, register_int_counter_with_registry,
     register_int_gauge_with_registry, Histogram, IntCounter, IntGauge, Registry,
 };
+use ranges::rewrite_ranges;
 use s3dir::S3Directory;
 use sha2::{Digest, Sha256};
 use sikula::{
@@ -28,13 +47,19 @@ use sikula::{
 };
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     fmt::{Debug, Display},
     ops::Bound,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
+use synonyms::Synonyms;
 use tantivy::{
+    aggregation::{
+        agg_req::Aggregations, intermediate_agg_result::IntermediateAggregationResults, AggregationCollector,
+        DistributedAggregationCollector,
+    },
     collector::TopDocs,
     directory::{MmapDirectory, INDEX_WRITER_LOCK},
     query::{AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, RangeQuery, RegexQuery, TermQuery},
@@ -44,9 +69,15 @@ use tantivy::{
 };
 use time::{OffsetDateTime, UtcOffset};
 use tokio::{spawn, sync::oneshot};
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{
+    AggregationResult, FacetCount, FacetPivot, Facets, HistogramBucket, HistogramParams, NumericStats, SearchField,
+    SearchFieldType, SearchOptions,
+};
 use trustification_storage::{Storage, StorageConfig};
 
+/// Maximum number of buckets returned for a single facet.
+const MAX_FACET_BUCKETS: u32 = 100;
+
 /// Configuration for the index.
 #[derive(Clone, Debug, clap::Parser)]
 #[command(rename_all_env = "SCREAMING_SNAKE_CASE", next_help_heading = "Index")]
@@ -66,6 +97,45 @@ pub struct IndexConfig {
     /// Synchronization interval for index persistence.
     #[arg(env = "INDEX_MODE", long = "index-mode", default_value_t = IndexMode::File)]
     pub mode: IndexMode,
+
+    /// File of synonyms, which exact matches of queries get expanded by, like `k8s, kubernetes` on a line.
+    #[arg(env = "INDEX_SYNONYMS", long = "index-synonyms")]
+    pub synonyms: Option<std::path::PathBuf>,
+
+    /// Tokenizers of text fields, overriding the default ones of the indexes, like `sbom_pkg_name=ngram`.
+    #[arg(env = "INDEX_TOKENIZERS", long = "index-tokenizer", value_delimiter = ',')]
+    pub tokenizers: Vec<tokenizer::FieldTokenizer>,
+
+    /// Boosts of the matches of qualifiers, overriding the default weights of the indexes, like `package=3`.
+    #[arg(env = "INDEX_BOOSTS", long = "index-boost", value_delimiter = ',')]
+    pub boosts: Vec<boosts::FieldBoost>,
+
+    /// Period before now in which documents are boosted as recent, by indexes ranking recent documents higher.
+    #[arg(env = "INDEX_RECENCY_WINDOW", long = "index-recency-window", default_value = "30d")]
+    pub recency_window: humantime::Duration,
+
+    /// Factor the scores of recent documents get multiplied by, `1` disables boosting them.
+    #[arg(env = "INDEX_RECENCY_BOOST", long = "index-recency-boost", default_value_t = 4.0)]
+    pub recency_boost: f32,
+
+    /// Number of shards splitting the index by the hash of the document identifiers. Only the v11y index supports
+    /// sharding, the other services fail to start with more than one shard.
+    ///
+    /// Changing it requires a reindex.
+    #[arg(env = "INDEX_SHARDS", long = "index-shards", default_value_t = 1)]
+    pub shards: usize,
+
+    /// Time budget of a query, after which its search gets aborted. `0s` disables aborting queries.
+    #[arg(env = "INDEX_QUERY_TIMEOUT", long = "index-query-timeout", default_value = "30s")]
+    pub query_timeout: humantime::Duration,
+
+    /// Number of the most recently used search results to cache, until the index changes. `0` disables caching.
+    #[arg(
+        env = "INDEX_SEARCH_CACHE_SIZE",
+        long = "index-search-cache-size",
+        default_value_t = 0
+    )]
+    pub search_cache_size: usize,
 }
 
 #[derive(Clone, Debug, clap::ValueEnum)]
@@ -95,7 +165,12 @@ struct Metrics {
     failed_total: IntCounter,
     snapshots_total: IntCounter,
     queries_total: IntCounter,
+    search_cache_hits_total: IntCounter,
     index_size_disk_bytes: IntGauge,
+    segments_size_bytes: IntGauge,
+    segments: IntGauge,
+    last_commit_timestamp_seconds: IntGauge,
+    last_sync_timestamp_seconds: IntGauge,
     indexing_latency_seconds: Histogram,
     query_latency_seconds: Histogram,
     documents: IntGauge,
@@ -130,6 +205,14 @@ impl Metrics {
             registry
         )?;
 
+        let search_cache_hits_total = register_int_counter_with_registry!(
+            opts!(
+                format!("{}_index_search_cache_hits_total", prefix),
+                "Total number of search queries answered from the cache"
+            ),
+            registry
+        )?;
+
         let snapshots_total = register_int_counter_with_registry!(
             opts!(
                 format!("{}_index_snapshots_total", prefix),
@@ -146,6 +229,35 @@ impl Metrics {
             registry
         )?;
 
+        let segments_size_bytes = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_index_segments_size_bytes", prefix),
+                "Amount of bytes consumed by the searchable segments of the index"
+            ),
+            registry
+        )?;
+
+        let segments = register_int_gauge_with_registry!(
+            opts!(format!("{}_index_segments", prefix), "Number of segments of the index"),
+            registry
+        )?;
+
+        let last_commit_timestamp_seconds = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_index_last_commit_timestamp_seconds", prefix),
+                "Time of the last commit to the index, in seconds since the epoch"
+            ),
+            registry
+        )?;
+
+        let last_sync_timestamp_seconds = register_int_gauge_with_registry!(
+            opts!(
+                format!("{}_index_last_sync_timestamp_seconds", prefix),
+                "Time of the last successful sync of the index from object storage, in seconds since the epoch"
+            ),
+            registry
+        )?;
+
         let indexing_latency_seconds = register_histogram_with_registry!(
             histogram_opts!(
                 format!("{}_index_indexing_latency_seconds", prefix),
@@ -194,7 +306,12 @@ impl Metrics {
             failed_total,
             snapshots_total,
             queries_total,
+            search_cache_hits_total,
             index_size_disk_bytes,
+            segments_size_bytes,
+            segments,
+            last_commit_timestamp_seconds,
+            last_sync_timestamp_seconds,
             indexing_latency_seconds,
             query_latency_seconds,
             documents,
@@ -202,6 +319,34 @@ impl Metrics {
             count_latency_seconds,
         })
     }
+
+    /// Record the statistics of the index, returning `false` if it can't be read.
+    fn record_stats(&self, inner: &RwLock<SearchIndex>) -> bool {
+        let Ok(reader) = inner.read().reader() else {
+            return false;
+        };
+        let searcher = reader.searcher();
+        let documents = searcher.num_docs();
+        let size = match searcher.space_usage() {
+            Ok(usage) => usage.total().get_bytes(),
+            Err(e) => {
+                log::debug!("Unable to compute the space usage of the index: {e}");
+                return false;
+            }
+        };
+
+        log::debug!("Counted {documents} documents in the index");
+
+        self.documents.set(documents.clamp(0, i64::MAX as _) as _);
+        self.segments.set(searcher.segment_readers().len() as _);
+        self.segments_size_bytes.set(size.clamp(0, i64::MAX as _) as _);
+        true
+    }
+}
+
+/// The current time, in seconds since the epoch.
+fn now_timestamp() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
 }
 
 /// A search index. This is a wrapper around the tantivy index that handles loading and storing of the index to object storage (via the local filesystem).
@@ -213,6 +358,10 @@ pub struct IndexStore<INDEX> {
     index: INDEX,
     index_writer_memory_bytes: usize,
     metrics: Metrics,
+    synonyms: Option<Arc<Synonyms>>,
+    boosts: Option<Arc<Boosts>>,
+    query_timeout: Option<Duration>,
+    cache: Option<SearchCache>,
 
     /// the handle running the counter for the metrics. We need to hold on to this handle.
     shutdown_counter: Option<oneshot::Sender<()>>,
@@ -248,10 +397,23 @@ impl<DOC> WriteIndex for Box<dyn WriteIndex<Document = DOC>> {
         self.as_ref().index_doc(id, document)
     }
 
+    fn index_labeled_doc(
+        &self,
+        id: &str,
+        document: &Self::Document,
+        labels: &Labels,
+    ) -> Result<Vec<(String, Document)>, Error> {
+        self.as_ref().index_labeled_doc(id, document, labels)
+    }
+
     fn doc_id_to_term(&self, id: &str) -> Term {
         self.as_ref().doc_id_to_term(id)
     }
 
+    fn contains(&self, id: &str) -> bool {
+        self.as_ref().contains(id)
+    }
+
     fn tokenizers(&self) -> Result<TokenizerManager, Error> {
         self.as_ref().tokenizers()
     }
@@ -265,7 +427,7 @@ pub trait WriteIndex {
     fn name(&self) -> &str;
     /// Tokenizers used by the index.
     fn tokenizers(&self) -> Result<TokenizerManager, Error> {
-        Ok(TokenizerManager::default())
+        Ok(tokenizer::tokenizer_manager())
     }
     /// Parse a document from a byte slice.
     fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, Error>;
@@ -275,24 +437,40 @@ pub trait WriteIndex {
     fn schema(&self) -> Schema;
     /// Process an input document and return a tantivy document to be added to the index.
     fn index_doc(&self, id: &str, document: &Self::Document) -> Result<Vec<(String, Document)>, Error>;
+    /// Process an input document, along with the labels assigned to it. Indexes not supporting labels ignore them.
+    fn index_labeled_doc(
+        &self,
+        id: &str,
+        document: &Self::Document,
+        labels: &Labels,
+    ) -> Result<Vec<(String, Document)>, Error> {
+        let _ = labels;
+        self.index_doc(id, document)
+    }
     /// Convert a document id to a term for referencing that document.
     fn doc_id_to_term(&self, id: &str) -> Term;
+    /// Whether the index holds the document of an identifier. Documents held by other indexes, like the other shards of
+    /// an index, are neither parsed nor indexed.
+    fn contains(&self, _id: &str) -> bool {
+        true
+    }
 }
 
 /// Defines the interface for an index that can be searched.
 pub trait Index: WriteIndex {
     /// Type of the matched document returned from a search.
-    type MatchedDocument: core::fmt::Debug;
+    type MatchedDocument: core::fmt::Debug + Clone + Send + Sync + 'static;
 
-    /// Prepare a query for searching and return a query object.
-    fn prepare_query(&self, q: &str) -> Result<SearchQuery, Error>;
-    /// Search the index for a query and return a list of matched documents.
+    /// Prepare a query for searching and return a query object, see [`QueryContext`].
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, Error>;
+    /// Search the index for a query and return a list of matched documents, ranking them by the boosts of the context.
     fn search(
         &self,
         searcher: &Searcher,
         query: &dyn Query,
         offset: usize,
         limit: usize,
+        context: &QueryContext,
     ) -> Result<(Vec<(f32, DocAddress)>, usize), Error>;
     /// Invoked for every matched document to process the document and return a result.
     fn process_hit(
@@ -303,6 +481,36 @@ pub trait Index: WriteIndex {
         query: &dyn Query,
         options: &SearchOptions,
     ) -> Result<Self::MatchedDocument, Error>;
+    /// Lookup the (fast) field backing a facet, returns `None` if the facet is not supported.
+    fn facet_field(&self, _name: &str) -> Option<Field> {
+        None
+    }
+    /// Lookup the (fast) field to sort by, returns `None` if the field cannot be sorted.
+    fn sort_field(&self, _name: &str) -> Option<Field> {
+        None
+    }
+    /// Lookup the (fast) date field backing a histogram, returns `None` if the histogram is not supported.
+    fn histogram_field(&self, _name: &str) -> Option<Field> {
+        None
+    }
+    /// Lookup the (fast) numeric field backing statistics, returns `None` if statistics are not supported.
+    fn stats_field(&self, _name: &str) -> Option<Field> {
+        None
+    }
+    /// Lookup the (fast) string field to deduplicate hits by, returns `None` if hits cannot be deduplicated by it.
+    fn dedup_field(&self, _name: &str) -> Option<Field> {
+        None
+    }
+    /// The names of the qualifiers and predicates of the query language, for clients to discover the query syntax.
+    ///
+    /// These are the variant names of the [`sikula::prelude::Search`] type of the index, see [`Self::qualifier_field`].
+    fn qualifiers(&self) -> &[&'static str] {
+        &[]
+    }
+    /// Describe the field searched by a qualifier, returns `None` for predicates.
+    fn qualifier_field(&self, _name: &str) -> Option<SearchField> {
+        None
+    }
 }
 
 /// Errors returned by the index.
@@ -316,6 +524,14 @@ pub enum Error {
     FieldNotFound(String),
     #[error("field {0} cannot be sorted")]
     NotSortable(String),
+    #[error("field {0} cannot be used as facet")]
+    NotFacetable(String),
+    #[error("field {0} cannot be used for histograms")]
+    NotHistogrammable(String),
+    #[error("field {0} cannot be used for statistics")]
+    NoStatistics(String),
+    #[error("field {0} cannot be used for deduplication")]
+    NotDeduplicable(String),
     #[error("operation cannot be done because index is not persisted")]
     NotPersisted,
     #[error("error parsing document {0}")]
@@ -326,12 +542,22 @@ pub enum Error {
     Storage(trustification_storage::Error),
     #[error("invalid limit parameter {0}")]
     InvalidLimitParameter(usize),
+    #[error("invalid cursor")]
+    InvalidCursor,
+    #[error("cursor expired, the index changed since it was created")]
+    CursorExpired,
     #[error("error from search {0}")]
     Search(tantivy::TantivyError),
     #[error("error configuring metrics {0}")]
     Prometheus(prometheus::Error),
     #[error("I/O error {0}")]
     Io(std::io::Error),
+    #[error("error loading synonyms {0}")]
+    Synonyms(String),
+    #[error("query exceeded its time budget of {0:?}")]
+    Timeout(Duration),
+    #[error("invalid interval {0}, expected a non-zero duration like 7d")]
+    InvalidInterval(String),
 }
 
 impl From<prometheus::Error> for Error {
@@ -361,6 +587,108 @@ pub struct SearchQuery {
     pub sort_by: Option<(Field, Order)>,
 }
 
+/// The configuration of an index which queries are prepared with, like the synonyms exact matches get expanded by,
+/// and the boosts overriding the default weights of the index.
+///
+/// The context also collects the patterns rejected while preparing a query, see [`patterns`].
+#[derive(Debug, Default)]
+pub struct QueryContext {
+    synonyms: Option<Arc<Synonyms>>,
+    boosts: Option<Arc<Boosts>>,
+    /// The deadline of the query, if its search times out
+    deadline: Option<Arc<timeout::Deadline>>,
+    rejected: Mutex<Vec<String>>,
+}
+
+impl QueryContext {
+    /// Expand exact matches by synonyms.
+    pub fn with_synonyms(mut self, synonyms: Option<Arc<Synonyms>>) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// The synonyms of a value, without the value itself.
+    pub fn synonyms_of(&self, value: &str) -> &[String] {
+        match &self.synonyms {
+            Some(synonyms) => synonyms.get(value),
+            None => &[],
+        }
+    }
+
+    /// Override the default weights by boosts.
+    pub fn with_boosts(mut self, boosts: Option<Arc<Boosts>>) -> Self {
+        self.boosts = boosts;
+        self
+    }
+
+    /// The boost of a qualifier, or the default weight of the index if none is configured.
+    pub fn boost(&self, field: &str, default: f32) -> f32 {
+        match &self.boosts {
+            Some(boosts) => boosts.get(field, default),
+            None => default,
+        }
+    }
+
+    /// The boost of recent documents.
+    pub fn recency(&self) -> Recency {
+        self.boosts.as_ref().map(|boosts| boosts.recency()).unwrap_or_default()
+    }
+
+    /// Stop matching patterns against the terms of fields once the deadline of the query passed, see [`timeout`].
+    pub(crate) fn with_deadline(mut self, deadline: Option<Arc<timeout::Deadline>>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub(crate) fn deadline(&self) -> Option<&Arc<timeout::Deadline>> {
+        self.deadline.as_ref()
+    }
+
+    /// Reject the query being prepared, for the reason.
+    pub(crate) fn reject(&self, reason: String) {
+        self.rejected.lock().push(reason);
+    }
+
+    /// Fail if any part of the query got rejected while preparing it.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match &*self.rejected.lock() {
+            rejected if rejected.is_empty() => Ok(()),
+            rejected => Err(Error::QueryParser(rejected.join(", "))),
+        }
+    }
+}
+
+/// The rank of a matching document, by its score or by the value it is sorted by, for merging the top documents of
+/// several shards.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+enum Rank {
+    Score(f32),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Date(DateTime),
+    Str(String),
+}
+
+impl Rank {
+    /// The score of the document, documents sorted by a value all score the same.
+    fn score(&self) -> f32 {
+        match self {
+            Self::Score(score) => *score,
+            _ => 1.0,
+        }
+    }
+}
+
+/// A new generation of an index, which gets built while the current generation keeps being used, see
+/// [`IndexStore::new_generation`].
+pub struct IndexGeneration {
+    inner: SearchIndex,
+    /// the state of the index directory holding the generation, if the index is persisted
+    state: Option<IndexState>,
+}
+
 /// A writer for an index that allows batching document writes before committing a batch.
 ///
 /// Batching document writes can improve performance by reducing the number of commits to the index.
@@ -377,7 +705,21 @@ impl IndexWriter {
         id: &str,
         data: &[u8],
     ) -> Result<(), Error> {
-        self.add_document_with_id(index, data, id, |_| id.to_string())
+        self.add_labeled_document(index, id, data, &Labels::default())
+    }
+
+    /// Add a document and the labels assigned to it to the batch.
+    pub fn add_labeled_document<DOC>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        id: &str,
+        data: &[u8],
+        labels: &Labels,
+    ) -> Result<(), Error> {
+        if !index.contains(id) {
+            return Ok(());
+        }
+        self.add(index, data, id, |_| id.to_string(), labels)
     }
 
     /// Add a document with a given identifier to the batch.
@@ -388,6 +730,20 @@ impl IndexWriter {
         name: &str,
         id: F,
     ) -> Result<(), Error>
+    where
+        F: FnOnce(&DOC) -> String,
+    {
+        self.add(index, data, name, id, &Labels::default())
+    }
+
+    fn add<DOC, F>(
+        &mut self,
+        index: &dyn WriteIndex<Document = DOC>,
+        data: &[u8],
+        name: &str,
+        id: F,
+        labels: &Labels,
+    ) -> Result<(), Error>
     where
         F: FnOnce(&DOC) -> String,
     {
@@ -395,7 +751,7 @@ impl IndexWriter {
         match index.parse_doc(data) {
             Ok(doc) => {
                 let id = &id(&doc);
-                let docs = index.index_doc(id, &doc).map_err(|e| {
+                let docs = index.index_labeled_doc(id, &doc, labels).map_err(|e| {
                     self.metrics.failed_total.inc();
                     e
                 })?;
@@ -422,6 +778,7 @@ impl IndexWriter {
     /// Commit the batch and consume the writer. May merge index segments.
     pub fn commit(mut self) -> Result<(), Error> {
         self.writer.commit()?;
+        self.metrics.last_commit_timestamp_seconds.set(now_timestamp());
         self.writer.wait_merging_threads()?;
         Ok(())
     }
@@ -452,17 +809,28 @@ impl IndexDirectory {
     ) -> Result<Option<SearchIndex>, Error> {
         let digest = Sha256::digest(data).to_vec();
         if self.digest != digest {
-            let next = self.state.next();
-            let path = next.directory(&self.path);
-            let index = self.unpack(schema, settings, tokenizers, data, &path)?;
-            self.state = next;
-            self.digest = digest;
-            Ok(Some(index))
+            Ok(Some(self.restore(schema, settings, tokenizers, data)?))
         } else {
             Ok(None)
         }
     }
 
+    /// Build a new index from the serialized zstd data, even if the same data was synced before
+    pub fn restore(
+        &mut self,
+        schema: Schema,
+        settings: IndexSettings,
+        tokenizers: TokenizerManager,
+        data: &[u8],
+    ) -> Result<SearchIndex, Error> {
+        let next = self.state.next();
+        let path = next.directory(&self.path);
+        let index = self.unpack(schema, settings, tokenizers, data, &path)?;
+        self.state = next;
+        self.digest = Sha256::digest(data).to_vec();
+        Ok(index)
+    }
+
     pub fn new(path: &PathBuf) -> Result<IndexDirectory, Error> {
         if path.exists() {
             std::fs::remove_dir_all(path).map_err(|e| Error::Open(e.to_string()))?;
@@ -482,6 +850,18 @@ impl IndexDirectory {
         schema: Schema,
         tokenizers: TokenizerManager,
     ) -> Result<SearchIndex, Error> {
+        let (index, next) = self.prepare(settings, schema, tokenizers)?;
+        self.state = next;
+        Ok(index)
+    }
+
+    /// Create a new, empty index in the directory of the next state, without switching to it
+    fn prepare(
+        &self,
+        settings: IndexSettings,
+        schema: Schema,
+        tokenizers: TokenizerManager,
+    ) -> Result<(SearchIndex, IndexState), Error> {
         let next = self.state.next();
         let path = next.directory(&self.path);
         if path.exists() {
@@ -489,8 +869,7 @@ impl IndexDirectory {
         }
         std::fs::create_dir_all(&path).map_err(|e| Error::Open(e.to_string()))?;
         let index = self.build_new(settings, schema, tokenizers, &path)?;
-        self.state = next;
-        Ok(index)
+        Ok((index, next))
     }
 
     fn build_new(
@@ -601,17 +980,40 @@ where
             index_writer_memory_bytes: 32 * 1024 * 1024,
             index_dir: None,
             metrics: Metrics::register(&Default::default(), &name)?,
+            synonyms: None,
+            boosts: None,
+            query_timeout: None,
+            cache: None,
             shutdown_counter: None,
         })
     }
 
-    /// runs an internal loop, counting documents and syncing that to the metrics
-    async fn run_index_count(inner: Arc<RwLock<SearchIndex>>, metrics: Metrics, mut shutdown: oneshot::Receiver<()>) {
-        fn count(inner: &RwLock<SearchIndex>) -> Option<u64> {
-            let count = inner.read().reader().ok()?;
-            Some(count.searcher().num_docs())
-        }
+    /// Expand exact matches of queries by their synonyms.
+    pub fn with_synonyms(mut self, synonyms: Synonyms) -> Self {
+        self.synonyms = (!synonyms.is_empty()).then(|| Arc::new(synonyms));
+        self
+    }
+
+    /// Override the default weights of the index by boosts.
+    pub fn with_boosts(mut self, boosts: Boosts) -> Self {
+        self.boosts = Some(Arc::new(boosts));
+        self
+    }
+
+    /// Abort the searches of queries taking longer than the timeout.
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
+    /// Cache the results of the most recently used searches, up to the capacity.
+    pub fn with_search_cache(mut self, capacity: usize) -> Self {
+        self.cache = SearchCache::new(capacity);
+        self
+    }
 
+    /// runs an internal loop, counting documents, segments and their size and syncing that to the metrics
+    async fn run_index_count(inner: Arc<RwLock<SearchIndex>>, metrics: Metrics, mut shutdown: oneshot::Receiver<()>) {
         log::info!("Starting index counter");
 
         let mut interval = tokio::time::interval(Duration::from_secs(60));
@@ -619,16 +1021,13 @@ where
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                     let count = {
+                    let recorded = {
                         let _timer = metrics.count_latency_seconds.start_timer();
-                        count(&inner)
-                     };
+                        metrics.record_stats(&inner)
+                    };
 
-                    log::debug!("Counted {count:?} documents in the index");
-
-                    match count {
-                        Some(count) => metrics.documents.set(count.clamp(0, i64::MAX as _) as _),
-                        None => metrics.count_errors.inc(),
+                    if !recorded {
+                        metrics.count_errors.inc();
                     }
                 },
                 _ = &mut shutdown => {
@@ -647,6 +1046,17 @@ where
         index: INDEX,
         metrics_registry: &Registry,
     ) -> Result<Self, Error> {
+        let synonyms = match &config.synonyms {
+            Some(path) => Some(Arc::new(Synonyms::load(path)?)),
+            None => None,
+        };
+        let recency = Recency {
+            window: config.recency_window.into(),
+            boost: config.recency_boost,
+        };
+        let boosts = Some(Arc::new(Boosts::new(&config.boosts, recency)));
+        let query_timeout: Duration = config.query_timeout.into();
+        let query_timeout = (!query_timeout.is_zero()).then_some(query_timeout);
         match config.mode {
             IndexMode::File => {
                 let path = config
@@ -682,6 +1092,10 @@ where
                     index_dir: Some(RwLock::new(index_dir)),
                     index,
                     metrics,
+                    synonyms,
+                    boosts,
+                    query_timeout,
+                    cache: SearchCache::new(config.search_cache_size),
                     shutdown_counter: Some(shutdown_counter),
                 })
             }
@@ -713,6 +1127,10 @@ where
                     index_dir: None,
                     index,
                     metrics,
+                    synonyms,
+                    boosts,
+                    query_timeout,
+                    cache: SearchCache::new(config.search_cache_size),
                     shutdown_counter: Some(shutdown_counter),
                 })
             }
@@ -742,10 +1160,12 @@ where
             ) {
                 Ok(Some(index)) => {
                     *self.inner.write() = index;
+                    self.metrics.last_sync_timestamp_seconds.set(now_timestamp());
                     log::debug!("Index replaced");
                 }
                 Ok(None) => {
                     // No index change
+                    self.metrics.last_sync_timestamp_seconds.set(now_timestamp());
                     log::debug!("No index change");
                 }
                 Err(e) => {
@@ -770,6 +1190,55 @@ where
         Ok(())
     }
 
+    /// Start a new, empty generation of the index, next to the current one.
+    ///
+    /// Documents get added to the new generation by writers of [`Self::generation_writer`], while the current
+    /// generation keeps serving queries and being published, until [`Self::switch_generation`] replaces it. Indexes
+    /// stored in S3 have a single generation, which keeps its documents.
+    pub fn new_generation(&mut self) -> Result<IndexGeneration, Error> {
+        match &self.index_dir {
+            Some(index_dir) => {
+                let (inner, state) =
+                    index_dir
+                        .read()
+                        .prepare(self.index.settings(), self.index.schema(), self.index.tokenizers()?)?;
+                Ok(IndexGeneration {
+                    inner,
+                    state: Some(state),
+                })
+            }
+            None => Ok(IndexGeneration {
+                inner: self.inner.read().clone(),
+                state: None,
+            }),
+        }
+    }
+
+    /// Create a writer adding documents to a new generation of the index.
+    pub fn generation_writer(&self, generation: &IndexGeneration) -> Result<IndexWriter, Error> {
+        let writer = generation.inner.writer(self.index_writer_memory_bytes)?;
+        Ok(IndexWriter {
+            writer,
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    /// Replace the current generation of the index by a new one, for all readers at once.
+    ///
+    /// The writers of the generation must be committed before. Writers of the index acquired before write to the
+    /// replaced generation, they must be dropped and acquired again.
+    pub fn switch_generation(&mut self, generation: IndexGeneration) {
+        if let (Some(index_dir), Some(state)) = (&self.index_dir, generation.state) {
+            let mut index_dir = index_dir.write();
+            let mut inner = self.inner.write();
+            index_dir.state = state;
+            *inner = generation.inner;
+        } else {
+            *self.inner.write() = generation.inner;
+        }
+        log::info!("Switched to the new generation of index {}", self.index.name());
+    }
+
     pub fn commit(&self, writer: IndexWriter) -> Result<(), Error> {
         writer.commit()?;
         Ok(())
@@ -837,6 +1306,57 @@ where
         }
     }
 
+    /// Take a named snapshot of the index and push it to object storage, without publishing it.
+    ///
+    /// Unlike [`Self::snapshot`], the services don't sync from the snapshot. It is kept to restore the index from it,
+    /// see [`Self::restore`].
+    ///
+    /// NOTE: Only applicable for file indices.
+    pub async fn create_snapshot(
+        &mut self,
+        writer: IndexWriter,
+        storage: &Storage,
+        snapshot: &str,
+    ) -> Result<(), Error> {
+        writer.commit()?;
+        let Some(index_dir) = &self.index_dir else {
+            return Err(Error::NotPersisted);
+        };
+
+        let out = {
+            let mut dir = index_dir.write();
+            let mut inner = self.inner.write();
+            inner.directory_mut().sync_directory().map_err(Error::Io)?;
+            // no writer may change the files while packing them
+            let _lock = inner.directory_mut().acquire_lock(&INDEX_WRITER_LOCK);
+            dir.pack()?
+        };
+        storage.put_index_snapshot(self.index.name(), snapshot, &out).await?;
+        log::info!("Snapshot {snapshot} of index {} stored", self.index.name());
+        Ok(())
+    }
+
+    /// Restore the index from a snapshot, see [`Self::create_snapshot`], replacing all of its documents.
+    ///
+    /// Writers acquired before don't write to the restored index, they must be dropped before and acquired again.
+    ///
+    /// NOTE: Only applicable for file indices.
+    pub fn restore(&mut self, data: &[u8]) -> Result<(), Error> {
+        let Some(index_dir) = &self.index_dir else {
+            return Err(Error::NotPersisted);
+        };
+        log::info!("Restoring index {}", self.index.name());
+        let mut index_dir = index_dir.write();
+        let index = index_dir.restore(
+            self.index.schema(),
+            self.index.settings(),
+            self.index.tokenizers()?,
+            data,
+        )?;
+        *self.inner.write() = index;
+        Ok(())
+    }
+
     pub fn writer(&mut self) -> Result<IndexWriter, Error> {
         let writer = self.inner.write().writer(self.index_writer_memory_bytes)?;
         Ok(IndexWriter {
@@ -847,13 +1367,91 @@ where
 }
 
 impl<INDEX: Index> IndexStore<INDEX> {
+    /// The context the queries of the index are prepared with.
+    fn query_context(&self) -> QueryContext {
+        QueryContext::default()
+            .with_synonyms(self.synonyms.clone())
+            .with_boosts(self.boosts.clone())
+    }
+
+    /// Prepare a query of the index, with its synonyms and boosts.
+    ///
+    /// Ranges of the alternative syntax get rewritten first, see [`ranges`]. Queries of patterns not meeting the
+    /// guardrails are rejected, see [`patterns`]. The time budget of the query starts when it gets prepared, searches
+    /// exceeding it get aborted, see [`timeout`].
+    fn prepare_query(&self, q: &str) -> Result<SearchQuery, Error> {
+        let q = rewrite_ranges(q);
+        let deadline = self
+            .query_timeout
+            .map(|timeout| Arc::new(timeout::Deadline::new(timeout)));
+        let context = self.query_context().with_deadline(deadline.clone());
+        let mut query = self.index.prepare_query(&q, &context)?;
+        context.check()?;
+        if let Some(deadline) = deadline {
+            query.query = Box::new(timeout::TimeoutQuery {
+                query: query.query,
+                deadline,
+            });
+        }
+        Ok(query)
+    }
+
+    /// A searcher of the current state of the index.
+    fn searcher(&self) -> Result<Searcher, Error> {
+        Ok(self.inner.read().reader()?.searcher())
+    }
+
+    /// Prepare a search query of the index, sorted by the order of the search options, if any.
+    fn prepare_search(&self, q: &str, options: &SearchOptions) -> Result<SearchQuery, Error> {
+        let mut query = self.prepare_query(q)?;
+
+        if let Some(sort) = &options.sort {
+            query.sort_by = Some(self.parse_sort(sort)?);
+        }
+
+        log::trace!("Processed query: {:?}", query);
+        Ok(query)
+    }
+
     /// Search the index for a given query and return matching documents.
+    ///
+    /// Results of identical searches are answered from the search cache, if enabled, until the index changes, see
+    /// [`IndexConfig::search_cache_size`].
     pub fn search(
         &self,
         q: &str,
         offset: usize,
         limit: usize,
         options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        let Some(cache) = &self.cache else {
+            return self.search_uncached(q, offset, limit, options);
+        };
+
+        // taken ahead of searching, so that results of a newer generation can only be cached as an older one
+        let generation = cursor::fingerprint(&self.searcher()?);
+        let key = SearchKey {
+            q: q.to_string(),
+            offset,
+            limit,
+            options,
+        };
+        if let Some(result) = cache.get(&key, generation) {
+            self.metrics.search_cache_hits_total.inc();
+            return Ok(result);
+        }
+
+        let result = self.search_uncached(q, offset, limit, key.options.clone())?;
+        cache.insert(key, generation, result.clone());
+        Ok(result)
+    }
+
+    fn search_uncached(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: SearchOptions,
     ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
         let latency = self.metrics.query_latency_seconds.start_timer();
 
@@ -865,13 +1463,46 @@ impl<INDEX: Index> IndexStore<INDEX> {
         let reader = inner.reader()?;
         let searcher = reader.searcher();
 
-        let query = self.index.prepare_query(q)?;
+        let query = self.prepare_search(q, &options)?;
+        let (top_docs, count) = match &options.dedup_by {
+            Some(dedup_by) => self.dedup_top_docs(&searcher, &query, dedup_by, offset, limit)?,
+            None => self.top_docs(&searcher, &query, offset, limit)?,
+        };
+        timeout::check(&*query.query)?;
 
-        log::trace!("Processed query: {:?}", query);
+        self.metrics.queries_total.inc();
+
+        log::info!("#matches={count} for query '{q}'");
+
+        if options.summaries {
+            let hits = self.process_hits(
+                &searcher,
+                &query.query,
+                top_docs.into_iter().map(|(rank, doc)| (rank.score(), doc)),
+                &options,
+            );
+
+            log::debug!("Filtered to {}", hits.len());
+
+            latency.observe_duration();
+            Ok((hits, count))
+        } else {
+            latency.observe_duration();
+            Ok((Vec::new(), count))
+        }
+    }
 
-        let (top_docs, count) = if let Some(sort_by) = query.sort_by {
+    /// Search for the top documents matching a prepared query, along with the number of all matching documents.
+    fn top_docs(
+        &self,
+        searcher: &Searcher,
+        query: &SearchQuery,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(Rank, DocAddress)>, usize), Error> {
+        if let Some(sort_by) = &query.sort_by {
             let field = sort_by.0;
-            let order = sort_by.1;
+            let order = &sort_by.1;
             let order_by_str = self.index.schema().get_field_name(field).to_string();
             let vtype = self.index.schema().get_field_entry(field).field_type().value_type();
             let mut hits = Vec::new();
@@ -887,7 +1518,7 @@ impl<INDEX: Index> IndexStore<INDEX> {
                         ),
                     )?;
                     for r in result.0 {
-                        hits.push((1.0, r.1));
+                        hits.push((Rank::U64(r.0), r.1));
                     }
                     result.1
                 }
@@ -902,7 +1533,7 @@ impl<INDEX: Index> IndexStore<INDEX> {
                         ),
                     )?;
                     for r in result.0 {
-                        hits.push((1.0, r.1));
+                        hits.push((Rank::I64(r.0), r.1));
                     }
                     result.1
                 }
@@ -917,7 +1548,7 @@ impl<INDEX: Index> IndexStore<INDEX> {
                         ),
                     )?;
                     for r in result.0 {
-                        hits.push((1.0, r.1));
+                        hits.push((Rank::F64(r.0), r.1));
                     }
                     result.1
                 }
@@ -932,7 +1563,7 @@ impl<INDEX: Index> IndexStore<INDEX> {
                         ),
                     )?;
                     for r in result.0 {
-                        hits.push((1.0, r.1));
+                        hits.push((Rank::Bool(r.0), r.1));
                     }
                     result.1
                 }
@@ -947,43 +1578,501 @@ impl<INDEX: Index> IndexStore<INDEX> {
                         ),
                     )?;
                     for r in result.0 {
-                        hits.push((1.0, r.1));
+                        hits.push((Rank::Date(r.0), r.1));
+                    }
+                    result.1
+                }
+                Type::Str => {
+                    let result = searcher.search(
+                        &query.query,
+                        &(
+                            TopDocs::with_limit(limit)
+                                .and_offset(offset)
+                                .order_by_string_fast_field(&order_by_str, order.clone()),
+                            tantivy::collector::Count,
+                        ),
+                    )?;
+                    for r in result.0 {
+                        hits.push((Rank::Str(r.0), r.1));
                     }
                     result.1
                 }
                 _ => return Err(Error::NotSortable(order_by_str)),
             };
-            (hits, total)
+            Ok((hits, total))
         } else {
-            self.index.search(&searcher, &query.query, offset, limit)?
-        };
-
+            let (hits, total) = self
+                .index
+                .search(searcher, &query.query, offset, limit, &self.query_context())?;
+            Ok((
+                hits.into_iter().map(|(score, doc)| (Rank::Score(score), doc)).collect(),
+                total,
+            ))
+        }
+    }
+
+    /// Search for the top documents matching a prepared query, collapsing the documents sharing the value of a field
+    /// to the highest ranking one, along with the number of documents left after collapsing them.
+    fn dedup_top_docs(
+        &self,
+        searcher: &Searcher,
+        query: &SearchQuery,
+        dedup_by: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<(Rank, DocAddress)>, usize), Error> {
+        let field = self.dedup_field(dedup_by)?;
+        let keys = dedup::Keys::new(searcher, &field)?;
+        let docs = dedup::dedup(
+            offset,
+            limit,
+            |window| Ok(self.top_docs(searcher, query, 0, window)?.0),
+            |(_, doc)| keys.get(*doc),
+        )?;
+        let distinct = searcher.search(&query.query, &dedup::DistinctCollector { field })?;
+        Ok((docs, distinct.count()))
+    }
+
+    /// The name of the field to deduplicate hits by.
+    fn dedup_field(&self, name: &str) -> Result<String, Error> {
+        let field = self
+            .index
+            .dedup_field(name)
+            .ok_or_else(|| Error::NotDeduplicable(name.to_string()))?;
+        Ok(self.index.schema().get_field_name(field).to_string())
+    }
+
+    /// Process the matching documents into the results of a search, skipping documents failing to process.
+    fn process_hits(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        docs: impl IntoIterator<Item = (f32, DocAddress)>,
+        options: &SearchOptions,
+    ) -> Vec<INDEX::MatchedDocument> {
+        let mut hits = Vec::new();
+        for hit in docs {
+            match self.index.process_hit(hit.1, hit.0, searcher, query, options) {
+                Ok(value) => {
+                    log::debug!("HIT: {:?}", value);
+                    hits.push(value);
+                }
+                Err(e) => {
+                    log::warn!("Error processing hit {:?}: {:?}", hit, e);
+                }
+            }
+        }
+        hits
+    }
+
+    /// Search the index for a given query, iterating the matching documents using a cursor.
+    ///
+    /// An empty cursor starts the iteration. Next to the matching documents and their total number, the cursor for
+    /// retrieving the next page is returned, as long as there are more documents left. Documents are returned in
+    /// the order of the index, and can't be sorted or deduplicated.
+    pub fn search_cursor(
+        &self,
+        q: &str,
+        cursor: &str,
+        limit: usize,
+        options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize, Option<String>), Error> {
+        let latency = self.metrics.query_latency_seconds.start_timer();
+
+        if limit == 0 {
+            return Err(Error::InvalidLimitParameter(limit));
+        }
+        if options.sort.is_some() {
+            return Err(Error::QueryParser(
+                "a cursor cannot be combined with a sort order".to_string(),
+            ));
+        }
+        if options.dedup_by.is_some() {
+            return Err(Error::QueryParser(
+                "a cursor cannot be combined with deduplication".to_string(),
+            ));
+        }
+
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let fingerprint = cursor::fingerprint(&searcher);
+        let after = match cursor::Cursor::decode(cursor)? {
+            Some(cursor) if cursor.fingerprint != fingerprint => return Err(Error::CursorExpired),
+            Some(cursor) => Some(cursor.after),
+            None => None,
+        };
+
+        let query = self.prepare_query(q)?;
+
+        log::trace!("Processed query: {:?}", query);
+
+        // fetch one more document to find out if there is a next page
+        let (mut docs, count) = searcher.search(
+            &query.query,
+            &(
+                cursor::CursorCollector {
+                    after,
+                    limit: limit + 1,
+                },
+                tantivy::collector::Count,
+            ),
+        )?;
+        timeout::check(&*query.query)?;
+
+        let next = match docs.len() > limit {
+            true => {
+                docs.truncate(limit);
+                docs.last().map(|after| {
+                    cursor::Cursor {
+                        fingerprint,
+                        after: *after,
+                    }
+                    .encode()
+                })
+            }
+            false => None,
+        };
+
         self.metrics.queries_total.inc();
 
-        log::info!("#matches={count} for query '{q}'");
+        log::info!("#matches={count} for query '{q}' (cursor)");
 
+        let mut hits = Vec::new();
         if options.summaries {
-            let mut hits = Vec::new();
-            for hit in top_docs {
-                match self.index.process_hit(hit.1, hit.0, &searcher, &query.query, &options) {
-                    Ok(value) => {
-                        log::debug!("HIT: {:?}", value);
-                        hits.push(value);
-                    }
+            for doc in docs {
+                match self.index.process_hit(doc, 1.0, &searcher, &query.query, &options) {
+                    Ok(value) => hits.push(value),
                     Err(e) => {
-                        log::warn!("Error processing hit {:?}: {:?}", hit, e);
+                        log::warn!("Error processing hit {:?}: {:?}", doc, e);
                     }
                 }
             }
+        }
 
-            log::debug!("Filtered to {}", hits.len());
+        latency.observe_duration();
+        Ok((hits, count, next))
+    }
 
-            latency.observe_duration();
-            Ok((hits, count))
-        } else {
-            latency.observe_duration();
-            Ok((Vec::new(), count))
+    /// Parse a sort order of the form `<field>[:<asc|desc>]`, defaulting to ascending order.
+    fn parse_sort(&self, sort: &str) -> Result<(Field, Order), Error> {
+        let (name, order) = sort.split_once(':').unwrap_or((sort, "asc"));
+        let order = match order {
+            "asc" => Order::Asc,
+            "desc" => Order::Desc,
+            _ => return Err(Error::QueryParser(format!("invalid sort order: {order}"))),
+        };
+        let field = self
+            .index
+            .sort_field(name)
+            .ok_or_else(|| Error::NotSortable(name.to_string()))?;
+        Ok((field, order))
+    }
+
+    /// The fields query terms can be qualified with, and the predicates, in the order of the query language.
+    pub fn search_fields(&self) -> Vec<SearchField> {
+        self.index
+            .qualifiers()
+            .iter()
+            .map(|name| {
+                self.index
+                    .qualifier_field(name)
+                    .unwrap_or_else(|| SearchField::predicate(*name))
+            })
+            .collect()
+    }
+
+    /// Compute the bucket counts of the requested facets, for all documents matching a given query.
+    pub fn facets(&self, q: &str, facets: &[String]) -> Result<Facets, Error> {
+        self.facets_with(facets, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute the bucket counts of facets, running the aggregations by a function, like over all shards.
+    fn facets_with(
+        &self,
+        facets: &[String],
+        run: impl FnOnce(serde_json::Value) -> Result<serde_json::Value, Error>,
+    ) -> Result<Facets, Error> {
+        if facets.is_empty() {
+            return Ok(Facets::new());
+        }
+
+        let mut aggs = serde_json::Map::new();
+        for name in facets {
+            aggs.insert(name.clone(), self.terms_request(name, MAX_FACET_BUCKETS)?);
+        }
+        let result = run(aggs.into())?;
+
+        let mut counts = Facets::new();
+        for name in facets {
+            counts.insert(name.clone(), facet_counts(&result[name]));
+        }
+
+        Ok(counts)
+    }
+
+    /// Compute the bucket counts of a facet, for all documents matching a given query, splitting each bucket by the
+    /// values of another facet.
+    pub fn pivot(&self, q: &str, facet: &str, split: &str) -> Result<Vec<FacetPivot>, Error> {
+        self.pivot_with(facet, split, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute the pivot of a facet, running the aggregations by a function, like over all shards.
+    fn pivot_with(
+        &self,
+        facet: &str,
+        split: &str,
+        run: impl FnOnce(serde_json::Value) -> Result<serde_json::Value, Error>,
+    ) -> Result<Vec<FacetPivot>, Error> {
+        let mut pivot = self.terms_request(facet, MAX_FACET_BUCKETS)?;
+        pivot["aggs"] = serde_json::json!({ "facet": self.terms_request(split, MAX_FACET_BUCKETS)? });
+        let result = run(serde_json::json!({ "pivot": pivot }))?;
+
+        Ok(buckets(&result["pivot"])
+            .iter()
+            .map(|bucket| FacetPivot {
+                value: bucket_key(bucket),
+                count: bucket["doc_count"].as_u64().unwrap_or_default(),
+                facets: facet_counts(&bucket["facet"]),
+            })
+            .collect())
+    }
+
+    /// Compute the number of documents matching a query for each interval of a date field, optionally split by the
+    /// values of a facet.
+    ///
+    /// Intervals start at the epoch, shifted by the offset. Only intervals between the first and last matching
+    /// document are returned, including the empty ones.
+    pub fn histogram(
+        &self,
+        q: &str,
+        name: &str,
+        interval: Duration,
+        offset: Duration,
+        facet: Option<&str>,
+    ) -> Result<Vec<HistogramBucket>, Error> {
+        self.histogram_with(name, interval, offset, facet, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute a histogram from the parameters of a histogram query, bucketing by the default field if the parameters
+    /// don't name one.
+    pub fn histogram_for(&self, params: &HistogramParams, default_field: &str) -> Result<Vec<HistogramBucket>, Error> {
+        let (interval, offset) = histogram_durations(params)?;
+        let field = params.field.as_deref().unwrap_or(default_field);
+        self.histogram(&params.q, field, interval, offset, params.facet.as_deref())
+    }
+
+    /// Compute a histogram, running the aggregations by a function, like over all shards.
+    fn histogram_with(
+        &self,
+        name: &str,
+        interval: Duration,
+        offset: Duration,
+        facet: Option<&str>,
+        run: impl FnOnce(serde_json::Value) -> Result<serde_json::Value, Error>,
+    ) -> Result<Vec<HistogramBucket>, Error> {
+        let histogram = self.histogram_request(name, interval, offset, facet)?;
+        let result = run(serde_json::json!({ "histogram": histogram }))?;
+        Ok(histogram_buckets(&result["histogram"]))
+    }
+
+    /// Compute a set of named aggregations, in a single pass over all documents matching a given query.
+    pub fn aggregate(
+        &self,
+        q: &str,
+        aggregations: &BTreeMap<String, Aggregation>,
+    ) -> Result<BTreeMap<String, AggregationResult>, Error> {
+        self.aggregate_with(aggregations, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute a set of named aggregations, running them by a function, like over all shards.
+    fn aggregate_with(
+        &self,
+        aggregations: &BTreeMap<String, Aggregation>,
+        run: impl FnOnce(serde_json::Value) -> Result<serde_json::Value, Error>,
+    ) -> Result<BTreeMap<String, AggregationResult>, Error> {
+        if aggregations.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let mut aggs = serde_json::Map::new();
+        for (name, aggregation) in aggregations {
+            let request = match aggregation {
+                Aggregation::Terms { facet, size } => self.terms_request(facet, (*size).min(MAX_FACET_BUCKETS))?,
+                Aggregation::DateHistogram {
+                    field,
+                    interval,
+                    offset,
+                    facet,
+                } => self.histogram_request(field, *interval, *offset, facet.as_deref())?,
+                Aggregation::Stats { field } => self.stats_request(field)?,
+            };
+            aggs.insert(name.clone(), request);
         }
+        let result = run(aggs.into())?;
+
+        Ok(aggregations
+            .iter()
+            .map(|(name, aggregation)| {
+                let result = &result[name];
+                let result = match aggregation {
+                    Aggregation::Terms { .. } => AggregationResult::Terms(facet_counts(result)),
+                    Aggregation::DateHistogram { .. } => AggregationResult::DateHistogram(histogram_buckets(result)),
+                    Aggregation::Stats { .. } => AggregationResult::Stats(numeric_stats(result)),
+                };
+                (name.clone(), result)
+            })
+            .collect())
+    }
+
+    /// Build the request of a terms aggregation over a facet.
+    fn terms_request(&self, facet: &str, size: u32) -> Result<serde_json::Value, Error> {
+        let field = self
+            .index
+            .facet_field(facet)
+            .ok_or_else(|| Error::NotFacetable(facet.to_string()))?;
+        Ok(serde_json::json!({
+            "terms": { "field": self.index.schema().get_field_name(field), "size": size }
+        }))
+    }
+
+    /// Build the request of a date histogram aggregation, optionally split by the values of a facet.
+    fn histogram_request(
+        &self,
+        name: &str,
+        interval: Duration,
+        offset: Duration,
+        facet: Option<&str>,
+    ) -> Result<serde_json::Value, Error> {
+        if interval.as_millis() == 0 {
+            return Err(Error::QueryParser("histogram interval must not be zero".to_string()));
+        }
+
+        let field = self
+            .index
+            .histogram_field(name)
+            .ok_or_else(|| Error::NotHistogrammable(name.to_string()))?;
+        let mut histogram = serde_json::json!({
+            "date_histogram": {
+                "field": self.index.schema().get_field_name(field),
+                "fixed_interval": format!("{}ms", interval.as_millis()),
+                "offset": format!("{}ms", offset.as_millis() % interval.as_millis()),
+                "min_doc_count": 0,
+            }
+        });
+        if let Some(facet) = facet {
+            histogram["aggs"] = serde_json::json!({ "facet": self.terms_request(facet, MAX_FACET_BUCKETS)? });
+        }
+        Ok(histogram)
+    }
+
+    /// Build the request of a stats aggregation over a numeric field.
+    fn stats_request(&self, name: &str) -> Result<serde_json::Value, Error> {
+        let field = self
+            .index
+            .stats_field(name)
+            .ok_or_else(|| Error::NoStatistics(name.to_string()))?;
+        Ok(serde_json::json!({
+            "stats": { "field": self.index.schema().get_field_name(field) }
+        }))
+    }
+
+    /// Run aggregation requests over all documents matching a given query, returning the result as JSON.
+    fn run_aggregations(&self, q: &str, aggs: serde_json::Value) -> Result<serde_json::Value, Error> {
+        let aggs = parse_aggregations(aggs)?;
+
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let query = self.prepare_query(q)?;
+
+        let result = searcher.search(&query.query, &AggregationCollector::from_aggs(aggs, Default::default()))?;
+        timeout::check(&*query.query)?;
+        serde_json::to_value(result).map_err(|e| Error::QueryParser(e.to_string()))
+    }
+
+    /// Compute the intermediate results of aggregations over all documents matching a query, for merging them with
+    /// the results of other shards.
+    fn collect_aggregations(&self, q: &str, aggs: &Aggregations) -> Result<IntermediateAggregationResults, Error> {
+        let inner = self.inner.read();
+        let reader = inner.reader()?;
+        let searcher = reader.searcher();
+
+        let query = self.prepare_query(q)?;
+
+        let result = searcher.search(
+            &query.query,
+            &DistributedAggregationCollector::from_aggs(aggs.clone(), Default::default()),
+        )?;
+        timeout::check(&*query.query)?;
+        Ok(result)
+    }
+}
+
+/// Parse the JSON request of aggregations.
+fn parse_aggregations(aggs: serde_json::Value) -> Result<Aggregations, Error> {
+    serde_json::from_value(aggs).map_err(|e| Error::QueryParser(e.to_string()))
+}
+
+/// The buckets of an aggregation result.
+fn buckets(aggregation: &serde_json::Value) -> &[serde_json::Value] {
+    aggregation["buckets"].as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// The key of a bucket of a terms aggregation.
+fn bucket_key(bucket: &serde_json::Value) -> String {
+    match &bucket["key"] {
+        serde_json::Value::String(key) => key.clone(),
+        key => key.to_string(),
+    }
+}
+
+/// The bucket counts of a terms aggregation result.
+fn facet_counts(aggregation: &serde_json::Value) -> Vec<FacetCount> {
+    buckets(aggregation)
+        .iter()
+        .map(|bucket| FacetCount {
+            value: bucket_key(bucket),
+            count: bucket["doc_count"].as_u64().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Parse the interval and the offset of a histogram query.
+pub(crate) fn histogram_durations(params: &HistogramParams) -> Result<(Duration, Duration), Error> {
+    let interval = humantime::parse_duration(&params.interval)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| Error::InvalidInterval(params.interval.clone()))?;
+    let offset = match &params.offset {
+        Some(offset) => humantime::parse_duration(offset).map_err(|_| Error::InvalidInterval(offset.clone()))?,
+        None => Duration::ZERO,
+    };
+    Ok((interval, offset))
+}
+
+/// The buckets of a date histogram aggregation result.
+fn histogram_buckets(aggregation: &serde_json::Value) -> Vec<HistogramBucket> {
+    buckets(aggregation)
+        .iter()
+        .map(|bucket| HistogramBucket {
+            start: bucket["key_as_string"].as_str().unwrap_or_default().to_string(),
+            count: bucket["doc_count"].as_u64().unwrap_or_default(),
+            facets: facet_counts(&bucket["facet"]),
+        })
+        .collect()
+}
+
+/// The statistics of a stats aggregation result.
+fn numeric_stats(aggregation: &serde_json::Value) -> NumericStats {
+    NumericStats {
+        count: aggregation["count"].as_u64().unwrap_or_default(),
+        sum: aggregation["sum"].as_f64().unwrap_or_default(),
+        min: aggregation["min"].as_f64(),
+        max: aggregation["max"].as_f64(),
+        avg: aggregation["avg"].as_f64(),
     }
 }
 
@@ -1054,7 +2143,7 @@ pub fn create_date_query(schema: &Schema, field: Field, value: &Ordered<time::Of
             let theday_after = theday_after.midnight().assume_utc();
 
             let from = Bound::Included(Term::from_field_date(field, DateTime::from_utc(theday)));
-            let to = Bound::Included(Term::from_field_date(field, DateTime::from_utc(theday_after)));
+            let to = Bound::Excluded(Term::from_field_date(field, DateTime::from_utc(theday_after)));
             Box::new(RangeQuery::new_term_bounds(field_name, Type::Date, &from, &to))
         }
         Ordered::Range(from, to) => {
@@ -1066,8 +2155,8 @@ pub fn create_date_query(schema: &Schema, field: Field, value: &Ordered<time::Of
 }
 
 /// Convert a sikula primary to a tantivy query for string fields
-pub fn create_string_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query> {
-    create_string_query_case(field, primary, Case::Sensitive)
+pub fn create_string_query(field: Field, primary: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
+    create_string_query_case(field, primary, Case::Sensitive, context)
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -1088,13 +2177,63 @@ impl Case {
     }
 }
 
+/// Split the edit distance off a value requesting fuzzy matching, returning `None` for other values.
+///
+/// Values prefixed by `~`, like `~quarkis`, match terms within an edit distance of 1, values prefixed by `~~` within
+/// an edit distance of 2. Transpositions count as a single edit.
+pub fn fuzzy_value(value: &str) -> Option<(&str, u8)> {
+    let (value, distance) = match value.strip_prefix("~~") {
+        Some(value) => (value, 2),
+        None => (value.strip_prefix('~')?, 1),
+    };
+    (!value.is_empty()).then_some((value, distance))
+}
+
+/// Create a query matching terms within the edit distance of a value.
+fn create_fuzzy_query(field: Field, value: &str, distance: u8) -> Box<dyn Query> {
+    Box::new(FuzzyTermQuery::new(Term::from_field_text(field, value), distance, true))
+}
+
+/// Create a query matching a value, or any of its synonyms, see [`synonyms`].
+fn create_synonym_query(
+    field: Field,
+    value: &str,
+    to_value: impl Fn(&str) -> String,
+    context: &QueryContext,
+) -> Box<dyn Query> {
+    let term = |value: &str| -> Box<dyn Query> {
+        Box::new(TermQuery::new(
+            Term::from_field_text(field, &to_value(value)),
+            Default::default(),
+        ))
+    };
+    let synonyms = context.synonyms_of(value);
+    if synonyms.is_empty() {
+        return term(value);
+    }
+    let mut queries = vec![term(value)];
+    queries.extend(synonyms.iter().map(|synonym| term(synonym)));
+    Box::new(BooleanQuery::union(queries))
+}
+
 /// Convert a sikula primary to a tantivy query for string fields
-pub fn create_string_query_case(field: Field, primary: &Primary<'_>, case: Case) -> Box<dyn Query> {
+///
+/// Exact matches are fuzzy if requested by the value, see [`fuzzy_value`], match a pattern, see [`pattern_value`], or
+/// else are expanded by their synonyms.
+pub fn create_string_query_case(
+    field: Field,
+    primary: &Primary<'_>,
+    case: Case,
+    context: &QueryContext,
+) -> Box<dyn Query> {
     match primary {
-        Primary::Equal(value) => Box::new(TermQuery::new(
-            Term::from_field_text(field, case.to_value(value).as_ref()),
-            Default::default(),
-        )),
+        Primary::Equal(value) => match (fuzzy_value(value), pattern_value(value)) {
+            (Some((value, distance)), _) => create_fuzzy_query(field, case.to_value(value).as_ref(), distance),
+            (None, Some(pattern)) => {
+                create_pattern_query(field, pattern, |value| case.to_value(value).into_owned(), context)
+            }
+            (None, None) => create_synonym_query(field, value, |value| case.to_value(value).into_owned(), context),
+        },
         Primary::Partial(value) => {
             // Note: This could be expensive so consider alternatives
             let pattern = format!(".*{}.*", case.to_value(value));
@@ -1113,13 +2252,29 @@ pub fn create_string_query_case(field: Field, primary: &Primary<'_>, case: Case)
     }
 }
 
+/// Convert a sikula primary to a tantivy query for fields of package URLs, which are indexed normalized
+///
+/// Only exact matches are normalized, partial matches and patterns don't need to be valid package URLs.
+pub fn create_purl_query(field: Field, primary: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
+    match primary {
+        Primary::Equal(value) if pattern_value(value).is_none() => {
+            create_string_query(field, &Primary::Equal(&trustification_purl::normalize(value)), context)
+        }
+        Primary::Equal(_) | Primary::Partial(_) => create_string_query(field, primary, context),
+    }
+}
+
 /// Convert a sikula primary to a tantivy query for text fields
-pub fn create_text_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query> {
+///
+/// Exact matches are fuzzy if requested by the value, see [`fuzzy_value`], match the words of a pattern, see
+/// [`pattern_value`], or else are expanded by their synonyms.
+pub fn create_text_query(field: Field, primary: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
     match primary {
-        Primary::Equal(value) => Box::new(TermQuery::new(
-            Term::from_field_text(field, &value.to_lowercase()),
-            Default::default(),
-        )),
+        Primary::Equal(value) => match (fuzzy_value(value), pattern_value(value)) {
+            (Some((value, distance)), _) => create_fuzzy_query(field, &value.to_lowercase(), distance),
+            (None, Some(pattern)) => create_pattern_query(field, pattern, str::to_lowercase, context),
+            (None, None) => create_synonym_query(field, value, str::to_lowercase, context),
+        },
         Primary::Partial(value) => Box::new(FuzzyTermQuery::new(
             Term::from_field_text(field, &value.to_lowercase()),
             2,
@@ -1128,6 +2283,17 @@ pub fn create_text_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query>
     }
 }
 
+/// Describe a field query terms can be qualified with, deriving its type from the field of the schema
+pub fn search_field(schema: &Schema, name: &str, field: Field, example: &str) -> SearchField {
+    let r#type = match schema.get_field_entry(field).field_type() {
+        FieldType::Date(_) => SearchFieldType::Date,
+        FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) => SearchFieldType::Number,
+        FieldType::Bool(_) => SearchFieldType::Boolean,
+        _ => SearchFieldType::Text,
+    };
+    SearchField::new(name, r#type, example)
+}
+
 /// Boost score of a term
 pub fn boost(q: Box<dyn Query>, weight: f32) -> Box<dyn Query> {
     Box::new(BoostQuery::new(q, weight))
@@ -1240,6 +2406,8 @@ mod tests {
         schema: Schema,
         id: Field,
         text: Field,
+        word: Field,
+        length: Field,
     }
 
     impl TestIndex {
@@ -1247,24 +2415,26 @@ mod tests {
             let mut builder = Schema::builder();
             let id = builder.add_text_field("id", STRING | FAST | STORED);
             let text = builder.add_text_field("text", TEXT);
+            let word = builder.add_text_field("word", STRING | FAST);
+            let length = builder.add_f64_field("length", FAST);
             let schema = builder.build();
-            Self { schema, id, text }
+            Self {
+                schema,
+                id,
+                text,
+                word,
+                length,
+            }
         }
     }
 
     impl Index for TestIndex {
         type MatchedDocument = String;
 
-        fn prepare_query(&self, q: &str) -> Result<SearchQuery, Error> {
+        fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, Error> {
             let queries: Vec<Box<dyn Query>> = vec![
-                Box::new(TermQuery::new(
-                    Term::from_field_text(self.id, q),
-                    IndexRecordOption::Basic,
-                )),
-                Box::new(TermQuery::new(
-                    Term::from_field_text(self.text, q),
-                    IndexRecordOption::Basic,
-                )),
+                create_string_query(self.id, &Primary::Equal(q), context),
+                create_text_query(self.text, &Primary::Equal(q), context),
             ];
             Ok(SearchQuery {
                 query: Box::new(BooleanQuery::union(queries)),
@@ -1294,12 +2464,34 @@ mod tests {
             query: &dyn Query,
             offset: usize,
             limit: usize,
+            _context: &QueryContext,
         ) -> Result<(Vec<(f32, DocAddress)>, usize), Error> {
             Ok(searcher.search(
                 query,
                 &(TopDocs::with_limit(limit).and_offset(offset), tantivy::collector::Count),
             )?)
         }
+
+        fn facet_field(&self, name: &str) -> Option<Field> {
+            match name {
+                "id" => Some(self.id),
+                _ => None,
+            }
+        }
+
+        fn stats_field(&self, name: &str) -> Option<Field> {
+            match name {
+                "length" => Some(self.length),
+                _ => None,
+            }
+        }
+
+        fn dedup_field(&self, name: &str) -> Option<Field> {
+            match name {
+                "word" => Some(self.word),
+                _ => None,
+            }
+        }
     }
 
     impl WriteIndex for TestIndex {
@@ -1325,10 +2517,14 @@ mod tests {
 
         fn index_doc(&self, id: &str, document: &Self::Document) -> Result<Vec<(String, Document)>, Error> {
             let mut documents: Vec<(String, Document)> = Vec::new();
-            let doc = tantivy::doc!(
+            let mut doc = tantivy::doc!(
                 self.id => id.to_string(),
-                self.text => document.to_string()
+                self.text => document.to_string(),
+                self.length => document.len() as f64
             );
+            if let Some(word) = document.split_whitespace().next() {
+                doc.add_text(self.word, word);
+            }
             documents.push((id.to_string(), doc));
             Ok(documents)
         }
@@ -1338,6 +2534,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fuzzy_value() {
+        assert_eq!(fuzzy_value("~quarkis"), Some(("quarkis", 1)));
+        assert_eq!(fuzzy_value("~~qurakis"), Some(("qurakis", 2)));
+        assert_eq!(fuzzy_value("quarkus"), None);
+        assert_eq!(fuzzy_value("~"), None);
+        assert_eq!(fuzzy_value("quarkus~"), None);
+    }
+
     #[tokio::test]
     async fn test_basic_index() {
         let _ = env_logger::try_init();
@@ -1353,6 +2558,28 @@ mod tests {
         assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 1);
     }
 
+    #[tokio::test]
+    async fn test_metrics() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        assert_eq!(store.metrics.last_commit_timestamp_seconds.get(), 0);
+
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer.commit().unwrap();
+
+        assert!(store.metrics.last_commit_timestamp_seconds.get() > 0);
+        assert!(store.metrics.record_stats(&store.inner));
+        assert_eq!(store.metrics.documents.get(), 1);
+        assert_eq!(store.metrics.segments.get(), 1);
+        assert!(store.metrics.segments_size_bytes.get() > 0);
+        // in memory indexes are neither snapshotted nor synced
+        assert_eq!(store.metrics.index_size_disk_bytes.get(), 0);
+        assert_eq!(store.metrics.last_sync_timestamp_seconds.get(), 0);
+    }
+
     #[tokio::test]
     async fn test_index_removal() {
         let _ = env_logger::try_init();
@@ -1374,6 +2601,144 @@ mod tests {
         assert_eq!(store.search("is", 0, 10, SearchOptions::default()).unwrap().1, 0);
     }
 
+    #[tokio::test]
+    async fn test_dedup() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (id, text) in [
+            ("a", "Foo is great"),
+            ("b", "Foo is fine"),
+            ("c", "Bar is great"),
+            ("d", "Foo is ok"),
+            ("e", "Baz is great"),
+        ] {
+            writer.add_document(store.index_as_mut(), id, text.as_bytes()).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let options = SearchOptions {
+            dedup_by: Some("word".to_string()),
+            ..Default::default()
+        };
+        let (result, total) = store.search("is", 0, 10, options.clone()).unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(result.len(), 3);
+        assert_eq!(
+            result
+                .iter()
+                .filter(|id| ["a", "b", "d"].contains(&id.as_str()))
+                .count(),
+            1
+        );
+
+        let mut found = Vec::new();
+        for page in 0..3 {
+            let (result, total) = store.search("is", page, 1, options.clone()).unwrap();
+            assert_eq!(total, 3);
+            found.extend(result);
+        }
+        found.sort();
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(&"c".to_string()) && found.contains(&"e".to_string()));
+
+        assert!(matches!(
+            store.search(
+                "is",
+                0,
+                10,
+                SearchOptions {
+                    dedup_by: Some("text".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Err(Error::NotDeduplicable(_))
+        ));
+        assert!(matches!(
+            store.search_cursor("is", "", 10, options),
+            Err(Error::QueryParser(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_cache() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new())
+            .unwrap()
+            .with_search_cache(10);
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "a", b"Foo is great").unwrap();
+        writer.commit().unwrap();
+
+        let search = |store: &IndexStore<TestIndex>| store.search("is", 0, 10, Default::default()).unwrap();
+        assert_eq!(search(&store), (vec!["a".to_string()], 1));
+        assert_eq!(search(&store), (vec!["a".to_string()], 1));
+        assert_eq!(store.metrics.search_cache_hits_total.get(), 1);
+
+        // changing the index invalidates the cached results
+        let mut writer = store.writer().unwrap();
+        writer.add_document(store.index_as_mut(), "b", b"Bar is fine").unwrap();
+        writer.commit().unwrap();
+        let (mut result, total) = search(&store);
+        result.sort();
+        assert_eq!((result, total), (vec!["a".to_string(), "b".to_string()], 2));
+        assert_eq!(store.metrics.search_cache_hits_total.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_timeout() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new())
+            .unwrap()
+            .with_query_timeout(Duration::ZERO);
+        let mut writer = store.writer().unwrap();
+        for i in 0..2 * timeout::CHECK_INTERVAL {
+            writer
+                .add_document(store.index_as_mut(), &format!("doc-{i}"), b"Foo is great")
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        assert!(matches!(
+            store.search("is", 0, 10, Default::default()),
+            Err(Error::Timeout(_))
+        ));
+        assert!(matches!(
+            store.search_cursor("is", "", 10, Default::default()),
+            Err(Error::Timeout(_))
+        ));
+
+        // no scorers get created once the deadline passed
+        assert!(matches!(
+            store.search("absent", 0, 10, Default::default()),
+            Err(Error::Timeout(_))
+        ));
+
+        let store = store.with_query_timeout(Duration::from_secs(60));
+        let (_, total) = store.search("is", 0, 10, Default::default()).unwrap();
+        assert_eq!(total, 2 * timeout::CHECK_INTERVAL as usize);
+    }
+
+    #[tokio::test]
+    async fn test_patterns() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        for (id, text) in [("foo", "Foo is great"), ("food", "Food is fine"), ("bar", "Bar is ok")] {
+            writer.add_document(store.index_as_mut(), id, text.as_bytes()).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let search = |q: &str| store.search(q, 0, 10, SearchOptions::default()).map(|(_, total)| total);
+        assert_eq!(search("foo*").unwrap(), 2);
+        assert_eq!(search("Foo*").unwrap(), 2);
+        assert_eq!(search("bar*").unwrap(), 1);
+        assert_eq!(search("/foo[dx]/").unwrap(), 1);
+        assert_eq!(search("/food?/").unwrap(), 2);
+        assert!(matches!(search("fo*"), Err(Error::QueryParser(_))));
+        assert!(matches!(search("/.*/"), Err(Error::QueryParser(_))));
+    }
+
     #[tokio::test]
     async fn test_zero_limit() {
         let _ = env_logger::try_init();
@@ -1384,6 +2749,287 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_search_cursor() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        for id in ["a", "b", "c", "d", "e"] {
+            writer.add_document(store.index_as_mut(), id, b"Foo is great").unwrap();
+        }
+        writer.commit().unwrap();
+
+        let mut ids = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let (result, total, next) = store.search_cursor("is", &cursor, 2, SearchOptions::default()).unwrap();
+            assert_eq!(total, 5);
+            ids.extend(result);
+            match next {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+        ids.sort();
+        assert_eq!(ids, vec!["a", "b", "c", "d", "e"]);
+
+        // changing the index expires the cursor
+        let (_, _, next) = store.search_cursor("is", "", 2, SearchOptions::default()).unwrap();
+        let writer = store.writer().unwrap();
+        writer.delete_document(store.index_as_mut(), "a");
+        writer.commit().unwrap();
+        assert!(matches!(
+            store.search_cursor("is", &next.unwrap(), 2, SearchOptions::default()),
+            Err(Error::CursorExpired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sharded_index() {
+        let _ = env_logger::try_init();
+        let mut store = ShardedIndexStore::new_in_memory(TestIndex::new, 3).unwrap();
+        let ids: Vec<_> = (0..20).map(|n| format!("doc{n:02}")).collect();
+
+        for shard in store.shards_mut() {
+            let mut writer = shard.writer().unwrap();
+            for id in &ids {
+                writer.add_document(shard.index_as_mut(), id, b"Foo is great").unwrap();
+            }
+            writer.commit().unwrap();
+        }
+
+        // each document is indexed by a single shard
+        let counts: usize = store
+            .shards()
+            .iter()
+            .map(|shard| shard.search("is", 0, 100, SearchOptions::default()).unwrap().1)
+            .sum();
+        assert_eq!(counts, 20);
+
+        let mut found = Vec::new();
+        for page in 0..4 {
+            let (result, total) = store.search("is", page * 6, 6, SearchOptions::default()).unwrap();
+            assert_eq!(total, 20);
+            assert_eq!(result.len(), if page < 3 { 6 } else { 2 });
+            found.extend(result);
+        }
+        found.sort();
+        assert_eq!(found, ids);
+
+        let mut found = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let (result, total, next) = store.search_cursor("is", &cursor, 7, SearchOptions::default()).unwrap();
+            assert_eq!(total, 20);
+            found.extend(result);
+            match next {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+        found.sort();
+        assert_eq!(found, ids);
+
+        // documents sharing a value are collapsed across shards
+        let options = SearchOptions {
+            dedup_by: Some("word".to_string()),
+            ..Default::default()
+        };
+        let (result, total) = store.search("is", 0, 10, options).unwrap();
+        assert_eq!((result.len(), total), (1, 1));
+
+        let facets = store.facets("is", &["id".to_string()]).unwrap();
+        assert_eq!(facets["id"].len(), 20);
+        assert!(facets["id"].iter().all(|facet| facet.count == 1));
+
+        let result = store
+            .aggregate(
+                "is",
+                &BTreeMap::from([("length".to_string(), Aggregation::stats("length"))]),
+            )
+            .unwrap();
+        assert_eq!(
+            result["length"],
+            AggregationResult::Stats(NumericStats {
+                count: 20,
+                sum: 240.0,
+                min: Some(12.0),
+                max: Some(12.0),
+                avg: Some(12.0),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_facets() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is great too")
+            .unwrap();
+
+        writer.commit().unwrap();
+
+        let facets = store.facets("is", &["id".to_string()]).unwrap();
+        let mut ids = facets["id"].clone();
+        ids.sort_by(|a, b| a.value.cmp(&b.value));
+        assert_eq!(
+            ids,
+            vec![
+                FacetCount {
+                    value: "bar".to_string(),
+                    count: 1
+                },
+                FacetCount {
+                    value: "foo".to_string(),
+                    count: 1
+                },
+            ]
+        );
+
+        assert!(matches!(
+            store.facets("is", &["text".to_string()]),
+            Err(Error::NotFacetable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pivot() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is great too")
+            .unwrap();
+
+        writer.commit().unwrap();
+
+        let mut pivot = store.pivot("is", "id", "id").unwrap();
+        pivot.sort_by(|a, b| a.value.cmp(&b.value));
+        assert_eq!(
+            pivot,
+            vec![
+                FacetPivot {
+                    value: "bar".to_string(),
+                    count: 1,
+                    facets: vec![FacetCount {
+                        value: "bar".to_string(),
+                        count: 1
+                    }],
+                },
+                FacetPivot {
+                    value: "foo".to_string(),
+                    count: 1,
+                    facets: vec![FacetCount {
+                        value: "foo".to_string(),
+                        count: 1
+                    }],
+                },
+            ]
+        );
+
+        assert!(matches!(store.pivot("is", "id", "text"), Err(Error::NotFacetable(_))));
+    }
+
+    #[tokio::test]
+    async fn test_aggregate() {
+        let _ = env_logger::try_init();
+        let mut store = IndexStore::new_in_memory(TestIndex::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Foo is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"Bar is great too")
+            .unwrap();
+
+        writer.commit().unwrap();
+
+        let aggregations = BTreeMap::from([
+            (
+                "ids".to_string(),
+                Aggregation::Terms {
+                    facet: "id".to_string(),
+                    size: 1,
+                },
+            ),
+            ("length".to_string(), Aggregation::stats("length")),
+        ]);
+        let result = store.aggregate("is", &aggregations).unwrap();
+
+        let AggregationResult::Terms(ids) = &result["ids"] else {
+            panic!("must be terms");
+        };
+        assert_eq!(ids.len(), 1);
+        assert_eq!(
+            result["length"],
+            AggregationResult::Stats(NumericStats {
+                count: 2,
+                sum: 28.0,
+                min: Some(12.0),
+                max: Some(16.0),
+                avg: Some(14.0),
+            })
+        );
+
+        // no values, no statistics
+        let result = store
+            .aggregate(
+                "nothing",
+                &BTreeMap::from([("length".to_string(), Aggregation::stats("length"))]),
+            )
+            .unwrap();
+        assert_eq!(
+            result["length"],
+            AggregationResult::Stats(NumericStats {
+                count: 0,
+                ..Default::default()
+            })
+        );
+
+        assert!(matches!(
+            store.aggregate("is", &BTreeMap::from([("id".to_string(), Aggregation::stats("id"))])),
+            Err(Error::NoStatistics(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_synonyms() {
+        let _ = env_logger::try_init();
+        let synonyms = Synonyms::parse("k8s, kubernetes").unwrap();
+        let mut store = IndexStore::new_in_memory(TestIndex::new())
+            .unwrap()
+            .with_synonyms(synonyms);
+        let mut writer = store.writer().unwrap();
+
+        writer
+            .add_document(store.index_as_mut(), "foo", b"Kubernetes is great")
+            .unwrap();
+        writer
+            .add_document(store.index_as_mut(), "bar", b"K8s is great too")
+            .unwrap();
+
+        writer.commit().unwrap();
+
+        assert_eq!(store.search("k8s", 0, 10, SearchOptions::default()).unwrap().1, 2);
+        assert_eq!(
+            store.search("Kubernetes", 0, 10, SearchOptions::default()).unwrap().1,
+            2
+        );
+        assert_eq!(store.search("great", 0, 10, SearchOptions::default()).unwrap().1, 2);
+    }
+
     #[tokio::test]
     async fn test_duplicates() {
         let _ = env_logger::try_init();
@@ -1450,6 +3096,49 @@ mod tests {
         assert_eq!(bad.state, IndexState::A);
     }
 
+    #[tokio::test]
+    async fn test_directory_restore() {
+        let _ = env_logger::try_init();
+
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING | FAST | STORED);
+        let schema = schema.build();
+
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("index.{}", r));
+        let mut dir = IndexDirectory::new(&dir).unwrap();
+
+        let store = dir
+            .build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let mut w = store.writer(15_000_000).unwrap();
+        w.add_document(doc!(id => "foo")).unwrap();
+        w.commit().unwrap();
+        w.wait_merging_threads().unwrap();
+        let snapshot = dir.pack().unwrap();
+
+        // syncing the same data again keeps the index, restoring it replaces the index
+        let store = dir
+            .sync(schema.clone(), Default::default(), Default::default(), &snapshot)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dir.state, IndexState::B);
+        let mut w = store.writer(15_000_000).unwrap();
+        w.add_document(doc!(id => "bar")).unwrap();
+        w.commit().unwrap();
+        w.wait_merging_threads().unwrap();
+        assert!(dir
+            .sync(schema.clone(), Default::default(), Default::default(), &snapshot)
+            .unwrap()
+            .is_none());
+
+        let store = dir
+            .restore(schema, Default::default(), Default::default(), &snapshot)
+            .unwrap();
+        assert_eq!(dir.state, IndexState::A);
+        assert_eq!(store.reader().unwrap().searcher().num_docs(), 1);
+    }
+
     #[tokio::test]
     async fn test_index_dir_reset() {
         let _ = env_logger::try_init();
@@ -1482,4 +3171,86 @@ mod tests {
         assert_eq!(store.reader().unwrap().searcher().num_docs(), 1);
         assert_eq!(clean.reader().unwrap().searcher().num_docs(), 0);
     }
+
+    #[tokio::test]
+    async fn test_index_dir_prepare() {
+        let _ = env_logger::try_init();
+
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING | FAST | STORED);
+        let schema = schema.build();
+
+        let r = rand::thread_rng().next_u32();
+        let dir = std::env::temp_dir().join(format!("index.{}", r));
+        let dir = IndexDirectory::new(&dir).unwrap();
+
+        let store = dir
+            .build(Default::default(), schema.clone(), Default::default())
+            .unwrap();
+        let mut w = store.writer(15_000_000).unwrap();
+        w.add_document(doc!(id => "foo")).unwrap();
+        w.commit().unwrap();
+
+        // the next generation is written while the current one can still be written to
+        let (next, state) = dir.prepare(Default::default(), schema, Default::default()).unwrap();
+        assert_eq!(state, IndexState::B);
+        assert_eq!(dir.state, IndexState::A);
+        let mut next_writer = next.writer(15_000_000).unwrap();
+        next_writer.add_document(doc!(id => "bar")).unwrap();
+        next_writer.commit().unwrap();
+        w.add_document(doc!(id => "baz")).unwrap();
+        w.add_document(doc!(id => "qux")).unwrap();
+        w.commit().unwrap();
+
+        assert_eq!(store.reader().unwrap().searcher().num_docs(), 3);
+        assert_eq!(next.reader().unwrap().searcher().num_docs(), 1);
+    }
+
+    #[test]
+    fn test_search_field() {
+        let mut schema = Schema::builder();
+        let id = schema.add_text_field("id", STRING);
+        let created = schema.add_date_field("created", INDEXED);
+        let score = schema.add_f64_field("score", INDEXED);
+        let schema = schema.build();
+
+        let field = search_field(&schema, "id", id, "id:foo").with_default();
+        assert_eq!(field.r#type, SearchFieldType::Text);
+        assert!(field.default);
+        assert!(!field.sortable);
+        assert_eq!(
+            search_field(&schema, "created", created, "created:>2023-01-01").r#type,
+            SearchFieldType::Date
+        );
+        assert_eq!(
+            search_field(&schema, "score", score, "score:>7").r#type,
+            SearchFieldType::Number
+        );
+        assert_eq!(SearchField::predicate("signed").example, "is:signed");
+    }
+
+    #[test]
+    fn test_histogram_durations() {
+        let params = |interval: &str, offset: Option<&str>| HistogramParams {
+            q: String::new(),
+            field: None,
+            interval: interval.to_string(),
+            offset: offset.map(ToString::to_string),
+            facet: None,
+        };
+        assert_eq!(
+            histogram_durations(&params("7d", Some("4d"))).unwrap(),
+            (Duration::from_secs(7 * 86400), Duration::from_secs(4 * 86400))
+        );
+        assert_eq!(
+            histogram_durations(&params("1h", None)).unwrap(),
+            (Duration::from_secs(3600), Duration::ZERO)
+        );
+        for (interval, offset) in [("0s", None), ("a week", None), ("7d", Some("monday"))] {
+            assert!(matches!(
+                histogram_durations(&params(interval, offset)),
+                Err(Error::InvalidInterval(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,184 @@
+//! Wildcard and regular expression values.
+//!
+//! Values containing a `*`, like `openssl*`, match terms with any characters in place of the wildcard, while values
+//! enclosed in slashes, like `/CVE-2023-[0-9]+/`, match terms by a regular expression, see [`pattern_value`]. Both
+//! match whole terms, so that a pattern of a text field matches single words. Regular expressions are matched against
+//! the terms as they are indexed, like lowercase words of text fields.
+//!
+//! To prevent scanning all terms of a field, patterns need to start with a literal prefix of at least
+//! [`MIN_PREFIX_LEN`] characters, like `ope*`. Queries violating that are rejected when they are prepared, see
+//! [`create_pattern_query`].
+
+use crate::{timeout::DeadlineRegexQuery, QueryContext};
+use tantivy::query::{EmptyQuery, Query, RegexQuery};
+use tantivy::schema::Field;
+use tantivy_fst::Regex;
+
+/// The minimum number of literal characters patterns have to start with.
+pub const MIN_PREFIX_LEN: usize = 3;
+
+/// A value matching terms by a pattern.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pattern<'a> {
+    /// A value with `*` wildcards
+    Wildcard(&'a str),
+    /// A regular expression, without the enclosing slashes
+    Regex(&'a str),
+}
+
+/// Characters with a special meaning in regular expressions.
+const META: &[char] = &['\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$'];
+
+impl Pattern<'_> {
+    /// The regular expression matching terms, converting the literal parts of wildcards to the case of the field.
+    pub fn to_regex(&self, to_value: impl Fn(&str) -> String) -> String {
+        match self {
+            Self::Wildcard(value) => value
+                .split('*')
+                .map(|literal| {
+                    let mut escaped = String::with_capacity(literal.len());
+                    for c in to_value(literal).chars() {
+                        if META.contains(&c) {
+                            escaped.push('\\');
+                        }
+                        escaped.push(c);
+                    }
+                    escaped
+                })
+                .collect::<Vec<_>>()
+                .join(".*"),
+            Self::Regex(regex) => regex.to_string(),
+        }
+    }
+
+    /// The number of literal characters every match starts with.
+    pub fn prefix_len(&self) -> usize {
+        match self {
+            Self::Wildcard(value) => value.chars().take_while(|c| *c != '*').count(),
+            Self::Regex(regex) => regex_prefix_len(regex),
+        }
+    }
+}
+
+/// The number of literal characters matches of a regular expression start with.
+fn regex_prefix_len(regex: &str) -> usize {
+    // alternatives don't share a prefix
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut class = false;
+    for c in regex.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => class = true,
+            ']' => class = false,
+            _ if class => {}
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '|' if depth == 0 => return 0,
+            _ => {}
+        }
+    }
+
+    let chars: Vec<char> = regex.chars().collect();
+    let len = chars.iter().position(|c| META.contains(c)).unwrap_or(chars.len());
+    // a quantifier makes the character before it optional
+    match chars.get(len) {
+        Some('?' | '*' | '{') => len.saturating_sub(1),
+        _ => len,
+    }
+}
+
+/// Split the pattern off a value, returning `None` for values without patterns.
+///
+/// Values enclosed in slashes are regular expressions, other values containing a `*` are wildcards.
+pub fn pattern_value(value: &str) -> Option<Pattern<'_>> {
+    if let Some(regex) = value.strip_prefix('/').and_then(|value| value.strip_suffix('/')) {
+        if !regex.is_empty() {
+            return Some(Pattern::Regex(regex));
+        }
+    }
+    value.contains('*').then_some(Pattern::Wildcard(value))
+}
+
+fn reject(reason: String, context: &QueryContext) -> Box<dyn Query> {
+    log::debug!("Rejected pattern: {reason}");
+    context.reject(reason);
+    Box::new(EmptyQuery)
+}
+
+/// Create a query matching the terms of a pattern, or rejecting the pattern, see [`MIN_PREFIX_LEN`].
+///
+/// Rejected patterns fail the query being prepared in the context, see [`QueryContext`].
+pub fn create_pattern_query(
+    field: Field,
+    pattern: Pattern<'_>,
+    to_value: impl Fn(&str) -> String,
+    context: &QueryContext,
+) -> Box<dyn Query> {
+    let regex = pattern.to_regex(to_value);
+    if pattern.prefix_len() < MIN_PREFIX_LEN {
+        return reject(
+            format!("pattern '{regex}' must start with at least {MIN_PREFIX_LEN} literal characters"),
+            context,
+        );
+    }
+    match Regex::new(&regex) {
+        Ok(automaton) => match context.deadline() {
+            // the terms of the field are walked when the query gets searched, which may take long
+            Some(deadline) => Box::new(DeadlineRegexQuery::new(field, automaton, deadline.clone())),
+            None => Box::new(RegexQuery::from_regex(automaton, field)),
+        },
+        Err(err) => reject(format!("invalid pattern '{regex}': {err}"), context),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_pattern_value() {
+        assert_eq!(pattern_value("openssl*"), Some(Pattern::Wildcard("openssl*")));
+        assert_eq!(pattern_value("/CVE-2023-.*/"), Some(Pattern::Regex("CVE-2023-.*")));
+        assert_eq!(pattern_value("openssl"), None);
+        assert_eq!(pattern_value("pkg:rpm/redhat/openssl"), None);
+        assert_eq!(pattern_value("//"), None);
+    }
+
+    #[test]
+    fn test_to_regex() {
+        let upper = |value: &str| value.to_uppercase();
+        assert_eq!(Pattern::Wildcard("cve-2023-*").to_regex(upper), "CVE-2023-.*");
+        assert_eq!(
+            Pattern::Wildcard("quarkus-*.jar").to_regex(str::to_string),
+            "quarkus-.*\\.jar"
+        );
+        assert_eq!(Pattern::Regex("[a-z]+").to_regex(upper), "[a-z]+");
+    }
+
+    #[test]
+    fn test_prefix_len() {
+        assert_eq!(Pattern::Wildcard("ope*ssl").prefix_len(), 3);
+        assert_eq!(Pattern::Wildcard("*ssl").prefix_len(), 0);
+        assert_eq!(Pattern::Regex("CVE-20[0-9]{2}").prefix_len(), 6);
+        assert_eq!(Pattern::Regex("openssl?").prefix_len(), 6);
+        assert_eq!(Pattern::Regex("open(ssl|ssh)").prefix_len(), 4);
+        assert_eq!(Pattern::Regex("openssl|.*").prefix_len(), 0);
+        assert_eq!(Pattern::Regex(".*ssl").prefix_len(), 0);
+    }
+
+    #[test]
+    fn test_reject() {
+        let field = Field::from_field_id(0);
+        let prepare = |value: &str| {
+            let context = QueryContext::default();
+            let _ = create_pattern_query(field, pattern_value(value).unwrap(), str::to_string, &context);
+            context.check()
+        };
+        assert!(prepare("ope*").is_ok());
+        assert!(matches!(prepare("op*"), Err(Error::QueryParser(_))));
+        assert!(matches!(prepare("/open[/"), Err(Error::QueryParser(_))));
+    }
+}
@@ -0,0 +1,172 @@
+//! Alternative syntax of range queries.
+//!
+//! Besides the ranges of the query language, like `created:>2024-01-01` and `created:2024-01-01..2024-06-30`,
+//! queries can use:
+//!
+//! * comparisons without a colon, like `created>2024-01-01` or `cvss>=7`
+//! * bracketed ranges, like `created:[2024-01-01 TO 2024-06-30]`, where square brackets include the bound, curly
+//!   brackets exclude it, and `*` leaves it open
+//!
+//! Those get rewritten to the query language before queries are parsed, see [`rewrite_ranges`].
+
+use std::borrow::Cow;
+
+/// Rewrite the alternative ranges of a query to the query language, leaving quoted values as they are.
+pub fn rewrite_ranges(q: &str) -> Cow<'_, str> {
+    let mut result = String::with_capacity(q.len());
+    let mut rest = q;
+    let mut changed = false;
+    // whether a qualifier can start at the current position
+    let mut boundary = true;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            let len = quoted_len(rest);
+            result.push_str(&rest[..len]);
+            rest = &rest[len..];
+            boundary = false;
+            continue;
+        }
+        if boundary {
+            if let Some((rewritten, len)) = rewrite_range(rest) {
+                result.push_str(&rewritten);
+                rest = &rest[len..];
+                changed = true;
+                boundary = false;
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+        boundary = c.is_whitespace() || c == '(' || c == '-';
+    }
+
+    match changed {
+        true => result.into(),
+        false => q.into(),
+    }
+}
+
+/// The length of the quoted value the text starts with, including its quotes.
+fn quoted_len(s: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Rewrite the range the text starts with, returning the rewritten range and the length of the original one.
+fn rewrite_range(s: &str) -> Option<(String, usize)> {
+    let name_len = s.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(s.len());
+    if name_len == 0 {
+        return None;
+    }
+    let (name, rest) = s.split_at(name_len);
+
+    if let Some(range) = rest.strip_prefix(':') {
+        let from_op = match range.chars().next()? {
+            '[' => ">=",
+            '{' => ">",
+            _ => return None,
+        };
+        let end = range.find([']', '}'])?;
+        let to_op = match range[end..].starts_with(']') {
+            true => "<=",
+            false => "<",
+        };
+        let (from, to) = range[1..end].split_once(" TO ")?;
+        let rewritten = match (bound(name, from_op, from), bound(name, to_op, to)) {
+            (Some(from), Some(to)) => format!("({from} {to})"),
+            (Some(bound), None) | (None, Some(bound)) => bound,
+            (None, None) => return None,
+        };
+        Some((rewritten, name_len + 1 + end + 1))
+    } else {
+        let op_len = if rest.starts_with(">=") || rest.starts_with("<=") {
+            2
+        } else if rest.starts_with(['>', '<']) {
+            1
+        } else {
+            return None;
+        };
+        let value = &rest[op_len..];
+        // only dates and numbers are compared, leaving other text as it is
+        let value_len = match value.chars().next()? {
+            '"' => quoted_len(value),
+            c if c.is_ascii_digit() => value
+                .find(|c: char| c.is_whitespace() || c == ')')
+                .unwrap_or(value.len()),
+            _ => return None,
+        };
+        Some((
+            format!("{name}:{}{}", &rest[..op_len], &value[..value_len]),
+            name_len + op_len + value_len,
+        ))
+    }
+}
+
+/// A bound of a bracketed range, or `None` if it is open.
+fn bound(name: &str, op: &str, value: &str) -> Option<String> {
+    match value.trim() {
+        "" | "*" => None,
+        // values like timestamps need quotes
+        value if value.contains(':') && !value.starts_with('"') => Some(format!("{name}:{op}\"{value}\"")),
+        value => Some(format!("{name}:{op}{value}")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(rewrite_ranges("created>2024-01-01"), "created:>2024-01-01");
+        assert_eq!(rewrite_ranges("cvss>=7 is:high"), "cvss:>=7 is:high");
+        assert_eq!(
+            rewrite_ranges("(release<2024-01-01 OR -initial<=2023-01-01)"),
+            "(release:<2024-01-01 OR -initial:<=2023-01-01)"
+        );
+        assert_eq!(
+            rewrite_ranges(r#"created>"2024-01-01T00:00:00Z""#),
+            r#"created:>"2024-01-01T00:00:00Z""#
+        );
+    }
+
+    #[test]
+    fn test_brackets() {
+        assert_eq!(
+            rewrite_ranges("release:[2024-01-01 TO 2024-06-30]"),
+            "(release:>=2024-01-01 release:<=2024-06-30)"
+        );
+        assert_eq!(
+            rewrite_ranges("openssl release:{2024-01-01 TO 2024-06-30}"),
+            "openssl (release:>2024-01-01 release:<2024-06-30)"
+        );
+        assert_eq!(rewrite_ranges("cvss:[7 TO *]"), "cvss:>=7");
+        assert_eq!(
+            rewrite_ranges("created:[* TO 2024-01-01T12:00:00Z}"),
+            r#"created:<"2024-01-01T12:00:00Z""#
+        );
+    }
+
+    #[test]
+    fn test_unchanged() {
+        for q in [
+            "created:>2024-01-01",
+            "release:2022-01-01..2023-01-01",
+            r#""a>1" in:title"#,
+            "a<b",
+            "cvss:[* TO *]",
+            "title:[foo]",
+        ] {
+            assert!(matches!(rewrite_ranges(q), Cow::Borrowed(_)), "{q}");
+        }
+    }
+}
@@ -0,0 +1,756 @@
+//! Sharding of indexes by the hash of the document identifiers.
+//!
+//! A sharded index is split into several indexes, each holding the documents whose identifiers hash to it, see
+//! [`Shard`]. Each shard gets committed, snapshotted and synced on its own, keeping them small for large corpora. The
+//! indexer routes every document to its shard by its identifier, before parsing it, while services query the shards in
+//! parallel and merge their results, see [`ShardedIndexStore`].
+//!
+//! An index of a single shard is the same as an index which isn't sharded.
+
+use crate::{
+    cache::{SearchCache, SearchKey},
+    cursor, dedup, histogram_durations, parse_aggregations, timeout, Aggregation, Error, Index, IndexConfig, IndexMode,
+    IndexStore, QueryContext, Rank, SearchQuery, WriteIndex,
+};
+use prometheus::Registry;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tantivy::{
+    collector::Count, query::Query, schema::Schema, tokenizer::TokenizerManager, DocAddress, Document, IndexSettings,
+    Order, Searcher, Term,
+};
+use trustification_api::search::{
+    AggregationResult, FacetPivot, Facets, HistogramBucket, HistogramParams, SearchField, SearchOptions,
+};
+use trustification_storage::{Labels, Storage, StorageConfig};
+
+/// The shard of an index, holding the documents whose identifiers hash to it.
+///
+/// Shards are named after the index and their number, like `v11y-shard-1`, except for indexes of a single shard, which
+/// keep the name of the index.
+pub struct Shard<INDEX> {
+    index: INDEX,
+    name: String,
+    shard: usize,
+    shards: usize,
+}
+
+impl<INDEX: WriteIndex> Shard<INDEX> {
+    /// Create a shard of an index, out of a number of shards.
+    pub fn new(index: INDEX, shard: usize, shards: usize) -> Self {
+        let name = match shards {
+            1 => index.name().to_string(),
+            _ => format!("{}-shard-{shard}", index.name()),
+        };
+        Self {
+            index,
+            name,
+            shard,
+            shards,
+        }
+    }
+
+    /// The index the shard is a part of.
+    pub fn index(&self) -> &INDEX {
+        &self.index
+    }
+}
+
+/// The shard a document belongs to, by the hash of its identifier.
+pub fn shard_of(id: &str, shards: usize) -> usize {
+    let digest = Sha256::digest(id.as_bytes());
+    let mut hash = [0u8; 8];
+    hash.copy_from_slice(&digest[0..8]);
+    (u64::from_be_bytes(hash) % shards.max(1) as u64) as usize
+}
+
+/// Split an index into the number of shards configured, creating the index of each shard by a function.
+///
+/// Sharded indexes must be stored in files, as indexes stored in S3 share the same bucket.
+pub fn split_shards<INDEX: WriteIndex>(
+    config: &IndexConfig,
+    index: impl Fn() -> INDEX,
+) -> Result<Vec<Shard<INDEX>>, Error> {
+    if config.shards == 0 {
+        return Err(Error::Open("the number of shards must not be zero".to_string()));
+    }
+    if config.shards > 1 && matches!(config.mode, IndexMode::S3) {
+        return Err(Error::Open("sharded indexes must be stored in files".to_string()));
+    }
+    Ok((0..config.shards)
+        .map(|shard| Shard::new(index(), shard, config.shards))
+        .collect())
+}
+
+/// Fail for services not supporting sharding, unless their index is configured as a single shard.
+pub fn require_unsharded(config: &IndexConfig, name: &str) -> Result<(), Error> {
+    match config.shards {
+        1 => Ok(()),
+        shards => Err(Error::Open(format!(
+            "the {name} index doesn't support sharding, but is configured with {shards} shards"
+        ))),
+    }
+}
+
+impl<INDEX: WriteIndex> WriteIndex for Shard<INDEX> {
+    type Document = INDEX::Document;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tokenizers(&self) -> Result<TokenizerManager, Error> {
+        self.index.tokenizers()
+    }
+
+    fn parse_doc(&self, data: &[u8]) -> Result<Self::Document, Error> {
+        self.index.parse_doc(data)
+    }
+
+    fn settings(&self) -> IndexSettings {
+        self.index.settings()
+    }
+
+    fn schema(&self) -> Schema {
+        self.index.schema()
+    }
+
+    fn index_doc(&self, id: &str, document: &Self::Document) -> Result<Vec<(String, Document)>, Error> {
+        if !self.contains(id) {
+            return Ok(Vec::new());
+        }
+        self.index.index_doc(id, document)
+    }
+
+    fn index_labeled_doc(
+        &self,
+        id: &str,
+        document: &Self::Document,
+        labels: &Labels,
+    ) -> Result<Vec<(String, Document)>, Error> {
+        if !self.contains(id) {
+            return Ok(Vec::new());
+        }
+        self.index.index_labeled_doc(id, document, labels)
+    }
+
+    fn doc_id_to_term(&self, id: &str) -> Term {
+        self.index.doc_id_to_term(id)
+    }
+
+    /// Whether a document belongs to the shard, by its identifier.
+    fn contains(&self, id: &str) -> bool {
+        shard_of(id, self.shards) == self.shard && self.index.contains(id)
+    }
+}
+
+impl<INDEX: Index> Index for Shard<INDEX> {
+    type MatchedDocument = INDEX::MatchedDocument;
+
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, Error> {
+        self.index.prepare_query(q, context)
+    }
+
+    fn search(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        offset: usize,
+        limit: usize,
+        context: &QueryContext,
+    ) -> Result<(Vec<(f32, DocAddress)>, usize), Error> {
+        self.index.search(searcher, query, offset, limit, context)
+    }
+
+    fn process_hit(
+        &self,
+        doc: DocAddress,
+        score: f32,
+        searcher: &Searcher,
+        query: &dyn Query,
+        options: &SearchOptions,
+    ) -> Result<Self::MatchedDocument, Error> {
+        self.index.process_hit(doc, score, searcher, query, options)
+    }
+
+    fn facet_field(&self, name: &str) -> Option<tantivy::schema::Field> {
+        self.index.facet_field(name)
+    }
+
+    fn sort_field(&self, name: &str) -> Option<tantivy::schema::Field> {
+        self.index.sort_field(name)
+    }
+
+    fn histogram_field(&self, name: &str) -> Option<tantivy::schema::Field> {
+        self.index.histogram_field(name)
+    }
+
+    fn stats_field(&self, name: &str) -> Option<tantivy::schema::Field> {
+        self.index.stats_field(name)
+    }
+
+    fn dedup_field(&self, name: &str) -> Option<tantivy::schema::Field> {
+        self.index.dedup_field(name)
+    }
+
+    fn qualifiers(&self) -> &[&'static str] {
+        self.index.qualifiers()
+    }
+
+    fn qualifier_field(&self, name: &str) -> Option<SearchField> {
+        self.index.qualifier_field(name)
+    }
+}
+
+/// An index split into shards, which get queried in parallel, merging their results.
+pub struct ShardedIndexStore<INDEX> {
+    shards: Vec<IndexStore<Shard<INDEX>>>,
+    /// The cache of searches merging the results of several shards
+    cache: Option<SearchCache>,
+}
+
+impl<INDEX: WriteIndex + 'static> ShardedIndexStore<INDEX> {
+    /// Create the stores of the shards configured, creating the index of each shard by a function.
+    pub fn new(
+        storage: &StorageConfig,
+        config: &IndexConfig,
+        index: impl Fn() -> INDEX,
+        metrics_registry: &Registry,
+    ) -> Result<Self, Error> {
+        // only the merged results are cached, caching the results of each shard as well would only take memory
+        let shard_config = IndexConfig {
+            search_cache_size: 0,
+            ..config.clone()
+        };
+        let shards = split_shards(config, index)?
+            .into_iter()
+            .map(|shard| IndexStore::new(storage, &shard_config, shard, metrics_registry))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            shards,
+            cache: SearchCache::new(config.search_cache_size),
+        })
+    }
+
+    pub fn new_in_memory(index: impl Fn() -> INDEX, shards: usize) -> Result<Self, Error> {
+        let shards = shards.max(1);
+        let shards = (0..shards)
+            .map(|shard| IndexStore::new_in_memory(Shard::new(index(), shard, shards)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { shards, cache: None })
+    }
+
+    /// The stores of the shards, like for writing documents to each of them.
+    pub fn shards(&self) -> &[IndexStore<Shard<INDEX>>] {
+        &self.shards
+    }
+
+    pub fn shards_mut(&mut self) -> &mut [IndexStore<Shard<INDEX>>] {
+        &mut self.shards
+    }
+
+    /// Sync all shards from their snapshots.
+    ///
+    /// NOTE: Only applicable for file indices.
+    pub async fn sync(&self, storage: &Storage) -> Result<(), Error> {
+        for shard in &self.shards {
+            shard.sync(storage).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<INDEX: Index + Sync> ShardedIndexStore<INDEX> {
+    /// The shard building the requests of aggregations, which is the same for all shards.
+    fn primary(&self) -> &IndexStore<Shard<INDEX>> {
+        &self.shards[0]
+    }
+
+    /// Run a function for each shard in parallel, returning the results in the order of the shards.
+    fn par_shards<R: Send>(&self, f: impl Fn(&IndexStore<Shard<INDEX>>) -> R + Sync) -> Vec<R> {
+        par_map(&self.shards, |_, shard| f(shard))
+    }
+
+    /// Search all shards for a given query and return the matching documents, merged by their rank.
+    pub fn search(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        if let [shard] = self.shards.as_slice() {
+            return shard.search(q, offset, limit, options);
+        }
+        let Some(cache) = &self.cache else {
+            return self.search_shards(q, offset, limit, options);
+        };
+
+        let searchers = self
+            .shards
+            .iter()
+            .map(|shard| shard.searcher())
+            .collect::<Result<Vec<_>, _>>()?;
+        let generation = fingerprint(&searchers);
+        let key = SearchKey {
+            q: q.to_string(),
+            offset,
+            limit,
+            options,
+        };
+        if let Some(result) = cache.get(&key, generation) {
+            // hits of all shards are counted by the first one
+            self.primary().metrics.search_cache_hits_total.inc();
+            return Ok(result);
+        }
+
+        let result = self.search_shards(q, offset, limit, key.options.clone())?;
+        cache.insert(key, generation, result.clone());
+        Ok(result)
+    }
+
+    fn search_shards(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        if limit == 0 {
+            return Err(Error::InvalidLimitParameter(limit));
+        }
+        if let Some(dedup_by) = &options.dedup_by {
+            return self.search_dedup(q, offset, limit, dedup_by, &options);
+        }
+
+        let results = self.par_shards(|shard| {
+            let _latency = shard.metrics.query_latency_seconds.start_timer();
+            let searcher = shard.searcher()?;
+            let query = shard.prepare_search(q, &options)?;
+            // any shard may hold all documents up to the end of the page
+            let (docs, count) = shard.top_docs(&searcher, &query, 0, offset + limit)?;
+            timeout::check(&*query.query)?;
+            shard.metrics.queries_total.inc();
+            Ok::<_, Error>((searcher, query, docs, count))
+        });
+
+        let mut searched = Vec::with_capacity(results.len());
+        let mut docs = Vec::new();
+        let mut total = 0;
+        for (i, result) in results.into_iter().enumerate() {
+            let (searcher, query, top_docs, count) = result?;
+            total += count;
+            docs.extend(top_docs.into_iter().map(|(rank, doc)| (i, rank, doc)));
+            searched.push((searcher, query));
+        }
+
+        let order = searched[0].1.sort_by.as_ref().map(|(_, order)| order.clone());
+        docs.sort_by(|a, b| compare(&a.1, &b.1, order.as_ref()));
+
+        log::info!("#matches={total} for query '{q}' ({} shards)", self.shards.len());
+
+        if !options.summaries {
+            return Ok((Vec::new(), total));
+        }
+
+        let mut hits = Vec::new();
+        for (i, rank, doc) in docs.into_iter().skip(offset).take(limit) {
+            let (searcher, query) = &searched[i];
+            hits.extend(self.shards[i].process_hits(searcher, &query.query, [(rank.score(), doc)], &options));
+        }
+        Ok((hits, total))
+    }
+
+    /// Search all shards for a given query, collapsing the documents sharing the value of a field across all shards.
+    fn search_dedup(
+        &self,
+        q: &str,
+        offset: usize,
+        limit: usize,
+        dedup_by: &str,
+        options: &SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize), Error> {
+        let field = self.primary().dedup_field(dedup_by)?;
+        let searched = self
+            .par_shards(|shard| {
+                let searcher = shard.searcher()?;
+                let query = shard.prepare_search(q, options)?;
+                let keys = dedup::Keys::new(&searcher, &field)?;
+                let distinct = searcher.search(&query.query, &dedup::DistinctCollector { field: field.clone() })?;
+                shard.metrics.queries_total.inc();
+                Ok::<_, Error>((searcher, query, keys, distinct))
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // documents sharing a key may be held by different shards
+        let mut distinct = dedup::Distinct::default();
+        let searched: Vec<_> = searched
+            .into_iter()
+            .map(|(searcher, query, keys, shard)| {
+                distinct.merge(shard);
+                (searcher, query, keys)
+            })
+            .collect();
+        let total = distinct.count();
+
+        let order = searched[0].1.sort_by.as_ref().map(|(_, order)| order.clone());
+        let docs = dedup::dedup(
+            offset,
+            limit,
+            |window| {
+                let results = par_map(&searched, |i, (searcher, query, _)| {
+                    self.shards[i].top_docs(searcher, query, 0, window)
+                });
+                let mut docs = Vec::new();
+                for (i, result) in results.into_iter().enumerate() {
+                    docs.extend(result?.0.into_iter().map(|(rank, doc)| (i, rank, doc)));
+                }
+                docs.sort_by(|a, b| compare(&a.1, &b.1, order.as_ref()));
+                docs.truncate(window);
+                Ok(docs)
+            },
+            |(i, _, doc)| searched[*i].2.get(*doc),
+        )?;
+        for (_, query, _) in &searched {
+            timeout::check(&*query.query)?;
+        }
+
+        log::info!(
+            "#matches={total} for query '{q}' ({} shards, deduplicated)",
+            self.shards.len()
+        );
+
+        if !options.summaries {
+            return Ok((Vec::new(), total));
+        }
+
+        let mut hits = Vec::new();
+        for (i, rank, doc) in docs {
+            let (searcher, query, _) = &searched[i];
+            hits.extend(self.shards[i].process_hits(searcher, &query.query, [(rank.score(), doc)], options));
+        }
+        Ok((hits, total))
+    }
+
+    /// Search all shards for a given query, iterating the matching documents using a cursor.
+    ///
+    /// Documents are returned shard by shard, in the order of the index, see [`IndexStore::search_cursor`]. The cursor
+    /// expires once any shard changes.
+    pub fn search_cursor(
+        &self,
+        q: &str,
+        cursor: &str,
+        limit: usize,
+        options: SearchOptions,
+    ) -> Result<(Vec<INDEX::MatchedDocument>, usize, Option<String>), Error> {
+        if let [shard] = self.shards.as_slice() {
+            return shard.search_cursor(q, cursor, limit, options);
+        }
+        if limit == 0 {
+            return Err(Error::InvalidLimitParameter(limit));
+        }
+        if options.sort.is_some() {
+            return Err(Error::QueryParser(
+                "a cursor cannot be combined with a sort order".to_string(),
+            ));
+        }
+        if options.dedup_by.is_some() {
+            return Err(Error::QueryParser(
+                "a cursor cannot be combined with deduplication".to_string(),
+            ));
+        }
+
+        let searchers = self
+            .shards
+            .iter()
+            .map(|shard| shard.searcher())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let fingerprint = fingerprint(&searchers);
+        let after = match cursor::Cursor::decode(cursor)? {
+            Some(cursor) if cursor.fingerprint != fingerprint => return Err(Error::CursorExpired),
+            Some(cursor) => Some(cursor.after),
+            None => None,
+        };
+
+        // segments are numbered across all shards, in the order of the shards
+        let mut base = 0;
+        let mut queries = Vec::with_capacity(self.shards.len());
+        let mut docs = Vec::new();
+        let mut total = 0;
+        for (i, (shard, searcher)) in self.shards.iter().zip(&searchers).enumerate() {
+            let query = shard.prepare_query(q)?;
+            let segments = searcher.segment_readers().len() as u32;
+            let (shard_after, done) = match after {
+                Some(after) if after.segment_ord >= base + segments => (None, true),
+                Some(after) if after.segment_ord >= base => {
+                    (Some(DocAddress::new(after.segment_ord - base, after.doc_id)), false)
+                }
+                _ => (None, false),
+            };
+
+            // fetch one more document to find out if there is a next page
+            let remaining = (limit + 1).saturating_sub(docs.len());
+            total += if done || remaining == 0 {
+                searcher.search(&query.query, &Count)?
+            } else {
+                let (found, count) = searcher.search(
+                    &query.query,
+                    &(
+                        cursor::CursorCollector {
+                            after: shard_after,
+                            limit: remaining,
+                        },
+                        Count,
+                    ),
+                )?;
+                docs.extend(found.into_iter().map(|doc| (i, base, doc)));
+                count
+            };
+
+            timeout::check(&*query.query)?;
+            base += segments;
+            queries.push(query);
+        }
+
+        let next = match docs.len() > limit {
+            true => {
+                docs.truncate(limit);
+                docs.last().map(|(_, base, doc)| {
+                    cursor::Cursor {
+                        fingerprint,
+                        after: DocAddress::new(base + doc.segment_ord, doc.doc_id),
+                    }
+                    .encode()
+                })
+            }
+            false => None,
+        };
+
+        log::info!(
+            "#matches={total} for query '{q}' (cursor, {} shards)",
+            self.shards.len()
+        );
+
+        let mut hits = Vec::new();
+        if options.summaries {
+            for (i, _, doc) in docs {
+                hits.extend(self.shards[i].process_hits(&searchers[i], &queries[i].query, [(1.0, doc)], &options));
+            }
+        }
+
+        Ok((hits, total, next))
+    }
+
+    /// The fields query terms can be qualified with.
+    pub fn search_fields(&self) -> Vec<SearchField> {
+        self.primary().search_fields()
+    }
+
+    /// Compute the bucket counts of the requested facets, for all documents of all shards matching a given query.
+    pub fn facets(&self, q: &str, facets: &[String]) -> Result<Facets, Error> {
+        self.primary()
+            .facets_with(facets, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute the bucket counts of a facet, splitting each bucket by the values of another facet, see
+    /// [`IndexStore::pivot`].
+    pub fn pivot(&self, q: &str, facet: &str, split: &str) -> Result<Vec<FacetPivot>, Error> {
+        self.primary()
+            .pivot_with(facet, split, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute the number of documents of all shards matching a query for each interval of a date field, see
+    /// [`IndexStore::histogram`].
+    pub fn histogram(
+        &self,
+        q: &str,
+        name: &str,
+        interval: Duration,
+        offset: Duration,
+        facet: Option<&str>,
+    ) -> Result<Vec<HistogramBucket>, Error> {
+        self.primary()
+            .histogram_with(name, interval, offset, facet, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Compute a histogram of all shards from the parameters of a histogram query, see [`IndexStore::histogram_for`].
+    pub fn histogram_for(&self, params: &HistogramParams, default_field: &str) -> Result<Vec<HistogramBucket>, Error> {
+        let (interval, offset) = histogram_durations(params)?;
+        let field = params.field.as_deref().unwrap_or(default_field);
+        self.histogram(&params.q, field, interval, offset, params.facet.as_deref())
+    }
+
+    /// Compute a set of named aggregations over all documents of all shards matching a given query.
+    pub fn aggregate(
+        &self,
+        q: &str,
+        aggregations: &BTreeMap<String, Aggregation>,
+    ) -> Result<BTreeMap<String, AggregationResult>, Error> {
+        self.primary()
+            .aggregate_with(aggregations, |aggs| self.run_aggregations(q, aggs))
+    }
+
+    /// Run aggregations on all shards in parallel, merging their intermediate results.
+    fn run_aggregations(&self, q: &str, aggs: serde_json::Value) -> Result<serde_json::Value, Error> {
+        let aggs = parse_aggregations(aggs)?;
+
+        let mut results = self
+            .par_shards(|shard| shard.collect_aggregations(q, &aggs))
+            .into_iter();
+        let mut merged = results.next().expect("an index has at least one shard")?;
+        for result in results {
+            merged.merge_fruits(result?)?;
+        }
+
+        let result = merged.into_final_result(aggs, &Default::default())?;
+        serde_json::to_value(result).map_err(|e| Error::QueryParser(e.to_string()))
+    }
+}
+
+/// Run a function for each item in parallel, along with its position, returning the results in the order of the items.
+fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(usize, &T) -> R + Sync) -> Vec<R> {
+    if let [item] = items {
+        return vec![f(0, item)];
+    }
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| scope.spawn(move || f(i, item)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| match handle.join() {
+                Ok(result) => result,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    })
+}
+
+/// Order the ranks of documents, by their descending score or by the order of the value they are sorted by.
+fn compare(a: &Rank, b: &Rank, order: Option<&Order>) -> Ordering {
+    let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+    match order {
+        Some(Order::Asc) => ordering,
+        _ => ordering.reverse(),
+    }
+}
+
+/// Fingerprint the state of all shards.
+fn fingerprint(searchers: &[Searcher]) -> u64 {
+    let mut digest = Sha256::new();
+    for searcher in searchers {
+        digest.update(cursor::fingerprint(searcher).to_be_bytes());
+    }
+    let digest = digest.finalize();
+    let mut result = [0u8; 8];
+    result.copy_from_slice(&digest[0..8]);
+    u64::from_be_bytes(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use tantivy::schema::{Field, STRING};
+
+    /// An index counting the documents it parsed.
+    struct CountingIndex {
+        schema: Schema,
+        id: Field,
+        parsed: AtomicUsize,
+    }
+
+    impl CountingIndex {
+        fn new() -> Self {
+            let mut schema = Schema::builder();
+            let id = schema.add_text_field("id", STRING);
+            Self {
+                schema: schema.build(),
+                id,
+                parsed: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl WriteIndex for CountingIndex {
+        type Document = ();
+
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn parse_doc(&self, _data: &[u8]) -> Result<Self::Document, Error> {
+            self.parsed.fetch_add(1, AtomicOrdering::Relaxed);
+            Ok(())
+        }
+
+        fn settings(&self) -> IndexSettings {
+            IndexSettings::default()
+        }
+
+        fn schema(&self) -> Schema {
+            self.schema.clone()
+        }
+
+        fn index_doc(&self, id: &str, _document: &Self::Document) -> Result<Vec<(String, Document)>, Error> {
+            Ok(vec![(id.to_string(), tantivy::doc!(self.id => id))])
+        }
+
+        fn doc_id_to_term(&self, id: &str) -> Term {
+            Term::from_field_text(self.id, id)
+        }
+    }
+
+    #[test]
+    fn test_route_before_parsing() {
+        let ids: Vec<_> = (0..20).map(|n| format!("CVE-2024-{n}")).collect();
+        let mut shards: Vec<_> = (0..4)
+            .map(|shard| IndexStore::new_in_memory(Shard::new(CountingIndex::new(), shard, 4)).unwrap())
+            .collect();
+        for store in &mut shards {
+            let mut writer = store.writer().unwrap();
+            for id in &ids {
+                writer.add_document(store.index_as_mut(), id, b"{}").unwrap();
+            }
+            writer.commit().unwrap();
+        }
+
+        for (shard, store) in shards.iter().enumerate() {
+            let owned = ids.iter().filter(|id| shard_of(id, 4) == shard).count();
+            assert_eq!(store.index().index().parsed.load(AtomicOrdering::Relaxed), owned);
+            assert_eq!(store.inner.read().reader().unwrap().searcher().num_docs(), owned as u64);
+        }
+    }
+
+    #[test]
+    fn test_shard_of() {
+        for id in ["CVE-2023-0286", "CVE-2024-1234", "GHSA-xxxx"] {
+            assert_eq!(shard_of(id, 1), 0);
+            assert!(shard_of(id, 4) < 4);
+            // stable across calls
+            assert_eq!(shard_of(id, 4), shard_of(id, 4));
+        }
+        let used: std::collections::HashSet<_> = (0..100).map(|n| shard_of(&format!("CVE-2024-{n}"), 4)).collect();
+        assert_eq!(used.len(), 4);
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare(&Rank::Score(2.0), &Rank::Score(1.0), None), Ordering::Less);
+        assert_eq!(
+            compare(&Rank::U64(2), &Rank::U64(1), Some(&Order::Asc)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare(&Rank::Str("a".into()), &Rank::Str("b".into()), Some(&Order::Desc)),
+            Ordering::Greater
+        );
+    }
+}
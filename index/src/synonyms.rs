@@ -0,0 +1,122 @@
+//! Expansion of query values by their synonyms.
+//!
+//! Synonyms are read from a file with one rule per line, ignoring empty lines and comments starting with `#`:
+//!
+//! * `k8s, kubernetes` makes all values of the line synonyms of each other
+//! * `jboss, wildfly => eap` makes the values on the right synonyms of the ones on the left, but not the other way
+//!   around
+//!
+//! Values are matched ignoring their case. Synonyms are applied to exact matches when the query is prepared, through
+//! the [`crate::QueryContext`] of the index, see [`crate::create_string_query_case`] and [`crate::create_text_query`].
+
+use crate::Error;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Synonyms of query values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Synonyms {
+    /// Synonyms by lowercase value
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+impl Synonyms {
+    /// Load synonyms from a file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| Error::Synonyms(format!("unable to read {}: {e}", path.display())))?;
+        Self::parse(&data)
+    }
+
+    /// Parse synonym rules.
+    pub fn parse(data: &str) -> Result<Self, Error> {
+        let mut synonyms = HashMap::<String, BTreeSet<String>>::new();
+        for (n, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let split = |values: &str| -> Vec<String> {
+                values
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            };
+            let (from, to) = match line.split_once("=>") {
+                Some((from, to)) => (split(from), split(to)),
+                None => {
+                    let values = split(line);
+                    (values.clone(), values)
+                }
+            };
+            if from.is_empty() || to.is_empty() || (from.len() == 1 && from == to) {
+                return Err(Error::Synonyms(format!("line {}: rule without synonyms", n + 1)));
+            }
+
+            for value in &from {
+                let key = value.to_lowercase();
+                let entry = synonyms.entry(key.clone()).or_default();
+                entry.extend(to.iter().filter(|to| to.to_lowercase() != key).cloned());
+            }
+        }
+
+        Ok(Self {
+            synonyms: synonyms
+                .into_iter()
+                .map(|(key, values)| (key, values.into_iter().collect()))
+                .collect(),
+        })
+    }
+
+    /// The synonyms of a value, without the value itself.
+    pub fn get(&self, value: &str) -> &[String] {
+        self.synonyms
+            .get(&value.to_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.synonyms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::QueryContext;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse() {
+        let synonyms = Synonyms::parse(
+            r#"
+            # container orchestration
+            k8s, Kubernetes
+            jboss, wildfly => EAP
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(synonyms.get("K8S"), ["Kubernetes".to_string()]);
+        assert_eq!(synonyms.get("kubernetes"), ["k8s".to_string()]);
+        assert_eq!(synonyms.get("wildfly"), ["EAP".to_string()]);
+        // rules with an arrow only apply one way
+        assert!(synonyms.get("eap").is_empty());
+        assert!(synonyms.get("quarkus").is_empty());
+
+        assert!(Synonyms::parse("k8s").is_err());
+        assert!(Synonyms::parse("k8s =>").is_err());
+    }
+
+    #[test]
+    fn test_context() {
+        let synonyms = Arc::new(Synonyms::parse("k8s, kubernetes").unwrap());
+        assert!(QueryContext::default().synonyms_of("k8s").is_empty());
+        let context = QueryContext::default().with_synonyms(Some(synonyms));
+        assert_eq!(context.synonyms_of("k8s"), ["kubernetes".to_string()]);
+    }
+}
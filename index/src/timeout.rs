@@ -0,0 +1,295 @@
+//! Query timeouts.
+//!
+//! Queries are wrapped into a [`TimeoutQuery`] when they are prepared, with the time budget starting at that point.
+//! Once the budget is spent, the scorers of the query stop matching documents, aborting the search instead of
+//! occupying the searching thread until all matching documents got collected. A search aborted that way fails with
+//! [`Error::Timeout`], see [`check`].
+//!
+//! Creating the scorers of some queries is expensive by itself, as patterns and fuzzy values get matched against the
+//! terms of the field. No more scorers get created once the budget is spent, and patterns stop walking the terms, see
+//! [`DeadlineAutomaton`].
+
+use crate::Error;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tantivy::{
+    query::{AutomatonWeight, EmptyScorer, EnableScoring, Explanation, Query, Scorer, Weight},
+    schema::Field,
+    DocId, DocSet, Score, SegmentReader, Term, TERMINATED,
+};
+use tantivy_fst::{Automaton, Regex};
+
+/// Number of documents a scorer advances, or of terms an automaton visits, between checks of the deadline, as reading
+/// the clock is not free.
+pub(crate) const CHECK_INTERVAL: u32 = 1024;
+
+/// The point in time a query has to be done by.
+#[derive(Debug)]
+pub(crate) struct Deadline {
+    timeout: Duration,
+    at: Instant,
+    /// Whether a scorer got aborted due to the deadline
+    exceeded: AtomicBool,
+}
+
+impl Deadline {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            at: Instant::now() + timeout,
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    /// Check if the deadline passed, marking it as exceeded if so.
+    fn passed(&self) -> bool {
+        if self.exceeded.load(Ordering::Relaxed) {
+            return true;
+        }
+        let passed = Instant::now() >= self.at;
+        if passed {
+            self.exceeded.store(true, Ordering::Relaxed);
+        }
+        passed
+    }
+}
+
+/// A query aborting the search once its deadline passed.
+#[derive(Clone, Debug)]
+pub(crate) struct TimeoutQuery {
+    pub(crate) query: Box<dyn Query>,
+    pub(crate) deadline: Arc<Deadline>,
+}
+
+impl Query for TimeoutQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(TimeoutWeight {
+            inner: self.query.weight(enable_scoring)?,
+            deadline: self.deadline.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+}
+
+/// Fail if the search of a query got aborted, as its deadline passed.
+pub(crate) fn check(query: &dyn Query) -> Result<(), Error> {
+    match query.downcast_ref::<TimeoutQuery>() {
+        Some(query) if query.deadline.exceeded.load(Ordering::Relaxed) => Err(Error::Timeout(query.deadline.timeout)),
+        _ => Ok(()),
+    }
+}
+
+struct TimeoutWeight {
+    inner: Box<dyn Weight>,
+    deadline: Arc<Deadline>,
+}
+
+impl Weight for TimeoutWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        if self.deadline.passed() {
+            return Ok(Box::new(EmptyScorer));
+        }
+        let inner = self.inner.scorer(reader, boost)?;
+        Ok(Box::new(TimeoutScorer {
+            inner,
+            deadline: self.deadline.clone(),
+            advanced: 0,
+            // creating the scorer may have spent the budget
+            aborted: self.deadline.passed(),
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.inner.explain(reader, doc)
+    }
+}
+
+/// A scorer terminating early once the deadline passed.
+struct TimeoutScorer {
+    inner: Box<dyn Scorer>,
+    deadline: Arc<Deadline>,
+    /// Number of documents advanced since the last check of the deadline
+    advanced: u32,
+    aborted: bool,
+}
+
+impl TimeoutScorer {
+    fn check(&mut self) {
+        self.advanced += 1;
+        if self.advanced >= CHECK_INTERVAL {
+            self.advanced = 0;
+            self.aborted = self.deadline.passed();
+        }
+    }
+}
+
+impl DocSet for TimeoutScorer {
+    fn advance(&mut self) -> DocId {
+        self.check();
+        if self.aborted {
+            return TERMINATED;
+        }
+        self.inner.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.check();
+        if self.aborted {
+            return TERMINATED;
+        }
+        self.inner.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        match self.aborted {
+            true => TERMINATED,
+            false => self.inner.doc(),
+        }
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.inner.size_hint()
+    }
+}
+
+impl Scorer for TimeoutScorer {
+    fn score(&mut self) -> Score {
+        self.inner.score()
+    }
+}
+
+/// An automaton no longer matching terms once the deadline passed.
+#[derive(Debug)]
+pub(crate) struct DeadlineAutomaton<A> {
+    inner: A,
+    deadline: Arc<Deadline>,
+    /// Number of states visited since the last check of the deadline
+    visited: AtomicU32,
+}
+
+impl<A> DeadlineAutomaton<A> {
+    pub(crate) fn new(inner: A, deadline: Arc<Deadline>) -> Self {
+        Self {
+            inner,
+            deadline,
+            visited: AtomicU32::new(0),
+        }
+    }
+
+    fn passed(&self) -> bool {
+        if self.deadline.exceeded.load(Ordering::Relaxed) {
+            return true;
+        }
+        self.visited.fetch_add(1, Ordering::Relaxed) % CHECK_INTERVAL == 0 && self.deadline.passed()
+    }
+}
+
+impl<A: Automaton> Automaton for DeadlineAutomaton<A> {
+    type State = A::State;
+
+    fn start(&self) -> Self::State {
+        self.inner.start()
+    }
+
+    fn is_match(&self, state: &Self::State) -> bool {
+        self.inner.is_match(state)
+    }
+
+    fn can_match(&self, state: &Self::State) -> bool {
+        !self.passed() && self.inner.can_match(state)
+    }
+
+    fn will_always_match(&self, state: &Self::State) -> bool {
+        self.inner.will_always_match(state)
+    }
+
+    fn accept(&self, state: &Self::State, byte: u8) -> Self::State {
+        self.inner.accept(state, byte)
+    }
+}
+
+/// A query matching the terms of a field by a regular expression, until the deadline passed.
+#[derive(Clone, Debug)]
+pub(crate) struct DeadlineRegexQuery {
+    field: Field,
+    automaton: Arc<DeadlineAutomaton<Regex>>,
+}
+
+impl DeadlineRegexQuery {
+    pub(crate) fn new(field: Field, regex: Regex, deadline: Arc<Deadline>) -> Self {
+        Self {
+            field,
+            automaton: Arc::new(DeadlineAutomaton::new(regex, deadline)),
+        }
+    }
+}
+
+impl Query for DeadlineRegexQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(AutomatonWeight::new(self.field, self.automaton.clone())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::patterns::{create_pattern_query, Pattern};
+    use crate::QueryContext;
+    use tantivy::{
+        collector::Count,
+        doc,
+        query::AllQuery,
+        schema::{Schema, STRING},
+        Index,
+    };
+
+    #[test]
+    fn test_check() {
+        let query = |timeout| TimeoutQuery {
+            query: Box::new(AllQuery),
+            deadline: Arc::new(Deadline::new(timeout)),
+        };
+
+        let passed = query(Duration::ZERO);
+        assert!(check(&passed).is_ok(), "only aborted searches fail");
+        assert!(passed.deadline.passed());
+        assert!(matches!(check(&passed), Err(Error::Timeout(_))));
+
+        let pending = query(Duration::from_secs(60));
+        assert!(!pending.deadline.passed());
+        assert!(check(&pending).is_ok());
+        assert!(check(&AllQuery).is_ok());
+    }
+
+    #[test]
+    fn test_slow_regex() {
+        let mut schema = Schema::builder();
+        let field = schema.add_text_field("name", STRING);
+        let index = Index::create_in_ram(schema.build());
+        let mut writer = index.writer(15_000_000).unwrap();
+        for i in 0..4 * CHECK_INTERVAL {
+            writer.add_document(doc!(field => format!("package-{i}"))).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        // a regular expression matched against all the terms of the field
+        let query = |timeout| {
+            let context = QueryContext::default().with_deadline(Some(Arc::new(Deadline::new(timeout))));
+            let query = create_pattern_query(field, Pattern::Regex("package-.*[0-9]"), str::to_string, &context);
+            (query, context)
+        };
+
+        let (pending, context) = query(Duration::from_secs(60));
+        assert_eq!(searcher.search(&pending, &Count).unwrap(), 4 * CHECK_INTERVAL as usize);
+        assert!(!context.deadline().unwrap().exceeded.load(Ordering::Relaxed));
+
+        let (passed, context) = query(Duration::ZERO);
+        assert_eq!(searcher.search(&passed, &Count).unwrap(), 0);
+        assert!(context.deadline().unwrap().exceeded.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,394 @@
+//! Tokenizers of text fields, which operators can configure per field.
+//!
+//! Indexes add the fields which can be tokenized differently through [`Tokenizers::add_text_field`], with the
+//! tokenizer they use by default, and operators override it with `<field>=<tokenizer>`, like `sbom_pkg_name=ngram`
+//! for substring matches of package names. Queries are analyzed by the same tokenizer as the text of the field, so
+//! changing the tokenizer of a field requires reindexing.
+
+use crate::{
+    create_string_query, create_text_query, fuzzy_value,
+    patterns::{create_pattern_query, pattern_value, Pattern},
+    QueryContext,
+};
+use sikula::prelude::Primary;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use tantivy::{
+    query::{BooleanQuery, EmptyQuery, FuzzyTermQuery, PhraseQuery, Query, TermQuery},
+    schema::{Field, IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions},
+    tokenizer::{
+        Language, LowerCaser, NgramTokenizer, RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter, TextAnalyzer,
+        TokenStream, TokenizerManager,
+    },
+    Term,
+};
+
+/// The name of the tokenizer of English text.
+const STEMMED: &str = "stemmed_en";
+/// The name of the tokenizer of substrings.
+const NGRAM: &str = "ngram";
+
+/// The minimum and maximum length of the substrings indexed by the ngram tokenizer.
+const NGRAM_SIZE: (usize, usize) = (2, 3);
+
+/// The tokenizer of a text field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Tokenizer {
+    /// Index the text as a whole, matching exact values only
+    Raw,
+    /// Lowercase the words of the text
+    Simple,
+    /// Stem English words, and skip stop words, so that `overflows` also matches `overflow`
+    Stemmed,
+    /// Index the lowercase substrings of the text, so that `ssl` matches `openssl`
+    Ngram,
+}
+
+impl Display for Tokenizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Raw => write!(f, "raw"),
+            Self::Simple => write!(f, "simple"),
+            Self::Stemmed => write!(f, "stemmed"),
+            Self::Ngram => write!(f, "ngram"),
+        }
+    }
+}
+
+impl Tokenizer {
+    /// The name the tokenizer is registered with, see [`tokenizer_manager`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Simple => "default",
+            Self::Stemmed => STEMMED,
+            Self::Ngram => NGRAM,
+        }
+    }
+
+    /// The options of a field, indexing its text with the tokenizer.
+    pub fn options(&self, options: impl Into<TextOptions>) -> TextOptions {
+        let record = match self {
+            Self::Raw => IndexRecordOption::Basic,
+            // substrings are matched all at once, their positions are not needed
+            Self::Ngram => IndexRecordOption::WithFreqs,
+            Self::Simple | Self::Stemmed => IndexRecordOption::WithFreqsAndPositions,
+        };
+        let indexing = TextFieldIndexing::default()
+            .set_tokenizer(self.name())
+            .set_index_option(record);
+        options.into().set_indexing_options(indexing)
+    }
+
+    /// Create a query of a field indexed with the tokenizer, analyzing the value like the text of the field.
+    ///
+    /// Values consisting of several words match the phrase, partial values match similar words too. Values of ngram
+    /// fields match the text containing them.
+    ///
+    /// Patterns match the terms as they are indexed, like the lowercase words of the text, see [`crate::patterns`].
+    /// The wildcards of ngram fields match the text containing all the literal parts of the pattern, while regular
+    /// expressions are rejected, as they can't match substrings.
+    pub fn query(
+        &self,
+        tokenizers: &TokenizerManager,
+        field: Field,
+        primary: &Primary<'_>,
+        context: &QueryContext,
+    ) -> Box<dyn Query> {
+        if *self == Self::Raw {
+            return create_string_query(field, primary, context);
+        }
+
+        let (value, partial) = match primary {
+            Primary::Equal(value) => (*value, false),
+            Primary::Partial(value) => (*value, true),
+        };
+        let (value, distance) = match fuzzy_value(value).filter(|_| !partial) {
+            Some((value, distance)) => (value, Some(distance)),
+            None => (value, None),
+        };
+        match pattern_value(value).filter(|_| !partial && distance.is_none()) {
+            Some(Pattern::Wildcard(value)) if *self == Self::Ngram => {
+                let queries: Vec<Box<dyn Query>> = value
+                    .split('*')
+                    .filter(|literal| literal.chars().count() >= NGRAM_SIZE.0)
+                    .map(|literal| self.query(tokenizers, field, &Primary::Equal(literal), context))
+                    .collect();
+                return match queries.len() {
+                    0 => Box::new(EmptyQuery),
+                    _ => Box::new(BooleanQuery::intersection(queries)),
+                };
+            }
+            Some(Pattern::Regex(regex)) if *self == Self::Ngram => {
+                context.reject(format!("pattern '{regex}' cannot match substrings"));
+                return Box::new(EmptyQuery);
+            }
+            Some(pattern) => return create_pattern_query(field, pattern, str::to_lowercase, context),
+            None => {}
+        }
+        let Some(mut analyzer) = tokenizers.get(self.name()) else {
+            return create_text_query(field, primary, context);
+        };
+        let mut analyze = |value: &str| {
+            let mut terms = Vec::new();
+            let mut stream = analyzer.token_stream(value);
+            while let Some(token) = stream.next() {
+                terms.push((token.position, Term::from_field_text(field, &token.text)));
+            }
+            terms
+        };
+
+        let mut terms = analyze(value);
+        if *self == Self::Ngram {
+            let queries: Vec<Box<dyn Query>> = terms
+                .into_iter()
+                .map(|(_, term)| Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)) as Box<dyn Query>)
+                .collect();
+            return match queries.len() {
+                0 => Box::new(EmptyQuery),
+                _ => Box::new(BooleanQuery::intersection(queries)),
+            };
+        }
+
+        match (terms.len(), partial, distance) {
+            // only stop words, which are not indexed
+            (0, _, _) => Box::new(EmptyQuery),
+            (1, true, _) => Box::new(FuzzyTermQuery::new(terms.remove(0).1, 2, true)),
+            (1, false, Some(distance)) => Box::new(FuzzyTermQuery::new(terms.remove(0).1, distance, true)),
+            (1, false, None) => {
+                let term =
+                    |term: Term| -> Box<dyn Query> { Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)) };
+                let synonyms = context.synonyms_of(value);
+                let mut queries = vec![term(terms.remove(0).1)];
+                if synonyms.is_empty() {
+                    return queries.remove(0);
+                }
+                for synonym in synonyms {
+                    let mut terms = analyze(synonym);
+                    queries.push(match terms.len() {
+                        0 => continue,
+                        1 => term(terms.remove(0).1),
+                        _ => Box::new(PhraseQuery::new_with_offset(terms)),
+                    });
+                }
+                Box::new(BooleanQuery::union(queries))
+            }
+            _ => Box::new(PhraseQuery::new_with_offset(terms)),
+        }
+    }
+}
+
+/// The tokenizers of all the [`Tokenizer`] variants.
+pub fn tokenizer_manager() -> TokenizerManager {
+    let tokenizers = TokenizerManager::default();
+
+    let mut stemmed = TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .dynamic();
+    if let Some(stop_words) = StopWordFilter::new(Language::English) {
+        stemmed = stemmed.filter_dynamic(stop_words);
+    }
+    tokenizers.register(STEMMED, stemmed.filter_dynamic(Stemmer::new(Language::English)).build());
+
+    match NgramTokenizer::new(NGRAM_SIZE.0, NGRAM_SIZE.1, false) {
+        Ok(ngram) => tokenizers.register(NGRAM, TextAnalyzer::builder(ngram).filter(LowerCaser).build()),
+        Err(err) => log::warn!("Unable to create the ngram tokenizer: {err}"),
+    }
+
+    tokenizers
+}
+
+/// The tokenizer of a field, configured as `<field>=<tokenizer>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldTokenizer {
+    pub field: String,
+    pub tokenizer: Tokenizer,
+}
+
+impl FromStr for FieldTokenizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, tokenizer) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <field>=<tokenizer>, got '{s}'"))?;
+        let field = field.trim();
+        if field.is_empty() {
+            return Err(format!("missing field name in '{s}'"));
+        }
+        Ok(Self {
+            field: field.to_string(),
+            tokenizer: <Tokenizer as clap::ValueEnum>::from_str(tokenizer.trim(), true)?,
+        })
+    }
+}
+
+impl Display for FieldTokenizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.field, self.tokenizer)
+    }
+}
+
+/// The tokenizers of the text fields of an index.
+#[derive(Clone)]
+pub struct Tokenizers {
+    /// Tokenizers configured by field name, overriding the default ones of the index
+    configured: HashMap<String, Tokenizer>,
+    /// Tokenizers of the fields added to the schema
+    fields: HashMap<Field, Tokenizer>,
+    manager: TokenizerManager,
+}
+
+impl Default for Tokenizers {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Tokenizers {
+    /// Use the configured tokenizers, ignoring the ones of fields which the index doesn't have.
+    pub fn new(configured: &[FieldTokenizer]) -> Self {
+        Self {
+            configured: configured
+                .iter()
+                .map(|configured| (configured.field.clone(), configured.tokenizer))
+                .collect(),
+            fields: HashMap::new(),
+            manager: tokenizer_manager(),
+        }
+    }
+
+    /// Add a text field to the schema, indexed with the tokenizer configured for it, or else the default one.
+    pub fn add_text_field(
+        &mut self,
+        schema: &mut SchemaBuilder,
+        name: &str,
+        default: Tokenizer,
+        options: impl Into<TextOptions>,
+    ) -> Field {
+        let tokenizer = self.configured.get(name).copied().unwrap_or(default);
+        let field = schema.add_text_field(name, tokenizer.options(options));
+        self.fields.insert(field, tokenizer);
+        field
+    }
+
+    /// The tokenizer of a field, fields not added through [`Self::add_text_field`] are raw.
+    pub fn get(&self, field: Field) -> Tokenizer {
+        self.fields.get(&field).copied().unwrap_or(Tokenizer::Raw)
+    }
+
+    /// Create a query of a field, analyzing the value with the tokenizer of the field.
+    pub fn query(&self, field: Field, primary: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
+        self.get(field).query(&self.manager, field, primary, context)
+    }
+
+    /// The tokenizers to register with the index.
+    pub fn manager(&self) -> TokenizerManager {
+        self.manager.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tantivy::{
+        collector::Count,
+        doc,
+        schema::{Schema, STORED},
+        Index,
+    };
+
+    fn tokens(tokenizer: Tokenizer, text: &str) -> Vec<String> {
+        let mut analyzer = tokenizer_manager().get(tokenizer.name()).unwrap();
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = vec![];
+        while let Some(token) = stream.next() {
+            tokens.push(token.text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(tokens(Tokenizer::Raw, "OpenSSL libs"), vec!["OpenSSL libs"]);
+        assert_eq!(tokens(Tokenizer::Simple, "OpenSSL libs"), vec!["openssl", "libs"]);
+        assert_eq!(
+            tokens(Tokenizer::Stemmed, "Memory overflows in the parser"),
+            vec!["memori", "overflow", "parser"]
+        );
+        assert_eq!(tokens(Tokenizer::Ngram, "SSL"), vec!["ss", "ssl", "sl"]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "sbom_pkg_name=NGRAM".parse::<FieldTokenizer>(),
+            Ok(FieldTokenizer {
+                field: "sbom_pkg_name".to_string(),
+                tokenizer: Tokenizer::Ngram,
+            })
+        );
+        assert!("sbom_pkg_name".parse::<FieldTokenizer>().is_err());
+        assert!("=ngram".parse::<FieldTokenizer>().is_err());
+        assert!("sbom_pkg_name=soundex".parse::<FieldTokenizer>().is_err());
+    }
+
+    #[test]
+    fn test_configured() {
+        let mut tokenizers = Tokenizers::new(&["name=ngram".parse().unwrap()]);
+        let mut schema = Schema::builder();
+        let name = tokenizers.add_text_field(&mut schema, "name", Tokenizer::Raw, STORED);
+        let desc = tokenizers.add_text_field(&mut schema, "desc", Tokenizer::Stemmed, STORED);
+        let schema = schema.build();
+
+        assert_eq!(tokenizers.get(name), Tokenizer::Ngram);
+        assert_eq!(tokenizers.get(desc), Tokenizer::Stemmed);
+        let indexing = schema.get_field_entry(name).field_type().get_index_record_option();
+        assert_eq!(indexing, Some(IndexRecordOption::WithFreqs));
+
+        let query = tokenizers.query(desc, &Primary::Equal("overflows"), &QueryContext::default());
+        assert_eq!(
+            format!("{query:?}"),
+            format!(
+                "{:?}",
+                TermQuery::new(Term::from_field_text(desc, "overflow"), IndexRecordOption::WithFreqs)
+            )
+        );
+        let query = tokenizers.query(desc, &Primary::Partial("the"), &QueryContext::default());
+        assert_eq!(format!("{query:?}"), format!("{:?}", EmptyQuery));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let mut tokenizers = Tokenizers::new(&["name=ngram".parse().unwrap()]);
+        let mut schema = Schema::builder();
+        let name = tokenizers.add_text_field(&mut schema, "name", Tokenizer::Raw, STORED);
+        let desc = tokenizers.add_text_field(&mut schema, "desc", Tokenizer::Stemmed, STORED);
+        let index = Index::builder()
+            .schema(schema.build())
+            .tokenizers(tokenizers.manager())
+            .create_in_ram()
+            .unwrap();
+        let mut writer = index.writer(15_000_000).unwrap();
+        writer
+            .add_document(doc!(name => "OpenSSL libs", desc => "Memory overflows in the parser"))
+            .unwrap();
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let search = |field, value| {
+            let context = QueryContext::default();
+            let query = tokenizers.query(field, &Primary::Equal(value), &context);
+            context.check().map(|_| searcher.search(&query, &Count).unwrap())
+        };
+        assert_eq!(search(desc, "overfl*").unwrap(), 1);
+        assert_eq!(search(desc, "/pars[a-z]+/").unwrap(), 1);
+        assert_eq!(search(desc, "parsers*").unwrap(), 0);
+        assert!(search(desc, "ov*").is_err());
+        assert_eq!(search(name, "ope*ssl").unwrap(), 1);
+        assert_eq!(search(name, "ope*ssh").unwrap(), 0);
+        assert!(search(name, "/open.*/").is_err());
+    }
+}
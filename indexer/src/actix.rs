@@ -1,7 +1,7 @@
 use crate::{IndexerCommand, IndexerStatus};
 use actix_web::{get, post, web, web::ServiceConfig, HttpResponse};
 use std::sync::Arc;
-use tokio::sync::{mpsc::Sender, Mutex};
+use tokio::sync::{mpsc::Sender, oneshot, Mutex};
 
 #[post("/reindex")]
 async fn post_command(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse {
@@ -29,10 +29,74 @@ async fn get_status(status: web::Data<Arc<Mutex<IndexerStatus>>>) -> HttpRespons
     }))
 }
 
+/// Send a command to the indexer, and respond with its reply.
+async fn call<T: serde::Serialize>(
+    sender: &Sender<IndexerCommand>,
+    command: impl FnOnce(oneshot::Sender<Result<T, String>>) -> IndexerCommand,
+) -> HttpResponse {
+    let (reply, response) = oneshot::channel();
+    if let Err(e) = sender.send(command(reply)).await {
+        return HttpResponse::InternalServerError().body(e.to_string());
+    }
+    match response.await {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Snapshot names become part of storage keys, so they are limited to a safe set of characters.
+fn valid_snapshot_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[get("/snapshots")]
+async fn list_snapshots(sender: web::Data<Sender<IndexerCommand>>) -> HttpResponse {
+    call(&sender, |reply| IndexerCommand::ListSnapshots { reply }).await
+}
+
+#[post("/snapshots/{name}")]
+async fn create_snapshot(sender: web::Data<Sender<IndexerCommand>>, name: web::Path<String>) -> HttpResponse {
+    let name = name.into_inner();
+    if !valid_snapshot_name(&name) {
+        return HttpResponse::BadRequest().body(format!("invalid snapshot name: {name}"));
+    }
+    call(&sender, |reply| IndexerCommand::Snapshot { name, reply }).await
+}
+
+#[post("/snapshots/{name}/restore")]
+async fn restore_snapshot(sender: web::Data<Sender<IndexerCommand>>, name: web::Path<String>) -> HttpResponse {
+    let name = name.into_inner();
+    if !valid_snapshot_name(&name) {
+        return HttpResponse::BadRequest().body(format!("invalid snapshot name: {name}"));
+    }
+    call(&sender, |reply| IndexerCommand::Restore { name, reply }).await
+}
+
 pub fn configure(status: Arc<Mutex<IndexerStatus>>, sender: Sender<IndexerCommand>, config: &mut ServiceConfig) {
     config
         .app_data(web::Data::new(sender))
         .app_data(web::Data::new(status))
         .service(post_command)
-        .service(get_status);
+        .service(get_status)
+        .service(list_snapshots)
+        .service(create_snapshot)
+        .service(restore_snapshot);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_name() {
+        assert!(valid_snapshot_name("before-upgrade_1.2"));
+        assert!(!valid_snapshot_name(""));
+        assert!(!valid_snapshot_name(".."));
+        assert!(!valid_snapshot_name("a/b"));
+    }
 }
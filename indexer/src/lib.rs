@@ -1,4 +1,5 @@
 use core::fmt;
+use std::collections::BTreeSet;
 use std::time::Duration;
 
 use futures::pin_mut;
@@ -6,14 +7,15 @@ use futures::StreamExt;
 use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot;
 use tokio::task::block_in_place;
 use tokio::time::Instant;
 use tokio::{select, sync::Mutex};
 use trustification_event_bus::{Error as BusError, EventBus};
-use trustification_index::{IndexStore, IndexWriter, WriteIndex};
+use trustification_index::{IndexGeneration, IndexStore, IndexWriter, WriteIndex};
 use trustification_infrastructure::health::checks::FailureRateHandle;
 use trustification_storage::ContinuationToken;
-use trustification_storage::{EventType, Storage};
+use trustification_storage::{EventType, Labels, Storage};
 
 pub mod actix;
 
@@ -26,6 +28,20 @@ pub enum IndexerStatus {
 
 pub enum IndexerCommand {
     Reindex,
+    /// Store a named snapshot of the indexes
+    Snapshot {
+        name: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    /// Restore the indexes from a named snapshot, and publish them
+    Restore {
+        name: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    /// List the names of the snapshots stored for all the indexes
+    ListSnapshots {
+        reply: oneshot::Sender<Result<Vec<String>, String>>,
+    },
 }
 
 #[derive(clap::ValueEnum, Default, Clone, Debug, PartialEq)]
@@ -53,6 +69,8 @@ pub struct Indexer<'a, DOC> {
     pub stored_topic: &'a str,
     pub indexed_topic: &'a str,
     pub failed_topic: &'a str,
+    /// Topic to notify about documents which got removed from the index
+    pub deleted_topic: &'a str,
     pub sync_interval: Duration,
     pub indexes: Vec<IndexStore<Box<dyn WriteIndex<Document = DOC>>>>,
     pub storage: Storage,
@@ -96,6 +114,7 @@ where
         let consumer = self.bus.subscribe("indexer", &[self.stored_topic]).await?;
         let mut processed_events = Vec::new();
         let mut indexed_events = Vec::new();
+        let mut deleted_events = Vec::new();
         let mut events = 0;
 
         *self.status.lock().await = IndexerStatus::Running;
@@ -103,27 +122,68 @@ where
             let tick = interval.tick();
             pin_mut!(tick);
             select! {
-                command = self.commands.recv() => {
-                    if let Some(IndexerCommand::Reindex) = command {
+                command = self.commands.recv() => match command {
+                    Some(IndexerCommand::Reindex) => {
                         self.handle_reindex(&mut writers).await?;
                     }
-                }
+                    Some(IndexerCommand::Snapshot { name, reply }) => {
+                        let result = self.handle_snapshot(&mut writers, &name).await;
+                        let _ = reply.send(result.map_err(|e| e.to_string()));
+                    }
+                    Some(IndexerCommand::Restore { name, reply }) => {
+                        let result = self.handle_restore(&mut writers, &name).await;
+                        let _ = reply.send(result.map_err(|e| e.to_string()));
+                    }
+                    Some(IndexerCommand::ListSnapshots { reply }) => {
+                        let _ = reply.send(self.list_snapshots().await.map_err(|e| e.to_string()));
+                    }
+                    None => {}
+                },
                 event = consumer.next() => match event {
                     Ok(Some(event)) => {
                         if let Some(payload) = event.payload() {
                             if let Ok(data) = self.storage.decode_event(payload) {
                                 log::debug!("Received {} records", data.records.len());
                                 let mut indexed = 0;
+                                let mut deleted = 0;
                                 for data in data.records {
                                     if self.storage.is_index(data.key()) {
                                         log::trace!("It's an index event, ignoring");
+                                    } else if self.storage.is_attestations(data.key())
+                                        || self.storage.is_revisions(data.key())
+                                        || self.storage.is_status(data.key())
+                                        || self.storage.is_uploads(data.key())
+                                        || self.storage.is_digests(data.key())
+                                    {
+                                        log::trace!("It's an attestations, revisions, status, uploads or digests event, ignoring");
+                                    } else if self.storage.is_labels(data.key()) {
+                                        // labels are removed along with their document, so only updates matter
+                                        if data.event_type() == EventType::Put {
+                                            let (_, key) = Storage::key_from_event(&data)?;
+                                            match self.storage.get_data(&key).await {
+                                                Ok(doc) => {
+                                                    let labels = self.labels(&key).await;
+                                                    for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
+                                                        if let Err(e) = self.index_doc(index.index(), writer, &key, &doc, &labels).await {
+                                                            log::warn!("(Ignored) Internal error when indexing {}: {:?}", key, e);
+                                                        }
+                                                    }
+                                                    log::info!("Updated labels of entry '{key}'");
+                                                    events += 1;
+                                                }
+                                                Err(e) => {
+                                                    log::warn!("Error retrieving labeled document, ignoring (error: {:?})", e);
+                                                }
+                                            }
+                                        }
                                     } else {
                                         match data.event_type() {
                                             EventType::Put => {
                                                 match self.storage.get_for_event(&data, true).await {
                                                     Ok(res) => {
+                                                        let labels = self.labels(&res.key).await;
                                                         for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                                            if let Err(e) = self.index_doc(index.index(), writer, &res.key, &res.data).await {
+                                                            if let Err(e) = self.index_doc(index.index(), writer, &res.key, &res.data, &labels).await {
                                                                 log::warn!("(Ignored) Internal error when indexing {}: {:?}", res.key, e);
                                                             }
                                                         }
@@ -142,6 +202,7 @@ where
                                                 }
                                                 log::info!("Deleted entry '{key}' from index");
                                                 events += 1;
+                                                deleted += 1;
                                             }
                                             _ => log::debug!("Non (PUT | DELETE)  event ({:?}), skipping", data),
                                         }
@@ -152,6 +213,11 @@ where
                                         indexed_events.push(payload.to_vec());
                                     }
                                 }
+                                if deleted > 0 {
+                                    if let Some(payload) = event.payload() {
+                                        deleted_events.push(payload.to_vec());
+                                    }
+                                }
                             } else {
                                 log::warn!("Error decoding event, skipping");
                             }
@@ -202,6 +268,16 @@ where
                                 }
                             }
 
+                            // only notify once the deletion is visible in the stored index
+                            for payload in deleted_events.drain(..) {
+                                if let Err(e) = self.bus.send(self.deleted_topic, &payload).await {
+                                    log::warn!(
+                                        "(Ignored) Error sending event to deleted topic {}: {:?}",
+                                        self.deleted_topic,
+                                        e
+                                    );
+                                }
+                            }
                         }
                         Err(e) => {
                             self.state.increment();
@@ -219,15 +295,15 @@ where
     async fn handle_reindex(&mut self, writers: &mut Vec<IndexWriter>) -> anyhow::Result<()> {
         log::info!("Reindexing all documents");
 
-        // set the indexes
+        // build new generations of the indexes, the current ones keep being published until they are complete
+        writers.clear();
+        let mut generations = Vec::new();
         for index in &mut self.indexes {
-            index.reset()?;
+            generations.push(index.new_generation()?);
         }
-
-        // after resetting, we need to acquire new writers, as the old indexes are gone
-        writers.clear();
-        for index in self.indexes.iter_mut() {
-            writers.push(block_in_place(|| index.writer())?);
+        let mut generation_writers = Vec::new();
+        for (index, generation) in self.indexes.iter().zip(&generations) {
+            generation_writers.push(block_in_place(|| index.generation_writer(generation))?);
         }
 
         // now walk the full content with the new (empty) indexes
@@ -236,9 +312,19 @@ where
         let mut token = ContinuationToken::default();
         loop {
             retries -= 1;
-            match self.reindex(writers, token).await {
+            match self.reindex(&generations, &mut generation_writers, token).await {
                 Ok(_) => {
-                    log::info!("Reindexing finished");
+                    log::info!("Reindexing finished, switching to the new indexes");
+                    for writer in generation_writers.drain(..) {
+                        block_in_place(|| writer.commit())?;
+                    }
+                    for (index, generation) in self.indexes.iter_mut().zip(generations.drain(..)) {
+                        index.switch_generation(generation);
+                    }
+
+                    for index in self.indexes.iter_mut() {
+                        writers.push(block_in_place(|| index.writer())?);
+                    }
                     for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
                         match index.snapshot(writer, &self.storage, true).await {
                             Ok(_) => {
@@ -271,8 +357,83 @@ where
         Ok(())
     }
 
+    async fn handle_snapshot(&mut self, writers: &mut Vec<IndexWriter>, name: &str) -> Result<(), IndexerError> {
+        log::info!("Taking snapshot {name}");
+
+        // the writers get committed, so all documents indexed until now are part of the snapshot
+        let mut result = Ok(());
+        for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
+            if let Err(e) = index.create_snapshot(writer, &self.storage, name).await {
+                result = Err(e.into());
+                break;
+            }
+        }
+
+        for index in self.indexes.iter_mut() {
+            writers.push(block_in_place(|| index.writer())?);
+        }
+        result
+    }
+
+    async fn handle_restore(&mut self, writers: &mut Vec<IndexWriter>, name: &str) -> Result<(), IndexerError> {
+        log::info!("Restoring snapshot {name}");
+
+        // fetch all snapshots first, so that no index gets restored if any snapshot is missing
+        let mut snapshots = Vec::new();
+        for index in &self.indexes {
+            snapshots.push(self.storage.get_index_snapshot(index.index().name(), name).await?);
+        }
+
+        // the old writers would write to the replaced indexes
+        writers.clear();
+        let mut result = Ok(());
+        for (index, data) in self.indexes.iter_mut().zip(snapshots) {
+            if let Err(e) = block_in_place(|| index.restore(&data)) {
+                result = Err(e.into());
+                break;
+            }
+        }
+
+        for index in self.indexes.iter_mut() {
+            writers.push(block_in_place(|| index.writer())?);
+        }
+        result?;
+
+        // publish the restored indexes, for the services to sync from
+        let mut result = Ok(());
+        for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
+            if let Err(e) = index.snapshot(writer, &self.storage, true).await {
+                result = Err(e.into());
+                break;
+            }
+        }
+        for index in self.indexes.iter_mut() {
+            writers.push(block_in_place(|| index.writer())?);
+        }
+        result
+    }
+
+    /// The names of the snapshots stored for all the indexes, sorted
+    async fn list_snapshots(&self) -> Result<Vec<String>, IndexerError> {
+        let mut result: Option<BTreeSet<String>> = None;
+        for index in &self.indexes {
+            let snapshots: BTreeSet<String> = self
+                .storage
+                .list_index_snapshots(index.index().name())
+                .await?
+                .into_iter()
+                .collect();
+            result = Some(match result {
+                Some(result) => result.intersection(&snapshots).cloned().collect(),
+                None => snapshots,
+            });
+        }
+        Ok(result.unwrap_or_default().into_iter().collect())
+    }
+
     async fn reindex(
         &mut self,
+        generations: &[IndexGeneration],
         writers: &mut Vec<IndexWriter>,
         resume_token: ContinuationToken,
     ) -> Result<(), (IndexerError, ContinuationToken)> {
@@ -292,9 +453,10 @@ where
                         Some(Ok((path, obj))) => {
                             let key = path.key();
                             log::info!("Reindexing {:?}", key);
+                            let labels = self.labels(key).await;
                             // Not sending notifications for reindexing
                             for (index, writer) in self.indexes.iter().zip(writers.iter_mut()) {
-                                if let Err(e) = self.index_doc(index.index(), writer, key, &obj).await {
+                                if let Err(e) = self.index_doc(index.index(), writer, key, &obj, &labels).await {
                                     log::warn!("(Ignored) Internal error when indexing {}: {:?}", key, e);
                                 }
                             }
@@ -312,34 +474,38 @@ where
                     }
                 }
                 _ = tick => {
-                    for (index, writer) in self.indexes.iter_mut().zip(writers.drain(..)) {
-                        match index.snapshot(writer, &self.storage, true).await {
-                            Ok(_) => {
-                                log::info!("Reindexed snapshot published");
-                            }
-                            Err(e) => {
-                                log::warn!("(Ignored) Error publishing index: {:?}", e);
-                            }
+                    // commit the reindexed documents, without publishing the new indexes before they are complete
+                    for writer in writers.drain(..) {
+                        if let Err(e) = block_in_place(|| writer.commit()) {
+                            log::warn!("(Ignored) Error committing reindexed documents: {:?}", e);
                         }
                     }
 
-
-                    for index in self.indexes.iter_mut() {
-                        writers.push(block_in_place(|| index.writer()).map_err(|e| (e.into(), resume_token.clone()))?);
+                    for (index, generation) in self.indexes.iter().zip(generations) {
+                        writers.push(block_in_place(|| index.generation_writer(generation)).map_err(|e| (e.into(), resume_token.clone()))?);
                     }
                 }
             }
         }
     }
 
+    /// The labels of a document, falling back to no labels if they can't be retrieved
+    async fn labels(&self, key: &str) -> Labels {
+        self.storage.get_labels(key).await.unwrap_or_else(|e| {
+            log::warn!("(Ignored) Error retrieving labels of {}: {:?}", key, e);
+            Labels::default()
+        })
+    }
+
     async fn index_doc(
         &self,
         index: &dyn WriteIndex<Document = DOC>,
         writer: &mut IndexWriter,
         key: &str,
         data: &[u8],
+        labels: &Labels,
     ) -> Result<(), anyhow::Error> {
-        match block_in_place(|| writer.add_document(index, key, data)) {
+        match block_in_place(|| writer.add_labeled_document(index, key, data, labels)) {
             Ok(_) => {
                 log::debug!("Inserted entry '{key}' into index");
             }
@@ -185,6 +185,7 @@ fn bombastic_indexer() -> bombastic_indexer::Run {
     bombastic_indexer::Run {
         stored_topic: "sbom-stored".into(),
         failed_topic: "sbom-failed".into(),
+        deleted_topic: "sbom-deleted".into(),
         indexed_topic: "sbom-indexed".into(),
         devmode: true,
         reindex: Default::default(),
@@ -193,6 +194,14 @@ fn bombastic_indexer() -> bombastic_indexer::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            synonyms: None,
+            tokenizers: vec![],
+            boosts: vec![],
+            recency_window: Duration::from_secs(30 * 24 * 3600).into(),
+            recency_boost: 4.0,
+            shards: 1,
+            query_timeout: Duration::from_secs(30).into(),
+            search_cache_size: 0,
         },
         storage: StorageConfig {
             region: None,
@@ -201,6 +210,7 @@ fn bombastic_indexer() -> bombastic_indexer::Run {
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::None,
+            compression: Default::default(),
         },
         bus: EventBusConfig {
             event_bus: EventBusType::Kafka,
@@ -225,6 +235,14 @@ fn bombastic_api() -> bombastic_api::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            synonyms: None,
+            tokenizers: vec![],
+            boosts: vec![],
+            recency_window: Duration::from_secs(30 * 24 * 3600).into(),
+            recency_boost: 4.0,
+            shards: 1,
+            query_timeout: Duration::from_secs(30).into(),
+            search_cache_size: 0,
         },
         storage: StorageConfig {
             region: Some(Region::Custom {
@@ -236,6 +254,7 @@ fn bombastic_api() -> bombastic_api::Run {
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::SBOM,
+            compression: Default::default(),
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
@@ -247,5 +266,23 @@ fn bombastic_api() -> bombastic_api::Run {
         swagger_ui_oidc: testing_swagger_ui_oidc(),
         http: Default::default(),
         publish_limit: ByteSize::mib(64).into(),
+        decompressed_limit: ByteSize::mib(256).into(),
+        deduplication: Default::default(),
+        keep_revisions: 5,
+        signature: Default::default(),
+        retention: Default::default(),
+        reindex: bombastic_api::ReindexConfig {
+            stored_topic: "sbom-stored".into(),
+            bus: EventBusConfig {
+                event_bus: EventBusType::Kafka,
+                kafka_bootstrap_servers: KAFKA_BOOTSTRAP_SERVERS.into(),
+                ..Default::default()
+            },
+        },
+        vex: bombastic_api::VexConfig {
+            vexination_url: None,
+            oidc: trustification_auth::client::OpenIdTokenProviderConfigArguments::devmode(),
+            client: Default::default(),
+        },
     }
 }
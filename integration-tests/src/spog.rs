@@ -131,11 +131,30 @@ fn spog_api(
         exhort_url,
         crda_url: option_env!("CRDA_URL").map(|url| url.parse().unwrap()),
         crda_payload_limit: DEFAULT_CRDA_PAYLOAD_LIMIT,
+        advisory_search_concurrency: 4,
+        advisory_search_timeout: "5s".parse().unwrap(),
+        advisory_cache_ttl: "5m".parse().unwrap(),
+        advisory_cache_invalidation_topic: None,
+        advisory_cache_group: None,
+        event_group: "spog-api".to_string(),
+        watch_sbom_topic: None,
+        watch_advisory_topic: None,
+        webhook_advisory_topic: None,
+        subscription_topic: None,
+        alias_vulnerability_topic: None,
+        alias_advisory_topic: None,
+        advisory_default_sort: None,
+        dashboard_cache_ttl: "1m".parse().unwrap(),
+        attestation_public_keys: vec![],
+        attestation_registries: vec![],
+        vex_namespace: None,
         snyk_token: None,
         collectorist_url,
         v11y_url,
         oidc: OpenIdTokenProviderConfigArguments::devmode(),
         config: None,
+        storage_base: None,
+        bus: Default::default(),
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
             infrastructure_bind: "127.0.0.1".into(),
@@ -147,6 +147,7 @@ fn vexination_indexer() -> vexination_indexer::Run {
         stored_topic: "vex-stored".into(),
         indexed_topic: "vex-indexed".into(),
         failed_topic: "vex-failed".into(),
+        deleted_topic: "vex-deleted".into(),
         devmode: true,
         reindex: ReindexMode::Always,
         bus: EventBusConfig {
@@ -161,6 +162,7 @@ fn vexination_indexer() -> vexination_indexer::Run {
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::None,
+            compression: Default::default(),
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
@@ -173,6 +175,14 @@ fn vexination_indexer() -> vexination_indexer::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            synonyms: None,
+            tokenizers: vec![],
+            boosts: vec![],
+            recency_window: Duration::from_secs(30 * 24 * 3600).into(),
+            recency_boost: 4.0,
+            shards: 1,
+            query_timeout: Duration::from_secs(30).into(),
+            search_cache_size: 0,
         },
     }
 }
@@ -187,6 +197,14 @@ fn vexination_api() -> vexination_api::Run {
             index_writer_memory_bytes: bytesize::ByteSize::mb(64),
             mode: Default::default(),
             sync_interval: Duration::from_secs(2).into(),
+            synonyms: None,
+            tokenizers: vec![],
+            boosts: vec![],
+            recency_window: Duration::from_secs(30 * 24 * 3600).into(),
+            recency_boost: 4.0,
+            shards: 1,
+            query_timeout: Duration::from_secs(30).into(),
+            search_cache_size: 0,
         },
         storage: StorageConfig {
             region: Some(Region::Custom {
@@ -198,6 +216,7 @@ fn vexination_api() -> vexination_api::Run {
             access_key: Some("admin".into()),
             secret_key: Some("password".into()),
             validator: Validator::VEX,
+            compression: Default::default(),
         },
         infra: InfrastructureConfig {
             infrastructure_enabled: false,
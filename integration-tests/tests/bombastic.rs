@@ -78,6 +78,122 @@ async fn delete_happy_sbom(context: &mut BombasticContext) {
     assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn replaced_sbom_versions(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-versions");
+    context.upload_sbom(&id, &input).await;
+    let mut replacement = input.clone();
+    replacement["version"] = json!(2);
+    context.upload_sbom(&id, &replacement).await;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(context.urlify(format!("/api/v1/sbom/{}/versions", encode(&id))))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let versions: Vec<String> = response.json().await.unwrap();
+    assert_eq!(versions, vec!["1"]);
+
+    let response = client
+        .get(context.urlify(format!("/api/v1/sbom/{}/versions/1", encode(&id))))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let output: Value = response.json().await.unwrap();
+    assert_eq!(input, output);
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn list_sbom_components(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-components");
+    context.upload_sbom(&id, &input).await;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(context.urlify(format!("/api/v1/sbom/{}/components?limit=10", encode(&id))))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Value = response.json().await.unwrap();
+    assert_eq!(page["total"], json!(95));
+    assert_eq!(page["result"].as_array().map(Vec::len), Some(10));
+    assert_eq!(page["result"][0]["name"], json!("seedwing-java-example"));
+
+    let response = client
+        .get(context.urlify(format!("/api/v1/sbom/{}/components?q=quarkus-arc", encode(&id))))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Value = response.json().await.unwrap();
+    assert!(page["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|component| component["name"].as_str().unwrap().contains("quarkus-arc")
+            || component["purl"].as_str().unwrap_or_default().contains("quarkus-arc")));
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn merge_sboms(context: &mut BombasticContext) {
+    let spdx: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let spdx_id = id("test-merge-spdx");
+    context.upload_sbom(&spdx_id, &spdx).await;
+    let cyclonedx: Value = serde_json::from_str(include_str!("../../bombastic/testdata/syft.cyclonedx.json")).unwrap();
+    let cyclonedx_id = id("test-merge-cyclonedx");
+    context.upload_sbom(&cyclonedx_id, &cyclonedx).await;
+
+    let response = reqwest::Client::new()
+        .post(context.urlify("/api/v1/sbom/merge"))
+        .json(&json!({ "name": "product", "version": "1.0", "ids": [spdx_id, cyclonedx_id] }))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: Value = response.json().await.unwrap();
+    assert_eq!(merged["bomFormat"], json!("CycloneDX"));
+    assert_eq!(merged["metadata"]["component"]["name"], json!("product"));
+    assert_eq!(merged["annotations"].as_array().map(Vec::len), Some(2));
+
+    let response = reqwest::Client::new()
+        .post(context.urlify("/api/v1/sbom/merge"))
+        .json(&json!({ "name": "product", "ids": [spdx_id, id("test-merge-missing")] }))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -92,6 +208,25 @@ async fn delete_missing_sbom(context: &mut BombasticContext) {
         .await
         .unwrap();
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // an empty query would match all SBOMs
+    let response = client
+        .delete(context.urlify("/api/v1/sbom?query=%20"))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .delete(context.urlify("/api/v1/sbom?id=missing&query=missing"))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     let response = client
         .delete(context.urlify("/api/v1/sbom?id="))
         .inject_token(&context.provider.provider_manager)
@@ -112,6 +247,175 @@ async fn delete_missing_sbom(context: &mut BombasticContext) {
     assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(90_000)]
+async fn delete_sboms_by_query(context: &mut BombasticContext) {
+    let mut input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/ubi9-sbom.json")).unwrap();
+    let marker = id("test-bulk-deletion");
+    input["packages"][617]["versionInfo"] = json!(marker);
+    let ids = vec![format!("{marker}-1"), format!("{marker}-2")];
+    for id in &ids {
+        context.upload_sbom(id, &input).await;
+    }
+    wait_for_sbom_search_result(context, &[("q", &encode(&marker))], |response| {
+        response["total"].as_u64().unwrap() == 2
+    })
+    .await;
+
+    let client = reqwest::Client::new();
+    let delete = |dry_run: bool| {
+        client
+            .delete(context.urlify("/api/v1/sbom"))
+            .query(&[("query", marker.as_str()), ("dry_run", &dry_run.to_string())])
+    };
+
+    let response = delete(true)
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result, json!({ "ids": ids, "dry_run": true, "deleted": [] }));
+
+    let response = delete(false)
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result, json!({ "ids": ids, "dry_run": false, "deleted": ids }));
+
+    for id in &ids {
+        let response = client
+            .get(context.urlify(format!("/api/v1/sbom?id={}", encode(id))))
+            .inject_token(&context.provider.provider_manager)
+            .await
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    wait_for_sbom_search_result(context, &[("q", &encode(&marker))], |response| {
+        response["total"].as_u64().unwrap() == 0
+    })
+    .await;
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(90_000)]
+async fn delete_sbom_cascades(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-cascading-deletion");
+    context.upload_sbom(&id, &input).await;
+    let mut replacement = input.clone();
+    replacement["version"] = json!(2);
+    context.upload_sbom(&id, &replacement).await;
+    let query = format!("id:\"{id}\"");
+    wait_for_sbom_search_result(context, &[("q", &encode(&query))], |response| {
+        response["total"].as_u64().unwrap() == 1
+    })
+    .await;
+
+    let client = reqwest::Client::new();
+    let labels = |labels: Value| {
+        client
+            .patch(context.urlify(format!("/api/v1/sbom/labels?id={}", encode(&id))))
+            .json(&labels)
+    };
+    let attestations = || context.urlify(format!("/api/v1/sbom/attestations?id={}", encode(&id)));
+    let versions = || context.urlify(format!("/api/v1/sbom/{}/versions", encode(&id)));
+
+    let response = labels(json!({ "team": "a" }))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let envelope = json!({ "payloadType": "application/vnd.in-toto+json", "payload": "e30=", "signatures": [] });
+    let response = client
+        .put(attestations())
+        .json(&json!([envelope]))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client
+        .delete(context.urlify(format!("/api/v1/sbom?id={}", encode(&id))))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // the revisions, attestations and index entries go with the SBOM
+    let response = client
+        .get(versions())
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Vec<String> = response.json().await.unwrap();
+    assert!(result.is_empty());
+    let response = client
+        .get(attestations())
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result, json!([]));
+    wait_for_sbom_search_result(context, &[("q", &encode(&query))], |response| {
+        response["total"].as_u64().unwrap() == 0
+    })
+    .await;
+
+    // an SBOM uploaded with the same id doesn't inherit the labels of the deleted one
+    context.upload_sbom(&id, &input).await;
+    let response = labels(json!({ "owner": "b" }))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: Value = response.json().await.unwrap();
+    assert_eq!(result, json!({ "owner": "b" }));
+    let response = client
+        .get(versions())
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    let result: Vec<String> = response.json().await.unwrap();
+    assert!(result.is_empty());
+}
+
 #[cfg(feature = "admin")]
 #[test_context(BombasticContext)]
 #[tokio::test]
@@ -482,6 +786,34 @@ async fn sbom_delete_user_not_allowed(context: &mut BombasticContext) {
     assert_eq!(response.status(), StatusCode::FORBIDDEN);
 }
 
+#[cfg(feature = "admin")]
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn reindex_user_not_allowed(context: &mut BombasticContext) {
+    let response = reqwest::Client::new()
+        .post(context.urlify("api/v1/admin/reindex"))
+        .inject_token(&context.provider.provider_user)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn reindex_unauthorized(context: &mut BombasticContext) {
+    let response = reqwest::Client::new()
+        .post(context.urlify("api/v1/admin/reindex"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[test_context(BombasticContext)]
 #[tokio::test]
 #[ntest::timeout(60_000)]
@@ -514,3 +846,104 @@ async fn get_sbom_with_missing_id(context: &mut BombasticContext) {
     let api_endpoint = context.urlify("api/v1/sbom?ID=test");
     get_response(&api_endpoint, StatusCode::BAD_REQUEST, &context.provider).await;
 }
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn get_sbom_converted(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/my-sbom.json")).unwrap();
+    let id = id("test-get-converted");
+    context.upload_sbom(&id, &input).await;
+
+    let client = reqwest::Client::new();
+    let get = |format: &str| {
+        client
+            .get(context.urlify("/api/v1/sbom"))
+            .query(&[("id", id.as_str()), ("format", format)])
+    };
+
+    let response = get("spdx-json")
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let output: Value = response.json().await.unwrap();
+    assert_eq!(output["spdxVersion"], json!("SPDX-2.3"));
+    let purl = "pkg:maven/io.seedwing/seedwing-java-example@1.0.0-SNAPSHOT?type=jar";
+    assert!(output["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|package| package["externalRefs"][0]["referenceLocator"] == json!(purl)));
+    assert!(!output["relationships"].as_array().unwrap().is_empty());
+
+    // SBOMs already in the requested format are returned as stored
+    let response = get("cyclonedx-json")
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let output: Value = response.json().await.unwrap();
+    assert_eq!(output, input);
+
+    let response = get("spdx-tag-value")
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test_context(BombasticContext)]
+#[tokio::test]
+#[ntest::timeout(60_000)]
+async fn convert_sbom(context: &mut BombasticContext) {
+    let input: Value = serde_json::from_str(include_str!("../../bombastic/testdata/syft.spdx.json")).unwrap();
+    let client = reqwest::Client::new();
+    let convert = |format: &str, input: &Value| {
+        client
+            .post(context.urlify("/api/v1/sbom/convert"))
+            .query(&[("format", format)])
+            .json(input)
+    };
+
+    let response = convert("cyclonedx-json", &input)
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let output: Value = response.json().await.unwrap();
+    assert_eq!(output["bomFormat"], json!("CycloneDX"));
+    let components = output["components"].as_array().unwrap();
+    assert_eq!(components.len(), input["packages"].as_array().unwrap().len());
+    assert!(components.iter().all(|component| component["purl"].is_string()));
+
+    let response = convert("spdx-tag-value", &input)
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = convert("spdx-json", &json!({"name": "not an SBOM"}))
+        .inject_token(&context.provider.provider_manager)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,247 @@
+//! Normalization of package URLs.
+//!
+//! The same package can be referred to by package URLs which differ in their spelling only, like
+//! `pkg:npm/Lodash@4.17.21` and `pkg:npm/lodash@4.17.21`. Normalizing them before indexing them, and before
+//! looking them up, makes those match. A normalized package URL:
+//!
+//! * has a lowercase type
+//! * has a lowercase namespace and name, for the types which are case insensitive, see [`CASE_INSENSITIVE`]
+//! * has the default namespace of the type, if it has none, see [`DEFAULT_NAMESPACES`]
+//! * has its qualifiers ordered by their lowercase key, without the ones having an empty value
+//! * has a subpath without empty, `.` and `..` segments
+//! * has all its parts percent-encoded the same way: all characters except alphanumerics and `-._~:+` are encoded,
+//!   for qualifier values and the subpath `/` as well
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// Types of which the namespace and the name are case insensitive, and lowercased.
+pub const CASE_INSENSITIVE: &[&str] = &["bitbucket", "composer", "github", "hex", "npm", "pypi"];
+
+/// Default namespaces of types, for package URLs without a namespace.
+pub const DEFAULT_NAMESPACES: &[(&str, &str)] = &[("docker", "library")];
+
+/// Normalize a package URL, returning it unchanged if it isn't a valid package URL.
+pub fn normalize(purl: &str) -> Cow<'_, str> {
+    match try_normalize(purl) {
+        Some(normalized) if normalized != purl => normalized.into(),
+        _ => purl.into(),
+    }
+}
+
+/// Normalize a package URL, returning `None` if it isn't a valid package URL.
+pub fn try_normalize(purl: &str) -> Option<String> {
+    let (scheme, rest) = purl.split_once(':')?;
+    if !scheme.eq_ignore_ascii_case("pkg") {
+        return None;
+    }
+    // slashes after the scheme are not significant
+    let rest = rest.trim_start_matches('/');
+    let (rest, subpath) = match rest.rsplit_once('#') {
+        Some((rest, subpath)) => (rest, Some(subpath)),
+        None => (rest, None),
+    };
+    let (rest, qualifiers) = match rest.rsplit_once('?') {
+        Some((rest, qualifiers)) => (rest, Some(qualifiers)),
+        None => (rest, None),
+    };
+
+    let (ty, rest) = rest.split_once('/')?;
+    let ty = ty.to_ascii_lowercase();
+    if ty.is_empty() || !ty.chars().all(|c| c.is_ascii_alphanumeric() || ".+-".contains(c)) {
+        return None;
+    }
+
+    let rest = rest.trim_end_matches('/');
+    // the version separator is the last `@` of the name, the namespace may contain one, like npm scopes
+    let (path, version) = match rest.rfind('@') {
+        Some(at) if rest.rfind('/').map(|slash| at > slash).unwrap_or(true) => (&rest[..at], Some(&rest[at + 1..])),
+        _ => (rest, None),
+    };
+
+    let mut segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(decode)
+        .collect::<Option<Vec<_>>>()?;
+    let mut name = segments.pop().filter(|name| !name.is_empty())?;
+    let mut namespace = segments;
+
+    if CASE_INSENSITIVE.contains(&ty.as_str()) {
+        name = name.to_lowercase();
+        for segment in &mut namespace {
+            *segment = segment.to_lowercase();
+        }
+    }
+    if ty == "pypi" {
+        name = name.replace('_', "-");
+    }
+    if namespace.is_empty() {
+        if let Some((_, default)) = DEFAULT_NAMESPACES.iter().find(|(t, _)| *t == ty) {
+            namespace.push(default.to_string());
+        }
+    }
+
+    let mut result = format!("pkg:{ty}/");
+    for segment in &namespace {
+        result.push_str(&encode(segment, ""));
+        result.push('/');
+    }
+    result.push_str(&encode(&name, ""));
+
+    let version = match version {
+        Some(version) => Some(decode(version)?),
+        None => None,
+    };
+    if let Some(version) = version.filter(|version| !version.is_empty()) {
+        result.push('@');
+        result.push_str(&encode(&version, ""));
+    }
+
+    let mut ordered = BTreeMap::new();
+    for qualifier in qualifiers.into_iter().flat_map(|q| q.split('&')) {
+        let Some((key, value)) = qualifier.split_once('=') else {
+            continue;
+        };
+        let value = decode(value)?;
+        if !key.is_empty() && !value.is_empty() {
+            ordered.insert(key.to_ascii_lowercase(), value);
+        }
+    }
+    for (n, (key, value)) in ordered.iter().enumerate() {
+        result.push(if n == 0 { '?' } else { '&' });
+        result.push_str(key);
+        result.push('=');
+        result.push_str(&encode(value, "/"));
+    }
+
+    let subpath = subpath
+        .into_iter()
+        .flat_map(|subpath| subpath.split('/'))
+        .filter(|segment| !matches!(*segment, "" | "." | ".."))
+        .map(decode)
+        .collect::<Option<Vec<_>>>()?;
+    if !subpath.is_empty() {
+        result.push('#');
+        let subpath: Vec<_> = subpath.iter().map(|segment| encode(segment, "")).collect();
+        result.push_str(&subpath.join("/"));
+    }
+
+    Some(result)
+}
+
+/// Percent-decode a value, returning `None` if the result isn't UTF-8.
+///
+/// Percent signs which don't start an escape sequence are kept.
+fn decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                result.push(byte);
+                i += 3;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(result).ok()
+}
+
+/// Percent-encode a value, keeping alphanumerics, `-._~:+` and the additional characters.
+fn encode(value: &str, keep: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() || "-._~:+".contains(c) || keep.contains(c) {
+            result.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                result.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        assert_eq!(normalize("pkg:npm/Lodash@4.17.21"), "pkg:npm/lodash@4.17.21");
+        assert_eq!(normalize("PKG:NPM/lodash@4.17.21"), "pkg:npm/lodash@4.17.21");
+        assert_eq!(normalize("pkg:PyPI/Django_Rest@1.0"), "pkg:pypi/django-rest@1.0");
+        // maven coordinates are case sensitive
+        assert_eq!(
+            normalize("pkg:Maven/org.Apache/Commons@1.0"),
+            "pkg:maven/org.Apache/Commons@1.0"
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(
+            normalize("pkg:npm/@angular/core@16.0.0"),
+            "pkg:npm/%40angular/core@16.0.0"
+        );
+        assert_eq!(
+            normalize("pkg:npm/%40angular/core@16.0.0"),
+            "pkg:npm/%40angular/core@16.0.0"
+        );
+        assert_eq!(normalize("pkg:npm/@angular/core"), "pkg:npm/%40angular/core");
+        assert_eq!(
+            normalize("pkg:deb/debian/curl@7.74.0-1.3%2Bdeb11u7"),
+            "pkg:deb/debian/curl@7.74.0-1.3+deb11u7"
+        );
+        assert_eq!(normalize("pkg:generic/a%20b@1"), "pkg:generic/a%20b@1");
+    }
+
+    #[test]
+    fn test_qualifiers() {
+        assert_eq!(
+            normalize("pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?epoch=1&Arch=x86_64&distro="),
+            "pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?arch=x86_64&epoch=1"
+        );
+        assert_eq!(
+            normalize("pkg:maven/org.apache/commons@1?repository_url=https%3A%2F%2Frepo.example.com%2Fmaven"),
+            "pkg:maven/org.apache/commons@1?repository_url=https://repo.example.com/maven"
+        );
+    }
+
+    #[test]
+    fn test_namespace() {
+        assert_eq!(normalize("pkg:docker/nginx@1.25"), "pkg:docker/library/nginx@1.25");
+        assert_eq!(
+            normalize("pkg:docker/library/nginx@1.25"),
+            "pkg:docker/library/nginx@1.25"
+        );
+        assert_eq!(normalize("pkg:cargo/serde@1.0"), "pkg:cargo/serde@1.0");
+    }
+
+    #[test]
+    fn test_subpath() {
+        assert_eq!(
+            normalize("pkg:golang/github.com/gorilla/context@234fd47#/api/./../v1/"),
+            "pkg:golang/github.com/gorilla/context@234fd47#api/v1"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(normalize("not a purl"), "not a purl");
+        assert_eq!(normalize("pkg:npm"), "pkg:npm");
+        assert_eq!(normalize("cpe:/a:redhat:openssl"), "cpe:/a:redhat:openssl");
+        assert!(try_normalize("pkg:npm/@1.0").is_none());
+        assert!(matches!(normalize("pkg:npm/lodash@4.17.21"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::app_state::AppState;
+use crate::consumer::consume;
+use crate::db::Db;
+use crate::error::Error;
+use crate::search::QueryParams;
+use crate::service::v11y::V11yService;
+use crate::watch::document_ids;
+use spog_model::saved::SearchTarget;
+use std::collections::{BTreeMap, BTreeSet};
+use trustification_api::search::SearchOptions;
+use trustification_event_bus::EventBus;
+
+/// maximum number of documents evaluated by a single query
+const MAX_DOCUMENTS: usize = 50;
+/// maximum number of hits of a single query, advisories are indexed by vulnerability
+const MAX_HITS: usize = 1000;
+
+/// Record the aliases of vulnerabilities whenever an event is received on a topic, until the consumer fails.
+///
+/// Events of CVE topics are expected to carry the storage events of the vulnerabilities indexed by v11y, recording
+/// their aliases, like GitHub advisories. Events of advisory topics are expected to carry the storage events of the
+/// advisories indexed by vexination, recording the CVEs they fix.
+pub async fn record_on_events(
+    state: &AppState,
+    v11y: &V11yService,
+    db: &Db,
+    bus: &EventBus,
+    group: &str,
+    topics: &[(String, SearchTarget)],
+) -> anyhow::Result<()> {
+    let names: Vec<_> = topics.iter().map(|(topic, _)| topic.as_str()).collect();
+    consume(bus, group, &names, |topic, payload| async move {
+        let target = topics
+            .iter()
+            .find(|(name, _)| *name == topic)
+            .map(|(_, target)| *target);
+        let ids = document_ids(&payload);
+        match (target, ids.is_empty()) {
+            (Some(target), false) => record(state, v11y, db, target, &ids).await,
+            _ => Ok(()),
+        }
+    })
+    .await
+}
+
+/// Look up the indexed documents, recording the identifiers they alias.
+async fn record(
+    state: &AppState,
+    v11y: &V11yService,
+    db: &Db,
+    target: SearchTarget,
+    ids: &[String],
+) -> Result<(), Error> {
+    for chunk in ids.chunks(MAX_DOCUMENTS) {
+        let documents = chunk
+            .iter()
+            .map(|id| format!(r#"id:"{id}""#))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        let mut aliases = BTreeMap::<String, BTreeSet<String>>::new();
+        match target {
+            SearchTarget::Cve => {
+                let q = QueryParams {
+                    q: format!("({documents}) (is:published OR is:rejected)"),
+                    offset: 0,
+                    limit: chunk.len(),
+                };
+                for hit in v11y
+                    .search(q, SearchOptions::default())
+                    .await
+                    .map_err(Error::V11y)?
+                    .result
+                {
+                    aliases.entry(hit.document.id).or_default().extend(hit.document.aliases);
+                }
+            }
+            SearchTarget::Advisory => {
+                let provider = state.provider.as_ref();
+                let result = state
+                    .search_vex(&documents, 0, MAX_HITS, SearchOptions::default(), provider)
+                    .await?;
+                for hit in result.result {
+                    aliases
+                        .entry(hit.document.advisory_id)
+                        .or_default()
+                        .extend(hit.document.cves);
+                }
+            }
+            // SBOMs don't identify vulnerabilities
+            SearchTarget::Sbom => {}
+        }
+
+        for (id, aliases) in aliases {
+            let aliases: Vec<_> = aliases.into_iter().collect();
+            db.insert_aliases(&id, &aliases).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve an identifier of a vulnerability to its CVE ID, if it is known to alias one.
+///
+/// CVE IDs, and identifiers without a known CVE alias, are returned as they are.
+pub(crate) async fn resolve_cve(db: &Db, id: &str) -> Result<String, Error> {
+    if id.to_uppercase().starts_with("CVE-") {
+        return Ok(id.to_string());
+    }
+    let aliases = db.get_aliases(id).await?;
+    Ok(aliases
+        .into_iter()
+        .find(|alias| alias.starts_with("CVE-"))
+        .unwrap_or_else(|| id.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_resolve_cve() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+        db.insert_aliases("RHSA-2023:5838", &["CVE-2023-44487".to_string()])
+            .await?;
+
+        assert_eq!(resolve_cve(&db, "RHSA-2023:5838").await?, "CVE-2023-44487");
+        assert_eq!(resolve_cve(&db, "cve-2023-0001").await?, "cve-2023-0001");
+        assert_eq!(resolve_cve(&db, "GHSA-QPPJ-FM5R-HXR3").await?, "GHSA-QPPJ-FM5R-HXR3");
+
+        Ok(())
+    }
+}
@@ -3,13 +3,15 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bytes::Bytes;
 use http::StatusCode;
+use openssl::pkey::{PKey, Public};
 use tracing::instrument;
 
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_api::Apply;
 use trustification_auth::client::{TokenInjector, TokenProvider};
 use trustification_infrastructure::tracing::PropagateCurrentContext;
 
+use crate::cache::{AdvisoryCache, TimedCache};
 use crate::error::Error;
 
 pub struct AppState {
@@ -18,6 +20,22 @@ pub struct AppState {
     pub bombastic: reqwest::Url,
     pub vexination: reqwest::Url,
     pub exhort: reqwest::Url,
+    /// Maximum number of parallel advisory lookups for SBOM search results
+    pub advisory_search_concurrency: usize,
+    /// Timeout of a single advisory lookup for SBOM search results
+    pub advisory_search_timeout: std::time::Duration,
+    /// Cache of advisory counts for SBOM search results
+    pub advisory_cache: AdvisoryCache,
+    /// Cache of the dashboard summary, by the sorted permissions of the users it got computed for
+    pub dashboard_cache: TimedCache<Vec<String>, spog_model::dashboard::DashboardSummary>,
+    /// Public keys trusted to sign attestations
+    pub attestation_keys: Vec<PKey<Public>>,
+    /// OCI registries attestations of images are looked up in, by host
+    pub attestation_registries: Vec<String>,
+    /// Default sort order of advisory searches
+    pub advisory_default_sort: Option<String>,
+    /// Base URL identifying the publisher of generated VEX documents
+    pub vex_namespace: Option<String>,
 }
 
 impl AppState {
@@ -62,6 +80,45 @@ impl AppState {
         Ok(())
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn delete_sbom(&self, id: &str, provider: &dyn TokenProvider) -> Result<(), Error> {
+        let url = self.bombastic.join("/api/v1/sbom")?;
+        self.client
+            .delete(url)
+            .query(&[("id", id)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self, provider), err)]
+    pub async fn get_sbom_attestations(
+        &self,
+        id: &str,
+        provider: &dyn TokenProvider,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        let url = self.bombastic.join("/api/v1/sbom/attestations")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("id", id)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn search_sbom(
         &self,
@@ -89,6 +146,23 @@ impl AppState {
         Ok(response.json::<bombastic_model::prelude::SearchResult>().await?)
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn search_sbom_fields(&self, provider: &dyn TokenProvider) -> Result<Vec<SearchField>, Error> {
+        let url = self.bombastic.join("/api/v1/sbom/search/fields")?;
+        let response = self
+            .client
+            .get(url)
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn search_package(
         &self,
@@ -138,6 +212,24 @@ impl AppState {
         Ok(response.bytes_stream())
     }
 
+    #[instrument(skip(self, provider), err)]
+    pub async fn get_vex_revision(&self, id: &str, rev: &str, provider: &dyn TokenProvider) -> Result<Bytes, Error> {
+        let url = self.vexination.join("/api/v1/vex/revision")?;
+        let response = self
+            .client
+            .get(url)
+            .query(&[("advisory", id), ("rev", rev)])
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.bytes().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn post_vex(&self, id: &str, provider: &dyn TokenProvider, data: Bytes) -> Result<(), Error> {
         let url = self.vexination.join("/api/v1/vex")?;
@@ -157,6 +249,29 @@ impl AppState {
         Ok(())
     }
 
+    #[instrument(skip(self, provider, data), fields(size = data.len()), err)]
+    pub async fn validate_vex(
+        &self,
+        provider: &dyn TokenProvider,
+        data: Bytes,
+    ) -> Result<vexination_model::validation::AdvisoryValidation, Error> {
+        let url = self.vexination.join("/api/v1/vex/validate")?;
+        let response = self
+            .client
+            .post(url)
+            .body(data)
+            .header("content-type", "application/json")
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json().await?)
+    }
+
     #[instrument(skip(self, provider), err)]
     pub async fn search_vex(
         &self,
@@ -183,6 +298,23 @@ impl AppState {
 
         Ok(response.json::<vexination_model::prelude::SearchResult>().await?)
     }
+
+    #[instrument(skip(self, provider), err)]
+    pub async fn search_vex_fields(&self, provider: &dyn TokenProvider) -> Result<Vec<SearchField>, Error> {
+        let url = self.vexination.join("/api/v1/vex/search/fields")?;
+        let response = self
+            .client
+            .get(url)
+            .propagate_current_context()
+            .inject_token(provider)
+            .await?
+            .send()
+            .await?
+            .or_status_error()
+            .await?;
+
+        Ok(response.json().await?)
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use trustification_event_bus::EventBus;
+
+/// maximum number of cached entries
+const MAX_ENTRIES: usize = 10_000;
+
+/// A cache of the number of advisories matching a query.
+pub struct AdvisoryCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, u64)>>,
+}
+
+impl AdvisoryCache {
+    /// Create a new cache, a TTL of zero disables caching.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn get(&self, query: &str) -> Option<u64> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        match entries.get(query) {
+            Some((created, count)) if created.elapsed() < self.ttl => Some(*count),
+            Some(_) => {
+                entries.remove(query);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, query: String, count: u64) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (created, _)| created.elapsed() < self.ttl);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(query, (Instant::now(), count));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|err| err.into_inner()).clear();
+    }
+
+    /// Clear the cache whenever an event is received on the topic, until the consumer fails.
+    ///
+    /// Every instance must receive all events, so it needs a group of its own.
+    pub async fn invalidate_on_events(&self, bus: &EventBus, group: &str, topic: &str) -> anyhow::Result<()> {
+        let consumer = bus.subscribe(group, &[topic]).await?;
+        log::info!("Invalidating advisory cache on events of topic '{topic}'");
+
+        loop {
+            match consumer.next().await {
+                Ok(Some(event)) => {
+                    log::debug!("Received event on '{}', clearing advisory cache", event.topic());
+                    self.clear();
+                    if let Err(err) = consumer.commit(&[event]).await {
+                        log::warn!("Failed to commit event: {err}");
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    log::warn!("Failed to receive event: {err}");
+                    // we may have missed an event
+                    self.clear();
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+}
+
+/// Values which are cached for a limited time, by a key like the permissions of the user they got computed for.
+pub struct TimedCache<K, T> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, T)>>,
+}
+
+impl<K: Eq + Hash, T: Clone> TimedCache<K, T> {
+    /// Create a new cache, a TTL of zero disables caching.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<T> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        match entries.get(key) {
+            Some((created, value)) if created.elapsed() < self.ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&self, key: K, value: T) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.retain(|_, (created, _)| created.elapsed() < self.ttl);
+        if entries.len() >= MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let cache = AdvisoryCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("a"), None);
+
+        cache.insert("a".to_string(), 42);
+        assert_eq!(cache.get("a"), Some(42));
+
+        cache.clear();
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_cache_disabled() {
+        let cache = AdvisoryCache::new(Duration::ZERO);
+        cache.insert("a".to_string(), 42);
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_cache_expired() {
+        let cache = AdvisoryCache::new(Duration::from_millis(1));
+        cache.insert("a".to_string(), 42);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_timed_cache() {
+        let cache = TimedCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get(&"a"), None);
+        cache.insert("a", 42);
+        assert_eq!(cache.get(&"a"), Some(42));
+        assert_eq!(cache.get(&"b"), None);
+
+        let cache = TimedCache::new(Duration::ZERO);
+        cache.insert("a", 42);
+        assert_eq!(cache.get(&"a"), None);
+    }
+}
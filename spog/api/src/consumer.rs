@@ -0,0 +1,38 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use trustification_event_bus::EventBus;
+
+/// Handle every event received on the topics, until the consumer fails.
+///
+/// The handler gets the topic and the payload of each event. Failing to handle an event is logged, but still commits
+/// it, as retrying wouldn't make a difference for most failures, like broken queries.
+pub async fn consume<F, Fut, E>(bus: &EventBus, group: &str, topics: &[&str], mut handle: F) -> anyhow::Result<()>
+where
+    F: FnMut(String, Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    let consumer = bus.subscribe(group, topics).await?;
+    log::info!("Consuming events of topics {topics:?} as group '{group}'");
+
+    loop {
+        match consumer.next().await {
+            Ok(Some(event)) => {
+                if let Some(payload) = event.payload() {
+                    if let Err(err) = handle(event.topic().to_string(), payload.to_vec()).await {
+                        log::warn!("Failed to handle event of topic '{}': {err}", event.topic());
+                    }
+                }
+                if let Err(err) = consumer.commit(&[event]).await {
+                    log::warn!("Failed to commit event: {err}");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                log::warn!("Failed to receive event: {err}");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
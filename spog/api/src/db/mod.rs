@@ -0,0 +1,1407 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use futures::TryStreamExt;
+use spog_model::product::{NewProduct, Product};
+use spog_model::saved::{NewSavedSearch, SavedSearch, SearchTarget};
+use spog_model::subscription::{NewSubscription, Notification, Subscription, SubscriptionKind};
+use spog_model::watch::{NewWatch, Watch, WatchHit};
+use spog_model::webhook::{DeliveryStatus, NewWebhook, Webhook, WebhookDelivery, WebhookFilter};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteRow};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+
+static DB_FILE_NAME: &str = "spog.db";
+
+/// maximum number of identifiers of the same vulnerability
+const MAX_ALIASES: usize = 100;
+
+pub struct Db {
+    pool: SqlitePool,
+}
+
+impl Db {
+    pub async fn new(base: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let db = Self {
+            pool: SqlitePool::connect_with(if cfg!(test) {
+                SqliteConnectOptions::from_str(":memory:")?
+            } else {
+                SqliteConnectOptions::default()
+                    .filename(base.as_ref().join(DB_FILE_NAME))
+                    .create_if_missing(true)
+            })
+            .await?,
+        };
+        db.initialize().await?;
+        Ok(db)
+    }
+
+    /// Save a search for a user.
+    ///
+    /// Returns `None` if the user already has a search with the same name.
+    pub async fn insert_saved_search(
+        &self,
+        owner: &str,
+        search: NewSavedSearch,
+    ) -> Result<Option<SavedSearch>, sqlx::Error> {
+        let created = OffsetDateTime::now_utc();
+        let result = sqlx::query(
+            r#"insert or ignore into saved_searches (owner, name, query, target, created) values ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(owner)
+        .bind(search.name.as_str())
+        .bind(search.query.as_str())
+        .bind(search.target.as_str())
+        .bind(created.unix_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(SavedSearch {
+            id: result.last_insert_rowid(),
+            name: search.name,
+            query: search.query,
+            target: search.target,
+            // strip the sub-second part, as it doesn't get stored
+            created: OffsetDateTime::from_unix_timestamp(created.unix_timestamp()).unwrap_or(created),
+        }))
+    }
+
+    /// Get all searches saved by a user, ordered by name.
+    pub async fn get_saved_searches(&self, owner: &str) -> Result<Vec<SavedSearch>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                id, name, query, target, created
+            from
+                saved_searches
+            where
+                owner = $1
+            order by
+                name
+            "#,
+        )
+        .bind(owner)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move { Ok(saved_search(&row)) })
+        .try_collect()
+        .await
+    }
+
+    /// Delete a saved search of a user.
+    ///
+    /// Returns `false` if the user had no search with this ID.
+    pub async fn delete_saved_search(&self, owner: &str, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(r#"delete from saved_searches where owner = $1 and id = $2"#)
+            .bind(owner)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Register a watch for a user, along with the permissions of the user evaluating it.
+    ///
+    /// No permissions means authentication is disabled. Returns `None` if the user already has a watch with the same
+    /// name.
+    pub async fn insert_watch(
+        &self,
+        owner: &str,
+        permissions: Option<&[String]>,
+        watch: NewWatch,
+    ) -> Result<Option<Watch>, sqlx::Error> {
+        let created = OffsetDateTime::now_utc();
+        let result = sqlx::query(
+            r#"
+            insert or ignore into watches
+                (owner, permissions, name, query, target, notify, created)
+            values
+                ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(owner)
+        .bind(permissions.map(|permissions| permissions.join(" ")))
+        .bind(watch.name.as_str())
+        .bind(watch.query.as_str())
+        .bind(watch.target.as_str())
+        .bind(watch.notify.as_deref())
+        .bind(created.unix_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Watch {
+            id: result.last_insert_rowid(),
+            name: watch.name,
+            query: watch.query,
+            target: watch.target,
+            notify: watch.notify,
+            // strip the sub-second part, as it doesn't get stored
+            created: OffsetDateTime::from_unix_timestamp(created.unix_timestamp()).unwrap_or(created),
+        }))
+    }
+
+    /// Get all watches of a user, ordered by name.
+    pub async fn get_watches(&self, owner: &str) -> Result<Vec<Watch>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                id, name, query, target, notify, created
+            from
+                watches
+            where
+                owner = $1
+            order by
+                name
+            "#,
+        )
+        .bind(owner)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move { Ok(watch(&row)) })
+        .try_collect()
+        .await
+    }
+
+    /// Get the watches of all users for an index, along with the permissions of their owners.
+    pub async fn get_watches_by_target(
+        &self,
+        target: SearchTarget,
+    ) -> Result<Vec<(Option<Vec<String>>, Watch)>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                id, permissions, name, query, target, notify, created
+            from
+                watches
+            where
+                target = $1
+            "#,
+        )
+        .bind(target.as_str())
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move {
+            let permissions: Option<String> = row.get("permissions");
+            let permissions = permissions.map(|permissions| permissions.split_whitespace().map(String::from).collect());
+            Ok(watch(&row).map(|watch| (permissions, watch)))
+        })
+        .try_collect()
+        .await
+    }
+
+    /// Delete a watch of a user, along with its hits.
+    ///
+    /// Returns `false` if the user had no watch with this ID.
+    pub async fn delete_watch(&self, owner: &str, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(r#"delete from watches where owner = $1 and id = $2"#)
+            .bind(owner)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(r#"delete from watch_hits where watch_id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Record documents matching a watch.
+    ///
+    /// Returns the documents which didn't match the watch before.
+    pub async fn insert_watch_hits(&self, watch_id: i64, ids: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        let matched = OffsetDateTime::now_utc().unix_timestamp();
+        let mut result = Vec::new();
+        for id in ids {
+            let inserted =
+                sqlx::query(r#"insert or ignore into watch_hits (watch_id, document, matched) values ($1, $2, $3)"#)
+                    .bind(watch_id)
+                    .bind(id.as_str())
+                    .bind(matched)
+                    .execute(&self.pool)
+                    .await?;
+            if inserted.rows_affected() > 0 {
+                result.push(id.clone());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get the hits of a watch of a user, most recent first.
+    ///
+    /// Returns `None` if the user has no watch with this ID.
+    pub async fn get_watch_hits(
+        &self,
+        owner: &str,
+        id: i64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Option<Vec<WatchHit>>, sqlx::Error> {
+        let exists = sqlx::query(r#"select id from watches where owner = $1 and id = $2"#)
+            .bind(owner)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let hits = sqlx::query(
+            r#"
+            select
+                document, matched
+            from
+                watch_hits
+            where
+                watch_id = $1
+            order by
+                matched desc, document
+            limit $2 offset $3
+            "#,
+        )
+        .bind(id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move {
+            Ok(OffsetDateTime::from_unix_timestamp(row.get("matched"))
+                .ok()
+                .map(|matched| WatchHit {
+                    id: row.get("document"),
+                    matched,
+                }))
+        })
+        .try_collect()
+        .await?;
+
+        Ok(Some(hits))
+    }
+
+    /// Register a webhook.
+    ///
+    /// Returns `None` if there already is a webhook with the same name.
+    pub async fn insert_webhook(&self, webhook: NewWebhook) -> Result<Option<Webhook>, sqlx::Error> {
+        let created = OffsetDateTime::now_utc();
+        let filter = serde_json::to_string(&webhook.filter).unwrap_or_default();
+        let result = sqlx::query(
+            r#"insert or ignore into webhooks (name, url, secret, filter, created) values ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(webhook.name.as_str())
+        .bind(webhook.url.as_str())
+        .bind(webhook.secret.as_str())
+        .bind(filter)
+        .bind(created.unix_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Webhook {
+            id: result.last_insert_rowid(),
+            name: webhook.name,
+            url: webhook.url,
+            filter: webhook.filter,
+            // strip the sub-second part, as it doesn't get stored
+            created: OffsetDateTime::from_unix_timestamp(created.unix_timestamp()).unwrap_or(created),
+        }))
+    }
+
+    /// Get all webhooks, ordered by name.
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        sqlx::query(r#"select id, name, url, filter, created from webhooks order by name"#)
+            .fetch(&self.pool)
+            .try_filter_map(|row| async move { Ok(webhook(&row)) })
+            .try_collect()
+            .await
+    }
+
+    /// Get the secret of a webhook, used to sign its notifications.
+    pub async fn get_webhook_secret(&self, id: i64) -> Result<Option<String>, sqlx::Error> {
+        Ok(sqlx::query(r#"select secret from webhooks where id = $1"#)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| row.get("secret")))
+    }
+
+    /// Delete a webhook, along with its deliveries.
+    ///
+    /// Returns `false` if there was no webhook with this ID.
+    pub async fn delete_webhook(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(r#"delete from webhooks where id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(r#"delete from webhook_deliveries where webhook_id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Record a pending delivery to a webhook.
+    pub async fn insert_delivery(
+        &self,
+        webhook_id: i64,
+        advisories: &[String],
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let created = OffsetDateTime::now_utc().unix_timestamp();
+        let result = sqlx::query(
+            r#"insert into webhook_deliveries (webhook_id, advisories, status, attempts, created) values ($1, $2, $3, 0, $4)"#,
+        )
+        .bind(webhook_id)
+        .bind(serde_json::to_string(advisories).unwrap_or_default())
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(created)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(WebhookDelivery {
+            id: result.last_insert_rowid(),
+            advisories: advisories.to_vec(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            created: OffsetDateTime::from_unix_timestamp(created).unwrap_or_else(|_| OffsetDateTime::now_utc()),
+            attempted: None,
+            response: None,
+            error: None,
+        })
+    }
+
+    /// Record the outcome of an attempt to deliver.
+    pub async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"update webhook_deliveries set status = $1, attempts = $2, attempted = $3, response = $4, error = $5 where id = $6"#,
+        )
+        .bind(delivery.status.as_str())
+        .bind(delivery.attempts)
+        .bind(delivery.attempted.map(|attempted| attempted.unix_timestamp()))
+        .bind(delivery.response)
+        .bind(delivery.error.as_deref())
+        .bind(delivery.id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the deliveries of a webhook, most recent first.
+    ///
+    /// Returns `None` if there is no webhook with this ID.
+    pub async fn get_deliveries(
+        &self,
+        webhook_id: i64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Option<Vec<WebhookDelivery>>, sqlx::Error> {
+        let exists = sqlx::query(r#"select id from webhooks where id = $1"#)
+            .bind(webhook_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let deliveries = sqlx::query(
+            r#"
+            select
+                id, advisories, status, attempts, created, attempted, response, error
+            from
+                webhook_deliveries
+            where
+                webhook_id = $1
+            order by
+                created desc, id desc
+            limit $2 offset $3
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move { Ok(delivery(&row)) })
+        .try_collect()
+        .await?;
+
+        Ok(Some(deliveries))
+    }
+
+    /// Get all pending deliveries, along with the ID of their webhook, oldest first.
+    pub async fn get_pending_deliveries(&self) -> Result<Vec<(i64, WebhookDelivery)>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                webhook_id, id, advisories, status, attempts, created, attempted, response, error
+            from
+                webhook_deliveries
+            where
+                status = $1
+            order by
+                created, id
+            "#,
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move { Ok(delivery(&row).map(|delivery| (row.get("webhook_id"), delivery))) })
+        .try_collect()
+        .await
+    }
+
+    /// Subscribe a user to a CVE or package.
+    ///
+    /// Returns `None` if the user is already subscribed to it.
+    pub async fn insert_subscription(
+        &self,
+        owner: &str,
+        subscription: NewSubscription,
+    ) -> Result<Option<Subscription>, sqlx::Error> {
+        let created = OffsetDateTime::now_utc();
+        let result = sqlx::query(
+            r#"insert or ignore into subscriptions (owner, kind, value, notify, created) values ($1, $2, $3, $4, $5)"#,
+        )
+        .bind(owner)
+        .bind(subscription.kind.as_str())
+        .bind(subscription.value.as_str())
+        .bind(subscription.notify.as_deref())
+        .bind(created.unix_timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(Subscription {
+            id: result.last_insert_rowid(),
+            kind: subscription.kind,
+            value: subscription.value,
+            notify: subscription.notify,
+            // strip the sub-second part, as it doesn't get stored
+            created: OffsetDateTime::from_unix_timestamp(created.unix_timestamp()).unwrap_or(created),
+        }))
+    }
+
+    /// Get all subscriptions of a user, ordered by kind and value.
+    pub async fn get_subscriptions(&self, owner: &str) -> Result<Vec<Subscription>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                id, kind, value, notify, created
+            from
+                subscriptions
+            where
+                owner = $1
+            order by
+                kind, value
+            "#,
+        )
+        .bind(owner)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move { Ok(subscription(&row)) })
+        .try_collect()
+        .await
+    }
+
+    /// Get the subscriptions of all users, along with their owner.
+    pub async fn get_all_subscriptions(&self) -> Result<Vec<(String, Subscription)>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                owner, id, kind, value, notify, created
+            from
+                subscriptions
+            "#,
+        )
+        .fetch(&self.pool)
+        .try_filter_map(
+            |row| async move { Ok(subscription(&row).map(|subscription| (row.get("owner"), subscription))) },
+        )
+        .try_collect()
+        .await
+    }
+
+    /// Delete a subscription of a user, along with its notifications.
+    ///
+    /// Returns `false` if the user had no subscription with this ID.
+    pub async fn delete_subscription(&self, owner: &str, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(r#"delete from subscriptions where owner = $1 and id = $2"#)
+            .bind(owner)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(r#"delete from notifications where subscription_id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Record the updates of vulnerabilities matching a subscription of a user.
+    pub async fn insert_notifications(
+        &self,
+        owner: &str,
+        subscription_id: i64,
+        vulnerabilities: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let created = OffsetDateTime::now_utc().unix_timestamp();
+        for vulnerability in vulnerabilities {
+            sqlx::query(
+                r#"insert into notifications (owner, subscription_id, vulnerability, created) values ($1, $2, $3, $4)"#,
+            )
+            .bind(owner)
+            .bind(subscription_id)
+            .bind(vulnerability.as_str())
+            .bind(created)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Get the notifications of a user, most recent first.
+    pub async fn get_notifications(
+        &self,
+        owner: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Notification>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            select
+                id, subscription_id, vulnerability, created
+            from
+                notifications
+            where
+                owner = $1
+            order by
+                created desc, id desc
+            limit $2 offset $3
+            "#,
+        )
+        .bind(owner)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch(&self.pool)
+        .try_filter_map(|row| async move {
+            Ok(OffsetDateTime::from_unix_timestamp(row.get("created"))
+                .ok()
+                .map(|created| Notification {
+                    id: row.get("id"),
+                    subscription: row.get("subscription_id"),
+                    vulnerability: row.get("vulnerability"),
+                    created,
+                }))
+        })
+        .try_collect()
+        .await
+    }
+
+    /// Record that an identifier of a vulnerability aliases others, in both directions.
+    pub async fn insert_aliases(&self, id: &str, aliases: &[String]) -> Result<(), sqlx::Error> {
+        let id = id.to_uppercase();
+        for alias in aliases {
+            let alias = alias.to_uppercase();
+            if alias == id {
+                continue;
+            }
+            for (id, alias) in [(&id, &alias), (&alias, &id)] {
+                sqlx::query(r#"insert or ignore into aliases (id, alias) values ($1, $2)"#)
+                    .bind(id.as_str())
+                    .bind(alias.as_str())
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get all known identifiers of the same vulnerability, following aliases of aliases.
+    ///
+    /// The identifiers are sorted, and don't include the requested one.
+    pub async fn get_aliases(&self, id: &str) -> Result<Vec<String>, sqlx::Error> {
+        let id = id.to_uppercase();
+        let mut known = BTreeSet::from([id.clone()]);
+        let mut pending = vec![id.clone()];
+        while let Some(next) = pending.pop() {
+            if known.len() >= MAX_ALIASES {
+                break;
+            }
+            let aliases: Vec<String> = sqlx::query(r#"select alias from aliases where id = $1"#)
+                .bind(next.as_str())
+                .map(|row: SqliteRow| row.get("alias"))
+                .fetch_all(&self.pool)
+                .await?;
+            for alias in aliases {
+                if known.insert(alias.clone()) {
+                    pending.push(alias);
+                }
+            }
+        }
+        known.remove(&id);
+        Ok(known.into_iter().collect())
+    }
+
+    /// Register a product release, or replace the description and SBOMs of an existing one.
+    ///
+    /// Returns the product, and whether it was newly registered.
+    pub async fn put_product(
+        &self,
+        name: &str,
+        version: &str,
+        product: NewProduct,
+    ) -> Result<(Product, bool), sqlx::Error> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        // readers must not see the product without its SBOMs while they get replaced
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"insert or ignore into products (name, version, description, created, updated) values ($1, $2, $3, $4, $4)"#,
+        )
+        .bind(name)
+        .bind(version)
+        .bind(product.description.as_deref())
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        let created = result.rows_affected() > 0;
+
+        let id = Self::product_id(&mut tx, name, version)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        if !created {
+            sqlx::query(r#"update products set description = $1, updated = $2 where id = $3"#)
+                .bind(product.description.as_deref())
+                .bind(now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(r#"delete from product_sboms where product_id = $1"#)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Self::insert_product_sboms(&mut tx, id, &product.sboms).await?;
+        tx.commit().await?;
+
+        let product = self.get_product(name, version).await?.ok_or(sqlx::Error::RowNotFound)?;
+        Ok((product, created))
+    }
+
+    /// Add SBOMs to a product release, keeping the ones it already has.
+    ///
+    /// Returns `None` if there is no such product release.
+    pub async fn add_product_sboms(
+        &self,
+        name: &str,
+        version: &str,
+        sboms: &[String],
+    ) -> Result<Option<Product>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(id) = Self::product_id(&mut tx, name, version).await? else {
+            return Ok(None);
+        };
+
+        Self::insert_product_sboms(&mut tx, id, sboms).await?;
+        sqlx::query(r#"update products set updated = $1 where id = $2"#)
+            .bind(OffsetDateTime::now_utc().unix_timestamp())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.get_product(name, version).await
+    }
+
+    async fn product_id(conn: &mut SqliteConnection, name: &str, version: &str) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query(r#"select id from products where name = $1 and version = $2"#)
+            .bind(name)
+            .bind(version)
+            .map(|row: SqliteRow| row.get("id"))
+            .fetch_optional(conn)
+            .await
+    }
+
+    async fn insert_product_sboms(conn: &mut SqliteConnection, id: i64, sboms: &[String]) -> Result<(), sqlx::Error> {
+        for sbom in sboms {
+            sqlx::query(r#"insert or ignore into product_sboms (product_id, sbom) values ($1, $2)"#)
+                .bind(id)
+                .bind(sbom.as_str())
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Get all product releases, ordered by name and version.
+    pub async fn get_products(&self) -> Result<Vec<Product>, sqlx::Error> {
+        self.query_products(None).await
+    }
+
+    /// Get a product release.
+    pub async fn get_product(&self, name: &str, version: &str) -> Result<Option<Product>, sqlx::Error> {
+        Ok(self.query_products(Some((name, version))).await?.into_iter().next())
+    }
+
+    async fn query_products(&self, select: Option<(&str, &str)>) -> Result<Vec<Product>, sqlx::Error> {
+        let (name, version) = select.unzip();
+        let rows: Vec<(Option<Product>, Option<String>)> = sqlx::query(
+            r#"
+            select
+                p.name, p.version, p.description, p.created, p.updated, s.sbom
+            from
+                products p left join product_sboms s on s.product_id = p.id
+            where
+                ($1 is null or p.name = $1) and ($2 is null or p.version = $2)
+            order by
+                p.name, p.version, s.sbom
+            "#,
+        )
+        .bind(name)
+        .bind(version)
+        .map(|row: SqliteRow| (product(&row), row.get("sbom")))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut result: Vec<Product> = Vec::new();
+        for (product, sbom) in rows {
+            let Some(product) = product else {
+                continue;
+            };
+            match result.last_mut() {
+                Some(last) if last.name == product.name && last.version == product.version => {
+                    last.sboms.extend(sbom);
+                }
+                _ => result.push(Product {
+                    sboms: sbom.into_iter().collect(),
+                    ..product
+                }),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Delete a product release, along with its SBOM mapping. The SBOMs themselves are kept.
+    ///
+    /// Returns `false` if there was no such product release.
+    pub async fn delete_product(&self, name: &str, version: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(id) = Self::product_id(&mut tx, name, version).await? else {
+            return Ok(false);
+        };
+
+        sqlx::query(r#"delete from product_sboms where product_id = $1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(r#"delete from products where id = $1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn initialize(&self) -> Result<(), anyhow::Error> {
+        self.create_saved_searches_table().await?;
+        self.create_watches_tables().await?;
+        self.create_webhooks_tables().await?;
+        self.create_subscriptions_tables().await?;
+        self.create_alias_tables().await?;
+        self.create_products_tables().await?;
+        Ok(())
+    }
+
+    async fn create_products_tables(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists products (
+                    id integer primary key autoincrement,
+                    name text not null,
+                    version text not null,
+                    description text,
+                    created integer not null,
+                    updated integer not null
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            create unique index if not exists product_idx on products ( name, version ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"create table if not exists product_sboms (
+                    product_id integer not null,
+                    sbom text not null,
+                    primary key ( product_id, sbom )
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_alias_tables(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists aliases (
+                    id text not null,
+                    alias text not null,
+                    primary key ( id, alias )
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_subscriptions_tables(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists subscriptions (
+                    id integer primary key autoincrement,
+                    owner text not null,
+                    kind text not null,
+                    value text not null,
+                    notify text,
+                    created integer not null
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            create unique index if not exists subscription_idx on subscriptions ( owner, kind, value ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"create table if not exists notifications (
+                    id integer primary key autoincrement,
+                    owner text not null,
+                    subscription_id integer not null,
+                    vulnerability text not null,
+                    created integer not null
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            create index if not exists notification_idx on notifications ( owner, created ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_webhooks_tables(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists webhooks (
+                    id integer primary key autoincrement,
+                    name text not null unique,
+                    url text not null,
+                    secret text not null,
+                    filter text not null,
+                    created integer not null
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"create table if not exists webhook_deliveries (
+                    id integer primary key autoincrement,
+                    webhook_id integer not null,
+                    advisories text not null,
+                    status text not null,
+                    attempts integer not null,
+                    created integer not null,
+                    attempted integer,
+                    response integer,
+                    error text
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            create index if not exists webhook_delivery_idx on webhook_deliveries ( webhook_id, created ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_watches_tables(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists watches (
+                    id integer primary key autoincrement,
+                    owner text not null,
+                    name text not null,
+                    query text not null,
+                    target text not null,
+                    notify text,
+                    created integer not null,
+                    permissions text
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // watches created before the permissions of their owners got recorded must not be evaluated
+        let recorded = sqlx::query(r#"select name from pragma_table_info('watches') where name = 'permissions'"#)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !recorded {
+            sqlx::query(r#"alter table watches add column permissions text"#)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query(r#"update watches set permissions = ''"#)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        sqlx::query(
+            r#"
+            create unique index if not exists watch_idx on watches ( owner, name ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"create table if not exists watch_hits (
+                    watch_id integer not null,
+                    document text not null,
+                    matched integer not null,
+                    primary key ( watch_id, document )
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_saved_searches_table(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(
+            r#"create table if not exists saved_searches (
+                    id integer primary key autoincrement,
+                    owner text not null,
+                    name text not null,
+                    query text not null,
+                    target text not null,
+                    created integer not null
+                )"#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            create unique index if not exists saved_search_idx on saved_searches ( owner, name ) ;
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Convert a row into a saved search, skipping rows with unknown targets.
+fn saved_search(row: &SqliteRow) -> Option<SavedSearch> {
+    Some(SavedSearch {
+        id: row.get("id"),
+        name: row.get("name"),
+        query: row.get("query"),
+        target: SearchTarget::parse(row.get("target"))?,
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+    })
+}
+
+/// Convert a row into a watch, skipping rows with unknown targets.
+fn watch(row: &SqliteRow) -> Option<Watch> {
+    Some(Watch {
+        id: row.get("id"),
+        name: row.get("name"),
+        query: row.get("query"),
+        target: SearchTarget::parse(row.get("target"))?,
+        notify: row.get("notify"),
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+    })
+}
+
+/// Convert a row into a subscription, skipping rows with unknown kinds.
+fn subscription(row: &SqliteRow) -> Option<Subscription> {
+    Some(Subscription {
+        id: row.get("id"),
+        kind: SubscriptionKind::parse(row.get("kind"))?,
+        value: row.get("value"),
+        notify: row.get("notify"),
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+    })
+}
+
+/// Convert a row into a product, without its SBOMs.
+fn product(row: &SqliteRow) -> Option<Product> {
+    Some(Product {
+        name: row.get("name"),
+        version: row.get("version"),
+        description: row.get("description"),
+        sboms: Vec::new(),
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+        updated: OffsetDateTime::from_unix_timestamp(row.get("updated")).ok()?,
+    })
+}
+
+/// Convert a row into a webhook.
+fn webhook(row: &SqliteRow) -> Option<Webhook> {
+    Some(Webhook {
+        id: row.get("id"),
+        name: row.get("name"),
+        url: row.get("url"),
+        filter: serde_json::from_str::<WebhookFilter>(row.get("filter")).ok()?,
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+    })
+}
+
+/// Convert a row into a delivery, skipping rows with unknown states.
+fn delivery(row: &SqliteRow) -> Option<WebhookDelivery> {
+    Some(WebhookDelivery {
+        id: row.get("id"),
+        advisories: serde_json::from_str(row.get("advisories")).ok()?,
+        status: DeliveryStatus::parse(row.get("status"))?,
+        attempts: row.get("attempts"),
+        created: OffsetDateTime::from_unix_timestamp(row.get("created")).ok()?,
+        attempted: row
+            .get::<Option<i64>, _>("attempted")
+            .and_then(|attempted| OffsetDateTime::from_unix_timestamp(attempted).ok()),
+        response: row.get("response"),
+        error: row.get("error"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn search(name: &str) -> NewSavedSearch {
+        NewSavedSearch {
+            name: name.to_string(),
+            query: "is:critical".to_string(),
+            target: SearchTarget::Advisory,
+        }
+    }
+
+    #[actix_web::test]
+    async fn saved_searches() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        let b = db.insert_saved_search("alice", search("b")).await?.unwrap();
+        let a = db.insert_saved_search("alice", search("a")).await?.unwrap();
+        db.insert_saved_search("bob", search("a")).await?.unwrap();
+
+        // names are unique per user
+        assert!(db.insert_saved_search("alice", search("a")).await?.is_none());
+
+        let searches = db.get_saved_searches("alice").await?;
+        assert_eq!(searches, vec![a.clone(), b.clone()]);
+
+        // users can only delete their own searches
+        assert!(!db.delete_saved_search("bob", a.id).await?);
+        assert!(db.delete_saved_search("alice", a.id).await?);
+        assert!(!db.delete_saved_search("alice", a.id).await?);
+
+        assert_eq!(db.get_saved_searches("alice").await?, vec![b]);
+        assert_eq!(db.get_saved_searches("bob").await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn saved_searches_per_user() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+        assert!(db.get_saved_searches("alice").await?.is_empty());
+
+        let search = NewSavedSearch {
+            name: "ubi".to_string(),
+            query: "ubi9 in:package".to_string(),
+            target: SearchTarget::Sbom,
+        };
+        let alice = db.insert_saved_search("alice", search.clone()).await?.unwrap();
+        assert_eq!(
+            (alice.name.as_str(), alice.query.as_str(), alice.target),
+            ("ubi", "ubi9 in:package", SearchTarget::Sbom)
+        );
+        // other users may use the same name
+        let bob = db.insert_saved_search("bob", search).await?.unwrap();
+        assert_ne!(alice.id, bob.id);
+
+        assert_eq!(db.get_saved_searches("alice").await?, vec![alice.clone()]);
+        assert_eq!(db.get_saved_searches("bob").await?, vec![bob.clone()]);
+        assert!(db.get_saved_searches("carol").await?.is_empty());
+
+        // deleting the search of one user keeps the search of the other
+        assert!(db.delete_saved_search("bob", bob.id).await?);
+        assert!(db.get_saved_searches("bob").await?.is_empty());
+        assert_eq!(db.get_saved_searches("alice").await?, vec![alice]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn watches() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        let watch = |name: &str| NewWatch {
+            name: name.to_string(),
+            query: "is:critical".to_string(),
+            target: SearchTarget::Advisory,
+            notify: None,
+        };
+
+        let permissions = vec!["read.vex".to_string(), "read.sbom".to_string()];
+        let a = db.insert_watch("alice", Some(&permissions), watch("a")).await?.unwrap();
+        assert!(db.insert_watch("alice", None, watch("a")).await?.is_none());
+        db.insert_watch("bob", None, watch("b")).await?.unwrap();
+
+        assert_eq!(db.get_watches("alice").await?, vec![a.clone()]);
+        let mut watches = db.get_watches_by_target(SearchTarget::Advisory).await?;
+        watches.sort_by_key(|(_, watch)| watch.id);
+        assert_eq!(
+            watches,
+            vec![(Some(permissions), a.clone()), (None, watches[1].1.clone())]
+        );
+        assert!(db.get_watches_by_target(SearchTarget::Sbom).await?.is_empty());
+
+        let ids = vec!["1".to_string(), "2".to_string()];
+        assert_eq!(db.insert_watch_hits(a.id, &ids).await?, ids);
+        // hits are only recorded once
+        assert_eq!(
+            db.insert_watch_hits(a.id, &["2".to_string(), "3".to_string()]).await?,
+            vec!["3".to_string()]
+        );
+
+        let hits = db.get_watch_hits("alice", a.id, 0, 10).await?.unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(db.get_watch_hits("alice", a.id, 1, 1).await?.unwrap().len(), 1);
+
+        // users can only access their own watches
+        assert!(db.get_watch_hits("bob", a.id, 0, 10).await?.is_none());
+        assert!(!db.delete_watch("bob", a.id).await?);
+        assert!(db.delete_watch("alice", a.id).await?);
+        assert!(db.get_watch_hits("alice", a.id, 0, 10).await?.is_none());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn webhooks() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        let webhook = |name: &str| NewWebhook {
+            name: name.to_string(),
+            url: "https://example.com/hook".to_string(),
+            secret: "secret".to_string(),
+            filter: WebhookFilter {
+                severities: vec!["critical".to_string()],
+                products: vec![],
+            },
+        };
+
+        let a = db.insert_webhook(webhook("a")).await?.unwrap();
+        assert!(db.insert_webhook(webhook("a")).await?.is_none());
+        let b = db.insert_webhook(webhook("b")).await?.unwrap();
+        assert_eq!(db.get_webhooks().await?, vec![a.clone(), b.clone()]);
+        assert_eq!(db.get_webhook_secret(a.id).await?.as_deref(), Some("secret"));
+
+        let ids = vec!["RHSA-2023:1441".to_string()];
+        let mut delivery = db.insert_delivery(a.id, &ids).await?;
+        db.insert_delivery(b.id, &ids).await?;
+        assert_eq!(db.get_pending_deliveries().await?.len(), 2);
+
+        delivery.status = DeliveryStatus::Delivered;
+        delivery.attempts = 1;
+        delivery.attempted = Some(delivery.created);
+        delivery.response = Some(204);
+        db.update_delivery(&delivery).await?;
+
+        assert_eq!(db.get_deliveries(a.id, 0, 10).await?.unwrap(), vec![delivery]);
+        assert_eq!(db.get_pending_deliveries().await?.len(), 1);
+        assert!(db.get_deliveries(0, 0, 10).await?.is_none());
+
+        assert!(db.delete_webhook(b.id).await?);
+        assert!(!db.delete_webhook(b.id).await?);
+        assert!(db.get_pending_deliveries().await?.is_empty());
+        assert_eq!(db.get_webhooks().await?, vec![a]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn subscriptions() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        let subscription = |kind, value: &str| NewSubscription {
+            kind,
+            value: value.to_string(),
+            notify: None,
+        };
+
+        let cve = db
+            .insert_subscription("alice", subscription(SubscriptionKind::Cve, "CVE-2023-44487"))
+            .await?
+            .unwrap();
+        let purl = db
+            .insert_subscription("alice", subscription(SubscriptionKind::Purl, "pkg:pypi/django"))
+            .await?
+            .unwrap();
+        // users can only subscribe once
+        assert!(db
+            .insert_subscription("alice", subscription(SubscriptionKind::Cve, "CVE-2023-44487"))
+            .await?
+            .is_none());
+        db.insert_subscription("bob", subscription(SubscriptionKind::Cve, "CVE-2023-44487"))
+            .await?
+            .unwrap();
+
+        assert_eq!(db.get_subscriptions("alice").await?, vec![cve.clone(), purl.clone()]);
+        assert_eq!(db.get_all_subscriptions().await?.len(), 3);
+
+        db.insert_notifications("alice", cve.id, &["CVE-2023-44487".to_string()])
+            .await?;
+        db.insert_notifications(
+            "alice",
+            purl.id,
+            &["GHSA-QMF9-6JQF-J8FQ".to_string(), "PYSEC-2023-228".to_string()],
+        )
+        .await?;
+
+        let notifications = db.get_notifications("alice", 0, 10).await?;
+        assert_eq!(notifications.len(), 3);
+        assert_eq!(notifications[0].vulnerability, "PYSEC-2023-228");
+        assert_eq!(db.get_notifications("alice", 1, 1).await?.len(), 1);
+        assert!(db.get_notifications("bob", 0, 10).await?.is_empty());
+
+        // users can only delete their own subscriptions
+        assert!(!db.delete_subscription("bob", purl.id).await?);
+        assert!(db.delete_subscription("alice", purl.id).await?);
+        assert_eq!(db.get_notifications("alice", 0, 10).await?.len(), 1);
+        assert_eq!(db.get_subscriptions("alice").await?, vec![cve]);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn aliases() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        db.insert_aliases("CVE-2023-44487", &["ghsa-qppj-fm5r-hxr3".to_string()])
+            .await?;
+        db.insert_aliases(
+            "RHSA-2023:5838",
+            &["CVE-2023-44487".to_string(), "RHSA-2023:5838".to_string()],
+        )
+        .await?;
+        // recording the same aliases again is fine
+        db.insert_aliases("GHSA-QPPJ-FM5R-HXR3", &["CVE-2023-44487".to_string()])
+            .await?;
+
+        assert_eq!(
+            db.get_aliases("cve-2023-44487").await?,
+            vec!["GHSA-QPPJ-FM5R-HXR3".to_string(), "RHSA-2023:5838".to_string()]
+        );
+        // aliases of aliases are followed
+        assert_eq!(
+            db.get_aliases("GHSA-QPPJ-FM5R-HXR3").await?,
+            vec!["CVE-2023-44487".to_string(), "RHSA-2023:5838".to_string()]
+        );
+        assert!(db.get_aliases("CVE-2023-0001").await?.is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn products() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+
+        let product = |sboms: &[&str]| NewProduct {
+            description: None,
+            sboms: sboms.iter().map(|sbom| sbom.to_string()).collect(),
+        };
+
+        let (rhel, created) = db.put_product("rhel", "9.2", product(&["b", "a"])).await?;
+        assert!(created);
+        assert_eq!(rhel.sboms, vec!["a".to_string(), "b".to_string()]);
+        let (_, created) = db.put_product("quarkus", "3.2", product(&[])).await?;
+        assert!(created);
+
+        // putting the product again replaces its SBOMs
+        let (rhel, created) = db.put_product("rhel", "9.2", product(&["c"])).await?;
+        assert!(!created);
+        assert_eq!(rhel.sboms, vec!["c".to_string()]);
+
+        let rhel = db
+            .add_product_sboms("rhel", "9.2", &["a".to_string(), "c".to_string()])
+            .await?
+            .unwrap();
+        assert_eq!(rhel.sboms, vec!["a".to_string(), "c".to_string()]);
+        assert!(db.add_product_sboms("rhel", "8.8", &["a".to_string()]).await?.is_none());
+
+        let products = db.get_products().await?;
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0].name, "quarkus");
+        assert!(products[0].sboms.is_empty());
+        assert_eq!(products[1], rhel);
+
+        assert!(db.delete_product("rhel", "9.2").await?);
+        assert!(!db.delete_product("rhel", "9.2").await?);
+        assert!(db.get_product("rhel", "9.2").await?.is_none());
+        // SBOMs don't stick around when registering the product again
+        let (rhel, _) = db.put_product("rhel", "9.2", product(&[])).await?;
+        assert!(rhel.sboms.is_empty());
+
+        Ok(())
+    }
+}
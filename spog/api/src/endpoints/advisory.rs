@@ -1,16 +1,31 @@
-use actix_web::{web, web::ServiceConfig, HttpResponse};
+use actix_web::{web, web::ServiceConfig, HttpRequest, HttpResponse};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use http::header;
 use log::trace;
 use spog_model::search::AdvisorySummary;
 use std::sync::Arc;
 use tracing::instrument;
-use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_api::search::{SearchField, SearchOptions, SearchResult};
 use trustification_auth::authenticator::Authenticator;
 use trustification_infrastructure::new_auth;
 use utoipa::IntoParams;
 
-use crate::{app_state::AppState, search::QueryParams};
+use crate::{
+    app_state::AppState,
+    search::{self, ExportFormat, ExportParams, QueryParams, StreamParams},
+};
+
+mod aging;
+mod diff;
+mod feed;
+mod impact;
+mod validate;
+
+pub use aging::*;
+pub use diff::*;
+pub use feed::*;
+pub use impact::*;
+pub use validate::*;
 
 const MAX_LIMIT: usize = 1_000;
 
@@ -18,9 +33,42 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
     |config: &mut ServiceConfig| {
         config.service(
             web::resource("/api/v1/advisory/search")
-                .wrap(new_auth!(auth))
+                .wrap(new_auth!(auth.clone()))
                 .to(search),
         );
+        config.service(
+            web::resource("/api/v1/advisory/search/export")
+                .wrap(new_auth!(auth.clone()))
+                .to(export),
+        );
+        config.service(
+            web::resource("/api/v1/advisory/search/fields")
+                .wrap(new_auth!(auth.clone()))
+                .to(search_fields),
+        );
+        // like the get operation, the feed checks the access_token query parameter, as feed readers can't authenticate
+        config.service(web::resource("/api/v1/advisory/feed.atom").to(feed));
+        config.service(
+            web::resource("/api/v1/advisory/metrics/aging")
+                .wrap(new_auth!(auth.clone()))
+                .to(aging),
+        );
+        config.service(
+            web::resource("/api/v1/advisory/validate")
+                .wrap(new_auth!(auth.clone()))
+                .app_data(web::PayloadConfig::new(validate::PAYLOAD_LIMIT))
+                .route(web::post().to(validate)),
+        );
+        config.service(
+            web::resource("/api/v1/advisory/{id}/diff")
+                .wrap(new_auth!(auth.clone()))
+                .to(diff),
+        );
+        config.service(
+            web::resource("/api/v1/advisory/{id}/impact")
+                .wrap(new_auth!(auth))
+                .to(impact),
+        );
         // the get operation doesn't get the authenticator added, as we check this using the access_token query parameter
         config.service(web::resource("/api/v1/advisory").to(get));
     }
@@ -64,48 +112,190 @@ pub async fn get(
     responses(
         (status = OK, description = "Search was performed successfully", body = SearchResultVex),
     ),
-    params(QueryParams, SearchOptions)
+    params(QueryParams, SearchOptions, StreamParams)
 )]
-#[instrument(skip(state, access_token), err)]
+#[instrument(skip(state, req, access_token), err)]
 pub async fn search(
     state: web::Data<AppState>,
+    req: HttpRequest,
     params: web::Query<QueryParams>,
     options: web::Query<SearchOptions>,
+    stream: web::Query<StreamParams>,
     access_token: Option<BearerAuth>,
 ) -> actix_web::Result<HttpResponse> {
     let params = params.into_inner();
     trace!("Querying VEX using {}", params.q);
+    let options = default_sort(state.advisory_default_sort.as_deref(), &params.q, options.into_inner());
+
+    if stream.enabled(&req) {
+        let options = SearchOptions {
+            facets: Vec::new(),
+            cursor: None,
+            ..options
+        };
+        return Ok(search::ndjson_response(search::ndjson(
+            params.offset,
+            params.limit.min(MAX_LIMIT),
+            move |offset, limit| {
+                let (state, q, options, access_token) =
+                    (state.clone(), params.q.clone(), options.clone(), access_token.clone());
+                async move {
+                    let result = state.search_vex(&q, offset, limit, options, &access_token).await?;
+                    Ok(result.result.into_iter().map(into_summary).collect::<Vec<_>>())
+                }
+            },
+        )));
+    }
+
     let result = state
         .search_vex(
             &params.q,
             params.offset,
             params.limit.min(MAX_LIMIT),
-            options.into_inner(),
+            options,
             &access_token,
         )
         .await?;
 
-    let mut m = Vec::with_capacity(result.result.len());
-    for item in result.result {
-        let metadata = item.metadata.unwrap_or_default();
-        let item = item.document;
-        m.push(AdvisorySummary {
-            id: item.advisory_id.clone(),
-            title: item.advisory_title,
-            snippet: item.advisory_snippet,
-            desc: item.advisory_desc,
-            date: item.advisory_date,
-            severity: item.advisory_severity,
-            cvss_max: item.cvss_max,
-            href: format!("/api/v1/advisory?id={}", item.advisory_id),
-            cves: item.cves,
-            cve_severity_count: item.cve_severity_count,
-            metadata,
-        });
-    }
-
     Ok(HttpResponse::Ok().json(SearchResult::<Vec<AdvisorySummary>> {
         total: Some(result.total),
-        result: m,
+        result: result.result.into_iter().map(into_summary).collect(),
+        facets: result.facets,
+        cursor: result.cursor,
     }))
 }
+
+/// Apply the default sort order, unless the search requests one, either as option or in the query, or uses a cursor.
+fn default_sort(default: Option<&str>, q: &str, mut options: SearchOptions) -> SearchOptions {
+    let sorted = q
+        .split_whitespace()
+        .any(|term| term.starts_with("sort:") || term.starts_with("-sort:"));
+    if options.sort.is_none() && options.cursor.is_none() && !sorted {
+        options.sort = default.map(ToString::to_string);
+    }
+    options
+}
+
+const COLUMNS: [&str; 8] = ["id", "title", "severity", "date", "cvss_max", "cves", "desc", "href"];
+
+fn row(advisory: &AdvisorySummary) -> [String; 8] {
+    [
+        advisory.id.clone(),
+        advisory.title.clone(),
+        advisory.severity.clone().unwrap_or_default(),
+        advisory.date.to_string(),
+        advisory.cvss_max.map(|score| score.to_string()).unwrap_or_default(),
+        advisory.cves.join(" "),
+        advisory.desc.clone(),
+        advisory.href.clone(),
+    ]
+}
+
+/// Export all advisories matching a query.
+///
+/// The query is run page by page, using `limit` as page size, streaming the results.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/search/export",
+    responses(
+        (status = OK, description = "Search was performed successfully", content_type = "text/csv", body = String),
+    ),
+    params(QueryParams, ExportParams)
+)]
+#[instrument(skip(state, access_token))]
+pub async fn export(
+    state: web::Data<AppState>,
+    params: web::Query<QueryParams>,
+    export: web::Query<ExportParams>,
+    access_token: Option<BearerAuth>,
+) -> HttpResponse {
+    let params = params.into_inner();
+    trace!("Exporting VEX using {}", params.q);
+    let options = default_sort(
+        state.advisory_default_sort.as_deref(),
+        &params.q,
+        SearchOptions::default(),
+    );
+
+    let pages = search::pages(params.offset, params.limit.min(MAX_LIMIT), move |offset, limit| {
+        let (state, q, options, access_token) =
+            (state.clone(), params.q.clone(), options.clone(), access_token.clone());
+        async move {
+            let result = state.search_vex(&q, offset, limit, options, &access_token).await?;
+            Ok(result.result.into_iter().map(into_summary).collect::<Vec<_>>())
+        }
+    });
+
+    match export.format {
+        ExportFormat::Csv => search::csv_response("advisories", search::csv(COLUMNS, pages, row)),
+    }
+}
+
+/// List the fields advisory search queries can be qualified with, including their type and an example query.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/search/fields",
+    responses(
+        (status = OK, description = "Search fields", body = Vec<SearchField>),
+    ),
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn search_fields(
+    state: web::Data<AppState>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let fields = state.search_vex_fields(&access_token).await?;
+    Ok(HttpResponse::Ok().json(fields))
+}
+
+/// Convert a search hit from vexination into a summary.
+pub(crate) fn into_summary(item: vexination_model::search::SearchHit) -> AdvisorySummary {
+    let metadata = item.metadata.unwrap_or_default();
+    let item = item.document;
+    AdvisorySummary {
+        id: item.advisory_id.clone(),
+        title: item.advisory_title,
+        snippet: item.advisory_snippet,
+        desc: item.advisory_desc,
+        date: item.advisory_date,
+        severity: item.advisory_severity,
+        cvss_max: item.cvss_max,
+        href: format!("/api/v1/advisory?id={}", item.advisory_id),
+        cves: item.cves,
+        cve_severity_count: item.cve_severity_count,
+        remediations: item.remediations,
+        metadata,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_sort() {
+        let sort = |default, q, options| default_sort(default, q, options).sort;
+
+        assert_eq!(sort(None, "openssl", SearchOptions::default()), None);
+        assert_eq!(
+            sort(Some("release:desc"), "openssl", SearchOptions::default()).as_deref(),
+            Some("release:desc")
+        );
+        assert_eq!(
+            sort(Some("release:desc"), "openssl -sort:severity", SearchOptions::default()),
+            None
+        );
+
+        let options = SearchOptions {
+            sort: Some("initial:asc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(sort(Some("release:desc"), "", options).as_deref(), Some("initial:asc"));
+
+        let options = SearchOptions {
+            cursor: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(sort(Some("release:desc"), "", options), None);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::aging::{AdvisoryAging, AgingBucket};
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_common::error::ErrorInformation;
+use utoipa::IntoParams;
+use vexination_model::search::SearchDocument;
+
+/// maximum number of advisories evaluated by a single request
+const MAX_ADVISORIES: usize = 10_000;
+/// number of advisories requested at once
+const PAGE_SIZE: usize = 1_000;
+/// upper bounds of the buckets, in days
+const BUCKETS: [u32; 7] = [0, 1, 7, 30, 90, 180, 365];
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct AgingParams {
+    /// A query string, further selecting the advisories
+    #[serde(default)]
+    pub q: String,
+    /// Only evaluate advisories of the publisher
+    pub publisher: Option<String>,
+    /// Only evaluate advisories about the product, identified by its purl or CPE
+    pub product: Option<String>,
+}
+
+/// Distribution of the time from the publication of CVEs to the release of the advisories addressing them.
+///
+/// The CVE publication date is the release date of the vulnerability, and the advisory release date the initial
+/// release date of the advisory, both as indexed.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/metrics/aging",
+    responses(
+        (status = OK, description = "Aging was computed", body = AdvisoryAging),
+        (status = BAD_REQUEST, description = "Invalid publisher or product"),
+    ),
+    params(AgingParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn aging(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<AgingParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(q) = query(&params) else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidParameter".to_string(),
+            message: "The publisher and product must not contain quotes".to_string(),
+            details: String::new(),
+        }));
+    };
+
+    let mut documents = vec![];
+    let mut total = 0;
+    while documents.len() < MAX_ADVISORIES {
+        let result = state
+            .search_vex(&q, documents.len(), PAGE_SIZE, SearchOptions::default(), &access_token)
+            .await?;
+        total = result.total;
+        let received = result.result.len();
+        documents.extend(result.result.into_iter().map(|hit| hit.document));
+        if received < PAGE_SIZE {
+            break;
+        }
+    }
+
+    let mut result = compute(&documents);
+    result.truncated = total > documents.len();
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Build the query of the parameters, unless they contain quotes.
+fn query(params: &AgingParams) -> Option<String> {
+    let mut q = vec![];
+    if !params.q.is_empty() {
+        q.push(format!("({})", params.q));
+    }
+    for (qualifier, value) in [("publisher", &params.publisher), ("package", &params.product)] {
+        if let Some(value) = value {
+            if value.contains('"') {
+                return None;
+            }
+            q.push(format!(r#"{qualifier}:"{value}""#));
+        }
+    }
+    Some(q.join(" "))
+}
+
+fn compute(documents: &[SearchDocument]) -> AdvisoryAging {
+    let mut result = AdvisoryAging {
+        advisories: documents.len(),
+        buckets: BUCKETS
+            .iter()
+            .map(|max| Some(*max))
+            .chain([None])
+            .map(|max_days| AgingBucket { max_days, count: 0 })
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut ages = vec![];
+    for document in documents {
+        let released = document.advisory_initial_date.unwrap_or(document.advisory_date);
+        for cve in &document.cves {
+            match document.cve_release_dates.get(cve) {
+                Some(published) => ages.push((released - *published).as_seconds_f64() / 86_400.0),
+                None => result.unknown += 1,
+            }
+        }
+    }
+    ages.sort_by(f64::total_cmp);
+
+    for age in &ages {
+        let bucket = BUCKETS
+            .iter()
+            .position(|max| *age <= *max as f64)
+            .unwrap_or(BUCKETS.len());
+        result.buckets[bucket].count += 1;
+    }
+
+    result.count = ages.len();
+    result.min = ages.first().copied();
+    result.max = ages.last().copied();
+    result.mean = (!ages.is_empty()).then(|| ages.iter().sum::<f64>() / ages.len() as f64);
+    result.median = percentile(&ages, 0.5);
+    result.p90 = percentile(&ages, 0.9);
+    result
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use time::{Duration, OffsetDateTime};
+
+    fn document(id: &str, released: OffsetDateTime, published: &[(&str, Option<Duration>)]) -> SearchDocument {
+        SearchDocument {
+            advisory_id: id.to_string(),
+            advisory_title: String::new(),
+            advisory_date: released + Duration::days(30),
+            advisory_initial_date: Some(released),
+            advisory_snippet: String::new(),
+            advisory_desc: String::new(),
+            advisory_severity: None,
+            cves: published.iter().map(|(cve, _)| cve.to_string()).collect(),
+            cvss_max: None,
+            cve_severity_count: HashMap::new(),
+            remediations: vec![],
+            cve_release_dates: published
+                .iter()
+                .filter_map(|(cve, age)| age.map(|age| (cve.to_string(), released - age)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_compute() {
+        let released = OffsetDateTime::from_unix_timestamp(1679570040).unwrap();
+        let result = compute(&[
+            document(
+                "RHSA-2023:1441",
+                released,
+                &[
+                    ("CVE-2023-0286", Some(Duration::days(44))),
+                    ("CVE-2023-0215", Some(Duration::hours(12))),
+                ],
+            ),
+            document(
+                "RHSA-2023:3408",
+                released,
+                &[
+                    ("CVE-2023-2700", Some(Duration::days(-2))),
+                    ("CVE-2023-2454", Some(Duration::days(400))),
+                    ("CVE-2023-2455", None),
+                ],
+            ),
+        ]);
+
+        assert_eq!(result.advisories, 2);
+        assert_eq!(result.count, 4);
+        assert_eq!(result.unknown, 1);
+        assert_eq!(result.min, Some(-2.0));
+        assert_eq!(result.max, Some(400.0));
+        assert_eq!(result.median, Some(0.5));
+        assert_eq!(result.p90, Some(400.0));
+        let counts: Vec<_> = result.buckets.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, [1, 1, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(result.buckets.last().unwrap().max_days, None);
+
+        let empty = compute(&[]);
+        assert_eq!(empty.count, 0);
+        assert_eq!(empty.median, None);
+    }
+
+    #[test]
+    fn test_query() {
+        let params = |q: &str, publisher: Option<&str>, product: Option<&str>| AgingParams {
+            q: q.to_string(),
+            publisher: publisher.map(ToString::to_string),
+            product: product.map(ToString::to_string),
+        };
+        assert_eq!(query(&params("", None, None)).unwrap(), "");
+        assert_eq!(
+            query(&params(
+                "is:critical",
+                Some("Red Hat Product Security"),
+                Some("cpe:/o:redhat:rhel_eus:8.6::baseos")
+            ))
+            .unwrap(),
+            r#"(is:critical) publisher:"Red Hat Product Security" package:"cpe:/o:redhat:rhel_eus:8.6::baseos""#
+        );
+        assert!(query(&params("", Some(r#"Red "Hat""#), None)).is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use crate::app_state::AppState;
+use crate::error::Error;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use csaf::{
+    definitions::ProductIdT,
+    vulnerability::{ProductStatus, Vulnerability},
+    Csaf,
+};
+use spog_model::csaf::walk_product_tree_branches;
+use spog_model::diff::{AdvisoryDiff, ScoreChange, StatementChange};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::instrument;
+use trustification_auth::client::TokenProvider;
+use trustification_common::error::ErrorInformation;
+use vexination_model::convert::parse_csaf;
+
+/// Product status of the vulnerabilities of an advisory, by vulnerability and product
+type Statements = BTreeMap<(String, String), String>;
+
+/// Compare two revisions of an advisory.
+///
+/// The revisions are passed as two `rev` query parameters, the first one being the older revision. Revisions are
+/// named after the version of the advisory.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/{id}/diff",
+    responses(
+        (status = OK, description = "Processing succeeded", body = AdvisoryDiff),
+        (status = BAD_REQUEST, description = "Not exactly two revisions were requested"),
+        (status = NOT_FOUND, description = "Revision was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the advisory"),
+        ("rev" = Vec<String>, Query, description = "The two revisions to compare"),
+    )
+)]
+#[instrument(skip(state, req, access_token), err)]
+pub async fn diff(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    req: HttpRequest,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let revisions: Vec<_> = url::form_urlencoded::parse(req.query_string().as_bytes())
+        .filter(|(key, _)| key == "rev")
+        .map(|(_, value)| value.to_string())
+        .collect();
+    let [from, to] = revisions.as_slice() else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidRevisions".to_string(),
+            message: "Exactly two revisions must be requested using the 'rev' parameter".to_string(),
+            details: format!("Requested revisions: {revisions:?}"),
+        }));
+    };
+
+    let (a, b) = futures::try_join!(
+        fetch_revision(&state, &access_token, &id, from),
+        fetch_revision(&state, &access_token, &id, to),
+    )?;
+
+    let mut result = diff_advisories(&a, &b);
+    result.from = from.clone();
+    result.to = to.clone();
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[instrument(skip(state, access_token), err)]
+async fn fetch_revision(
+    state: &AppState,
+    access_token: &dyn TokenProvider,
+    id: &str,
+    rev: &str,
+) -> Result<Csaf, Error> {
+    let data = state.get_vex_revision(id, rev, access_token).await?;
+    Ok(parse_csaf(&data)?)
+}
+
+/// Compare two advisories, `a` being the older one.
+fn diff_advisories(a: &Csaf, b: &Csaf) -> AdvisoryDiff {
+    let (products_a, products_b) = (products(a), products(b));
+    let (scores_a, scores_b) = (scores(a), scores(b));
+    let (statements_a, statements_b) = (statements(a), statements(b));
+
+    let scores = scores_a
+        .keys()
+        .chain(scores_b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|vulnerability| {
+            let (from, to) = (
+                scores_a.get(vulnerability).copied(),
+                scores_b.get(vulnerability).copied(),
+            );
+            (from != to).then(|| ScoreChange {
+                vulnerability: vulnerability.clone(),
+                from,
+                to,
+            })
+        })
+        .collect();
+
+    let statements = statements_a
+        .keys()
+        .chain(statements_b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|key| {
+            let (from, to) = (statements_a.get(key).cloned(), statements_b.get(key).cloned());
+            (from != to).then(|| StatementChange {
+                vulnerability: key.0.clone(),
+                product: key.1.clone(),
+                from,
+                to,
+            })
+        })
+        .collect();
+
+    AdvisoryDiff {
+        from: a.document.tracking.version.clone(),
+        to: b.document.tracking.version.clone(),
+        products_added: products_b.difference(&products_a).cloned().collect(),
+        products_removed: products_a.difference(&products_b).cloned().collect(),
+        scores,
+        statements,
+    }
+}
+
+/// Collect the IDs of all products defined by the product tree.
+fn products(csaf: &Csaf) -> BTreeSet<String> {
+    let mut result = BTreeSet::new();
+    walk_product_tree_branches(&csaf.product_tree, |_, branch| {
+        if let Some(product) = &branch.product {
+            result.insert(product.product_id.0.clone());
+        }
+    });
+
+    if let Some(tree) = &csaf.product_tree {
+        for product in tree.full_product_names.iter().flatten() {
+            result.insert(product.product_id.0.clone());
+        }
+        for relationship in tree.relationships.iter().flatten() {
+            result.insert(relationship.full_product_name.product_id.0.clone());
+        }
+    }
+
+    result
+}
+
+/// The key of a vulnerability: its CVE, falling back to its title or position.
+pub(super) fn vulnerability_keys(csaf: &Csaf) -> impl Iterator<Item = (String, &Vulnerability)> {
+    csaf.vulnerabilities.iter().flatten().enumerate().map(|(n, vuln)| {
+        let key = vuln
+            .cve
+            .clone()
+            .or_else(|| vuln.title.clone())
+            .unwrap_or_else(|| format!("#{n}"));
+        (key, vuln)
+    })
+}
+
+/// Collect the highest CVSS v3 score of each vulnerability.
+fn scores(csaf: &Csaf) -> BTreeMap<String, f64> {
+    vulnerability_keys(csaf)
+        .filter_map(|(key, vuln)| {
+            vuln.scores
+                .iter()
+                .flatten()
+                .filter_map(|score| score.cvss_v3.as_ref())
+                .map(|cvss| cvss.score().value())
+                .reduce(f64::max)
+                .map(|score| (key, score))
+        })
+        .collect()
+}
+
+/// Collect the status of each product, for each vulnerability.
+fn statements(csaf: &Csaf) -> Statements {
+    let mut result = Statements::new();
+    for (key, vuln) in vulnerability_keys(csaf) {
+        let Some(status) = &vuln.product_status else {
+            continue;
+        };
+        for (name, products) in product_status(status) {
+            for product in products.iter().flatten() {
+                result.insert((key.clone(), product.0.clone()), name.to_string());
+            }
+        }
+    }
+    result
+}
+
+fn product_status(status: &ProductStatus) -> [(&'static str, &Option<Vec<ProductIdT>>); 8] {
+    [
+        ("first_affected", &status.first_affected),
+        ("first_fixed", &status.first_fixed),
+        ("fixed", &status.fixed),
+        ("known_affected", &status.known_affected),
+        ("known_not_affected", &status.known_not_affected),
+        ("last_affected", &status.last_affected),
+        ("recommended", &status.recommended),
+        ("under_investigation", &status.under_investigation),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    const ADVISORY: &[u8] = include_bytes!("../../../../../vexination/testdata/rhsa-2023_1441.json");
+
+    #[test]
+    fn test_diff_unchanged() {
+        let csaf: Csaf = serde_json::from_slice(ADVISORY).unwrap();
+        let diff = diff_advisories(&csaf, &csaf);
+        assert!(diff.products_added.is_empty());
+        assert!(diff.products_removed.is_empty());
+        assert!(diff.scores.is_empty());
+        assert!(diff.statements.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let a: Csaf = serde_json::from_slice(ADVISORY).unwrap();
+
+        let mut json: Value = serde_json::from_slice(ADVISORY).unwrap();
+        let vuln = &mut json["vulnerabilities"][0];
+        vuln.as_object_mut().unwrap().remove("scores");
+        let status = vuln["product_status"].as_object_mut().unwrap();
+        let product = status["fixed"].as_array_mut().unwrap().remove(0);
+        status["known_affected"].as_array_mut().unwrap().push(product.clone());
+        json["product_tree"]["full_product_names"] =
+            serde_json::json!([{ "name": "new product", "product_id": "new-product" }]);
+        let b: Csaf = serde_json::from_value(json).unwrap();
+
+        let diff = diff_advisories(&a, &b);
+        assert_eq!(diff.products_added, vec!["new-product".to_string()]);
+        assert!(diff.products_removed.is_empty());
+        assert_eq!(
+            diff.scores,
+            vec![ScoreChange {
+                vulnerability: "CVE-2023-0286".to_string(),
+                from: Some(7.4),
+                to: None,
+            }]
+        );
+        assert_eq!(
+            diff.statements,
+            vec![StatementChange {
+                vulnerability: "CVE-2023-0286".to_string(),
+                product: product.as_str().unwrap().to_string(),
+                from: Some("fixed".to_string()),
+                to: Some("known_affected".to_string()),
+            }]
+        );
+    }
+}
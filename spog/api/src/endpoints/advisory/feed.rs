@@ -0,0 +1,179 @@
+use super::into_summary;
+use crate::app_state::AppState;
+use crate::error::Error;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::search::AdvisorySummary;
+use std::fmt::Write;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use utoipa::IntoParams;
+
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct FeedParams {
+    /// The query string, selecting the advisories of the feed
+    #[serde(default)]
+    pub q: String,
+    /// Maximum number of entries in the feed
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// The bearer token, as feed readers commonly can't send an authorization header
+    pub token: Option<String>,
+}
+
+const fn default_limit() -> usize {
+    50
+}
+
+/// An Atom feed of the most recently ingested advisories matching a query.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/feed.atom",
+    responses(
+        (status = OK, description = "Feed was generated", content_type = "application/atom+xml", body = String),
+    ),
+    params(FeedParams)
+)]
+#[instrument(skip(state, req, access_token), err)]
+pub async fn feed(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    web::Query(params): web::Query<FeedParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let token = params
+        .token
+        .clone()
+        .or_else(|| access_token.map(|s| s.token().to_string()));
+    let options = SearchOptions {
+        sort: Some("indexed:desc".to_string()),
+        ..Default::default()
+    };
+    let result = state
+        .search_vex(&params.q, 0, params.limit.min(MAX_ENTRIES), options, &token)
+        .await?;
+    let advisories: Vec<_> = result.result.into_iter().map(into_summary).collect();
+
+    let info = req.connection_info();
+    let base = format!("{}://{}", info.scheme(), info.host());
+    let feed = atom(&base, &params.q, &advisories).map_err(|err| Error::Generic(err.to_string()))?;
+
+    Ok(HttpResponse::Ok().content_type("application/atom+xml").body(feed))
+}
+
+/// Render the advisories as Atom feed.
+fn atom(base: &str, q: &str, advisories: &[AdvisorySummary]) -> Result<String, std::fmt::Error> {
+    let format = |date: OffsetDateTime| date.format(&Rfc3339).unwrap_or_default();
+    let self_link = format!("{base}/api/v1/advisory/feed.atom?q={}", urlencoding::encode(q));
+    let title = match q.is_empty() {
+        true => "Advisories".to_string(),
+        false => format!("Advisories matching '{q}'"),
+    };
+    let updated = advisories
+        .iter()
+        .map(|advisory| advisory.date)
+        .max()
+        .unwrap_or_else(OffsetDateTime::now_utc);
+
+    let mut feed = String::new();
+    writeln!(feed, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(feed, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#)?;
+    writeln!(feed, "  <id>{}</id>", escape(&self_link))?;
+    writeln!(feed, "  <title>{}</title>", escape(&title))?;
+    writeln!(feed, "  <updated>{}</updated>", format(updated))?;
+    writeln!(feed, r#"  <link rel="self" href="{}"/>"#, escape(&self_link))?;
+    writeln!(feed, "  <generator>trustification</generator>")?;
+
+    for advisory in advisories {
+        let link = format!("{base}/api/v1/advisory?id={}", urlencoding::encode(&advisory.id));
+        writeln!(feed, "  <entry>")?;
+        writeln!(feed, "    <id>{}</id>", escape(&link))?;
+        writeln!(
+            feed,
+            "    <title>{}: {}</title>",
+            escape(&advisory.id),
+            escape(&advisory.title)
+        )?;
+        writeln!(feed, "    <updated>{}</updated>", format(advisory.date))?;
+        writeln!(feed, r#"    <link rel="alternate" href="{}"/>"#, escape(&link))?;
+        if let Some(severity) = &advisory.severity {
+            writeln!(feed, r#"    <category term="{}"/>"#, escape(severity))?;
+        }
+        for cve in &advisory.cves {
+            writeln!(feed, r#"    <category term="{}"/>"#, escape(cve))?;
+        }
+        writeln!(feed, "    <summary>{}</summary>", escape(&advisory.desc))?;
+        writeln!(feed, "  </entry>")?;
+    }
+
+    writeln!(feed, "</feed>")?;
+    Ok(feed)
+}
+
+/// Escape text for XML content and attributes.
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn advisory(id: &str, timestamp: i64) -> AdvisorySummary {
+        AdvisorySummary {
+            id: id.to_string(),
+            title: "openssl security update".to_string(),
+            severity: Some("important".to_string()),
+            snippet: String::new(),
+            desc: "A <type confusion> & more".to_string(),
+            date: OffsetDateTime::from_unix_timestamp(timestamp).unwrap(),
+            cves: vec!["CVE-2023-0286".to_string()],
+            cvss_max: Some(7.4),
+            href: format!("/api/v1/advisory?id={id}"),
+            cve_severity_count: Default::default(),
+            remediations: vec![],
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_atom() {
+        let feed = atom(
+            "https://spog.example.com",
+            "openssl",
+            &[
+                advisory("RHSA-2023:1441", 1679559478),
+                advisory("RHSA-2023:3408", 1685577600),
+            ],
+        )
+        .unwrap();
+
+        assert!(feed.contains("<title>Advisories matching &apos;openssl&apos;</title>"));
+        assert!(feed.contains("<updated>2023-06-01T00:00:00Z</updated>"));
+        assert!(feed.contains("<id>https://spog.example.com/api/v1/advisory?id=RHSA-2023%3A1441</id>"));
+        assert!(feed.contains("<summary>A &lt;type confusion&gt; &amp; more</summary>"));
+        assert!(feed.contains(r#"<category term="CVE-2023-0286"/>"#));
+        assert_eq!(feed.matches("<entry>").count(), 2);
+    }
+
+    #[test]
+    fn test_atom_empty() {
+        let feed = atom("https://spog.example.com", "", &[]).unwrap();
+        assert!(feed.contains("<title>Advisories</title>"));
+        assert!(!feed.contains("<entry>"));
+    }
+}
@@ -0,0 +1,200 @@
+use super::diff::vulnerability_keys;
+use crate::app_state::AppState;
+use crate::endpoints::sbom;
+use crate::error::Error;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bytes::BytesMut;
+use csaf::{definitions::ProductIdT, product_tree::RelationshipCategory, Csaf};
+use futures::TryStreamExt;
+use spog_model::csaf::{trace_product, RelationshipsCache};
+use spog_model::impact::{AdvisoryImpact, ImpactedProduct};
+use spog_model::search::SbomSummary;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_auth::client::TokenProvider;
+use vexination_model::convert::parse_csaf;
+
+/// maximum number of packages evaluated by a single query
+const MAX_PACKAGES: usize = 50;
+/// maximum number of SBOMs returned by a single query
+const MAX_SBOMS: usize = 1000;
+
+/// A vulnerability of an advisory, with the packages and products it affects.
+#[derive(Debug, PartialEq, Eq)]
+struct AffectedBy {
+    vulnerability: String,
+    severity: String,
+    /// package URLs and CPEs of the affected products
+    packages: BTreeSet<String>,
+}
+
+/// Find the SBOMs impacted by an advisory.
+///
+/// Affected products of the advisory are resolved to their package URLs and CPEs, which are then looked up in the
+/// packages of the stored SBOMs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/advisory/{id}/impact",
+    responses(
+        (status = OK, description = "Processing succeeded", body = AdvisoryImpact),
+        (status = NOT_FOUND, description = "Advisory was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the advisory"),
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn impact(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let csaf = fetch_advisory(&state, &access_token, &id).await?;
+
+    let mut products = BTreeMap::<String, (SbomSummary, BTreeMap<String, String>)>::new();
+    for affected in affected(&csaf) {
+        for sbom in search_sboms(&state, &access_token, &affected.packages).await? {
+            products
+                .entry(sbom.id.clone())
+                .or_insert_with(|| (sbom, BTreeMap::new()))
+                .1
+                .insert(affected.vulnerability.clone(), affected.severity.clone());
+        }
+    }
+
+    let mut result = AdvisoryImpact {
+        advisory: id.into_inner(),
+        ..Default::default()
+    };
+    for (sbom, vulnerabilities) in products.into_values() {
+        let mut severities = BTreeMap::<String, usize>::new();
+        for severity in vulnerabilities.values() {
+            *severities.entry(severity.clone()).or_default() += 1;
+        }
+        for severity in severities.keys() {
+            *result.severities.entry(severity.clone()).or_default() += 1;
+        }
+        result.products.push(ImpactedProduct {
+            sbom,
+            vulnerabilities: vulnerabilities.into_keys().collect(),
+            severities,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[instrument(skip(state, access_token), err)]
+async fn fetch_advisory(state: &AppState, access_token: &dyn TokenProvider, id: &str) -> Result<Csaf, Error> {
+    let data: BytesMut = state.get_vex(id, access_token).await?.try_collect().await?;
+    Ok(parse_csaf(&data)?)
+}
+
+/// Find all SBOMs declaring any of the packages.
+#[instrument(skip(state, access_token, packages), err)]
+async fn search_sboms(
+    state: &AppState,
+    access_token: &dyn TokenProvider,
+    packages: &BTreeSet<String>,
+) -> Result<Vec<SbomSummary>, Error> {
+    let packages: Vec<_> = packages.iter().collect();
+
+    let mut result = Vec::new();
+    for chunk in packages.chunks(MAX_PACKAGES) {
+        let q = chunk
+            .iter()
+            .map(|package| format!(r#"package:"{package}""#))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let sboms = state
+            .search_sbom(&q, 0, MAX_SBOMS, SearchOptions::default(), access_token)
+            .await?;
+        result.extend(sboms.result.into_iter().map(sbom::into_summary));
+    }
+    Ok(result)
+}
+
+/// Collect the vulnerabilities of an advisory, with the packages and products they affect.
+fn affected(csaf: &Csaf) -> Vec<AffectedBy> {
+    let relationships = RelationshipsCache::new(csaf);
+    let aggregate = csaf
+        .document
+        .aggregate_severity
+        .as_ref()
+        .map(|severity| severity.text.to_lowercase());
+
+    vulnerability_keys(csaf)
+        .filter_map(|(vulnerability, vuln)| {
+            let status = vuln.product_status.as_ref()?;
+            let mut packages = BTreeSet::new();
+            for product in [&status.known_affected, &status.first_affected, &status.last_affected]
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                add_packages(csaf, product, &mut packages);
+                // only the component is affected, not the product it is a default component of
+                for relationship in relationships
+                    .relations(&product.0)
+                    .filter(|relationship| matches!(relationship.category, RelationshipCategory::DefaultComponentOf))
+                {
+                    add_packages(csaf, &relationship.product_reference, &mut packages);
+                }
+            }
+            // the values are used as part of a query
+            packages.retain(|package| !package.contains('"'));
+            if packages.is_empty() {
+                return None;
+            }
+
+            let severity = vuln
+                .scores
+                .iter()
+                .flatten()
+                .filter_map(|score| score.cvss_v3.as_ref())
+                .max_by(|a, b| a.score().value().total_cmp(&b.score().value()))
+                .map(|cvss| cvss.severity().as_str().to_string())
+                .or_else(|| aggregate.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+
+            Some(AffectedBy {
+                vulnerability,
+                severity,
+                packages,
+            })
+        })
+        .collect()
+}
+
+/// Add the package URL and CPE of a product, if it has any.
+fn add_packages(csaf: &Csaf, product: &ProductIdT, packages: &mut BTreeSet<String>) {
+    let helper = trace_product(csaf, &product.0)
+        .last()
+        .and_then(|branch| branch.product.as_ref())
+        .and_then(|product| product.product_identification_helper.as_ref());
+    if let Some(helper) = helper {
+        packages.extend(helper.purl.as_ref().map(|purl| purl.to_string()));
+        packages.extend(helper.cpe.as_ref().map(|cpe| cpe.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_affected() {
+        let csaf: Csaf =
+            serde_json::from_slice(include_bytes!("../../../../../vexination/testdata/rhsa-2023_1441.json")).unwrap();
+        assert_eq!(
+            affected(&csaf),
+            vec![AffectedBy {
+                vulnerability: "CVE-2023-0286".to_string(),
+                severity: "high".to_string(),
+                packages: BTreeSet::from(["pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?arch=x86_64&epoch=1".to_string()]),
+            }]
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bytes::Bytes;
+use serde_json::Value;
+use tracing::instrument;
+use vexination_model::validation::AdvisoryValidation;
+
+/// maximum size of a validated advisory
+pub(crate) const PAYLOAD_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Validate a CSAF advisory, without storing it.
+///
+/// Next to the schema, the advisory is checked for missing mandatory fields, references to undefined products,
+/// contradicting product status and CVSS vectors not matching their scores. Each finding points to the offending
+/// value using a JSON pointer.
+#[utoipa::path(
+    post,
+    path = "/api/v1/advisory/validate",
+    request_body(content = Value, description = "The advisory to be validated", content_type = "application/json"),
+    responses(
+        (status = OK, description = "Advisory was validated, the findings tell if it is valid", body = AdvisoryValidation),
+    )
+)]
+#[instrument(skip(state, data, access_token), fields(size = data.len()), err)]
+pub async fn validate(
+    state: web::Data<AppState>,
+    data: Bytes,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let validation = state.validate_vex(&access_token, data).await?;
+    Ok(HttpResponse::Ok().json(validation))
+}
@@ -0,0 +1,79 @@
+use crate::db::Db;
+use crate::error::Error;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use spog_model::alias::VulnerabilityAliases;
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::Authenticator;
+use trustification_infrastructure::new_auth;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/vuln/alias/{id}")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_aliases)),
+        );
+    }
+}
+
+/// Get all known identifiers of a vulnerability, like its CVE ID, GitHub advisories, or Red Hat advisories.
+///
+/// Aliases are recorded when vulnerabilities and advisories get indexed.
+#[utoipa::path(
+    get,
+    tag = "vulnerability",
+    path = "/api/v1/vuln/alias/{id}",
+    responses(
+        (status = OK, description = "The known identifiers of the vulnerability", body = VulnerabilityAliases),
+        (status = NOT_FOUND, description = "The vulnerability has no known aliases"),
+    ),
+    params(
+        ("id" = String, Path, description = "Any identifier of the vulnerability"),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_aliases(db: web::Data<Db>, id: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let id = id.into_inner();
+    let aliases = db.get_aliases(&id).await.map_err(Error::from)?;
+    if aliases.is_empty() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    Ok(HttpResponse::Ok().json(VulnerabilityAliases { id, aliases }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+
+    #[actix_web::test]
+    async fn test_get_aliases() -> Result<(), anyhow::Error> {
+        let db = Db::new(".").await?;
+        db.insert_aliases("RHSA-2023:5838", &["CVE-2023-44487".to_string()])
+            .await?;
+        db.insert_aliases("CVE-2023-44487", &["GHSA-qppj-fm5r-hxr3".to_string()])
+            .await?;
+        let app = test::init_service(App::new().app_data(web::Data::new(db)).configure(configure(None))).await;
+
+        let request = test::TestRequest::get()
+            .uri("/api/v1/vuln/alias/GHSA-qppj-fm5r-hxr3")
+            .to_request();
+        let result: VulnerabilityAliases = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(
+            result,
+            VulnerabilityAliases {
+                id: "GHSA-qppj-fm5r-hxr3".to_string(),
+                aliases: vec!["CVE-2023-44487".to_string(), "RHSA-2023:5838".to_string()],
+            }
+        );
+
+        let request = test::TestRequest::get()
+            .uri("/api/v1/vuln/alias/CVE-2023-0001")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
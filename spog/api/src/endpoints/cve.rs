@@ -1,8 +1,9 @@
 use crate::{
-    app_state::AppState, error::Error, search, service::collectorist::CollectoristService, service::guac::GuacService,
-    service::v11y::V11yService,
+    alias::resolve_cve, app_state::AppState, db::Db, error::Error, search, service::collectorist::CollectoristService,
+    service::guac::GuacService, service::v11y::V11yService,
 };
 use actix_web::{
+    http::StatusCode,
     web::{self, ServiceConfig},
     HttpResponse, HttpResponseBuilder,
 };
@@ -19,13 +20,21 @@ use spog_model::{
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::Arc;
 use tracing::{info_span, instrument, Instrument};
-use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_api::search::{SearchField, SearchOptions, SearchResult};
 use trustification_auth::{
     authenticator::Authenticator,
     client::{BearerTokenProvider, TokenProvider},
 };
+use trustification_common::error::ErrorInformation;
 use trustification_infrastructure::new_auth;
+use v11y_client::batch::{CveBatch, MAX_BATCH_SIZE};
 use v11y_client::search::{SearchDocument, SearchHit};
+use v11y_client::{epss::Epss, kev::KnownExploited, related::RelatedCve};
+use vexination_model::convert::parse_csaf;
+
+mod trend;
+
+pub use trend::*;
 
 pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
@@ -33,7 +42,18 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
             web::scope("/api/v1/cve")
                 .wrap(new_auth!(auth))
                 .service(web::resource("").to(cve_search))
+                .service(web::resource("/search/fields").to(cve_search_fields))
+                // must be registered ahead of the CVE lookup, which would take `batch` as id
+                .service(
+                    web::resource("/batch")
+                        .app_data(web::JsonConfig::default().limit(BATCH_PAYLOAD_LIMIT))
+                        .route(web::post().to(cve_batch)),
+                )
+                .service(web::resource("/metrics/trend").to(cve_trend))
                 .service(web::resource("/{id}").to(cve_get))
+                .service(web::resource("/{id}/epss").to(cve_epss))
+                .service(web::resource("/{id}/kev").to(cve_known_exploited))
+                .service(web::resource("/{id}/related").to(cve_related))
                 .service(web::resource("/{id}/related-products").to(cve_related_product)),
         );
     }
@@ -45,16 +65,22 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
     responses(
         (status = OK, description = "Search was performed successfully", body = SearchResultCve),
     ),
-    params(search::QueryParams)
+    params(search::QueryParams, SearchOptions)
 )]
 #[instrument(skip(v11y, state, guac), err)]
 async fn cve_search(
     web::Query(params): web::Query<search::QueryParams>,
+    web::Query(options): web::Query<SearchOptions>,
     v11y: web::Data<V11yService>,
     state: web::Data<AppState>,
     guac: web::Data<GuacService>,
 ) -> actix_web::Result<HttpResponse> {
-    let SearchResult { result, total } = v11y.search(params).await.map_err(Error::V11y)?;
+    let SearchResult {
+        result,
+        total,
+        facets,
+        cursor,
+    } = v11y.search(params, options).await.map_err(Error::V11y)?;
 
     // enrich the results with counts of relations
     let result: Vec<_> = stream::iter(result.into_iter().map(Ok::<_, Error>))
@@ -74,7 +100,25 @@ async fn cve_search(
         .try_collect()
         .await?;
 
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
+}
+
+/// List the fields CVE search queries can be qualified with, including their type and an example query.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cve/search/fields",
+    responses(
+        (status = OK, description = "Search fields", body = Vec<SearchField>),
+    ),
+)]
+#[instrument(skip(v11y), err)]
+async fn cve_search_fields(v11y: web::Data<V11yService>) -> actix_web::Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(v11y.search_fields().await?))
 }
 
 /// return the number of related advisories for a CVE
@@ -104,18 +148,163 @@ async fn count_related_products(guac: &GuacService, cve: &str) -> Result<usize,
         (status = OK, description = "Search was performed successfully", body = SearchResultCve),
     ),
     params(
-        ("id" = String, Path, description = "The CVE to retrieve"),
+        ("id" = String, Path, description = "The CVE to retrieve, or one of its aliases"),
     )
 )]
-#[instrument(skip(v11y), err)]
-async fn cve_get(id: web::Path<String>, v11y: web::Data<V11yService>) -> actix_web::Result<HttpResponse> {
+#[instrument(skip(v11y, db), err)]
+async fn cve_get(
+    id: web::Path<String>,
+    v11y: web::Data<V11yService>,
+    db: web::Data<Db>,
+) -> actix_web::Result<HttpResponse> {
     let id = id.into_inner();
 
-    let response = v11y.fetch_cve(&id).await?;
+    let mut response = v11y.fetch_cve(&id).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        // the alias may not be a record of its own, like the ID of an advisory
+        let cve = resolve_cve(&db, &id).await?;
+        if cve != id {
+            response = v11y.fetch_cve(&cve).await?;
+        }
+    }
 
     Ok(HttpResponseBuilder::new(response.status()).streaming(response.bytes_stream()))
 }
 
+/// maximum size of the list of CVE ids of a batch request
+const BATCH_PAYLOAD_LIMIT: usize = MAX_BATCH_SIZE * 64;
+
+/// Get several CVEs at once, instead of requesting them one by one.
+///
+/// The request body is the list of CVE ids. CVEs which are not known are reported as missing.
+#[utoipa::path(
+    post,
+    path = "/api/v1/cve/batch",
+    request_body = Vec<String>,
+    responses(
+        (status = OK, description = "The known CVEs", body = CveBatch),
+        (status = BAD_REQUEST, description = "Too many CVEs were requested"),
+    )
+)]
+#[instrument(skip(v11y, ids), fields(ids = ids.len()), err)]
+async fn cve_batch(
+    web::Json(ids): web::Json<Vec<String>>,
+    v11y: web::Data<V11yService>,
+) -> actix_web::Result<HttpResponse> {
+    // the ids are normalized the way v11y does, so that duplicates don't count towards the limit
+    let ids: Vec<_> = ids
+        .iter()
+        .map(|id| id.trim().to_uppercase())
+        .filter(|id| !id.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if ids.len() > MAX_BATCH_SIZE {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "BatchTooLarge".to_string(),
+            message: format!("batch of {} CVEs exceeds the limit of {MAX_BATCH_SIZE}", ids.len()),
+            details: String::new(),
+        }));
+    }
+
+    let batch = v11y.fetch_cves(&ids).await?;
+    Ok(HttpResponse::Ok().json(batch))
+}
+
+/// Get the EPSS score of a CVE, the probability of it getting exploited in the next 30 days.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cve/{id}/epss",
+    responses(
+        (status = OK, description = "The CVE has a score", body = Epss),
+        (status = NOT_FOUND, description = "The CVE is not known, or has no score"),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to get the score of, or one of its aliases"),
+    )
+)]
+#[instrument(skip(v11y, db), err)]
+async fn cve_epss(
+    id: web::Path<String>,
+    v11y: web::Data<V11yService>,
+    db: web::Data<Db>,
+) -> actix_web::Result<HttpResponse> {
+    let id = resolve_cve(&db, &id).await?;
+    match v11y.fetch_epss(&id).await? {
+        Some(epss) => Ok(HttpResponse::Ok().json(epss)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Get the entry of a CVE in the known exploited vulnerabilities catalog.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cve/{id}/kev",
+    responses(
+        (status = OK, description = "The CVE is known to be exploited", body = KnownExploited),
+        (status = NOT_FOUND, description = "The CVE is not known, or not known to be exploited"),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to get the catalog entry of, or one of its aliases"),
+    )
+)]
+#[instrument(skip(v11y, db), err)]
+async fn cve_known_exploited(
+    id: web::Path<String>,
+    v11y: web::Data<V11yService>,
+    db: web::Data<Db>,
+) -> actix_web::Result<HttpResponse> {
+    let id = resolve_cve(&db, &id).await?;
+    match v11y.fetch_known_exploited(&id).await? {
+        Some(kev) => Ok(HttpResponse::Ok().json(kev)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct RelatedParams {
+    /// Max number of vulnerabilities for each kind of relation
+    #[serde(default = "default_related_limit")]
+    pub limit: usize,
+}
+
+const fn default_related_limit() -> usize {
+    20
+}
+
+/// Get the vulnerabilities related to a CVE, by alias relationships, shared references, or shared affected packages.
+#[utoipa::path(
+    get,
+    path = "/api/v1/cve/{id}/related",
+    responses(
+        (status = OK, description = "The related vulnerabilities", body = Vec<RelatedCve>),
+        (status = NOT_FOUND, description = "The CVE is not known"),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to get the related vulnerabilities of, or one of its aliases"),
+        RelatedParams,
+    )
+)]
+#[instrument(skip(v11y, db), err)]
+async fn cve_related(
+    id: web::Path<String>,
+    web::Query(params): web::Query<RelatedParams>,
+    v11y: web::Data<V11yService>,
+    db: web::Data<Db>,
+) -> actix_web::Result<HttpResponse> {
+    let mut related = v11y.fetch_related(&id, params.limit).await?;
+    if related.is_none() {
+        let cve = resolve_cve(&db, &id).await?;
+        if cve != *id {
+            related = v11y.fetch_related(&cve, params.limit).await?;
+        }
+    }
+    match related {
+        Some(related) => Ok(HttpResponse::Ok().json(related)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
 async fn cve_related_product(
     _app_state: web::Data<AppState>,
     guac: web::Data<GuacService>,
@@ -123,8 +312,9 @@ async fn cve_related_product(
     _access_token: BearerAuth,
     _collectorist: web::Data<CollectoristService>,
     _v11y: web::Data<V11yService>,
+    db: web::Data<Db>,
 ) -> actix_web::Result<HttpResponse> {
-    let id = id.into_inner();
+    let id = resolve_cve(&db, &id).await?;
 
     let result = guac.product_by_cve(id).await?;
 
@@ -183,7 +373,7 @@ where
         let stream = app.get_vex(&id, &provider).await?;
         let x: BytesMut = stream.try_collect().instrument(info_span!("receive vex", id)).await?;
 
-        let csaf: Csaf = serde_json::from_slice(&x)?;
+        let csaf = parse_csaf(&x)?;
         let relationships = RelationshipsCache::new(&csaf);
 
         for vuln in csaf
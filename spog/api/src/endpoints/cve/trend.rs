@@ -0,0 +1,206 @@
+use crate::error::Error;
+use crate::service::v11y::V11yService;
+use actix_web::{web, HttpResponse};
+use spog_model::trend::{SeverityTrend, TrendBucket, TrendDate};
+use std::collections::BTreeMap;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time};
+use tracing::instrument;
+use trustification_api::search::HistogramBucket;
+use trustification_common::error::ErrorInformation;
+use utoipa::IntoParams;
+
+/// length of a bucket
+const WEEK: std::time::Duration = std::time::Duration::from_secs(7 * 86_400);
+/// shift of the weeks from the epoch, which was a Thursday, so that weeks start on Mondays
+const MONDAY: std::time::Duration = std::time::Duration::from_secs(4 * 86_400);
+/// longest window, limiting the number of buckets
+const MAX_WINDOW: Duration = Duration::days(3 * 365);
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct TrendParams {
+    /// Only count CVEs affecting the product, identified by its CPE, like `cpe:/a:redhat:openshift`
+    ///
+    /// Only the vendor and product of the CPE are matched.
+    pub product: Option<String>,
+    /// The date to count CVEs by, `published` (default) or `updated`
+    #[serde(default)]
+    pub date: TrendDate,
+    /// The time span to count CVEs for, ending now, like `90d`
+    #[serde(default = "default_window")]
+    pub window: String,
+}
+
+fn default_window() -> String {
+    "90d".to_string()
+}
+
+/// Number of CVEs published or updated each week, by severity, for trend charts.
+///
+/// The time span starts on the Monday of the week starting the window.
+#[utoipa::path(
+    get,
+    tag = "dashboard",
+    path = "/api/v1/cve/metrics/trend",
+    responses(
+        (status = OK, description = "Trend was computed", body = SeverityTrend),
+        (status = BAD_REQUEST, description = "Invalid product or window"),
+    ),
+    params(TrendParams)
+)]
+#[instrument(skip(v11y), err)]
+pub async fn cve_trend(
+    web::Query(params): web::Query<TrendParams>,
+    v11y: web::Data<V11yService>,
+) -> actix_web::Result<HttpResponse> {
+    let error = |error: &str, message: String| {
+        HttpResponse::BadRequest().json(ErrorInformation {
+            error: error.to_string(),
+            message,
+            details: String::new(),
+        })
+    };
+
+    let window = match humantime::parse_duration(&params.window).map(Duration::try_from) {
+        Ok(Ok(window)) if window.is_positive() && window <= MAX_WINDOW => window,
+        _ => {
+            return Ok(error(
+                "InvalidWindow",
+                format!("The window must be a duration like 90d, of at most {MAX_WINDOW}"),
+            ))
+        }
+    };
+    if params
+        .product
+        .as_deref()
+        .is_some_and(|product| !product.starts_with("cpe:") || product.contains('"'))
+    {
+        return Ok(error(
+            "InvalidProduct",
+            "The product must be a CPE, without quotes".to_string(),
+        ));
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let from = week_start(now - window);
+    let q = query(from, params.date, params.product.as_deref()).map_err(actix_web::error::ErrorInternalServerError)?;
+    let buckets = v11y
+        .histogram(&q, params.date.field(), WEEK, MONDAY, Some("severity"))
+        .await
+        .map_err(Error::V11y)?;
+
+    Ok(HttpResponse::Ok().json(SeverityTrend {
+        product: params.product,
+        date: params.date,
+        weeks: weeks(from, now, &buckets),
+    }))
+}
+
+/// The start of the week of a point in time, at midnight UTC on Monday.
+fn week_start(time: OffsetDateTime) -> OffsetDateTime {
+    let days = time.weekday().number_days_from_monday();
+    (time - Duration::days(days.into())).replace_time(Time::MIDNIGHT)
+}
+
+fn query(from: OffsetDateTime, date: TrendDate, product: Option<&str>) -> Result<String, time::error::Format> {
+    let mut q = format!(r#"{}:>"{}""#, date.field(), from.format(&Rfc3339)?);
+    if let Some(product) = product {
+        q.push_str(&format!(r#" cpe:"{product}""#));
+    }
+    Ok(q)
+}
+
+/// Convert the buckets of the histogram to weeks, adding the weeks without any CVEs.
+fn weeks(from: OffsetDateTime, now: OffsetDateTime, buckets: &[HistogramBucket]) -> Vec<TrendBucket> {
+    let mut counts = BTreeMap::new();
+    for bucket in buckets {
+        match OffsetDateTime::parse(&bucket.start, &Rfc3339) {
+            Ok(start) => {
+                counts.insert(start, bucket);
+            }
+            Err(err) => log::warn!("Skipping bucket with invalid start '{}': {err}", bucket.start),
+        }
+    }
+
+    let mut weeks = vec![];
+    let mut start = from;
+    while start <= now {
+        weeks.push(match counts.get(&start) {
+            Some(bucket) => TrendBucket {
+                start,
+                count: bucket.count,
+                severities: bucket
+                    .facets
+                    .iter()
+                    .map(|facet| (facet.value.clone(), facet.count))
+                    .collect(),
+            },
+            None => TrendBucket {
+                start,
+                count: 0,
+                severities: BTreeMap::new(),
+            },
+        });
+        start += Duration::WEEK;
+    }
+    weeks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::Weekday;
+    use trustification_api::search::FacetCount;
+
+    fn datetime(timestamp: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(timestamp).unwrap()
+    }
+
+    #[test]
+    fn test_week_start() {
+        let start = week_start(datetime(1700753400));
+        assert_eq!(start, datetime(1700438400));
+        assert_eq!(start.weekday(), Weekday::Monday);
+        assert_eq!(week_start(start), start);
+    }
+
+    #[test]
+    fn test_query() {
+        let from = datetime(1700438400);
+        assert_eq!(
+            query(from, TrendDate::Published, None).unwrap(),
+            r#"datePublished:>"2023-11-20T00:00:00Z""#
+        );
+        assert_eq!(
+            query(from, TrendDate::Updated, Some("cpe:/a:redhat:openshift")).unwrap(),
+            r#"dateUpdated:>"2023-11-20T00:00:00Z" cpe:"cpe:/a:redhat:openshift""#
+        );
+    }
+
+    #[test]
+    fn test_weeks() {
+        let buckets = [HistogramBucket {
+            start: "2023-11-27T00:00:00Z".to_string(),
+            count: 3,
+            facets: vec![
+                FacetCount {
+                    value: "high".to_string(),
+                    count: 2,
+                },
+                FacetCount {
+                    value: "low".to_string(),
+                    count: 1,
+                },
+            ],
+        }];
+        let weeks = weeks(datetime(1700438400), datetime(1701777600), &buckets);
+
+        assert_eq!(weeks.len(), 3);
+        assert_eq!(weeks[0].count, 0);
+        assert!(weeks[0].severities.is_empty());
+        assert_eq!(weeks[1].start, datetime(1701043200));
+        assert_eq!(weeks[1].count, 3);
+        assert_eq!(weeks[1].severities["high"], 2);
+        assert_eq!(weeks[2].start, datetime(1701648000));
+        assert_eq!(weeks[2].count, 0);
+    }
+}
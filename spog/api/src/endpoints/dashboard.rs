@@ -0,0 +1,177 @@
+use crate::app_state::AppState;
+use crate::endpoints::sbom::{process_get_vulnerabilities_summary, SummaryParams};
+use crate::error::Error;
+use crate::search::QueryParams;
+use crate::service::{guac::GuacService, v11y::V11yService};
+use actix_web::{
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures::future::try_join_all;
+use serde_json::Value;
+use spog_model::dashboard::DashboardSummary;
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_auth::client::TokenProvider;
+use trustification_infrastructure::new_auth;
+
+/// CVE severities, as known by the v11y index
+const SEVERITIES: [&str; 4] = ["critical", "high", "medium", "low"];
+/// number of the most recent SBOMs being evaluated for the most vulnerable products
+const VULNERABLE_CANDIDATES: usize = 50;
+/// number of the most vulnerable products to return
+const MOST_VULNERABLE: usize = 5;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/dashboard/summary")
+                .wrap(new_auth!(auth))
+                .to(get_summary),
+        );
+    }
+}
+
+/// Get a summary of the overall state of SBOMs, advisories and CVEs.
+///
+/// The most vulnerable products are evaluated from the most recently created SBOMs. The summary is cached for a
+/// short time, for each set of permissions, as those decide about the documents being visible.
+#[utoipa::path(
+    get,
+    tag = "dashboard",
+    path = "/api/v1/dashboard/summary",
+    responses(
+        (status = OK, description = "Summary was computed successfully", body = DashboardSummary),
+    ),
+)]
+#[instrument(skip(state, v11y, guac, user, access_token), err)]
+pub async fn get_summary(
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    user: UserInformation,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let key = permissions(&user);
+    if let Some(summary) = state.dashboard_cache.get(&key) {
+        return Ok(HttpResponse::Ok().json(summary));
+    }
+
+    let summary = summarize(&state, &v11y, &guac, &access_token).await?;
+    state.dashboard_cache.insert(key, summary.clone());
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// The permissions of a user, in a stable order
+fn permissions(user: &UserInformation) -> Vec<String> {
+    let mut permissions = match user {
+        UserInformation::Authenticated(details) => details.permissions.clone(),
+        UserInformation::Anonymous => vec![],
+    };
+    permissions.sort_unstable();
+    permissions.dedup();
+    permissions
+}
+
+async fn summarize(
+    state: &AppState,
+    v11y: &V11yService,
+    guac: &GuacService,
+    access_token: &dyn TokenProvider,
+) -> Result<DashboardSummary, Error> {
+    // only the count and the metadata of the most recently indexed document are needed
+    let latest = SearchOptions {
+        metadata: true,
+        sort: Some("indexed:desc".to_string()),
+        ..Default::default()
+    };
+
+    let (sboms, advisories, cves, most_vulnerable) = futures::try_join!(
+        state.search_sbom("", 0, 1, latest.clone(), access_token),
+        state.search_vex("", 0, 1, latest, access_token),
+        count_cves(v11y),
+        process_get_vulnerabilities_summary(
+            state,
+            v11y,
+            guac,
+            access_token,
+            SummaryParams {
+                q: "-sort:created".to_string(),
+                offset: 0,
+                limit: VULNERABLE_CANDIDATES,
+                // rank the candidates only, instead of all SBOMs
+                top: None,
+            },
+        ),
+    )?;
+
+    let (total_cves, cves_by_severity) = cves;
+
+    Ok(DashboardSummary {
+        total_sboms: sboms.total,
+        total_advisories: advisories.total,
+        total_cves,
+        cves_by_severity,
+        last_sbom_ingested: sboms
+            .result
+            .first()
+            .and_then(|hit| indexed_timestamp(hit.metadata.as_ref())),
+        last_advisory_ingested: advisories
+            .result
+            .first()
+            .and_then(|hit| indexed_timestamp(hit.metadata.as_ref())),
+        most_vulnerable: most_vulnerable.result.into_iter().take(MOST_VULNERABLE).collect(),
+    })
+}
+
+/// Count all CVEs, and the CVEs of each severity.
+async fn count_cves(v11y: &V11yService) -> Result<(usize, std::collections::BTreeMap<String, usize>), Error> {
+    let count = |q: String| async move {
+        let result = v11y
+            .search(QueryParams { q, offset: 0, limit: 1 }, SearchOptions::default())
+            .await
+            .map_err(Error::V11y)?;
+        Ok::<_, Error>(result.total.unwrap_or_default())
+    };
+
+    let total = count(String::new());
+    let severities =
+        try_join_all(SEVERITIES.map(|severity| async move {
+            Ok::<_, Error>((severity.to_string(), count(format!("is:{severity}")).await?))
+        }));
+
+    let (total, severities) = futures::try_join!(total, severities)?;
+    Ok((total, severities.into_iter().collect()))
+}
+
+/// Extract the indexing timestamp from the metadata of a search hit.
+fn indexed_timestamp(metadata: Option<&Value>) -> Option<OffsetDateTime> {
+    let value = metadata?["indexed_timestamp"]["values"][0].as_str()?;
+    OffsetDateTime::parse(value, &Rfc3339).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_indexed_timestamp() {
+        let metadata = json!({
+            "indexed_timestamp": {
+                "field": {},
+                "values": ["2023-11-21T09:55:03.123Z"],
+            }
+        });
+        let timestamp = indexed_timestamp(Some(&metadata)).unwrap();
+        assert_eq!(timestamp.unix_timestamp(), 1700560503);
+
+        assert_eq!(indexed_timestamp(None), None);
+        assert_eq!(indexed_timestamp(Some(&json!({}))), None);
+    }
+}
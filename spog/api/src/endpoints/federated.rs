@@ -0,0 +1,288 @@
+use crate::app_state::AppState;
+use crate::endpoints::{advisory, sbom};
+use crate::error::Error;
+use crate::search::QueryParams;
+use crate::service::v11y::V11yService;
+use actix_web::{
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::search::{FederatedCounts, FederatedErrors, FederatedHit, FederatedSearchResult};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::Authenticator;
+use trustification_infrastructure::new_auth;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(web::resource("/api/v1/search").wrap(new_auth!(auth)).to(search));
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct FederatedParams {
+    /// The query string, run against each source as it is
+    #[serde(default)]
+    pub q: String,
+    /// Maximum number of results to return of each source
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    10
+}
+
+const MAX_LIMIT: usize = 1_000;
+
+/// Search SBOMs, advisories and CVEs at once, like for a global search box.
+///
+/// The query is run against the indexes of all sources concurrently, so it should only use qualifiers all of them
+/// know, like free text. The top hits of the sources are merged by taking the next hit of each source in turn.
+///
+/// Sources which can't be searched are reported along with the hits of the other sources, the search only fails if
+/// none of the sources could be searched.
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    responses(
+        (status = OK, description = "Search was performed successfully", body = FederatedSearchResult),
+    ),
+    params(FederatedParams)
+)]
+#[instrument(skip(state, v11y, access_token), err)]
+pub async fn search(
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    params: web::Query<FederatedParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let FederatedParams { q, limit } = params.into_inner();
+    let limit = limit.min(MAX_LIMIT);
+
+    let (sboms, advisories, cves) = match futures::join!(
+        state.search_sbom(&q, 0, limit, SearchOptions::default(), &access_token),
+        state.search_vex(&q, 0, limit, SearchOptions::default(), &access_token),
+        async {
+            v11y.search(
+                QueryParams {
+                    q: q.clone(),
+                    offset: 0,
+                    limit,
+                },
+                SearchOptions::default(),
+            )
+            .await
+            .map_err(Error::V11y)
+        },
+    ) {
+        (Err(err), Err(_), Err(_)) => return Err(err.into()),
+        results => results,
+    };
+
+    let mut counts = FederatedCounts::default();
+    let mut errors = FederatedErrors::default();
+    let sboms = match partial("SBOMs", sboms, &mut errors.sboms) {
+        Some(sboms) => {
+            counts.sboms = sboms.total;
+            sboms
+                .result
+                .into_iter()
+                .map(|hit| FederatedHit::Sbom(sbom::into_summary(hit)))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let advisories = match partial("advisories", advisories, &mut errors.advisories) {
+        Some(advisories) => {
+            counts.advisories = advisories.total;
+            advisories
+                .result
+                .into_iter()
+                .map(|hit| FederatedHit::Advisory(advisory::into_summary(hit)))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let cves = match partial("CVEs", cves, &mut errors.cves) {
+        Some(cves) => {
+            counts.cves = cves.total.unwrap_or(cves.result.len());
+            cves.result
+                .into_iter()
+                .map(|hit| FederatedHit::Cve(hit.document))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let result = interleave(vec![sboms, advisories, cves]);
+    Ok(HttpResponse::Ok().json(FederatedSearchResult { result, counts, errors }))
+}
+
+/// The result of searching a source, recording its error instead of failing the whole search.
+fn partial<T>(source: &str, result: Result<T, Error>, error: &mut Option<String>) -> Option<T> {
+    match result {
+        Ok(result) => Some(result),
+        Err(err) => {
+            log::warn!("Unable to search {source}: {err}");
+            *error = Some(err.to_string());
+            None
+        }
+    }
+}
+
+/// Merge ranked lists, taking the next item of each list in turn.
+fn interleave<T>(lists: Vec<Vec<T>>) -> Vec<T> {
+    let len = lists.iter().map(Vec::len).sum();
+    let mut lists: Vec<_> = lists.into_iter().map(Vec::into_iter).collect();
+    let mut result = Vec::with_capacity(len);
+    while result.len() < len {
+        for list in &mut lists {
+            result.extend(list.next());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cache::{AdvisoryCache, TimedCache};
+    use actix_web::{test, App, HttpServer};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use trustification_auth::client::NoTokenProvider;
+
+    /// Serve the searches of bombastic, vexination and v11y, with the one of vexination failing.
+    ///
+    /// The total number of SBOMs is the limit they were requested with.
+    fn upstream() -> reqwest::Url {
+        let listener = std::net::TcpListener::bind("localhost:0").unwrap();
+        let url = reqwest::Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = HttpServer::new(|| {
+            App::new()
+                .route(
+                    "/api/v1/sbom/search",
+                    web::get().to(|params: web::Query<HashMap<String, String>>| async move {
+                        let total: usize = params["limit"].parse().unwrap();
+                        HttpResponse::Ok().json(serde_json::json!({ "total": total, "result": [] }))
+                    }),
+                )
+                .route(
+                    "/api/v1/vex/search",
+                    web::get().to(|| async { HttpResponse::InternalServerError().body("index unavailable") }),
+                )
+                .route(
+                    "/api/v1/search",
+                    web::get()
+                        .to(|| async { HttpResponse::Ok().json(serde_json::json!({ "total": 2, "result": [] })) }),
+                )
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+        url
+    }
+
+    fn state(url: reqwest::Url) -> (web::Data<AppState>, web::Data<V11yService>) {
+        let client = reqwest::Client::new();
+        let provider = Arc::new(NoTokenProvider);
+        let state = AppState {
+            client: client.clone(),
+            provider: provider.clone(),
+            bombastic: url.clone(),
+            vexination: url.clone(),
+            exhort: url.clone(),
+            advisory_search_concurrency: 1,
+            advisory_search_timeout: Duration::from_secs(10),
+            advisory_cache: AdvisoryCache::new(Duration::from_secs(60)),
+            dashboard_cache: TimedCache::new(Duration::from_secs(60)),
+            attestation_keys: Vec::new(),
+            attestation_registries: Vec::new(),
+            advisory_default_sort: None,
+            vex_namespace: None,
+        };
+        (
+            web::Data::new(state),
+            web::Data::new(V11yService::new(client, url, provider)),
+        )
+    }
+
+    #[actix_web::test]
+    async fn test_partial_results() {
+        let (state, v11y) = state(upstream());
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .app_data(v11y)
+                .route("/api/v1/search", web::get().to(search)),
+        )
+        .await;
+
+        let request = test::TestRequest::get().uri("/api/v1/search?q=openssl").to_request();
+        let result: FederatedSearchResult = test::call_and_read_body_json(&app, request).await;
+        assert!(result.result.is_empty());
+        assert_eq!(
+            result.counts,
+            FederatedCounts {
+                sboms: default_limit(),
+                advisories: 0,
+                cves: 2,
+            }
+        );
+        assert!(result.errors.sboms.is_none());
+        assert!(result.errors.advisories.unwrap().contains("index unavailable"));
+        assert!(result.errors.cves.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_limit_capped() {
+        let (state, v11y) = state(upstream());
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .app_data(v11y)
+                .route("/api/v1/search", web::get().to(search)),
+        )
+        .await;
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/api/v1/search?q=openssl&limit={}", usize::MAX))
+            .to_request();
+        let result: FederatedSearchResult = test::call_and_read_body_json(&app, request).await;
+        assert_eq!(result.counts.sboms, MAX_LIMIT);
+    }
+
+    #[actix_web::test]
+    async fn test_all_sources_failing() {
+        // nothing listens on the port of a closed listener
+        let listener = std::net::TcpListener::bind("localhost:0").unwrap();
+        let url = reqwest::Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+        let (state, v11y) = state(url);
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .app_data(v11y)
+                .route("/api/v1/search", web::get().to(search)),
+        )
+        .await;
+
+        let request = test::TestRequest::get().uri("/api/v1/search?q=openssl").to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(!response.status().is_success());
+    }
+
+    #[test]
+    fn test_interleave() {
+        assert_eq!(
+            interleave(vec![vec![1, 2, 3], vec![], vec![10, 20], vec![100]]),
+            [1, 10, 100, 2, 20, 3]
+        );
+        assert_eq!(interleave::<u32>(vec![vec![], vec![]]), Vec::<u32>::new());
+    }
+}
@@ -1,10 +1,20 @@
 pub mod advisory;
+pub mod alias;
 pub mod analyze;
 pub mod cve;
+pub mod dashboard;
+pub mod federated;
 pub mod index;
 pub mod package;
+pub mod product;
+pub mod products;
 pub mod sbom;
+pub mod searches;
+pub mod subscriptions;
 pub mod suggestion;
+pub mod vex;
+pub mod watches;
+pub mod webhooks;
 pub mod wellknown;
 
 use crate::openapi;
@@ -18,10 +28,29 @@ use utoipa::OpenApi;
         trustification_version::version::version,
 
         sbom::get,
+        sbom::upload,
+        sbom::delete,
         sbom::search,
+        sbom::export,
+        sbom::search_fields,
         sbom::get_vulnerabilities,
+        sbom::get_vulnerabilities_summary,
+        sbom::diff,
+        sbom::by_purl,
+        sbom::by_hash,
+        sbom::get_tree,
+        sbom::get_licenses,
+        sbom::get_attestations,
+        sbom::get_related_sboms,
         advisory::get,
         advisory::search,
+        advisory::export,
+        advisory::search_fields,
+        advisory::diff,
+        advisory::impact,
+        advisory::feed,
+        advisory::aging,
+        advisory::validate,
 
         analyze::report,
 
@@ -32,8 +61,44 @@ use utoipa::OpenApi;
         package::get_dependencies,
         package::get_dependents,
 
+        product::product_search,
+        products::get_products,
+        products::get_product,
+        products::put_product,
+        products::add_product_sboms,
+        products::delete_product,
+        products::get_product_vulnerabilities,
+
         cve::cve_get,
+        cve::cve_batch,
+        cve::cve_epss,
+        cve::cve_known_exploited,
+        cve::cve_related,
         cve::cve_search,
+        cve::cve_search_fields,
+        cve::cve_trend,
+        federated::search,
+        alias::get_aliases,
+
+        dashboard::get_summary,
+
+        searches::get_searches,
+        searches::save_search,
+        searches::delete_search,
+        watches::get_watches,
+        watches::create_watch,
+        watches::delete_watch,
+        watches::get_watch_hits,
+        subscriptions::get_subscriptions,
+        subscriptions::create_subscription,
+        subscriptions::delete_subscription,
+        subscriptions::get_notifications,
+        vex::generate,
+        vex::merge,
+        webhooks::get_webhooks,
+        webhooks::create_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_deliveries,
     ),
 
     components(
@@ -41,6 +106,36 @@ use utoipa::OpenApi;
             openapi::SearchResultSbom,
             openapi::SearchResultVex,
             openapi::SearchResultCve,
+            openapi::SearchResultSbomVulnerabilitySummary,
+
+            spog_model::dashboard::DashboardSummary,
+            spog_model::aging::AdvisoryAging,
+            spog_model::aging::AgingBucket,
+            spog_model::trend::SeverityTrend,
+            spog_model::trend::TrendBucket,
+            spog_model::trend::TrendDate,
+            spog_model::alias::VulnerabilityAliases,
+            spog_model::attestation::AttestationReport,
+            spog_model::attestation::Attestation,
+            spog_model::attestation::AttestationSource,
+            spog_model::attestation::VerificationStatus,
+
+            spog_model::diff::SbomDiff,
+            spog_model::diff::ComponentRef,
+            spog_model::diff::VersionChange,
+            spog_model::diff::VulnerabilityDiff,
+            spog_model::diff::AdvisoryDiff,
+            spog_model::diff::ScoreChange,
+            spog_model::diff::StatementChange,
+            spog_model::impact::AdvisoryImpact,
+            spog_model::impact::ImpactedProduct,
+
+            spog_model::license::LicenseReport,
+            spog_model::license::LicenseSummary,
+            spog_model::license::UnknownLicense,
+
+            spog_model::tree::SbomTree,
+            spog_model::tree::DependencyNode,
 
             spog_model::pkg::PackageRefList,
             spog_model::pkg::PackageRef,
@@ -50,8 +145,58 @@ use utoipa::OpenApi;
             spog_model::package_info::ProductRelatedToPackage,
             spog_model::package_info::V11yRef,
 
+            spog_model::product::ProductSearchResult,
+            spog_model::product::NewProduct,
+            spog_model::product::Product,
+            spog_model::product::ProductVulnerabilityReport,
+            spog_model::product::ProductVulnerability,
+
+            spog_model::related::RelatedSbom,
+
+            spog_model::validation::ValidationReport,
+            spog_model::validation::Diagnostic,
+            spog_model::validation::Severity,
+
+            spog_model::saved::SearchTarget,
+            spog_model::saved::NewSavedSearch,
+            spog_model::saved::SavedSearch,
+            spog_model::watch::NewWatch,
+            spog_model::watch::Watch,
+            spog_model::watch::WatchHit,
+            spog_model::watch::WatchNotification,
+            spog_model::subscription::SubscriptionKind,
+            spog_model::subscription::NewSubscription,
+            spog_model::subscription::Subscription,
+            spog_model::subscription::Notification,
+            spog_model::subscription::SubscriptionNotification,
+            spog_model::vex::VexRequest,
+            spog_model::vex::Disposition,
+            spog_model::vex::DispositionStatus,
+            spog_model::vex::Justification,
+            spog_model::vex::VexFormat,
+            spog_model::vex::GeneratedVex,
+            spog_model::vex::VexMergeRequest,
+            spog_model::vex::VexSource,
+            spog_model::vex::MergeStrategy,
+            spog_model::vex::MergedVex,
+            spog_model::vex::SupersededStatement,
+            spog_model::webhook::NewWebhook,
+            spog_model::webhook::WebhookFilter,
+            spog_model::webhook::Webhook,
+            spog_model::webhook::WebhookNotification,
+            spog_model::webhook::DeliveryStatus,
+            spog_model::webhook::WebhookDelivery,
+
             spog_model::search::AdvisorySummary,
+            vexination_model::search::AdvisoryRemediation,
+            vexination_model::validation::AdvisoryValidation,
+            vexination_model::validation::ValidationFinding,
+            vexination_model::validation::FindingSeverity,
             spog_model::search::SbomSummary,
+            spog_model::search::FederatedSearchResult,
+            spog_model::search::FederatedCounts,
+            spog_model::search::FederatedErrors,
+            spog_model::search::FederatedHit,
 
             spog_model::suggestion::Suggestion,
             spog_model::suggestion::Action,
@@ -60,6 +205,11 @@ use utoipa::OpenApi;
             spog_model::vuln::SbomReport,
             spog_model::vuln::SbomReportVulnerability,
             spog_model::vuln::SummaryEntry,
+            spog_model::vuln::SbomVulnerabilitySummary,
+
+            trustification_api::search::FacetCount,
+            trustification_api::search::SearchField,
+            trustification_api::search::SearchFieldType,
 
             trustification_version::VersionInformation,
             trustification_version::Version,
@@ -68,15 +218,26 @@ use utoipa::OpenApi;
 
             v11y_model::search::SearchHitWithDocument,
             v11y_model::search::SearchDocument,
+            v11y_model::batch::CveBatch,
+            v11y_model::epss::Epss,
+            v11y_model::kev::KnownExploited,
+            v11y_model::exploit::Exploit,
+            v11y_model::exploit::ExploitSource,
+            v11y_model::cwe::Cwe,
+            v11y_model::related::RelatedCve,
+            v11y_model::related::Relation,
         )
     ),
     tags(
         (name = "package", description = "Package endpoints"),
         (name = "advisory", description = "Advisory endpoints"),
+        (name = "dashboard", description = "Dashboard endpoints"),
+        (name = "product", description = "Product endpoints"),
         (name = "sbom", description = "SBOM endpoints"),
         (name = "vulnerability", description = "Vulnerability endpoints"),
         (name = "well-known", description = ".well-known endpoints"),
         (name = "search", description = "Search endpoint"),
+        (name = "webhook", description = "Webhook endpoints"),
     ),
 )]
 pub struct ApiDoc;
@@ -68,6 +68,8 @@ pub async fn package_search(
     let result = SearchResult {
         total: Some(data.total),
         result: m,
+        facets: Default::default(),
+        cursor: None,
     };
 
     Ok(HttpResponse::Ok().json(result))
@@ -0,0 +1,213 @@
+use crate::app_state::AppState;
+use crate::endpoints::{advisory, sbom};
+use actix_web::{
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::product::ProductSearchResult;
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::Authenticator;
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+/// number of components of the URI binding: part, vendor, product, version, update, edition, language
+const URI_COMPONENTS: usize = 7;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/product/search")
+                .wrap(new_auth!(auth))
+                .to(product_search),
+        );
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct ProductSearchParams {
+    /// The CPE of the product, either in its URI (`cpe:/…`) or formatted string (`cpe:2.3:…`) binding
+    pub cpe: String,
+    /// Maximum number of SBOMs and advisories to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Search for SBOMs and advisories of a product, by its CPE.
+///
+/// Components which are missing, or set to `*`, match any value. A trailing `*` of the last component matches any
+/// value starting with the preceding characters, e.g. a version of `4.*`.
+#[utoipa::path(
+    get,
+    tag = "sbom",
+    path = "/api/v1/product/search",
+    responses(
+        (status = OK, description = "Search was performed successfully", body = ProductSearchResult),
+        (status = BAD_REQUEST, description = "Invalid CPE"),
+    ),
+    params(ProductSearchParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn product_search(
+    state: web::Data<AppState>,
+    params: web::Query<ProductSearchParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(cpe) = CpeMatch::parse(&params.cpe) else {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidCpe".to_string(),
+            message: format!("Unable to parse CPE: {}", params.cpe),
+            details: String::new(),
+        }));
+    };
+
+    let q = cpe.query();
+    log::debug!("Searching products using {q}");
+
+    let (sboms, advisories) = futures::try_join!(
+        state.search_sbom(&q, 0, params.limit, SearchOptions::default(), &access_token),
+        state.search_vex(&q, 0, params.limit, SearchOptions::default(), &access_token),
+    )?;
+
+    Ok(HttpResponse::Ok().json(ProductSearchResult {
+        cpe: cpe.value,
+        total_sboms: sboms.total,
+        sboms: sboms.result.into_iter().map(sbom::into_summary).collect(),
+        total_advisories: advisories.total,
+        advisories: advisories.result.into_iter().map(advisory::into_summary).collect(),
+    }))
+}
+
+/// A CPE, converted into the URI binding used by the indexes.
+#[derive(Debug, PartialEq, Eq)]
+struct CpeMatch {
+    /// The CPE, or its prefix
+    value: String,
+    /// The value is a prefix of a component, rather than a sequence of complete components
+    partial_component: bool,
+    /// All components are set, the value must match exactly
+    exact: bool,
+}
+
+impl CpeMatch {
+    /// Parse a CPE, returns `None` if it isn't a valid CPE.
+    ///
+    /// Extended attributes of the formatted string binding (`sw_edition` and later) are not part of the URI binding,
+    /// and are ignored.
+    fn parse(cpe: &str) -> Option<Self> {
+        let cpe = cpe.trim().to_lowercase();
+        let mut components = if let Some(rest) = cpe.strip_prefix("cpe:2.3:") {
+            split_formatted(rest)
+        } else if let Some(rest) = cpe.strip_prefix("cpe:/") {
+            rest.split(':').map(|c| c.to_string()).collect()
+        } else {
+            return None;
+        };
+
+        components.truncate(URI_COMPONENTS);
+        for component in &mut components {
+            if component == "*" {
+                component.clear();
+            }
+        }
+        while components.last().map(|c| c.is_empty()).unwrap_or(false) {
+            components.pop();
+        }
+
+        if components.is_empty() || components.iter().any(|c| c.contains('"')) {
+            return None;
+        }
+
+        // only the last component may end with a wildcard
+        let mut partial_component = false;
+        if let Some(last) = components.last_mut() {
+            if let Some(prefix) = last.strip_suffix('*') {
+                *last = prefix.to_string();
+                partial_component = true;
+            }
+        }
+        if components.iter().any(|c| c.contains('*')) {
+            return None;
+        }
+
+        Some(Self {
+            exact: !partial_component && components.len() == URI_COMPONENTS,
+            value: format!("cpe:/{}", components.join(":")),
+            partial_component,
+        })
+    }
+
+    /// Build the query matching the CPE, in both the SBOM and the advisory index.
+    fn query(&self) -> String {
+        let value = &self.value;
+        match (self.exact, self.partial_component) {
+            (true, _) => format!(r#"package:"{value}""#),
+            (false, true) => format!(r#""{value}" in:package"#),
+            // the remaining components may either be absent, or follow after the next separator
+            (false, false) => format!(r#"package:"{value}" OR "{value}:" in:package"#),
+        }
+    }
+}
+
+/// Split the components of a formatted string binding at unescaped colons, removing the escaping.
+fn split_formatted(value: &str) -> Vec<String> {
+    let mut result = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.last_mut().unwrap().extend(chars.next()),
+            ':' => result.push(String::new()),
+            c => result.last_mut().unwrap().push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_formatted() {
+        let cpe = CpeMatch::parse("cpe:2.3:o:redhat:enterprise_linux:8\\.6:*:baseos:*:*:*:*:*").unwrap();
+        assert_eq!(cpe.value, "cpe:/o:redhat:enterprise_linux:8.6::baseos");
+        assert!(!cpe.exact);
+        assert_eq!(
+            cpe.query(),
+            r#"package:"cpe:/o:redhat:enterprise_linux:8.6::baseos" OR "cpe:/o:redhat:enterprise_linux:8.6::baseos:" in:package"#
+        );
+    }
+
+    #[test]
+    fn test_parse_wildcard_version() {
+        let cpe = CpeMatch::parse("cpe:2.3:a:redhat:openshift:4.*:*:*:*:*:*:*:*").unwrap();
+        assert_eq!(cpe.value, "cpe:/a:redhat:openshift:4.");
+        assert_eq!(cpe.query(), r#""cpe:/a:redhat:openshift:4." in:package"#);
+
+        let cpe = CpeMatch::parse("cpe:/a:redhat:openshift").unwrap();
+        assert_eq!(cpe.value, "cpe:/a:redhat:openshift");
+        assert!(!cpe.partial_component);
+    }
+
+    #[test]
+    fn test_parse_exact() {
+        let cpe = CpeMatch::parse("CPE:/a:redhat:openshift:4.13:update:el9:en").unwrap();
+        assert!(cpe.exact);
+        assert_eq!(cpe.query(), r#"package:"cpe:/a:redhat:openshift:4.13:update:el9:en""#);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(CpeMatch::parse("pkg:rpm/redhat/openssl").is_none());
+        assert!(CpeMatch::parse("cpe:2.3:*:*:*").is_none());
+        assert!(CpeMatch::parse("cpe:2.3:a:red*:openshift").is_none());
+        assert!(CpeMatch::parse(r#"cpe:/a:redhat:"openshift""#).is_none());
+    }
+}
@@ -0,0 +1,492 @@
+use crate::app_state::AppState;
+use crate::db::Db;
+use crate::endpoints::sbom::{into_severity, process_get_vulnerabilities, SUMMARY_SOURCE};
+use crate::error::Error;
+use crate::service::{guac::GuacService, v11y::V11yService};
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures::stream::iter;
+use futures::{StreamExt, TryStreamExt};
+use http::StatusCode;
+use spog_model::product::{NewProduct, Product, ProductVulnerability, ProductVulnerabilityReport};
+use spog_model::vuln::{SbomReport, SbomVulnerabilitySummary, SummaryEntry, VexStatus};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_auth::{authorizer::Authorizer, Permission};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+/// number of SBOMs being analyzed in parallel
+const PARALLEL_ANALYZE_SBOM: usize = 4;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/products")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_products)),
+        );
+        config.service(
+            web::resource("/api/v1/products/{name}/{version}")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_product))
+                .route(web::put().to(put_product))
+                .route(web::delete().to(delete_product)),
+        );
+        config.service(
+            web::resource("/api/v1/products/{name}/{version}/sboms")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::post().to(add_product_sboms)),
+        );
+        config.service(
+            web::resource("/api/v1/products/{name}/{version}/vulnerabilities")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_product_vulnerabilities)),
+        );
+    }
+}
+
+/// Get all registered product releases, along with their SBOMs.
+#[utoipa::path(
+    get,
+    tag = "product",
+    path = "/api/v1/products",
+    responses(
+        (status = OK, description = "Registered product releases", body = Vec<Product>),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_products(db: web::Data<Db>) -> actix_web::Result<HttpResponse> {
+    let products = db.get_products().await.map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(products))
+}
+
+/// Get a product release, along with its SBOMs.
+#[utoipa::path(
+    get,
+    tag = "product",
+    path = "/api/v1/products/{name}/{version}",
+    responses(
+        (status = OK, description = "The product release", body = Product),
+        (status = NOT_FOUND, description = "Product release was not found"),
+    ),
+    params(
+        ("name" = String, Path, description = "Name of the product"),
+        ("version" = String, Path, description = "Version of the product"),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_product(db: web::Data<Db>, path: web::Path<(String, String)>) -> actix_web::Result<HttpResponse> {
+    let (name, version) = path.into_inner();
+    match db.get_product(&name, &version).await.map_err(Error::from)? {
+        Some(product) => Ok(HttpResponse::Ok().json(product)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Register a product release, or replace the SBOMs of an existing one.
+///
+/// The SBOMs are referred to by their ID, they don't need to be uploaded yet.
+#[utoipa::path(
+    put,
+    tag = "product",
+    path = "/api/v1/products/{name}/{version}",
+    request_body = NewProduct,
+    responses(
+        (status = OK, description = "Product release was updated", body = Product),
+        (status = CREATED, description = "Product release was registered", body = Product),
+        (status = BAD_REQUEST, description = "The product has no name or version, or an SBOM has no ID"),
+        (status = FORBIDDEN, description = "The user may not manage products"),
+    ),
+    params(
+        ("name" = String, Path, description = "Name of the product"),
+        ("version" = String, Path, description = "Version of the product"),
+    )
+)]
+#[instrument(skip(db, authorizer, product), err)]
+pub async fn put_product(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    path: web::Path<(String, String)>,
+    web::Json(product): web::Json<NewProduct>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageProducts)?;
+
+    let (name, version) = path.into_inner();
+    if let Some(error) = validate(&name, &version, &product.sboms) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    match db.put_product(&name, &version, product).await.map_err(Error::from)? {
+        (product, true) => Ok(HttpResponse::Created().json(product)),
+        (product, false) => Ok(HttpResponse::Ok().json(product)),
+    }
+}
+
+/// Add SBOMs to a product release, keeping the ones it already has.
+#[utoipa::path(
+    post,
+    tag = "product",
+    path = "/api/v1/products/{name}/{version}/sboms",
+    request_body = Vec<String>,
+    responses(
+        (status = OK, description = "SBOMs were added", body = Product),
+        (status = BAD_REQUEST, description = "An SBOM has no ID"),
+        (status = FORBIDDEN, description = "The user may not manage products"),
+        (status = NOT_FOUND, description = "Product release was not found"),
+    ),
+    params(
+        ("name" = String, Path, description = "Name of the product"),
+        ("version" = String, Path, description = "Version of the product"),
+    )
+)]
+#[instrument(skip(db, authorizer), err)]
+pub async fn add_product_sboms(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    path: web::Path<(String, String)>,
+    web::Json(sboms): web::Json<Vec<String>>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageProducts)?;
+
+    let (name, version) = path.into_inner();
+    if let Some(error) = validate(&name, &version, &sboms) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    match db
+        .add_product_sboms(&name, &version, &sboms)
+        .await
+        .map_err(Error::from)?
+    {
+        Some(product) => Ok(HttpResponse::Ok().json(product)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+fn validate(name: &str, version: &str, sboms: &[String]) -> Option<ErrorInformation> {
+    let error = |error: &str, message: &str| {
+        Some(ErrorInformation {
+            error: error.to_string(),
+            message: message.to_string(),
+            details: String::new(),
+        })
+    };
+
+    if name.trim().is_empty() {
+        return error("MissingName", "A product requires a name");
+    }
+    if version.trim().is_empty() {
+        return error("MissingVersion", "A product requires a version");
+    }
+    if sboms.iter().any(|sbom| sbom.trim().is_empty()) {
+        return error(
+            "MissingSbomId",
+            "SBOMs are referred to by their ID, which must not be empty",
+        );
+    }
+    None
+}
+
+/// Delete a product release. Its SBOMs are kept.
+#[utoipa::path(
+    delete,
+    tag = "product",
+    path = "/api/v1/products/{name}/{version}",
+    responses(
+        (status = NO_CONTENT, description = "Product release was deleted"),
+        (status = FORBIDDEN, description = "The user may not manage products"),
+        (status = NOT_FOUND, description = "Product release was not found"),
+    ),
+    params(
+        ("name" = String, Path, description = "Name of the product"),
+        ("version" = String, Path, description = "Version of the product"),
+    )
+)]
+#[instrument(skip(db, authorizer), err)]
+pub async fn delete_product(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageProducts)?;
+
+    let (name, version) = path.into_inner();
+    match db.delete_product(&name, &version).await.map_err(Error::from)? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct ProductVulnerabilityParams {
+    /// Leave out vulnerabilities which VEX documents state the SBOMs are not affected by
+    #[serde(default)]
+    pub hide_not_affected: bool,
+}
+
+/// Get the vulnerabilities of a product release, rolled up from the vulnerability reports of its SBOMs.
+///
+/// A vulnerability affecting several SBOMs of the product is counted once.
+#[utoipa::path(
+    get,
+    tag = "product",
+    path = "/api/v1/products/{name}/{version}/vulnerabilities",
+    responses(
+        (status = OK, description = "Vulnerabilities of the product release", body = ProductVulnerabilityReport),
+        (status = NOT_FOUND, description = "Product release was not found"),
+    ),
+    params(
+        ("name" = String, Path, description = "Name of the product"),
+        ("version" = String, Path, description = "Version of the product"),
+        ProductVulnerabilityParams,
+    )
+)]
+#[instrument(skip(state, db, v11y, guac, access_token), err)]
+pub async fn get_product_vulnerabilities(
+    state: web::Data<AppState>,
+    db: web::Data<Db>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    path: web::Path<(String, String)>,
+    web::Query(params): web::Query<ProductVulnerabilityParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let (name, version) = path.into_inner();
+    let Some(product) = db.get_product(&name, &version).await.map_err(Error::from)? else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let (state, v11y, guac, access_token) = (&state, &v11y, &guac, &access_token);
+    let analyzed = iter(product.sboms)
+        .map(|id| async move {
+            match process_get_vulnerabilities(state, v11y, guac, access_token, &id, None, None).await {
+                Ok(report) => Ok((id, report)),
+                // the mapping may refer to SBOMs which are not (yet) uploaded
+                Err(Error::Response(StatusCode::NOT_FOUND, _)) => Ok((id, None)),
+                Err(err) => Err(err),
+            }
+        })
+        .buffer_unordered(PARALLEL_ANALYZE_SBOM)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut reports = Vec::new();
+    let mut missing = Vec::new();
+    for (id, report) in analyzed {
+        match report {
+            Some(mut report) => {
+                if params.hide_not_affected {
+                    report
+                        .details
+                        .retain(|vuln| vuln.status != Some(VexStatus::NotAffected));
+                }
+                reports.push((id, report));
+            }
+            None => missing.push(id),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(rollup(product.name, product.version, reports, missing)))
+}
+
+/// Roll up the vulnerability reports of the SBOMs of a product release.
+fn rollup(
+    name: String,
+    version: String,
+    reports: Vec<(String, SbomReport)>,
+    mut missing: Vec<String>,
+) -> ProductVulnerabilityReport {
+    let mut vulnerabilities = BTreeMap::<String, ProductVulnerability>::new();
+    // SBOMs of a product often share packages, which are only counted once
+    let mut packages = BTreeMap::<String, BTreeSet<String>>::new();
+    let mut sboms = Vec::with_capacity(reports.len());
+
+    for (id, report) in reports {
+        let mut counts = BTreeMap::<Option<cvss::Severity>, usize>::new();
+        for vuln in &report.details {
+            let score = vuln.sources.get(SUMMARY_SOURCE).and_then(|source| source.score);
+            *counts.entry(score.map(into_severity)).or_default() += 1;
+
+            let entry = vulnerabilities
+                .entry(vuln.id.clone())
+                .or_insert_with(|| ProductVulnerability {
+                    id: vuln.id.clone(),
+                    score: None,
+                    sboms: Vec::new(),
+                    affected_packages: 0,
+                    known_exploited: false,
+                });
+            // sources may disagree between the reports, keep the most severe score
+            entry.score = match (entry.score, score) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            entry.sboms.push(id.clone());
+            packages
+                .entry(vuln.id.clone())
+                .or_default()
+                .extend(vuln.affected_packages.keys().cloned());
+            entry.known_exploited |= vuln.known_exploited.is_some();
+        }
+
+        sboms.push(SbomVulnerabilitySummary {
+            id,
+            name: report.name,
+            version: report.version,
+            total: report.details.len(),
+            summary: into_summary(counts),
+        });
+    }
+
+    let mut counts = BTreeMap::<Option<cvss::Severity>, usize>::new();
+    for vuln in vulnerabilities.values_mut() {
+        vuln.sboms.sort();
+        vuln.affected_packages = packages.get(&vuln.id).map_or(0, BTreeSet::len);
+        *counts.entry(vuln.score.map(into_severity)).or_default() += 1;
+    }
+
+    let mut vulnerabilities: Vec<_> = vulnerabilities.into_values().collect();
+    // most severe first, vulnerabilities without a score last
+    vulnerabilities.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    // most affected first, keep the order stable for equal counts
+    sboms.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.id.cmp(&b.id)));
+    missing.sort();
+
+    ProductVulnerabilityReport {
+        name,
+        version,
+        total: vulnerabilities.len(),
+        summary: into_summary(counts),
+        vulnerabilities,
+        sboms,
+        missing,
+    }
+}
+
+fn into_summary(counts: BTreeMap<Option<cvss::Severity>, usize>) -> Vec<SummaryEntry> {
+    counts
+        .into_iter()
+        .map(|(severity, count)| SummaryEntry { severity, count })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spog_model::vuln::{SbomReportVulnerability, SourceDetails};
+    use std::collections::HashMap;
+
+    fn report(name: &str, vulns: &[(&str, Option<f32>)]) -> SbomReport {
+        SbomReport {
+            name: name.to_string(),
+            version: None,
+            created: None,
+            summary: Vec::new(),
+            details: vulns
+                .iter()
+                .map(|(id, score)| SbomReportVulnerability {
+                    id: id.to_string(),
+                    sources: HashMap::from([(SUMMARY_SOURCE.to_string(), SourceDetails { score: *score })]),
+                    affected_packages: BTreeMap::from([(format!("pkg:rpm/redhat/{name}"), Vec::new())]),
+                    ..Default::default()
+                })
+                .collect(),
+            backtraces: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_rollup() {
+        let report = rollup(
+            "rhel".to_string(),
+            "9.2".to_string(),
+            vec![
+                (
+                    "a".to_string(),
+                    report("a", &[("CVE-2023-0001", Some(9.8)), ("CVE-2023-0002", Some(5.0))]),
+                ),
+                (
+                    "b".to_string(),
+                    report("b", &[("CVE-2023-0002", Some(7.5)), ("CVE-2023-0003", None)]),
+                ),
+                ("c".to_string(), report("c", &[])),
+            ],
+            vec!["d".to_string()],
+        );
+
+        // vulnerabilities affecting several SBOMs are counted once
+        assert_eq!(report.total, 3);
+        let ids: Vec<_> = report.vulnerabilities.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, vec!["CVE-2023-0001", "CVE-2023-0002", "CVE-2023-0003"]);
+
+        let shared = &report.vulnerabilities[1];
+        assert_eq!(shared.score, Some(7.5));
+        assert_eq!(shared.sboms, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(shared.affected_packages, 2);
+
+        assert_eq!(
+            report.summary,
+            vec![
+                SummaryEntry {
+                    severity: None,
+                    count: 1
+                },
+                SummaryEntry {
+                    severity: Some(cvss::Severity::High),
+                    count: 1
+                },
+                SummaryEntry {
+                    severity: Some(cvss::Severity::Critical),
+                    count: 1
+                },
+            ]
+        );
+
+        let sboms: Vec<_> = report.sboms.iter().map(|s| (s.id.as_str(), s.total)).collect();
+        assert_eq!(sboms, vec![("a", 2), ("b", 2), ("c", 0)]);
+        assert_eq!(report.missing, vec!["d".to_string()]);
+    }
+
+    #[test]
+    fn test_rollup_shared_packages() {
+        let report = rollup(
+            "rhel".to_string(),
+            "9.2".to_string(),
+            vec![
+                ("a".to_string(), report("a", &[("CVE-2023-0001", Some(9.8))])),
+                ("b".to_string(), report("a", &[("CVE-2023-0001", Some(9.8))])),
+            ],
+            vec![],
+        );
+
+        let vuln = &report.vulnerabilities[0];
+        assert_eq!(vuln.sboms, vec!["a".to_string(), "b".to_string()]);
+        // both SBOMs contain the same package
+        assert_eq!(vuln.affected_packages, 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("rhel", "9.2", &["a".to_string()]).is_none());
+        assert_eq!(validate(" ", "9.2", &[]).unwrap().error, "MissingName");
+        assert_eq!(validate("rhel", "", &[]).unwrap().error, "MissingVersion");
+        assert_eq!(
+            validate("rhel", "9.2", &["".to_string()]).unwrap().error,
+            "MissingSbomId"
+        );
+    }
+}
@@ -0,0 +1,466 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use base64::{engine::general_purpose, Engine};
+use openssl::{
+    hash::MessageDigest,
+    pkey::{Id, PKey, Public},
+    sign::Verifier,
+};
+use packageurl::PackageUrl;
+use serde_json::Value;
+use spog_model::attestation::{Attestation, AttestationReport, AttestationSource, VerificationStatus};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_common::{error::ErrorInformation, oci::Registry};
+
+/// media type of DSSE envelopes, stored as layers of an attestation manifest
+const DSSE_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+/// payload type of in-toto statements
+const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+/// manifest types accepted from the registry
+const MANIFEST_TYPES: &[&str] = &[
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+/// Load the public keys trusted for signing attestations, from PEM encoded files.
+pub(crate) fn load_attestation_keys(paths: &[PathBuf]) -> anyhow::Result<Vec<PKey<Public>>> {
+    paths
+        .iter()
+        .map(|path| {
+            let pem = std::fs::read(path)
+                .map_err(|err| anyhow::anyhow!("Failed to read attestation key {}: {err}", path.display()))?;
+            PKey::public_key_from_pem(&pem)
+                .map_err(|err| anyhow::anyhow!("Failed to parse attestation key {}: {err}", path.display()))
+        })
+        .collect()
+}
+
+/// Get the in-toto attestations of an SBOM, along with their verification status.
+///
+/// Attestations are either attached to the SBOM when it was stored, or looked up in the OCI registry of the container
+/// image the SBOM describes, if that registry is a configured one. An attestation is verified if it is signed by a
+/// trusted key, and its subject is the SBOM document, or the image it describes.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/{id}/attestations",
+    responses(
+        (status = OK, description = "Processing succeeded", body = AttestationReport),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the SBOM"),
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn get_attestations(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(hit) = state
+        .search_sbom(&format!(r#"id:"{id}""#), 0, 1, SearchOptions::default(), &access_token)
+        .await?
+        .result
+        .into_iter()
+        .next()
+    else {
+        return Ok(HttpResponse::NotFound().json(ErrorInformation {
+            error: "NotFound".to_string(),
+            message: format!("SBOM {id} was not found"),
+            details: String::new(),
+        }));
+    };
+    let document = hit.document;
+
+    let image = document.purl.as_deref().and_then(ImageReference::from_purl);
+    let digests: HashSet<String> = [document.file_sha256.as_str(), document.sha256.as_str()]
+        .into_iter()
+        .chain(image.as_ref().map(|image| image.digest.as_str()))
+        .filter(|digest| !digest.is_empty())
+        .map(normalize_digest)
+        .collect();
+
+    let mut report = AttestationReport::default();
+
+    for envelope in state.get_sbom_attestations(&id, &access_token).await? {
+        report.attestations.push(verify(
+            &envelope,
+            AttestationSource::Attached,
+            &state.attestation_keys,
+            &digests,
+        ));
+    }
+
+    if !state.attestation_registries.is_empty() {
+        let registries = &state.attestation_registries;
+        match image {
+            Some(image) if !is_allowed(registries, &image.registry) => {
+                log::debug!(
+                    "SBOM {id} describes an image of the registry {}, which is not allowed",
+                    image.registry
+                )
+            }
+            Some(image) => match fetch_registry_attestations(&state.client, registries, &image).await {
+                Ok(envelopes) => {
+                    for envelope in envelopes {
+                        report.attestations.push(verify(
+                            &envelope,
+                            AttestationSource::Registry,
+                            &state.attestation_keys,
+                            &digests,
+                        ));
+                    }
+                }
+                Err(err) => {
+                    log::info!("Failed to look up attestations of {}: {err}", image.repository);
+                    report
+                        .errors
+                        .push(format!("Failed to look up attestations of {}: {err}", image.repository));
+                }
+            },
+            None => log::debug!("SBOM {id} doesn't describe an image with a known registry"),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Prefix a digest with its algorithm, assuming SHA-256 if there is none.
+fn normalize_digest(digest: &str) -> String {
+    let digest = digest.to_lowercase();
+    match digest.contains(':') {
+        true => digest,
+        false => format!("sha256:{digest}"),
+    }
+}
+
+/// A container image, by digest, as referenced by an OCI package URL
+#[derive(Debug, PartialEq, Eq)]
+struct ImageReference {
+    /// The host of the registry
+    registry: String,
+    /// The repository, within the registry
+    repository: String,
+    /// The digest of the image manifest, prefixed with its algorithm
+    digest: String,
+}
+
+impl ImageReference {
+    /// Create a reference from a package URL like `pkg:oci/name@sha256:…?repository_url=quay.io/org/name`.
+    fn from_purl(purl: &str) -> Option<Self> {
+        let purl = PackageUrl::from_str(purl).ok()?;
+        if purl.ty() != "oci" {
+            return None;
+        }
+        let digest = purl.version().filter(|version| version.contains(':'))?.to_lowercase();
+        let (registry, repository) = purl.qualifiers().get("repository_url")?.split_once('/')?;
+        Some(Self {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            digest,
+        })
+    }
+}
+
+/// Check if a host, optionally with a port, is one of the allowed registries.
+fn is_allowed(registries: &[String], host: &str) -> bool {
+    registries.iter().any(|registry| registry.eq_ignore_ascii_case(host))
+}
+
+/// Fetch the attestations stored alongside an image, using the tag convention of cosign (`sha256-<hex>.att`).
+///
+/// Anonymous tokens are only requested from the allowed registries.
+async fn fetch_registry_attestations(
+    client: &reqwest::Client,
+    registries: &[String],
+    image: &ImageReference,
+) -> anyhow::Result<Vec<Value>> {
+    let mut registry =
+        Registry::new(client.clone(), &image.registry, &image.repository, false)?.with_allowed_realms(registries);
+
+    let tag = format!("{}.att", image.digest.replace(':', "-"));
+    let Some((_, manifest)) = registry.manifest(&tag, MANIFEST_TYPES).await? else {
+        return Ok(vec![]);
+    };
+
+    let mut result = Vec::new();
+    for layer in manifest["layers"].as_array().into_iter().flatten() {
+        if layer["mediaType"].as_str() != Some(DSSE_MEDIA_TYPE) {
+            continue;
+        }
+        let Some(digest) = layer["digest"].as_str() else {
+            continue;
+        };
+        if let Some(blob) = registry.blob(digest).await? {
+            result.push(serde_json::from_slice(&blob)?);
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    payload_type: String,
+    payload: String,
+    #[serde(default)]
+    signatures: Vec<Signature>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Signature {
+    #[serde(default)]
+    keyid: Option<String>,
+    sig: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Statement {
+    #[serde(default)]
+    subject: Vec<Subject>,
+    predicate_type: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Subject {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    digest: BTreeMap<String, String>,
+}
+
+/// The pre-authentication encoding of DSSE, which is what gets signed.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut result = format!("DSSEv1 {} {payload_type} {} ", payload_type.len(), payload.len()).into_bytes();
+    result.extend_from_slice(payload);
+    result
+}
+
+/// Decode base64, DSSE allows both the standard and the URL safe alphabet.
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(value)
+        .or_else(|_| general_purpose::URL_SAFE.decode(value))
+        .ok()
+}
+
+/// Check a signature, using SHA-256 for RSA and EC keys.
+fn verify_signature(key: &PKey<Public>, message: &[u8], signature: &[u8]) -> bool {
+    let result = match key.id() {
+        Id::ED25519 => {
+            Verifier::new_without_digest(key).and_then(|mut verifier| verifier.verify_oneshot(signature, message))
+        }
+        _ => Verifier::new(MessageDigest::sha256(), key).and_then(|mut verifier| {
+            verifier.update(message)?;
+            verifier.verify(signature)
+        }),
+    };
+    result.unwrap_or(false)
+}
+
+/// Verify a DSSE envelope carrying an in-toto statement.
+fn verify(
+    envelope: &Value,
+    source: AttestationSource,
+    keys: &[PKey<Public>],
+    digests: &HashSet<String>,
+) -> Attestation {
+    let invalid = |message: &str| Attestation {
+        source,
+        predicate_type: None,
+        subjects: vec![],
+        status: VerificationStatus::Invalid,
+        message: Some(message.to_string()),
+        key_id: None,
+    };
+
+    let Ok(envelope) = serde_json::from_value::<Envelope>(envelope.clone()) else {
+        return invalid("Not a DSSE envelope");
+    };
+    if envelope.payload_type != IN_TOTO_PAYLOAD_TYPE {
+        return invalid(&format!("Unsupported payload type: {}", envelope.payload_type));
+    }
+    let Some(payload) = decode_base64(&envelope.payload) else {
+        return invalid("Payload is not base64 encoded");
+    };
+    let Ok(statement) = serde_json::from_slice::<Statement>(&payload) else {
+        return invalid("Payload is not an in-toto statement");
+    };
+
+    let mut attestation = Attestation {
+        source,
+        predicate_type: statement.predicate_type,
+        subjects: statement
+            .subject
+            .iter()
+            .flat_map(|subject| {
+                subject
+                    .digest
+                    .iter()
+                    .map(move |(alg, digest)| format!("{}@{alg}:{digest}", subject.name))
+            })
+            .collect(),
+        status: VerificationStatus::Unverified,
+        message: None,
+        key_id: None,
+    };
+
+    let matches_subject = statement.subject.iter().any(|subject| {
+        subject
+            .digest
+            .iter()
+            .any(|(alg, digest)| digests.contains(&format!("{alg}:{digest}").to_lowercase()))
+    });
+    if !matches_subject {
+        attestation.status = VerificationStatus::Invalid;
+        attestation.message = Some("The subject is neither the SBOM, nor the image it describes".to_string());
+        return attestation;
+    }
+
+    if keys.is_empty() {
+        attestation.message = Some("No trusted keys are configured".to_string());
+        return attestation;
+    }
+
+    let message = pae(&envelope.payload_type, &payload);
+    let verified = envelope.signatures.iter().find(|signature| {
+        decode_base64(&signature.sig)
+            .map(|sig| keys.iter().any(|key| verify_signature(key, &message, &sig)))
+            .unwrap_or(false)
+    });
+
+    match verified {
+        Some(signature) => {
+            attestation.status = VerificationStatus::Verified;
+            attestation.key_id = signature.keyid.clone().filter(|keyid| !keyid.is_empty());
+        }
+        None => attestation.message = Some("Not signed by a trusted key".to_string()),
+    }
+
+    attestation
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        nid::Nid,
+        pkey::Private,
+        sign::Signer,
+    };
+    use serde_json::json;
+
+    const DIGEST: &str = "sha256:4ea4d5c0a0a6e1ec1b8e3e8a0a2b1e8e4f3c5dcd2a9c7e0c0b7e6a5f4d3c2b1a";
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn public(key: &PKey<Private>) -> PKey<Public> {
+        PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap()
+    }
+
+    fn envelope(key: &PKey<Private>, digest: &str) -> Value {
+        let (alg, digest) = digest.split_once(':').unwrap();
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "quay.io/org/app", "digest": { alg: digest } }],
+            "predicateType": "https://slsa.dev/provenance/v1",
+            "predicate": {},
+        });
+        let payload = serde_json::to_vec(&statement).unwrap();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(&pae(IN_TOTO_PAYLOAD_TYPE, &payload)).unwrap();
+        let sig = signer.sign_to_vec().unwrap();
+
+        json!({
+            "payloadType": IN_TOTO_PAYLOAD_TYPE,
+            "payload": general_purpose::STANDARD.encode(payload),
+            "signatures": [{ "keyid": "test", "sig": general_purpose::STANDARD.encode(sig) }],
+        })
+    }
+
+    #[test]
+    fn test_pae() {
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let key = key();
+        let digests = HashSet::from([DIGEST.to_string()]);
+
+        let attestation = verify(
+            &envelope(&key, DIGEST),
+            AttestationSource::Attached,
+            &[public(&key)],
+            &digests,
+        );
+        assert_eq!(attestation.status, VerificationStatus::Verified);
+        assert_eq!(attestation.key_id.as_deref(), Some("test"));
+        assert_eq!(
+            attestation.predicate_type.as_deref(),
+            Some("https://slsa.dev/provenance/v1")
+        );
+        assert_eq!(attestation.subjects, vec![format!("quay.io/org/app@{DIGEST}")]);
+
+        // signed by another key
+        let attestation = verify(
+            &envelope(&key, DIGEST),
+            AttestationSource::Attached,
+            &[public(&self::key())],
+            &digests,
+        );
+        assert_eq!(attestation.status, VerificationStatus::Unverified);
+
+        // different subject
+        let attestation = verify(
+            &envelope(&key, "sha256:0000"),
+            AttestationSource::Attached,
+            &[public(&key)],
+            &digests,
+        );
+        assert_eq!(attestation.status, VerificationStatus::Invalid);
+
+        let attestation = verify(&json!({}), AttestationSource::Registry, &[], &digests);
+        assert_eq!(attestation.status, VerificationStatus::Invalid);
+    }
+
+    #[test]
+    fn test_image_reference() {
+        let image = ImageReference::from_purl(&format!(
+            "pkg:oci/app@{}?repository_url=quay.io/org/app&tag=v1",
+            DIGEST.replace(':', "%3A")
+        ))
+        .unwrap();
+        assert_eq!(image.registry, "quay.io");
+        assert_eq!(image.repository, "org/app");
+        assert_eq!(image.digest, DIGEST);
+
+        assert!(ImageReference::from_purl("pkg:rpm/redhat/openssl@1.1.1k").is_none());
+        assert!(ImageReference::from_purl("pkg:oci/app@sha256%3A1234").is_none());
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let registries = ["quay.io".to_string(), "localhost:5000".to_string()];
+        assert!(is_allowed(&registries, "quay.io"));
+        assert!(is_allowed(&registries, "Quay.IO"));
+        assert!(is_allowed(&registries, "localhost:5000"));
+        assert!(!is_allowed(&registries, "localhost"));
+        assert!(!is_allowed(&registries, "169.254.169.254"));
+    }
+}
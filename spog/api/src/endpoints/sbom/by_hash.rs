@@ -0,0 +1,100 @@
+use super::into_summary;
+use crate::app_state::AppState;
+use crate::search;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::search::SbomSummary;
+use tracing::instrument;
+use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_common::error::ErrorInformation;
+use utoipa::IntoParams;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct ByHashParams {
+    /// Offset to start from returning results.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of results to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Check if the value is a SHA256 or SHA1 digest, optionally prefixed with its algorithm.
+fn is_digest(value: &str) -> bool {
+    let (alg, digest) = value.rsplit_once(':').unwrap_or(("", value));
+    let len = match alg.to_lowercase().as_str() {
+        "" => digest.len(),
+        "sha256" if digest.len() == 64 => 64,
+        "sha1" if digest.len() == 40 => 40,
+        _ => return false,
+    };
+    matches!(len, 40 | 64) && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Find all SBOMs containing an artifact with the provided SHA256 or SHA1 digest.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/by-hash/{digest}",
+    responses(
+        (status = OK, description = "Search was performed successfully", body = SearchResultSbom),
+        (status = BAD_REQUEST, description = "The value is not a SHA256 or SHA1 digest"),
+    ),
+    params(
+        ("digest" = String, Path, description = "Digest of the artifact, optionally prefixed with `sha256:` or `sha1:`"),
+        ByHashParams,
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn by_hash(
+    state: web::Data<AppState>,
+    digest: web::Path<String>,
+    params: web::Query<ByHashParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    if !is_digest(&digest) {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidDigest".to_string(),
+            message: "The value must be a SHA256 or SHA1 digest".to_string(),
+            details: digest.into_inner(),
+        }));
+    }
+
+    let q = format!(r#"artifact:"{digest}""#);
+    let data = state
+        .search_sbom(&q, params.offset, params.limit, SearchOptions::default(), &access_token)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(SearchResult::<Vec<SbomSummary>> {
+        total: Some(data.total),
+        result: data.result.into_iter().map(into_summary).collect(),
+        facets: Default::default(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_digest() {
+        assert!(is_digest(
+            "a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc"
+        ));
+        assert!(is_digest("e743499c22f07eda63501cb6ccc3dc8e05acc399"));
+        assert!(is_digest(
+            "SHA256:A638B684D70CA77EDE090F349D2831D428F1217557B59624485D6CD1E058CFFC"
+        ));
+        assert!(is_digest("sha1:e743499c22f07eda63501cb6ccc3dc8e05acc399"));
+
+        assert!(!is_digest(
+            "sha1:a638b684d70ca77ede090f349d2831d428f1217557b59624485d6cd1e058cffc"
+        ));
+        assert!(!is_digest("md5:3fb22c817d5693fb4cc719520ecc396c"));
+        assert!(!is_digest("e743499c22f07eda63501cb6ccc3dc8e05acc39x"));
+        assert!(!is_digest("\" OR id:foo"));
+    }
+}
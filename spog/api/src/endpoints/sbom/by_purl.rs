@@ -0,0 +1,216 @@
+use super::into_summary;
+use crate::app_state::AppState;
+use crate::error::Error;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures::stream::iter;
+use futures::{StreamExt, TryStreamExt};
+use spog_model::search::SbomSummary;
+use std::collections::BTreeMap;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_common::error::ErrorInformation;
+use utoipa::IntoParams;
+
+/// maximum number of package URLs in a single request
+const MAX_PURLS: usize = 1000;
+/// number of parallel searches
+const PARALLEL_SEARCH: usize = 8;
+/// maximum size of the request payload
+pub(crate) const PAYLOAD_LIMIT: usize = 1024 * 1024;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct ByPurlParams {
+    /// Maximum number of SBOMs to return per package URL
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Lookup the SBOMs declaring any of the provided package URLs as a component.
+///
+/// The result is a map, using the requested package URLs as key.
+#[utoipa::path(
+    post,
+    path = "/api/v1/sbom/by-purl",
+    request_body = Vec<String>,
+    responses(
+        (status = OK, description = "Lookup was performed successfully", body = BTreeMap<String, Vec<SbomSummary>>),
+        (status = BAD_REQUEST, description = "Too many package URLs requested"),
+    ),
+    params(ByPurlParams)
+)]
+#[instrument(skip(state, purls, access_token), fields(purls = purls.len()), err)]
+pub async fn by_purl(
+    state: web::Data<AppState>,
+    params: web::Query<ByPurlParams>,
+    web::Json(mut purls): web::Json<Vec<String>>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    if purls.len() > MAX_PURLS {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "TooManyPurls".to_string(),
+            message: format!("A maximum of {MAX_PURLS} package URLs can be requested at once"),
+            details: String::new(),
+        }));
+    }
+
+    purls.sort_unstable();
+    purls.dedup();
+
+    let limit = params.limit;
+    let state = &state;
+    let access_token = &access_token;
+
+    let result: BTreeMap<String, Vec<SbomSummary>> = iter(purls)
+        .map(|purl| async move {
+            let term = purl.replace('\"', "");
+            let q = format!(r#"package:"{term}" OR dependency:"{term}""#);
+            let sboms = state
+                .search_sbom(&q, 0, limit, SearchOptions::default(), access_token)
+                .await?;
+            Ok::<_, Error>((purl, sboms.result.into_iter().map(into_summary).collect()))
+        })
+        .buffer_unordered(PARALLEL_SEARCH)
+        .try_collect()
+        .await?;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cache::{AdvisoryCache, TimedCache};
+    use actix_web::{test, App, HttpServer};
+    use bombastic_model::search::{SearchDocument, SearchHit, SearchResult};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use trustification_auth::client::NoTokenProvider;
+
+    const PURL: &str = "pkg:rpm/redhat/openssl@3.0.7-16.el9?arch=x86_64";
+
+    fn document(id: &str) -> SearchDocument {
+        SearchDocument {
+            id: id.to_string(),
+            uid: None,
+            name: "ubi9".to_string(),
+            version: "9.2".to_string(),
+            cpe: None,
+            purl: None,
+            file_sha256: String::new(),
+            sha256: String::new(),
+            license: String::new(),
+            supplier: String::new(),
+            classifier: String::new(),
+            spec_version: None,
+            description: String::new(),
+            snippet: String::new(),
+            created: time::OffsetDateTime::UNIX_EPOCH,
+            dependencies: 1,
+            labels: Default::default(),
+            signed: false,
+            verified: false,
+            quality_score: None,
+            quality: None,
+        }
+    }
+
+    /// Serve the SBOM search of bombastic, finding a single SBOM depending on the known package URL.
+    fn upstream() -> reqwest::Url {
+        let listener = std::net::TcpListener::bind("localhost:0").unwrap();
+        let url = reqwest::Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = HttpServer::new(|| {
+            App::new().route(
+                "/api/v1/sbom/search",
+                web::get().to(|params: web::Query<HashMap<String, String>>| async move {
+                    let result = match params["q"] == format!(r#"package:"{PURL}" OR dependency:"{PURL}""#) {
+                        true => vec![SearchHit {
+                            document: document("ubi9"),
+                            score: 1.0,
+                            explanation: None,
+                            metadata: None,
+                        }],
+                        false => vec![],
+                    };
+                    HttpResponse::Ok().json(SearchResult {
+                        total: result.len(),
+                        result,
+                        facets: Default::default(),
+                        cursor: None,
+                    })
+                }),
+            )
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+        url
+    }
+
+    fn state(url: reqwest::Url) -> web::Data<AppState> {
+        web::Data::new(AppState {
+            client: reqwest::Client::new(),
+            provider: Arc::new(NoTokenProvider),
+            bombastic: url.clone(),
+            vexination: url.clone(),
+            exhort: url,
+            advisory_search_concurrency: 1,
+            advisory_search_timeout: Duration::from_secs(10),
+            advisory_cache: AdvisoryCache::new(Duration::from_secs(60)),
+            dashboard_cache: TimedCache::new(Duration::from_secs(60)),
+            attestation_keys: Vec::new(),
+            attestation_registries: Vec::new(),
+            advisory_default_sort: None,
+            vex_namespace: None,
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_by_purl() {
+        let app = test::init_service(
+            App::new()
+                .app_data(state(upstream()))
+                .route("/api/v1/sbom/by-purl", web::post().to(by_purl)),
+        )
+        .await;
+
+        let request = test::TestRequest::post()
+            .uri("/api/v1/sbom/by-purl")
+            .set_json([PURL, "pkg:rpm/redhat/unknown@1.0", "not a package URL", PURL])
+            .to_request();
+        let result: BTreeMap<String, Vec<Value>> = test::call_and_read_body_json(&app, request).await;
+
+        // requested package URLs are deduplicated, the ones not found are still part of the result
+        assert_eq!(result.len(), 3);
+        let found: Vec<_> = result[PURL].iter().map(|sbom| sbom["id"].as_str().unwrap()).collect();
+        assert_eq!(found, vec!["ubi9"]);
+        assert!(result["pkg:rpm/redhat/unknown@1.0"].is_empty());
+        assert!(result["not a package URL"].is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_by_purl_too_many() {
+        let app = test::init_service(
+            App::new()
+                .app_data(state(upstream()))
+                .route("/api/v1/sbom/by-purl", web::post().to(by_purl)),
+        )
+        .await;
+
+        let purls: Vec<_> = (0..=MAX_PURLS).map(|n| format!("pkg:rpm/redhat/p{n}@1.0")).collect();
+        let request = test::TestRequest::post()
+            .uri("/api/v1/sbom/by-purl")
+            .set_json(purls)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
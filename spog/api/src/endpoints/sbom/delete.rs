@@ -0,0 +1,32 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use tracing::instrument;
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct DeleteParams {
+    /// ID of the SBOM to delete
+    pub id: String,
+}
+
+/// Delete an SBOM.
+///
+/// The SBOM is removed from storage, which removes it from the index as well. Once the index was updated, the indexer
+/// emits a deletion event, allowing downstream consumers to clean up data derived from the SBOM.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/sbom",
+    responses(
+        (status = NO_CONTENT, description = "SBOM either deleted or nonexistent"),
+    ),
+    params(DeleteParams)
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn delete(
+    state: web::Data<AppState>,
+    web::Query(DeleteParams { id }): web::Query<DeleteParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    state.delete_sbom(&id, &access_token).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
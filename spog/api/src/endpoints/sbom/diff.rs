@@ -0,0 +1,249 @@
+use super::{fetch_sbom, vuln::process_get_vulnerabilities};
+use crate::app_state::AppState;
+use crate::error::Error;
+use crate::service::{guac::GuacService, v11y::V11yService};
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::data::SBOM;
+use packageurl::PackageUrl;
+use spog_model::diff::{ComponentRef, SbomDiff, VersionChange, VulnerabilityDiff};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use tracing::instrument;
+use trustification_auth::client::TokenProvider;
+use utoipa::IntoParams;
+
+/// Components of an SBOM, by normalized identity, with all versions found
+type Components = BTreeMap<String, BTreeSet<String>>;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct DiffParams {
+    /// ID of the first (older) SBOM
+    pub a: String,
+    /// ID of the second (newer) SBOM
+    pub b: String,
+}
+
+/// Compare two SBOMs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/diff",
+    responses(
+        (status = OK, description = "Processing succeeded", body = SbomDiff),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(DiffParams)
+)]
+#[instrument(skip(state, v11y, guac, access_token), err)]
+pub async fn diff(
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    params: web::Query<DiffParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let (a, b) = futures::try_join!(
+        fetch_components(&state, &access_token, &params.a),
+        fetch_components(&state, &access_token, &params.b),
+    )?;
+
+    let (vulns_a, vulns_b) = futures::try_join!(
+        fetch_vulnerabilities(&state, &v11y, &guac, &access_token, &params.a),
+        fetch_vulnerabilities(&state, &v11y, &guac, &access_token, &params.b),
+    )?;
+
+    let mut result = diff_components(&a, &b);
+    result.vulnerabilities = VulnerabilityDiff {
+        added: vulns_b.difference(&vulns_a).cloned().collect(),
+        removed: vulns_a.difference(&vulns_b).cloned().collect(),
+    };
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[instrument(skip(state, access_token), err)]
+async fn fetch_components(state: &AppState, access_token: &dyn TokenProvider, id: &str) -> Result<Components, Error> {
+    let sbom = fetch_sbom(state, access_token, id).await?;
+
+    let mut result = Components::new();
+    let mut add = |purl: Option<String>, name: &str, version: Option<&str>| {
+        let (id, version) = normalize(purl.as_deref(), name, version);
+        let versions = result.entry(id).or_default();
+        if let Some(version) = version {
+            versions.insert(version);
+        }
+    };
+
+    match sbom {
+        SBOM::SPDX(spdx) => {
+            for pi in &spdx.package_information {
+                let purl = pi
+                    .external_reference
+                    .iter()
+                    .find(|er| er.reference_type == "purl")
+                    .map(|er| er.reference_locator.clone());
+                add(purl, &pi.package_name, pi.package_version.as_deref());
+            }
+        }
+        SBOM::CycloneDX(cyclone) => {
+            let mut todo: Vec<_> = cyclone.components.iter().flat_map(|c| c.0.iter()).collect();
+            while let Some(component) = todo.pop() {
+                let version = component.version.as_ref().map(|v| v.to_string());
+                add(
+                    component.purl.as_ref().map(|p| p.to_string()),
+                    &component.name.to_string(),
+                    version.as_deref(),
+                );
+                todo.extend(component.components.iter().flat_map(|c| c.0.iter()));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[instrument(skip(state, v11y, guac, access_token), err)]
+async fn fetch_vulnerabilities(
+    state: &AppState,
+    v11y: &V11yService,
+    guac: &GuacService,
+    access_token: &dyn TokenProvider,
+    id: &str,
+) -> Result<BTreeSet<String>, Error> {
+    Ok(
+        process_get_vulnerabilities(state, v11y, guac, access_token, id, None, None)
+            .await?
+            .map(|report| report.details.into_iter().map(|vuln| vuln.id).collect())
+            .unwrap_or_default(),
+    )
+}
+
+/// Normalize a component into its identity and version.
+///
+/// If the component has a valid package URL, the identity is the package URL without version and qualifiers,
+/// otherwise it is the plain name.
+fn normalize(purl: Option<&str>, name: &str, version: Option<&str>) -> (String, Option<String>) {
+    match purl.and_then(|purl| PackageUrl::from_str(purl).ok()) {
+        Some(purl) => {
+            let id = match purl.namespace() {
+                Some(namespace) => format!("pkg:{}/{}/{}", purl.ty(), namespace, purl.name()),
+                None => format!("pkg:{}/{}", purl.ty(), purl.name()),
+            };
+            let version = purl.version().or(version).map(ToString::to_string);
+            (id, version)
+        }
+        None => (name.to_string(), version.map(ToString::to_string)),
+    }
+}
+
+fn join(versions: &BTreeSet<String>) -> Option<String> {
+    match versions.is_empty() {
+        true => None,
+        false => Some(versions.iter().cloned().collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn diff_components(a: &Components, b: &Components) -> SbomDiff {
+    let mut result = SbomDiff::default();
+
+    let refs = |id: &String, versions: &BTreeSet<String>| -> Vec<ComponentRef> {
+        match versions.is_empty() {
+            true => vec![ComponentRef {
+                id: id.clone(),
+                version: None,
+            }],
+            false => versions
+                .iter()
+                .map(|version| ComponentRef {
+                    id: id.clone(),
+                    version: Some(version.clone()),
+                })
+                .collect(),
+        }
+    };
+
+    for (id, versions) in a {
+        match b.get(id) {
+            None => result.removed.extend(refs(id, versions)),
+            Some(other) if other != versions => result.changed.push(VersionChange {
+                id: id.clone(),
+                from: join(versions),
+                to: join(other),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for (id, versions) in b {
+        if !a.contains_key(id) {
+            result.added.extend(refs(id, versions));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn components(items: &[(&str, &[&str])]) -> Components {
+        items
+            .iter()
+            .map(|(id, versions)| (id.to_string(), versions.iter().map(ToString::to_string).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(
+                Some("pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?arch=x86_64"),
+                "openssl",
+                None
+            ),
+            ("pkg:rpm/redhat/openssl".to_string(), Some("1.1.1k-7.el8_6".to_string()))
+        );
+        assert_eq!(
+            normalize(Some("not a purl"), "openssl", Some("1.1.1")),
+            ("openssl".to_string(), Some("1.1.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_diff_components() {
+        let a = components(&[
+            ("pkg:rpm/redhat/openssl", &["1.1.1"]),
+            ("pkg:rpm/redhat/bash", &["5.1"]),
+        ]);
+        let b = components(&[
+            ("pkg:rpm/redhat/openssl", &["3.0.7"]),
+            ("pkg:rpm/redhat/curl", &["7.76"]),
+        ]);
+
+        let result = diff_components(&a, &b);
+
+        assert_eq!(
+            result.added,
+            vec![ComponentRef {
+                id: "pkg:rpm/redhat/curl".to_string(),
+                version: Some("7.76".to_string()),
+            }]
+        );
+        assert_eq!(
+            result.removed,
+            vec![ComponentRef {
+                id: "pkg:rpm/redhat/bash".to_string(),
+                version: Some("5.1".to_string()),
+            }]
+        );
+        assert_eq!(
+            result.changed,
+            vec![VersionChange {
+                id: "pkg:rpm/redhat/openssl".to_string(),
+                from: Some("1.1.1".to_string()),
+                to: Some("3.0.7".to_string()),
+            }]
+        );
+    }
+}
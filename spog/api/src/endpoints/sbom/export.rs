@@ -0,0 +1,87 @@
+use super::{into_summary, search_advisories};
+use crate::app_state::AppState;
+use crate::search::{self, ExportParams};
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use spog_model::search::SbomSummary;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+
+const COLUMNS: [&str; 14] = [
+    "id",
+    "name",
+    "version",
+    "purl",
+    "cpe",
+    "sha256",
+    "license",
+    "classifier",
+    "supplier",
+    "description",
+    "dependencies",
+    "advisories",
+    "created",
+    "href",
+];
+
+fn row(sbom: &SbomSummary) -> [String; 14] {
+    [
+        sbom.id.clone(),
+        sbom.name.clone(),
+        sbom.version.clone(),
+        sbom.purl.clone().unwrap_or_default(),
+        sbom.cpe.clone().unwrap_or_default(),
+        sbom.sha256.clone(),
+        sbom.license.clone(),
+        sbom.classifier.clone(),
+        sbom.supplier.clone(),
+        sbom.description.clone(),
+        sbom.dependencies.to_string(),
+        sbom.advisories
+            .map(|advisories| advisories.to_string())
+            .unwrap_or_default(),
+        sbom.created.to_string(),
+        sbom.href.clone(),
+    ]
+}
+
+/// Export all SBOMs matching a query.
+///
+/// The query is run page by page, using `limit` as page size, streaming the results.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/search/export",
+    responses(
+        (status = OK, description = "Search was performed successfully", content_type = "text/csv", body = String),
+    ),
+    params(
+        search::QueryParams,
+        ExportParams,
+    )
+)]
+#[instrument(skip(state, access_token))]
+pub async fn export(
+    state: web::Data<AppState>,
+    params: web::Query<search::QueryParams>,
+    export: web::Query<ExportParams>,
+    access_token: Option<BearerAuth>,
+) -> HttpResponse {
+    let params = params.into_inner();
+    log::trace!("Exporting SBOMs using {}", params.q);
+
+    let pages = search::pages(params.offset, params.limit, move |offset, limit| {
+        let (state, q, access_token) = (state.clone(), params.q.clone(), access_token.clone());
+        async move {
+            let data = state
+                .search_sbom(&q, offset, limit, SearchOptions::default(), &access_token)
+                .await?;
+            let mut result: Vec<SbomSummary> = data.result.into_iter().map(into_summary).collect();
+            search_advisories(state, &mut result, &access_token).await;
+            Ok(result)
+        }
+    });
+
+    match export.format {
+        search::ExportFormat::Csv => search::csv_response("sboms", search::csv(COLUMNS, pages, row)),
+    }
+}
@@ -1,6 +1,8 @@
+use super::{download_sbom, parse_sbom};
 use crate::app_state::AppState;
-use actix_web::{web, HttpResponse};
+use actix_web::{http::header::ContentType, web, HttpResponse};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::convert::{convert, Format};
 use http::header;
 use tracing::instrument;
 
@@ -10,9 +12,15 @@ pub struct GetParams {
     pub id: String,
     /// Access token to use for authentication
     pub token: Option<String>,
+    /// Convert the SBOM to this format (`cyclonedx-json` or `spdx-json`) before returning it
+    #[param(value_type = Option<String>)]
+    pub format: Option<Format>,
 }
 
 /// Get (aka download) an SBOM.
+///
+/// If a format is requested, the SBOM is converted into that format, carrying over its components and their
+/// dependencies, see [`bombastic_model::convert`]. SBOMs which already are in the requested format are returned as is.
 #[utoipa::path(
     get,
     path = "/api/v1/sbom",
@@ -25,10 +33,25 @@ pub struct GetParams {
 #[instrument(skip(state, access_token))]
 pub async fn get(
     state: web::Data<AppState>,
-    web::Query(GetParams { id, token }): web::Query<GetParams>,
+    web::Query(GetParams { id, token, format }): web::Query<GetParams>,
     access_token: Option<BearerAuth>,
 ) -> actix_web::Result<HttpResponse> {
     let token = token.or_else(|| access_token.map(|s| s.token().to_string()));
+
+    if let Some(format) = format {
+        let data = download_sbom(&state, &token, &id).await?;
+        let sbom = parse_sbom(&data)?;
+        let body = match convert(&sbom, &id, format) {
+            Some(converted) => serde_json::to_vec(&converted).map_err(crate::error::Error::from)?,
+            None => data.to_vec(),
+        };
+        let value = format!(r#"attachment; filename="{}.json""#, id);
+        return Ok(HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .append_header((header::CONTENT_DISPOSITION, value))
+            .body(body));
+    }
+
     let response = state.get_sbom(&id, &token).await?;
     // TODO: should check the content type, but assume JSON for now
     let value = format!(r#"attachment; filename="{}.json""#, id);
@@ -0,0 +1,200 @@
+use super::fetch_sbom;
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::data::SBOM;
+use cyclonedx_bom::models::license::{LicenseChoice, LicenseIdentifier};
+use spog_model::license::{LicenseReport, LicenseSummary, UnknownLicense};
+use std::collections::BTreeMap;
+use tracing::instrument;
+
+/// Get a report of the licenses used by the components of an SBOM.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/{id}/licenses",
+    responses(
+        (status = OK, description = "Processing succeeded", body = LicenseReport),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the SBOM"),
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn get_licenses(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let sbom = fetch_sbom(&state, &access_token, &id).await?;
+    Ok(HttpResponse::Ok().json(license_report(&sbom)))
+}
+
+/// License information of a component, as found in the SBOM
+enum License {
+    /// An SPDX license expression
+    Expression(String),
+    /// A license name, not following the SPDX format
+    Name(String),
+}
+
+fn component_licenses(sbom: &SBOM) -> Vec<(String, Vec<License>)> {
+    match sbom {
+        SBOM::SPDX(spdx) => spdx
+            .package_information
+            .iter()
+            .map(|pi| {
+                let name = pi
+                    .external_reference
+                    .iter()
+                    .find(|er| er.reference_type == "purl")
+                    .map(|er| er.reference_locator.clone())
+                    .unwrap_or_else(|| match &pi.package_version {
+                        Some(version) => format!("{}@{}", pi.package_name, version),
+                        None => pi.package_name.clone(),
+                    });
+
+                // prefer the declared license, falling back to the concluded one
+                let license = [&pi.declared_license, &pi.concluded_license]
+                    .into_iter()
+                    .flatten()
+                    .map(|license| license.to_string())
+                    .find(|license| !is_none(license));
+
+                (name, license.map(License::Expression).into_iter().collect())
+            })
+            .collect(),
+        SBOM::CycloneDX(bom) => {
+            let mut result = Vec::new();
+            let mut todo: Vec<_> = bom.components.iter().flat_map(|c| c.0.iter()).collect();
+            while let Some(component) = todo.pop() {
+                todo.extend(component.components.iter().flat_map(|c| c.0.iter()));
+
+                let name =
+                    component
+                        .purl
+                        .as_ref()
+                        .map(|purl| purl.to_string())
+                        .unwrap_or_else(|| match &component.version {
+                            Some(version) => format!("{}@{}", component.name, version),
+                            None => component.name.to_string(),
+                        });
+
+                let licenses = component
+                    .licenses
+                    .iter()
+                    .flat_map(|l| l.0.iter())
+                    .map(|l| match l {
+                        LicenseChoice::License(l) => match &l.license_identifier {
+                            LicenseIdentifier::SpdxId(id) => License::Expression(id.to_string()),
+                            LicenseIdentifier::Name(name) => License::Name(name.to_string()),
+                        },
+                        LicenseChoice::Expression(expr) => License::Expression(expr.to_string()),
+                    })
+                    .collect();
+
+                result.push((name, licenses));
+            }
+            result
+        }
+    }
+}
+
+/// Check for SPDX values, which express the absence of license information.
+fn is_none(license: &str) -> bool {
+    matches!(license, "" | "NONE" | "NOASSERTION")
+}
+
+/// Resolve license information into the SPDX licenses it references.
+///
+/// Returns `None` if the information could not be resolved.
+fn resolve(license: &License) -> Option<Vec<spdx::LicenseId>> {
+    match license {
+        License::Expression(expr) => {
+            let expr = spdx::Expression::parse_mode(expr, spdx::ParseMode::LAX).ok()?;
+            expr.requirements()
+                .map(|req| match req.req.license {
+                    spdx::LicenseItem::Spdx { id, .. } => Some(id),
+                    spdx::LicenseItem::Other { .. } => None,
+                })
+                .collect()
+        }
+        License::Name(name) => spdx::imprecise_license_id(name).map(|(id, _)| vec![id]),
+    }
+}
+
+fn license_report(sbom: &SBOM) -> LicenseReport {
+    let mut report = LicenseReport::default();
+    let mut licenses = BTreeMap::<&'static str, (spdx::LicenseId, usize)>::new();
+
+    for (component, component_licenses) in component_licenses(sbom) {
+        let component_licenses: Vec<_> = component_licenses
+            .into_iter()
+            .filter(|license| match license {
+                License::Expression(expr) | License::Name(expr) => !is_none(expr),
+            })
+            .collect();
+
+        if component_licenses.is_empty() {
+            report.missing.push(component);
+            continue;
+        }
+
+        let mut ids = Vec::new();
+        for license in component_licenses {
+            match resolve(&license) {
+                Some(resolved) => ids.extend(resolved),
+                None => report.unknown.push(UnknownLicense {
+                    component: component.clone(),
+                    license: match license {
+                        License::Expression(value) | License::Name(value) => value,
+                    },
+                }),
+            }
+        }
+
+        // count every license only once per component
+        ids.sort_unstable_by_key(|id| id.name);
+        ids.dedup_by_key(|id| id.name);
+        for id in ids {
+            licenses.entry(id.name).or_insert((id, 0)).1 += 1;
+        }
+    }
+
+    report.licenses = licenses
+        .into_values()
+        .map(|(id, components)| LicenseSummary {
+            id: id.name.to_string(),
+            name: id.full_name.to_string(),
+            copyleft: id.is_copyleft(),
+            osi_approved: id.is_osi_approved(),
+            components,
+        })
+        .collect();
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let ids = resolve(&License::Expression("MIT OR GPL-3.0-or-later".to_string())).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0].name, "MIT");
+        assert!(!ids[0].is_copyleft());
+        assert!(ids[1].is_copyleft());
+
+        assert!(resolve(&License::Expression("LicenseRef-Proprietary".to_string())).is_none());
+        assert!(resolve(&License::Expression("not a license (".to_string())).is_none());
+    }
+
+    #[test]
+    fn test_is_none() {
+        assert!(is_none("NOASSERTION"));
+        assert!(is_none("NONE"));
+        assert!(!is_none("MIT"));
+    }
+}
@@ -1,14 +1,41 @@
+mod attestations;
+mod by_hash;
+mod by_purl;
+mod delete;
+mod diff;
+mod export;
 mod get;
+mod licenses;
+mod related;
 mod search;
+mod tree;
+mod upload;
 mod vuln;
 
+pub use attestations::*;
+pub use by_hash::*;
+pub use by_purl::*;
+pub use delete::*;
+pub use diff::*;
+pub use export::*;
 pub use get::*;
+pub use licenses::*;
+pub use related::*;
 pub use search::*;
+pub use tree::*;
+pub use upload::*;
 pub use vuln::*;
 
-use actix_web::{web, web::ServiceConfig};
+use crate::app_state::AppState;
+use crate::error::Error;
+use actix_web::{guard, web, web::ServiceConfig};
+use bombastic_model::data::SBOM;
+use bytes::BytesMut;
+use futures::TryStreamExt;
 use std::sync::Arc;
+use tracing::{info_span, Instrument};
 use trustification_auth::authenticator::Authenticator;
+use trustification_auth::client::TokenProvider;
 use trustification_infrastructure::new_auth;
 
 pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
@@ -18,12 +45,95 @@ pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut Se
                 .wrap(new_auth!(auth.clone()))
                 .to(search),
         );
+        config.service(
+            web::resource("/api/v1/sbom/search/export")
+                .wrap(new_auth!(auth.clone()))
+                .to(export),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/search/fields")
+                .wrap(new_auth!(auth.clone()))
+                .to(search_fields),
+        );
         config.service(
             web::resource("/api/v1/sbom/vulnerabilities")
-                .wrap(new_auth!(auth))
+                .wrap(new_auth!(auth.clone()))
                 .to(get_vulnerabilities),
         );
+        config.service(
+            web::resource("/api/v1/sbom/by-purl")
+                .wrap(new_auth!(auth.clone()))
+                .app_data(web::JsonConfig::default().limit(by_purl::PAYLOAD_LIMIT))
+                .route(web::post().to(by_purl)),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/by-hash/{digest}")
+                .wrap(new_auth!(auth.clone()))
+                .to(by_hash),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/diff")
+                .wrap(new_auth!(auth.clone()))
+                .to(diff),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/{id}/attestations")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_attestations),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/{id}/licenses")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_licenses),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/{id}/related")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_related_sboms),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/{id}/tree")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_tree),
+        );
+        config.service(
+            web::resource("/api/v1/sbom/vulnerabilities/summary")
+                .wrap(new_auth!(auth.clone()))
+                .to(get_vulnerabilities_summary),
+        );
+        config.service(
+            web::resource("/api/v1/sbom")
+                .guard(guard::Any(guard::Post()).or(guard::Delete()))
+                .wrap(new_auth!(auth))
+                .app_data(web::PayloadConfig::new(upload::PAYLOAD_LIMIT))
+                .route(web::post().to(upload))
+                .route(web::delete().to(delete)),
+        );
         // the get operation doesn't get the authenticator added, as we check this using the access_token query parameter
         config.service(web::resource("/api/v1/sbom").to(get));
     }
 }
+
+/// Download the raw data of an SBOM.
+pub(crate) async fn download_sbom(
+    state: &AppState,
+    access_token: &dyn TokenProvider,
+    id: &str,
+) -> Result<BytesMut, Error> {
+    Ok(state
+        .get_sbom(id, access_token)
+        .await?
+        .try_collect()
+        .instrument(info_span!("download SBOM data"))
+        .await?)
+}
+
+/// Download and parse an SBOM.
+pub(crate) async fn fetch_sbom(state: &AppState, access_token: &dyn TokenProvider, id: &str) -> Result<SBOM, Error> {
+    let sbom = download_sbom(state, access_token, id).await?;
+    parse_sbom(&sbom)
+}
+
+pub(crate) fn parse_sbom(data: &[u8]) -> Result<SBOM, Error> {
+    SBOM::parse(data).map_err(|err| Error::Generic(format!("Unable to parse SBOM: {err}")))
+}
@@ -0,0 +1,176 @@
+use super::{fetch_sbom, into_summary};
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::{data::SBOM, graph::Graph};
+use futures::stream::iter;
+use futures::StreamExt;
+use spog_model::related::RelatedSbom;
+use std::collections::BTreeSet;
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use utoipa::IntoParams;
+
+/// maximum number of package URLs used to search for candidates
+const MAX_QUERY_PURLS: usize = 100;
+/// number of parallel candidate downloads
+const PARALLEL_FETCH: usize = 4;
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct RelatedParams {
+    /// Maximum number of related SBOMs to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Minimum share of common package URLs for SBOMs of other products (between 0 and 1)
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f64,
+}
+
+const fn default_limit() -> usize {
+    10
+}
+
+const fn default_min_similarity() -> f64 {
+    0.5
+}
+
+/// Find SBOMs related to an SBOM.
+///
+/// Candidates are SBOMs of the same product (CPE), or sharing package URLs, as ranked by the index. For each
+/// candidate, the share of common package URLs is evaluated. Candidates of other products must have at least the
+/// requested similarity.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/{id}/related",
+    responses(
+        (status = OK, description = "Processing succeeded", body = Vec<RelatedSbom>),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the SBOM"),
+        RelatedParams,
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn get_related_sboms(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    params: web::Query<RelatedParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let sbom = fetch_sbom(&state, &access_token, &id).await?;
+    let purls = purls(&sbom);
+
+    let cpe = state
+        .search_sbom(&format!(r#"id:"{id}""#), 0, 1, SearchOptions::default(), &access_token)
+        .await?
+        .result
+        .into_iter()
+        .next()
+        .and_then(|hit| hit.document.cpe);
+
+    let terms: Vec<_> = cpe
+        .iter()
+        .map(|cpe| format!(r#"package:"{cpe}""#))
+        .chain(
+            purls
+                .iter()
+                .take(MAX_QUERY_PURLS)
+                .map(|purl| format!(r#"dependency:"{purl}""#)),
+        )
+        .collect();
+
+    if terms.is_empty() {
+        return Ok(HttpResponse::Ok().json(Vec::<RelatedSbom>::new()));
+    }
+
+    // fetch some more, as the SBOM itself is part of the result, and candidates may get filtered out
+    let candidates = state
+        .search_sbom(
+            &terms.join(" OR "),
+            0,
+            params.limit * 2 + 1,
+            SearchOptions::default(),
+            &access_token,
+        )
+        .await?
+        .result
+        .into_iter()
+        .filter(|hit| hit.document.id != *id)
+        .map(into_summary);
+
+    let (state, access_token, purls, cpe) = (&state, &access_token, &purls, &cpe);
+    let mut result: Vec<RelatedSbom> = iter(candidates)
+        .map(|candidate| async move {
+            let other = match fetch_sbom(state, access_token, &candidate.id).await {
+                Ok(other) => other,
+                Err(err) => {
+                    log::info!("Failed to fetch candidate SBOM {}: {err}", candidate.id);
+                    return None;
+                }
+            };
+            Some(RelatedSbom {
+                same_product: cpe.is_some() && candidate.cpe == *cpe,
+                similarity: similarity(purls, &self::purls(&other)),
+                sbom: candidate,
+            })
+        })
+        .buffer_unordered(PARALLEL_FETCH)
+        .filter_map(|related| async move { related })
+        .filter(|related| {
+            let keep = related.same_product || related.similarity >= params.min_similarity;
+            async move { keep }
+        })
+        .collect()
+        .await;
+
+    result.sort_by(|a, b| {
+        b.same_product
+            .cmp(&a.same_product)
+            .then_with(|| b.similarity.total_cmp(&a.similarity))
+            .then_with(|| a.sbom.id.cmp(&b.sbom.id))
+    });
+    result.truncate(params.limit);
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// The package URLs of all components of an SBOM.
+fn purls(sbom: &SBOM) -> BTreeSet<String> {
+    Graph::from(sbom)
+        .components
+        .into_values()
+        .filter_map(|component| component.purl)
+        .collect()
+}
+
+/// The share of package URLs of `a` which are also part of `b`.
+fn similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    match a.len() {
+        0 => 0.0,
+        len => a.intersection(b).count() as f64 / len as f64,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        let set = |purls: &[&str]| purls.iter().map(|p| p.to_string()).collect::<BTreeSet<_>>();
+
+        let a = set(&[
+            "pkg:rpm/redhat/a@1",
+            "pkg:rpm/redhat/b@1",
+            "pkg:rpm/redhat/c@1",
+            "pkg:rpm/redhat/d@1",
+        ]);
+        let b = set(&["pkg:rpm/redhat/a@1", "pkg:rpm/redhat/b@1", "pkg:rpm/redhat/e@1"]);
+
+        assert_eq!(similarity(&a, &b), 0.5);
+        assert_eq!(similarity(&b, &a), 2.0 / 3.0);
+        assert_eq!(similarity(&a, &a), 1.0);
+        assert_eq!(similarity(&set(&[]), &a), 0.0);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::app_state::AppState;
 use crate::search;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::search::SearchHit;
+use futures::stream::iter;
+use futures::StreamExt;
 use spog_model::search::SbomSummary;
 use tracing::instrument;
-use trustification_api::search::{SearchOptions, SearchResult};
+use trustification_api::search::{SearchField, SearchOptions, SearchResult};
 use trustification_auth::client::TokenProvider;
 
 #[utoipa::path(
@@ -16,17 +19,43 @@ use trustification_auth::client::TokenProvider;
     params(
         search::QueryParams,
         SearchOptions,
+        search::StreamParams,
     )
 )]
-#[instrument(skip(state, access_token), err)]
+#[instrument(skip(state, req, access_token), err)]
 pub async fn search(
     state: web::Data<AppState>,
+    req: HttpRequest,
     params: web::Query<search::QueryParams>,
     options: web::Query<SearchOptions>,
+    stream: web::Query<search::StreamParams>,
     access_token: Option<BearerAuth>,
 ) -> actix_web::Result<HttpResponse> {
     let params = params.into_inner();
     log::trace!("Querying SBOM using {}", params.q);
+
+    if stream.enabled(&req) {
+        let options = SearchOptions {
+            facets: Vec::new(),
+            cursor: None,
+            ..options.into_inner()
+        };
+        return Ok(search::ndjson_response(search::ndjson(
+            params.offset,
+            params.limit,
+            move |offset, limit| {
+                let (state, q, options, access_token) =
+                    (state.clone(), params.q.clone(), options.clone(), access_token.clone());
+                async move {
+                    let data = state.search_sbom(&q, offset, limit, options, &access_token).await?;
+                    let mut result: Vec<SbomSummary> = data.result.into_iter().map(into_summary).collect();
+                    search_advisories(state, &mut result, &access_token).await;
+                    Ok(result)
+                }
+            },
+        )));
+    }
+
     let data = state
         .search_sbom(
             &params.q,
@@ -36,34 +65,13 @@ pub async fn search(
             &access_token,
         )
         .await?;
-    let mut m: Vec<SbomSummary> = Vec::with_capacity(data.result.len());
-    for item in data.result {
-        let metadata = item.metadata.unwrap_or_default();
-        let item = item.document;
-        m.push(SbomSummary {
-            id: item.id.clone(),
-            purl: item.purl,
-            name: item.name,
-            cpe: item.cpe,
-            version: item.version,
-            sha256: item.sha256,
-            license: item.license,
-            snippet: item.snippet,
-            classifier: item.classifier,
-            supplier: item.supplier.trim_start_matches("Organization: ").to_string(),
-            href: format!("/api/v1/sbom?id={}", item.id),
-            description: item.description,
-            dependencies: item.dependencies,
-            vulnerabilities: vec![],
-            advisories: None,
-            created: item.created,
-            metadata,
-        });
-    }
+    let m: Vec<SbomSummary> = data.result.into_iter().map(into_summary).collect();
 
     let mut result = SearchResult {
         total: Some(data.total),
         result: m,
+        facets: data.facets,
+        cursor: data.cursor,
     };
 
     // TODO: Use guac to lookup advisories for each sbom!
@@ -71,26 +79,257 @@ pub async fn search(
     Ok(HttpResponse::Ok().json(result))
 }
 
+/// List the fields SBOM search queries can be qualified with, including their type and an example query.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/search/fields",
+    responses(
+        (status = OK, description = "Search fields", body = Vec<SearchField>),
+    ),
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn search_fields(
+    state: web::Data<AppState>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let fields = state.search_sbom_fields(&access_token).await?;
+    Ok(HttpResponse::Ok().json(fields))
+}
+
+/// Convert a search hit from bombastic into a summary.
+pub(crate) fn into_summary(item: SearchHit) -> SbomSummary {
+    let mut metadata = item.metadata.unwrap_or_default();
+    let item = item.document;
+    if !item.labels.is_empty() {
+        if !metadata.is_object() {
+            metadata = serde_json::Value::Object(Default::default());
+        }
+        metadata["labels"] = serde_json::to_value(&item.labels).unwrap_or_default();
+    }
+    SbomSummary {
+        id: item.id.clone(),
+        purl: item.purl,
+        name: item.name,
+        cpe: item.cpe,
+        version: item.version,
+        sha256: item.sha256,
+        license: item.license,
+        snippet: item.snippet,
+        classifier: item.classifier,
+        supplier: item.supplier.trim_start_matches("Organization: ").to_string(),
+        href: format!("/api/v1/sbom?id={}", item.id),
+        description: item.description,
+        dependencies: item.dependencies,
+        vulnerabilities: vec![],
+        advisories: None,
+        created: item.created,
+        quality: item.quality,
+        metadata,
+    }
+}
+
 #[instrument(skip_all)]
-async fn search_advisories(state: web::Data<AppState>, sboms: &mut Vec<SbomSummary>, provider: &dyn TokenProvider) {
-    for sbom in sboms {
-        if let Some(q) = sbom.advisories_query() {
-            if let Ok(result) = state
-                .search_vex(
-                    &q,
-                    0,
-                    100000,
-                    SearchOptions {
-                        explain: false,
-                        metadata: false,
-                        summaries: false,
-                    },
-                    provider,
+pub(crate) async fn search_advisories(
+    state: web::Data<AppState>,
+    sboms: &mut [SbomSummary],
+    provider: &dyn TokenProvider,
+) {
+    let timeout = state.advisory_search_timeout;
+
+    let mut queries = Vec::new();
+    for (idx, sbom) in sboms.iter_mut().enumerate() {
+        let Some(q) = sbom.advisories_query() else {
+            continue;
+        };
+        match state.advisory_cache.get(&q) {
+            Some(count) => sbom.advisories = Some(count),
+            None => queries.push((idx, q)),
+        }
+    }
+
+    let results = iter(queries)
+        .map(|(idx, q)| {
+            let state = &state;
+            async move {
+                let result = tokio::time::timeout(
+                    timeout,
+                    state.search_vex(
+                        &q,
+                        0,
+                        100000,
+                        SearchOptions {
+                            explain: false,
+                            metadata: false,
+                            summaries: false,
+                            ..Default::default()
+                        },
+                        provider,
+                    ),
                 )
-                .await
-            {
-                sbom.advisories = Some(result.total as u64);
+                .await;
+                (idx, q, result)
+            }
+        })
+        .buffer_unordered(state.advisory_search_concurrency)
+        .collect::<Vec<_>>()
+        .await;
+
+    for (idx, q, result) in results {
+        let sbom = &mut sboms[idx];
+        match result {
+            Ok(Ok(result)) => {
+                let count = result.total as u64;
+                state.advisory_cache.insert(q, count);
+                sbom.advisories = Some(count);
             }
+            Ok(Err(err)) => log::info!("Failed to search advisories for SBOM {}: {err}", sbom.id),
+            Err(_) => log::info!("Timeout searching advisories for SBOM {}", sbom.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cache::{AdvisoryCache, TimedCache};
+    use actix_web::{App, HttpServer};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use trustification_auth::client::NoTokenProvider;
+
+    /// Advisory searches received by the upstream.
+    #[derive(Default)]
+    struct Searches {
+        total: AtomicUsize,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    /// Search advisories, finding three for every package. Searches for "slow" packages take longer than the timeout
+    /// of the test, searches for "broken" packages fail.
+    async fn search_vex(searches: web::Data<Searches>, params: web::Query<HashMap<String, String>>) -> HttpResponse {
+        searches.total.fetch_add(1, Ordering::SeqCst);
+        let running = searches.running.fetch_add(1, Ordering::SeqCst) + 1;
+        searches.max_running.fetch_max(running, Ordering::SeqCst);
+
+        let q = &params["q"];
+        let delay = match q.contains("slow") {
+            true => Duration::from_secs(5),
+            false => Duration::from_millis(100),
+        };
+        actix_web::rt::time::sleep(delay).await;
+        searches.running.fetch_sub(1, Ordering::SeqCst);
+
+        match q.contains("broken") {
+            true => HttpResponse::InternalServerError().finish(),
+            false => HttpResponse::Ok().json(json!({ "total": 3, "result": [] })),
         }
     }
+
+    fn upstream(searches: Arc<Searches>) -> reqwest::Url {
+        let listener = std::net::TcpListener::bind("localhost:0").unwrap();
+        let url = reqwest::Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::from(searches.clone()))
+                .route("/api/v1/vex/search", web::get().to(search_vex))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+        url
+    }
+
+    fn state(url: reqwest::Url) -> web::Data<AppState> {
+        web::Data::new(AppState {
+            client: reqwest::Client::new(),
+            provider: Arc::new(NoTokenProvider),
+            bombastic: url.clone(),
+            vexination: url.clone(),
+            exhort: url,
+            advisory_search_concurrency: 2,
+            advisory_search_timeout: Duration::from_millis(500),
+            advisory_cache: AdvisoryCache::new(Duration::from_secs(60)),
+            dashboard_cache: TimedCache::new(Duration::from_secs(60)),
+            attestation_keys: Vec::new(),
+            attestation_registries: Vec::new(),
+            advisory_default_sort: None,
+            vex_namespace: None,
+        })
+    }
+
+    fn sbom(name: &str) -> SbomSummary {
+        SbomSummary {
+            id: name.to_string(),
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            purl: Some(format!("pkg:rpm/redhat/{name}@1.0")),
+            cpe: None,
+            sha256: String::new(),
+            license: String::new(),
+            snippet: String::new(),
+            classifier: String::new(),
+            description: String::new(),
+            supplier: String::new(),
+            dependencies: 0,
+            href: format!("/api/v1/sbom?id={name}"),
+            advisories: None,
+            created: time::OffsetDateTime::UNIX_EPOCH,
+            vulnerabilities: Vec::new(),
+            quality: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_search_advisories() {
+        let searches = Arc::new(Searches::default());
+        let state = state(upstream(searches.clone()));
+
+        let cached = sbom("cached");
+        state.advisory_cache.insert(cached.advisories_query().unwrap(), 7);
+        let mut unidentified = sbom("unidentified");
+        unidentified.purl = None;
+        // the slow search comes last, as the upstream keeps running it after the timeout
+        let mut sboms = vec![
+            sbom("a"),
+            cached,
+            unidentified,
+            sbom("broken"),
+            sbom("b"),
+            sbom("c"),
+            sbom("slow"),
+        ];
+
+        search_advisories(state.clone(), &mut sboms, &NoTokenProvider).await;
+
+        let advisories: Vec<_> = sboms.iter().map(|sbom| (sbom.id.as_str(), sbom.advisories)).collect();
+        assert_eq!(
+            advisories,
+            vec![
+                ("a", Some(3)),
+                ("cached", Some(7)),
+                ("unidentified", None),
+                ("broken", None),
+                ("b", Some(3)),
+                ("c", Some(3)),
+                ("slow", None),
+            ]
+        );
+
+        // cached and unidentified SBOMs aren't searched, the others are searched with a bounded concurrency
+        assert_eq!(searches.total.load(Ordering::SeqCst), 5);
+        assert_eq!(searches.max_running.load(Ordering::SeqCst), 2);
+
+        // only successful searches are cached
+        let query = |name: &str| sbom(name).advisories_query().unwrap();
+        assert_eq!(state.advisory_cache.get(&query("a")), Some(3));
+        assert_eq!(state.advisory_cache.get(&query("broken")), None);
+        assert_eq!(state.advisory_cache.get(&query("slow")), None);
+    }
 }
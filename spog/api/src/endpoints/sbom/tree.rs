@@ -0,0 +1,116 @@
+use super::fetch_sbom;
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::graph::{Component, Graph};
+use spog_model::tree::{DependencyNode, SbomTree};
+use std::collections::HashSet;
+use tracing::instrument;
+
+/// Get the dependency tree of an SBOM.
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/{id}/tree",
+    responses(
+        (status = OK, description = "Processing succeeded", body = SbomTree),
+        (status = NOT_FOUND, description = "SBOM was not found")
+    ),
+    params(
+        ("id" = String, Path, description = "ID of the SBOM"),
+    )
+)]
+#[instrument(skip(state, access_token), err)]
+pub async fn get_tree(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let sbom = fetch_sbom(&state, &access_token, &id).await?;
+    Ok(HttpResponse::Ok().json(into_tree(Graph::from(&sbom))))
+}
+
+/// Convert the graph into a tree, starting with the roots.
+///
+/// Components reachable through multiple paths are expanded on their first occurrence only, which also takes care
+/// of cycles.
+fn into_tree(graph: Graph) -> SbomTree {
+    let mut seen = HashSet::new();
+    let roots = graph
+        .roots
+        .iter()
+        .map(|root| node(&graph, root, 0, &mut seen))
+        .collect();
+    SbomTree { roots }
+}
+
+fn node<'a>(graph: &'a Graph, id: &'a str, depth: usize, seen: &mut HashSet<&'a str>) -> DependencyNode {
+    let component = graph.components.get(id).cloned().unwrap_or_else(|| Component {
+        name: id.to_string(),
+        ..Default::default()
+    });
+
+    let repeated = !seen.insert(id);
+    let dependencies = match repeated {
+        true => vec![],
+        false => graph
+            .edges
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(|dep| node(graph, dep, depth + 1, seen))
+            .collect(),
+    };
+
+    DependencyNode {
+        name: component.name,
+        version: component.version,
+        purl: component.purl,
+        direct: depth == 1,
+        repeated,
+        dependencies,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_into_tree() {
+        let mut graph = Graph {
+            roots: vec!["a".to_string()],
+            ..Default::default()
+        };
+        graph.add_edge("a", "b");
+        graph.add_edge("a", "c");
+        graph.add_edge("b", "c");
+        // cycle
+        graph.add_edge("c", "a");
+
+        let tree = into_tree(graph);
+        assert_eq!(tree.roots.len(), 1);
+
+        let a = &tree.roots[0];
+        assert_eq!(a.name, "a");
+        assert!(!a.direct);
+        assert_eq!(a.dependencies.len(), 2);
+
+        let b = &a.dependencies[0];
+        assert_eq!(b.name, "b");
+        assert!(b.direct);
+        assert!(!b.repeated);
+
+        // transitive, expanded first through "b"
+        let c = &b.dependencies[0];
+        assert_eq!(c.name, "c");
+        assert!(!c.direct);
+        assert_eq!(c.dependencies.len(), 1);
+        assert!(c.dependencies[0].repeated);
+
+        // direct, but already expanded
+        let c = &a.dependencies[1];
+        assert!(c.direct);
+        assert!(c.repeated);
+        assert!(c.dependencies.is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::app_state::AppState;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bytes::Bytes;
+use serde_json::Value;
+use spog_model::validation::{Diagnostic, ValidationReport};
+use tracing::instrument;
+
+/// maximum size of an uploaded SBOM
+pub(crate) const PAYLOAD_LIMIT: usize = 128 * 1024 * 1024;
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct UploadParams {
+    /// Identifier to store the SBOM with, defaults to the UUID of the CycloneDX serial number
+    pub id: Option<String>,
+}
+
+/// Upload an SBOM, validating it before it gets stored.
+///
+/// The SBOM must be a CycloneDX or SPDX JSON document. Documents failing the validation are rejected, the response
+/// lists the problems found in the document.
+#[utoipa::path(
+    post,
+    path = "/api/v1/sbom",
+    request_body(content = Value, description = "The SBOM to be uploaded", content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "SBOM was validated and uploaded", body = ValidationReport),
+        (status = BAD_REQUEST, description = "SBOM failed validation", body = ValidationReport),
+    ),
+    params(UploadParams)
+)]
+#[instrument(skip(state, data, access_token), fields(size = data.len()), err)]
+pub async fn upload(
+    state: web::Data<AppState>,
+    params: web::Query<UploadParams>,
+    data: Bytes,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let mut report = validate(&data);
+    if let Some(id) = params.into_inner().id {
+        report.id = Some(id);
+    }
+
+    if report.has_errors() {
+        return Ok(HttpResponse::BadRequest().json(report));
+    }
+
+    let Some(id) = &report.id else {
+        report.diagnostics.push(Diagnostic::error(
+            "The document has no serial number, an identifier must be provided using the 'id' parameter",
+        ));
+        return Ok(HttpResponse::BadRequest().json(report));
+    };
+
+    state.post_sbom(id, &access_token, data).await?;
+    Ok(HttpResponse::Created().json(report))
+}
+
+/// Validate an SBOM document.
+///
+/// For CycloneDX documents having a valid serial number, the report's identifier is set to its UUID.
+pub(crate) fn validate(data: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let value: Value = match serde_json::from_slice(data) {
+        Ok(value) => value,
+        Err(err) => {
+            report.diagnostics.push(json_error("Malformed JSON", &err));
+            return report;
+        }
+    };
+
+    if value.get("bomFormat").and_then(Value::as_str) == Some("CycloneDX") {
+        report.format = Some("cyclonedx".to_string());
+        validate_cyclonedx(data, &value, &mut report);
+    } else if value.get("spdxVersion").is_some() || bombastic_model::spdx3::is_spdx3(&value) {
+        report.format = Some("spdx".to_string());
+        validate_spdx(data, &mut report);
+    } else {
+        report.diagnostics.push(Diagnostic::error(
+            "Unknown document format, expected a CycloneDX or SPDX JSON document",
+        ));
+    }
+
+    report
+}
+
+fn validate_cyclonedx(data: &[u8], value: &Value, report: &mut ValidationReport) {
+    match value.get("serialNumber") {
+        None => report
+            .diagnostics
+            .push(Diagnostic::warning("The document has no serial number")),
+        Some(Value::String(serial)) => match serial.strip_prefix("urn:uuid:").filter(|uuid| is_uuid(uuid)) {
+            Some(uuid) => report.id = Some(uuid.to_string()),
+            None => report.diagnostics.push(Diagnostic::error(format!(
+                "Invalid serial number '{serial}', expected a UUID URN (urn:uuid:<uuid>)"
+            ))),
+        },
+        Some(_) => report
+            .diagnostics
+            .push(Diagnostic::error("The serial number must be a string")),
+    }
+
+    if let Err(err) = bombastic_model::cyclonedx::CycloneDx::parse(data) {
+        report
+            .diagnostics
+            .push(Diagnostic::error(format!("Invalid CycloneDX document: {err}")));
+    }
+}
+
+fn validate_spdx(data: &[u8], report: &mut ValidationReport) {
+    match bombastic_model::spdx3::parse(data) {
+        Ok(spdx) => {
+            if spdx.document_creation_information.spdx_document_namespace.is_empty() {
+                report
+                    .diagnostics
+                    .push(Diagnostic::warning("The document has no document namespace"));
+            }
+        }
+        Err(err) => report.diagnostics.push(json_error("Invalid SPDX document", &err)),
+    }
+}
+
+fn json_error(context: &str, err: &serde_json::Error) -> Diagnostic {
+    let diagnostic = Diagnostic::error(format!("{context}: {err}"));
+    match err.line() {
+        // no location information
+        0 => diagnostic,
+        line => diagnostic.at(line, err.column()),
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(n, c)| match n {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spog_model::validation::Severity;
+
+    #[test]
+    fn test_valid_cyclonedx() {
+        let report = validate(include_bytes!("../../../../example-data/java-sbom.json"));
+        assert!(!report.has_errors(), "{report:?}");
+        assert_eq!(report.format.as_deref(), Some("cyclonedx"));
+        assert_eq!(report.id.as_deref(), Some("41fbfe3d-1e7d-4144-9f4b-4244d6969219"));
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let report = validate(b"{\n  \"bomFormat\": \"CycloneDX\",\n}");
+        assert!(report.has_errors());
+        assert_eq!(report.format, None);
+        assert_eq!(report.diagnostics[0].line, Some(3));
+    }
+
+    #[test]
+    fn test_invalid_serial_number() {
+        let report =
+            validate(br#"{"bomFormat": "CycloneDX", "specVersion": "1.4", "serialNumber": "foo", "version": 1}"#);
+        assert!(report.has_errors());
+        assert_eq!(report.id, None);
+        assert!(report.diagnostics[0].message.contains("serial number"));
+    }
+
+    #[test]
+    fn test_missing_serial_number() {
+        let report = validate(br#"{"bomFormat": "CycloneDX", "specVersion": "1.4", "version": 1}"#);
+        assert!(!report.has_errors(), "{report:?}");
+        assert_eq!(report.diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let report = validate(br#"{"foo": "bar"}"#);
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn test_is_uuid() {
+        assert!(is_uuid("41fbfe3d-1e7d-4144-9f4b-4244d6969219"));
+        assert!(!is_uuid("41fbfe3d1e7d41449f4b4244d6969219"));
+        assert!(!is_uuid("41fbfe3d-1e7d-4144-9f4b-4244d696921x"));
+    }
+}
@@ -8,7 +8,7 @@ use futures::{stream, StreamExt, TryStreamExt};
 use guac::client::intrinsic::vuln_metadata::VulnerabilityScoreType;
 use packageurl::PackageUrl;
 use spog_model::csaf::has_purl;
-use spog_model::prelude::{Backtrace, Remediation};
+use spog_model::prelude::{Backtrace, Remediation, VexStatus};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::rc::Rc;
 use std::str::FromStr;
@@ -31,6 +31,8 @@ pub struct AnalyzeOutcome {
     pub cve_to_purl: BTreeMap<String, BTreeMap<String, Vec<Remediation>>>,
     // PURL to backtrace
     pub purl_to_backtrace: BTreeMap<String, BTreeSet<Backtrace>>,
+    // CVE to VEX status
+    pub cve_to_status: BTreeMap<String, VexStatus>,
 }
 
 /// Analyze by purls
@@ -77,6 +79,17 @@ pub async fn analyze_spdx(
 
     let vex = collect_vex(state, token, cve_to_purl.keys()).await?;
 
+    // evaluate the VEX status
+
+    let cve_to_status = info_span!("scrape_status").in_scope(|| {
+        cve_to_purl
+            .iter()
+            .filter_map(|(cve, purls)| {
+                scrape_status(cve, purls.keys().map(String::as_str), &vex).map(|status| (cve.clone(), status))
+            })
+            .collect()
+    });
+
     // fill in the remediations
 
     let cve_to_purl = info_span!("scrape_remediations").in_scope(|| {
@@ -108,9 +121,73 @@ pub async fn analyze_spdx(
     Ok(AnalyzeOutcome {
         cve_to_purl,
         purl_to_backtrace,
+        cve_to_status,
     })
 }
 
+/// from a set of relevant VEXes, evaluate the status of a vulnerability for a set of PURLs
+///
+/// The most relevant status of all PURLs is returned, "affected" being the most relevant one. If any PURL is not
+/// covered by a VEX statement, the vulnerability can't be considered "not affected" or "fixed" for all of them, so
+/// no status is returned in that case.
+fn scrape_status<'a>(
+    id: &str,
+    purls: impl IntoIterator<Item = &'a str>,
+    vex: &HashMap<String, Vec<Rc<Csaf>>>,
+) -> Option<VexStatus> {
+    let mut result = None;
+    let mut unknown = false;
+
+    for purl in purls {
+        match purl_status(id, purl, vex) {
+            Some(status) => result = result.max(Some(status)),
+            None => unknown = true,
+        }
+    }
+
+    match unknown && result <= Some(VexStatus::Fixed) {
+        true => None,
+        false => result,
+    }
+}
+
+/// find the most relevant status of a vulnerability for a PURL
+fn purl_status(id: &str, purl: &str, vex: &HashMap<String, Vec<Rc<Csaf>>>) -> Option<VexStatus> {
+    let mut result = None;
+
+    for vex in vex.get(id).iter().flat_map(|v| *v) {
+        if vex.document.category != Category::Vex {
+            continue;
+        }
+
+        for status in vex
+            .vulnerabilities
+            .iter()
+            .flatten()
+            .filter(|v| v.cve.as_deref() == Some(id))
+            .filter_map(|v| v.product_status.as_ref())
+        {
+            let statuses = [
+                (&status.known_not_affected, VexStatus::NotAffected),
+                (&status.fixed, VexStatus::Fixed),
+                (&status.first_fixed, VexStatus::Fixed),
+                (&status.under_investigation, VexStatus::UnderInvestigation),
+                (&status.known_affected, VexStatus::Affected),
+                (&status.first_affected, VexStatus::Affected),
+                (&status.last_affected, VexStatus::Affected),
+            ];
+
+            for (ids, status) in statuses {
+                if has_purl(vex, ids, purl) {
+                    result = result.max(Some(status));
+                }
+            }
+        }
+    }
+
+    result
+}
+
 /// from a set of relevant VEXes, fetch the matching remediations for this PURL
 fn scrape_remediations(id: &str, purl: &str, vex: &HashMap<String, Vec<Rc<Csaf>>>) -> Vec<Remediation> {
     let mut result = vec![];
@@ -167,4 +244,23 @@ mod test {
             details: "Before applying this update, make sure all previously released errata\nrelevant to your system have been applied.\n\nFor details on how to apply this update, refer to:\n\nhttps://access.redhat.com/articles/11258".to_string()
         }]);
     }
+
+    #[test]
+    fn test_scrape_status() {
+        let csaf = include_bytes!("../../../../../example-data/cve-2023-22998.json");
+        let csaf: Csaf = serde_json::from_slice(csaf).unwrap();
+
+        let mut vex = HashMap::new();
+        vex.insert("CVE-2023-22998".to_string(), vec![Rc::new(csaf)]);
+
+        let fixed = "pkg:rpm/redhat/kernel-rt-modules-extra@5.14.0-284.11.1.rt14.296.el9_2?arch=x86_64";
+        assert_eq!(scrape_status("CVE-2023-22998", [fixed], &vex), Some(VexStatus::Fixed));
+
+        // not covered by any statement
+        assert_eq!(
+            scrape_status("CVE-2023-22998", [fixed, "pkg:rpm/redhat/foo@1.0"], &vex),
+            None
+        );
+        assert_eq!(scrape_status("CVE-2023-22998", Vec::<&str>::new(), &vex), None);
+    }
 }
@@ -1,8 +1,11 @@
 mod analyze;
 mod backtrace;
+mod summary;
 mod vex;
 
-use crate::app_state::{AppState, ResponseError};
+pub use summary::*;
+
+use crate::app_state::AppState;
 use crate::endpoints::sbom::vuln::analyze::AnalyzeOutcome;
 use crate::error::Error;
 use crate::service::{guac::GuacService, v11y::V11yService};
@@ -19,7 +22,7 @@ use serde_json::Value;
 use spdx_rs::models::{PackageInformation, SPDX};
 use spog_model::{
     prelude::{SbomReport, SummaryEntry},
-    vuln::{SbomReportVulnerability, SourceDetails},
+    vuln::{SbomReportVulnerability, SourceDetails, VexStatus},
 };
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
@@ -29,6 +32,10 @@ use tracing::{info_span, instrument, Instrument};
 use trustification_auth::client::TokenProvider;
 use trustification_common::error::ErrorInformation;
 use utoipa::IntoParams;
+use v11y_model::batch::MAX_BATCH_SIZE;
+use v11y_model::cve_record;
+use v11y_model::cvss4::Cvss4;
+use v11y_model::exploit::Exploit;
 
 /// chunk size for finding VEX by CVE IDs
 const SEARCH_CHUNK_SIZE: usize = 10;
@@ -41,6 +48,9 @@ pub struct GetParams {
     pub id: String,
     pub offset: Option<i64>,
     pub limit: Option<i64>,
+    /// Hide vulnerabilities which VEX documents state the SBOM is not affected by
+    #[serde(default)]
+    pub hide_not_affected: bool,
 }
 
 #[utoipa::path(
@@ -60,7 +70,7 @@ pub async fn get_vulnerabilities(
     params: web::Query<GetParams>,
     access_token: Option<BearerAuth>,
 ) -> actix_web::Result<HttpResponse> {
-    if let Some(result) = process_get_vulnerabilities(
+    if let Some(mut result) = process_get_vulnerabilities(
         &state,
         &v11y,
         &guac,
@@ -71,6 +81,12 @@ pub async fn get_vulnerabilities(
     )
     .await?
     {
+        if params.hide_not_affected {
+            result
+                .details
+                .retain(|vuln| vuln.status != Some(VexStatus::NotAffected));
+            result.summary = report_summary(&result.details);
+        }
         Ok(HttpResponse::Ok().json(result))
     } else {
         Ok(HttpResponse::NotFound().json(ErrorInformation {
@@ -82,7 +98,7 @@ pub async fn get_vulnerabilities(
 }
 
 #[instrument(skip(state, guac, v11y, access_token), err)]
-async fn process_get_vulnerabilities(
+pub(crate) async fn process_get_vulnerabilities(
     state: &AppState,
     v11y: &V11yService,
     guac: &GuacService,
@@ -100,7 +116,7 @@ async fn process_get_vulnerabilities(
         .await?;
 
     let sbom = SBOM::parse(&sbom).map_err(|err| Error::Generic(format!("Unable to parse SBOM: {err}")))?;
-    let (name, version, created, analyze, backtraces) = match sbom {
+    let (name, version, created, analyze, backtraces, status) = match sbom {
         SBOM::SPDX(spdx) => {
             // get the main packages
             let main = find_main(&spdx);
@@ -108,6 +124,7 @@ async fn process_get_vulnerabilities(
             let AnalyzeOutcome {
                 cve_to_purl,
                 purl_to_backtrace,
+                cve_to_status,
             } = analyze_spdx(
                 state,
                 guac,
@@ -131,7 +148,7 @@ async fn process_get_vulnerabilities(
             )
             .ok();
 
-            (name, version, created, cve_to_purl, purl_to_backtrace)
+            (name, version, created, cve_to_purl, purl_to_backtrace, cve_to_status)
         }
         SBOM::CycloneDX(cyclone) => {
             let name = cyclone
@@ -164,55 +181,65 @@ async fn process_get_vulnerabilities(
             let AnalyzeOutcome {
                 cve_to_purl,
                 purl_to_backtrace,
+                cve_to_status,
             } = analyze_spdx(state, guac, access_token, &sbom_id, offset, limit).await?;
 
-            (name, version, created, cve_to_purl, purl_to_backtrace)
+            (name, version, created, cve_to_purl, purl_to_backtrace, cve_to_status)
         }
     };
 
-    // fetch CVE details
+    // fetch CVE details, in batches
+
+    let ids: Vec<_> = analyze.keys().cloned().collect();
+    let mut cves = BTreeMap::new();
+    for chunk in ids.chunks(MAX_BATCH_SIZE) {
+        cves.append(&mut v11y.fetch_cves(chunk).await?.cves);
+    }
+    // CVEs not known to v11y are skipped, the batch has them by their uppercase id
+    let records: Vec<_> = analyze
+        .into_iter()
+        .filter_map(|(id, affected_packages)| {
+            let record = cves.remove(&id.to_uppercase())?;
+            Some((id, affected_packages, record))
+        })
+        .collect();
 
-    let details = iter(analyze)
-        .map(|(id, affected_packages)| async move {
+    let status = &status;
+    let details = iter(records)
+        .map(|(id, affected_packages, mut record)| async move {
             // FIXME: need to provide packages to entry
-            let cve: Cve = match v11y.fetch_cve(&id).await?.or_status_error_opt().await? {
-                Some(cve) => cve.json().await?,
-                None => return Ok(None),
-            };
-            let score = get_score(&cve);
+            let cve: Cve = cve_record::from_value(&mut record)?;
+            // the CNA container is looked up in its JSON form, for the parts not covered by the CVE record model
+            let cna = &record["containers"]["cna"];
+            let score = get_score(cna);
+            // known exploited vulnerabilities are to be prioritized, but the report doesn't depend on them
+            let known_exploited = v11y.fetch_known_exploited(&id).await.unwrap_or_else(|err| {
+                log::warn!("Failed to look up {id} in the known exploited vulnerabilities catalog: {err}");
+                None
+            });
 
             let mut sources = HashMap::new();
             sources.insert("mitre".to_string(), SourceDetails { score });
 
-            Ok(Some(SbomReportVulnerability {
+            Ok::<_, Error>(SbomReportVulnerability {
                 id: cve.id().to_string(),
                 description: get_description(&cve),
                 sources,
                 published: cve.common_metadata().date_published.map(|t| t.assume_utc()),
                 updated: cve.common_metadata().date_updated.map(|t| t.assume_utc()),
                 affected_packages,
-            }))
+                status: status.get(&id).copied(),
+                known_exploited,
+                exploit_available: exploit_available(cna),
+            })
         })
         .buffer_unordered(4)
-        // filter out missing ones
-        .try_filter_map(|r| async move { Ok::<_, Error>(r) })
         .try_collect::<Vec<_>>()
         .await?;
 
     // summarize scores
 
-    let summary = summarize_vulns(&details)
-        .into_iter()
-        .map(|(source, counts)| {
-            (
-                source,
-                counts
-                    .into_iter()
-                    .map(|(severity, count)| SummaryEntry { severity, count })
-                    .collect::<Vec<_>>(),
-            )
-        })
-        .collect();
+    let summary = report_summary(&details);
 
     // done
 
@@ -226,7 +253,23 @@ async fn process_get_vulnerabilities(
     }))
 }
 
-fn into_severity(score: f32) -> cvss::Severity {
+/// Create the per-source summary of vulnerability severities
+fn report_summary(details: &[SbomReportVulnerability]) -> Vec<(String, Vec<SummaryEntry>)> {
+    summarize_vulns(details)
+        .into_iter()
+        .map(|(source, counts)| {
+            (
+                source,
+                counts
+                    .into_iter()
+                    .map(|(severity, count)| SummaryEntry { severity, count })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn into_severity(score: f32) -> cvss::Severity {
     if score >= 9.0 {
         cvss::Severity::Critical
     } else if score >= 7.0 {
@@ -269,13 +312,21 @@ fn get_description(cve: &Cve) -> Option<String> {
         .next()
 }
 
-/// get the CVSS score as a plain number
-fn get_score(cve: &Cve) -> Option<f32> {
-    let p = match cve {
-        Cve::Published(p) => p,
-        Cve::Rejected(_) => return None,
-    };
+/// Check if the references of the CNA container of a CVE, in its JSON form, point to a published exploit
+fn exploit_available(cna: &Value) -> bool {
+    let urls = cna["references"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|reference| reference["url"].as_str());
+    !Exploit::from_references(urls).is_empty()
+}
 
+/// get the CVSS score as a plain number
+///
+/// The metrics are looked up in the CNA container in its JSON form, as CVSS 4.0 is newer than the CVE record model.
+/// Rejected CVEs have no metrics.
+fn get_score(cna: &Value) -> Option<f32> {
     let score = |value: &Value| {
         value["vectorString"]
             .as_str()
@@ -283,23 +334,26 @@ fn get_score(cve: &Cve) -> Option<f32> {
             .map(|base| base.score().value() as f32)
     };
 
+    let mut v4_0 = None;
     let mut v3_1 = None;
     let mut v3_0 = None;
     let mut v2_0 = None;
 
-    for m in &p.containers.cna.metrics {
-        if let Some(m) = m.cvss_v3_1.as_ref().and_then(score) {
+    for m in cna["metrics"].as_array().into_iter().flatten() {
+        if let Some(Ok(cvss)) = Cvss4::from_cve_metric(m) {
+            v4_0 = Some(cvss.score() as f32);
+        } else if let Some(m) = score(&m["cvssV3_1"]) {
             v3_1 = Some(m);
-        } else if let Some(m) = m.cvss_v3_0.as_ref().and_then(score) {
+        } else if let Some(m) = score(&m["cvssV3_0"]) {
             v3_0 = Some(m);
-        } else if let Some(m) = m.cvss_v2_0.as_ref().and_then(score) {
+        } else if let Some(m) = score(&m["cvssV2_0"]) {
             v2_0 = Some(m);
         }
     }
 
     // FIXME: we need to provide some indication what score version this was
 
-    v3_1.or(v3_0).or(v2_0)
+    v4_0.or(v3_1).or(v3_0).or(v2_0)
 }
 
 /// Collect a summary of count, based on CVSS v3 severities
@@ -352,3 +406,18 @@ fn map_purls(pi: &PackageInformation) -> impl IntoIterator<Item = String> + '_ {
         }
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_score_cve_5_1() {
+        let mut record: Value =
+            serde_json::from_slice(include_bytes!("../../../../../../v11y/testdata/CVE-2024-1597.json")).unwrap();
+        let cve: Cve = cve_record::from_value(&mut record).unwrap();
+        assert_eq!(cve.id(), "CVE-2024-1597");
+        // the CVSS 4.0 score takes precedence over the CVSS 3.1 one
+        assert_eq!(get_score(&record["containers"]["cna"]), Some(9.3));
+    }
+}
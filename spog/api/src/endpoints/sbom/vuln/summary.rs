@@ -0,0 +1,331 @@
+use super::process_get_vulnerabilities;
+use crate::app_state::AppState;
+use crate::error::Error;
+use crate::service::{guac::GuacService, v11y::V11yService};
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use futures::stream::iter;
+use futures::{StreamExt, TryStreamExt};
+use spog_model::prelude::SbomReport;
+use spog_model::vuln::SbomVulnerabilitySummary;
+use std::future::Future;
+use tracing::instrument;
+use trustification_api::search::{Facets, SearchOptions, SearchResult};
+use trustification_auth::client::TokenProvider;
+use utoipa::IntoParams;
+
+/// number of SBOMs being analyzed in parallel
+const PARALLEL_ANALYZE_SBOM: usize = 4;
+/// number of SBOMs searched at once when ranking all SBOMs of a query
+const TOP_PAGE_SIZE: usize = 100;
+/// maximum number of SBOMs a query may match for ranking them
+pub(crate) const MAX_TOP_SBOMS: usize = 1000;
+
+/// the source used for summarizing the vulnerabilities
+pub(crate) const SUMMARY_SOURCE: &str = "mitre";
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct SummaryParams {
+    /// The query string selecting the SBOMs
+    #[serde(default)]
+    pub q: String,
+    /// Offset to start from returning results.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of SBOMs to analyze
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Only return the N most affected SBOMs of all SBOMs matching the query, ignoring offset and limit
+    pub top: Option<usize>,
+}
+
+const fn default_limit() -> usize {
+    10
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/sbom/vulnerabilities/summary",
+    responses(
+        (status = OK, description = "Processing succeeded", body = SearchResultSbomVulnerabilitySummary),
+        (status = BAD_REQUEST, description = "Too many SBOMs match the query for ranking them"),
+    ),
+    params(SummaryParams)
+)]
+#[instrument(skip(state, v11y, guac, access_token), err)]
+pub async fn get_vulnerabilities_summary(
+    state: web::Data<AppState>,
+    v11y: web::Data<V11yService>,
+    guac: web::Data<GuacService>,
+    params: web::Query<SummaryParams>,
+    access_token: Option<BearerAuth>,
+) -> actix_web::Result<HttpResponse> {
+    let params = params.into_inner();
+    let result = process_get_vulnerabilities_summary(&state, &v11y, &guac, &access_token, params).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[instrument(skip(state, guac, v11y, access_token), err)]
+pub(crate) async fn process_get_vulnerabilities_summary(
+    state: &AppState,
+    v11y: &V11yService,
+    guac: &GuacService,
+    access_token: &dyn TokenProvider,
+    params: SummaryParams,
+) -> Result<SearchResult<Vec<SbomVulnerabilitySummary>>, Error> {
+    let options = SearchOptions {
+        explain: false,
+        metadata: false,
+        summaries: true,
+        ..Default::default()
+    };
+
+    let Some(top) = params.top else {
+        let sboms = state
+            .search_sbom(&params.q, params.offset, params.limit, options, access_token)
+            .await?;
+        let ids = sboms.result.into_iter().map(|hit| hit.document.id).collect();
+        return Ok(SearchResult {
+            total: Some(sboms.total),
+            result: summarize(state, v11y, guac, access_token, ids).await?,
+            facets: sboms.facets,
+            cursor: sboms.cursor,
+        });
+    };
+
+    // the most affected SBOMs can only be known by analyzing all SBOMs of the query
+    let (ids, total, facets) =
+        collect_ids(|offset| state.search_sbom(&params.q, offset, TOP_PAGE_SIZE, options.clone(), access_token))
+            .await?;
+
+    let mut result = summarize(state, v11y, guac, access_token, ids).await?;
+    result.truncate(top);
+
+    Ok(SearchResult {
+        total: Some(total),
+        result,
+        facets,
+        cursor: None,
+    })
+}
+
+/// Collect the IDs of all SBOMs matching a query, searching them page by page, starting at the offset passed to
+/// `search`.
+///
+/// Returns the IDs, the total number of matches and the facets of the last page.
+async fn collect_ids<F, Fut>(search: F) -> Result<(Vec<String>, usize, Facets), Error>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = Result<bombastic_model::search::SearchResult, Error>>,
+{
+    let mut ids = Vec::new();
+    loop {
+        let sboms = search(ids.len()).await?;
+        if sboms.total > MAX_TOP_SBOMS {
+            return Err(Error::BadRequest(format!(
+                "The query matches {} SBOMs, a maximum of {MAX_TOP_SBOMS} can be ranked",
+                sboms.total
+            )));
+        }
+        let len = sboms.result.len();
+        ids.extend(sboms.result.into_iter().map(|hit| hit.document.id));
+        if len == 0 || ids.len() >= sboms.total {
+            return Ok((ids, sboms.total, sboms.facets));
+        }
+    }
+}
+
+/// Summarize the vulnerabilities of SBOMs, most affected first.
+async fn summarize(
+    state: &AppState,
+    v11y: &V11yService,
+    guac: &GuacService,
+    access_token: &dyn TokenProvider,
+    ids: Vec<String>,
+) -> Result<Vec<SbomVulnerabilitySummary>, Error> {
+    let mut result = iter(ids)
+        .map(|id| async move {
+            let report = process_get_vulnerabilities(state, v11y, guac, access_token, &id, None, None).await?;
+            Ok::<_, Error>(report.map(|report| summarize_report(id, report)))
+        })
+        .buffer_unordered(PARALLEL_ANALYZE_SBOM)
+        // filter out SBOMs without package information
+        .try_filter_map(|r| async move { Ok(r) })
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    rank(&mut result);
+
+    Ok(result)
+}
+
+/// Summarize the vulnerability report of an SBOM, counting the vulnerabilities of the summary source.
+fn summarize_report(id: String, report: SbomReport) -> SbomVulnerabilitySummary {
+    let summary = report.summary(SUMMARY_SOURCE).map(|s| s.to_vec()).unwrap_or_default();
+    let total = summary.iter().map(|entry| entry.count).sum();
+
+    SbomVulnerabilitySummary {
+        id,
+        name: report.name,
+        version: report.version,
+        total,
+        summary,
+    }
+}
+
+/// Sort the summaries most affected first, keeping the order stable for equal counts.
+fn rank(summaries: &mut [SbomVulnerabilitySummary]) {
+    summaries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.id.cmp(&b.id)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bombastic_model::search::{SearchDocument, SearchHit};
+    use cvss::Severity;
+    use futures::future::ready;
+    use spog_model::prelude::SummaryEntry;
+    use std::sync::Mutex;
+
+    fn entry(severity: Severity, count: usize) -> SummaryEntry {
+        SummaryEntry {
+            severity: Some(severity),
+            count,
+        }
+    }
+
+    fn report(summary: Vec<(String, Vec<SummaryEntry>)>) -> SbomReport {
+        SbomReport {
+            name: "ubi9".to_string(),
+            version: Some("9.2".to_string()),
+            created: None,
+            summary,
+            details: Vec::new(),
+            backtraces: Default::default(),
+        }
+    }
+
+    fn summary(id: &str, total: usize) -> SbomVulnerabilitySummary {
+        SbomVulnerabilitySummary {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: None,
+            total,
+            summary: Vec::new(),
+        }
+    }
+
+    fn hit(id: String) -> SearchHit {
+        SearchHit {
+            document: SearchDocument {
+                id,
+                uid: None,
+                name: "ubi9".to_string(),
+                version: "9.2".to_string(),
+                cpe: None,
+                purl: None,
+                file_sha256: String::new(),
+                sha256: String::new(),
+                license: String::new(),
+                supplier: String::new(),
+                classifier: String::new(),
+                spec_version: None,
+                description: String::new(),
+                snippet: String::new(),
+                created: time::OffsetDateTime::UNIX_EPOCH,
+                dependencies: 0,
+                labels: Default::default(),
+                signed: false,
+                verified: false,
+                quality_score: None,
+                quality: None,
+            },
+            score: 1.0,
+            explanation: None,
+            metadata: None,
+        }
+    }
+
+    /// Search `total` SBOMs, returning up to `page` of them and recording the requested offsets.
+    fn search(
+        total: usize,
+        page: usize,
+        offsets: &Mutex<Vec<usize>>,
+    ) -> impl Fn(usize) -> futures::future::Ready<Result<bombastic_model::search::SearchResult, Error>> + '_ {
+        move |offset| {
+            offsets.lock().unwrap().push(offset);
+            let end = total.min(offset + page);
+            ready(Ok(bombastic_model::search::SearchResult {
+                total,
+                result: (offset..end).map(|n| hit(format!("sbom-{n}"))).collect(),
+                facets: Default::default(),
+                cursor: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_summarize_report() {
+        let result = summarize_report(
+            "ubi9".to_string(),
+            report(vec![
+                (
+                    "mitre".to_string(),
+                    vec![entry(Severity::Critical, 2), entry(Severity::Low, 3)],
+                ),
+                ("other".to_string(), vec![entry(Severity::High, 7)]),
+            ]),
+        );
+        // only the vulnerabilities of the summary source count
+        assert_eq!(result.id, "ubi9");
+        assert_eq!(result.version.as_deref(), Some("9.2"));
+        assert_eq!(result.total, 5);
+        assert_eq!(
+            result.summary,
+            vec![entry(Severity::Critical, 2), entry(Severity::Low, 3)]
+        );
+
+        let result = summarize_report("ubi9".to_string(), report(vec![]));
+        assert_eq!(result.total, 0);
+        assert!(result.summary.is_empty());
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut summaries = vec![summary("b", 1), summary("c", 3), summary("a", 1), summary("d", 0)];
+        rank(&mut summaries);
+        let ids: Vec<_> = summaries.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b", "d"]);
+    }
+
+    #[actix_web::test]
+    async fn test_collect_ids() {
+        let offsets = Mutex::new(Vec::new());
+        let (ids, total, _) = collect_ids(search(250, TOP_PAGE_SIZE, &offsets)).await.unwrap();
+        assert_eq!(total, 250);
+        assert_eq!(ids, (0..250).map(|n| format!("sbom-{n}")).collect::<Vec<_>>());
+        assert_eq!(*offsets.lock().unwrap(), vec![0, 100, 200]);
+
+        // nothing to collect
+        let offsets = Mutex::new(Vec::new());
+        let (ids, total, _) = collect_ids(search(0, TOP_PAGE_SIZE, &offsets)).await.unwrap();
+        assert_eq!(total, 0);
+        assert!(ids.is_empty());
+        assert_eq!(*offsets.lock().unwrap(), vec![0]);
+    }
+
+    #[actix_web::test]
+    async fn test_collect_ids_too_many() {
+        let offsets = Mutex::new(Vec::new());
+        let result = collect_ids(search(MAX_TOP_SBOMS + 1, TOP_PAGE_SIZE, &offsets)).await;
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+        // the first page tells it all
+        assert_eq!(*offsets.lock().unwrap(), vec![0]);
+
+        let offsets = Mutex::new(Vec::new());
+        let (ids, _, _) = collect_ids(search(MAX_TOP_SBOMS, TOP_PAGE_SIZE, &offsets))
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), MAX_TOP_SBOMS);
+    }
+}
@@ -10,6 +10,7 @@ use std::rc::Rc;
 use tracing::{info_span, instrument, Instrument};
 use trustification_api::search::SearchOptions;
 use trustification_auth::client::TokenProvider;
+use vexination_model::convert::parse_csaf;
 
 /// take a set of CVE id and fetch their related CSAF documents
 #[instrument(skip_all, fields(num_ids), err)]
@@ -68,7 +69,7 @@ pub async fn collect_vex<'a>(
 
             let mut result = Vec::new();
 
-            if let Ok(doc) = serde_json::from_slice::<Csaf>(&doc) {
+            if let Ok(doc) = parse_csaf(&doc) {
                 let doc = Rc::new(doc);
                 if let Some(v) = &doc.vulnerabilities {
                     for v in v {
@@ -0,0 +1,113 @@
+use crate::db::Db;
+use crate::error::Error;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use spog_model::saved::{NewSavedSearch, SavedSearch};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/searches")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_searches))
+                .route(web::post().to(save_search)),
+        );
+        config.service(
+            web::resource("/api/v1/searches/{id}")
+                .wrap(new_auth!(auth))
+                .route(web::delete().to(delete_search)),
+        );
+    }
+}
+
+/// The owner of saved searches.
+///
+/// When authentication is disabled, all searches are owned by the anonymous user.
+fn owner(user: &UserInformation) -> &str {
+    user.id().unwrap_or_default()
+}
+
+/// Get the searches saved by the current user.
+#[utoipa::path(
+    get,
+    tag = "search",
+    path = "/api/v1/searches",
+    responses(
+        (status = OK, description = "Saved searches of the user", body = Vec<SavedSearch>),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_searches(db: web::Data<Db>, user: UserInformation) -> actix_web::Result<HttpResponse> {
+    let searches = db.get_saved_searches(owner(&user)).await.map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(searches))
+}
+
+/// Save a search for the current user.
+#[utoipa::path(
+    post,
+    tag = "search",
+    path = "/api/v1/searches",
+    request_body = NewSavedSearch,
+    responses(
+        (status = CREATED, description = "Search was saved", body = SavedSearch),
+        (status = BAD_REQUEST, description = "The search has no name"),
+        (status = CONFLICT, description = "A search with the same name already exists"),
+    )
+)]
+#[instrument(skip(db, search), err)]
+pub async fn save_search(
+    db: web::Data<Db>,
+    user: UserInformation,
+    web::Json(search): web::Json<NewSavedSearch>,
+) -> actix_web::Result<HttpResponse> {
+    if search.name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "MissingName".to_string(),
+            message: "A saved search requires a name".to_string(),
+            details: String::new(),
+        }));
+    }
+
+    let name = search.name.clone();
+    match db
+        .insert_saved_search(owner(&user), search)
+        .await
+        .map_err(Error::from)?
+    {
+        Some(saved) => Ok(HttpResponse::Created().json(saved)),
+        None => Ok(HttpResponse::Conflict().json(ErrorInformation {
+            error: "Conflict".to_string(),
+            message: format!("A search named '{name}' already exists"),
+            details: String::new(),
+        })),
+    }
+}
+
+/// Delete a search of the current user.
+#[utoipa::path(
+    delete,
+    tag = "search",
+    path = "/api/v1/searches/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Search was deleted"),
+        (status = NOT_FOUND, description = "Search was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the saved search"),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn delete_search(
+    db: web::Data<Db>,
+    user: UserInformation,
+    id: web::Path<i64>,
+) -> actix_web::Result<HttpResponse> {
+    match db.delete_saved_search(owner(&user), *id).await.map_err(Error::from)? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
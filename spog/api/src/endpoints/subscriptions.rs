@@ -0,0 +1,246 @@
+use crate::db::Db;
+use crate::error::Error;
+use crate::utils::target;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use packageurl::PackageUrl;
+use spog_model::subscription::{NewSubscription, Notification, Subscription, SubscriptionKind};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/subscriptions")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_subscriptions))
+                .route(web::post().to(create_subscription)),
+        );
+        config.service(
+            web::resource("/api/v1/subscriptions/{id}")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::delete().to(delete_subscription)),
+        );
+        config.service(
+            web::resource("/api/v1/notifications")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_notifications)),
+        );
+    }
+}
+
+/// The owner of subscriptions.
+///
+/// When authentication is disabled, all subscriptions are owned by the anonymous user.
+fn owner(user: &UserInformation) -> &str {
+    user.id().unwrap_or_default()
+}
+
+/// Get the subscriptions of the current user.
+#[utoipa::path(
+    get,
+    tag = "vulnerability",
+    path = "/api/v1/subscriptions",
+    responses(
+        (status = OK, description = "Subscriptions of the user", body = Vec<Subscription>),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_subscriptions(db: web::Data<Db>, user: UserInformation) -> actix_web::Result<HttpResponse> {
+    let subscriptions = db.get_subscriptions(owner(&user)).await.map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(subscriptions))
+}
+
+/// Subscribe the current user to a CVE or package.
+///
+/// Whenever v11y indexes an update of a vulnerability matching the subscription, a notification is recorded, and
+/// sent to the notification URL, if there is one.
+#[utoipa::path(
+    post,
+    tag = "vulnerability",
+    path = "/api/v1/subscriptions",
+    request_body = NewSubscription,
+    responses(
+        (status = CREATED, description = "Subscription was registered", body = Subscription),
+        (status = BAD_REQUEST, description = "The subscription has an invalid CVE ID or package URL, or the notification URL isn't an HTTPS URL of a public host"),
+        (status = CONFLICT, description = "The user is already subscribed"),
+    )
+)]
+#[instrument(skip(db, subscription), err)]
+pub async fn create_subscription(
+    db: web::Data<Db>,
+    user: UserInformation,
+    web::Json(subscription): web::Json<NewSubscription>,
+) -> actix_web::Result<HttpResponse> {
+    let subscription = match normalize(subscription) {
+        Ok(subscription) => subscription,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(error)),
+    };
+
+    let value = subscription.value.clone();
+    match db
+        .insert_subscription(owner(&user), subscription)
+        .await
+        .map_err(Error::from)?
+    {
+        Some(subscription) => Ok(HttpResponse::Created().json(subscription)),
+        None => Ok(HttpResponse::Conflict().json(ErrorInformation {
+            error: "Conflict".to_string(),
+            message: format!("Already subscribed to '{value}'"),
+            details: String::new(),
+        })),
+    }
+}
+
+/// Validate a subscription, normalizing its value.
+fn normalize(mut subscription: NewSubscription) -> Result<NewSubscription, ErrorInformation> {
+    let error = |error: &str, message: &str| ErrorInformation {
+        error: error.to_string(),
+        message: message.to_string(),
+        details: String::new(),
+    };
+
+    let value = subscription.value.trim();
+    // the value is used as part of a query
+    if value.is_empty() || value.contains('"') {
+        return Err(error("InvalidValue", "A subscription requires a CVE ID or package URL"));
+    }
+    subscription.value = match subscription.kind {
+        SubscriptionKind::Cve if value.to_uppercase().starts_with("CVE-") => value.to_uppercase(),
+        SubscriptionKind::Cve => return Err(error("InvalidCve", "The value must be a CVE ID")),
+        // package URLs are matched against the normalized package URLs of the vulnerabilities
+        SubscriptionKind::Purl => match PackageUrl::from_str(value) {
+            Ok(purl) => trustification_purl::normalize(&purl.to_string()).into_owned(),
+            Err(err) => return Err(error("InvalidPurl", &format!("The value must be a package URL: {err}"))),
+        },
+    };
+
+    if let Some(notify) = &subscription.notify {
+        if let Err(message) = target::parse(notify) {
+            return Err(error("InvalidUrl", message));
+        }
+    }
+    Ok(subscription)
+}
+
+/// Delete a subscription of the current user, along with its notifications.
+#[utoipa::path(
+    delete,
+    tag = "vulnerability",
+    path = "/api/v1/subscriptions/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Subscription was deleted"),
+        (status = NOT_FOUND, description = "Subscription was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the subscription"),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn delete_subscription(
+    db: web::Data<Db>,
+    user: UserInformation,
+    id: web::Path<i64>,
+) -> actix_web::Result<HttpResponse> {
+    match db.delete_subscription(owner(&user), *id).await.map_err(Error::from)? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct NotificationsParams {
+    /// Offset to start from returning notifications
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of notifications to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Get the notifications about updated vulnerabilities of the current user, most recent first.
+#[utoipa::path(
+    get,
+    tag = "vulnerability",
+    path = "/api/v1/notifications",
+    responses(
+        (status = OK, description = "Notifications of the user", body = Vec<Notification>),
+    ),
+    params(
+        NotificationsParams,
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_notifications(
+    db: web::Data<Db>,
+    user: UserInformation,
+    params: web::Query<NotificationsParams>,
+) -> actix_web::Result<HttpResponse> {
+    let notifications = db
+        .get_notifications(owner(&user), params.offset, params.limit)
+        .await
+        .map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(notifications))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn subscription(kind: SubscriptionKind, value: &str, notify: Option<&str>) -> NewSubscription {
+        NewSubscription {
+            kind,
+            value: value.to_string(),
+            notify: notify.map(|notify| notify.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(subscription(SubscriptionKind::Cve, " cve-2023-44487 ", None))
+                .unwrap()
+                .value,
+            "CVE-2023-44487"
+        );
+        assert_eq!(
+            normalize(subscription(
+                SubscriptionKind::Purl,
+                "pkg:pypi/django",
+                Some("https://example.com/hook")
+            ))
+            .unwrap()
+            .value,
+            "pkg:pypi/django"
+        );
+        assert_eq!(
+            normalize(subscription(SubscriptionKind::Purl, "pkg:PyPI/Django", None))
+                .unwrap()
+                .value,
+            "pkg:pypi/django"
+        );
+        assert!(normalize(subscription(SubscriptionKind::Cve, "GHSA-QMF9-6JQF-J8FQ", None)).is_err());
+        assert!(normalize(subscription(SubscriptionKind::Cve, r#"CVE-1" OR id:"2"#, None)).is_err());
+        assert!(normalize(subscription(SubscriptionKind::Purl, "django", None)).is_err());
+        assert!(normalize(subscription(
+            SubscriptionKind::Cve,
+            "CVE-2023-44487",
+            Some("file:///etc/passwd")
+        ))
+        .is_err());
+        assert!(normalize(subscription(
+            SubscriptionKind::Cve,
+            "CVE-2023-44487",
+            Some("https://169.254.169.254/latest/meta-data")
+        ))
+        .is_err());
+    }
+}
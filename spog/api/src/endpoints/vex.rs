@@ -0,0 +1,574 @@
+use crate::app_state::AppState;
+use crate::endpoints::sbom::{self, fetch_sbom};
+use crate::error::Error;
+use actix_web::{
+    web::{self, ServiceConfig},
+    HttpResponse,
+};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bombastic_model::data::SBOM;
+use serde_json::{json, Value};
+use spog_model::search::SbomSummary;
+use spog_model::vex::{Disposition, DispositionStatus, GeneratedVex, VexFormat, VexRequest};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::instrument;
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+
+mod merge;
+
+pub use merge::*;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/vex/generate")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::post().to(generate)),
+        );
+        config.service(
+            web::resource("/api/v1/vex/merge")
+                .wrap(new_auth!(auth))
+                .route(web::post().to(merge)),
+        );
+    }
+}
+
+/// A product and its packages, like the product of an SBOM.
+struct Product {
+    /// The ID of the product in the generated document
+    id: String,
+    name: String,
+    purl: Option<String>,
+    cpe: Option<String>,
+    /// Package URLs of all packages
+    packages: BTreeSet<String>,
+}
+
+/// A product, along with the dispositions regarding it.
+type Subject = (Product, Vec<Disposition>);
+
+/// The author and origin of a generated document.
+struct Author {
+    name: String,
+    /// The base URL of the service generating the document
+    namespace: String,
+    timestamp: String,
+}
+
+/// Generate a VEX document for the product of an SBOM.
+///
+/// The product tree of the document contains the product and all packages of the SBOM. The document is signed off by
+/// the authenticated user, who is recorded as its publisher.
+#[utoipa::path(
+    post,
+    tag = "advisory",
+    path = "/api/v1/vex/generate",
+    request_body = VexRequest,
+    responses(
+        (status = OK, description = "VEX document was generated", body = GeneratedVex),
+        (status = CREATED, description = "VEX document was generated and published", body = GeneratedVex),
+        (status = BAD_REQUEST, description = "Invalid dispositions"),
+        (status = NOT_FOUND, description = "SBOM was not found"),
+    )
+)]
+#[instrument(skip(state, user, access_token, request), err)]
+pub async fn generate(
+    state: web::Data<AppState>,
+    user: UserInformation,
+    access_token: Option<BearerAuth>,
+    web::Json(request): web::Json<VexRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let result = state
+        .search_sbom(
+            &format!(r#"id:"{}""#, request.sbom),
+            0,
+            1,
+            SearchOptions::default(),
+            &access_token,
+        )
+        .await?;
+    let Some(summary) = result.result.into_iter().next().map(sbom::into_summary) else {
+        return Ok(HttpResponse::NotFound().json(ErrorInformation {
+            error: "NotFound".to_string(),
+            message: format!("SBOM '{}' was not found", request.sbom),
+            details: String::new(),
+        }));
+    };
+    let product = Product::new(summary, &fetch_sbom(&state, &access_token, &request.sbom).await?);
+
+    if let Some(error) = validate(&request, &product) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    let author = Author::new(&state, &user)?;
+    let id = request
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("{}-vex-{}", request.sbom, OffsetDateTime::now_utc().unix_timestamp()));
+
+    let document = render(request.format, &id, &[(product, request.dispositions)], &author)?;
+
+    if request.publish {
+        let data = serde_json::to_vec(&document).map_err(Error::from)?;
+        state.post_vex(&id, &access_token, data.into()).await?;
+    }
+
+    let result = GeneratedVex {
+        id,
+        format: request.format,
+        published: request.publish,
+        document,
+    };
+    Ok(match request.publish {
+        true => HttpResponse::Created().json(result),
+        false => HttpResponse::Ok().json(result),
+    })
+}
+
+/// Render the subjects as VEX document of the format.
+fn render(format: VexFormat, id: &str, subjects: &[Subject], author: &Author) -> Result<Value, Error> {
+    Ok(match format {
+        VexFormat::Csaf => {
+            let document = csaf(id, subjects, author);
+            // ensure the document is valid, at least regarding the model
+            serde_json::from_value::<csaf::Csaf>(document.clone())
+                .map_err(|err| Error::Generic(format!("Generated an invalid CSAF document: {err}")))?;
+            document
+        }
+        VexFormat::OpenVex => openvex(id, subjects, author),
+    })
+}
+
+impl Author {
+    /// The authenticated user, generating a document now.
+    ///
+    /// The namespace is configured, as the host of a request is chosen by the client.
+    fn new(state: &AppState, user: &UserInformation) -> Result<Self, Error> {
+        let namespace = state
+            .vex_namespace
+            .clone()
+            .ok_or_else(|| Error::Generic("No namespace of generated VEX documents configured".to_string()))?;
+        Ok(Self {
+            name: user.id().unwrap_or("anonymous").to_string(),
+            namespace,
+            timestamp: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .map_err(|err| Error::Generic(err.to_string()))?,
+        })
+    }
+}
+
+impl Product {
+    fn new(summary: SbomSummary, sbom: &SBOM) -> Self {
+        let mut packages = BTreeSet::new();
+        match sbom {
+            SBOM::SPDX(spdx) => {
+                for pi in &spdx.package_information {
+                    packages.extend(
+                        pi.external_reference
+                            .iter()
+                            .filter(|er| er.reference_type == "purl")
+                            .map(|er| er.reference_locator.clone()),
+                    );
+                }
+            }
+            SBOM::CycloneDX(cyclone) => {
+                let mut todo: Vec<_> = cyclone.components.iter().flat_map(|c| c.0.iter()).collect();
+                while let Some(component) = todo.pop() {
+                    packages.extend(component.purl.as_ref().map(|p| p.to_string()));
+                    todo.extend(component.components.iter().flat_map(|c| c.0.iter()));
+                }
+            }
+        }
+
+        let name = match summary.version.is_empty() {
+            true => summary.name,
+            false => format!("{} {}", summary.name, summary.version),
+        };
+        Self {
+            id: summary.id,
+            name,
+            purl: summary.purl,
+            cpe: summary.cpe,
+            packages,
+        }
+    }
+}
+
+fn validate(request: &VexRequest, product: &Product) -> Option<ErrorInformation> {
+    let error = |error: &str, message: String| {
+        Some(ErrorInformation {
+            error: error.to_string(),
+            message,
+            details: String::new(),
+        })
+    };
+
+    if request.dispositions.is_empty() {
+        return error(
+            "MissingDispositions",
+            "At least one disposition is required".to_string(),
+        );
+    }
+    for disposition in &request.dispositions {
+        if !disposition.cve.starts_with("CVE-") {
+            return error("InvalidCve", format!("Invalid CVE: {}", disposition.cve));
+        }
+        if disposition.status == DispositionStatus::NotAffected
+            && disposition.justification.is_none()
+            && disposition.statement.is_none()
+        {
+            return error(
+                "MissingJustification",
+                format!(
+                    "Not being affected by {} requires a justification or statement",
+                    disposition.cve
+                ),
+            );
+        }
+        if disposition.status != DispositionStatus::NotAffected && disposition.justification.is_some() {
+            return error(
+                "UnexpectedJustification",
+                format!("Only not being affected by {} can be justified", disposition.cve),
+            );
+        }
+        if let Some(package) = disposition
+            .packages
+            .iter()
+            .find(|package| !product.packages.contains(*package))
+        {
+            return error("UnknownPackage", format!("The SBOM has no package {package}"));
+        }
+    }
+    None
+}
+
+/// The name of an enumeration value, as serialized.
+fn name<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// The product ID of a package of the product.
+fn component_id(product: &Product, package: &str) -> String {
+    format!("{}:{}", product.id, package)
+}
+
+/// Generate a CSAF VEX document.
+fn csaf(id: &str, subjects: &[Subject], author: &Author) -> Value {
+    let mut branches = vec![];
+    let mut packages = BTreeSet::new();
+    for (product, _) in subjects {
+        let mut helper = serde_json::Map::new();
+        if let Some(purl) = &product.purl {
+            helper.insert("purl".to_string(), purl.as_str().into());
+        }
+        if let Some(cpe) = &product.cpe {
+            helper.insert("cpe".to_string(), cpe.as_str().into());
+        }
+        let mut product_branch = json!({
+            "category": "product_name",
+            "name": product.name,
+            "product": { "name": product.name, "product_id": product.id },
+        });
+        if !helper.is_empty() {
+            product_branch["product"]["product_identification_helper"] = helper.into();
+        }
+        branches.push(product_branch);
+        packages.extend(&product.packages);
+    }
+    branches.extend(packages.into_iter().map(|package| {
+        json!({
+            "category": "product_version",
+            "name": package,
+            "product": {
+                "name": package,
+                "product_id": package,
+                "product_identification_helper": { "purl": package },
+            },
+        })
+    }));
+
+    let relationships: Vec<_> = subjects
+        .iter()
+        .flat_map(|(product, dispositions)| {
+            dispositions
+                .iter()
+                .flat_map(|disposition| &disposition.packages)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(move |package| {
+                    json!({
+                        "category": "default_component_of",
+                        "full_product_name": {
+                            "name": format!("{package} as a component of {}", product.name),
+                            "product_id": component_id(product, package),
+                        },
+                        "product_reference": package,
+                        "relates_to_product_reference": product.id,
+                    })
+                })
+        })
+        .collect();
+
+    let mut vulnerabilities = BTreeMap::<&str, Value>::new();
+    for (product, dispositions) in subjects {
+        for disposition in dispositions {
+            let product_ids: Vec<_> = match disposition.packages.is_empty() {
+                true => vec![product.id.clone()],
+                false => disposition
+                    .packages
+                    .iter()
+                    .map(|package| component_id(product, package))
+                    .collect(),
+            };
+
+            let vuln = vulnerabilities
+                .entry(&disposition.cve)
+                .or_insert_with(|| json!({ "cve": disposition.cve, "product_status": {} }));
+            let status = match disposition.status {
+                DispositionStatus::NotAffected => "known_not_affected",
+                DispositionStatus::Affected => "known_affected",
+                DispositionStatus::Fixed => "fixed",
+                DispositionStatus::UnderInvestigation => "under_investigation",
+            };
+            append(
+                &mut vuln["product_status"][status],
+                product_ids.iter().map(|id| json!(id)),
+            );
+
+            if let Some(justification) = &disposition.justification {
+                append(
+                    &mut vuln["flags"],
+                    [json!({ "label": name(justification), "product_ids": product_ids })],
+                );
+            }
+            match (disposition.status, &disposition.statement) {
+                (DispositionStatus::NotAffected, Some(statement)) => append(
+                    &mut vuln["threats"],
+                    [json!({ "category": "impact", "details": statement, "product_ids": product_ids })],
+                ),
+                (DispositionStatus::Affected, statement) => append(
+                    &mut vuln["remediations"],
+                    [json!({
+                        "category": if statement.is_some() { "workaround" } else { "none_available" },
+                        "details": statement.as_deref().unwrap_or("No remediation is available yet"),
+                        "product_ids": product_ids,
+                    })],
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    let names: Vec<_> = subjects.iter().map(|(product, _)| product.name.as_str()).collect();
+    let signed_off = format!("Signed off by {}", author.name);
+    json!({
+        "document": {
+            "category": "csaf_vex",
+            "csaf_version": "2.0",
+            "title": format!("VEX of {}", names.join(", ")),
+            "publisher": {
+                "category": "user",
+                "name": author.name,
+                "namespace": author.namespace,
+            },
+            "notes": [{
+                "category": "general",
+                "title": "Sign-off",
+                "text": signed_off,
+            }],
+            "tracking": {
+                "id": id,
+                "status": "final",
+                "version": "1",
+                "initial_release_date": author.timestamp,
+                "current_release_date": author.timestamp,
+                "revision_history": [{
+                    "date": author.timestamp,
+                    "number": "1",
+                    "summary": signed_off,
+                }],
+                "generator": {
+                    "date": author.timestamp,
+                    "engine": { "name": "trustification" },
+                },
+            },
+        },
+        "product_tree": {
+            "branches": branches,
+            "relationships": relationships,
+        },
+        "vulnerabilities": vulnerabilities.into_values().collect::<Vec<_>>(),
+    })
+}
+
+/// Generate an OpenVEX document.
+fn openvex(id: &str, subjects: &[Subject], author: &Author) -> Value {
+    let mut statements = vec![];
+    for (product, dispositions) in subjects {
+        let product_id = product.purl.as_ref().or(product.cpe.as_ref()).unwrap_or(&product.id);
+        statements.extend(dispositions.iter().map(|disposition| {
+            let mut product = json!({ "@id": product_id });
+            if !disposition.packages.is_empty() {
+                product["subcomponents"] = disposition
+                    .packages
+                    .iter()
+                    .map(|package| json!({ "@id": package }))
+                    .collect();
+            }
+            let mut statement = json!({
+                "vulnerability": { "name": disposition.cve },
+                "products": [product],
+                "status": name(&disposition.status),
+            });
+            if let Some(justification) = &disposition.justification {
+                statement["justification"] = name(justification);
+            }
+            match (disposition.status, &disposition.statement) {
+                (DispositionStatus::NotAffected, Some(text)) => statement["impact_statement"] = json!(text),
+                (DispositionStatus::Affected, Some(text)) => statement["action_statement"] = json!(text),
+                _ => {}
+            }
+            statement
+        }));
+    }
+
+    json!({
+        "@context": "https://openvex.dev/ns/v0.2.0",
+        "@id": format!("{}/vex/{}", author.namespace, id),
+        "author": author.name,
+        "role": "Document Creator",
+        "timestamp": author.timestamp,
+        "version": 1,
+        "tooling": "trustification",
+        "statements": statements,
+    })
+}
+
+/// Append values to a JSON array, creating it if necessary.
+fn append(target: &mut Value, values: impl IntoIterator<Item = Value>) {
+    if !target.is_array() {
+        *target = Value::Array(vec![]);
+    }
+    if let Value::Array(array) = target {
+        array.extend(values);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spog_model::vex::Justification;
+
+    fn product() -> Product {
+        Product {
+            id: "ubi9:9.2".to_string(),
+            name: "ubi9 9.2".to_string(),
+            purl: Some(
+                "pkg:oci/ubi9@sha256:cbd7d2e2a2d504c8ad2003aa4c2897a08e4fc58d14d2ef6f06fa0d8c5a76c1fd".to_string(),
+            ),
+            cpe: None,
+            packages: BTreeSet::from([
+                "pkg:rpm/redhat/openssl@3.0.7-6.el9_2?arch=x86_64".to_string(),
+                "pkg:rpm/redhat/curl@7.76.1-23.el9?arch=x86_64".to_string(),
+            ]),
+        }
+    }
+
+    fn author() -> Author {
+        Author {
+            name: "alice".to_string(),
+            namespace: "https://spog.example.com".to_string(),
+            timestamp: "2023-11-21T09:55:03Z".to_string(),
+        }
+    }
+
+    fn dispositions() -> Vec<Disposition> {
+        vec![
+            Disposition {
+                cve: "CVE-2023-0286".to_string(),
+                status: DispositionStatus::NotAffected,
+                justification: Some(Justification::VulnerableCodeNotInExecutePath),
+                statement: None,
+                packages: vec!["pkg:rpm/redhat/openssl@3.0.7-6.el9_2?arch=x86_64".to_string()],
+            },
+            Disposition {
+                cve: "CVE-2023-38545".to_string(),
+                status: DispositionStatus::Affected,
+                justification: None,
+                statement: Some("Upgrade curl".to_string()),
+                packages: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_csaf() {
+        let document = csaf("ubi9-vex", &[(product(), dispositions())], &author());
+        let csaf: csaf::Csaf = serde_json::from_value(document).unwrap();
+
+        assert_eq!(csaf.document.tracking.id, "ubi9-vex");
+        assert_eq!(csaf.document.publisher.name, "alice");
+
+        let vulns = csaf.vulnerabilities.unwrap();
+        assert_eq!(vulns.len(), 2);
+        let status = vulns[0].product_status.as_ref().unwrap();
+        assert_eq!(
+            status.known_not_affected.as_ref().unwrap()[0].0,
+            "ubi9:9.2:pkg:rpm/redhat/openssl@3.0.7-6.el9_2?arch=x86_64"
+        );
+        assert_eq!(vulns[0].flags.as_ref().unwrap().len(), 1);
+        let status = vulns[1].product_status.as_ref().unwrap();
+        assert_eq!(status.known_affected.as_ref().unwrap()[0].0, "ubi9:9.2");
+        assert_eq!(vulns[1].remediations.as_ref().unwrap().len(), 1);
+
+        let tree = csaf.product_tree.unwrap();
+        assert_eq!(tree.branches.unwrap().0.len(), 3);
+        assert_eq!(tree.relationships.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_openvex() {
+        let document = openvex("ubi9-vex", &[(product(), dispositions())], &author());
+        assert_eq!(document["@id"], "https://spog.example.com/vex/ubi9-vex");
+        assert_eq!(document["statements"][0]["status"], "not_affected");
+        assert_eq!(
+            document["statements"][0]["justification"],
+            "vulnerable_code_not_in_execute_path"
+        );
+        assert_eq!(
+            document["statements"][0]["products"][0]["subcomponents"][0]["@id"],
+            "pkg:rpm/redhat/openssl@3.0.7-6.el9_2?arch=x86_64"
+        );
+        assert_eq!(document["statements"][1]["action_statement"], "Upgrade curl");
+    }
+
+    #[test]
+    fn test_validate() {
+        let request = |dispositions| VexRequest {
+            sbom: "ubi9:9.2".to_string(),
+            dispositions,
+            format: VexFormat::Csaf,
+            id: None,
+            publish: false,
+        };
+        assert!(validate(&request(dispositions()), &product()).is_none());
+        assert!(validate(&request(vec![]), &product()).is_some());
+
+        let mut invalid = dispositions();
+        invalid[0].justification = None;
+        assert_eq!(
+            validate(&request(invalid), &product()).unwrap().error,
+            "MissingJustification"
+        );
+
+        let mut invalid = dispositions();
+        invalid[0].packages = vec!["pkg:rpm/redhat/bash".to_string()];
+        assert_eq!(validate(&request(invalid), &product()).unwrap().error, "UnknownPackage");
+    }
+}
@@ -0,0 +1,575 @@
+use super::{render, Author, Product, Subject};
+use crate::app_state::AppState;
+use crate::error::Error;
+use actix_web::{web, HttpResponse};
+use actix_web_httpauth::extractors::bearer::BearerAuth;
+use bytes::BytesMut;
+use futures::TryStreamExt;
+use serde_json::Value;
+use spog_model::vex::{
+    Disposition, DispositionStatus, Justification, MergeStrategy, MergedVex, SupersededStatement, VexMergeRequest,
+};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::instrument;
+use trustification_auth::authenticator::user::UserInformation;
+use trustification_auth::client::TokenProvider;
+use trustification_common::error::ErrorInformation;
+use vexination_model::convert::downgrade;
+
+/// maximum number of documents merged by a single request
+const MAX_SOURCES: usize = 20;
+
+/// Categories of the product status of a CSAF vulnerability, and their disposition status
+const STATUSES: [(&str, DispositionStatus); 7] = [
+    ("known_not_affected", DispositionStatus::NotAffected),
+    ("known_affected", DispositionStatus::Affected),
+    ("first_affected", DispositionStatus::Affected),
+    ("last_affected", DispositionStatus::Affected),
+    ("fixed", DispositionStatus::Fixed),
+    ("first_fixed", DispositionStatus::Fixed),
+    ("under_investigation", DispositionStatus::UnderInvestigation),
+];
+
+/// A statement of a source about a vulnerability and product.
+#[derive(Clone, Debug, PartialEq)]
+struct Statement {
+    cve: String,
+    /// The identifier of the product: its purl, CPE or name
+    product: String,
+    /// The identifier of the package of the product, if the statement is limited to it
+    package: Option<String>,
+    status: DispositionStatus,
+    justification: Option<Justification>,
+    statement: Option<String>,
+    timestamp: OffsetDateTime,
+    priority: i32,
+    /// The index of the source
+    source: usize,
+}
+
+/// Merge VEX documents about the same product into a single, consolidated document.
+///
+/// Products and packages are matched across documents by their purl or CPE, falling back to their name. For every
+/// vulnerability and product, or package of a product, only one statement is kept, picked according to the strategy of
+/// the request. Statements about a whole product don't override those about some of its packages.
+#[utoipa::path(
+    post,
+    tag = "advisory",
+    path = "/api/v1/vex/merge",
+    request_body = VexMergeRequest,
+    responses(
+        (status = OK, description = "VEX documents were merged", body = MergedVex),
+        (status = CREATED, description = "VEX documents were merged and the result published", body = MergedVex),
+        (status = BAD_REQUEST, description = "Invalid sources, or a source isn't a valid VEX document"),
+        (status = NOT_FOUND, description = "A source document was not found"),
+    )
+)]
+#[instrument(skip(state, user, access_token, request), err)]
+pub async fn merge(
+    state: web::Data<AppState>,
+    user: UserInformation,
+    access_token: Option<BearerAuth>,
+    web::Json(request): web::Json<VexMergeRequest>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some(error) = validate(&request) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    let mut statements = vec![];
+    for (index, source) in request.sources.iter().enumerate() {
+        let document = match (&source.id, &source.document) {
+            (Some(id), _) => fetch(&state, &access_token, id).await?,
+            (None, Some(document)) => document.clone(),
+            (None, None) => continue,
+        };
+        match parse(&document, index, source.priority) {
+            Ok(parsed) => statements.extend(parsed),
+            Err(details) => {
+                return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+                    error: "InvalidDocument".to_string(),
+                    message: format!("Source {index} is not a valid CSAF or OpenVEX document"),
+                    details,
+                }))
+            }
+        }
+    }
+    let (subjects, superseded) = consolidate(statements, request.strategy);
+
+    let author = Author::new(&state, &user)?;
+    let id = request
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("merged-vex-{}", OffsetDateTime::now_utc().unix_timestamp()));
+    let document = render(request.format, &id, &subjects, &author)?;
+
+    if request.publish {
+        let data = serde_json::to_vec(&document).map_err(Error::from)?;
+        state.post_vex(&id, &access_token, data.into()).await?;
+    }
+
+    let result = MergedVex {
+        id,
+        format: request.format,
+        published: request.publish,
+        document,
+        superseded,
+    };
+    Ok(match request.publish {
+        true => HttpResponse::Created().json(result),
+        false => HttpResponse::Ok().json(result),
+    })
+}
+
+fn validate(request: &VexMergeRequest) -> Option<ErrorInformation> {
+    let error = |error: &str, message: String| {
+        Some(ErrorInformation {
+            error: error.to_string(),
+            message,
+            details: String::new(),
+        })
+    };
+
+    if request.sources.len() < 2 {
+        return error("MissingSources", "At least two documents are required".to_string());
+    }
+    if request.sources.len() > MAX_SOURCES {
+        return error(
+            "TooManySources",
+            format!("At most {MAX_SOURCES} documents can be merged"),
+        );
+    }
+    if let Some(index) = request
+        .sources
+        .iter()
+        .position(|source| source.id.is_some() == source.document.is_some())
+    {
+        return error(
+            "InvalidSource",
+            format!("Source {index} requires either an ID or a document"),
+        );
+    }
+    None
+}
+
+#[instrument(skip(state, access_token), err)]
+async fn fetch(state: &AppState, access_token: &dyn TokenProvider, id: &str) -> Result<Value, Error> {
+    let data: BytesMut = state.get_vex(id, access_token).await?.try_collect().await?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Extract the statements of a CSAF or OpenVEX document.
+fn parse(document: &Value, source: usize, priority: i32) -> Result<Vec<Statement>, String> {
+    let openvex = document["@context"]
+        .as_str()
+        .is_some_and(|context| context.starts_with("https://openvex.dev/ns"));
+    match openvex {
+        true => parse_openvex(document, source, priority),
+        false => {
+            // statements are extracted by the CSAF 2.0 model, which is what other versions are downgraded to
+            let mut document = document.clone();
+            downgrade(&mut document);
+            // ensure the document is valid, at least regarding the model
+            serde_json::from_value::<csaf::Csaf>(document.clone()).map_err(|err| err.to_string())?;
+            parse_csaf(&document, source, priority)
+        }
+    }
+}
+
+fn parse_csaf(csaf: &Value, source: usize, priority: i32) -> Result<Vec<Statement>, String> {
+    let timestamp = timestamp(&csaf["document"]["tracking"]["current_release_date"])
+        .ok_or("The current release date is missing or invalid")?;
+
+    let mut names = HashMap::new();
+    let mut branches: Vec<_> = array(&csaf["product_tree"]["branches"]).collect();
+    while let Some(branch) = branches.pop() {
+        names.extend(identify(&branch["product"]));
+        branches.extend(array(&branch["branches"]));
+    }
+    names.extend(array(&csaf["product_tree"]["full_product_names"]).filter_map(identify));
+
+    let mut relationships = HashMap::new();
+    for relationship in array(&csaf["product_tree"]["relationships"]) {
+        if let (Some(id), Some(product), Some(package)) = (
+            relationship["full_product_name"]["product_id"].as_str(),
+            relationship["relates_to_product_reference"].as_str(),
+            relationship["product_reference"].as_str(),
+        ) {
+            relationships.insert(id, (product, package));
+        }
+    }
+
+    let name = |id: &str| names.get(id).cloned().unwrap_or_else(|| id.to_string());
+    let mut result = vec![];
+    for vulnerability in array(&csaf["vulnerabilities"]) {
+        let Some(cve) = vulnerability["cve"].as_str() else {
+            continue;
+        };
+        for (category, status) in STATUSES {
+            for id in array(&vulnerability["product_status"][category]).filter_map(Value::as_str) {
+                let (product, package) = match relationships.get(id) {
+                    Some((product, package)) => (name(product), Some(name(package))),
+                    None => (name(id), None),
+                };
+                let justification = match status {
+                    DispositionStatus::NotAffected => array(&vulnerability["flags"])
+                        .find(|flag| contains(&flag["product_ids"], id))
+                        .and_then(|flag| justification(&flag["label"])),
+                    _ => None,
+                };
+                let statement = match status {
+                    DispositionStatus::NotAffected => details(&vulnerability["threats"], id, |c| c == "impact"),
+                    DispositionStatus::Affected => {
+                        details(&vulnerability["remediations"], id, |c| c != "none_available")
+                    }
+                    _ => None,
+                };
+                result.push(Statement {
+                    cve: cve.to_string(),
+                    product,
+                    package,
+                    status,
+                    justification,
+                    statement,
+                    timestamp,
+                    priority,
+                    source,
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn parse_openvex(openvex: &Value, source: usize, priority: i32) -> Result<Vec<Statement>, String> {
+    let updated = timestamp(&openvex["last_updated"]).or_else(|| timestamp(&openvex["timestamp"]));
+
+    let mut result = vec![];
+    for statement in array(&openvex["statements"]) {
+        let vulnerability = &statement["vulnerability"];
+        let cve = vulnerability["name"]
+            .as_str()
+            .or(vulnerability.as_str())
+            .ok_or("A statement has no vulnerability")?;
+        let status: DispositionStatus =
+            serde_json::from_value(statement["status"].clone()).map_err(|err| format!("Invalid status: {err}"))?;
+        let timestamp = timestamp(&statement["timestamp"])
+            .or(updated)
+            .ok_or("The timestamp is missing or invalid")?;
+        let text = match status {
+            DispositionStatus::NotAffected => statement["impact_statement"].as_str(),
+            DispositionStatus::Affected => statement["action_statement"].as_str(),
+            _ => None,
+        };
+
+        for product in array(&statement["products"]) {
+            let Some(id) = reference(product) else {
+                continue;
+            };
+            // subcomponents of the statement are used before v0.2.0 of the specification
+            let mut packages: Vec<_> = array(&product["subcomponents"])
+                .chain(array(&statement["subcomponents"]))
+                .filter_map(reference)
+                .map(|package| Some(package.to_string()))
+                .collect();
+            if packages.is_empty() {
+                packages.push(None);
+            }
+            result.extend(packages.into_iter().map(|package| Statement {
+                cve: cve.to_string(),
+                product: id.to_string(),
+                package,
+                status,
+                justification: justification(&statement["justification"]),
+                statement: text.map(ToString::to_string),
+                timestamp,
+                priority,
+                source,
+            }));
+        }
+    }
+    Ok(result)
+}
+
+/// Keep the preferred statement for every vulnerability and product, grouping them by product.
+fn consolidate(statements: Vec<Statement>, strategy: MergeStrategy) -> (Vec<Subject>, Vec<SupersededStatement>) {
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for statement in statements {
+        let key = (
+            statement.product.clone(),
+            statement.package.clone(),
+            statement.cve.clone(),
+        );
+        groups.entry(key).or_default().push(statement);
+    }
+
+    let mut products = BTreeMap::<String, (BTreeSet<String>, Vec<Disposition>)>::new();
+    let mut superseded = vec![];
+    for (_, mut group) in groups {
+        group.sort_by(|a, b| compare(strategy, a, b));
+        let Some(winner) = group.pop() else {
+            continue;
+        };
+        superseded.extend(
+            group
+                .into_iter()
+                .filter(|statement| statement.source != winner.source)
+                .map(|statement| SupersededStatement {
+                    cve: statement.cve,
+                    product: statement.product,
+                    package: statement.package,
+                    status: statement.status,
+                    source: statement.source,
+                    superseded_by: winner.source,
+                }),
+        );
+
+        let (packages, dispositions) = products.entry(winner.product).or_default();
+        packages.extend(winner.package.clone());
+        dispositions.push(Disposition {
+            cve: winner.cve,
+            status: winner.status,
+            justification: winner.justification,
+            statement: winner.statement,
+            packages: winner.package.into_iter().collect(),
+        });
+    }
+
+    let subjects = products
+        .into_iter()
+        .map(|(id, (packages, dispositions))| {
+            let product = Product {
+                name: id.clone(),
+                purl: id.starts_with("pkg:").then(|| id.clone()),
+                cpe: id.starts_with("cpe:").then(|| id.clone()),
+                id,
+                packages,
+            };
+            (product, dispositions)
+        })
+        .collect();
+    (subjects, superseded)
+}
+
+/// Order statements by preference, the preferred one being the greatest.
+fn compare(strategy: MergeStrategy, a: &Statement, b: &Statement) -> Ordering {
+    let recency = a.timestamp.cmp(&b.timestamp);
+    let priority = a.priority.cmp(&b.priority);
+    match strategy {
+        MergeStrategy::LatestWins => recency.then(priority),
+        MergeStrategy::PriorityWins => priority.then(recency),
+    }
+    .then(a.source.cmp(&b.source))
+}
+
+fn array(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn contains(ids: &Value, id: &str) -> bool {
+    array(ids).any(|value| value == id)
+}
+
+fn timestamp(value: &Value) -> Option<OffsetDateTime> {
+    value
+        .as_str()
+        .and_then(|value| OffsetDateTime::parse(value, &Rfc3339).ok())
+}
+
+fn justification(label: &Value) -> Option<Justification> {
+    serde_json::from_value(label.clone()).ok()
+}
+
+/// The details of the first threat or remediation of an accepted category, which applies to the product ID.
+fn details(items: &Value, id: &str, accept: impl Fn(&str) -> bool) -> Option<String> {
+    array(items)
+        .filter(|item| item["category"].as_str().is_some_and(&accept))
+        .find(|item| contains(&item["product_ids"], id))
+        .and_then(|item| item["details"].as_str())
+        .map(ToString::to_string)
+}
+
+/// The product ID of a CSAF full product name, along with the identifier of the product.
+fn identify(product: &Value) -> Option<(&str, String)> {
+    let id = product["product_id"].as_str()?;
+    let helper = &product["product_identification_helper"];
+    let identifier = helper["purl"]
+        .as_str()
+        .or(helper["cpe"].as_str())
+        .or(product["name"].as_str())
+        .unwrap_or(id);
+    Some((id, identifier.to_string()))
+}
+
+/// The identifier of an OpenVEX product or subcomponent, either by its `@id` or a plain string.
+fn reference(product: &Value) -> Option<&str> {
+    product["@id"].as_str().or(product.as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{csaf, openvex};
+    use super::*;
+
+    const UBI: &str = "pkg:oci/ubi9@sha256:cbd7d2e2a2d504c8ad2003aa4c2897a08e4fc58d14d2ef6f06fa0d8c5a76c1fd";
+    const OPENSSL: &str = "pkg:rpm/redhat/openssl@3.0.7-6.el9_2?arch=x86_64";
+
+    fn product() -> Product {
+        Product {
+            id: "ubi9:9.2".to_string(),
+            name: "ubi9 9.2".to_string(),
+            purl: Some(UBI.to_string()),
+            cpe: None,
+            packages: BTreeSet::from([OPENSSL.to_string()]),
+        }
+    }
+
+    fn author(name: &str, timestamp: &str) -> Author {
+        Author {
+            name: name.to_string(),
+            namespace: "https://spog.example.com".to_string(),
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    /// The vendor claims to be affected, with a workaround.
+    fn vendor() -> Value {
+        let dispositions = vec![
+            Disposition {
+                cve: "CVE-2023-0286".to_string(),
+                status: DispositionStatus::Affected,
+                justification: None,
+                statement: Some("Disable X.400 support".to_string()),
+                packages: vec![OPENSSL.to_string()],
+            },
+            Disposition {
+                cve: "CVE-2023-38545".to_string(),
+                status: DispositionStatus::Fixed,
+                justification: None,
+                statement: None,
+                packages: vec![],
+            },
+        ];
+        csaf(
+            "vendor",
+            &[(product(), dispositions)],
+            &author("vendor", "2023-11-01T00:00:00Z"),
+        )
+    }
+
+    /// Internal analysis finds the code isn't used.
+    fn internal() -> Value {
+        let dispositions = vec![Disposition {
+            cve: "CVE-2023-0286".to_string(),
+            status: DispositionStatus::NotAffected,
+            justification: Some(Justification::VulnerableCodeNotInExecutePath),
+            statement: None,
+            packages: vec![OPENSSL.to_string()],
+        }];
+        openvex(
+            "internal",
+            &[(product(), dispositions)],
+            &author("alice", "2023-11-21T09:55:03Z"),
+        )
+    }
+
+    #[test]
+    fn test_parse_csaf() {
+        let statements = parse(&vendor(), 0, 0).unwrap();
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].cve, "CVE-2023-0286");
+        assert_eq!(statements[0].product, UBI);
+        assert_eq!(statements[0].package.as_deref(), Some(OPENSSL));
+        assert_eq!(statements[0].status, DispositionStatus::Affected);
+        assert_eq!(statements[0].statement.as_deref(), Some("Disable X.400 support"));
+        assert_eq!(statements[1].package, None);
+        assert_eq!(statements[1].status, DispositionStatus::Fixed);
+    }
+
+    #[test]
+    fn test_parse_csaf_2_1() {
+        let document: Value =
+            serde_json::from_slice(include_bytes!("../../../../../vexination/testdata/csaf-2.1.json")).unwrap();
+        let statements = parse(&document, 0, 0).unwrap();
+        assert!(!statements.is_empty());
+        assert!(statements.iter().all(|statement| statement.cve == "CVE-2024-00001"));
+    }
+
+    #[test]
+    fn test_parse_openvex() {
+        let statements = parse(&internal(), 1, 0).unwrap();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].product, UBI);
+        assert_eq!(statements[0].package.as_deref(), Some(OPENSSL));
+        assert_eq!(
+            statements[0].justification,
+            Some(Justification::VulnerableCodeNotInExecutePath)
+        );
+        assert_eq!(statements[0].source, 1);
+
+        assert!(parse(&serde_json::json!({ "document": {} }), 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_consolidate() {
+        let statements = |vendor_priority| {
+            let mut statements = parse(&vendor(), 0, vendor_priority).unwrap();
+            statements.extend(parse(&internal(), 1, 0).unwrap());
+            statements
+        };
+
+        let (subjects, superseded) = consolidate(statements(10), MergeStrategy::LatestWins);
+        assert_eq!(subjects.len(), 1);
+        let (product, dispositions) = &subjects[0];
+        assert_eq!(product.purl.as_deref(), Some(UBI));
+        assert_eq!(dispositions.len(), 2);
+        assert_eq!(dispositions[0].status, DispositionStatus::Fixed);
+        assert_eq!(dispositions[1].status, DispositionStatus::NotAffected);
+        assert_eq!(superseded.len(), 1);
+        assert_eq!(superseded[0].status, DispositionStatus::Affected);
+        assert_eq!(superseded[0].superseded_by, 1);
+
+        let (subjects, superseded) = consolidate(statements(10), MergeStrategy::PriorityWins);
+        assert_eq!(subjects[0].1[1].status, DispositionStatus::Affected);
+        assert_eq!(superseded[0].status, DispositionStatus::NotAffected);
+
+        // the merged document is valid
+        let document = csaf("merged", &subjects, &author("alice", "2023-11-22T00:00:00Z"));
+        assert!(serde_json::from_value::<csaf::Csaf>(document).is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let request = |sources| VexMergeRequest {
+            sources,
+            strategy: Default::default(),
+            format: Default::default(),
+            id: None,
+            publish: false,
+        };
+        let source = |id: Option<&str>, document: Option<Value>| spog_model::vex::VexSource {
+            id: id.map(ToString::to_string),
+            document,
+            priority: 0,
+        };
+
+        assert!(validate(&request(vec![
+            source(Some("RHSA-2023:1441"), None),
+            source(None, Some(internal()))
+        ]))
+        .is_none());
+        assert_eq!(
+            validate(&request(vec![source(Some("RHSA-2023:1441"), None)]))
+                .unwrap()
+                .error,
+            "MissingSources"
+        );
+        assert_eq!(
+            validate(&request(vec![source(None, None), source(None, Some(internal()))]))
+                .unwrap()
+                .error,
+            "InvalidSource"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::db::Db;
+use crate::error::Error;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use spog_model::saved::SearchTarget;
+use spog_model::watch::{NewWatch, Watch, WatchHit};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/watches")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_watches))
+                .route(web::post().to(create_watch)),
+        );
+        config.service(
+            web::resource("/api/v1/watches/{id}")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::delete().to(delete_watch)),
+        );
+        config.service(
+            web::resource("/api/v1/watches/{id}/hits")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_watch_hits)),
+        );
+    }
+}
+
+/// The owner of watches.
+///
+/// When authentication is disabled, all watches are owned by the anonymous user.
+fn owner(user: &UserInformation) -> &str {
+    user.id().unwrap_or_default()
+}
+
+/// Permissions evaluating the watches of a user, none if authentication is disabled.
+fn permissions(user: &UserInformation) -> Option<&[String]> {
+    match user {
+        UserInformation::Authenticated(details) => Some(&details.permissions),
+        UserInformation::Anonymous => None,
+    }
+}
+
+/// Get the watches of the current user.
+#[utoipa::path(
+    get,
+    tag = "search",
+    path = "/api/v1/watches",
+    responses(
+        (status = OK, description = "Watches of the user", body = Vec<Watch>),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_watches(db: web::Data<Db>, user: UserInformation) -> actix_web::Result<HttpResponse> {
+    let watches = db.get_watches(owner(&user)).await.map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(watches))
+}
+
+/// Register a watch for the current user.
+///
+/// The query of the watch is evaluated whenever new documents get indexed. Matching documents are recorded as hits,
+/// and sent to the notification URL, if there is one.
+#[utoipa::path(
+    post,
+    tag = "search",
+    path = "/api/v1/watches",
+    request_body = NewWatch,
+    responses(
+        (status = CREATED, description = "Watch was registered", body = Watch),
+        (status = BAD_REQUEST, description = "The watch has no name, an unsupported target, or an invalid notification URL"),
+        (status = CONFLICT, description = "A watch with the same name already exists"),
+    )
+)]
+#[instrument(skip(db, watch), err)]
+pub async fn create_watch(
+    db: web::Data<Db>,
+    user: UserInformation,
+    web::Json(watch): web::Json<NewWatch>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some(error) = validate(&watch) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    let name = watch.name.clone();
+    match db
+        .insert_watch(owner(&user), permissions(&user), watch)
+        .await
+        .map_err(Error::from)?
+    {
+        Some(watch) => Ok(HttpResponse::Created().json(watch)),
+        None => Ok(HttpResponse::Conflict().json(ErrorInformation {
+            error: "Conflict".to_string(),
+            message: format!("A watch named '{name}' already exists"),
+            details: String::new(),
+        })),
+    }
+}
+
+fn validate(watch: &NewWatch) -> Option<ErrorInformation> {
+    let error = |error: &str, message: &str| {
+        Some(ErrorInformation {
+            error: error.to_string(),
+            message: message.to_string(),
+            details: String::new(),
+        })
+    };
+
+    if watch.name.trim().is_empty() {
+        return error("MissingName", "A watch requires a name");
+    }
+    if watch.target == SearchTarget::Cve {
+        return error("UnsupportedTarget", "Only SBOMs and advisories can be watched");
+    }
+    if let Some(notify) = &watch.notify {
+        if !matches!(
+            url::Url::parse(notify).map(|url| url.scheme().to_string()).as_deref(),
+            Ok("http" | "https")
+        ) {
+            return error("InvalidUrl", "The notification target must be an HTTP(S) URL");
+        }
+    }
+    None
+}
+
+/// Delete a watch of the current user, along with its hits.
+#[utoipa::path(
+    delete,
+    tag = "search",
+    path = "/api/v1/watches/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Watch was deleted"),
+        (status = NOT_FOUND, description = "Watch was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the watch"),
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn delete_watch(
+    db: web::Data<Db>,
+    user: UserInformation,
+    id: web::Path<i64>,
+) -> actix_web::Result<HttpResponse> {
+    match db.delete_watch(owner(&user), *id).await.map_err(Error::from)? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct HitsParams {
+    /// Offset to start from returning hits
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of hits to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Get the documents which matched a watch of the current user, most recent first.
+#[utoipa::path(
+    get,
+    tag = "search",
+    path = "/api/v1/watches/{id}/hits",
+    responses(
+        (status = OK, description = "Hits of the watch", body = Vec<WatchHit>),
+        (status = NOT_FOUND, description = "Watch was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the watch"),
+        HitsParams,
+    )
+)]
+#[instrument(skip(db), err)]
+pub async fn get_watch_hits(
+    db: web::Data<Db>,
+    user: UserInformation,
+    id: web::Path<i64>,
+    params: web::Query<HitsParams>,
+) -> actix_web::Result<HttpResponse> {
+    match db
+        .get_watch_hits(owner(&user), *id, params.offset, params.limit)
+        .await
+        .map_err(Error::from)?
+    {
+        Some(hits) => Ok(HttpResponse::Ok().json(hits)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn watch(target: SearchTarget, notify: Option<&str>) -> NewWatch {
+        NewWatch {
+            name: "critical".to_string(),
+            query: "is:critical".to_string(),
+            target,
+            notify: notify.map(|notify| notify.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&watch(SearchTarget::Advisory, None)).is_none());
+        assert!(validate(&watch(SearchTarget::Sbom, Some("https://example.com/hook"))).is_none());
+        assert!(validate(&watch(SearchTarget::Cve, None)).is_some());
+        assert!(validate(&watch(SearchTarget::Sbom, Some("file:///etc/passwd"))).is_some());
+        assert!(validate(&watch(SearchTarget::Sbom, Some("not a url"))).is_some());
+    }
+}
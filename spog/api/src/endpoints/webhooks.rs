@@ -0,0 +1,246 @@
+use crate::db::Db;
+use crate::error::Error;
+use actix_web::{web, web::ServiceConfig, HttpResponse};
+use spog_model::webhook::{NewWebhook, Webhook, WebhookDelivery};
+use std::sync::Arc;
+use tracing::instrument;
+use trustification_auth::authenticator::{user::UserInformation, Authenticator};
+use trustification_auth::{authorizer::Authorizer, Permission};
+use trustification_common::error::ErrorInformation;
+use trustification_infrastructure::new_auth;
+use utoipa::IntoParams;
+use vexination_model::severity::Severity;
+
+pub(crate) fn configure(auth: Option<Arc<Authenticator>>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        config.service(
+            web::resource("/api/v1/webhooks")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::get().to(get_webhooks))
+                .route(web::post().to(create_webhook)),
+        );
+        config.service(
+            web::resource("/api/v1/webhooks/{id}")
+                .wrap(new_auth!(auth.clone()))
+                .route(web::delete().to(delete_webhook)),
+        );
+        config.service(
+            web::resource("/api/v1/webhooks/{id}/deliveries")
+                .wrap(new_auth!(auth))
+                .route(web::get().to(get_deliveries)),
+        );
+    }
+}
+
+/// Get all webhooks.
+#[utoipa::path(
+    get,
+    tag = "webhook",
+    path = "/api/v1/webhooks",
+    responses(
+        (status = OK, description = "Registered webhooks", body = Vec<Webhook>),
+        (status = FORBIDDEN, description = "The user may not manage webhooks"),
+    )
+)]
+#[instrument(skip(db, authorizer), err)]
+pub async fn get_webhooks(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageWebhooks)?;
+
+    let webhooks = db.get_webhooks().await.map_err(Error::from)?;
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+/// Register a webhook.
+///
+/// The webhook gets notified whenever advisories matching its filter get ingested. Notifications are signed using the
+/// secret of the webhook, and retried if they can't be delivered.
+#[utoipa::path(
+    post,
+    tag = "webhook",
+    path = "/api/v1/webhooks",
+    request_body = NewWebhook,
+    responses(
+        (status = CREATED, description = "Webhook was registered", body = Webhook),
+        (status = BAD_REQUEST, description = "The webhook has no name or secret, an invalid URL, or an unknown severity"),
+        (status = FORBIDDEN, description = "The user may not manage webhooks"),
+        (status = CONFLICT, description = "A webhook with the same name already exists"),
+    )
+)]
+#[instrument(skip(db, authorizer, webhook), err)]
+pub async fn create_webhook(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    web::Json(webhook): web::Json<NewWebhook>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageWebhooks)?;
+
+    if let Some(error) = validate(&webhook) {
+        return Ok(HttpResponse::BadRequest().json(error));
+    }
+
+    let name = webhook.name.clone();
+    match db.insert_webhook(webhook).await.map_err(Error::from)? {
+        Some(webhook) => Ok(HttpResponse::Created().json(webhook)),
+        None => Ok(HttpResponse::Conflict().json(ErrorInformation {
+            error: "Conflict".to_string(),
+            message: format!("A webhook named '{name}' already exists"),
+            details: String::new(),
+        })),
+    }
+}
+
+fn validate(webhook: &NewWebhook) -> Option<ErrorInformation> {
+    let error = |error: &str, message: String| {
+        Some(ErrorInformation {
+            error: error.to_string(),
+            message,
+            details: String::new(),
+        })
+    };
+
+    if webhook.name.trim().is_empty() {
+        return error("MissingName", "A webhook requires a name".to_string());
+    }
+    if webhook.secret.is_empty() {
+        return error("MissingSecret", "A webhook requires a secret".to_string());
+    }
+    if !matches!(
+        url::Url::parse(&webhook.url)
+            .map(|url| url.scheme().to_string())
+            .as_deref(),
+        Ok("http" | "https")
+    ) {
+        return error("InvalidUrl", "The webhook must be an HTTP(S) URL".to_string());
+    }
+    if let Some(severity) = webhook
+        .filter
+        .severities
+        .iter()
+        .find(|severity| Severity::normalize(severity).is_none())
+    {
+        return error("UnknownSeverity", format!("Unknown severity: {severity}"));
+    }
+    // the values are used as part of a query
+    if webhook.filter.products.iter().any(|product| product.contains('"')) {
+        return error("InvalidProduct", "Products must not contain quotes".to_string());
+    }
+    None
+}
+
+/// Delete a webhook, along with its deliveries.
+#[utoipa::path(
+    delete,
+    tag = "webhook",
+    path = "/api/v1/webhooks/{id}",
+    responses(
+        (status = NO_CONTENT, description = "Webhook was deleted"),
+        (status = FORBIDDEN, description = "The user may not manage webhooks"),
+        (status = NOT_FOUND, description = "Webhook was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the webhook"),
+    )
+)]
+#[instrument(skip(db, authorizer), err)]
+pub async fn delete_webhook(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    id: web::Path<i64>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageWebhooks)?;
+
+    match db.delete_webhook(*id).await.map_err(Error::from)? {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct DeliveriesParams {
+    /// Index of the first delivery to return
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of deliveries to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Get the deliveries of a webhook, most recent first, reporting their status.
+#[utoipa::path(
+    get,
+    tag = "webhook",
+    path = "/api/v1/webhooks/{id}/deliveries",
+    responses(
+        (status = OK, description = "Deliveries of the webhook", body = Vec<WebhookDelivery>),
+        (status = FORBIDDEN, description = "The user may not manage webhooks"),
+        (status = NOT_FOUND, description = "Webhook was not found"),
+    ),
+    params(
+        ("id" = i64, Path, description = "ID of the webhook"),
+        DeliveriesParams,
+    )
+)]
+#[instrument(skip(db, authorizer), err)]
+pub async fn get_deliveries(
+    db: web::Data<Db>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+    id: web::Path<i64>,
+    web::Query(params): web::Query<DeliveriesParams>,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ManageWebhooks)?;
+
+    match db
+        .get_deliveries(*id, params.offset, params.limit.min(1000))
+        .await
+        .map_err(Error::from)?
+    {
+        Some(deliveries) => Ok(HttpResponse::Ok().json(deliveries)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spog_model::webhook::WebhookFilter;
+
+    fn webhook() -> NewWebhook {
+        NewWebhook {
+            name: "critical".to_string(),
+            url: "https://example.com/hook".to_string(),
+            secret: "secret".to_string(),
+            filter: WebhookFilter {
+                severities: vec!["Critical".to_string(), "important".to_string()],
+                products: vec!["cpe:/o:redhat:rhel_eus:8.6::baseos".to_string()],
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&webhook()).is_none());
+
+        let mut invalid = webhook();
+        invalid.url = "ftp://example.com/hook".to_string();
+        assert_eq!(validate(&invalid).unwrap().error, "InvalidUrl");
+
+        let mut invalid = webhook();
+        invalid.secret = String::new();
+        assert_eq!(validate(&invalid).unwrap().error, "MissingSecret");
+
+        let mut invalid = webhook();
+        invalid.filter.severities.push("catastrophic".to_string());
+        assert_eq!(validate(&invalid).unwrap().error, "UnknownSeverity");
+    }
+}
@@ -1,5 +1,5 @@
 use crate::service::{collectorist, guac, v11y};
-use actix_web::{http::header::ContentType, HttpResponse};
+use actix_web::{http::header::ContentType, HttpResponse, ResponseError};
 use http::StatusCode;
 use trustification_common::error::ErrorInformation;
 
@@ -23,15 +23,21 @@ pub enum Error {
     V11y(#[from] v11y::Error),
     #[error(transparent)]
     PackageUrl(#[from] packageurl::Error),
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    BadRequest(String),
     #[error("{0}")]
     Generic(String),
 }
 
-impl actix_web::error::ResponseError for Error {
+impl ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::Response(status, _) => *status,
             Self::PackageUrl(_) => StatusCode::BAD_REQUEST,
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::V11y(error) => error.status_code(),
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -84,6 +90,16 @@ impl actix_web::error::ResponseError for Error {
                 message: "Invalid package URL syntax".to_string(),
                 details: error.to_string(),
             }),
+            Self::Database(error) => res.json(ErrorInformation {
+                error: "Database".to_string(),
+                message: "Error accessing the database".to_string(),
+                details: error.to_string(),
+            }),
+            Self::BadRequest(error) => res.json(ErrorInformation {
+                error: "BadRequest".to_string(),
+                message: error.clone(),
+                details: error.to_string(),
+            }),
             Self::Generic(error) => res.json(ErrorInformation {
                 error: "Generic".to_string(),
                 message: error.clone(),
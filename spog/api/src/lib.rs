@@ -1,13 +1,20 @@
+mod alias;
 mod analytics;
 mod app_state;
+mod cache;
 mod config;
+mod consumer;
+mod db;
 mod endpoints;
 mod error;
 mod openapi;
 mod search;
 mod server;
 mod service;
+mod subscription;
 mod utils;
+mod watch;
+mod webhook;
 
 use hide::Hide;
 use std::process::ExitCode;
@@ -17,6 +24,7 @@ use trustification_auth::{
     auth::AuthConfigArguments, client::OpenIdTokenProviderConfigArguments, swagger_ui::SwaggerUiOidcConfig,
 };
 use trustification_common::tls::ClientConfig;
+use trustification_event_bus::EventBusConfig;
 use trustification_infrastructure::{
     app::http::HttpServerConfig,
     endpoint::{self, Endpoint, SpogApi},
@@ -65,13 +73,101 @@ pub struct Run {
     #[arg(long = "crda-payload-limit", env, default_value_t = DEFAULT_CRDA_PAYLOAD_LIMIT)]
     pub crda_payload_limit: usize,
 
+    /// Maximum number of parallel advisory lookups when enriching SBOM search results
+    #[arg(long = "advisory-search-concurrency", env, default_value_t = 4)]
+    pub advisory_search_concurrency: usize,
+
+    /// Timeout of a single advisory lookup when enriching SBOM search results
+    #[arg(long = "advisory-search-timeout", env, default_value = "5s")]
+    pub advisory_search_timeout: humantime::Duration,
+
+    /// Time advisory counts of SBOM search results are cached, zero disables the cache
+    #[arg(long = "advisory-cache-ttl", env, default_value = "5m")]
+    pub advisory_cache_ttl: humantime::Duration,
+
+    /// Topic of new advisory events, invalidating the advisory cache (e.g. "vex-indexed")
+    #[arg(long = "advisory-cache-invalidation-topic", env)]
+    pub advisory_cache_invalidation_topic: Option<String>,
+
+    /// Consumer group invalidating the advisory cache, defaulting to the event group followed by the host name
+    ///
+    /// Each instance has its own cache, so every instance must use a different group, which stays the same on restarts.
+    #[arg(long = "advisory-cache-group", env)]
+    pub advisory_cache_group: Option<String>,
+
+    /// Prefix of the consumer groups of events, like the ones evaluating watches
+    ///
+    /// Each instance has its own database, so instances not sharing a database must use different groups.
+    #[arg(long = "event-group", env, default_value = "spog-api")]
+    pub event_group: String,
+
+    /// Topic of indexed SBOM events, evaluating watches of SBOMs (e.g. "sbom-indexed")
+    #[arg(long = "watch-sbom-topic", env)]
+    pub watch_sbom_topic: Option<String>,
+
+    /// Topic of indexed advisory events, evaluating watches of advisories (e.g. "vex-indexed")
+    #[arg(long = "watch-advisory-topic", env)]
+    pub watch_advisory_topic: Option<String>,
+
+    /// Topic of indexed advisory events, notifying webhooks (e.g. "vex-indexed")
+    #[arg(long = "webhook-advisory-topic", env)]
+    pub webhook_advisory_topic: Option<String>,
+
+    /// Topic of indexed vulnerability events, notifying subscribers of CVEs and packages (e.g. "v11y-indexed")
+    #[arg(long = "subscription-topic", env)]
+    pub subscription_topic: Option<String>,
+
+    /// Topic of indexed vulnerability events, recording the aliases of vulnerabilities (e.g. "v11y-indexed")
+    #[arg(long = "alias-vulnerability-topic", env)]
+    pub alias_vulnerability_topic: Option<String>,
+
+    /// Topic of indexed advisory events, recording the CVEs of advisories as their aliases (e.g. "vex-indexed")
+    #[arg(long = "alias-advisory-topic", env)]
+    pub alias_advisory_topic: Option<String>,
+
+    /// Default sort order of advisory searches not requesting one (e.g. "release:desc"), relevance if missing
+    #[arg(long = "advisory-default-sort", env)]
+    pub advisory_default_sort: Option<String>,
+
+    /// Time the dashboard summary is cached, zero disables the cache
+    #[arg(long = "dashboard-cache-ttl", env, default_value = "1m")]
+    pub dashboard_cache_ttl: humantime::Duration,
+
+    /// PEM encoded public keys, trusted to sign attestations
+    #[arg(
+        long = "attestation-public-key",
+        env = "ATTESTATION_PUBLIC_KEYS",
+        value_delimiter = ','
+    )]
+    pub attestation_public_keys: Vec<PathBuf>,
+
+    /// OCI registries to look up attestations of the image an SBOM describes in, by host (and port)
+    ///
+    /// Images of other registries are not looked up, and tokens are only requested from authentication realms on
+    /// those hosts. No registries disables the lookup.
+    #[arg(long = "attestation-registry", env = "ATTESTATION_REGISTRIES", value_delimiter = ',')]
+    pub attestation_registries: Vec<String>,
+
+    /// Base URL identifying the publisher of generated VEX documents (e.g. "https://spog.example.com")
+    ///
+    /// Generating and merging VEX documents is disabled if missing.
+    #[arg(long = "vex-namespace", env)]
+    pub vex_namespace: Option<Url>,
+
     #[arg(long = "snyk-token", env)]
     pub snyk_token: Option<Hide<String>>,
 
+    /// Base path to the database store. Defaults to the local directory.
+    #[arg(env, long = "storage-base")]
+    pub storage_base: Option<PathBuf>,
+
     /// Path to the UI configuration file, overriding the default configuration file.
     #[arg(short, long = "config", env = "SPOG_UI_CONFIG")]
     pub config: Option<PathBuf>,
 
+    #[command(flatten)]
+    pub bus: EventBusConfig,
+
     #[command(flatten)]
     pub infra: InfrastructureConfig,
 
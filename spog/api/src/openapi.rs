@@ -8,8 +8,11 @@ use v11y_model::search::SearchHitWithDocument;
     SearchResultVex = LocalSearchResult<Vec<AdvisorySummary>>,
     SearchResultCve = LocalSearchResult<Vec<SearchHitWithDocument>>,
     SearchResultPackage = LocalSearchResult<Vec<PackageInfo>>,
+    SearchResultSbomVulnerabilitySummary = LocalSearchResult<Vec<SbomVulnerabilitySummary>>,
 )]
 pub struct LocalSearchResult<T> {
     pub result: T,
     pub total: Option<usize>,
+    pub facets: std::collections::BTreeMap<String, Vec<trustification_api::search::FacetCount>>,
+    pub cursor: Option<String>,
 }
@@ -1,4 +1,9 @@
-use serde::Deserialize;
+use crate::error::Error;
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use bytes::Bytes;
+use futures::stream::try_unfold;
+use futures::{Future, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use utoipa::IntoParams;
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -21,3 +26,188 @@ const fn default_offset() -> usize {
 const fn default_limit() -> usize {
     100
 }
+
+/// The maximum number of results fetched at once when streaming results
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// The content type of newline delimited JSON
+pub const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct StreamParams {
+    /// Stream all results as newline delimited JSON, using `limit` as page size, up to 1000.
+    ///
+    /// The same can be requested using an `Accept` header of `application/x-ndjson`.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+impl StreamParams {
+    /// Check if the client requested results to be streamed
+    pub fn enabled(&self, req: &HttpRequest) -> bool {
+        self.stream
+            || req
+                .headers()
+                .get_all(header::ACCEPT)
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|value| value.split(';').next().map(str::trim) == Some(NDJSON))
+    }
+}
+
+/// Fetch search results page by page.
+///
+/// Pages are fetched starting at `offset`, until a page comes back short. Only a single page is held in memory at
+/// a time, which is why the page size is capped at [`MAX_PAGE_SIZE`].
+pub fn pages<T, F, Fut>(offset: usize, page_size: usize, fetch: F) -> impl Stream<Item = Result<Vec<T>, Error>>
+where
+    F: Fn(usize, usize) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Error>>,
+{
+    let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+    try_unfold(Some(offset), move |offset| {
+        let page = offset.map(|offset| (offset, fetch(offset, page_size)));
+        async move {
+            let Some((offset, page)) = page else {
+                return Ok(None);
+            };
+            let page = page.await?;
+
+            let next = match page.len() < page_size {
+                true => None,
+                false => Some(offset + page.len()),
+            };
+
+            Ok(Some((page, next)))
+        }
+    })
+}
+
+/// Stream search results as newline delimited JSON.
+pub fn ndjson<T, F, Fut>(offset: usize, page_size: usize, fetch: F) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    T: Serialize,
+    F: Fn(usize, usize) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Error>>,
+{
+    pages(offset, page_size, fetch).map(|page| {
+        let mut buf = Vec::new();
+        for item in page? {
+            serde_json::to_writer(&mut buf, &item)?;
+            buf.push(b'\n');
+        }
+        Ok(Bytes::from(buf))
+    })
+}
+
+/// Create a streaming response of newline delimited JSON.
+pub fn ndjson_response<S>(stream: S) -> HttpResponse
+where
+    S: Stream<Item = Result<Bytes, Error>> + 'static,
+{
+    HttpResponse::Ok().content_type(NDJSON).streaming(stream)
+}
+
+/// File formats search results can be exported to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ExportParams {
+    /// The format of the exported file, defaults to `csv`
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub format: ExportFormat,
+}
+
+/// The content type of comma separated values
+pub const CSV: &str = "text/csv";
+
+/// Stream search results as CSV, starting with a header row.
+pub fn csv<T, const N: usize>(
+    header: [&'static str; N],
+    pages: impl Stream<Item = Result<Vec<T>, Error>>,
+    row: fn(&T) -> [String; N],
+) -> impl Stream<Item = Result<Bytes, Error>> {
+    let header = futures::stream::once(async move { csv_records([header]) });
+    header.chain(pages.map(move |page| csv_records(page?.iter().map(row))))
+}
+
+fn csv_records<R, I>(records: R) -> Result<Bytes, Error>
+where
+    R: IntoIterator<Item = I>,
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .write_record(record)
+            .map_err(|err| Error::Generic(format!("Unable to write CSV: {err}")))?;
+    }
+    let buf = writer
+        .into_inner()
+        .map_err(|err| Error::Generic(format!("Unable to write CSV: {err}")))?;
+    Ok(Bytes::from(buf))
+}
+
+/// Create a streaming response of a CSV file.
+pub fn csv_response<S>(name: &str, stream: S) -> HttpResponse
+where
+    S: Stream<Item = Result<Bytes, Error>> + 'static,
+{
+    HttpResponse::Ok()
+        .content_type(CSV)
+        .append_header((
+            header::CONTENT_DISPOSITION,
+            format!(r#"attachment; filename="{name}.csv""#),
+        ))
+        .streaming(stream)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_ndjson_pages() {
+        let pages: Vec<Bytes> = ndjson(1, 2, |offset, limit| async move {
+            Ok((offset..(offset + limit).min(6)).collect::<Vec<_>>())
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(
+            pages,
+            vec![Bytes::from("1\n2\n"), Bytes::from("3\n4\n"), Bytes::from("5\n")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_page_size_capped() {
+        let limits: Vec<usize> = pages(0, usize::MAX, |_, limit| async move { Ok(vec![limit]) })
+            .map_ok(|page| page[0])
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(limits, vec![MAX_PAGE_SIZE]);
+    }
+
+    #[tokio::test]
+    async fn test_csv() {
+        let pages = futures::stream::iter([Ok(vec![(1, "a")]), Ok(vec![(2, "b, c")])]);
+        let rows: Vec<Bytes> = csv(["id", "name"], pages, |(id, name)| [id.to_string(), name.to_string()])
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(rows.concat(), b"id,name\n1,a\n2,\"b, c\"\n");
+    }
+}
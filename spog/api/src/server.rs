@@ -1,22 +1,27 @@
 use crate::{
+    alias,
     app_state::AppState,
+    cache::{AdvisoryCache, TimedCache},
     config,
+    db::Db,
     endpoints::{
         self,
         analyze::{self, CrdaClient},
         wellknown::endpoints::Endpoints,
     },
     service::{collectorist::CollectoristService, guac::GuacService, v11y::V11yService},
-    Run,
+    subscription, watch, webhook, Run,
 };
 use actix_web::web;
 use futures::future::select_all;
 use hide::Hide;
+use spog_model::saved::SearchTarget;
 use std::future::Future;
 use std::pin::Pin;
 use std::{net::TcpListener, sync::Arc};
 use trustification_analytics::Tracker;
 use trustification_auth::{authenticator::Authenticator, authorizer::Authorizer, swagger_ui::SwaggerUiOidc};
+use trustification_common::reqwest::ClientFactory;
 use trustification_infrastructure::{app::http::HttpServerBuilder, MainContext};
 use trustification_version::version;
 use utoipa::OpenApi;
@@ -33,14 +38,99 @@ impl Server {
 
     pub async fn run(self, context: MainContext<()>, listener: Option<TcpListener>) -> anyhow::Result<()> {
         let provider = self.run.oidc.into_provider_or_devmode(self.run.devmode).await?;
+        let attestation_keys = endpoints::sbom::load_attestation_keys(&self.run.attestation_public_keys)?;
         let state = web::Data::new(AppState {
             client: self.run.client.build_client()?,
             bombastic: self.run.bombastic_url.clone(),
             vexination: self.run.vexination_url.clone(),
             exhort: self.run.exhort_url.clone(),
             provider: provider.clone(),
+            advisory_search_concurrency: self.run.advisory_search_concurrency.max(1),
+            advisory_search_timeout: self.run.advisory_search_timeout.into(),
+            advisory_cache: AdvisoryCache::new(self.run.advisory_cache_ttl.into()),
+            dashboard_cache: TimedCache::new(self.run.dashboard_cache_ttl.into()),
+            attestation_keys,
+            attestation_registries: self.run.attestation_registries.clone(),
+            advisory_default_sort: self.run.advisory_default_sort.clone(),
+            vex_namespace: self
+                .run
+                .vex_namespace
+                .as_ref()
+                .map(|url| url.as_str().trim_end_matches('/').to_string()),
         });
 
+        let db = web::Data::new(Db::new(self.run.storage_base.unwrap_or_else(|| ".".into())).await?);
+
+        let watch_topics: Vec<_> = [
+            (self.run.watch_sbom_topic, SearchTarget::Sbom),
+            (self.run.watch_advisory_topic, SearchTarget::Advisory),
+        ]
+        .into_iter()
+        .filter_map(|(topic, target)| topic.map(|topic| (topic, target)))
+        .collect();
+        let alias_topics: Vec<_> = [
+            (self.run.alias_vulnerability_topic, SearchTarget::Cve),
+            (self.run.alias_advisory_topic, SearchTarget::Advisory),
+        ]
+        .into_iter()
+        .filter_map(|(topic, target)| topic.map(|topic| (topic, target)))
+        .collect();
+
+        // the event bus registers its metrics, so it must only be created once
+        let bus = match self.run.advisory_cache_invalidation_topic.is_some()
+            || !watch_topics.is_empty()
+            || self.run.webhook_advisory_topic.is_some()
+            || self.run.subscription_topic.is_some()
+            || !alias_topics.is_empty()
+        {
+            true => Some(Arc::new(self.run.bus.create(context.metrics.registry()).await?)),
+            false => None,
+        };
+
+        let invalidation = match (self.run.advisory_cache_invalidation_topic, &bus) {
+            (Some(topic), Some(bus)) => {
+                let bus = bus.clone();
+                let state = state.clone();
+                let group = self.run.advisory_cache_group.unwrap_or_else(|| {
+                    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+                    format!("{}-advisory-cache-{host}", self.run.event_group)
+                });
+                Some(
+                    Box::pin(async move { state.advisory_cache.invalidate_on_events(&bus, &group, &topic).await })
+                        as Pin<Box<dyn Future<Output = anyhow::Result<()>>>>,
+                )
+            }
+            _ => None,
+        };
+
+        let webhooks = match (self.run.webhook_advisory_topic, &bus) {
+            (Some(topic), Some(bus)) => {
+                let bus = bus.clone();
+                let state = state.clone();
+                let db = db.clone();
+                let group = format!("{}-webhooks", self.run.event_group);
+                Some(
+                    Box::pin(async move { webhook::notify_on_events(&state, &db, &bus, &group, &topic).await })
+                        as Pin<Box<dyn Future<Output = anyhow::Result<()>>>>,
+                )
+            }
+            _ => None,
+        };
+
+        let watches = match (watch_topics.is_empty(), &bus) {
+            (false, Some(bus)) => {
+                let bus = bus.clone();
+                let state = state.clone();
+                let db = db.clone();
+                let group = format!("{}-watches", self.run.event_group);
+                Some(
+                    Box::pin(async move { watch::evaluate_on_events(&state, &db, &bus, &group, &watch_topics).await })
+                        as Pin<Box<dyn Future<Output = anyhow::Result<()>>>>,
+                )
+            }
+            _ => None,
+        };
+
         let config_configurator = config::configurator(self.run.config).await?;
 
         let (authn, authz) = self.run.auth.split(self.run.devmode)?.unzip();
@@ -89,6 +179,34 @@ impl Server {
             provider.clone(),
         ));
 
+        let subscriptions = match (self.run.subscription_topic, &bus) {
+            (Some(topic), Some(bus)) => {
+                let client = ClientFactory::from(&self.run.client);
+                let bus = bus.clone();
+                let v11y = v11y.clone();
+                let db = db.clone();
+                let group = format!("{}-subscriptions", self.run.event_group);
+                Some(Box::pin(
+                    async move { subscription::notify_on_events(&client, &v11y, &db, &bus, &group, &topic).await },
+                ) as Pin<Box<dyn Future<Output = anyhow::Result<()>>>>)
+            }
+            _ => None,
+        };
+
+        let aliases = match (alias_topics.is_empty(), &bus) {
+            (false, Some(bus)) => {
+                let bus = bus.clone();
+                let state = state.clone();
+                let v11y = v11y.clone();
+                let db = db.clone();
+                let group = format!("{}-aliases", self.run.event_group);
+                Some(Box::pin(
+                    async move { alias::record_on_events(&state, &v11y, &db, &bus, &group, &alias_topics).await },
+                ) as Pin<Box<dyn Future<Output = anyhow::Result<()>>>>)
+            }
+            _ => None,
+        };
+
         let (tracker, flusher) = Tracker::new(self.run.analytics);
         let tracker = web::Data::from(tracker);
 
@@ -103,14 +221,24 @@ impl Server {
                     .app_data(tracker.clone())
                     .app_data(v11y.clone())
                     .app_data(collectorist.clone())
+                    .app_data(db.clone())
                     .configure(endpoints::index::configure())
                     .configure(version::configurator(version!()))
                     .configure(endpoints::wellknown::endpoints::configurator(endpoints.clone()))
                     .configure(endpoints::sbom::configure(authenticator.clone()))
                     .configure(endpoints::advisory::configure(authenticator.clone()))
                     .configure(endpoints::cve::configure(authenticator.clone()))
+                    .configure(endpoints::dashboard::configure(authenticator.clone()))
+                    .configure(endpoints::federated::configure(authenticator.clone()))
                     .configure(endpoints::package::configure(authenticator.clone()))
+                    .configure(endpoints::product::configure(authenticator.clone()))
+                    .configure(endpoints::products::configure(authenticator.clone()))
                     .configure(endpoints::suggestion::configure(authenticator.clone()))
+                    .configure(endpoints::searches::configure(authenticator.clone()))
+                    .configure(endpoints::watches::configure(authenticator.clone()))
+                    .configure(endpoints::webhooks::configure(authenticator.clone()))
+                    .configure(endpoints::subscriptions::configure(authenticator.clone()))
+                    .configure(endpoints::alias::configure(authenticator.clone()))
                     .configure(config_configurator.clone())
                     .service({
                         let mut openapi = endpoints::ApiDoc::openapi();
@@ -123,6 +251,10 @@ impl Server {
                         swagger.url("/openapi.json", openapi)
                     });
 
+                if state.vex_namespace.is_some() {
+                    svc.configure(endpoints::vex::configure(authenticator.clone()));
+                }
+
                 if let Some(crda) = &crda {
                     svc.app_data(crda.clone())
                         .configure(analyze::configure(crda_payload_limit));
@@ -142,6 +274,11 @@ impl Server {
         let mut tasks = vec![http];
 
         tasks.extend(flusher);
+        tasks.extend(invalidation);
+        tasks.extend(watches);
+        tasks.extend(webhooks);
+        tasks.extend(subscriptions);
+        tasks.extend(aliases);
 
         // run all tasks
 
@@ -1,15 +1,15 @@
 use crate::search::QueryParams;
 use actix_web::body::BoxBody;
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use reqwest::Response;
 use std::sync::Arc;
 use tracing::instrument;
-use trustification_api::search::SearchResult;
+use trustification_api::search::{HistogramBucket, SearchField, SearchOptions, SearchResult};
 use trustification_auth::client::TokenProvider;
 use trustification_common::error::ErrorInformation;
 use url::Url;
 use v11y_client::search::{SearchDocument, SearchHit};
-use v11y_client::{V11yClient, Vulnerability};
+use v11y_client::{batch::CveBatch, epss::Epss, kev::KnownExploited, related::RelatedCve, V11yClient, Vulnerability};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -18,9 +18,20 @@ pub enum Error {
 }
 
 impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            // forward client errors of v11y, like an invalid or expired cursor
+            Error::Any(err) => err
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+                .filter(|status| status.is_client_error() || *status == StatusCode::GATEWAY_TIMEOUT)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
     fn error_response(&self) -> HttpResponse<BoxBody> {
         match self {
-            Error::Any(err) => HttpResponse::InternalServerError().json(ErrorInformation {
+            Error::Any(err) => HttpResponse::build(self.status_code()).json(ErrorInformation {
                 error: "V11yError".into(),
                 message: "Failed to contact v11y".into(),
                 details: err.to_string(),
@@ -45,16 +56,60 @@ impl V11yService {
         self.client.get_cve(id).await.map_err(Error::Any)
     }
 
+    #[instrument(skip(self, ids), fields(ids = ids.len()), err)]
+    pub async fn fetch_cves(&self, ids: &[String]) -> Result<CveBatch, Error> {
+        self.client.get_cves(ids).await.map_err(Error::Any)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn fetch_epss(&self, id: &str) -> Result<Option<Epss>, Error> {
+        self.client.get_epss(id).await.map_err(Error::Any)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn fetch_known_exploited(&self, id: &str) -> Result<Option<KnownExploited>, Error> {
+        self.client.get_known_exploited(id).await.map_err(Error::Any)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn fetch_related(&self, id: &str, limit: usize) -> Result<Option<Vec<RelatedCve>>, Error> {
+        self.client.get_related(id, limit).await.map_err(Error::Any)
+    }
+
     #[instrument(skip(self), err)]
     pub async fn fetch_by_alias(&self, alias: &str) -> Result<Vec<Vulnerability>, Error> {
         self.client.get_vulnerability_by_alias(alias).await.map_err(Error::Any)
     }
 
     #[instrument(skip(self), err)]
-    pub async fn search(&self, query: QueryParams) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, Error> {
+    pub async fn histogram(
+        &self,
+        q: &str,
+        field: &str,
+        interval: std::time::Duration,
+        offset: std::time::Duration,
+        facet: Option<&str>,
+    ) -> Result<Vec<HistogramBucket>, Error> {
+        self.client
+            .histogram(q, field, interval, offset, facet)
+            .await
+            .map_err(Error::Any)
+    }
+
+    #[instrument(skip(self), err)]
+    pub async fn search(
+        &self,
+        query: QueryParams,
+        options: SearchOptions,
+    ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, Error> {
         self.client
-            .search(&query.q, query.limit, query.offset)
+            .search_with_options(&query.q, query.limit, query.offset, &options)
             .await
             .map_err(Error::Any)
     }
+
+    #[instrument(skip(self), err)]
+    pub async fn search_fields(&self) -> Result<Vec<SearchField>, Error> {
+        self.client.search_fields().await.map_err(Error::Any)
+    }
 }
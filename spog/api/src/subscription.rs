@@ -0,0 +1,211 @@
+use crate::consumer::consume;
+use crate::db::Db;
+use crate::error::Error;
+use crate::search::QueryParams;
+use crate::service::v11y::V11yService;
+use crate::utils::target;
+use crate::watch::document_ids;
+use spog_model::subscription::{Subscription, SubscriptionKind, SubscriptionNotification};
+use std::collections::BTreeSet;
+use trustification_api::search::SearchOptions;
+use trustification_common::reqwest::ClientFactory;
+use trustification_event_bus::EventBus;
+use v11y_model::search::SearchDocument;
+
+/// maximum number of vulnerabilities evaluated by a single query
+const MAX_DOCUMENTS: usize = 50;
+
+/// Notify subscribers about vulnerabilities, whenever an event is received on the topic, until the consumer fails.
+///
+/// Events are expected to carry the storage events of the vulnerabilities indexed by v11y.
+pub async fn notify_on_events(
+    client: &ClientFactory,
+    v11y: &V11yService,
+    db: &Db,
+    bus: &EventBus,
+    group: &str,
+    topic: &str,
+) -> anyhow::Result<()> {
+    consume(bus, group, &[topic], |_, payload| async move {
+        let ids = document_ids(&payload);
+        match ids.is_empty() {
+            true => Ok(()),
+            false => evaluate(client, v11y, db, &ids).await,
+        }
+    })
+    .await
+}
+
+/// Match updated vulnerabilities against all subscriptions, recording a notification for each match.
+///
+/// A single query retrieves each chunk of vulnerabilities, which are then matched against all subscriptions.
+async fn evaluate(client: &ClientFactory, v11y: &V11yService, db: &Db, ids: &[String]) -> Result<(), Error> {
+    let subscriptions = db.get_all_subscriptions().await?;
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    for chunk in ids.chunks(MAX_DOCUMENTS) {
+        let q = QueryParams {
+            q: query(chunk),
+            offset: 0,
+            limit: chunk.len(),
+        };
+        let documents: Vec<_> = v11y
+            .search(q, SearchOptions::default())
+            .await?
+            .result
+            .into_iter()
+            .map(|hit| hit.document)
+            .collect();
+
+        for (owner, subscription) in &subscriptions {
+            let vulnerabilities: BTreeSet<_> = documents
+                .iter()
+                .filter(|document| matches(subscription, document))
+                .map(|document| document.id.clone())
+                .collect();
+            if vulnerabilities.is_empty() {
+                continue;
+            }
+
+            let vulnerabilities: Vec<_> = vulnerabilities.into_iter().collect();
+            db.insert_notifications(owner, subscription.id, &vulnerabilities)
+                .await?;
+            if let Some(url) = &subscription.notify {
+                notify(client, url, subscription, vulnerabilities).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the query of the vulnerabilities.
+///
+/// Rejected records match too, as their rejection is an update worth notifying about.
+fn query(ids: &[String]) -> String {
+    let documents = ids
+        .iter()
+        .map(|id| format!(r#"id:"{id}""#))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    format!("({documents}) (is:published OR is:rejected)")
+}
+
+/// Check if a vulnerability matches a subscription.
+///
+/// CVEs also match the records aliasing them, like GitHub advisories. Package URLs match the normalized package URLs
+/// of the affected packages, as subscriptions are normalized when created.
+fn matches(subscription: &Subscription, document: &SearchDocument) -> bool {
+    let value = &subscription.value;
+    match subscription.kind {
+        SubscriptionKind::Cve => {
+            document.id.eq_ignore_ascii_case(value)
+                || document.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(value))
+        }
+        SubscriptionKind::Purl => document.purls.iter().any(|purl| purl == value),
+    }
+}
+
+/// Notify the target of a subscription, failures are only logged.
+///
+/// The target is checked again, as it must still resolve to a public address. The client connects to the checked
+/// address, and doesn't follow redirects, which could lead anywhere.
+async fn notify(client: &ClientFactory, url: &str, subscription: &Subscription, vulnerabilities: Vec<String>) {
+    let notification = SubscriptionNotification {
+        subscription: subscription.clone(),
+        vulnerabilities,
+    };
+    let result = async {
+        let url = target::parse(url).map_err(|err| anyhow::anyhow!(err))?;
+        let (host, addr) = target::resolve(&url).await?;
+        let client = client
+            .new_builder()?
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(&host, addr)
+            .build()?;
+        client.post(url).json(&notification).send().await?.error_for_status()?;
+        Ok::<_, anyhow::Error>(())
+    };
+    if let Err(err) = result.await {
+        log::info!("Failed to notify target of subscription {}: {err}", subscription.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::OffsetDateTime;
+
+    fn subscription(kind: SubscriptionKind, value: &str) -> Subscription {
+        Subscription {
+            id: 1,
+            kind,
+            value: value.to_string(),
+            notify: None,
+            created: OffsetDateTime::now_utc(),
+        }
+    }
+
+    fn document(id: &str, aliases: &[&str], purls: &[&str]) -> SearchDocument {
+        SearchDocument {
+            id: id.to_string(),
+            published: true,
+            state: None,
+            title: None,
+            descriptions: vec![],
+            aliases: aliases.iter().map(ToString::to_string).collect(),
+            affected: vec![],
+            purls: purls.iter().map(ToString::to_string).collect(),
+            cvss3x_score: None,
+            cvss4_score: None,
+            cwes: vec![],
+            date_published: None,
+            date_updated: None,
+            indexed_timestamp: None,
+            epss: None,
+            known_exploited: None,
+            exploit_available: false,
+            exploits: vec![],
+            cpe_matches: vec![],
+        }
+    }
+
+    #[test]
+    fn test_query() {
+        let ids = ["CVE-2023-44487".to_string(), "GHSA-QPPJ-FM5R-HXR3".to_string()];
+
+        assert_eq!(
+            query(&ids),
+            r#"(id:"CVE-2023-44487" OR id:"GHSA-QPPJ-FM5R-HXR3") (is:published OR is:rejected)"#
+        );
+        assert_eq!(
+            query(&ids[1..]),
+            r#"(id:"GHSA-QPPJ-FM5R-HXR3") (is:published OR is:rejected)"#
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let cve = document("CVE-2023-44487", &[], &[]);
+        let ghsa = document(
+            "GHSA-QPPJ-FM5R-HXR3",
+            &["CVE-2023-44487"],
+            &["pkg:golang/golang.org/x/net"],
+        );
+
+        let subscription_cve = subscription(SubscriptionKind::Cve, "CVE-2023-44487");
+        assert!(matches(&subscription_cve, &cve));
+        assert!(matches(&subscription_cve, &ghsa));
+        assert!(!matches(&subscription(SubscriptionKind::Cve, "CVE-2023-39325"), &ghsa));
+
+        let subscription_purl = subscription(SubscriptionKind::Purl, "pkg:golang/golang.org/x/net");
+        assert!(!matches(&subscription_purl, &cve));
+        assert!(matches(&subscription_purl, &ghsa));
+        assert!(!matches(
+            &subscription(SubscriptionKind::Purl, "pkg:golang/golang.org/x/text"),
+            &ghsa
+        ));
+    }
+}
@@ -1 +1,2 @@
 pub mod spdx;
+pub mod target;
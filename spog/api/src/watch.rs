@@ -0,0 +1,217 @@
+use crate::app_state::AppState;
+use crate::consumer::consume;
+use crate::db::Db;
+use crate::error::Error;
+use serde_json::Value;
+use spog_model::saved::SearchTarget;
+use spog_model::watch::{Watch, WatchNotification};
+use std::collections::BTreeSet;
+use trustification_api::search::SearchOptions;
+use trustification_auth::Permission;
+use trustification_event_bus::EventBus;
+
+/// maximum number of documents evaluated by a single query, which is also the maximum number of its hits
+const MAX_DOCUMENTS: usize = 50;
+
+/// Evaluate the watches of the index whenever an event is received on its topic, until the consumer fails.
+///
+/// Events are expected to carry the storage events of the indexed documents, as forwarded by the indexer.
+pub async fn evaluate_on_events(
+    state: &AppState,
+    db: &Db,
+    bus: &EventBus,
+    group: &str,
+    topics: &[(String, SearchTarget)],
+) -> anyhow::Result<()> {
+    let names: Vec<_> = topics.iter().map(|(topic, _)| topic.as_str()).collect();
+    consume(bus, group, &names, |topic, payload| async move {
+        let target = topics
+            .iter()
+            .find(|(name, _)| *name == topic)
+            .map(|(_, target)| *target);
+        let ids = document_ids(&payload);
+        match (target, ids.is_empty()) {
+            (Some(target), false) => evaluate(state, db, target, &ids).await,
+            _ => Ok(()),
+        }
+    })
+    .await
+}
+
+/// Match newly indexed documents against all watches of the index.
+async fn evaluate(state: &AppState, db: &Db, target: SearchTarget, ids: &[String]) -> Result<(), Error> {
+    let watches: Vec<_> = db
+        .get_watches_by_target(target)
+        .await?
+        .into_iter()
+        .filter(|(permissions, watch)| {
+            let permitted = may_read(permissions.as_deref(), target);
+            if !permitted {
+                log::debug!("Skipping watch {}, its owner may not read its documents", watch.id);
+            }
+            permitted
+        })
+        .map(|(_, watch)| watch)
+        .collect();
+    if watches.is_empty() {
+        return Ok(());
+    }
+
+    for chunk in ids.chunks(MAX_DOCUMENTS) {
+        let documents = chunk
+            .iter()
+            .map(|id| format!(r#"id:"{id}""#))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        for watch in &watches {
+            let q = format!("({}) ({documents})", watch.query);
+            let matched = match search(state, target, &q).await {
+                Ok(matched) => matched,
+                Err(err) => {
+                    // the query of a single watch may be broken, which must not affect other watches
+                    log::info!("Failed to evaluate watch {}: {err}", watch.id);
+                    continue;
+                }
+            };
+            if matched.is_empty() {
+                continue;
+            }
+
+            let documents = db.insert_watch_hits(watch.id, &matched).await?;
+            log::debug!("Watch {} matched {} new documents", watch.id, documents.len());
+            if let (Some(url), false) = (&watch.notify, documents.is_empty()) {
+                notify(state, url, watch, documents).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the owner of a watch may read the documents of its target, by the permissions recorded with the watch.
+///
+/// Queries run with the permissions of the service, so the permissions of the owner must be checked before. Watches
+/// registered without authentication have no permissions recorded, and may read everything.
+fn may_read(permissions: Option<&[String]>, target: SearchTarget) -> bool {
+    let required = match target {
+        SearchTarget::Sbom => Permission::ReadSbom,
+        SearchTarget::Advisory => Permission::ReadVex,
+        SearchTarget::Cve => Permission::ReadCve,
+    };
+    permissions
+        .map(|permissions| permissions.iter().any(|permission| permission == required.as_ref()))
+        .unwrap_or(true)
+}
+
+/// Run a query, returning the IDs of the matching documents.
+async fn search(state: &AppState, target: SearchTarget, q: &str) -> Result<Vec<String>, Error> {
+    let provider = state.provider.as_ref();
+    let ids: BTreeSet<_> = match target {
+        SearchTarget::Sbom => state
+            .search_sbom(q, 0, MAX_DOCUMENTS, SearchOptions::default(), provider)
+            .await?
+            .result
+            .into_iter()
+            .map(|hit| hit.document.id)
+            .collect(),
+        SearchTarget::Advisory => state
+            .search_vex(q, 0, MAX_DOCUMENTS, SearchOptions::default(), provider)
+            .await?
+            .result
+            .into_iter()
+            .map(|hit| hit.document.advisory_id)
+            .collect(),
+        // CVEs are not indexed through events
+        SearchTarget::Cve => BTreeSet::new(),
+    };
+    Ok(ids.into_iter().collect())
+}
+
+/// Notify the target of a watch, failures are only logged.
+async fn notify(state: &AppState, url: &str, watch: &Watch, documents: Vec<String>) {
+    let notification = WatchNotification {
+        watch: watch.clone(),
+        documents,
+    };
+    let result = state
+        .client
+        .post(url)
+        .json(&notification)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        log::info!("Failed to notify target of watch {}: {err}", watch.id);
+    }
+}
+
+/// Extract the IDs of the documents from a storage event.
+///
+/// Events replaying stored documents for reindexing them don't change any document, so they don't carry any IDs.
+pub(crate) fn document_ids(payload: &[u8]) -> Vec<String> {
+    let Ok(event) = serde_json::from_slice::<Value>(payload) else {
+        return vec![];
+    };
+    if event["Reindex"].as_bool() == Some(true) {
+        return vec![];
+    }
+
+    let ids: BTreeSet<_> = event["Records"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|record| record["s3"]["object"]["key"].as_str())
+        .filter_map(|key| urlencoding::decode(key).ok())
+        .filter_map(|key| {
+            key.strip_prefix("data/")
+                .or_else(|| key.strip_prefix("labels/"))
+                .map(|id| id.to_string())
+        })
+        // the values are used as part of a query
+        .filter(|id| !id.is_empty() && !id.contains('"'))
+        .collect();
+
+    ids.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_document_ids() {
+        let event = json!({
+            "Records": [
+                { "eventName": "s3:ObjectCreated:Put", "s3": { "object": { "key": "data/ubi9%3A9.2" } } },
+                { "eventName": "s3:ObjectCreated:Put", "s3": { "object": { "key": "labels/ubi9%3A9.2" } } },
+                { "eventName": "s3:ObjectCreated:Put", "s3": { "object": { "key": "attestations/ubi9%3A9.2" } } },
+                { "eventName": "s3:ObjectCreated:Put", "s3": { "object": { "key": "data/rhsa-2023_1441" } } },
+            ]
+        });
+        assert_eq!(
+            document_ids(&serde_json::to_vec(&event).unwrap()),
+            vec!["rhsa-2023_1441".to_string(), "ubi9:9.2".to_string()]
+        );
+
+        assert!(document_ids(b"not json").is_empty());
+
+        let event = json!({
+            "Records": [
+                { "eventName": "s3:ObjectCreated:Put", "s3": { "object": { "key": "data/ubi9%3A9.2" } } },
+            ],
+            "Reindex": true
+        });
+        assert!(document_ids(&serde_json::to_vec(&event).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_may_read() {
+        let permissions = vec!["read.sbom".to_string()];
+        assert!(may_read(Some(&permissions), SearchTarget::Sbom));
+        assert!(!may_read(Some(&permissions), SearchTarget::Advisory));
+        assert!(!may_read(Some(&[]), SearchTarget::Sbom));
+        assert!(may_read(None, SearchTarget::Advisory));
+    }
+}
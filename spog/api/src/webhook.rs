@@ -0,0 +1,258 @@
+use crate::app_state::AppState;
+use crate::consumer::consume;
+use crate::db::Db;
+use crate::error::Error;
+use crate::watch::document_ids;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use spog_model::webhook::{DeliveryStatus, Webhook, WebhookDelivery, WebhookFilter, WebhookNotification};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use time::OffsetDateTime;
+use trustification_api::search::SearchOptions;
+use trustification_event_bus::EventBus;
+
+/// maximum number of documents evaluated by a single query, which is also the maximum number of its hits
+const MAX_DOCUMENTS: usize = 50;
+/// maximum number of attempts to deliver a notification
+const MAX_ATTEMPTS: u32 = 5;
+/// delay before the first retry, doubled with every further attempt
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+/// timeout of a single attempt
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The header carrying the signature of a notification.
+pub const SIGNATURE_HEADER: &str = "X-Trustification-Signature";
+
+/// Notify webhooks about advisories, whenever an event is received on the topic, until the consumer fails.
+///
+/// Failed deliveries are retried in the background, with an increasing delay.
+pub async fn notify_on_events(
+    state: &AppState,
+    db: &Db,
+    bus: &EventBus,
+    group: &str,
+    topic: &str,
+) -> anyhow::Result<()> {
+    let events = consume(bus, group, &[topic], |_, payload| async move {
+        let ids = document_ids(&payload);
+        match ids.is_empty() {
+            true => Ok(()),
+            false => evaluate(state, db, &ids).await,
+        }
+    });
+    futures::try_join!(events, retry(state, db))?;
+    Ok(())
+}
+
+/// Periodically retry pending deliveries, whose delay has passed.
+async fn retry(state: &AppState, db: &Db) -> anyhow::Result<()> {
+    loop {
+        tokio::time::sleep(RETRY_DELAY).await;
+
+        let pending = match db.get_pending_deliveries().await {
+            Ok(pending) => pending,
+            Err(err) => {
+                log::warn!("Failed to get pending deliveries: {err}");
+                continue;
+            }
+        };
+        let webhooks = match db.get_webhooks().await {
+            Ok(webhooks) => webhooks,
+            Err(err) => {
+                log::warn!("Failed to get webhooks: {err}");
+                continue;
+            }
+        };
+        let now = OffsetDateTime::now_utc();
+        for (webhook_id, mut delivery) in pending {
+            if !due(&delivery, now) {
+                continue;
+            }
+            match webhooks.iter().find(|webhook| webhook.id == webhook_id) {
+                Some(webhook) => {
+                    if let Err(err) = deliver(state, db, webhook, &mut delivery).await {
+                        log::warn!("Failed to record delivery {}: {err}", delivery.id);
+                    }
+                }
+                None => log::debug!("Skipping delivery {} of deleted webhook {webhook_id}", delivery.id),
+            }
+        }
+    }
+}
+
+/// Check if the next attempt of a pending delivery is due.
+fn due(delivery: &WebhookDelivery, now: OffsetDateTime) -> bool {
+    match delivery.attempted {
+        None => true,
+        Some(attempted) => {
+            let delay = RETRY_DELAY * 2u32.saturating_pow(delivery.attempts.saturating_sub(1));
+            attempted + delay <= now
+        }
+    }
+}
+
+/// Match newly ingested advisories against all webhooks, and notify them.
+async fn evaluate(state: &AppState, db: &Db, ids: &[String]) -> Result<(), Error> {
+    let webhooks = db.get_webhooks().await?;
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    for chunk in ids.chunks(MAX_DOCUMENTS) {
+        for webhook in &webhooks {
+            let q = query(&webhook.filter, chunk);
+            let advisories: BTreeSet<_> = match state
+                .search_vex(&q, 0, MAX_DOCUMENTS, SearchOptions::default(), state.provider.as_ref())
+                .await
+            {
+                Ok(result) => result.result.into_iter().map(|hit| hit.document.advisory_id).collect(),
+                Err(err) => {
+                    log::info!("Failed to evaluate webhook {}: {err}", webhook.id);
+                    continue;
+                }
+            };
+            if advisories.is_empty() {
+                continue;
+            }
+
+            let advisories: Vec<_> = advisories.into_iter().collect();
+            let mut delivery = db.insert_delivery(webhook.id, &advisories).await?;
+            deliver(state, db, webhook, &mut delivery).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the query of the filter, limited to the advisories.
+fn query(filter: &WebhookFilter, ids: &[String]) -> String {
+    let any = |terms: Vec<String>| format!("({})", terms.join(" OR "));
+
+    let mut q = vec![any(ids.iter().map(|id| format!(r#"id:"{id}""#)).collect())];
+    if !filter.severities.is_empty() {
+        q.push(any(filter.severities.iter().map(|s| format!("severity:{s}")).collect()));
+    }
+    if !filter.products.is_empty() {
+        q.push(any(filter
+            .products
+            .iter()
+            .map(|p| format!(r#"package:"{p}""#))
+            .collect()));
+    }
+    q.join(" ")
+}
+
+/// Attempt to deliver a notification, recording the outcome.
+async fn deliver(state: &AppState, db: &Db, webhook: &Webhook, delivery: &mut WebhookDelivery) -> Result<(), Error> {
+    let Some(secret) = db.get_webhook_secret(webhook.id).await? else {
+        return Ok(());
+    };
+    let body = serde_json::to_vec(&WebhookNotification {
+        webhook: webhook.id,
+        delivery: delivery.id,
+        advisories: delivery.advisories.clone(),
+    })?;
+
+    let result = state
+        .client
+        .post(&webhook.url)
+        .timeout(TIMEOUT)
+        .header("content-type", "application/json")
+        .header(SIGNATURE_HEADER, signature(&secret, &body))
+        .body(body)
+        .send()
+        .await;
+
+    delivery.attempts += 1;
+    delivery.attempted = Some(OffsetDateTime::now_utc());
+    match result {
+        Ok(response) => {
+            delivery.response = Some(response.status().as_u16());
+            delivery.error = match response.status().is_success() {
+                true => None,
+                false => Some(format!("Unexpected response: {}", response.status())),
+            };
+        }
+        Err(err) => {
+            delivery.response = None;
+            delivery.error = Some(err.to_string());
+        }
+    }
+    delivery.status = match (delivery.error.is_none(), delivery.attempts >= MAX_ATTEMPTS) {
+        (true, _) => DeliveryStatus::Delivered,
+        (false, false) => DeliveryStatus::Pending,
+        (false, true) => DeliveryStatus::Failed,
+    };
+    if let Some(error) = &delivery.error {
+        log::info!(
+            "Failed to deliver {} to webhook {} (attempt {}): {error}",
+            delivery.id,
+            webhook.id,
+            delivery.attempts
+        );
+    }
+
+    db.update_delivery(delivery).await?;
+    Ok(())
+}
+
+/// Sign the body of a notification.
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let ids = ["RHSA-2023:1441".to_string(), "RHSA-2023:3408".to_string()];
+        assert_eq!(
+            query(&WebhookFilter::default(), &ids),
+            r#"(id:"RHSA-2023:1441" OR id:"RHSA-2023:3408")"#
+        );
+        assert_eq!(
+            query(
+                &WebhookFilter {
+                    severities: vec!["critical".to_string(), "high".to_string()],
+                    products: vec!["cpe:/o:redhat:rhel_eus:8.6::baseos".to_string()],
+                },
+                &ids[..1]
+            ),
+            r#"(id:"RHSA-2023:1441") (severity:critical OR severity:high) (package:"cpe:/o:redhat:rhel_eus:8.6::baseos")"#
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        // example of RFC 4231, test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_due() {
+        let now = OffsetDateTime::now_utc();
+        let delivery = |attempts, attempted| WebhookDelivery {
+            id: 1,
+            advisories: vec![],
+            status: DeliveryStatus::Pending,
+            attempts,
+            created: now,
+            attempted,
+            response: None,
+            error: None,
+        };
+        assert!(due(&delivery(0, None), now));
+        assert!(!due(&delivery(1, Some(now - Duration::from_secs(10))), now));
+        assert!(due(&delivery(1, Some(now - Duration::from_secs(30))), now));
+        assert!(!due(&delivery(2, Some(now - Duration::from_secs(30))), now));
+        assert!(due(&delivery(2, Some(now - Duration::from_secs(60))), now));
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Distribution of the time from the publication of CVEs to the release of the advisories addressing them.
+///
+/// Every vulnerability of an advisory counts once, ages are measured in days and are negative if an advisory was
+/// released before the CVE got published.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct AdvisoryAging {
+    /// Number of vulnerabilities measured
+    pub count: usize,
+    /// Number of vulnerabilities skipped, as their publication date is unknown
+    pub unknown: usize,
+    /// Number of advisories evaluated
+    pub advisories: usize,
+    /// More advisories matched than could be evaluated
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub median: Option<f64>,
+    /// The 90th percentile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p90: Option<f64>,
+    /// Number of vulnerabilities by age, in increasing order
+    pub buckets: Vec<AgingBucket>,
+}
+
+/// Number of vulnerabilities of an age range.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct AgingBucket {
+    /// The upper bound of the range in days, inclusive, unbounded if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_days: Option<u32>,
+    pub count: usize,
+}
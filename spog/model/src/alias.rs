@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// All known identifiers of the same vulnerability.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct VulnerabilityAliases {
+    /// The requested identifier, like `GHSA-qppj-fm5r-hxr3`
+    pub id: String,
+    /// The other identifiers, like `CVE-2023-44487` or `RHSA-2023:5838`
+    pub aliases: Vec<String>,
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The attestations of an SBOM, along with their verification status.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct AttestationReport {
+    pub attestations: Vec<Attestation>,
+    /// Problems looking up attestations, which were not fatal
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// A single in-toto attestation.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Attestation {
+    /// Where the attestation was found
+    pub source: AttestationSource,
+    /// The type of the predicate, e.g. `https://slsa.dev/provenance/v1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicate_type: Option<String>,
+    /// The subjects of the attestation, in the form of `<name>@<algorithm>:<digest>`
+    pub subjects: Vec<String>,
+    /// Outcome of the verification
+    pub status: VerificationStatus,
+    /// The reason for the status, unless the attestation was verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// The key ID of the signature which was verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttestationSource {
+    /// Attached to the SBOM when it was stored
+    Attached,
+    /// Found in the OCI registry of the image the SBOM describes
+    Registry,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    /// Signed by a trusted key, and referencing the SBOM or its subject
+    Verified,
+    /// Well formed and referencing the SBOM or its subject, but not signed by a trusted key
+    Unverified,
+    /// Malformed, or referencing a different subject
+    Invalid,
+}
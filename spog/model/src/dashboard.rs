@@ -0,0 +1,26 @@
+use crate::vuln::SbomVulnerabilitySummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Summary of the overall state, as shown on the dashboard.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct DashboardSummary {
+    /// Total number of SBOMs
+    pub total_sboms: usize,
+    /// Total number of advisories
+    pub total_advisories: usize,
+    /// Total number of CVEs
+    pub total_cves: usize,
+    /// Number of CVEs, by severity
+    pub cves_by_severity: BTreeMap<String, usize>,
+    /// When the most recent SBOM was ingested
+    #[schema(value_type = Option<String>)]
+    pub last_sbom_ingested: Option<OffsetDateTime>,
+    /// When the most recent advisory was ingested
+    #[schema(value_type = Option<String>)]
+    pub last_advisory_ingested: Option<OffsetDateTime>,
+    /// The most vulnerable products, most affected first
+    pub most_vulnerable: Vec<SbomVulnerabilitySummary>,
+}
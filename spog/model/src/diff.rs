@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Differences between two SBOMs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SbomDiff {
+    /// Components only present in the second SBOM
+    pub added: Vec<ComponentRef>,
+    /// Components only present in the first SBOM
+    pub removed: Vec<ComponentRef>,
+    /// Components present in both SBOMs, but with a different version
+    pub changed: Vec<VersionChange>,
+    /// Differences of the vulnerabilities affecting the SBOMs
+    pub vulnerabilities: VulnerabilityDiff,
+}
+
+/// A component of an SBOM, identified by its name or (version-less) package URL.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema, Serialize, Deserialize)]
+pub struct ComponentRef {
+    /// The normalized identity of the component
+    pub id: String,
+    /// The version of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// A version change of a component.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema, Serialize, Deserialize)]
+pub struct VersionChange {
+    /// The normalized identity of the component
+    pub id: String,
+    /// The version in the first SBOM
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// The version in the second SBOM
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+/// Vulnerabilities which got introduced or fixed between two SBOMs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct VulnerabilityDiff {
+    /// Vulnerability IDs only affecting the second SBOM
+    pub added: Vec<String>,
+    /// Vulnerability IDs only affecting the first SBOM
+    pub removed: Vec<String>,
+}
+
+/// Differences between two revisions of an advisory.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct AdvisoryDiff {
+    /// The first (older) revision
+    pub from: String,
+    /// The second (newer) revision
+    pub to: String,
+    /// Products only referenced by the second revision
+    pub products_added: Vec<String>,
+    /// Products only referenced by the first revision
+    pub products_removed: Vec<String>,
+    /// Vulnerabilities which got a different score
+    pub scores: Vec<ScoreChange>,
+    /// Product status statements which changed
+    pub statements: Vec<StatementChange>,
+}
+
+/// A change of the (highest) CVSS score of a vulnerability.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ScoreChange {
+    /// The vulnerability, by its CVE or title
+    pub vulnerability: String,
+    /// The score in the first revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<f64>,
+    /// The score in the second revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<f64>,
+}
+
+/// A change of the status of a product regarding a vulnerability.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema, Serialize, Deserialize)]
+pub struct StatementChange {
+    /// The vulnerability, by its CVE or title
+    pub vulnerability: String,
+    /// The product ID
+    pub product: String,
+    /// The status in the first revision, e.g. `known_affected`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// The status in the second revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
@@ -0,0 +1,25 @@
+use crate::search::SbomSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// SBOMs impacted by the vulnerabilities of an advisory.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct AdvisoryImpact {
+    /// The ID of the advisory
+    pub advisory: String,
+    /// The impacted SBOMs
+    pub products: Vec<ImpactedProduct>,
+    /// Number of impacted SBOMs, by the severity of the vulnerabilities affecting them
+    pub severities: BTreeMap<String, usize>,
+}
+
+/// An SBOM containing packages, or describing a product, affected by an advisory.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ImpactedProduct {
+    pub sbom: SbomSummary,
+    /// The vulnerabilities affecting the SBOM, by their CVE or title
+    pub vulnerabilities: Vec<String>,
+    /// Number of vulnerabilities affecting the SBOM, by severity
+    pub severities: BTreeMap<String, usize>,
+}
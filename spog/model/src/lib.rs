@@ -1,12 +1,33 @@
+pub mod aging;
+pub mod alias;
+pub mod attestation;
 pub mod config;
 pub mod csaf;
 pub mod cve;
+pub mod dashboard;
+pub mod diff;
+pub mod impact;
+pub mod license;
 pub mod package_info;
 pub mod pkg;
+pub mod product;
+pub mod related;
+pub mod saved;
 pub mod search;
+pub mod subscription;
 pub mod suggestion;
+pub mod tree;
+pub mod trend;
+pub mod validation;
+pub mod vex;
 pub mod vuln;
+pub mod watch;
+pub mod webhook;
 
 pub mod prelude {
-    pub use crate::{config::*, cve::*, package_info::*, pkg::*, search::*, suggestion::*, vuln::*};
+    pub use crate::{
+        aging::*, alias::*, attestation::*, config::*, cve::*, dashboard::*, diff::*, impact::*, license::*,
+        package_info::*, pkg::*, product::*, related::*, saved::*, search::*, subscription::*, suggestion::*, tree::*,
+        trend::*, validation::*, vex::*, vuln::*, watch::*, webhook::*,
+    };
 }
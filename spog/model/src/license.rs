@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Report of the licenses used by the components of an SBOM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct LicenseReport {
+    /// Unique licenses, referenced by the components
+    pub licenses: Vec<LicenseSummary>,
+    /// Components which don't carry any license information
+    pub missing: Vec<String>,
+    /// Components with license information which could not be resolved to SPDX licenses
+    pub unknown: Vec<UnknownLicense>,
+}
+
+/// A license, used by one or more components.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct LicenseSummary {
+    /// The SPDX license identifier
+    pub id: String,
+    /// The full name of the license
+    pub name: String,
+    /// If the license is considered a copyleft license
+    pub copyleft: bool,
+    /// If the license is approved by the OSI
+    pub osi_approved: bool,
+    /// Number of components using the license
+    pub components: usize,
+}
+
+/// A license which could not be resolved.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct UnknownLicense {
+    /// The component carrying the license information
+    pub component: String,
+    /// The license information, as found in the SBOM
+    pub license: String,
+}
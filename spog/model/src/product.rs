@@ -0,0 +1,84 @@
+use crate::search::{AdvisorySummary, SbomSummary};
+use crate::vuln::{SbomVulnerabilitySummary, SummaryEntry};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// SBOMs and advisories matching a product CPE.
+#[derive(Clone, Debug, Default, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ProductSearchResult {
+    /// The CPE the search was performed with, in its URI binding
+    pub cpe: String,
+    /// SBOMs declaring the product
+    pub sboms: Vec<SbomSummary>,
+    /// Total number of matching SBOMs
+    pub total_sboms: usize,
+    /// Advisories referencing the product
+    pub advisories: Vec<AdvisorySummary>,
+    /// Total number of matching advisories
+    pub total_advisories: usize,
+}
+
+/// The SBOMs of a product release to register.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct NewProduct {
+    /// A description of the product release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// IDs of the SBOMs making up the product release, like the ones of all of its container images
+    #[serde(default)]
+    pub sboms: Vec<String>,
+}
+
+/// A product release, grouping the SBOMs it is made of.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Product {
+    /// The name of the product
+    pub name: String,
+    /// The version of the product, unique per name
+    pub version: String,
+    /// A description of the product release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// IDs of the SBOMs making up the product release, sorted
+    pub sboms: Vec<String>,
+    pub created: OffsetDateTime,
+    pub updated: OffsetDateTime,
+}
+
+/// Vulnerabilities of a product release, rolled up from the reports of its SBOMs.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ProductVulnerabilityReport {
+    /// The name of the product
+    pub name: String,
+    /// The version of the product
+    pub version: String,
+    /// Number of distinct vulnerabilities affecting any SBOM of the product
+    pub total: usize,
+    /// Distinct vulnerabilities by severity
+    pub summary: Vec<SummaryEntry>,
+    /// Vulnerabilities affecting the product, most severe first
+    pub vulnerabilities: Vec<ProductVulnerability>,
+    /// Vulnerability counts of each SBOM of the product
+    pub sboms: Vec<SbomVulnerabilitySummary>,
+    /// SBOMs of the product which were not found, or didn't contain any packages describing their content
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
+
+/// A vulnerability affecting SBOMs of a product release.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct ProductVulnerability {
+    /// The ID of the vulnerability
+    pub id: String,
+    /// The CVSS score of the vulnerability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// The IDs of the SBOMs affected by the vulnerability
+    pub sboms: Vec<String>,
+    /// Number of distinct packages affected by the vulnerability, across all SBOMs
+    pub affected_packages: usize,
+    /// The vulnerability is known to be exploited in at least one of the SBOMs
+    #[serde(default)]
+    pub known_exploited: bool,
+}
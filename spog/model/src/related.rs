@@ -0,0 +1,13 @@
+use crate::search::SbomSummary;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An SBOM related to another SBOM.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct RelatedSbom {
+    pub sbom: SbomSummary,
+    /// If both SBOMs describe the same product, having the same CPE
+    pub same_product: bool,
+    /// The share of package URLs of the original SBOM, which are also part of this SBOM (between 0 and 1)
+    pub similarity: f64,
+}
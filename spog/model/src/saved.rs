@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// The index a saved search runs against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTarget {
+    Sbom,
+    Advisory,
+    Cve,
+}
+
+impl SearchTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sbom => "sbom",
+            Self::Advisory => "advisory",
+            Self::Cve => "cve",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sbom" => Some(Self::Sbom),
+            "advisory" => Some(Self::Advisory),
+            "cve" => Some(Self::Cve),
+            _ => None,
+        }
+    }
+}
+
+/// A search query to be saved.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct NewSavedSearch {
+    /// The name of the search, unique per user
+    pub name: String,
+    /// The sikula query
+    pub query: String,
+    /// The index to run the query against
+    pub target: SearchTarget,
+}
+
+/// A search query, saved by a user.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    /// The name of the search, unique per user
+    pub name: String,
+    /// The sikula query
+    pub query: String,
+    /// The index to run the query against
+    pub target: SearchTarget,
+    pub created: OffsetDateTime,
+}
@@ -1,6 +1,9 @@
+use bombastic_model::quality::Quality;
 use serde_json::Value;
 use std::collections::HashMap;
 use time::OffsetDateTime;
+use v11y_model::search::SearchDocument;
+use vexination_model::search::AdvisoryRemediation;
 
 #[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
 pub struct AdvisorySummary {
@@ -14,6 +17,9 @@ pub struct AdvisorySummary {
     pub cvss_max: Option<f64>,
     pub href: String,
     pub cve_severity_count: HashMap<String, u64>,
+    /// Remediations of the vulnerabilities, as extracted when indexing the advisory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remediations: Vec<AdvisoryRemediation>,
 
     #[serde(default, skip_serializing_if = "Value::is_null", rename = "$metadata")]
     pub metadata: Value,
@@ -48,6 +54,9 @@ pub struct SbomSummary {
     pub advisories: Option<u64>,
     pub created: OffsetDateTime,
     pub vulnerabilities: Vec<String>,
+    /// The quality of the SBOM, by its minimum elements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
     #[serde(default, skip_serializing_if = "Value::is_null", rename = "$metadata")]
     pub metadata: Value,
 }
@@ -69,3 +78,49 @@ impl SbomSummary {
         }
     }
 }
+
+/// The result of searching SBOMs, advisories and CVEs at once.
+#[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+pub struct FederatedSearchResult {
+    /// The top hits of each source, alternating between the sources by rank, as their scores are not comparable
+    pub result: Vec<FederatedHit>,
+    /// The total number of matches of each source
+    pub counts: FederatedCounts,
+    /// The errors of the sources which couldn't be searched, their hits are missing from the result
+    #[serde(default, skip_serializing_if = "FederatedErrors::is_empty")]
+    pub errors: FederatedErrors,
+}
+
+/// The total number of matches of a federated search, by source.
+#[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct FederatedCounts {
+    pub sboms: usize,
+    pub advisories: usize,
+    pub cves: usize,
+}
+
+/// The errors of a federated search, by source.
+#[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct FederatedErrors {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sboms: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advisories: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cves: Option<String>,
+}
+
+impl FederatedErrors {
+    pub fn is_empty(&self) -> bool {
+        self.sboms.is_none() && self.advisories.is_none() && self.cves.is_none()
+    }
+}
+
+/// A hit of a federated search, tagged by its source.
+#[derive(utoipa::ToSchema, serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+#[serde(tag = "type", content = "document", rename_all = "lowercase")]
+pub enum FederatedHit {
+    Sbom(SbomSummary),
+    Advisory(AdvisorySummary),
+    Cve(SearchDocument),
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// What a subscription refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionKind {
+    /// A vulnerability, by its CVE ID or one of its aliases
+    Cve,
+    /// A package URL, affected by vulnerabilities
+    Purl,
+}
+
+impl SubscriptionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cve => "cve",
+            Self::Purl => "purl",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "cve" => Some(Self::Cve),
+            "purl" => Some(Self::Purl),
+            _ => None,
+        }
+    }
+}
+
+/// A subscription to be registered.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct NewSubscription {
+    pub kind: SubscriptionKind,
+    /// The CVE ID, like `CVE-2023-44487`, or the package URL, like `pkg:pypi/django`
+    pub value: String,
+    /// URL receiving a POST request with a [`SubscriptionNotification`] whenever vulnerabilities get updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+}
+
+/// A CVE or package a user is subscribed to, being notified about updates of matching vulnerabilities.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: i64,
+    pub kind: SubscriptionKind,
+    /// The CVE ID, or the package URL
+    pub value: String,
+    /// URL receiving a POST request with a [`SubscriptionNotification`] whenever vulnerabilities get updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    pub created: OffsetDateTime,
+}
+
+/// The update of a vulnerability, matching a subscription.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: i64,
+    /// The ID of the subscription
+    pub subscription: i64,
+    /// The ID of the updated vulnerability
+    pub vulnerability: String,
+    /// The time the update was recorded
+    pub created: OffsetDateTime,
+}
+
+/// The notification sent to the target of a subscription.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SubscriptionNotification {
+    pub subscription: Subscription,
+    /// The IDs of the updated vulnerabilities
+    pub vulnerabilities: Vec<String>,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The dependency tree of an SBOM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SbomTree {
+    /// The top-level components described by the SBOM
+    pub roots: Vec<DependencyNode>,
+}
+
+/// A component in the dependency tree.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct DependencyNode {
+    /// The name of the component
+    pub name: String,
+    /// The version of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The package URL of the component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    /// If the component is a direct dependency of a top-level component
+    pub direct: bool,
+    /// If the component already occurred in the tree, its dependencies are only listed at the first occurrence
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub repeated: bool,
+    /// The dependencies of the component
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<DependencyNode>,
+}
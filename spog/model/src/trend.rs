@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// Number of CVEs published or updated by week and severity, oldest week first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SeverityTrend {
+    /// The CPE of the product, if the CVEs are limited to the ones affecting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<String>,
+    /// The date of the CVEs the weeks count by
+    pub date: TrendDate,
+    pub weeks: Vec<TrendBucket>,
+}
+
+/// The date of CVEs to count them by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrendDate {
+    /// The date the CVE was published
+    #[default]
+    Published,
+    /// The date the CVE was last updated
+    Updated,
+}
+
+impl TrendDate {
+    /// The name of the date when searching CVEs.
+    pub fn field(&self) -> &'static str {
+        match self {
+            Self::Published => "datePublished",
+            Self::Updated => "dateUpdated",
+        }
+    }
+}
+
+/// Number of CVEs published or updated during a week.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct TrendBucket {
+    /// Start of the week, on Monday
+    #[schema(value_type = String)]
+    pub start: OffsetDateTime,
+    pub count: u64,
+    /// Number of CVEs, by severity. CVEs scored by several sources may count for more than one severity.
+    pub severities: BTreeMap<String, u64>,
+}
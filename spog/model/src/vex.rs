@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A request to generate a VEX document for the product of an SBOM.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct VexRequest {
+    /// The ID of the SBOM describing the product
+    pub sbom: String,
+    /// The status of the product regarding each vulnerability
+    pub dispositions: Vec<Disposition>,
+    /// The format of the document to generate
+    #[serde(default)]
+    pub format: VexFormat,
+    /// The ID of the document, generated from the SBOM ID if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Publish the generated document into vexination
+    #[serde(default)]
+    pub publish: bool,
+}
+
+/// The status of a product, or some of its packages, regarding a CVE.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Disposition {
+    pub cve: String,
+    pub status: DispositionStatus,
+    /// Why the product is not affected, required for `not_affected` unless there is a statement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub justification: Option<Justification>,
+    /// The impact statement for `not_affected`, or the action statement for `affected`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement: Option<String>,
+    /// Package URLs of the SBOM the disposition is limited to, the whole product if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispositionStatus {
+    NotAffected,
+    Affected,
+    Fixed,
+    UnderInvestigation,
+}
+
+/// Justifications for a product not being affected, shared by CSAF and OpenVEX.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Justification {
+    ComponentNotPresent,
+    VulnerableCodeNotPresent,
+    VulnerableCodeNotInExecutePath,
+    VulnerableCodeCannotBeControlledByAdversary,
+    InlineMitigationsAlreadyExist,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VexFormat {
+    #[default]
+    Csaf,
+    OpenVex,
+}
+
+/// A generated VEX document.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct GeneratedVex {
+    /// The ID of the document
+    pub id: String,
+    pub format: VexFormat,
+    /// The document got published into vexination
+    pub published: bool,
+    #[schema(value_type = Object)]
+    pub document: serde_json::Value,
+}
+
+/// A request to merge VEX documents about the same product into a single, consolidated document.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct VexMergeRequest {
+    /// The documents to merge
+    pub sources: Vec<VexSource>,
+    /// How to pick the statement of a vulnerability and product, when sources disagree
+    #[serde(default)]
+    pub strategy: MergeStrategy,
+    /// The format of the document to generate
+    #[serde(default)]
+    pub format: VexFormat,
+    /// The ID of the document, generated if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Publish the merged document into vexination
+    #[serde(default)]
+    pub publish: bool,
+}
+
+/// A VEX document to merge, either stored in vexination or provided inline, in CSAF or OpenVEX format.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct VexSource {
+    /// The ID of a document stored in vexination
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The document itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub document: Option<serde_json::Value>,
+    /// The priority of the statements of the document, higher being preferred
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Rules for picking one of several statements about a vulnerability and product.
+///
+/// Remaining ties are broken by the order of the sources, later sources being preferred.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// The most recent statement wins, ties are broken by priority
+    #[default]
+    LatestWins,
+    /// The statement of the source with the highest priority wins, ties are broken by recency
+    PriorityWins,
+}
+
+/// A merged VEX document.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct MergedVex {
+    /// The ID of the document
+    pub id: String,
+    pub format: VexFormat,
+    /// The document got published into vexination
+    pub published: bool,
+    #[schema(value_type = Object)]
+    pub document: serde_json::Value,
+    /// Statements which were dropped in favor of a statement of another source
+    pub superseded: Vec<SupersededStatement>,
+}
+
+/// A statement dropped while merging.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct SupersededStatement {
+    pub cve: String,
+    pub product: String,
+    /// The package of the product the statement is limited to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub status: DispositionStatus,
+    /// The index of the source of the statement
+    pub source: usize,
+    /// The index of the source of the statement superseding it
+    pub superseded_by: usize,
+}
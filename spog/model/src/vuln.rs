@@ -86,6 +86,22 @@ pub struct SummaryEntry {
     pub count: usize,
 }
 
+/// Aggregated vulnerability counts of a single SBOM.
+#[derive(Clone, Debug, PartialEq, ToSchema, Serialize, Deserialize)]
+pub struct SbomVulnerabilitySummary {
+    /// The SBOM ID
+    pub id: String,
+    /// The SBOM name
+    pub name: String,
+    /// The SBOM version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Total number of vulnerabilities affecting the SBOM
+    pub total: usize,
+    /// Vulnerabilities by severity
+    pub summary: Vec<SummaryEntry>,
+}
+
 mod schema {
     use crate::vuln::Backtrace;
     use cvss::Severity;
@@ -174,6 +190,29 @@ pub struct SbomReportVulnerability {
 
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub sources: HashMap<String, SourceDetails>,
+
+    /// The status of the vulnerability for the affected packages, as stated by the vendor's VEX documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<VexStatus>,
+
+    /// The entry of the known exploited vulnerabilities catalog, if the vulnerability is known to be exploited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_exploited: Option<v11y_model::kev::KnownExploited>,
+    /// Whether an exploit of the vulnerability is published, like an Exploit Database entry or a Metasploit module
+    #[serde(default)]
+    pub exploit_available: bool,
+}
+
+/// The status of a vulnerability, as stated by a VEX document.
+///
+/// Variants are ordered by their relevance, `Affected` being the most relevant one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VexStatus {
+    NotAffected,
+    Fixed,
+    UnderInvestigation,
+    Affected,
 }
 
 impl SbomReportVulnerability {
@@ -0,0 +1,51 @@
+use crate::saved::SearchTarget;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// A watch to be registered.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct NewWatch {
+    /// The name of the watch, unique per user
+    pub name: String,
+    /// The sikula query newly indexed documents are matched against
+    pub query: String,
+    /// The index to watch, either `sbom` or `advisory`
+    pub target: SearchTarget,
+    /// URL receiving a POST request with a [`WatchNotification`] whenever documents match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+}
+
+/// A search query, evaluated whenever new documents get indexed.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Watch {
+    pub id: i64,
+    /// The name of the watch, unique per user
+    pub name: String,
+    /// The sikula query newly indexed documents are matched against
+    pub query: String,
+    /// The index to watch
+    pub target: SearchTarget,
+    /// URL receiving a POST request with a [`WatchNotification`] whenever documents match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    pub created: OffsetDateTime,
+}
+
+/// A document which matched the query of a watch.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct WatchHit {
+    /// The ID of the document
+    pub id: String,
+    /// The time the match was recorded
+    pub matched: OffsetDateTime,
+}
+
+/// The notification sent to the target of a watch.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct WatchNotification {
+    pub watch: Watch,
+    /// The IDs of the documents which newly matched
+    pub documents: Vec<String>,
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+/// A webhook to be registered.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct NewWebhook {
+    /// The name of the webhook, unique
+    pub name: String,
+    /// URL receiving a POST request with a [`WebhookNotification`] whenever matching advisories are ingested
+    pub url: String,
+    /// Secret used to sign notifications with HMAC-SHA256
+    pub secret: String,
+    /// Advisories to notify about, all if empty
+    #[serde(default)]
+    pub filter: WebhookFilter,
+}
+
+/// Selects the advisories a webhook gets notified about.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct WebhookFilter {
+    /// Severities of the advisory, any if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub severities: Vec<String>,
+    /// Package URLs or CPEs of products the advisory must refer to, any if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub products: Vec<String>,
+}
+
+/// A registered webhook. Its secret is never returned.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    /// The name of the webhook, unique
+    pub name: String,
+    /// URL receiving a POST request with a [`WebhookNotification`] whenever matching advisories are ingested
+    pub url: String,
+    pub filter: WebhookFilter,
+    pub created: OffsetDateTime,
+}
+
+/// The notification sent to a webhook.
+///
+/// The body is signed using the secret of the webhook, the signature is sent as hex encoded HMAC-SHA256 in the
+/// `X-Trustification-Signature` header, prefixed with `sha256=`.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct WebhookNotification {
+    /// The ID of the webhook
+    pub webhook: i64,
+    /// The ID of the delivery, which stays the same when retrying
+    pub delivery: i64,
+    /// The IDs of the ingested advisories
+    pub advisories: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not delivered yet, waiting for another attempt
+    Pending,
+    Delivered,
+    /// Not delivered, and never retried again
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(Self::Pending),
+            "delivered" => Some(Self::Delivered),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// The delivery of a notification to a webhook.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    /// The IDs of the advisories notified about
+    pub advisories: Vec<String>,
+    pub status: DeliveryStatus,
+    /// The number of attempts made so far
+    pub attempts: u32,
+    pub created: OffsetDateTime,
+    /// The time of the last attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempted: Option<OffsetDateTime>,
+    /// The HTTP status code of the last attempt, if a response was received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<u16>,
+    /// The error of the last attempt, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -6,6 +6,7 @@ use spog_ui_common::error::*;
 use std::rc::Rc;
 use trustification_api::search::SearchResult;
 use trustification_api::Apply;
+use vexination_model::convert::parse_csaf;
 use yew_oauth2::prelude::*;
 
 pub struct VexService {
@@ -60,7 +61,8 @@ impl VexService {
             return Ok(None);
         }
 
-        Ok(Some(response.api_error_for_status().await?.json().await?))
+        let data = response.api_error_for_status().await?.bytes().await?;
+        Ok(Some(parse_csaf(&data)?))
     }
 
     pub async fn upload(&self, data: impl Into<Body>) -> Result<String, ApiError> {
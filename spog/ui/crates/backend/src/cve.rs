@@ -6,6 +6,7 @@ use spog_ui_common::error::*;
 use std::rc::Rc;
 use trustification_api::search::SearchResult;
 use trustification_api::Apply;
+use v11y_model::related::RelatedCve;
 use v11y_model::search::SearchHit;
 use yew_oauth2::prelude::*;
 
@@ -79,6 +80,26 @@ impl CveService {
         Ok(response.api_error_for_status().await?.json().await?)
     }
 
+    pub async fn get_related(&self, id: impl AsRef<str>) -> Result<Vec<RelatedCve>, ApiError> {
+        let url = self.backend.join(
+            Endpoint::Api,
+            &format!("/api/v1/cve/{id}/related", id = urlencoding::encode(id.as_ref())),
+        )?;
+
+        let response = self
+            .client
+            .get(url)
+            .latest_access_token(&self.access_token)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(vec![]);
+        }
+
+        Ok(response.api_error_for_status().await?.json().await?)
+    }
+
     pub async fn search(
         &self,
         q: &str,
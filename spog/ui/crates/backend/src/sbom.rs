@@ -1,6 +1,6 @@
 use crate::{ApplyAccessToken, Backend, Endpoint};
 use reqwest::{Body, StatusCode};
-use spog_model::prelude::{SbomReport, SbomSummary};
+use spog_model::prelude::{AttestationReport, SbomReport, SbomSummary};
 use spog_ui_common::error::*;
 use std::rc::Rc;
 use trustification_api::search::SearchResult;
@@ -92,6 +92,26 @@ impl SBOMService {
         Ok(Some(response.api_error_for_status().await?.json().await?))
     }
 
+    pub async fn get_attestations(&self, id: &str) -> Result<Option<AttestationReport>, ApiError> {
+        let url = self.backend.join(
+            Endpoint::Api,
+            &format!("/api/v1/sbom/{}/attestations", urlencoding::encode(id)),
+        )?;
+
+        let response = self
+            .client
+            .get(url)
+            .latest_access_token(&self.access_token)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(response.api_error_for_status().await?.json().await?))
+    }
+
     pub async fn get_package(&self, id: &str) -> Result<SearchResult<Vec<SbomSummary>>, ApiError> {
         let q = format!(r#"uid:"{id}""#);
         let response = self
@@ -18,6 +18,7 @@ impl Default for SearchParameters {
                 metadata: default_metadata(),
                 summaries: true,
                 explain: false,
+                ..Default::default()
             },
         }
     }
@@ -60,8 +60,8 @@ impl TableEntryRenderer<Column> for CveEntry {
             </>),
             Column::Severity => html!(
                 <>
-                    if let Some(score)= &self.cve.cvss3x_score {
-                        <CvssScore cvss={Cvss{score: (*score) as _}} />
+                    if let Some(score) = self.cve.score() {
+                        <CvssScore cvss={Cvss{score: score as _}} />
                     }
                 </>
             ),
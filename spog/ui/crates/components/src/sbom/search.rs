@@ -194,6 +194,9 @@ pub fn help() -> Html {
                     <p>The type of package (e.g. <code>oci</code>).</p>
                 "#.into()) }</DescriptionGroup>
                 <DescriptionGroup term="supplier">{ "The supplier of the package." }</DescriptionGroup>
+                <DescriptionGroup term="qualityScore">{ Html::from_html_unchecked(r#"
+                    <p>The quality score of the SBOM, from 0 to 100 (e.g. <code>qualityScore:>=80</code>).</p>
+                "#.into()) }</DescriptionGroup>
             </DescriptionList>
         </SearchHelpPopover>
     )
@@ -18,12 +18,12 @@ pub enum SBOM {
 impl SBOM {
     pub fn parse(source: String) -> Self {
         let source = Rc::new(source);
-        if let Ok(bom) = cyclonedx_bom::prelude::Bom::parse_from_json(source.as_bytes()) {
+        if let Ok(sbom) = bombastic_model::cyclonedx::CycloneDx::parse(source.as_bytes()) {
             SBOM::CycloneDX {
-                bom: Rc::new(bom),
+                bom: Rc::new(sbom.bom),
                 source,
             }
-        } else if let Ok(bom) = serde_json::from_str::<spdx_rs::models::SPDX>(&source) {
+        } else if let Ok(bom) = bombastic_model::spdx3::parse(source.as_bytes()) {
             SBOM::SPDX {
                 bom: Rc::new(bom),
                 source,
@@ -1,6 +1,7 @@
 mod advisories;
 mod packages;
 mod products;
+mod vulnerabilities;
 
 use crate::hooks::use_related_advisories;
 use advisories::RelatedAdvisories;
@@ -17,6 +18,8 @@ use spog_ui_components::{
 };
 use std::rc::Rc;
 use std::str::FromStr;
+use v11y_model::cwe::Cwe;
+use vulnerabilities::RelatedVulnerabilities;
 use yew::prelude::*;
 use yew_more_hooks::{
     hooks::{use_async_with_cloned_deps, use_page_state},
@@ -48,7 +51,9 @@ pub fn result_view(props: &ResultViewProperties) -> Html {
                     .map_err(|err| err.to_string())?
                     .map(|source| {
                         Ok::<_, String>((
-                            Rc::new(serde_json::from_str::<cve::Cve>(&source).map_err(|err| err.to_string())?),
+                            Rc::new(
+                                v11y_model::cve_record::from_str::<cve::Cve>(&source).map_err(|err| err.to_string())?,
+                            ),
                             Rc::new(source),
                         ))
                     })
@@ -76,10 +81,27 @@ pub fn result_view(props: &ResultViewProperties) -> Html {
 
     let related_advisories = use_related_advisories(props.id.clone());
 
+    let related_vulnerabilities = {
+        let backend = backend.clone();
+        let access_token = access_token.clone();
+        use_async_with_cloned_deps(
+            move |id| async move {
+                let service = CveService::new(backend.clone(), access_token.clone());
+                service
+                    .get_related(&id)
+                    .await
+                    .map(Rc::new)
+                    .map_err(|err| err.to_string())
+            },
+            props.id.clone(),
+        )
+    };
+
     #[derive(Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
     enum TabIndex {
         Products,
         Advisories,
+        Vulnerabilities,
         Source,
     }
 
@@ -139,6 +161,7 @@ pub fn result_view(props: &ResultViewProperties) -> Html {
                 <Tabs<TabIndex> r#box=true selected={page_state.tab} {onselect} detached=true>
                     <Tab<TabIndex> index={TabIndex::Products} title="Related Products" />
                     <Tab<TabIndex> index={TabIndex::Advisories} title="Related Advisories" />
+                    <Tab<TabIndex> index={TabIndex::Vulnerabilities} title="Related Vulnerabilities" />
                     { for config.features.show_source.then(|| html_nested!(
                         <Tab<TabIndex> index={TabIndex::Source} title="Source" />
                     )) }
@@ -152,6 +175,9 @@ pub fn result_view(props: &ResultViewProperties) -> Html {
                 <Visible visible={matches!(page_state.tab, TabIndex::Advisories)} >
                     { async_content(&*related_advisories, |advisories| html!(<RelatedAdvisories {advisories} />)) }
                 </Visible>
+                <Visible visible={matches!(page_state.tab, TabIndex::Vulnerabilities)} >
+                    { async_content(&*related_vulnerabilities, |related| html!(<RelatedVulnerabilities {related} />)) }
+                </Visible>
                 <Visible visible={matches!(page_state.tab, TabIndex::Source)} style="height: 100%;">
                     { async_content(&*cve_details, |details| html!(
                         if let Some((_, content)) = details {
@@ -216,6 +242,23 @@ fn descriptions(props: &DescriptionsProperties) -> Html {
     )
 }
 
+#[derive(PartialEq, Properties)]
+struct WeaknessesProperties {
+    pub cwes: Vec<Cwe>,
+}
+
+#[function_component(Weaknesses)]
+fn weaknesses(props: &WeaknessesProperties) -> Html {
+    html!(
+        <ul class="pf-v5-c-list pf-m-plain">
+            { for props.cwes.iter().map(|cwe| match &cwe.name {
+                Some(name) => html!(<li>{ format!("{}: {name}", cwe.id) }</li>),
+                None => html!(<li>{ cwe.id.clone() }</li>),
+            })}
+        </ul>
+    )
+}
+
 #[derive(PartialEq, Properties)]
 pub struct CveDetailsViewProperties {
     pub details: Rc<cve::Cve>,
@@ -227,6 +270,9 @@ pub fn cve_details(props: &CveDetailsViewProperties) -> Html {
         <Grid gutter=true> {
             match &*props.details {
                 cve::Cve::Published(details) => {
+                    let cwes = serde_json::to_value(&details.containers.cna)
+                        .map(|cna| Cwe::from_container(&cna))
+                        .unwrap_or_default();
                     html!(
                         <>
                             <GridItem cols={[6.lg(), 8.md(), 12.all()]}>
@@ -244,6 +290,9 @@ pub fn cve_details(props: &CveDetailsViewProperties) -> Html {
                                     if let Some(timestamp) = details.metadata.date_updated {
                                         <DescriptionGroup term="Last modified"><Date timestamp={timestamp.assume_utc()} /></DescriptionGroup>
                                     }
+                                    if !cwes.is_empty() {
+                                        <DescriptionGroup term="Weaknesses"><Weaknesses {cwes} /></DescriptionGroup>
+                                    }
                                 </DescriptionList>
                             </GridItem>
                         </>
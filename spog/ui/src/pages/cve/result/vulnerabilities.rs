@@ -0,0 +1,83 @@
+use patternfly_yew::prelude::*;
+use spog_ui_navigation::{AppRoute, View};
+use std::rc::Rc;
+use v11y_model::related::{RelatedCve, Relation};
+use yew::prelude::*;
+use yew_nested_router::components::Link;
+
+#[derive(PartialEq, Properties)]
+pub struct RelatedVulnerabilitiesProperties {
+    pub related: Rc<Vec<RelatedCve>>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Column {
+    Id,
+    Title,
+    Relations,
+}
+
+#[function_component(RelatedVulnerabilities)]
+pub fn related_vulnerabilities(props: &RelatedVulnerabilitiesProperties) -> Html {
+    let (entries, _) = use_table_data(MemoizedTableModel::new(props.related.clone()));
+
+    impl TableEntryRenderer<Column> for RelatedCve {
+        fn render_cell(&self, context: CellContext<'_, Column>) -> Cell {
+            match context.column {
+                // only CVEs can be shown, other records are just listed
+                Column::Id => match self.id.starts_with("CVE-") {
+                    true => html!(
+                        <Link<AppRoute> target={AppRoute::Cve(View::Content {id: self.id.clone()})} >
+                            { self.id.clone() }
+                        </Link<AppRoute>>
+                    ),
+                    false => html!(self.id.clone()),
+                },
+                Column::Title => html!(self.title.clone().unwrap_or_default()),
+                Column::Relations => html!(
+                    { for self.relations.iter().map(|relation| {
+                        let label = match relation {
+                            Relation::Alias => "Alias",
+                            Relation::Reference => "Shared reference",
+                            Relation::Package => "Shared package",
+                        };
+                        html!(<><Label {label} />{ " " }</>)
+                    })}
+                ),
+            }
+            .into()
+        }
+    }
+
+    let header = html_nested!(
+        <TableHeader<Column>>
+            <TableColumn<Column> index={Column::Id} label="ID" />
+            <TableColumn<Column> index={Column::Title} label="Title" />
+            <TableColumn<Column> index={Column::Relations} label="Related by" />
+        </TableHeader<Column>>
+    );
+
+    match props.related.is_empty() {
+        true => html!(
+            <Panel>
+                <PanelMain>
+                    <Bullseye>
+                        <EmptyState
+                            title="No related vulnerabilities"
+                            icon={Icon::Search}
+                        >
+                            { "No related vulnerabilities have been found." }
+                        </EmptyState>
+                    </Bullseye>
+                </PanelMain>
+            </Panel>
+        ),
+        false => html!(
+            <Table<Column, UseTableData<Column, MemoizedTableModel<RelatedCve>>>
+                {header}
+                {entries}
+                mode={TableMode::Default}
+            />
+        ),
+    }
+}
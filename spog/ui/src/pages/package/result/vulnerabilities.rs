@@ -44,8 +44,8 @@ impl TableEntryRenderer<Column> for TableData {
             </>),
             Column::Severity => html!( <>
                 if let Some(cve) = &self.cve {
-                    if let Some(score)= &cve.cvss3x_score {
-                        <CvssScore cvss={Cvss{score: (*score) as _}} />
+                    if let Some(score) = cve.score() {
+                        <CvssScore cvss={Cvss{score: score as _}} />
                     }
                 }
             </>),
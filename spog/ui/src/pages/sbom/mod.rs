@@ -3,6 +3,7 @@
 use crate::{common::clean_ext, model, pages::sbom_report::SbomReport};
 use patternfly_yew::prelude::*;
 use reqwest::Body;
+use spog_model::prelude::{AttestationSource, VerificationStatus};
 use spog_ui_backend::{use_backend, AnalyzeService};
 use spog_ui_common::error::components::ApiError;
 use spog_ui_common::{config::use_config, error::components::Error};
@@ -129,6 +130,131 @@ pub fn sbom_title(props: &SBOMTitleProperties) -> Html {
     )
 }
 
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct SBOMQualityProperties {
+    pub id: String,
+}
+
+/// The quality of the SBOM, by its minimum elements, as scored when it got indexed
+#[function_component(SBOMQuality)]
+pub fn sbom_quality(props: &SBOMQualityProperties) -> Html {
+    let backend = use_backend();
+    let access_token = use_latest_access_token();
+
+    let quality = use_async_with_cloned_deps(
+        |(id, backend)| async move {
+            spog_ui_backend::SBOMService::new(backend.clone(), access_token)
+                .get_from_index(&id)
+                .await
+                .map(|search_result| search_result.result.into_iter().next().and_then(|data| data.quality))
+        },
+        (props.id.clone(), backend),
+    );
+
+    let share = |value: f64| format!("{:.0}%", value * 100.0);
+
+    html!(
+        <Card full_height=true>
+            <CardTitle><Title size={Size::XLarge}>{"Quality"}</Title></CardTitle>
+            <CardBody>
+                {
+                    match &*quality {
+                        UseAsyncState::Ready(Ok(Some(quality))) => html!(
+                            <DescriptionList>
+                                <DescriptionGroup term="Score">{ format!("{:.0} / 100", quality.score()) }</DescriptionGroup>
+                                <DescriptionGroup term="Supplier">{ share(quality.supplier) }</DescriptionGroup>
+                                <DescriptionGroup term="Version">{ share(quality.version) }</DescriptionGroup>
+                                <DescriptionGroup term="Unique identifiers">{ share(quality.unique_id) }</DescriptionGroup>
+                                <DescriptionGroup term="Timestamp">{ share(quality.timestamp) }</DescriptionGroup>
+                                <DescriptionGroup term="Dependencies">{ share(quality.dependencies) }</DescriptionGroup>
+                            </DescriptionList>
+                        ),
+                        UseAsyncState::Ready(_) => html!({"Not scored"}),
+                        _ => html!(<Spinner/>),
+                    }
+                }
+            </CardBody>
+        </Card>
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct SBOMAttestationsProperties {
+    pub id: String,
+}
+
+/// The attestations of the SBOM, along with their verification status
+#[function_component(SBOMAttestations)]
+pub fn sbom_attestations(props: &SBOMAttestationsProperties) -> Html {
+    let backend = use_backend();
+    let access_token = use_latest_access_token();
+
+    let report = use_async_with_cloned_deps(
+        |(id, backend)| async move {
+            spog_ui_backend::SBOMService::new(backend.clone(), access_token)
+                .get_attestations(&id)
+                .await
+        },
+        (props.id.clone(), backend),
+    );
+
+    let status = |status: VerificationStatus| match status {
+        VerificationStatus::Verified => html!(<Label label="Verified" color={Color::Green} />),
+        VerificationStatus::Unverified => html!(<Label label="Unverified" color={Color::Orange} />),
+        VerificationStatus::Invalid => html!(<Label label="Invalid" color={Color::Red} />),
+    };
+    let source = |source: AttestationSource| match source {
+        AttestationSource::Attached => "Attached to the SBOM",
+        AttestationSource::Registry => "Registry of the image",
+    };
+
+    match &*report {
+        UseAsyncState::Pending | UseAsyncState::Processing => html!(<Spinner/>),
+        UseAsyncState::Ready(Ok(None)) => html!(<NotFound/>),
+        UseAsyncState::Ready(Ok(Some(report))) => html!(
+            <Stack gutter=true>
+                { for report.errors.iter().map(|error| html_nested!(
+                    <StackItem>
+                        <Alert inline=true r#type={AlertType::Warning} title={error.clone()} />
+                    </StackItem>
+                )) }
+                if report.attestations.is_empty() {
+                    <StackItem>{ "No attestations found" }</StackItem>
+                }
+                { for report.attestations.iter().map(|attestation| html_nested!(
+                    <StackItem>
+                        <Card>
+                            <CardTitle>
+                                <Title size={Size::Large}>
+                                    { attestation.predicate_type.clone().unwrap_or_else(|| "Unknown predicate".to_string()) }
+                                    {" "} { status(attestation.status) }
+                                </Title>
+                            </CardTitle>
+                            <CardBody>
+                                <DescriptionList>
+                                    <DescriptionGroup term="Source">{ source(attestation.source) }</DescriptionGroup>
+                                    <DescriptionGroup term="Subjects">
+                                        <List>
+                                            { for attestation.subjects.iter().map(|subject| html_nested!(<ListItem>{ subject }</ListItem>)) }
+                                        </List>
+                                    </DescriptionGroup>
+                                    if let Some(key_id) = &attestation.key_id {
+                                        <DescriptionGroup term="Key ID">{ key_id }</DescriptionGroup>
+                                    }
+                                    if let Some(message) = &attestation.message {
+                                        <DescriptionGroup term="Details">{ message }</DescriptionGroup>
+                                    }
+                                </DescriptionList>
+                            </CardBody>
+                        </Card>
+                    </StackItem>
+                )) }
+            </Stack>
+        ),
+        UseAsyncState::Ready(Err(err)) => html!(<ApiError error={err.clone()} />),
+    }
+}
+
 #[derive(Clone, PartialEq, Properties)]
 struct DetailsProps {
     id: String,
@@ -144,6 +270,7 @@ fn details(props: &DetailsProps) -> Html {
         Packages,
         Source,
         Report,
+        Attestations,
     }
 
     let config = use_config();
@@ -160,6 +287,7 @@ fn details(props: &DetailsProps) -> Html {
                             <Tab<TabIndex> index={TabIndex::Info} title="Info" />
                             <Tab<TabIndex> index={TabIndex::Packages} title="Packages" />
                             <Tab<TabIndex> index={TabIndex::Overview} title="Related advisories" />
+                            <Tab<TabIndex> index={TabIndex::Attestations} title="Attestations" />
                             { for config.features.show_report.then(|| html_nested!(
                                 <Tab<TabIndex> index={TabIndex::Report} title="Dependency Analytics Report" />
                             )) }
@@ -182,6 +310,11 @@ fn details(props: &DetailsProps) -> Html {
                                     <GridItem cols={[3]}>{spdx_stats(source.as_bytes().len(), bom)}</GridItem>
                                 </Grid>
                             </StackItem>
+                            <StackItem>
+                                <Grid gutter=true>
+                                    <GridItem cols={[3]}><SBOMQuality id={props.id.clone()} /></GridItem>
+                                </Grid>
+                            </StackItem>
                             <StackItem>
                                 <Grid gutter=true>
                                     <GridItem cols={[12]}>{spdx_main(bom)}</GridItem>
@@ -194,6 +327,10 @@ fn details(props: &DetailsProps) -> Html {
                         <SpdxPackages bom={bom.clone()} />
                     </PageSection>
 
+                    <PageSection hidden={*tab != TabIndex::Attestations} fill={PageSectionFill::Fill}>
+                        <SBOMAttestations id={props.id.clone()} />
+                    </PageSection>
+
                     <PageSection hidden={*tab != TabIndex::Source} variant={PageSectionVariant::Light} fill={PageSectionFill::Fill}>
                         <SourceCode source={source.clone()} />
                     </PageSection>
@@ -211,6 +348,7 @@ fn details(props: &DetailsProps) -> Html {
                             <Tab<TabIndex> index={TabIndex::Info} title="Info" />
                             <Tab<TabIndex> index={TabIndex::Packages} title="Packages" />
                             <Tab<TabIndex> index={TabIndex::Overview} title="Related advisories" />
+                            <Tab<TabIndex> index={TabIndex::Attestations} title="Attestations" />
                             { for config.features.show_report.then(|| html_nested!(
                                 <Tab<TabIndex> index={TabIndex::Report} title="Dependency Analytics Report" />
                             )) }
@@ -235,6 +373,11 @@ fn details(props: &DetailsProps) -> Html {
                                     </GridItem>
                                 </Grid>
                             </StackItem>
+                            <StackItem>
+                                <Grid gutter=true>
+                                    <GridItem cols={[3]}><SBOMQuality id={props.id.clone()} /></GridItem>
+                                </Grid>
+                            </StackItem>
                         </Stack>
                     </PageSection>
 
@@ -242,6 +385,10 @@ fn details(props: &DetailsProps) -> Html {
                         <CycloneDxPackages bom={bom.clone()} />
                     </PageSection>
 
+                    <PageSection hidden={*tab != TabIndex::Attestations} fill={PageSectionFill::Fill}>
+                        <SBOMAttestations id={props.id.clone()} />
+                    </PageSection>
+
                     <PageSection hidden={*tab != TabIndex::Source} fill={PageSectionFill::Fill}>
                         <SourceCode source={source.clone()} />
                     </PageSection>
@@ -36,7 +36,27 @@ pub fn details(props: &DetailsProps) -> Html {
     impl TableEntryRenderer<Column> for Entry {
         fn render_cell(&self, context: CellContext<'_, Column>) -> Cell {
             match context.column {
-                Column::Id => Cell::new(html!(self.vuln.id.clone())).text_modifier(TextModifier::NoWrap),
+                Column::Id => Cell::new(html!(
+                    <>
+                        { self.vuln.id.clone() }
+                        if let Some(kev) = &self.vuln.known_exploited {
+                            {" "}
+                            <Label
+                                color={Color::Red}
+                                compact=true
+                                label={match &kev.due_date {
+                                    Some(due_date) => format!("Known exploited, due {due_date}"),
+                                    None => "Known exploited".to_string(),
+                                }}
+                            />
+                        }
+                        if self.vuln.exploit_available {
+                            {" "}
+                            <Label color={Color::Orange} compact=true label="Exploit available" />
+                        }
+                    </>
+                ))
+                .text_modifier(TextModifier::NoWrap),
                 Column::Description => html!({ for self.vuln.description.clone() }).into(),
                 Column::Cvss => html!(
                     <>
@@ -64,17 +64,10 @@ pub fn parse(data: &[u8]) -> Result<SBOM, anyhow::Error> {
 
     #[allow(clippy::single_match)]
     match &sbom {
-        SBOM::CycloneDX(_bom) => {
-            // re-parse to check for the spec version
-            let json = serde_json::from_slice::<Value>(data).ok();
-            let spec_version = json.as_ref().and_then(|json| json["specVersion"].as_str());
-
-            match spec_version {
-                Some("1.3") | Some("1.4") => {}
-                Some(other) => bail!("Unsupported CycloneDX version: {other}"),
-                None => bail!("Unable to detect CycloneDX version"),
-            }
-        }
+        SBOM::CycloneDX(bom) => match bom.spec_version.as_str() {
+            "1.3" | "1.4" | "1.5" | "1.6" => {}
+            other => bail!("Unsupported CycloneDX version: {other}"),
+        },
         SBOM::SPDX(_bom) => {
             let _json = serde_json::from_slice::<Value>(data).ok();
         }
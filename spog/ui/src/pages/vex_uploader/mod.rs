@@ -14,7 +14,7 @@ const EMPTY_BODY_CONTENT: &str = r#"
 "#;
 
 pub fn parse(data: &[u8]) -> Result<csaf::Csaf, anyhow::Error> {
-    let vex = serde_json::from_slice::<csaf::Csaf>(data)?;
+    let vex = vexination_model::convert::parse_csaf(data)?;
     Ok(vex)
 }
 
@@ -1,6 +1,9 @@
 mod stream;
+mod upload;
 pub mod validator;
 
+pub use upload::{Upload, UploadPart};
+
 use async_stream::try_stream;
 use bytes::Bytes;
 use futures::pin_mut;
@@ -13,14 +16,16 @@ use prometheus::{
 };
 use s3::{creds::error::CredentialsError, error::S3Error, Bucket};
 pub use s3::{creds::Credentials, Region};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use validator::Validator;
 
 pub struct Storage {
     bucket: Bucket,
     metrics: Metrics,
     validator: Validator,
+    compression: Compression,
 }
 
 #[derive(Clone)]
@@ -137,6 +142,40 @@ pub struct StorageConfig {
     /// Validation choice
     #[arg(env = "VALIDATOR", long = "validator", default_value = "none")]
     pub validator: Validator,
+
+    /// Compression of stored documents, which weren't uploaded compressed already
+    #[arg(env = "STORAGE_COMPRESSION", long = "storage-compression", default_value_t = Compression::Zstd)]
+    pub compression: Compression,
+}
+
+/// Compression of stored documents, recorded as their content encoding.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Store documents as they were uploaded
+    #[clap(name = "none")]
+    None,
+    #[default]
+    #[clap(name = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The content encoding of documents compressed with this compression
+    pub fn encoding(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Zstd => Some("zstd"),
+        }
+    }
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
 }
 
 impl TryInto<Bucket> for StorageConfig {
@@ -218,8 +257,14 @@ pub enum Error {
     InvalidKey(String),
     #[error("invalid storage content")]
     InvalidContent,
+    #[error("invalid SBOM: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    InvalidSbom(Vec<bombastic_model::prelude::Violation>),
+    #[error("incomplete upload, missing parts {0:?}")]
+    IncompleteUpload(Vec<u32>),
     #[error("unexpected encoding {0}")]
     Encoding(String),
+    #[error("content exceeds the limit of {0} bytes")]
+    TooLarge(usize),
     #[error("Prometheus error {0}")]
     Prometheus(prometheus::Error),
 }
@@ -276,23 +321,45 @@ impl From<prometheus::Error> for Error {
 
 const DATA_PATH: &str = "/data/";
 const INDEX_PATH: &str = "/index";
+/// Snapshots of indexes, below the path of indexes so that their events are ignored the same way
+const SNAPSHOTS_PATH: &str = "/index/snapshots/";
+const LABELS_PATH: &str = "/labels/";
+const ATTESTATIONS_PATH: &str = "/attestations/";
+const REVISIONS_PATH: &str = "/revisions/";
+const STATUS_PATH: &str = "/status/";
+const UPLOADS_PATH: &str = "/uploads/";
+const DIGESTS_PATH: &str = "/digests/";
 const VERSION_HEADER: &str = "x-amz-meta-version";
 const VERSION: u32 = 1;
-const DEFAULT_ENCODING: &str = "zstd";
+
+/// Key/value labels, assigned to a stored object
+pub type Labels = BTreeMap<String, String>;
+
+/// The key, size and modification time of a stored object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectInfo {
+    pub key: String,
+    /// Size of the object as stored, after compression
+    pub size: u64,
+    pub last_modified: String,
+}
 
 pub struct Head {
     pub status: StatusCode,
     pub content_encoding: Option<String>,
+    pub e_tag: Option<String>,
 }
 
 impl Storage {
     pub fn new(config: StorageConfig, registry: &Registry) -> Result<Self, Error> {
         let validator = config.validator.clone();
+        let compression = config.compression;
         let bucket = config.try_into()?;
         Ok(Self {
             bucket,
             metrics: Metrics::register(registry)?,
             validator,
+            compression,
         })
     }
 
@@ -300,10 +367,36 @@ impl Storage {
         format!("/{}", key).starts_with(INDEX_PATH)
     }
 
+    pub fn is_labels(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(LABELS_PATH)
+    }
+
+    pub fn is_attestations(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(ATTESTATIONS_PATH)
+    }
+
+    pub fn is_revisions(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(REVISIONS_PATH)
+    }
+
+    pub fn is_status(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(STATUS_PATH)
+    }
+
+    pub fn is_uploads(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(UPLOADS_PATH)
+    }
+
+    pub fn is_digests(&self, key: &str) -> bool {
+        format!("/{}", key).starts_with(DIGESTS_PATH)
+    }
+
     pub fn key_from_event(record: &Record) -> Result<(Cow<str>, String), Error> {
         if let Ok(decoded) = urlencoding::decode(record.key()) {
             let key = decoded
                 .strip_prefix("data/")
+                .or_else(|| decoded.strip_prefix("labels/"))
+                .or_else(|| decoded.strip_prefix("attestations/"))
                 .map(|s| s.to_string())
                 .unwrap_or(decoded.to_string());
             Ok((decoded, key))
@@ -318,29 +411,66 @@ impl Storage {
         content_type: &'a str,
         encoding: Option<&str>,
         data: impl Stream<Item = Result<Bytes, Error>>,
+    ) -> Result<usize, Error> {
+        self.put_stream_archiving(key, content_type, encoding, data, 0, None)
+            .await
+    }
+
+    /// Store an object like [`Self::put_stream`], keeping the object it replaces as a revision, see
+    /// [`Self::archive_revision`], and replacing its labels along with it.
+    ///
+    /// The revision is only kept once the new data passed the validation, so that rejected data doesn't create one.
+    /// The labels are stored before the data, so that the event of the data already finds them, and restored if the
+    /// data can't be stored.
+    pub async fn put_stream_archiving<'a>(
+        &self,
+        key: &'a str,
+        content_type: &'a str,
+        encoding: Option<&str>,
+        data: impl Stream<Item = Result<Bytes, Error>>,
+        keep: usize,
+        labels: Option<&Labels>,
     ) -> Result<usize, Error> {
         self.metrics.puts_total.inc();
         let put_start = self.metrics.put_latency_seconds.start_timer();
         let mut headers = http::HeaderMap::new();
         headers.insert(VERSION_HEADER, VERSION.into());
-        headers.insert(
-            CONTENT_ENCODING,
-            HeaderValue::from_str(encoding.unwrap_or(DEFAULT_ENCODING))?,
-        );
+        // documents uploaded compressed are stored as uploaded
+        if let Some(encoding) = encoding.or(self.compression.encoding()) {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_str(encoding)?);
+        }
         let bucket = self.bucket.with_extra_headers(headers);
 
         let data = self.validator.validate(encoding, Box::pin(data)).await?;
-        let mut rdr = stream::encoded_reader(DEFAULT_ENCODING, encoding, data)?;
+        if let Some(revision) = self.archive_revision(key, keep).await? {
+            log::debug!("Replacing {key}, kept as revision {revision}");
+        }
+        let previous = match labels {
+            Some(labels) => {
+                let previous = self.get_labels(key).await?;
+                self.put_labels(key, labels).await?;
+                Some(previous)
+            }
+            None => None,
+        };
+        let mut rdr = stream::encoded_reader(self.compression.encoding(), encoding, data)?;
         let path = format!("{}{}", DATA_PATH, key);
 
-        let len = bucket
+        let result = bucket
             .put_object_stream_with_content_type(&mut rdr, path, content_type)
-            .await
-            .map_err(|e| {
+            .await;
+        let len = match result {
+            Ok(response) => response.uploaded_bytes(),
+            Err(e) => {
                 self.metrics.puts_failed_total.inc();
-                e
-            })?
-            .uploaded_bytes();
+                if let Some(previous) = previous {
+                    if let Err(e) = self.put_labels(key, &previous).await {
+                        log::warn!("Unable to restore the labels of {key}: {e}");
+                    }
+                }
+                return Err(e.into());
+            }
+        };
         put_start.observe_duration();
         Ok(len)
     }
@@ -355,6 +485,7 @@ impl Storage {
         Ok(Head {
             status: StatusCode::from_u16(status).map_err(|_| Error::Internal)?,
             content_encoding: head.content_encoding,
+            e_tag: head.e_tag,
         })
     }
 
@@ -429,6 +560,217 @@ impl Storage {
         }
     }
 
+    /// Get the decoded data of an object
+    ///
+    /// This will load the entire S3 object into memory
+    pub async fn get_data(&self, key: &str) -> Result<Vec<u8>, Error> {
+        self.get_decoded_object(&S3Path::from_key(key)).await
+    }
+
+    /// Get the labels of an object, which is empty if no labels were assigned.
+    pub async fn get_labels(&self, key: &str) -> Result<Labels, Error> {
+        let path = format!("{}{}", LABELS_PATH, key);
+        match self.bucket.get_object(path).await.map_err(Error::from) {
+            Ok(data) => serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent),
+            Err(Error::NotFound) => Ok(Labels::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace the labels of an object
+    pub async fn put_labels(&self, key: &str, labels: &Labels) -> Result<(), Error> {
+        let path = format!("{}{}", LABELS_PATH, key);
+        let data = serde_json::to_vec(labels).map_err(|_| Error::Internal)?;
+        self.bucket.put_object(path, &data).await?;
+        Ok(())
+    }
+
+    /// Get the attestations attached to an object, which is empty if none were attached.
+    pub async fn get_attestations(&self, key: &str) -> Result<Vec<serde_json::Value>, Error> {
+        let path = format!("{}{}", ATTESTATIONS_PATH, key);
+        match self.bucket.get_object(path).await.map_err(Error::from) {
+            Ok(data) => serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent),
+            Err(Error::NotFound) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace the attestations attached to an object
+    pub async fn put_attestations(&self, key: &str, attestations: &[serde_json::Value]) -> Result<(), Error> {
+        let path = format!("{}{}", ATTESTATIONS_PATH, key);
+        let data = serde_json::to_vec(attestations).map_err(|_| Error::Internal)?;
+        self.bucket.put_object(path, &data).await?;
+        Ok(())
+    }
+
+    /// Store a revision of an object, replacing a stored revision with the same name
+    pub async fn put_revision(&self, key: &str, revision: &str, data: &[u8]) -> Result<(), Error> {
+        let path = format!("{}{}/{}", REVISIONS_PATH, key, revision);
+        let Some(encoding) = self.compression.encoding() else {
+            self.bucket.put_object(path, data).await?;
+            return Ok(());
+        };
+
+        let stream = stream::encode(
+            Some(encoding),
+            Box::pin(once(ok::<_, Error>(Bytes::copy_from_slice(data)))),
+        )?;
+        let mut data = vec![];
+        pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?)
+        }
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        self.bucket.with_extra_headers(headers).put_object(path, &data).await?;
+        Ok(())
+    }
+
+    /// Get the data of a stored revision of an object
+    pub async fn get_revision(&self, key: &str, revision: &str) -> Result<Vec<u8>, Error> {
+        let path = format!("{}{}/{}", REVISIONS_PATH, key, revision);
+        // revisions kept before they got compressed have no encoding
+        self.get_decoded_object(&S3Path::from_path(&path)).await
+    }
+
+    /// List the keys of all data objects, along with the time they were last modified, without fetching the data.
+    pub async fn list_keys(&self) -> Result<Vec<(String, String)>, Error> {
+        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| {
+                obj.key
+                    .strip_prefix(&DATA_PATH[1..])
+                    .map(|key| (key.to_string(), obj.last_modified))
+            })
+            .collect())
+    }
+
+    /// List the key, size and modification time of all data objects, without fetching the data.
+    pub async fn list_objects_info(&self) -> Result<Vec<ObjectInfo>, Error> {
+        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| {
+                obj.key.strip_prefix(&DATA_PATH[1..]).map(|key| ObjectInfo {
+                    key: key.to_string(),
+                    size: obj.size,
+                    last_modified: obj.last_modified,
+                })
+            })
+            .collect())
+    }
+
+    /// List the keys of all objects having labels, without fetching the labels.
+    pub async fn list_labeled_keys(&self) -> Result<Vec<String>, Error> {
+        let prefix = &LABELS_PATH[1..];
+        let results = self.bucket.list(prefix.to_string(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| obj.key.strip_prefix(prefix).map(|key| key.to_string()))
+            .collect())
+    }
+
+    /// List the names of the stored revisions of an object
+    pub async fn list_revisions(&self, key: &str) -> Result<Vec<String>, Error> {
+        let prefix = format!("{}{}/", &REVISIONS_PATH[1..], key);
+        let results = self.bucket.list(prefix.clone(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| obj.key.strip_prefix(&prefix).map(|s| s.to_string()))
+            .collect())
+    }
+
+    /// Keep the current data of an object as a numbered revision, before it gets replaced, returning the name of the
+    /// revision.
+    ///
+    /// Revisions are numbered from 1, and only the newest `keep` revisions are kept. Nothing is kept if the object
+    /// doesn't exist yet.
+    pub async fn archive_revision(&self, key: &str, keep: usize) -> Result<Option<String>, Error> {
+        if keep == 0 || self.get_head(S3Path::from_key(key)).await?.status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let data = self.get_data(key).await?;
+
+        let mut numbers: Vec<u64> = self
+            .list_revisions(key)
+            .await?
+            .iter()
+            .filter_map(|revision| revision.parse().ok())
+            .collect();
+        numbers.sort_unstable();
+        let revision = (numbers.last().copied().unwrap_or_default() + 1).to_string();
+        self.put_revision(key, &revision, &data).await?;
+
+        let excess = (numbers.len() + 1).saturating_sub(keep);
+        for number in &numbers[..excess] {
+            self.bucket
+                .delete_object(format!("{}{}/{}", REVISIONS_PATH, key, number))
+                .await?;
+        }
+        Ok(Some(revision))
+    }
+
+    /// Get a status stored by name, like the status of a source synced by a walker, if it was stored.
+    pub async fn get_status(&self, name: &str) -> Result<Option<serde_json::Value>, Error> {
+        let path = format!("{}{}", STATUS_PATH, name);
+        match self.bucket.get_object(path).await.map_err(Error::from) {
+            Ok(data) => serde_json::from_slice(&data.to_vec())
+                .map(Some)
+                .map_err(|_| Error::InvalidContent),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace a status stored by name
+    pub async fn put_status(&self, name: &str, status: &serde_json::Value) -> Result<(), Error> {
+        let path = format!("{}{}", STATUS_PATH, name);
+        let data = serde_json::to_vec(status).map_err(|_| Error::Internal)?;
+        self.bucket.put_object(path, &data).await?;
+        Ok(())
+    }
+
+    /// Get the key of the object recorded for a digest, like `sha256:<hex>`, if one was recorded.
+    ///
+    /// The object may have been replaced or deleted since, so the caller must check it still has the digest.
+    pub async fn get_digest(&self, digest: &str) -> Result<Option<String>, Error> {
+        let path = format!("{}{}", DIGESTS_PATH, digest.replace(':', "/"));
+        match self.bucket.get_object(path).await.map_err(Error::from) {
+            Ok(data) => String::from_utf8(data.to_vec())
+                .map(Some)
+                .map_err(|_| Error::InvalidContent),
+            Err(Error::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record the key of an object for a digest, replacing the key recorded before
+    pub async fn put_digest(&self, digest: &str, key: &str) -> Result<(), Error> {
+        let path = format!("{}{}", DIGESTS_PATH, digest.replace(':', "/"));
+        self.bucket.put_object(path, key.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Remove the record of a digest, if it's still recorded for the key of an object
+    pub async fn delete_digest(&self, digest: &str, key: &str) -> Result<(), Error> {
+        if self.get_digest(digest).await?.as_deref() == Some(key) {
+            let path = format!("{}{}", DIGESTS_PATH, digest.replace(':', "/"));
+            self.bucket.delete_object(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Decode data encoded like data to be stored, as with `put_stream`, failing if the decoded data exceeds a limit.
+    pub async fn decode(&self, encoding: Option<&str>, data: Bytes, limit: usize) -> Result<Vec<u8>, Error> {
+        stream::decode_limited(encoding, data, limit).await
+    }
+
     pub async fn put_index(&self, name: &str, index: &[u8]) -> Result<(), Error> {
         let index_path = format!("{}/{}", INDEX_PATH, name);
         self.bucket.put_object(index_path, index).await?;
@@ -442,6 +784,39 @@ impl Storage {
         Ok(data.to_vec())
     }
 
+    /// Store a named snapshot of an index, replacing a stored snapshot with the same name
+    pub async fn put_index_snapshot(&self, name: &str, snapshot: &str, index: &[u8]) -> Result<(), Error> {
+        let path = format!("{}{}/{}", SNAPSHOTS_PATH, name, snapshot);
+        self.bucket.put_object(path, index).await?;
+        Ok(())
+    }
+
+    /// Get a named snapshot of an index
+    pub async fn get_index_snapshot(&self, name: &str, snapshot: &str) -> Result<Vec<u8>, Error> {
+        let path = format!("{}{}/{}", SNAPSHOTS_PATH, name, snapshot);
+        let data = self.bucket.get_object(path).await?;
+        Ok(data.to_vec())
+    }
+
+    /// List the names of the stored snapshots of an index
+    pub async fn list_index_snapshots(&self, name: &str) -> Result<Vec<String>, Error> {
+        let prefix = format!("{}{}/", &SNAPSHOTS_PATH[1..], name);
+        let results = self.bucket.list(prefix.clone(), None).await?;
+        Ok(results
+            .into_iter()
+            .flat_map(|result| result.contents)
+            .filter_map(|obj| obj.key.strip_prefix(&prefix).map(|s| s.to_string()))
+            .collect())
+    }
+
+    /// Create an event of storing the object with the key again, marked as replaying it for reindexing
+    pub fn reindex_event(&self, key: &str) -> StorageEvent {
+        StorageEvent {
+            reindex: true,
+            ..StorageEvent::put(&self.bucket.name, &format!("{}{}", &DATA_PATH[1..], key))
+        }
+    }
+
     pub fn decode_event(&self, event: &[u8]) -> Result<StorageEvent, Error> {
         serde_json::from_slice::<StorageEvent>(event).map_err(|_e| Error::Internal)
     }
@@ -499,12 +874,28 @@ impl Storage {
                 self.metrics.deletes_failed_total.inc();
                 e
             })?;
+        // deleting an object which doesn't exist succeeds
+        self.bucket.delete_object(format!("{}{}", LABELS_PATH, key)).await?;
+        self.bucket
+            .delete_object(format!("{}{}", ATTESTATIONS_PATH, key))
+            .await?;
+        for revision in self.list_revisions(key).await? {
+            self.bucket
+                .delete_object(format!("{}{}/{}", REVISIONS_PATH, key, revision))
+                .await?;
+        }
         Ok(res)
     }
 
     // Deletes all data in the bucket (except index)
     pub async fn delete_all(&self) -> Result<(), Error> {
-        let results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        let mut results = self.bucket.list(DATA_PATH[1..].to_string(), None).await?;
+        results.extend(self.bucket.list(LABELS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(ATTESTATIONS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(REVISIONS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(STATUS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(UPLOADS_PATH[1..].to_string(), None).await?);
+        results.extend(self.bucket.list(DIGESTS_PATH[1..].to_string(), None).await?);
         for result in results {
             for obj in result.contents {
                 self.metrics.deletes_total.inc();
@@ -537,13 +928,42 @@ pub enum EventType {
     Other,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct StorageEvent {
     #[serde(rename = "Records")]
     pub records: Vec<Record>,
+    /// Whether the event replays storing unchanged objects, for reindexing them. The indexer forwards events as they
+    /// are, so this tells the consumers of indexed documents not to notify about the objects again.
+    #[serde(rename = "Reindex", default, skip_serializing_if = "std::ops::Not::not")]
+    pub reindex: bool,
 }
 
-#[derive(Deserialize, Debug)]
+impl StorageEvent {
+    /// An event of a single object stored in a bucket, by its path
+    pub fn put(bucket: &str, path: &str) -> Self {
+        Self {
+            records: vec![Record {
+                s3: S3Data {
+                    object: S3Object {
+                        // like the notifications of the storage, keys are URL encoded
+                        key: path
+                            .split('/')
+                            .map(|segment| urlencoding::encode(segment))
+                            .collect::<Vec<_>>()
+                            .join("/"),
+                    },
+                    bucket: S3Bucket {
+                        name: bucket.to_string(),
+                    },
+                },
+                event_name: format!("s3:{PUT_EVENT}"),
+            }],
+            reindex: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Record {
     #[serde(rename = "s3")]
     s3: S3Data,
@@ -598,7 +1018,7 @@ impl S3Path {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Data {
     #[serde(rename = "object")]
     object: S3Object,
@@ -606,13 +1026,13 @@ pub struct S3Data {
     bucket: S3Bucket,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Object {
     #[serde(rename = "key")]
     key: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct S3Bucket {
     #[serde(rename = "name")]
     name: String,
@@ -678,6 +1098,26 @@ mod tests {
         assert_eq!(decoded.bucket(), "bombastic");
     }
 
+    #[test]
+    fn test_put_event() {
+        let event = serde_json::to_vec(&StorageEvent::put("bombastic", "data/my sbom")).unwrap();
+        let decoded = serde_json::from_slice::<StorageEvent>(&event).unwrap();
+
+        assert_eq!(1, decoded.records.len());
+        let decoded = &decoded.records[0];
+        assert_eq!(decoded.event_type(), EventType::Put);
+        assert_eq!(decoded.bucket(), "bombastic");
+        assert_eq!(Storage::key_from_event(decoded).unwrap().1, "my sbom");
+
+        let event = StorageEvent {
+            reindex: true,
+            ..StorageEvent::put("bombastic", "data/my sbom")
+        };
+        let decoded = serde_json::from_slice::<StorageEvent>(&serde_json::to_vec(&event).unwrap()).unwrap();
+        assert!(decoded.reindex);
+        assert_eq!(1, decoded.records.len());
+    }
+
     #[test]
     fn test_minio_decode() {
         let event = r#"{"EventName":"s3:ObjectCreated:Put","Key":"vexination/index","Records":[{"eventVersion":"2.0","eventSource":"minio:s3","awsRegion":"","eventTime":"2023-06-05T11:04:06.851Z","eventName":"s3:ObjectCreated:Put","userIdentity":{"principalId":"admin"},"requestParameters":{"principalId":"admin","region":"","sourceIPAddress":"10.89.1.9"},"responseElements":{"content-length":"0","x-amz-id-2":"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855","x-amz-request-id":"1765BE755F95378E","x-minio-deployment-id":"7637fbd9-a700-4918-bc9d-f7929adf0d8f","x-minio-origin-endpoint":"http://10.89.1.9:9000"},"s3":{"s3SchemaVersion":"1.0","configurationId":"Config","bucket":{"name":"vexination","ownerIdentity":{"principalId":"admin"},"arn":"arn:aws:s3:::vexination"},"object":{"key":"index","size":2851,"eTag":"8aebf225551d1a9c71914a91bf36c7e3","contentType":"application/octet-stream","userMetadata":{"content-type":"application/octet-stream"},"sequencer":"1765BE756000DEDE"}},"source":{"host":"10.89.1.9","port":"","userAgent":""}}]}"#;
@@ -704,4 +1144,79 @@ mod tests {
         let p = S3Path::from_path("/data/foo/BAR");
         assert_eq!(p.key(), "foo/BAR");
     }
+
+    #[test]
+    fn test_unknown_encoding() {
+        let data = || once(ok::<_, Error>(Bytes::from_static(b"{}")));
+        assert!(matches!(
+            stream::encoded_reader(Compression::Zstd.encoding(), Some("gzip"), data()),
+            Err(Error::Encoding(_))
+        ));
+        assert!(matches!(
+            stream::decode(Some("gzip"), Box::pin(data())),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    /// The storage of the development environment, compressing documents as configured.
+    fn devmode_storage(compression: Compression) -> Storage {
+        let config = StorageConfig {
+            compression,
+            ..Default::default()
+        };
+        Storage::new(config.process("bombastic", true), &prometheus::Registry::new()).unwrap()
+    }
+
+    async fn collect(stream: impl Stream<Item = Result<Bytes, Error>>) -> Vec<u8> {
+        use futures::TryStreamExt;
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        chunks.concat()
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires the storage of the development environment"]
+    async fn test_compression() {
+        let data: &'static [u8] = br#"{"name": "compressed"}"#;
+        let bzip2 = collect(stream::encode(Some("bzip2"), Box::pin(once(ok(Bytes::from_static(data))))).unwrap()).await;
+
+        // documents are compressed as configured, unless they were uploaded compressed already
+        for (compression, uploaded, stored) in [
+            (Compression::Zstd, None, Some("zstd")),
+            (Compression::None, None, None),
+            (Compression::Zstd, Some("bzip2"), Some("bzip2")),
+            (Compression::None, Some("bzip2"), Some("bzip2")),
+        ] {
+            let storage = devmode_storage(compression);
+            let key = format!("test-compression-{compression}-{}", uploaded.unwrap_or("none"));
+            let upload = match uploaded {
+                Some(_) => Bytes::from(bzip2.clone()),
+                None => Bytes::from_static(data),
+            };
+            storage
+                .put_stream(&key, "application/json", uploaded, once(ok(upload)))
+                .await
+                .unwrap();
+
+            let path = S3Path::from_key(&key);
+            let (head, _) = storage.bucket.head_object(path.path.clone()).await.unwrap();
+            assert_eq!(head.content_encoding.as_deref(), stored, "{key}");
+
+            assert_eq!(collect(storage.get_decoded_stream(&path).await.unwrap()).await, data);
+            let encoded = collect(storage.get_encoded_stream(path).await.unwrap()).await;
+            assert_eq!(storage.decode(stored, encoded.into(), data.len()).await.unwrap(), data);
+
+            storage.delete(&key).await.unwrap();
+        }
+
+        let storage = devmode_storage(Compression::Zstd);
+        let result = storage
+            .put_stream(
+                "test-compression-unknown",
+                "application/json",
+                Some("gzip"),
+                once(ok(Bytes::from_static(data))),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Encoding(_))));
+    }
 }
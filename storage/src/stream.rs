@@ -9,12 +9,12 @@ use crate::Error;
 pub type ObjectStream<'a> = LocalBoxStream<'a, Result<Bytes, Error>>;
 
 pub fn encoded_reader<'a>(
-    default: &str,
+    default: Option<&str>,
     encoding: Option<&str>,
     data: impl Stream<Item = Result<Bytes, Error>> + Unpin + 'a,
 ) -> Result<Box<dyn AsyncRead + Unpin + 'a>, Error> {
     Ok(match encoding {
-        None => encode(Some(default), Box::pin(data)),
+        None => encode(default, Box::pin(data)),
         Some(s) => match s {
             "zstd" | "bzip2" => encode(None, Box::pin(data)),
             e => Err(Error::Encoding(e.to_string())),
@@ -45,6 +45,56 @@ pub fn encode<'a>(encoding: Option<&str>, stream: ObjectStream<'a>) -> Result<Ob
     }
 }
 
+/// Decode data, failing once the decoded data exceeds a limit, like for data compressing very well.
+pub async fn decode_limited(encoding: Option<&str>, data: Bytes, limit: usize) -> Result<Vec<u8>, Error> {
+    let mut stream = decode(encoding, futures::stream::once(async { Ok(data) }).boxed_local())?;
+    let mut bytes = vec![];
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit {
+            return Err(Error::TooLarge(limit));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
 fn boxed<'a, T: AsyncRead + 'a>(t: T) -> ObjectStream<'a> {
     ReaderStream::new(t).map_err(Error::Io).boxed_local()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encoded(encoding: &str, data: &[u8]) -> Bytes {
+        let data = Bytes::copy_from_slice(data);
+        let stream = encode(Some(encoding), futures::stream::once(async { Ok(data) }).boxed_local()).unwrap();
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        chunks.concat().into()
+    }
+
+    #[tokio::test]
+    async fn test_decode_limited() {
+        let data = vec![0u8; 1024 * 1024];
+        for encoding in ["zstd", "bzip2"] {
+            let compressed = encoded(encoding, &data).await;
+            assert!(compressed.len() < 1024);
+            assert_eq!(
+                decode_limited(Some(encoding), compressed.clone(), data.len())
+                    .await
+                    .unwrap(),
+                data
+            );
+            assert!(matches!(
+                decode_limited(Some(encoding), compressed, data.len() - 1).await,
+                Err(Error::TooLarge(_))
+            ));
+        }
+
+        assert!(matches!(
+            decode_limited(None, Bytes::from_static(b"{}"), 1).await,
+            Err(Error::TooLarge(1))
+        ));
+    }
+}
@@ -0,0 +1,270 @@
+//! Resumable uploads of objects, transferred in parts, backed by multipart uploads of the bucket.
+//!
+//! The state of an upload is stored next to the data, along with every part received, so that an upload can be
+//! resumed by another instance after a client lost its connection, and parts may be uploaded concurrently. The parts
+//! are assembled apart from the stored objects, so that completing an upload doesn't touch the object being replaced,
+//! which only gets stored by the caller, once it accepted the assembled data.
+
+use crate::{validator::Validator, Error, S3Path, Storage, UPLOADS_PATH, VERSION, VERSION_HEADER};
+use bytes::Bytes;
+use futures::{future::ok, pin_mut, stream::once, StreamExt};
+use http::{header::CONTENT_ENCODING, HeaderValue};
+use s3::serde_types::Part;
+use serde::{Deserialize, Serialize};
+
+/// An upload of an object, in progress.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Upload {
+    /// The identifier of the upload
+    pub id: String,
+    /// The key of the object being uploaded
+    pub key: String,
+    pub content_type: String,
+    /// The encoding of the object, which is stored as uploaded
+    pub encoding: Option<String>,
+    /// The user who initiated the upload, who is the only one allowed to continue it
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// The identifier of the multipart upload of the bucket
+    upload_id: String,
+    /// The parts received so far, ordered by their number
+    #[serde(skip)]
+    pub parts: Vec<UploadPart>,
+}
+
+impl Upload {
+    /// The numbers of the parts missing in between the parts received so far, or the first part if none was received.
+    pub fn missing_parts(&self) -> Vec<u32> {
+        let last = self.parts.last().map(|part| part.number).unwrap_or(1);
+        (1..=last)
+            .filter(|number| !self.parts.iter().any(|part| part.number == *number))
+            .collect()
+    }
+
+    /// The total size of the parts received so far
+    pub fn size(&self) -> usize {
+        self.parts.iter().map(|part| part.size).sum()
+    }
+
+    /// Check if the upload was initiated by a user, identified like when initiating it.
+    pub fn is_owned_by(&self, user: Option<&str>) -> bool {
+        self.owner.as_deref() == user
+    }
+}
+
+/// A part of an upload, which was received.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadPart {
+    /// The number of the part, starting with 1
+    pub number: u32,
+    pub etag: String,
+    pub size: usize,
+}
+
+fn upload_path(id: &str) -> String {
+    format!("{}{}", UPLOADS_PATH, id)
+}
+
+fn part_path(id: &str, number: u32) -> String {
+    format!("{}{}/{:05}", UPLOADS_PATH, id, number)
+}
+
+/// The path the parts get assembled at, outside of the prefix of the parts
+fn data_path(id: &str) -> String {
+    format!("{}{}.data", UPLOADS_PATH, id)
+}
+
+impl Storage {
+    /// Initiate the upload of an object by a user, see [`Upload::owner`].
+    pub async fn initiate_upload(
+        &self,
+        key: &str,
+        content_type: &str,
+        encoding: Option<&str>,
+        owner: Option<&str>,
+    ) -> Result<Upload, Error> {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(VERSION_HEADER, VERSION.into());
+        if let Some(encoding) = encoding {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_str(encoding)?);
+        }
+        let bucket = self.bucket.with_extra_headers(headers);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let response = bucket.initiate_multipart_upload(&data_path(&id), content_type).await?;
+        let upload = Upload {
+            id,
+            key: key.to_string(),
+            content_type: content_type.to_string(),
+            encoding: encoding.map(ToString::to_string),
+            owner: owner.map(ToString::to_string),
+            upload_id: response.upload_id,
+            parts: vec![],
+        };
+
+        let data = serde_json::to_vec(&upload).map_err(|_| Error::Internal)?;
+        self.bucket.put_object(upload_path(&upload.id), &data).await?;
+        Ok(upload)
+    }
+
+    /// Get an upload in progress, along with the parts received so far.
+    pub async fn get_upload(&self, id: &str) -> Result<Upload, Error> {
+        // the identifier is part of the path of the state, so only accept identifiers which were handed out
+        if uuid::Uuid::parse_str(id).is_err() {
+            return Err(Error::NotFound);
+        }
+
+        let data = self.bucket.get_object(upload_path(id)).await?;
+        let mut upload: Upload = serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent)?;
+
+        let prefix = format!("{}/", &upload_path(id)[1..]);
+        for result in self.bucket.list(prefix, None).await? {
+            for obj in result.contents {
+                let data = self.bucket.get_object(obj.key).await?;
+                upload
+                    .parts
+                    .push(serde_json::from_slice(&data.to_vec()).map_err(|_| Error::InvalidContent)?);
+            }
+        }
+        upload.parts.sort_by_key(|part| part.number);
+        Ok(upload)
+    }
+
+    /// Store a part of an upload, replacing a part received before with the same number.
+    ///
+    /// All parts but the last one must have a size of at least 5 MiB.
+    pub async fn put_upload_part(&self, id: &str, number: u32, data: Vec<u8>) -> Result<UploadPart, Error> {
+        let upload = self.get_upload(id).await?;
+        let size = data.len();
+        let part = self
+            .bucket
+            .put_multipart_chunk(data, &data_path(id), number, &upload.upload_id, &upload.content_type)
+            .await?;
+
+        let part = UploadPart {
+            number,
+            etag: part.etag,
+            size,
+        };
+        let data = serde_json::to_vec(&part).map_err(|_| Error::Internal)?;
+        self.bucket.put_object(part_path(id, number), &data).await?;
+        Ok(part)
+    }
+
+    /// Complete an upload, assembling the object out of all the parts received, and returning it as uploaded.
+    ///
+    /// The parts must be numbered without gaps, starting with 1. The object isn't stored, it's up to the caller to
+    /// store it and to drop the upload, see [`Self::delete_upload`]. If the object fails the validation, the upload is
+    /// dropped.
+    pub async fn complete_upload(&self, id: &str) -> Result<(Upload, Vec<u8>), Error> {
+        let upload = self.get_upload(id).await?;
+        let missing = upload.missing_parts();
+        if !missing.is_empty() {
+            return Err(Error::IncompleteUpload(missing));
+        }
+
+        let parts = upload
+            .parts
+            .iter()
+            .map(|part| Part {
+                part_number: part.number,
+                etag: part.etag.clone(),
+            })
+            .collect();
+        self.bucket
+            .complete_multipart_upload(&data_path(id), &upload.upload_id, parts)
+            .await?;
+
+        // the validation needs the whole object, which exists only now
+        let data = self.get_encoded_object(S3Path::from_path(&data_path(id))).await?;
+        if let Err(err) = self.validate_upload(&upload, &data).await {
+            self.delete_upload(id).await?;
+            return Err(err);
+        }
+        Ok((upload, data))
+    }
+
+    /// Abort an upload, dropping all parts received so far.
+    pub async fn abort_upload(&self, id: &str) -> Result<(), Error> {
+        let upload = self.get_upload(id).await?;
+        self.bucket.abort_upload(&data_path(id), &upload.upload_id).await?;
+        self.delete_upload(id).await
+    }
+
+    async fn validate_upload(&self, upload: &Upload, data: &[u8]) -> Result<(), Error> {
+        if let Validator::None = self.validator {
+            return Ok(());
+        }
+        let data = once(ok::<_, Error>(Bytes::copy_from_slice(data)));
+        let data = self
+            .validator
+            .validate(upload.encoding.as_deref(), Box::pin(data))
+            .await?;
+        pin_mut!(data);
+        while let Some(chunk) = data.next().await {
+            chunk?;
+        }
+        Ok(())
+    }
+
+    /// Delete the state of an upload, along with the assembled object of a completed upload.
+    pub async fn delete_upload(&self, id: &str) -> Result<(), Error> {
+        let prefix = format!("{}/", &upload_path(id)[1..]);
+        for result in self.bucket.list(prefix, None).await? {
+            for obj in result.contents {
+                self.bucket.delete_object(obj.key).await?;
+            }
+        }
+        self.bucket.delete_object(data_path(id)).await?;
+        self.bucket.delete_object(upload_path(id)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload(parts: &[u32]) -> Upload {
+        Upload {
+            id: "6ec1e2b8-7a8e-4b43-8ba0-8a43ad4a3d3c".to_string(),
+            key: "sbom".to_string(),
+            content_type: "application/json".to_string(),
+            encoding: None,
+            owner: Some("alice".to_string()),
+            upload_id: "upload".to_string(),
+            parts: parts
+                .iter()
+                .map(|number| UploadPart {
+                    number: *number,
+                    etag: format!("etag-{number}"),
+                    size: 10,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_missing_parts() {
+        assert_eq!(upload(&[]).missing_parts(), vec![1]);
+        assert_eq!(upload(&[1, 2, 3]).missing_parts(), Vec::<u32>::new());
+        assert_eq!(upload(&[2, 4]).missing_parts(), vec![1, 3]);
+        assert_eq!(upload(&[2, 4]).size(), 20);
+    }
+
+    #[test]
+    fn test_owner() {
+        assert!(upload(&[]).is_owned_by(Some("alice")));
+        assert!(!upload(&[]).is_owned_by(Some("bob")));
+        assert!(!upload(&[]).is_owned_by(None));
+    }
+
+    #[test]
+    fn test_state() {
+        // the parts are stored on their own
+        let json = serde_json::to_value(upload(&[1])).unwrap();
+        assert!(json.get("parts").is_none());
+        let upload: Upload = serde_json::from_value(json).unwrap();
+        assert!(upload.parts.is_empty());
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     stream::{decode, encode, ObjectStream},
     Error,
 };
-use bombastic_model::prelude::SBOM as SBOMValidator;
+use bombastic_model::validation;
 use bytes::Bytes;
 use futures::{future::ok, pin_mut, stream::once, StreamExt};
 use std::str::FromStr;
@@ -41,10 +41,15 @@ impl Validator {
             None => Ok(data),
             SBOM => {
                 check(encoding, data, |bytes| {
-                    SBOMValidator::parse(bytes).map_err(|e| {
-                        log::error!("Invalid SBOM: {e}");
-                        Error::InvalidContent
-                    })
+                    let violations = validation::validate(bytes);
+                    match violations.is_empty() {
+                        true => Ok(()),
+                        false => {
+                            let err = Error::InvalidSbom(violations);
+                            log::error!("{err}");
+                            Err(err)
+                        }
+                    }
                 })
                 .await
             }
@@ -140,6 +145,20 @@ mod tests {
         assert!(test(Validator::SBOM, Some("bzip2"), expected).await.is_err())
     }
 
+    #[tokio::test]
+    async fn sbom_violations() {
+        let expected = include_bytes!("../../bombastic/testdata/3amp-2.json.bz2");
+        match test(Validator::SBOM, Some("bzip2"), expected).await {
+            Err(Error::InvalidSbom(violations)) => {
+                assert!(!violations.is_empty());
+                assert!(violations
+                    .iter()
+                    .all(|violation| violation.path.ends_with("/licenseDeclared")));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn sbom_zstd_valid() -> Result<(), Error> {
         let expected = include_bytes!("../../bombastic/testdata/ubi8-valid.json.zst");
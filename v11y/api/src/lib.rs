@@ -12,7 +12,7 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
-use trustification_index::{IndexConfig, IndexStore};
+use trustification_index::{IndexConfig, ShardedIndexStore};
 use trustification_infrastructure::{
     app::http::{BinaryByteSize, HttpServerBuilder, HttpServerConfig},
     endpoint::V11y,
@@ -20,6 +20,7 @@ use trustification_infrastructure::{
     Infrastructure, InfrastructureConfig,
 };
 use trustification_storage::{Storage, StorageConfig};
+use v11y_index::TextAnalysis;
 
 use crate::db::Db;
 
@@ -35,6 +36,10 @@ pub struct Run {
     #[command(flatten)]
     pub index: IndexConfig,
 
+    /// Analysis of CVE titles and descriptions, which must match the one of the indexer
+    #[arg(long = "text-analysis", env = "V11Y_TEXT_ANALYSIS", default_value_t = TextAnalysis::English)]
+    pub text_analysis: TextAnalysis,
+
     #[command(flatten)]
     pub storage: StorageConfig,
 
@@ -90,6 +95,7 @@ impl Run {
                     let state = Self::configure(
                         self.storage_base,
                         index,
+                        self.text_analysis,
                         storage,
                         probe,
                         context.metrics.registry(),
@@ -122,6 +128,7 @@ impl Run {
     async fn configure(
         base: Option<PathBuf>,
         index_config: IndexConfig,
+        text_analysis: TextAnalysis,
         storage: StorageConfig,
         probe: Probe,
         registry: &Registry,
@@ -129,7 +136,14 @@ impl Run {
     ) -> anyhow::Result<Arc<AppState>> {
         let base = base.unwrap_or_else(|| ".".into());
 
-        let index = block_in_place(|| IndexStore::new(&storage, &index_config, v11y_index::Index::new(), registry))?;
+        let index = block_in_place(|| {
+            ShardedIndexStore::new(
+                &storage,
+                &index_config,
+                || v11y_index::Index::with_tokenizers(text_analysis, &index_config.tokenizers),
+                registry,
+            )
+        })?;
         let storage = Storage::new(storage.process("v11y", devmode), registry)?;
 
         let state = Arc::new(AppState::new(base, storage, index).await?);
@@ -165,14 +179,14 @@ pub struct AppState {
     db: Db,
 
     storage: Storage,
-    index: IndexStore<v11y_index::Index>,
+    index: ShardedIndexStore<v11y_index::Index>,
 }
 
 impl AppState {
     pub async fn new(
         base: impl AsRef<Path>,
         storage: Storage,
-        index: IndexStore<v11y_index::Index>,
+        index: ShardedIndexStore<v11y_index::Index>,
     ) -> Result<Self, anyhow::Error> {
         Ok(Self {
             db: Db::new(base).await?,
@@ -0,0 +1,212 @@
+use crate::server::Error;
+use crate::AppState;
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Deserialize;
+use trustification_api::search::SearchOptions;
+use trustification_auth::authenticator::user::UserInformation;
+use trustification_auth::authorizer::Authorizer;
+use trustification_auth::Permission;
+use trustification_common::error::ErrorInformation;
+use v11y_model::cpe::{AffectedCpe, Cpe};
+use v11y_model::search::SearchDocument;
+
+/// maximum number of vulnerabilities of a product evaluated by a single request
+const MAX_CANDIDATES: usize = 10000;
+
+/// Parameters for evaluating a CPE.
+#[derive(Debug, Deserialize)]
+pub struct CpeParams {
+    /// The CPE of a product, like `cpe:2.3:a:redhat:quarkus:2.13`
+    pub cpe: String,
+    /// The CPEs of the platforms the product runs on, like `cpe:2.3:o:redhat:enterprise_linux:9`, separated by commas
+    #[serde(default)]
+    pub platforms: String,
+    /// Max number of vulnerabilities to return
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    100
+}
+
+/// Check if a CVE, or another vulnerability record, affects a CPE.
+///
+/// The CPE is evaluated against the match expressions of the affected products, wildcards and version ranges
+/// included. Attributes missing from the CPE, like the version, match any value. Products only affected on specific
+/// platforms, like an operating system, are only affected if one of the given platforms matches.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "The CPE was evaluated", body = AffectedCpe),
+        (status = BAD_REQUEST, description = "Invalid CPE"),
+        (status = NOT_FOUND, description = "The vulnerability is not known"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to evaluate the CPE against"),
+        ("cpe" = String, Query, description = "The CPE of the product, like `cpe:2.3:a:redhat:quarkus:2.13`"),
+        ("platforms" = Option<String>, Query, description = "The CPEs of the platforms, separated by commas"),
+    )
+)]
+#[get("/cve/{id}/affects")]
+pub(crate) async fn cve_affects(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    params: web::Query<CpeParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let cpe = match parse(&params.cpe) {
+        Ok(cpe) => cpe,
+        Err(response) => return Ok(response),
+    };
+    let platforms = match parse_platforms(&params.platforms) {
+        Ok(platforms) => platforms,
+        Err(response) => return Ok(response),
+    };
+    // values can't be escaped in queries, but quotes don't occur in ids anyway
+    let id = id.to_uppercase();
+    if id.contains('"') {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let q = format!(r#"id:"{id}" (is:published OR is:rejected)"#);
+    let (hits, _) = web::block(move || state.index.search(&q, 0, 1, SearchOptions::default()))
+        .await?
+        .map_err(Error::Index)?;
+
+    match hits.into_iter().next() {
+        Some(hit) => Ok(HttpResponse::Ok().json(evaluate(&hit.document, &cpe, &platforms, &params.cpe))),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Get the published CVEs, and other vulnerability records, affecting a CPE.
+///
+/// The CPE is evaluated against the match expressions of the affected products, wildcards and version ranges
+/// included. Attributes missing from the CPE, like the version, match any value. Products only affected on specific
+/// platforms, like an operating system, are only affected if one of the given platforms matches.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "The vulnerabilities affecting the CPE", body = Vec<AffectedCpe>),
+        (status = BAD_REQUEST, description = "Invalid CPE"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("cpe" = String, Query, description = "The CPE of the product, like `cpe:2.3:a:redhat:quarkus:2.13`"),
+        ("platforms" = Option<String>, Query, description = "The CPEs of the platforms, separated by commas"),
+        ("limit" = Option<usize>, Query, description = "Max number of vulnerabilities to return"),
+    )
+)]
+#[get("/cve/cpe")]
+pub(crate) async fn cve_by_cpe(
+    state: web::Data<AppState>,
+    params: web::Query<CpeParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    let cpe = match parse(&params.cpe) {
+        Ok(cpe) => cpe,
+        Err(response) => return Ok(response),
+    };
+    let platforms = match parse_platforms(&params.platforms) {
+        Ok(platforms) => platforms,
+        Err(response) => return Ok(response),
+    };
+
+    // the index finds the vulnerabilities of the product, their versions are evaluated here
+    let q = format!(r#"cpe:"{}""#, params.cpe);
+    let (hits, total) = web::block(move || state.index.search(&q, 0, MAX_CANDIDATES, SearchOptions::default()))
+        .await?
+        .map_err(Error::Index)?;
+    if total > MAX_CANDIDATES {
+        log::info!(
+            "Evaluating {MAX_CANDIDATES} of {total} vulnerabilities of {}",
+            params.cpe
+        );
+    }
+
+    let affected: Vec<_> = hits
+        .iter()
+        .map(|hit| evaluate(&hit.document, &cpe, &platforms, &params.cpe))
+        .filter(|affected| affected.affected)
+        .take(params.limit)
+        .collect();
+    Ok(HttpResponse::Ok().json(affected))
+}
+
+fn parse(cpe: &str) -> Result<Cpe, HttpResponse> {
+    // the CPE is used as part of a query
+    if cpe.contains('"') {
+        return Err(invalid("The CPE must not contain quotes".to_string()));
+    }
+    Cpe::parse(cpe).map_err(|err| invalid(err.to_string()))
+}
+
+/// Parse the platforms of a product, split at unescaped commas.
+fn parse_platforms(value: &str) -> Result<Vec<Cpe>, HttpResponse> {
+    let mut result = vec![];
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars().chain([',']) {
+        match (c, escaped) {
+            (',', false) => {
+                if !current.trim().is_empty() {
+                    result.push(Cpe::parse(&current).map_err(|err| invalid(err.to_string()))?);
+                }
+                current.clear();
+            }
+            (c, _) => {
+                escaped = c == '\\' && !escaped;
+                current.push(c);
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn invalid(message: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorInformation {
+        error: "InvalidCpe".to_string(),
+        message,
+        details: String::new(),
+    })
+}
+
+fn evaluate(document: &SearchDocument, cpe: &Cpe, platforms: &[Cpe], value: &str) -> AffectedCpe {
+    let matches = document.matching_on(cpe, platforms);
+    AffectedCpe {
+        id: document.id.clone(),
+        cpe: value.to_string(),
+        affected: !matches.is_empty(),
+        matches,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_platforms() {
+        assert_eq!(parse_platforms("").unwrap(), vec![]);
+        assert_eq!(
+            parse_platforms("cpe:2.3:o:redhat:enterprise_linux:9, cpe:/h:intel:x86").unwrap(),
+            vec![
+                Cpe::parse("cpe:2.3:o:redhat:enterprise_linux:9").unwrap(),
+                Cpe::parse("cpe:2.3:h:intel:x86").unwrap(),
+            ]
+        );
+        assert_eq!(parse_platforms(r"cpe:2.3:a:vendor:a\,b").unwrap()[0].product(), r"a\,b");
+        assert!(parse_platforms("cpe:2.3:o:redhat:enterprise_linux:9,pkg:rpm/redhat/openssl").is_err());
+    }
+}
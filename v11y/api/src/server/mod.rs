@@ -1,5 +1,5 @@
-use crate::server::vulnerability::ingest_vulnerability;
-use actix_web::{web, ResponseError};
+use crate::server::vulnerability::{get_cves, ingest_osv, ingest_vulnerability, BATCH_PAYLOAD_LIMIT};
+use actix_web::{http::StatusCode, web, ResponseError};
 use derive_more::{Display, Error, From};
 use std::sync::Arc;
 use trustification_auth::{
@@ -9,7 +9,10 @@ use trustification_auth::{
 use trustification_infrastructure::new_auth;
 use utoipa::OpenApi;
 
+mod cpe;
+mod related;
 mod search;
+mod status;
 mod vulnerability;
 
 #[derive(OpenApi)]
@@ -22,8 +25,20 @@ mod vulnerability;
     ),
     paths(
         crate::server::vulnerability::ingest_vulnerability,
+        crate::server::vulnerability::ingest_osv,
         crate::server::vulnerability::get,
+        crate::server::vulnerability::get_cves,
+        crate::server::vulnerability::get_epss,
+        crate::server::vulnerability::get_known_exploited,
+        crate::server::related::get_related,
+        crate::server::cpe::cve_affects,
+        crate::server::cpe::cve_by_cpe,
         crate::server::search::search_cve,
+        crate::server::search::search_cve_fields,
+        crate::server::search::cve_changes,
+        crate::server::search::cve_histogram,
+        crate::server::search::cve_by_year,
+        crate::server::status::cve_status,
         //crate::server::vulnerability::get_by_alias,
     ),
     components(
@@ -35,6 +50,23 @@ mod vulnerability;
             v11y_model::Version,
             v11y_model::ScoreType,
             v11y_model::Reference,
+            v11y_model::batch::CveBatch,
+            v11y_model::epss::Epss,
+            v11y_model::kev::KnownExploited,
+            v11y_model::exploit::Exploit,
+            v11y_model::exploit::ExploitSource,
+            v11y_model::cwe::Cwe,
+            v11y_model::cpe::AffectedCpe,
+            v11y_model::cpe::CpeMatch,
+            v11y_model::related::RelatedCve,
+            v11y_model::related::Relation,
+            v11y_model::metrics::YearCount,
+            v11y_model::status::Source,
+            v11y_model::status::SourceStatus,
+            trustification_api::search::HistogramBucket,
+            trustification_api::search::FacetCount,
+            trustification_api::search::SearchField,
+            trustification_api::search::SearchFieldType,
         )
     )
 )]
@@ -55,7 +87,29 @@ pub fn config(
                     .app_data(web::PayloadConfig::new(publish_limit))
                     .app_data(web::JsonConfig::default().limit(publish_limit)),
             )
+            .service(
+                web::resource("/osv")
+                    .post(ingest_osv)
+                    .app_data(web::PayloadConfig::new(publish_limit)),
+            )
+            // must be registered ahead of the CVE lookup, which would take `changes`, `cpe`, `histogram` or
+            // `status` as id
+            .service(search::cve_changes)
+            .service(status::cve_status)
+            .service(cpe::cve_by_cpe)
+            .service(search::cve_histogram)
+            .service(search::cve_by_year)
+            .service(
+                web::resource("/cve/batch")
+                    .post(get_cves)
+                    .app_data(web::JsonConfig::default().limit(BATCH_PAYLOAD_LIMIT)),
+            )
             .service(vulnerability::get_cve)
+            .service(vulnerability::get_epss)
+            .service(vulnerability::get_known_exploited)
+            .service(related::get_related)
+            .service(cpe::cve_affects)
+            .service(search::search_cve_fields)
             .service(search::search_cve),
     )
     .service(swagger_ui_with_auth(ApiDoc::openapi(), swagger_ui_oidc));
@@ -69,4 +123,13 @@ pub enum Error {
     Index(trustification_index::Error),
 }
 
-impl ResponseError for Error {}
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Index(trustification_index::Error::InvalidCursor) => StatusCode::BAD_REQUEST,
+            Self::Index(trustification_index::Error::CursorExpired) => StatusCode::GONE,
+            Self::Index(trustification_index::Error::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
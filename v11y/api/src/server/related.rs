@@ -0,0 +1,196 @@
+use crate::server::vulnerability::CveError;
+use crate::server::Error;
+use crate::AppState;
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Deserialize;
+use trustification_api::search::SearchOptions;
+use v11y_index::Record;
+use v11y_model::osv;
+use v11y_model::related::{RelatedCve, Relation};
+
+/// Parameters for looking up related vulnerabilities.
+#[derive(Debug, Deserialize)]
+pub struct RelatedParams {
+    /// Max number of vulnerabilities to return for each kind of relation
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+const fn default_limit() -> usize {
+    20
+}
+
+/// Retrieve the vulnerabilities related to a CVE, or another vulnerability record.
+///
+/// Vulnerabilities are related by alias relationships, shared references, or shared affected package URLs.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Successfully retrieved", body = Vec<RelatedCve>),
+        (status = NOT_FOUND, description = "The vulnerability is not known"),
+    ),
+    params(
+        ("id" = String, Path, description = "The CVE to get the related vulnerabilities of"),
+        ("limit" = Option<usize>, Query, description = "Max number of vulnerabilities for each kind of relation"),
+    )
+)]
+#[get("/cve/{id}/related")]
+pub(crate) async fn get_related(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+    params: web::Query<RelatedParams>,
+) -> actix_web::Result<impl Responder> {
+    let id = id.to_uppercase();
+
+    let data = state.storage.get_data(&id).await.map_err(CveError::from)?;
+    let record = Record::parse(&data).map_err(|err| CveError::InvalidRecord(err.to_string()))?;
+
+    // aliasing records get assigned as labels of CVEs
+    let mut aliases = record.aliases();
+    match state.storage.get_labels(&id).await {
+        Ok(labels) => aliases.extend(osv::aliases_from_labels(&labels)),
+        Err(err) => log::warn!("Failed to get the labels of {id}: {err}"),
+    }
+
+    let queries = queries(&id, &aliases, &record.references(), &record.purls());
+    let limit = params.limit.max(1);
+    let related = web::block(move || {
+        let mut related: Vec<RelatedCve> = vec![];
+        for (relation, q) in queries {
+            log::debug!("Querying {relation:?} relations: '{q}'");
+            let (hits, _) = state.index.search(&q, 0, limit, SearchOptions::default())?;
+            for hit in hits {
+                add(&mut related, &id, relation, hit.document.id, hit.document.title);
+            }
+        }
+        Ok::<_, trustification_index::Error>(related)
+    })
+    .await?
+    .map_err(Error::Index)?;
+
+    Ok(HttpResponse::Ok().json(related))
+}
+
+/// Create the queries for the vulnerabilities related in each way, skipping ways without any query terms.
+fn queries(id: &str, aliases: &[String], references: &[String], purls: &[String]) -> Vec<(Relation, String)> {
+    // values can't be escaped in queries, but quotes don't occur in ids and URLs anyway
+    let terms = |qualifiers: &[&str], values: &[String]| {
+        values
+            .iter()
+            .filter(|value| !value.is_empty() && !value.contains('"'))
+            .flat_map(|value| {
+                qualifiers
+                    .iter()
+                    .map(move |qualifier| format!(r#"{qualifier}:"{value}""#))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut alias_terms = terms(&["alias"], &[id.to_string()]);
+    alias_terms.extend(terms(&["id", "alias"], aliases));
+
+    [
+        (Relation::Alias, alias_terms),
+        (Relation::Reference, terms(&["reference"], references)),
+        (Relation::Package, terms(&["purl"], purls)),
+    ]
+    .into_iter()
+    .filter(|(_, terms)| !terms.is_empty())
+    .map(|(relation, terms)| (relation, terms.join(" OR ")))
+    .collect()
+}
+
+/// Add a related vulnerability, merging the relations of vulnerabilities found more than once.
+fn add(related: &mut Vec<RelatedCve>, id: &str, relation: Relation, other: String, title: Option<String>) {
+    if other == id {
+        return;
+    }
+    match related.iter_mut().find(|related| related.id == other) {
+        Some(existing) => {
+            if !existing.relations.contains(&relation) {
+                existing.relations.push(relation);
+            }
+        }
+        None => related.push(RelatedCve {
+            id: other,
+            title,
+            relations: vec![relation],
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_queries() {
+        let queries = queries(
+            "PYSEC-2023-228",
+            &["CVE-2023-46695".to_string()],
+            &["https://www.djangoproject.com/weblog/2023/nov/01/security-releases/".to_string()],
+            &[],
+        );
+        assert_eq!(
+            queries,
+            vec![
+                (
+                    Relation::Alias,
+                    r#"alias:"PYSEC-2023-228" OR id:"CVE-2023-46695" OR alias:"CVE-2023-46695""#.to_string()
+                ),
+                (
+                    Relation::Reference,
+                    r#"reference:"https://www.djangoproject.com/weblog/2023/nov/01/security-releases/""#.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add() {
+        let mut related = vec![];
+        add(
+            &mut related,
+            "CVE-2023-46695",
+            Relation::Alias,
+            "CVE-2023-46695".into(),
+            None,
+        );
+        add(
+            &mut related,
+            "CVE-2023-46695",
+            Relation::Alias,
+            "GHSA-QMF9-6JQF-J8FQ".into(),
+            None,
+        );
+        add(
+            &mut related,
+            "CVE-2023-46695",
+            Relation::Package,
+            "GHSA-QMF9-6JQF-J8FQ".into(),
+            None,
+        );
+        add(
+            &mut related,
+            "CVE-2023-46695",
+            Relation::Package,
+            "CVE-2023-43665".into(),
+            None,
+        );
+
+        assert_eq!(
+            related,
+            vec![
+                RelatedCve {
+                    id: "GHSA-QMF9-6JQF-J8FQ".into(),
+                    title: None,
+                    relations: vec![Relation::Alias, Relation::Package],
+                },
+                RelatedCve {
+                    id: "CVE-2023-43665".into(),
+                    title: None,
+                    relations: vec![Relation::Package],
+                },
+            ]
+        );
+    }
+}
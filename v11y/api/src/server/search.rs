@@ -2,10 +2,16 @@ use crate::server::Error;
 use crate::AppState;
 use actix_web::{get, web, HttpResponse, Responder};
 use serde::Deserialize;
-use trustification_api::search::{SearchOptions, SearchResult};
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, UtcOffset};
+use trustification_api::search::{
+    FacetPivot, HistogramBucket, HistogramParams, SearchField, SearchOptions, SearchResult,
+};
 use trustification_auth::authenticator::user::UserInformation;
 use trustification_auth::authorizer::Authorizer;
 use trustification_auth::Permission;
+use trustification_common::error::ErrorInformation;
+use v11y_model::metrics::YearCount;
 
 /// Parameters for search query.
 #[derive(Debug, Deserialize)]
@@ -27,6 +33,12 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Cursor to iterate the results with, instead of using an offset
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Comma separated list of facets to compute bucket counts for
+    #[serde(default, with = "trustification_api::search::comma_separated")]
+    pub facets: Vec<String>,
 }
 
 const fn default_offset() -> usize {
@@ -55,6 +67,11 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            sort: None,
+            highlight: false,
+            cursor: value.cursor.clone(),
+            dedup_by: None,
         }
     }
 }
@@ -72,6 +89,8 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("cursor" = Option<String>, Query, description = "Iterate the results using a cursor: empty for the first page, or the cursor returned by the previous page"),
+        ("facets" = Option<String>, Query, description = "Comma separated list of facets: cwe, severity, year"),
     )
 )]
 #[get("/search")]
@@ -87,14 +106,253 @@ async fn search_cve(
 
     log::debug!("Querying CVE: '{}'", params.q);
 
+    let (result, total, facets, cursor) = web::block(move || {
+        let (result, total, cursor) = match &params.cursor {
+            Some(cursor) => state
+                .index
+                .search_cursor(&params.q, cursor, params.limit, (&params).into())?,
+            None => {
+                let (result, total) = state
+                    .index
+                    .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        let facets = state.index.facets(&params.q, &params.facets)?;
+        Ok::<_, trustification_index::Error>((result, total, facets, cursor))
+    })
+    .await?
+    .map_err(|err| {
+        log::warn!("Failed to search: {err}");
+        err
+    })
+    .map_err(Error::Index)?;
+
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total: Some(total),
+        result,
+        facets,
+        cursor,
+    }))
+}
+
+/// List the fields CVE search queries can be qualified with.
+///
+/// Each field comes with its type and an example query, for building queries programmatically.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "Search fields", body = Vec<SearchField>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/search/fields")]
+async fn search_cve_fields(
+    state: web::Data<AppState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    Ok(HttpResponse::Ok().json(state.index.search_fields()))
+}
+
+/// Count the CVEs matching a search query for each interval of time.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "Histogram computed", body = Vec<HistogramBucket>),
+        (status = BAD_REQUEST, description = "Bad query, field, interval or facet"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = Option<String>, Query, description = "Search query"),
+        ("field" = Option<String>, Query, description = "Date to bucket by: indexed (default), published, dateReserved, dateUpdated, dateRejected, kevAdded or kevDue"),
+        ("interval" = Option<String>, Query, description = "Length of the intervals, defaults to 7d"),
+        ("offset" = Option<String>, Query, description = "Shift of the intervals from the epoch"),
+        ("facet" = Option<String>, Query, description = "Facet to split the intervals by: cwe or severity"),
+    )
+)]
+#[get("/cve/histogram")]
+async fn cve_histogram(
+    state: web::Data<AppState>,
+    params: web::Query<HistogramParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+
+    log::debug!("Computing CVE histogram: '{}'", params.q);
+
+    let result = web::block(move || state.index.histogram_for(&params, "indexed")).await?;
+
+    match result {
+        Ok(buckets) => Ok(HttpResponse::Ok().json(buckets)),
+        Err(
+            err @ (trustification_index::Error::NotHistogrammable(_)
+            | trustification_index::Error::NotFacetable(_)
+            | trustification_index::Error::InvalidInterval(_)
+            | trustification_index::Error::QueryParser(_)),
+        ) => Ok(HttpResponse::BadRequest().json(ErrorInformation {
+            error: "InvalidHistogram".to_string(),
+            message: err.to_string(),
+            details: String::new(),
+        })),
+        Err(err) => {
+            log::warn!("Failed to compute histogram: {err}");
+            Err(Error::Index(err).into())
+        }
+    }
+}
+
+/// Parameters for the metrics by year.
+#[derive(Debug, Deserialize)]
+pub struct ByYearParams {
+    /// Search query string, selecting the records to count
+    #[serde(default)]
+    pub q: String,
+}
+
+/// Count the CVEs, and other vulnerability records, by the year of their publication and their severity.
+///
+/// Records without publication date, like rejected CVEs which were never published, aren't counted.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "Counts computed", body = Vec<YearCount>),
+        (status = BAD_REQUEST, description = "Bad query"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("q" = Option<String>, Query, description = "Search query"),
+    )
+)]
+#[get("/cve/metrics/by-year")]
+async fn cve_by_year(
+    state: web::Data<AppState>,
+    params: web::Query<ByYearParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    log::debug!("Counting CVEs by year: '{}'", params.q);
+
+    let result = web::block(move || state.index.pivot(&params.q, "year", "severity")).await?;
+    match result {
+        Ok(years) => Ok(HttpResponse::Ok().json(by_year(years))),
+        Err(err @ trustification_index::Error::QueryParser(_)) => {
+            Ok(HttpResponse::BadRequest().json(ErrorInformation {
+                error: "InvalidQuery".to_string(),
+                message: err.to_string(),
+                details: String::new(),
+            }))
+        }
+        Err(err) => {
+            log::warn!("Failed to count CVEs by year: {err}");
+            Err(Error::Index(err).into())
+        }
+    }
+}
+
+/// Convert the buckets of the years into counts, most recent year first.
+fn by_year(years: Vec<FacetPivot>) -> Vec<YearCount> {
+    let mut years: Vec<_> = years
+        .into_iter()
+        .filter_map(|year| {
+            Some(YearCount {
+                year: year.value.parse().ok()?,
+                count: year.count,
+                severities: year
+                    .facets
+                    .into_iter()
+                    .map(|facet| (facet.value, facet.count))
+                    .collect(),
+            })
+        })
+        .collect();
+    years.sort_by(|a, b| b.year.cmp(&a.year));
+    years
+}
+
+/// Parameters for the changes query.
+#[derive(Debug, Deserialize)]
+pub struct ChangesParams {
+    /// Only return records created or modified after this time, in RFC 3339 format
+    pub since: String,
+    /// Offset of documents to return (for pagination)
+    #[serde(default = "default_offset")]
+    pub offset: usize,
+    /// Max number of documents to return
+    #[serde(default = "default_changes_limit")]
+    pub limit: usize,
+}
+
+const fn default_changes_limit() -> usize {
+    100
+}
+
+/// Get the CVEs, and other vulnerability records, created or modified after a point in time.
+///
+/// Records are returned in the order of their last change, oldest first, so that the time of the last change of the
+/// last record can be used as `since` of the next request. Rejected records are included, as a rejection is a change.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "Changes found"),
+        (status = BAD_REQUEST, description = "Invalid point in time"),
+        (status = 401, description = "Not authenticated"),
+    ),
+    params(
+        ("since" = String, Query, description = "Point in time, in RFC 3339 format"),
+        ("offset" = Option<usize>, Query, description = "Offset of the records to return"),
+        ("limit" = Option<usize>, Query, description = "Max number of records to return"),
+    )
+)]
+#[get("/cve/changes")]
+async fn cve_changes(
+    state: web::Data<AppState>,
+    params: web::Query<ChangesParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let params = params.into_inner();
+    let since = match OffsetDateTime::parse(&params.since, &Rfc3339) {
+        Ok(since) => since.to_offset(UtcOffset::UTC),
+        Err(err) => {
+            return Ok(HttpResponse::BadRequest().json(ErrorInformation {
+                error: "InvalidSince".to_string(),
+                message: "Invalid point in time, expected RFC 3339 format".to_string(),
+                details: err.to_string(),
+            }))
+        }
+    };
+    let q = format!(
+        r#"indexed:>"{}" (is:published OR is:rejected) sort:indexed"#,
+        since
+            .format(&Rfc3339)
+            .map_err(actix_web::error::ErrorInternalServerError)?
+    );
+
+    log::debug!("Querying CVE changes: '{q}'");
+
     let (result, total) = web::block(move || {
         state
             .index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+            .search(&q, params.offset, params.limit, SearchOptions::default())
     })
     .await?
     .map_err(|err| {
-        log::warn!("Failed to search: {err}");
+        log::warn!("Failed to search changes: {err}");
         err
     })
     .map_err(Error::Index)?;
@@ -102,5 +360,39 @@ async fn search_cve(
     Ok(HttpResponse::Ok().json(SearchResult {
         total: Some(total),
         result,
+        facets: Default::default(),
+        cursor: None,
     }))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use trustification_api::search::FacetCount;
+
+    #[test]
+    fn test_by_year() {
+        let pivot = |value: &str, count, facets: &[(&str, u64)]| FacetPivot {
+            value: value.to_string(),
+            count,
+            facets: facets
+                .iter()
+                .map(|(value, count)| FacetCount {
+                    value: value.to_string(),
+                    count: *count,
+                })
+                .collect(),
+        };
+        let years = by_year(vec![
+            pivot("2022", 3, &[("low", 1)]),
+            pivot("2023", 5, &[("high", 2), ("critical", 1)]),
+            pivot("", 1, &[]),
+        ]);
+
+        assert_eq!(years.len(), 2);
+        assert_eq!(years[0].year, 2023);
+        assert_eq!(years[0].count, 5);
+        assert_eq!(years[0].severities["high"], 2);
+        assert_eq!(years[1].year, 2022);
+    }
+}
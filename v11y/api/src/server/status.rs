@@ -0,0 +1,65 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use time::OffsetDateTime;
+use trustification_auth::authenticator::user::UserInformation;
+use trustification_auth::authorizer::Authorizer;
+use trustification_auth::Permission;
+use trustification_storage::Storage;
+use v11y_model::status::{Source, SourceStatus};
+
+use crate::server::vulnerability::CveError;
+use crate::AppState;
+
+/// Get the status of a source, which is the status of a source never synced if none was stored.
+async fn load(storage: &Storage, source: Source) -> Result<SourceStatus, CveError> {
+    match storage.get_status(source.name()).await? {
+        Some(status) => {
+            serde_json::from_value(status).map_err(|err| CveError::InvalidRecord(format!("status of {source}: {err}")))
+        }
+        None => Ok(SourceStatus::new(source)),
+    }
+}
+
+/// Record the outcome of a manual upload, logging failures to record it, which don't fail the upload.
+///
+/// Uploads handled concurrently may overwrite the status of each other, so the counts are approximate.
+pub(crate) async fn record_upload(storage: &Storage, stored: bool) {
+    let result = async {
+        let mut status = load(storage, Source::Manual).await?;
+        status.record_upload(OffsetDateTime::now_utc(), stored);
+        let status = serde_json::to_value(&status).map_err(|err| CveError::InvalidRecord(err.to_string()))?;
+        storage.put_status(Source::Manual.name(), &status).await?;
+        Ok::<_, CveError>(())
+    }
+    .await;
+    if let Err(err) = result {
+        log::warn!("Failed to record the status of manual uploads: {err}");
+    }
+}
+
+/// Report the status of the sources of vulnerability information
+///
+/// For every source, the last successful sync, and the numbers of stored and failed documents, tell whether its
+/// mirror is stale.
+#[utoipa::path(
+    get,
+    tag = "cve",
+    responses(
+        (status = 200, description = "Status retrieved", body = Vec<SourceStatus>),
+        (status = 401, description = "Not authenticated"),
+    ),
+)]
+#[get("/cve/status")]
+pub(crate) async fn cve_status(
+    state: web::Data<AppState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::ReadSbom)?;
+
+    let mut result = Vec::with_capacity(Source::ALL.len());
+    for source in Source::ALL {
+        result.push(load(&state.storage, source).await?);
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
@@ -1,14 +1,20 @@
 use actix_web::body::BoxBody;
-use actix_web::{error::ResponseError, get, web, HttpResponse, Responder};
+use actix_web::{error::ResponseError, get, web, web::Bytes, HttpResponse, Responder};
+use futures::{stream, StreamExt};
+use std::collections::BTreeSet;
 use trustification_auth::authenticator::user::UserInformation;
 use trustification_auth::authorizer::Authorizer;
 use trustification_auth::Permission;
 use trustification_common::error::ErrorInformation;
 use trustification_storage::S3Path;
+use v11y_model::batch::{CveBatch, MAX_BATCH_SIZE};
+use v11y_model::epss::Epss;
+use v11y_model::kev::KnownExploited;
+use v11y_model::osv::Osv;
 use v11y_model::Vulnerability;
 
 use crate::db::DbError;
-use crate::server::Error;
+use crate::server::{status, Error};
 use crate::AppState;
 
 impl ResponseError for DbError {}
@@ -17,12 +23,26 @@ impl ResponseError for DbError {}
 pub enum CveError {
     #[error("storage error: {0}")]
     Storage(#[from] trustification_storage::Error),
+    #[error("invalid record: {0}")]
+    InvalidRecord(String),
+    #[error("batch of {0} CVEs exceeds the limit of {MAX_BATCH_SIZE}")]
+    BatchTooLarge(usize),
 }
 
 impl ResponseError for CveError {
     fn error_response(&self) -> HttpResponse<BoxBody> {
         match self {
             Self::Storage(trustification_storage::Error::NotFound) => HttpResponse::NotFound().finish(),
+            Self::InvalidRecord(details) => HttpResponse::BadRequest().json(ErrorInformation {
+                error: "InvalidRecord".to_string(),
+                message: "Invalid record".to_string(),
+                details: details.clone(),
+            }),
+            Self::BatchTooLarge(_) => HttpResponse::BadRequest().json(ErrorInformation {
+                error: "BatchTooLarge".to_string(),
+                message: self.to_string(),
+                details: String::new(),
+            }),
             err => HttpResponse::InternalServerError().json(ErrorInformation {
                 error: "InternalError".to_string(),
                 message: "Internal error".to_string(),
@@ -49,10 +69,57 @@ pub(crate) async fn ingest_vulnerability(
 
     log::info!("Ingest: {:#?}", &vuln.0);
 
-    state.db.ingest(&vuln).await.map_err(|_| Error::Db)?;
+    let result = state.db.ingest(&vuln).await;
+    status::record_upload(&state.storage, result.is_ok()).await;
+    result.map_err(|_| Error::Db)?;
     Ok(HttpResponse::Ok().finish())
 }
 
+/// Store a vulnerability record in the OSV format, which then gets indexed
+#[utoipa::path(post, path = "/osv",
+    request_body(content = Object, description = "The OSV record", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully stored"),
+        (status = BAD_REQUEST, description = "Invalid OSV record, or identified by or aliasing a CVE id"),
+    ),
+)]
+pub(crate) async fn ingest_osv(
+    state: web::Data<AppState>,
+    data: Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<impl Responder> {
+    authorizer.require(&user, Permission::IngestVulnerability)?;
+
+    let result = store_osv(&state, &data).await;
+    status::record_upload(&state.storage, result.is_ok()).await;
+    result?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Parse and store an uploaded OSV record.
+async fn store_osv(state: &AppState, data: &[u8]) -> Result<(), CveError> {
+    let osv: Osv = serde_json::from_slice(data).map_err(|err| CveError::InvalidRecord(err.to_string()))?;
+    let key = osv.storage_key().ok_or_else(|| {
+        CveError::InvalidRecord(format!(
+            "{} must not be a CVE id, CVEs are taken from the CVE list",
+            osv.id
+        ))
+    })?;
+    // the vulnerability is stored as its CVE, which gets the aliases of the records from the collectors
+    let cves = osv.cve_aliases();
+    if !cves.is_empty() {
+        return Err(CveError::InvalidRecord(format!(
+            "{key} must not alias a CVE, as it is stored as {}",
+            cves.join(", ")
+        )));
+    }
+
+    log::debug!("Storing OSV record {key}");
+    state.storage.put_json_slice(&key, data).await?;
+    Ok(())
+}
+
 /// Retrieve vulnerability information
 #[utoipa::path(
     responses(
@@ -69,6 +136,105 @@ pub(crate) async fn get_cve(state: web::Data<AppState>, id: web::Path<String>) -
     Ok(HttpResponse::Ok().streaming(cve))
 }
 
+/// maximum number of CVEs fetched from the storage in parallel
+const BATCH_CONCURRENCY: usize = 16;
+/// maximum size of the list of CVE ids of a batch request
+pub(crate) const BATCH_PAYLOAD_LIMIT: usize = MAX_BATCH_SIZE * 64;
+
+/// Retrieve several CVEs at once
+///
+/// The request body is the list of CVE ids. Records which are not known are reported as missing, instead of failing
+/// the request.
+#[utoipa::path(post, path = "/cve/batch",
+    request_body = Vec<String>,
+    responses(
+        (status = 200, description = "Successfully retrieved", body = CveBatch),
+        (status = BAD_REQUEST, description = "Too many CVEs were requested"),
+    ),
+)]
+pub(crate) async fn get_cves(
+    state: web::Data<AppState>,
+    ids: web::Json<Vec<String>>,
+) -> Result<impl Responder, CveError> {
+    let ids = batch_ids(ids.into_inner())?;
+
+    let mut results = stream::iter(ids)
+        .map(|id| {
+            let state = state.clone();
+            async move {
+                let result = state.storage.get_data(&id).await;
+                (id, result)
+            }
+        })
+        .buffer_unordered(BATCH_CONCURRENCY);
+
+    let mut batch = CveBatch::default();
+    while let Some((id, result)) = results.next().await {
+        match result {
+            Ok(data) => {
+                let cve =
+                    serde_json::from_slice(&data).map_err(|err| CveError::InvalidRecord(format!("{id}: {err}")))?;
+                batch.cves.insert(id, cve);
+            }
+            Err(trustification_storage::Error::NotFound) => batch.missing.push(id),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    batch.missing.sort_unstable();
+
+    Ok(HttpResponse::Ok().json(batch))
+}
+
+/// Normalize the ids of a batch request, checking its size.
+fn batch_ids(ids: Vec<String>) -> Result<BTreeSet<String>, CveError> {
+    let ids: BTreeSet<_> = ids
+        .iter()
+        .map(|id| id.trim().to_uppercase())
+        .filter(|id| !id.is_empty())
+        .collect();
+    match ids.len() > MAX_BATCH_SIZE {
+        true => Err(CveError::BatchTooLarge(ids.len())),
+        false => Ok(ids),
+    }
+}
+
+/// Retrieve the EPSS score of a CVE
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Successfully retrieved", body = Epss),
+        (status = NOT_FOUND, description = "The CVE is not known, or has no score"),
+    ),
+)]
+#[get("/cve/{id}/epss")]
+pub(crate) async fn get_epss(state: web::Data<AppState>, id: web::Path<String>) -> Result<impl Responder, CveError> {
+    let labels = state.storage.get_labels(&id.to_uppercase()).await?;
+
+    match Epss::from_labels(&labels) {
+        Some(epss) => Ok(HttpResponse::Ok().json(epss)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Retrieve the entry of the known exploited vulnerabilities catalog of a CVE
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Successfully retrieved", body = KnownExploited),
+        (status = NOT_FOUND, description = "The CVE is not known, or not known to be exploited"),
+    ),
+)]
+#[get("/cve/{id}/kev")]
+pub(crate) async fn get_known_exploited(
+    state: web::Data<AppState>,
+    id: web::Path<String>,
+) -> Result<impl Responder, CveError> {
+    let labels = state.storage.get_labels(&id.to_uppercase()).await?;
+
+    match KnownExploited::from_labels(&labels) {
+        Some(kev) => Ok(HttpResponse::Ok().json(kev)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
 /// Retrieve vulnerability information
 #[utoipa::path(
     responses(
@@ -103,20 +269,41 @@ pub(crate) async fn get_by_alias(
 
 #[cfg(test)]
 mod test {
+    use super::{batch_ids, CveError, MAX_BATCH_SIZE};
     use crate::server::config;
     use crate::AppState;
     use actix_web::{test, test::TestRequest, web, App};
     use prometheus::Registry;
     use std::sync::Arc;
     use tempdir::TempDir;
-    use trustification_index::IndexStore;
+    use trustification_index::ShardedIndexStore;
     use trustification_storage::{Storage, StorageConfig};
+    use v11y_model::epss::Epss;
+    use v11y_model::kev::KnownExploited;
     use v11y_model::Vulnerability;
 
+    #[test]
+    fn test_batch_ids() {
+        let ids = batch_ids(vec![
+            "cve-2023-44487".to_string(),
+            " CVE-2023-44487 ".to_string(),
+            "".to_string(),
+            "CVE-2021-44228".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            ids.into_iter().collect::<Vec<_>>(),
+            vec!["CVE-2021-44228".to_string(), "CVE-2023-44487".to_string()]
+        );
+
+        let ids = (0..=MAX_BATCH_SIZE).map(|i| format!("CVE-2023-{i}")).collect();
+        assert!(matches!(batch_ids(ids), Err(CveError::BatchTooLarge(_))));
+    }
+
     async fn app_state() -> anyhow::Result<Arc<AppState>> {
         let base = TempDir::new("v11y")?;
         let storage = Storage::new(StorageConfig::default(), &Registry::new())?;
-        let index = IndexStore::new_in_memory(v11y_index::Index::new())?;
+        let index = ShardedIndexStore::new_in_memory(v11y_index::Index::new, 1)?;
         Ok(Arc::new(AppState::new(base, storage, index).await?))
     }
 
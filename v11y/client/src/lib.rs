@@ -1,5 +1,6 @@
 use reqwest::{Response, Url};
-use trustification_api::search::SearchResult;
+use trustification_api::search::{HistogramBucket, SearchField, SearchOptions, SearchResult};
+use trustification_api::Apply;
 use trustification_auth::client::{TokenInjector, TokenProvider};
 use trustification_infrastructure::tracing::PropagateCurrentContext;
 use url::ParseError;
@@ -20,6 +21,10 @@ impl V11yUrl {
         self.base_url.join("/api/v1/vulnerability")
     }
 
+    pub fn osv_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/osv")
+    }
+
     pub fn get_cve_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
         let mut url = self.base_url.join("/api/v1/cve")?;
         url.path_segments_mut()
@@ -28,10 +33,74 @@ impl V11yUrl {
         Ok(url)
     }
 
+    pub fn get_cves_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/batch")
+    }
+
+    pub fn get_epss_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
+        let mut url = self.base_url.join("/api/v1/cve")?;
+        url.path_segments_mut()
+            .map_err(|()| ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .push(id.as_ref())
+            .push("epss");
+        Ok(url)
+    }
+
+    pub fn get_known_exploited_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
+        let mut url = self.base_url.join("/api/v1/cve")?;
+        url.path_segments_mut()
+            .map_err(|()| ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .push(id.as_ref())
+            .push("kev");
+        Ok(url)
+    }
+
+    pub fn get_related_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
+        let mut url = self.base_url.join("/api/v1/cve")?;
+        url.path_segments_mut()
+            .map_err(|()| ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .push(id.as_ref())
+            .push("related");
+        Ok(url)
+    }
+
+    pub fn get_affects_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
+        let mut url = self.base_url.join("/api/v1/cve")?;
+        url.path_segments_mut()
+            .map_err(|()| ParseError::RelativeUrlWithCannotBeABaseBase)?
+            .push(id.as_ref())
+            .push("affects");
+        Ok(url)
+    }
+
+    pub fn by_cpe_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/cpe")
+    }
+
+    pub fn status_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/status")
+    }
+
     pub fn search_url(&self) -> Result<Url, ParseError> {
         self.base_url.join("/api/v1/search")
     }
 
+    pub fn search_fields_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/search/fields")
+    }
+
+    pub fn changes_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/changes")
+    }
+
+    pub fn histogram_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/histogram")
+    }
+
+    pub fn by_year_url(&self) -> Result<Url, ParseError> {
+        self.base_url.join("/api/v1/cve/metrics/by-year")
+    }
+
     pub fn get_vulnerability_url(&self, id: impl AsRef<str>) -> Result<Url, ParseError> {
         self.base_url.join("/api/v1/vulnerability/")?.join(id.as_ref())
     }
@@ -97,6 +166,20 @@ impl V11yClient {
             .map(|_| ())?)
     }
 
+    /// Store a record in the OSV format, which must neither be identified by a CVE id, nor alias one.
+    pub async fn ingest_osv(&self, osv: &impl serde::Serialize) -> Result<(), anyhow::Error> {
+        self.client
+            .post(self.v11y_url.osv_url()?)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .json(osv)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
     pub async fn get_cve(&self, id: &str) -> Result<Response, anyhow::Error> {
         Ok(self
             .client
@@ -108,6 +191,121 @@ impl V11yClient {
             .await?)
     }
 
+    /// Get several CVEs at once, at most [`batch::MAX_BATCH_SIZE`].
+    pub async fn get_cves(&self, ids: &[String]) -> Result<batch::CveBatch, anyhow::Error> {
+        Ok(self
+            .client
+            .post(self.v11y_url.get_cves_url()?)
+            .json(ids)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Get the EPSS score of a CVE, which is `None` if the CVE has no score.
+    pub async fn get_epss(&self, id: &str) -> Result<Option<epss::Epss>, anyhow::Error> {
+        let response = self
+            .client
+            .get(self.v11y_url.get_epss_url(id)?)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Get the entry of the known exploited vulnerabilities catalog of a CVE, which is `None` if the CVE is not
+    /// known to be exploited.
+    pub async fn get_known_exploited(&self, id: &str) -> Result<Option<kev::KnownExploited>, anyhow::Error> {
+        let response = self
+            .client
+            .get(self.v11y_url.get_known_exploited_url(id)?)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Get the vulnerabilities related to a CVE, which is `None` if the CVE is not known.
+    pub async fn get_related(&self, id: &str, limit: usize) -> Result<Option<Vec<related::RelatedCve>>, anyhow::Error> {
+        let response = self
+            .client
+            .get(self.v11y_url.get_related_url(id)?)
+            .query(&[("limit", limit)])
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Check if a CVE affects a CPE, which is `None` if the CVE is not known.
+    pub async fn affects(&self, id: &str, cpe: &str) -> Result<Option<cpe::AffectedCpe>, anyhow::Error> {
+        let response = self
+            .client
+            .get(self.v11y_url.get_affects_url(id)?)
+            .query(&[("cpe", cpe)])
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Get the vulnerabilities affecting a CPE.
+    pub async fn by_cpe(&self, cpe: &str, limit: usize) -> Result<Vec<cpe::AffectedCpe>, anyhow::Error> {
+        Ok(self
+            .client
+            .get(self.v11y_url.by_cpe_url()?)
+            .query(&[("cpe", cpe)])
+            .query(&[("limit", limit)])
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Get the status of the sources of vulnerability information.
+    pub async fn status(&self) -> Result<Vec<status::SourceStatus>, anyhow::Error> {
+        Ok(self
+            .client
+            .get(self.v11y_url.status_url()?)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
     pub async fn get_vulnerability(&self, id: &str) -> Result<Vec<Vulnerability>, Error> {
         Ok(self
             .client
@@ -141,12 +339,110 @@ impl V11yClient {
         q: &str,
         limit: usize,
         offset: usize,
+    ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, anyhow::Error> {
+        self.search_with_options(q, limit, offset, &SearchOptions::default())
+            .await
+    }
+
+    /// Search, using additional options like facets.
+    pub async fn search_with_options(
+        &self,
+        q: &str,
+        limit: usize,
+        offset: usize,
+        options: &SearchOptions,
     ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, anyhow::Error> {
         Ok(self
             .client
             .get(self.v11y_url.search_url()?)
             .query(&[("q", q)])
             .query(&[("limit", limit), ("offset", offset)])
+            .apply(options)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Get the fields search queries can be qualified with.
+    pub async fn search_fields(&self) -> Result<Vec<SearchField>, anyhow::Error> {
+        Ok(self
+            .client
+            .get(self.v11y_url.search_fields_url()?)
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Count the records matching a query for each interval of a date, like `indexed`, optionally split by a facet.
+    pub async fn histogram(
+        &self,
+        q: &str,
+        field: &str,
+        interval: std::time::Duration,
+        offset: std::time::Duration,
+        facet: Option<&str>,
+    ) -> Result<Vec<HistogramBucket>, anyhow::Error> {
+        let mut request = self
+            .client
+            .get(self.v11y_url.histogram_url()?)
+            .query(&[("q", q), ("field", field)])
+            .query(&[
+                ("interval", format!("{}s", interval.as_secs())),
+                ("offset", format!("{}s", offset.as_secs())),
+            ]);
+        if let Some(facet) = facet {
+            request = request.query(&[("facet", facet)]);
+        }
+        Ok(request
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Count the records matching a query by the year of their publication and their severity.
+    pub async fn by_year(&self, q: &str) -> Result<Vec<metrics::YearCount>, anyhow::Error> {
+        Ok(self
+            .client
+            .get(self.v11y_url.by_year_url()?)
+            .query(&[("q", q)])
+            .propagate_current_context()
+            .inject_token(self.provider.as_ref())
+            .await?
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Get the records created or modified after a point in time, oldest change first.
+    pub async fn changes(
+        &self,
+        since: time::OffsetDateTime,
+        limit: usize,
+        offset: usize,
+    ) -> Result<SearchResult<Vec<SearchHit<SearchDocument>>>, anyhow::Error> {
+        let since = since.format(&time::format_description::well_known::Rfc3339)?;
+        Ok(self
+            .client
+            .get(self.v11y_url.changes_url()?)
+            .query(&[("since", since)])
+            .query(&[("limit", limit), ("offset", offset)])
             .propagate_current_context()
             .inject_token(self.provider.as_ref())
             .await?
@@ -1,3 +1,5 @@
+mod text;
+
 use core::str::FromStr;
 pub use cve::Cve;
 use cve::{common, Published, Rejected, Timestamp};
@@ -5,30 +7,135 @@ use cvss::v3::Base;
 use cvss::Severity;
 use serde_json::Value;
 use sikula::prelude::*;
+use std::cell::Cell;
 use std::time::Duration;
+use strum::VariantNames;
+pub use text::TextAnalysis;
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    create_boolean_query, create_date_query, create_float_query, create_string_query_case, create_text_query,
-    field2bool, field2date_opt, field2str, field2strvec,
+    boosts::boost_query,
+    create_boolean_query, create_date_query, create_float_query, create_purl_query, create_string_query,
+    create_string_query_case, field2bool, field2date_opt, field2str, field2strvec,
     metadata::doc2metadata,
-    sort_by,
+    search_field, sort_by,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
-        query::{AllQuery, Occur, Query, TermQuery},
-        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
+        query::{AllQuery, BooleanQuery, EmptyQuery, Occur, Query, RegexQuery, TermQuery},
+        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING},
         store::ZstdCompressor,
+        tokenizer::TokenizerManager,
         DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader,
     },
-    term2query, Case, Document, Error as SearchError, SearchQuery,
+    term2query,
+    tokenizer::{FieldTokenizer, Tokenizer, Tokenizers},
+    Case, Document, Error as SearchError, Labels, QueryContext, SearchQuery,
 };
-use v11y_model::search::{Cves, CvesSortable, SearchDocument, SearchHit};
+use v11y_model::cpe::{Cpe, CpeMatch};
+use v11y_model::cve_record;
+use v11y_model::cvss4::Cvss4;
+use v11y_model::cwe::{self, Cwe};
+use v11y_model::epss::Epss;
+use v11y_model::exploit::Exploit;
+use v11y_model::kev::KnownExploited;
+use v11y_model::nvd::NvdAnalysis;
+use v11y_model::osv;
+pub use v11y_model::osv::Osv;
+use v11y_model::search::{CveState, Cves, CvesSortable, SearchDocument, SearchHit};
+
+/// A stored vulnerability record.
+#[derive(Clone, Debug)]
+pub enum Record {
+    /// A CVE record, along with the JSON form of its containers, the CNA container followed by the ADP containers.
+    ///
+    /// The containers are kept as they were stored, as the CVE record model doesn't cover all of their parts, like the
+    /// package URLs and CVSS 4.0 metrics of CVE JSON 5.1, nor the ADP containers at all.
+    Cve(Cve, Vec<Value>),
+    Osv(Osv),
+}
+
+impl Record {
+    /// Parse a record, which is either a CVE record of any CVE JSON 5 version, or an OSV record.
+    pub fn parse(data: &[u8]) -> Result<Self, serde_json::Error> {
+        let mut value: Value = serde_json::from_slice(data)?;
+        if value["dataType"].as_str() == Some("CVE_RECORD") {
+            let cna = value["containers"]["cna"].clone();
+            let adp = cve_record::to_model(&mut value);
+            let containers = std::iter::once(cna).chain(adp).collect();
+            Ok(Self::Cve(serde_json::from_value(value)?, containers))
+        } else {
+            Ok(Self::Osv(serde_json::from_value(value)?))
+        }
+    }
+
+    /// The other identifiers of the vulnerability. CVE records don't carry any.
+    pub fn aliases(&self) -> Vec<String> {
+        match self {
+            Self::Cve(..) => vec![],
+            Self::Osv(osv) => osv.aliases.iter().map(|alias| alias.to_uppercase()).collect(),
+        }
+    }
+
+    /// The URLs of the references.
+    pub fn references(&self) -> Vec<String> {
+        match self {
+            Self::Cve(Cve::Published(_), containers) => unique(containers.iter().flat_map(container_references)),
+            Self::Cve(Cve::Rejected(_), _) => vec![],
+            Self::Osv(osv) => osv.references.iter().map(|reference| reference.url.clone()).collect(),
+        }
+    }
+
+    /// The package URLs of the affected packages.
+    pub fn purls(&self) -> Vec<String> {
+        match self {
+            Self::Cve(Cve::Published(_), containers) => unique(containers.iter().flat_map(container_purls)),
+            Self::Cve(Cve::Rejected(_), _) => vec![],
+            Self::Osv(osv) => osv
+                .affected
+                .iter()
+                .filter_map(|affected| affected.package.as_ref()?.purl.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Collect values, skipping duplicates.
+fn unique(values: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for value in values {
+        if !result.contains(&value) {
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// The URLs of the references of a CNA or ADP container.
+fn container_references(cna: &Value) -> Vec<String> {
+    cna["references"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|reference| reference["url"].as_str().map(ToString::to_string))
+        .collect()
+}
+
+/// The package URLs of the affected products of a CNA or ADP container, as available since CVE JSON 5.1.
+fn container_purls(cna: &Value) -> Vec<String> {
+    cna["affected"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|affected| affected["packageURL"].as_str().map(ToString::to_string))
+        .collect()
+}
 
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    tokenizers: Tokenizers,
 }
 
 struct Fields {
@@ -36,19 +143,49 @@ struct Fields {
 
     id: Field,
     published: Field,
+    state: Field,
 
     date_reserved: Field,
     date_published: Field,
     date_updated: Field,
     date_rejected: Field,
+    /// the year of the publication date
+    year: Field,
 
     assigner_short_name: Field,
 
     title: Field,
     description: Field,
 
+    alias: Field,
+    ecosystem: Field,
+    package: Field,
+    purl: Field,
+    affected: Field,
+    reference: Field,
+
     cvss3x_score: Field,
+    cvss4_score: Field,
     severity: Field,
+
+    cwe: Field,
+    cwe_name: Field,
+
+    epss: Field,
+    epss_percentile: Field,
+    epss_date: Field,
+
+    known_exploited: Field,
+    kev_date_added: Field,
+    kev_due_date: Field,
+    kev_required_action: Field,
+    kev_ransomware: Field,
+
+    exploit_available: Field,
+    exploit: Field,
+
+    cpe: Field,
+    cpe_match: Field,
 }
 
 impl Default for Index {
@@ -59,28 +196,71 @@ impl Default for Index {
 
 impl Index {
     pub fn new() -> Self {
+        Self::with_analysis(TextAnalysis::default())
+    }
+
+    /// Create an index, analyzing titles and descriptions as configured.
+    pub fn with_analysis(analysis: TextAnalysis) -> Self {
+        Self::with_tokenizers(analysis, &[])
+    }
+
+    /// Create an index, with the tokenizers configured for its fields.
+    ///
+    /// Titles and descriptions are analyzed as configured by default, package names are raw.
+    pub fn with_tokenizers(analysis: TextAnalysis, configured: &[FieldTokenizer]) -> Self {
+        let mut tokenizers = Tokenizers::new(configured);
         let mut schema = Schema::builder();
 
         let fields = Fields {
-            indexed_timestamp: schema.add_date_field("indexed_timestamp", STORED),
+            indexed_timestamp: schema.add_date_field("indexed_timestamp", INDEXED | FAST | STORED),
             id: schema.add_text_field("id", STRING | FAST | STORED),
             published: schema.add_bool_field("published", FAST | INDEXED | STORED),
+            state: schema.add_text_field("state", STRING | FAST | STORED),
 
             assigner_short_name: schema.add_text_field("assigner_short_name", STRING | STORED),
-            date_reserved: schema.add_date_field("date_reserved", INDEXED),
+            date_reserved: schema.add_date_field("date_reserved", INDEXED | FAST),
             date_published: schema.add_date_field("date_published", INDEXED | FAST | STORED),
             date_updated: schema.add_date_field("date_updated", INDEXED | FAST | STORED),
             date_rejected: schema.add_date_field("date_rejected", INDEXED | FAST | STORED),
+            year: schema.add_text_field("year", STRING | FAST),
+
+            title: tokenizers.add_text_field(&mut schema, "title", analysis.tokenizer(), STORED),
+            description: tokenizers.add_text_field(&mut schema, "description", analysis.tokenizer(), STORED),
 
-            title: schema.add_text_field("title", TEXT | STORED),
-            description: schema.add_text_field("description", TEXT | STORED),
+            alias: schema.add_text_field("alias", STRING | STORED),
+            ecosystem: schema.add_text_field("ecosystem", STRING),
+            package: tokenizers.add_text_field(&mut schema, "package", Tokenizer::Raw, ()),
+            purl: schema.add_text_field("purl", STRING | STORED),
+            affected: schema.add_text_field("affected", STORED),
+            reference: schema.add_text_field("reference", STRING),
 
             cvss3x_score: schema.add_f64_field("cvss3x_score", FAST | INDEXED | STORED),
+            cvss4_score: schema.add_f64_field("cvss4_score", FAST | INDEXED | STORED),
             severity: schema.add_text_field("severity", STRING | FAST),
+
+            cwe: schema.add_text_field("cwe", STRING | FAST | STORED),
+            cwe_name: schema.add_text_field("cwe_name", STORED),
+
+            epss: schema.add_f64_field("epss", FAST | INDEXED | STORED),
+            epss_percentile: schema.add_f64_field("epss_percentile", FAST | INDEXED | STORED),
+            epss_date: schema.add_text_field("epss_date", STRING | STORED),
+
+            known_exploited: schema.add_bool_field("known_exploited", INDEXED | STORED),
+            kev_date_added: schema.add_date_field("kev_date_added", INDEXED | FAST | STORED),
+            kev_due_date: schema.add_date_field("kev_due_date", INDEXED | FAST | STORED),
+            kev_required_action: schema.add_text_field("kev_required_action", STORED),
+            kev_ransomware: schema.add_bool_field("kev_ransomware", STORED),
+
+            exploit_available: schema.add_bool_field("exploit_available", INDEXED | STORED),
+            exploit: schema.add_text_field("exploit", STRING | STORED),
+
+            cpe: schema.add_text_field("cpe", STRING),
+            cpe_match: schema.add_text_field("cpe_match", STORED),
         };
         Self {
             schema: schema.build(),
             fields,
+            tokenizers,
         }
     }
 
@@ -94,6 +274,10 @@ impl Index {
 
         Self::add_timestamp(document, self.fields.date_reserved, metadata.date_reserved);
         Self::add_timestamp(document, self.fields.date_published, metadata.date_published);
+        let published: Option<Timestamp> = metadata.date_published.into();
+        if let Some(published) = published {
+            document.add_text(self.fields.year, published.year().to_string());
+        }
         Self::add_timestamp(document, self.fields.date_updated, metadata.date_updated);
 
         if let Some(short_name) = &metadata.assigner_short_name {
@@ -101,12 +285,18 @@ impl Index {
         }
     }
 
-    fn index_published_cve(&self, cve: &Published, _id: &str) -> Result<Vec<(String, Document)>, SearchError> {
+    fn index_published_cve(
+        &self,
+        cve: &Published,
+        containers: &[Value],
+        _id: &str,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
         log::debug!("Indexing published CVE document");
         let mut documents: Vec<(String, Document)> = Vec::new();
         let mut document = doc!();
 
         document.add_bool(self.fields.published, true);
+        document.add_text(self.fields.state, CveState::Published.as_str());
         self.index_common(&mut document, &cve.metadata.common, &cve.containers.cna.common);
 
         if let Some(title) = &cve.containers.cna.title {
@@ -117,6 +307,36 @@ impl Index {
             document.add_text(self.fields.description, &desc.value);
         }
 
+        // ADP containers add to the CNA container, like the CISA ADP adding CWEs, scores and CPEs
+
+        // names are stored in the order of the ids, as there is one name for each id
+        let mut cwes: Vec<Cwe> = vec![];
+        for cwe in containers.iter().flat_map(Cwe::from_container) {
+            if !cwes.iter().any(|existing| existing.id == cwe.id) {
+                cwes.push(cwe);
+            }
+        }
+        for cwe in cwes {
+            document.add_text(self.fields.cwe, &cwe.id);
+            document.add_text(self.fields.cwe_name, cwe.name.unwrap_or_default());
+        }
+
+        for reference in unique(containers.iter().flat_map(container_references)) {
+            document.add_text(self.fields.reference, reference);
+        }
+        let purls = containers.iter().flat_map(container_purls);
+        for purl in unique(purls.map(|purl| trustification_purl::normalize(&purl).into_owned())) {
+            document.add_text(self.fields.purl, purl);
+        }
+        for affected in containers
+            .iter()
+            .flat_map(|container| container["affected"].as_array().into_iter().flatten())
+        {
+            for expression in CpeMatch::from_affected(affected) {
+                self.add_cpe_match(&mut document, &expression);
+            }
+        }
+
         fn parse_score(score: &Value, version: &str) -> Option<Base> {
             let score = score["vectorString"].as_str()?;
 
@@ -129,30 +349,154 @@ impl Index {
             }
         }
 
-        for metric in &cve.containers.cna.metrics {
-            let score = metric
-                .cvss_v3_1
-                .as_ref()
-                .and_then(|score| parse_score(score, "3.1"))
-                .or_else(|| metric.cvss_v3_0.as_ref().and_then(|score| parse_score(score, "3.0")));
+        fn parse_score_v4(metric: &Value) -> Option<Cvss4> {
+            match Cvss4::from_cve_metric(metric)? {
+                Ok(score) => Some(score),
+                Err(err) => {
+                    log::warn!(
+                        "Failed to parse CVSS 4.0 ({}): {err}",
+                        metric["cvssV4_0"]["vectorString"]
+                    );
+                    None
+                }
+            }
+        }
+
+        let mut severities = Vec::new();
+        let mut severities_v4 = Vec::new();
+        // the scores of the CNA take precedence, the ones of ADP containers only score CVEs the CNA didn't score
+        let metrics = containers
+            .iter()
+            .filter_map(|container| container["metrics"].as_array())
+            .find(|metrics| !metrics.is_empty())
+            .cloned()
+            .unwrap_or_default();
+        for metric in &metrics {
+            let score = parse_score(&metric["cvssV3_1"], "3.1").or_else(|| parse_score(&metric["cvssV3_0"], "3.0"));
 
             if let Some(score) = score {
                 document.add_f64(self.fields.cvss3x_score, score.score().value());
-                document.add_text(self.fields.severity, score.severity().to_string());
+                severities.push(score.severity());
+            }
+
+            // metrics are looked up by their JSON name, as CVSS 4.0 is newer than the CVE record model
+            if let Some(score) = parse_score_v4(metric) {
+                document.add_f64(self.fields.cvss4_score, score.score());
+                severities_v4.push(score.severity());
             }
         }
 
+        // the severity is the one of the CVSS 4.0 scores, if there are any
+        if !severities_v4.is_empty() {
+            severities = severities_v4;
+        }
+        for severity in severities {
+            document.add_text(self.fields.severity, severity.to_string());
+        }
+
         log::debug!("Indexed {:?}", document);
         documents.push((_id.to_string(), document));
         Ok(documents)
     }
 
+    fn index_osv(&self, osv: &Osv, id: &str) -> Result<Vec<(String, Document)>, SearchError> {
+        log::debug!("Indexing OSV document");
+        let mut document = doc!();
+
+        // the id must match the storage key, which is uppercase like all CVE ids
+        document.add_text(self.fields.id, id);
+        document.add_date(
+            self.fields.indexed_timestamp,
+            DateTime::from_utc(OffsetDateTime::now_utc()),
+        );
+
+        document.add_bool(self.fields.published, osv.withdrawn.is_none());
+        let state = match osv.withdrawn {
+            None => CveState::Published,
+            Some(_) => CveState::Rejected,
+        };
+        document.add_text(self.fields.state, state.as_str());
+        Self::add_date(&mut document, self.fields.date_published, osv.published);
+        if let Some(published) = osv.published {
+            document.add_text(self.fields.year, published.year().to_string());
+        }
+        Self::add_date(&mut document, self.fields.date_updated, Some(osv.modified));
+        Self::add_date(&mut document, self.fields.date_rejected, osv.withdrawn);
+
+        if let Some(summary) = &osv.summary {
+            document.add_text(self.fields.title, summary);
+        }
+        if let Some(details) = &osv.details {
+            document.add_text(self.fields.description, details);
+        }
+
+        for alias in &osv.aliases {
+            document.add_text(self.fields.alias, alias.to_uppercase());
+        }
+        for reference in &osv.references {
+            document.add_text(self.fields.reference, &reference.url);
+        }
+
+        let severities = osv
+            .severity
+            .iter()
+            .chain(osv.affected.iter().flat_map(|affected| &affected.severity));
+        let mut severity = None;
+        let mut severity_v4 = None;
+        for score in severities {
+            match score.r#type.as_str() {
+                "CVSS_V3" => match Base::from_str(&score.score) {
+                    Ok(score) => {
+                        document.add_f64(self.fields.cvss3x_score, score.score().value());
+                        severity = severity.max(Some(score.severity()));
+                    }
+                    Err(err) => log::warn!("Failed to parse CVSS 3.x ({}): {err}", score.score),
+                },
+                "CVSS_V4" => match Cvss4::from_str(&score.score) {
+                    Ok(score) => {
+                        document.add_f64(self.fields.cvss4_score, score.score());
+                        severity_v4 = severity_v4.max(Some(score.severity()));
+                    }
+                    Err(err) => log::warn!("Failed to parse CVSS 4.0 ({}): {err}", score.score),
+                },
+                _ => {}
+            }
+        }
+        if let Some(severity) = severity_v4.or(severity) {
+            document.add_text(self.fields.severity, severity.to_string());
+        }
+
+        for affected in &osv.affected {
+            if let Some(package) = &affected.package {
+                Self::add_package(&mut document, &self.fields, package);
+            }
+            if let Some(description) = affected.describe() {
+                document.add_text(self.fields.affected, description);
+            }
+        }
+
+        log::debug!("Indexed {:?}", document);
+        Ok(vec![(id.to_string(), document)])
+    }
+
+    fn add_package(document: &mut Document, fields: &Fields, package: &osv::Package) {
+        document.add_text(fields.ecosystem, package.ecosystem_name());
+        if package.ecosystem_name() != package.ecosystem {
+            document.add_text(fields.ecosystem, &package.ecosystem);
+        }
+        document.add_text(fields.package, &package.name);
+        if let Some(purl) = &package.purl {
+            document.add_text(fields.purl, trustification_purl::normalize(purl));
+        }
+    }
+
     fn index_rejected_cve(&self, cve: &Rejected, _id: &str) -> Result<Vec<(String, Document)>, SearchError> {
         log::debug!("Indexing rejected CVE document");
         let mut documents: Vec<(String, Document)> = Vec::new();
         let mut document = doc!();
 
         document.add_bool(self.fields.published, false);
+        document.add_text(self.fields.state, CveState::Rejected.as_str());
         self.index_common(&mut document, &cve.metadata.common, &cve.containers.cna.common);
 
         Self::add_timestamp(&mut document, self.fields.date_rejected, cve.metadata.date_rejected);
@@ -166,23 +510,82 @@ impl Index {
         Ok(documents)
     }
 
-    fn resource2query(&self, resource: &Cves) -> Box<dyn Query> {
+    fn resource2query(&self, resource: &Cves, context: &QueryContext) -> Box<dyn Query> {
         match resource {
-            Cves::Id(value) => create_string_query_case(self.fields.id, value, Case::Uppercase),
+            Cves::Id(value) => boost_query(
+                "id",
+                1.0,
+                create_string_query_case(self.fields.id, value, Case::Uppercase, context),
+                context,
+            ),
 
-            // TODO: consider boosting the title
-            Cves::Title(value) => create_text_query(self.fields.title, value),
-            Cves::Description(value) => create_text_query(self.fields.description, value),
+            Cves::Title(value) => boost_query(
+                "title",
+                1.0,
+                self.tokenizers.query(self.fields.title, value, context),
+                context,
+            ),
+            Cves::Description(value) => boost_query(
+                "description",
+                1.0,
+                self.tokenizers.query(self.fields.description, value, context),
+                context,
+            ),
+            Cves::Alias(value) => boost_query(
+                "alias",
+                1.0,
+                create_string_query_case(self.fields.alias, value, Case::Uppercase, context),
+                context,
+            ),
 
-            Cves::Score(value) => create_float_query(&self.schema, [self.fields.cvss3x_score], value),
+            Cves::Ecosystem(value) => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.ecosystem, value),
+                Default::default(),
+            )),
+            Cves::Package(value) => self.tokenizers.query(self.fields.package, value, context),
+            Cves::Purl(value) => create_purl_query(self.fields.purl, value, context),
+            Cves::Reference(value) => create_string_query(self.fields.reference, value, context),
+
+            Cves::Score(value) => {
+                create_float_query(&self.schema, [self.fields.cvss4_score, self.fields.cvss3x_score], value)
+            }
+            Cves::Cvss4(value) => create_float_query(&self.schema, [self.fields.cvss4_score], value),
+            Cves::Cwe(value) => Box::new(TermQuery::new(
+                Term::from_field_text(
+                    self.fields.cwe,
+                    &cwe::normalize(value).unwrap_or_else(|| value.to_string()),
+                ),
+                Default::default(),
+            )),
+            // versions can't be matched by terms, the expressions of the candidates need to be evaluated
+            Cves::Cpe(value) => match Cpe::parse(value) {
+                Ok(cpe) => self.create_cpe_query(&cpe),
+                Err(_) => Box::new(EmptyQuery),
+            },
+            Cves::Epss(value) => create_float_query(&self.schema, [self.fields.epss], value),
+            Cves::EpssPercentile(value) => create_float_query(&self.schema, [self.fields.epss_percentile], value),
 
             Cves::DateReserved(value) => create_date_query(&self.schema, self.fields.date_reserved, value),
             Cves::DatePublished(value) => create_date_query(&self.schema, self.fields.date_published, value),
             Cves::DateUpdated(value) => create_date_query(&self.schema, self.fields.date_updated, value),
             Cves::DateRejected(value) => create_date_query(&self.schema, self.fields.date_rejected, value),
+            Cves::Indexed(value) => create_date_query(&self.schema, self.fields.indexed_timestamp, value),
 
+            Cves::State(value) => Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.state, &value.to_uppercase()),
+                Default::default(),
+            )),
             Cves::Published => create_boolean_query(Occur::Should, Term::from_field_bool(self.fields.published, true)),
             Cves::Rejected => create_boolean_query(Occur::Should, Term::from_field_bool(self.fields.published, false)),
+            Cves::KnownExploited => {
+                create_boolean_query(Occur::Should, Term::from_field_bool(self.fields.known_exploited, true))
+            }
+            Cves::KevAdded(value) => create_date_query(&self.schema, self.fields.kev_date_added, value),
+            Cves::ExploitAvailable => create_boolean_query(
+                Occur::Should,
+                Term::from_field_bool(self.fields.exploit_available, true),
+            ),
+            Cves::KevDue(value) => create_date_query(&self.schema, self.fields.kev_due_date, value),
 
             Cves::Severity(value) => Box::new(TermQuery::new(
                 Term::from_field_text(self.fields.severity, value),
@@ -207,6 +610,46 @@ impl Index {
         }
     }
 
+    /// Create a query for the candidates of a CPE, which are the vulnerabilities with expressions for its product, or
+    /// for any product, vendor or part.
+    fn create_cpe_query(&self, cpe: &Cpe) -> Box<dyn Query> {
+        if let Some(pattern) = cpe.product_key_pattern() {
+            return match RegexQuery::from_pattern(&pattern, self.fields.cpe) {
+                Ok(query) => Box::new(query),
+                Err(err) => {
+                    log::debug!("Invalid pattern '{pattern}' of CPE {cpe}: {err}");
+                    Box::new(EmptyQuery)
+                }
+            };
+        }
+        let queries = cpe.product_keys().into_iter().map(|key| {
+            let query: Box<dyn Query> = Box::new(TermQuery::new(
+                Term::from_field_text(self.fields.cpe, &key),
+                Default::default(),
+            ));
+            (Occur::Should, query)
+        });
+        Box::new(BooleanQuery::new(queries.collect()))
+    }
+
+    /// Add a CPE match expression, indexing the product it refers to, and storing the expression for its evaluation.
+    fn add_cpe_match(&self, document: &mut Document, expression: &CpeMatch) {
+        let Ok(criteria) = Cpe::parse(&expression.criteria) else {
+            log::debug!("Skipping invalid CPE match expression: {}", expression.criteria);
+            return;
+        };
+        if let Ok(value) = serde_json::to_string(expression) {
+            document.add_text(self.fields.cpe, criteria.product_key());
+            document.add_text(self.fields.cpe_match, value);
+        }
+    }
+
+    fn add_date(document: &mut Document, field: Field, date: Option<OffsetDateTime>) {
+        if let Some(date) = date {
+            document.add_date(field, DateTime::from_utc(date));
+        }
+    }
+
     fn add_timestamp(document: &mut Document, field: Field, timestamp: impl Into<Option<Timestamp>>) {
         if let Some(timestamp) = timestamp.into() {
             // by definition, timestamps without timezone are considered UTC
@@ -218,7 +661,7 @@ impl Index {
 impl trustification_index::Index for Index {
     type MatchedDocument = SearchHit<SearchDocument>;
 
-    fn prepare_query(&self, q: &str) -> Result<SearchQuery, SearchError> {
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, SearchError> {
         let mut query = Cves::parse(q).map_err(|err| SearchError::QueryParser(err.to_string()))?;
         query.term = query.term.compact();
 
@@ -226,15 +669,45 @@ impl trustification_index::Index for Index {
 
         let sort_by = query.sorting.first().map(|f| match f.qualifier {
             CvesSortable::Score => sort_by(f.direction, self.fields.cvss3x_score),
+            CvesSortable::Cvss4 => sort_by(f.direction, self.fields.cvss4_score),
             CvesSortable::DatePublished => sort_by(f.direction, self.fields.date_published),
             CvesSortable::DateUpdated => sort_by(f.direction, self.fields.date_updated),
             CvesSortable::DateRejected => sort_by(f.direction, self.fields.date_rejected),
+            CvesSortable::Indexed => sort_by(f.direction, self.fields.indexed_timestamp),
+            CvesSortable::Epss => sort_by(f.direction, self.fields.epss),
+            CvesSortable::EpssPercentile => sort_by(f.direction, self.fields.epss_percentile),
+            CvesSortable::KevAdded => sort_by(f.direction, self.fields.kev_date_added),
+            CvesSortable::KevDue => sort_by(f.direction, self.fields.kev_due_date),
         });
 
+        let mentions_state = Cell::new(false);
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            term2query(&query.term, &|resource| {
+                if matches!(resource, Cves::State(_) | Cves::Published | Cves::Rejected) {
+                    mentions_state.set(true);
+                }
+                self.resource2query(resource, context)
+            })
+        };
+
+        // rejected and reserved records are only matched on demand, by querying for the state
+        let query = match mentions_state.get() {
+            true => query,
+            false => {
+                let excluded = |state: CveState| -> Box<dyn Query> {
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(self.fields.state, state.as_str()),
+                        Default::default(),
+                    ))
+                };
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, query),
+                    (Occur::MustNot, excluded(CveState::Rejected)),
+                    (Occur::MustNot, excluded(CveState::Reserved)),
+                ]))
+            }
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -247,8 +720,10 @@ impl trustification_index::Index for Index {
         query: &dyn Query,
         offset: usize,
         limit: usize,
+        _context: &QueryContext,
     ) -> Result<(Vec<(f32, DocAddress)>, usize), SearchError> {
         let severity_field = self.schema.get_field_name(self.fields.cvss3x_score).to_string();
+        let severity_v4_field = self.schema.get_field_name(self.fields.cvss4_score).to_string();
         let date_field = self.schema.get_field_name(self.fields.date_updated).to_string();
         let now = tantivy::DateTime::from_utc(OffsetDateTime::now_utc());
         Ok(searcher.search(
@@ -258,14 +733,21 @@ impl trustification_index::Index for Index {
                     .and_offset(offset)
                     .tweak_score(move |segment_reader: &SegmentReader| {
                         let severity_reader = segment_reader.fast_fields().f64(&severity_field);
+                        let severity_v4_reader = segment_reader.fast_fields().f64(&severity_v4_field);
 
                         let date_reader = segment_reader.fast_fields().date(&date_field);
 
                         move |doc: DocId, original_score: Score| {
                             let severity_reader = severity_reader.clone();
+                            let severity_v4_reader = severity_v4_reader.clone();
                             let date_reader = date_reader.clone();
                             let mut tweaked = original_score;
-                            if let Ok(Some(score)) = severity_reader.map(|s| s.first(doc)) {
+                            // prefer the CVSS 4.0 score
+                            let score = match severity_v4_reader.map(|s| s.first(doc)) {
+                                Ok(Some(score)) => Ok(Some(score)),
+                                _ => severity_reader.map(|s| s.first(doc)),
+                            };
+                            if let Ok(Some(score)) = score {
                                 log::trace!("CVSS score impact {} -> {}", tweaked, (score as f32) * tweaked);
                                 tweaked *= score as f32;
 
@@ -296,6 +778,73 @@ impl trustification_index::Index for Index {
         )?)
     }
 
+    fn facet_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "cwe" => Some(self.fields.cwe),
+            "severity" => Some(self.fields.severity),
+            "year" => Some(self.fields.year),
+            _ => None,
+        }
+    }
+
+    fn histogram_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "indexed" => Some(self.fields.indexed_timestamp),
+            "published" => Some(self.fields.date_published),
+            "dateReserved" => Some(self.fields.date_reserved),
+            "datePublished" => Some(self.fields.date_published),
+            "dateUpdated" => Some(self.fields.date_updated),
+            "dateRejected" => Some(self.fields.date_rejected),
+            "kevAdded" => Some(self.fields.kev_date_added),
+            "kevDue" => Some(self.fields.kev_due_date),
+            _ => None,
+        }
+    }
+
+    fn stats_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "score" => Some(self.fields.cvss3x_score),
+            "cvss4" => Some(self.fields.cvss4_score),
+            "epss" => Some(self.fields.epss),
+            "epssPercentile" => Some(self.fields.epss_percentile),
+            _ => None,
+        }
+    }
+
+    fn qualifiers(&self) -> &[&'static str] {
+        Cves::VARIANTS
+    }
+
+    fn qualifier_field(&self, name: &str) -> Option<SearchField> {
+        let field = |f, example| search_field(&self.schema, name, f, example);
+        Some(match name {
+            "id" => field(self.fields.id, "id:CVE-2023-44487").with_default(),
+            "title" => field(self.fields.title, "title:http").with_default(),
+            "description" => field(self.fields.description, "description:\"rapid reset\"").with_default(),
+            "alias" => field(self.fields.alias, "alias:GHSA-qppj-fm5r-hxr3").with_default(),
+            "ecosystem" => field(self.fields.ecosystem, "ecosystem:npm"),
+            "package" => field(self.fields.package, "package:nghttp2"),
+            "purl" => field(self.fields.purl, "purl:\"pkg:npm/lodash@4.17.21\""),
+            "reference" => field(self.fields.reference, "reference:\"https://www.cve.org\""),
+            "cpe" => field(self.fields.cpe, "cpe:\"cpe:/a:redhat:openshift\""),
+            "score" => field(self.fields.cvss3x_score, "score:>=7").with_sortable(),
+            "cvss4" => field(self.fields.cvss4_score, "cvss4:>=7").with_sortable(),
+            "cwe" => field(self.fields.cwe, "cwe:CWE-400"),
+            "dateReserved" => field(self.fields.date_reserved, "dateReserved:>2023-01-01"),
+            "datePublished" => field(self.fields.date_published, "datePublished:>2023-01-01").with_sortable(),
+            "dateUpdated" => field(self.fields.date_updated, "dateUpdated:>2023-01-01").with_sortable(),
+            "dateRejected" => field(self.fields.date_rejected, "dateRejected:>2023-01-01").with_sortable(),
+            "indexed" => field(self.fields.indexed_timestamp, "indexed:>2023-01-01").with_sortable(),
+            "epss" => field(self.fields.epss, "epss:>0.5").with_sortable(),
+            "epssPercentile" => field(self.fields.epss_percentile, "epssPercentile:>0.9").with_sortable(),
+            "kevAdded" => field(self.fields.kev_date_added, "kevAdded:>2023-01-01").with_sortable(),
+            "kevDue" => field(self.fields.kev_due_date, "kevDue:<2024-01-01").with_sortable(),
+            "severity" => field(self.fields.severity, "severity:high"),
+            "state" => field(self.fields.state, "state:published"),
+            _ => return None,
+        })
+    }
+
     fn process_hit(
         &self,
         doc_address: DocAddress,
@@ -309,25 +858,104 @@ impl trustification_index::Index for Index {
         let id = field2str(&self.schema, &doc, self.fields.id)?;
         let title = doc.get_first(self.fields.title).and_then(|s| s.as_text());
         let published = field2bool(&self.schema, &doc, self.fields.published)?;
+        let state = doc
+            .get_first(self.fields.state)
+            .and_then(|s| s.as_text())
+            .and_then(CveState::parse);
         let descriptions = field2strvec(&doc, self.fields.description)?
             .iter()
             .map(|s| s.to_string())
             .collect();
 
+        let aliases = field2strvec(&doc, self.fields.alias)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let affected = field2strvec(&doc, self.fields.affected)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let purls = field2strvec(&doc, self.fields.purl)?
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
         let cvss3x_score = doc.get_first(self.fields.cvss3x_score).and_then(|s| s.as_f64());
+        let cvss4_score = doc.get_first(self.fields.cvss4_score).and_then(|s| s.as_f64());
+
+        let cwe_names = field2strvec(&doc, self.fields.cwe_name)?;
+        let cwes = field2strvec(&doc, self.fields.cwe)?
+            .iter()
+            .enumerate()
+            .map(|(n, id)| Cwe {
+                id: id.to_string(),
+                name: cwe_names
+                    .get(n)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| name.to_string()),
+            })
+            .collect();
 
         let date_published = field2date_opt(&doc, self.fields.date_published);
         let date_updated = field2date_opt(&doc, self.fields.date_updated);
+        let indexed_timestamp = field2date_opt(&doc, self.fields.indexed_timestamp);
+
+        let epss = doc
+            .get_first(self.fields.epss)
+            .and_then(|s| s.as_f64())
+            .map(|score| Epss {
+                score,
+                percentile: doc
+                    .get_first(self.fields.epss_percentile)
+                    .and_then(|s| s.as_f64())
+                    .unwrap_or_default(),
+                date: doc
+                    .get_first(self.fields.epss_date)
+                    .and_then(|s| s.as_text())
+                    .map(ToString::to_string),
+            });
+
+        let known_exploited = field2date_opt(&doc, self.fields.kev_date_added).map(|added| KnownExploited {
+            date_added: added.date().to_string(),
+            due_date: field2date_opt(&doc, self.fields.kev_due_date).map(|due| due.date().to_string()),
+            required_action: doc
+                .get_first(self.fields.kev_required_action)
+                .and_then(|s| s.as_text())
+                .map(ToString::to_string),
+            ransomware: doc
+                .get_first(self.fields.kev_ransomware)
+                .and_then(|s| s.as_bool())
+                .unwrap_or_default(),
+        });
+
+        let exploits = Exploit::from_references(field2strvec(&doc, self.fields.exploit)?);
+        let cpe_matches = field2strvec(&doc, self.fields.cpe_match)?
+            .into_iter()
+            .filter_map(|value| serde_json::from_str(value).ok())
+            .collect();
 
         let document = SearchDocument {
             id: id.to_string(),
             title: title.map(ToString::to_string),
             descriptions,
+            aliases,
+            affected,
+            purls,
             published,
+            state,
             cvss3x_score,
+            cvss4_score,
+            cwes,
 
             date_published,
             date_updated,
+            indexed_timestamp,
+
+            epss,
+            known_exploited,
+            exploit_available: !exploits.is_empty(),
+            exploits,
+            cpe_matches,
         };
 
         let explanation: Option<Value> = if options.explain {
@@ -354,21 +982,101 @@ impl trustification_index::Index for Index {
 }
 
 impl trustification_index::WriteIndex for Index {
-    type Document = Cve;
+    type Document = Record;
 
     fn name(&self) -> &str {
         "cve"
     }
 
-    fn index_doc(&self, id: &str, doc: &Cve) -> Result<Vec<(String, Document)>, SearchError> {
-        match doc {
-            Cve::Published(cve) => self.index_published_cve(cve, id),
-            Cve::Rejected(cve) => self.index_rejected_cve(cve, id),
+    fn tokenizers(&self) -> Result<TokenizerManager, SearchError> {
+        Ok(self.tokenizers.manager())
+    }
+
+    fn index_doc(&self, id: &str, doc: &Record) -> Result<Vec<(String, Document)>, SearchError> {
+        let mut documents = match doc {
+            Record::Cve(Cve::Published(cve), containers) => self.index_published_cve(cve, containers, id),
+            Record::Cve(Cve::Rejected(cve), _) => self.index_rejected_cve(cve, id),
+            Record::Osv(osv) => self.index_osv(osv, id),
+        }?;
+        // exploits are referenced by the records, like entries of the Exploit Database or Metasploit modules
+        let exploits = Exploit::from_references(doc.references().iter().map(String::as_str));
+        if !exploits.is_empty() {
+            for (_, document) in &mut documents {
+                document.add_bool(self.fields.exploit_available, true);
+                for exploit in &exploits {
+                    document.add_text(self.fields.exploit, &exploit.url);
+                }
+            }
         }
+        Ok(documents)
     }
 
-    fn parse_doc(&self, data: &[u8]) -> Result<Cve, SearchError> {
-        serde_json::from_slice(data).map_err(|err| SearchError::DocParser(err.to_string()))
+    fn index_labeled_doc(
+        &self,
+        id: &str,
+        doc: &Record,
+        labels: &Labels,
+    ) -> Result<Vec<(String, Document)>, SearchError> {
+        let mut documents = self.index_doc(id, doc)?;
+        // scores are assigned by the EPSS collector, as labels of the CVE
+        if let Some(epss) = Epss::from_labels(labels) {
+            for (_, document) in &mut documents {
+                document.add_f64(self.fields.epss, epss.score);
+                document.add_f64(self.fields.epss_percentile, epss.percentile);
+                if let Some(date) = &epss.date {
+                    document.add_text(self.fields.epss_date, date);
+                }
+            }
+        }
+        // aliasing records are assigned by the GHSA collector, as labels of the CVE
+        for alias in osv::aliases_from_labels(labels) {
+            for (_, document) in &mut documents {
+                document.add_text(self.fields.alias, alias.to_uppercase());
+            }
+        }
+        // catalog entries are assigned by the KEV collector, as labels of the CVE
+        if let Some(kev) = KnownExploited::from_labels(labels) {
+            for (_, document) in &mut documents {
+                document.add_bool(self.fields.known_exploited, true);
+                Self::add_date(document, self.fields.kev_date_added, kev.added());
+                Self::add_date(document, self.fields.kev_due_date, kev.due());
+                if let Some(required_action) = &kev.required_action {
+                    document.add_text(self.fields.kev_required_action, required_action);
+                }
+                document.add_bool(self.fields.kev_ransomware, kev.ransomware);
+            }
+        }
+        // the analysis is assigned by the NVD collector, its score is used if the CNA didn't score the CVE
+        if let Some(cvss3) = NvdAnalysis::from_labels(labels).and_then(|nvd| nvd.cvss3) {
+            match Base::from_str(&cvss3) {
+                Ok(score) => {
+                    for (_, document) in &mut documents {
+                        if document.get_first(self.fields.cvss3x_score).is_some() {
+                            continue;
+                        }
+                        document.add_f64(self.fields.cvss3x_score, score.score().value());
+                        if document.get_first(self.fields.severity).is_none() {
+                            document.add_text(self.fields.severity, score.severity().to_string());
+                        }
+                    }
+                }
+                Err(err) => log::warn!("Failed to parse CVSS 3.x of the NVD ({cvss3}): {err}"),
+            }
+        }
+        // so are the expressions matching the vulnerable products
+        for expression in NvdAnalysis::from_labels(labels)
+            .map(|nvd| nvd.cpe_matches)
+            .unwrap_or_default()
+        {
+            for (_, document) in &mut documents {
+                self.add_cpe_match(document, &expression);
+            }
+        }
+        Ok(documents)
+    }
+
+    fn parse_doc(&self, data: &[u8]) -> Result<Record, SearchError> {
+        Record::parse(data).map_err(|err| SearchError::DocParser(err.to_string()))
     }
 
     fn schema(&self) -> Schema {
@@ -393,8 +1101,11 @@ impl trustification_index::WriteIndex for Index {
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_json::json;
     use std::path::Path;
+    use trustification_api::search::SearchFieldType;
     use trustification_index::{IndexStore, IndexWriter};
+    use v11y_model::exploit::ExploitSource;
 
     const TESTDATA: &[&str] = &["../testdata/CVE-2023-44487.json"];
 
@@ -438,11 +1149,613 @@ mod test {
                     metadata: false,
                     explain: false,
                     summaries: true,
+                    ..Default::default()
                 },
             )
             .unwrap()
     }
 
+    #[tokio::test]
+    async fn test_epss() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read(TESTDATA[0]).unwrap();
+        let labels = Labels::from([
+            ("epss".to_string(), "0.61027".to_string()),
+            ("epss_percentile".to_string(), "0.97652".to_string()),
+            ("epss_date".to_string(), "2023-10-14".to_string()),
+        ]);
+        writer
+            .add_labeled_document(store.index_as_mut(), "CVE-2023-44487", &data, &labels)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "epss:>0.5");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(
+            result.0[0].document.epss,
+            Some(Epss {
+                score: 0.61027,
+                percentile: 0.97652,
+                date: Some("2023-10-14".to_string()),
+            })
+        );
+        assert_eq!(search(&store, "epss:<0.5").0.len(), 0);
+        assert_eq!(search(&store, "epssPercentile:>0.9").0.len(), 1);
+
+        // documents without labels don't have a score
+        assert_search(|index| {
+            let result = search(&index, r#"id:"CVE-2023-44487""#);
+            assert_eq!(result.0[0].document.epss, None);
+            assert_eq!(search(&index, "epss:>0").0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_exploit_available() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let mut cve: Value = serde_json::from_slice(&std::fs::read(TESTDATA[0]).unwrap()).unwrap();
+        cve["containers"]["cna"]["references"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "url": "https://www.exploit-db.com/exploits/51107" }));
+        writer
+            .add_document(
+                store.index_as_mut(),
+                "CVE-2023-44487",
+                &serde_json::to_vec(&cve).unwrap(),
+            )
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "is:exploitAvailable");
+        assert_eq!(result.0.len(), 1);
+        assert!(result.0[0].document.exploit_available);
+        assert_eq!(
+            result.0[0].document.exploits,
+            vec![Exploit {
+                source: ExploitSource::ExploitDb,
+                url: "https://www.exploit-db.com/exploits/51107".to_string(),
+            }]
+        );
+
+        assert_search(|index| {
+            assert_eq!(search(&index, "is:exploitAvailable").0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_known_exploited() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read(TESTDATA[0]).unwrap();
+        let kev = KnownExploited {
+            date_added: "2023-10-10".to_string(),
+            due_date: Some("2023-10-31".to_string()),
+            required_action: Some("Apply mitigations per vendor instructions.".to_string()),
+            ransomware: false,
+        };
+        let mut labels = Labels::new();
+        kev.apply(&mut labels);
+        writer
+            .add_labeled_document(store.index_as_mut(), "CVE-2023-44487", &data, &labels)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "is:knownExploited");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.known_exploited, Some(kev));
+        assert_eq!(search(&store, "kevDue:<2023-11-01").0.len(), 1);
+        assert_eq!(search(&store, "kevAdded:>2023-10-11").0.len(), 0);
+
+        assert_search(|index| {
+            assert_eq!(search(&index, "is:knownExploited").0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cpe() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let mut cve: Value = serde_json::from_slice(&std::fs::read(TESTDATA[0]).unwrap()).unwrap();
+        cve["containers"]["cna"]["affected"] = json!([{
+            "vendor": "Red Hat",
+            "product": "Quarkus",
+            "cpes": ["cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*"],
+            "versions": [{ "version": "2.0.0", "lessThan": "2.13.9", "status": "affected" }],
+        }]);
+        let mut labels = Labels::new();
+        NvdAnalysis {
+            status: "Analyzed".to_string(),
+            cvss3: None,
+            cpe_matches: vec![
+                CpeMatch {
+                    criteria: "cpe:2.3:a:golang:http2:*:*:*:*:*:go:*:*".to_string(),
+                    version_end_excluding: Some("0.17.0".to_string()),
+                    ..Default::default()
+                },
+                CpeMatch {
+                    criteria: "cpe:2.3:a:*:nghttp2:*:*:*:*:*:*:*:*".to_string(),
+                    version_end_excluding: Some("1.57.0".to_string()),
+                    platforms: vec![vec![CpeMatch {
+                        criteria: "cpe:2.3:o:redhat:enterprise_linux:9.0:*:*:*:*:*:*:*".to_string(),
+                        ..Default::default()
+                    }]],
+                    ..Default::default()
+                },
+            ],
+        }
+        .apply(&mut labels);
+        writer
+            .add_labeled_document(
+                store.index_as_mut(),
+                "CVE-2023-44487",
+                &serde_json::to_vec(&cve).unwrap(),
+                &labels,
+            )
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, r#"cpe:"cpe:2.3:a:redhat:quarkus:2.13""#);
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.cpe_matches.len(), 3);
+        let quarkus = Cpe::parse("cpe:2.3:a:redhat:quarkus:2.13").unwrap();
+        assert!(result.0[0].document.affects(&quarkus));
+        let quarkus = Cpe::parse("cpe:2.3:a:redhat:quarkus:3.2").unwrap();
+        assert!(!result.0[0].document.affects(&quarkus));
+
+        assert_eq!(search(&store, r#"cpe:"cpe:/a:golang:http2""#).0.len(), 1);
+        assert_eq!(search(&store, r#"cpe:"cpe:2.3:a:redhat:quark*""#).0.len(), 1);
+        assert_eq!(search(&store, r#"cpe:"cpe:2.3:a:*:quarkus""#).0.len(), 1);
+        assert_eq!(search(&store, r#"cpe:"cpe:2.3:a:go?ang:*""#).0.len(), 1);
+        assert_eq!(search(&store, r#"cpe:"cpe:2.3:a:redhat:quarkus_*""#).0.len(), 0);
+
+        // expressions for any vendor are candidates, but only match on the platforms of their configuration
+        let result = search(&store, r#"cpe:"cpe:2.3:a:nghttp2:nghttp2:1.56.0""#);
+        assert_eq!(result.0.len(), 1);
+        let nghttp2 = Cpe::parse("cpe:2.3:a:nghttp2:nghttp2:1.56.0").unwrap();
+        assert!(!result.0[0].document.affects(&nghttp2));
+        let rhel = Cpe::parse("cpe:2.3:o:redhat:enterprise_linux:9").unwrap();
+        assert_eq!(result.0[0].document.matching_on(&nghttp2, &[rhel]).len(), 1);
+        assert_eq!(search(&store, r#"cpe:"cpe:2.3:a:redhat:quarkus_core""#).0.len(), 0);
+        assert_eq!(search(&store, r#"cpe:"pkg:maven/io.quarkus/quarkus-core""#).0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_nvd_score() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read(TESTDATA[0]).unwrap();
+        let mut labels = Labels::new();
+        NvdAnalysis {
+            status: "Modified".to_string(),
+            cvss3: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H".to_string()),
+            cpe_matches: vec![],
+        }
+        .apply(&mut labels);
+        writer
+            .add_labeled_document(store.index_as_mut(), "CVE-2023-44487", &data, &labels)
+            .unwrap();
+        writer.commit().unwrap();
+
+        // the CNA didn't score the CVE
+        let result = search(&store, "is:high");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.cvss3x_score, Some(7.5));
+
+        assert_search(|index| {
+            assert_eq!(search(&index, "is:high").0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cwe() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let mut data: Value = serde_json::from_slice(&std::fs::read(TESTDATA[0]).unwrap()).unwrap();
+        data["containers"]["cna"]["problemTypes"] = serde_json::json!([{
+            "descriptions": [{
+                "lang": "en",
+                "type": "CWE",
+                "cweId": "CWE-400",
+                "description": "CWE-400 Uncontrolled Resource Consumption"
+            }]
+        }]);
+        writer
+            .add_document(
+                store.index_as_mut(),
+                "CVE-2023-44487",
+                &serde_json::to_vec(&data).unwrap(),
+            )
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "cwe:CWE-400");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(
+            result.0[0].document.cwes,
+            vec![Cwe {
+                id: "CWE-400".to_string(),
+                name: Some("Uncontrolled Resource Consumption".to_string()),
+            }]
+        );
+        assert_eq!(search(&store, "cwe:400").0.len(), 1);
+        assert_eq!(search(&store, "cwe:502").0.len(), 0);
+
+        let facets = store.facets("", &["cwe".to_string()]).unwrap();
+        assert_eq!(facets["cwe"][0].value, "CWE-400");
+        assert_eq!(facets["cwe"][0].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cve_5_1() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let mut data: Value = serde_json::from_slice(&std::fs::read(TESTDATA[0]).unwrap()).unwrap();
+        data["dataVersion"] = json!("5.1");
+        data["containers"]["adp"] = json!([{
+            "providerMetadata": {
+                "orgId": "134c704f-9b21-4f2e-91b3-4a467353bcc0",
+                "shortName": "CISA-ADP"
+            },
+            "title": "CISA ADP Vulnrichment",
+            "metrics": [{
+                "cvssV3_1": {
+                    "version": "3.1",
+                    "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H",
+                    "baseScore": 7.5,
+                    "baseSeverity": "HIGH"
+                }
+            }],
+            "problemTypes": [{
+                "descriptions": [{
+                    "lang": "en",
+                    "type": "CWE",
+                    "cweId": "CWE-400",
+                    "description": "CWE-400 Uncontrolled Resource Consumption"
+                }]
+            }],
+            "references": [{"url": "https://www.cisa.gov/known-exploited-vulnerabilities-catalog"}]
+        }]);
+
+        let data = serde_json::to_vec(&data).unwrap();
+        let Ok(Record::Cve(Cve::Published(_), containers)) = Record::parse(&data) else {
+            panic!("failed to parse the CVE JSON 5.1 record");
+        };
+        // the CNA container, followed by the ADP container
+        assert_eq!(containers.len(), 2);
+
+        writer
+            .add_document(store.index_as_mut(), "CVE-2023-44487", &data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "cwe:CWE-400");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.cvss3x_score, Some(7.5));
+        assert_eq!(search(&store, "is:high").0.len(), 1);
+        assert_eq!(
+            search(
+                &store,
+                r#"reference:"https://www.cisa.gov/known-exploited-vulnerabilities-catalog""#
+            )
+            .0
+            .len(),
+            1
+        );
+    }
+
+    /// A CVE JSON 5.1 record, as stored by the walker.
+    const CVE_5_1: &str = "../testdata/CVE-2024-1597.json";
+
+    fn load_raw_record(path: &str) -> IndexStore<Index> {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read(path).unwrap();
+        let name = Path::new(path).file_stem().unwrap().to_str().unwrap();
+        writer.add_document(store.index_as_mut(), name, &data).unwrap();
+        writer.commit().unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_raw_record_purl() {
+        let data = std::fs::read(CVE_5_1).unwrap();
+        let record = Record::parse(&data).unwrap();
+        // the CNA container is kept as stored, including its package URLs
+        assert_eq!(record.purls(), vec!["pkg:maven/org.postgresql/postgresql"]);
+
+        let store = load_raw_record(CVE_5_1);
+        let (hits, _) = search(&store, r#"purl:"pkg:maven/org.postgresql/postgresql""#);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.purls, vec!["pkg:maven/org.postgresql/postgresql"]);
+        assert_eq!(
+            search(&store, r#"purl:"pkg:maven/org.postgresql/r2dbc-postgresql""#)
+                .0
+                .len(),
+            0
+        );
+        assert_eq!(
+            search(
+                &store,
+                r#"reference:"https://security.netapp.com/advisory/ntap-20240419-0008/""#
+            )
+            .0
+            .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_raw_record_cwe() {
+        let store = load_raw_record(CVE_5_1);
+
+        let result = search(&store, "cwe:CWE-89");
+        assert_eq!(result.0.len(), 1);
+        // all problem types of the CNA are kept, the one of the ADP container repeats the CNA
+        assert_eq!(
+            result.0[0].document.cwes,
+            vec![
+                Cwe {
+                    id: "CWE-89".to_string(),
+                    name: Some(
+                        "Improper Neutralization of Special Elements used in an SQL Command ('SQL Injection')"
+                            .to_string()
+                    ),
+                },
+                Cwe {
+                    id: "CWE-20".to_string(),
+                    name: Some("Improper Input Validation".to_string()),
+                },
+            ]
+        );
+        assert_eq!(search(&store, "cwe:20").0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_raw_record_cvss4() {
+        let store = load_raw_record(CVE_5_1);
+
+        let result = search(&store, "cvss4:>=9");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.cvss4_score, Some(9.3));
+        assert_eq!(result.0[0].document.cvss3x_score, Some(9.8));
+        assert_eq!(search(&store, "is:critical").0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_indexed() {
+        use time::format_description::well_known::Rfc3339;
+
+        let before = (OffsetDateTime::now_utc() - time::Duration::hours(1))
+            .format(&Rfc3339)
+            .unwrap();
+        let after = (OffsetDateTime::now_utc() + time::Duration::hours(1))
+            .format(&Rfc3339)
+            .unwrap();
+
+        assert_search(|index| {
+            let result = search(&index, &format!(r#"indexed:>"{before}" sort:indexed"#));
+            assert_eq!(result.0.len(), 1);
+            assert!(result.0[0].document.indexed_timestamp.is_some());
+            assert_eq!(search(&index, &format!(r#"indexed:>"{after}""#)).0.len(), 0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_histogram() {
+        use time::format_description::well_known::Rfc3339;
+
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        load_valid_file(&mut store, &mut writer, TESTDATA[0]);
+        let data = std::fs::read("../testdata/PYSEC-2023-228.json").unwrap();
+        writer
+            .add_document(store.index_as_mut(), "PYSEC-2023-228", &data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let week = Duration::from_secs(7 * 86_400);
+        let buckets = store
+            .histogram("", "indexed", week, Duration::from_secs(4 * 86_400), Some("severity"))
+            .unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].count, 2);
+        // only the OSV record is scored
+        assert_eq!(buckets[0].facets.len(), 1);
+        assert_eq!(buckets[0].facets[0].value, "high");
+        assert_eq!(buckets[0].facets[0].count, 1);
+
+        // weeks start on mondays
+        let start = OffsetDateTime::parse(&buckets[0].start, &Rfc3339).unwrap();
+        assert_eq!(start.weekday(), time::Weekday::Monday);
+        assert!(start <= OffsetDateTime::now_utc());
+
+        let buckets = store
+            .histogram("is:high", "indexed", week, Duration::ZERO, None)
+            .unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].count, 1);
+        assert!(buckets[0].facets.is_empty());
+
+        assert!(matches!(
+            store.histogram("", "title", week, Duration::ZERO, None),
+            Err(SearchError::NotHistogrammable(_))
+        ));
+
+        let years = store.pivot("", "year", "severity").unwrap();
+        assert_eq!(years.len(), 1);
+        assert_eq!(years[0].value, "2023");
+        assert_eq!(years[0].count, 2);
+        assert_eq!(years[0].facets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_state() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let published = std::fs::read(TESTDATA[0]).unwrap();
+        let rejected = serde_json::json!({
+            "dataType": "CVE_RECORD",
+            "dataVersion": "5.0",
+            "cveMetadata": {
+                "cveId": "CVE-2023-0001",
+                "assignerOrgId": "8254265b-2729-46b6-b9e3-3dfca2d5bfca",
+                "state": "REJECTED",
+                "assignerShortName": "mitre",
+                "dateReserved": "2023-01-01T00:00:00",
+                "dateUpdated": "2023-02-01T00:00:00",
+                "dateRejected": "2023-02-01T00:00:00"
+            },
+            "containers": {
+                "cna": {
+                    "providerMetadata": {
+                        "orgId": "8254265b-2729-46b6-b9e3-3dfca2d5bfca",
+                        "shortName": "mitre",
+                        "dateUpdated": "2023-02-01T00:00:00"
+                    },
+                    "rejectedReasons": [{
+                        "lang": "en",
+                        "value": "** REJECT ** DO NOT USE THIS CANDIDATE NUMBER."
+                    }]
+                }
+            }
+        });
+        writer
+            .add_document(store.index_as_mut(), "CVE-2023-44487", &published)
+            .unwrap();
+        writer
+            .add_document(
+                store.index_as_mut(),
+                "CVE-2023-0001",
+                &serde_json::to_vec(&rejected).unwrap(),
+            )
+            .unwrap();
+        writer.commit().unwrap();
+
+        // rejected records are filtered, unless the state is queried for
+        let result = search(&store, "");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.state, Some(CveState::Published));
+        assert_eq!(search(&store, "CVE-2023-0001").0.len(), 0);
+
+        let result = search(&store, "state:rejected");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.id, "CVE-2023-0001");
+        assert_eq!(result.0[0].document.state, Some(CveState::Rejected));
+        assert_eq!(search(&store, "CVE-2023-0001 is:rejected").0.len(), 1);
+        assert_eq!(search(&store, "state:published OR state:rejected").0.len(), 2);
+        assert_eq!(search(&store, "state:reserved").0.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_alias_labels() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        let data = std::fs::read(TESTDATA[0]).unwrap();
+        let mut labels = Labels::new();
+        osv::add_aliases(&mut labels, &["GHSA-qqpg-mvqg-649v".to_string()]);
+        writer
+            .add_labeled_document(store.index_as_mut(), "CVE-2023-44487", &data, &labels)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "GHSA-qqpg-mvqg-649v");
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].document.aliases, vec!["GHSA-QQPG-MVQG-649V"]);
+    }
+
+    #[tokio::test]
+    async fn test_osv() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let mut writer = store.writer().unwrap();
+        load_valid_file(&mut store, &mut writer, TESTDATA[0]);
+        let data = std::fs::read("../testdata/PYSEC-2023-228.json").unwrap();
+        writer
+            .add_document(store.index_as_mut(), "PYSEC-2023-228", &data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, r#"id:"PYSEC-2023-228""#);
+        assert_eq!(result.0.len(), 1);
+        let document = &result.0[0].document;
+        assert_eq!(document.aliases, vec!["CVE-2023-46695", "GHSA-QMF9-6JQF-J8FQ"]);
+        assert_eq!(
+            document.affected,
+            vec!["PyPI/django >=3.2, <3.2.23 | >=4.1, <4.1.13 | >=4.2, <4.2.7"]
+        );
+        assert_eq!(document.cvss3x_score, Some(7.5));
+        assert!(document.published);
+
+        // aliases are searched by default
+        assert_eq!(search(&store, "CVE-2023-46695").0.len(), 1);
+        assert_eq!(search(&store, "alias:ghsa-qmf9-6jqf-j8fq").0.len(), 1);
+        assert_eq!(search(&store, "ecosystem:PyPI").0.len(), 1);
+        assert_eq!(search(&store, "package:django").0.len(), 1);
+        assert_eq!(search(&store, r#"purl:"pkg:pypi/django""#).0.len(), 1);
+        // package URLs are normalized
+        assert_eq!(search(&store, r#"purl:"pkg:PyPI/Django""#).0.len(), 1);
+        assert_eq!(search(&store, "is:high").0.len(), 1);
+        assert_eq!(search(&store, "ecosystem:npm").0.len(), 0);
+        assert_eq!(
+            search(
+                &store,
+                r#"reference:"https://docs.djangoproject.com/en/4.2/releases/security/""#
+            )
+            .0
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let cve = std::fs::read(TESTDATA[0]).unwrap();
+        assert!(matches!(Record::parse(&cve), Ok(Record::Cve(..))));
+        let osv = std::fs::read("../testdata/PYSEC-2023-228.json").unwrap();
+        assert!(matches!(Record::parse(&osv), Ok(Record::Osv(_))));
+        assert!(Record::parse(br#"{"id": "PYSEC-2023-228"}"#).is_err());
+    }
+
+    #[test]
+    fn test_record_relations() {
+        let osv = Record::parse(&std::fs::read("../testdata/PYSEC-2023-228.json").unwrap()).unwrap();
+        assert_eq!(osv.aliases(), vec!["CVE-2023-46695", "GHSA-QMF9-6JQF-J8FQ"]);
+        assert_eq!(osv.purls(), vec!["pkg:pypi/django"]);
+        assert!(osv
+            .references()
+            .contains(&"https://docs.djangoproject.com/en/4.2/releases/security/".to_string()));
+
+        let cve = Record::parse(&std::fs::read(TESTDATA[0]).unwrap()).unwrap();
+        assert!(cve.aliases().is_empty());
+        assert!(!cve.references().is_empty());
+    }
+
     #[tokio::test]
     async fn test_by_id() {
         assert_search(|index| {
@@ -509,6 +1822,15 @@ mod test {
         });
     }
 
+    #[tokio::test]
+    async fn test_in_description_stemmed() {
+        assert_search(|index| {
+            assert_eq!(search(&index, r#"in:description "exploits""#).0.len(), 1);
+            assert_eq!(search(&index, r#"in:description "resetting many streams""#).0.len(), 1);
+            assert_eq!(search(&index, r#"in:description "streams many""#).0.len(), 0);
+        });
+    }
+
     #[tokio::test]
     async fn test_in_description_special() {
         assert_search(|index| {
@@ -524,4 +1846,25 @@ mod test {
             assert_eq!(result.0.len(), 1);
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            assert!(fields
+                .iter()
+                .any(|f| f.name == "epss" && f.r#type == SearchFieldType::Number && f.sortable));
+            assert!(fields
+                .iter()
+                .any(|f| f.name == "knownExploited" && f.r#type == SearchFieldType::Predicate));
+            // all examples are valid queries, including the ones of qualifiers lacking a field, described as predicates
+            for field in fields {
+                assert!(
+                    index.search(&field.example, 0, 10, Default::default()).is_ok(),
+                    "{}",
+                    field.example
+                );
+            }
+        });
+    }
 }
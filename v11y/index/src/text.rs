@@ -0,0 +1,90 @@
+//! Analysis of the full text fields, like titles and descriptions.
+
+use std::fmt::{Display, Formatter};
+use trustification_index::tokenizer::Tokenizer;
+
+/// The analysis of full text fields.
+///
+/// Queries are analyzed the same way as the text, so changing the analysis requires reindexing. The tokenizer of
+/// single fields can be configured as well, overriding the analysis, see [`crate::Index::with_tokenizers`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TextAnalysis {
+    /// Lowercase the words of the text
+    Raw,
+    /// Stem English words, and skip stop words, so that `overflows` also matches `overflow`
+    #[default]
+    English,
+}
+
+impl Display for TextAnalysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Raw => write!(f, "raw"),
+            Self::English => write!(f, "english"),
+        }
+    }
+}
+
+impl TextAnalysis {
+    /// The tokenizer of full text fields.
+    pub fn tokenizer(&self) -> Tokenizer {
+        match self {
+            Self::Raw => Tokenizer::Simple,
+            Self::English => Tokenizer::Stemmed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sikula::prelude::Primary;
+    use trustification_index::tantivy::{
+        query::{EmptyQuery, TermQuery},
+        schema::{IndexRecordOption, Schema, STORED},
+        tokenizer::TokenStream,
+        Term,
+    };
+    use trustification_index::tokenizer::{tokenizer_manager, Tokenizers};
+    use trustification_index::QueryContext;
+
+    fn tokens(analysis: TextAnalysis, text: &str) -> Vec<String> {
+        let mut analyzer = tokenizer_manager().get(analysis.tokenizer().name()).unwrap();
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = vec![];
+        while let Some(token) = stream.next() {
+            tokens.push(token.text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            tokens(TextAnalysis::English, "Memory overflows in the parser"),
+            vec!["memori", "overflow", "parser"]
+        );
+        assert_eq!(
+            tokens(TextAnalysis::Raw, "Memory overflows in the parser"),
+            vec!["memory", "overflows", "in", "the", "parser"]
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let mut schema = Schema::builder();
+        let mut tokenizers = Tokenizers::default();
+        let field = tokenizers.add_text_field(&mut schema, "description", TextAnalysis::English.tokenizer(), STORED);
+
+        let query = tokenizers.query(field, &Primary::Equal("overflows"), &QueryContext::default());
+        assert_eq!(
+            format!("{query:?}"),
+            format!(
+                "{:?}",
+                TermQuery::new(Term::from_field_text(field, "overflow"), IndexRecordOption::WithFreqs)
+            )
+        );
+        let query = tokenizers.query(field, &Primary::Partial("the"), &QueryContext::default());
+        assert_eq!(format!("{query:?}"), format!("{:?}", EmptyQuery));
+    }
+}
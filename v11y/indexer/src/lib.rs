@@ -5,11 +5,12 @@ use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
 use trustification_event_bus::EventBusConfig;
-use trustification_index::{IndexConfig, IndexStore, WriteIndex};
+use trustification_index::{split_shards, IndexConfig, IndexStore, WriteIndex};
 use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
 use trustification_infrastructure::health::checks::FailureRate;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
+use v11y_index::TextAnalysis;
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the indexer", args_conflicts_with_subcommands = true)]
@@ -23,6 +24,9 @@ pub struct Run {
     #[arg(long = "failed-topic", default_value = "v11y-failed")]
     pub failed_topic: String,
 
+    #[arg(long = "deleted-topic", default_value = "v11y-deleted")]
+    pub deleted_topic: String,
+
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
@@ -40,6 +44,10 @@ pub struct Run {
 
     #[command(flatten)]
     pub index: IndexConfig,
+
+    /// Analysis of CVE titles and descriptions, changing it requires a reindex
+    #[arg(long = "text-analysis", env = "V11Y_TEXT_ANALYSIS", default_value_t = TextAnalysis::English)]
+    pub text_analysis: TextAnalysis,
 }
 
 impl Run {
@@ -54,9 +62,17 @@ impl Run {
                 "v11y-indexer",
                 |_context| async { Ok(()) },
                 |context| async move {
-                    let index: Box<dyn WriteIndex<Document = v11y_index::Cve>> = Box::new(v11y_index::Index::new());
-                    let index = block_in_place(|| {
-                        IndexStore::new(&self.storage, &self.index, index, context.metrics.registry())
+                    let shards = split_shards(&self.index, || {
+                        v11y_index::Index::with_tokenizers(self.text_analysis, &self.index.tokenizers)
+                    })?;
+                    let indexes = block_in_place(|| {
+                        shards
+                            .into_iter()
+                            .map(|shard| {
+                                let index: Box<dyn WriteIndex<Document = v11y_index::Record>> = Box::new(shard);
+                                IndexStore::new(&self.storage, &self.index, index, context.metrics.registry())
+                            })
+                            .collect::<Result<Vec<_>, _>>()
                     })?;
                     let storage = Storage::new(storage.process("v11y", self.devmode), context.metrics.registry())?;
 
@@ -70,12 +86,13 @@ impl Run {
                     context.health.liveness.register("index_state", check).await;
 
                     let mut indexer = Indexer {
-                        indexes: vec![index],
+                        indexes,
                         storage,
                         bus,
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: self.deleted_topic.as_str(),
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
@@ -0,0 +1,18 @@
+//! Retrieval of several CVEs at once.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The maximum number of CVEs retrieved by a single request.
+pub const MAX_BATCH_SIZE: usize = 5000;
+
+/// The CVEs of a batch request.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct CveBatch {
+    /// The CVE records, by their uppercase id
+    #[schema(value_type = HashMap<String, Object>)]
+    pub cves: BTreeMap<String, Value>,
+    /// The requested ids which are not known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<String>,
+}
@@ -0,0 +1,690 @@
+//! Matching of CPE 2.3 names against the match expressions of the products affected by vulnerabilities.
+//!
+//! Match expressions are either assessed by the NVD, or derived from the affected products of CVE records carrying
+//! CPEs, as available since CVE JSON 5.1.
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+/// The number of attributes of a CPE name, from `part` to `other`.
+const ATTRIBUTES: usize = 11;
+/// The index of the `version` attribute.
+const VERSION: usize = 3;
+
+/// The value matching any value of an attribute.
+const ANY: &str = "*";
+/// The value of attributes which are not applicable.
+const NA: &str = "-";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CpeError {
+    #[error("invalid CPE '{0}': {1}")]
+    Invalid(String, &'static str),
+}
+
+/// A CPE name, like `cpe:2.3:a:redhat:quarkus:2.13`, or a match expression with wildcards.
+///
+/// Names are parsed case-insensitively, attributes missing at the end match any value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cpe {
+    attributes: [String; ATTRIBUTES],
+}
+
+impl Cpe {
+    /// Parse a CPE in its formatted string binding (`cpe:2.3:…`) or its URI binding (`cpe:/…`).
+    pub fn parse(value: &str) -> Result<Self, CpeError> {
+        let invalid = |reason| CpeError::Invalid(value.to_string(), reason);
+        let lower = value.trim().to_lowercase();
+
+        let values: Vec<String> = if let Some(formatted) = lower.strip_prefix("cpe:2.3:") {
+            split_formatted(formatted)
+        } else if let Some(uri) = lower.strip_prefix("cpe:/") {
+            uri.split(':').map(decode_uri).collect()
+        } else {
+            return Err(invalid("unknown binding"));
+        };
+        if values.len() > ATTRIBUTES {
+            return Err(invalid("too many attributes"));
+        }
+
+        let mut attributes: [String; ATTRIBUTES] = Default::default();
+        for (attribute, value) in attributes
+            .iter_mut()
+            .zip(values.into_iter().chain(std::iter::repeat_with(String::new)))
+        {
+            *attribute = match value.is_empty() {
+                true => ANY.to_string(),
+                false => value,
+            };
+        }
+        if !matches!(attributes[0].as_str(), "a" | "o" | "h" | ANY) {
+            return Err(invalid("unknown part"));
+        }
+
+        Ok(Self { attributes })
+    }
+
+    pub fn part(&self) -> &str {
+        &self.attributes[0]
+    }
+
+    pub fn vendor(&self) -> &str {
+        &self.attributes[1]
+    }
+
+    pub fn product(&self) -> &str {
+        &self.attributes[2]
+    }
+
+    pub fn version(&self) -> &str {
+        &self.attributes[VERSION]
+    }
+
+    /// The same CPE with another version, like a version of an affected range.
+    pub fn with_version(&self, version: &str) -> Self {
+        let mut result = self.clone();
+        result.attributes[VERSION] = match version.trim() {
+            "" | ANY => ANY.to_string(),
+            version => escape(&version.to_lowercase()),
+        };
+        result
+    }
+
+    /// The part, vendor and product, like `a:redhat:quarkus`, identifying the product regardless of its version.
+    pub fn product_key(&self) -> String {
+        format!("{}:{}:{}", self.part(), self.vendor(), self.product())
+    }
+
+    /// The product keys of the expressions which may match the CPE, including the ones with `*` as part, vendor or
+    /// product, like `a:*:quarkus` for `a:redhat:quarkus`.
+    pub fn product_keys(&self) -> Vec<String> {
+        let mut result = vec![];
+        for n in 0..8 {
+            let attribute = |index: usize| match n & (1 << index) {
+                0 => self.attributes[index].as_str(),
+                _ => ANY,
+            };
+            let key = format!("{}:{}:{}", attribute(0), attribute(1), attribute(2));
+            if !result.contains(&key) {
+                result.push(key);
+            }
+        }
+        result
+    }
+
+    /// A regular expression matching the product keys of the expressions which may match the CPE, if its part,
+    /// vendor or product contain wildcards, like `a:redhat:quark*`.
+    pub fn product_key_pattern(&self) -> Option<String> {
+        let attributes = &self.attributes[..VERSION];
+        if attributes.iter().all(|attribute| is_literal(&pattern(attribute))) {
+            return None;
+        }
+
+        // a single character of an attribute in its escaped form
+        const CHAR: &str = r"(?:\\.|[^:\\])";
+        let attributes: Vec<String> = attributes
+            .iter()
+            .map(|attribute| {
+                let mut result = String::new();
+                for token in pattern(attribute) {
+                    match token {
+                        Token::Char(c) => {
+                            for c in escape(&c.to_string()).chars() {
+                                if r"\.+*?()|[]{}^$".contains(c) {
+                                    result.push('\\');
+                                }
+                                result.push(c);
+                            }
+                        }
+                        Token::Any => result.push_str(&format!("{CHAR}*")),
+                        Token::One => result.push_str(CHAR),
+                    }
+                }
+                // expressions using `*` for the attribute match any value
+                format!(r"(?:\*|{result})")
+            })
+            .collect();
+        Some(attributes.join(":"))
+    }
+}
+
+impl Display for Cpe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cpe:2.3:{}", self.attributes.join(":"))
+    }
+}
+
+/// An expression matching the CPE names of affected products, optionally limited to a range of versions.
+///
+/// The fields are the ones of the match criteria of the NVD.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CpeMatch {
+    /// The CPE of the product, may contain wildcards, like `cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*`
+    pub criteria: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_start_including: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_start_excluding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_end_including: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_end_excluding: Option<String>,
+    /// The platforms the product is only affected on, like an operating system, from configurations of the NVD
+    /// combining vulnerable and non-vulnerable products. One expression of each group must match a platform.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<Vec<CpeMatch>>,
+}
+
+impl CpeMatch {
+    /// Check if a CPE name matches the expression.
+    ///
+    /// Attributes of the name which are missing or `*`, like a missing version, match any value of the expression.
+    /// Versions are compared by their segments, so that `2.13` is within `[2.9, 2.13.1)`. Expressions limited to
+    /// platforms never match, see [`Self::matches_on`].
+    pub fn matches(&self, cpe: &Cpe) -> bool {
+        self.matches_on(cpe, &[])
+    }
+
+    /// Check if a CPE name, running on the given platforms, matches the expression.
+    pub fn matches_on(&self, cpe: &Cpe, platforms: &[Cpe]) -> bool {
+        self.matches_name(cpe)
+            && self.platforms.iter().all(|group| {
+                group
+                    .iter()
+                    .any(|expression| platforms.iter().any(|platform| expression.matches_name(platform)))
+            })
+    }
+
+    fn matches_name(&self, cpe: &Cpe) -> bool {
+        let Ok(criteria) = Cpe::parse(&self.criteria) else {
+            return false;
+        };
+
+        let attributes = criteria.attributes.iter().zip(&cpe.attributes).enumerate();
+        for (n, (source, target)) in attributes {
+            if !attribute_matches(source, target, n == VERSION) {
+                return false;
+            }
+        }
+        self.in_range(cpe.version())
+    }
+
+    /// Check if a version is within the range of versions of the expression.
+    fn in_range(&self, version: &str) -> bool {
+        if version == ANY {
+            return true;
+        }
+        let bounded = |bound: &Option<String>, accepted: &[Ordering]| match bound {
+            Some(bound) => accepted.contains(&compare_versions(version, bound)),
+            None => true,
+        };
+        let no_range = self.version_start_including.is_none()
+            && self.version_start_excluding.is_none()
+            && self.version_end_including.is_none()
+            && self.version_end_excluding.is_none();
+
+        no_range
+            || (version != NA
+                && bounded(&self.version_start_including, &[Ordering::Greater, Ordering::Equal])
+                && bounded(&self.version_start_excluding, &[Ordering::Greater])
+                && bounded(&self.version_end_including, &[Ordering::Less, Ordering::Equal])
+                && bounded(&self.version_end_excluding, &[Ordering::Less]))
+    }
+
+    /// Get the expressions of an affected product of a CVE container, in its JSON form.
+    ///
+    /// Each CPE of the product is limited to the affected versions of the product. Without any versions, the CPE is
+    /// used as it is, if the product is affected by default, or if the CPE refers to a specific version.
+    pub fn from_affected(affected: &Value) -> Vec<Self> {
+        let versions: Vec<&Value> = affected["versions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|version| version["status"].as_str() == Some("affected"))
+            .collect();
+        let default_affected = affected["defaultStatus"].as_str() == Some("affected");
+
+        let unversioned = affected["versions"].as_array().into_iter().flatten().next().is_none();
+
+        let mut result = vec![];
+        let cpes = affected["cpes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str);
+        for cpe in cpes.filter_map(|cpe| Cpe::parse(cpe).ok()) {
+            if unversioned {
+                if default_affected || cpe.version() != ANY {
+                    result.push(Self {
+                        criteria: cpe.to_string(),
+                        ..Default::default()
+                    });
+                }
+                continue;
+            }
+
+            for version in &versions {
+                let start = version["version"].as_str().unwrap_or(ANY);
+                let less_than = version["lessThan"].as_str();
+                let less_than_or_equal = version["lessThanOrEqual"].as_str();
+                let expression = match (less_than, less_than_or_equal) {
+                    (None, None) => Self {
+                        criteria: cpe.with_version(start).to_string(),
+                        ..Default::default()
+                    },
+                    _ => Self {
+                        criteria: cpe.with_version(ANY).to_string(),
+                        // a range starting at `0` covers all versions up to the end
+                        version_start_including: Some(start.to_string())
+                            .filter(|start| !matches!(start.as_str(), "0" | ANY | "")),
+                        version_end_excluding: less_than.filter(|end| *end != ANY).map(ToString::to_string),
+                        version_end_including: less_than_or_equal.filter(|end| *end != ANY).map(ToString::to_string),
+                        ..Default::default()
+                    },
+                };
+                if !result.contains(&expression) {
+                    result.push(expression);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// The evaluation of a CPE against the affected products of a vulnerability.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct AffectedCpe {
+    /// The ID of the vulnerability
+    pub id: String,
+    /// The evaluated CPE
+    pub cpe: String,
+    pub affected: bool,
+    /// The expressions of the affected products matching the CPE
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<CpeMatch>,
+}
+
+/// Compare two versions by their segments, like `2.13.0` and `2.9`.
+///
+/// Numeric segments are compared by their value, other segments alphabetically. Missing segments count as `0`, and
+/// rank above alphabetic segments, so that `1.0` is newer than `1.0-beta1`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let a = segments(&unescape(a));
+    let b = segments(&unescape(b));
+    for n in 0..a.len().max(b.len()) {
+        let zero = Segment::Number(String::new());
+        let ordering = a.get(n).unwrap_or(&zero).cmp(b.get(n).unwrap_or(&zero));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    /// The digits of a number, without leading zeros
+    Number(String),
+    Text(String),
+}
+
+impl PartialOrd for Segment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Segment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+            (Self::Number(_), Self::Text(_)) => Ordering::Greater,
+            (Self::Text(_), Self::Number(_)) => Ordering::Less,
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+        }
+    }
+}
+
+/// Split a version into its numeric and alphabetic segments, like `2.0rc1` into `2`, `0`, `rc` and `1`.
+fn segments(version: &str) -> Vec<Segment> {
+    let mut result = vec![];
+    let mut current = String::new();
+    let mut numeric = false;
+    let mut push = |current: &mut String, numeric: bool| {
+        if !current.is_empty() {
+            result.push(match numeric {
+                true => Segment::Number(current.trim_start_matches('0').to_string()),
+                false => Segment::Text(current.to_lowercase()),
+            });
+            current.clear();
+        }
+    };
+
+    for c in version.chars() {
+        if !c.is_alphanumeric() {
+            push(&mut current, numeric);
+            continue;
+        }
+        if !current.is_empty() && c.is_ascii_digit() != numeric {
+            push(&mut current, numeric);
+        }
+        numeric = c.is_ascii_digit();
+        current.push(c);
+    }
+    push(&mut current, numeric);
+    result
+}
+
+/// Check if the value of an attribute of a name matches the value of an expression.
+fn attribute_matches(source: &str, target: &str, version: bool) -> bool {
+    if source == ANY || target == ANY {
+        return true;
+    }
+    if source == NA || target == NA {
+        return source == target;
+    }
+
+    // either the expression or the name, like one searched for, may contain wildcards
+    let source_pattern = pattern(source);
+    let target_pattern = pattern(target);
+    match (is_literal(&source_pattern), is_literal(&target_pattern)) {
+        (true, true) => {
+            let source = unescape(source);
+            let target = unescape(target);
+            source == target || (version && compare_versions(&source, &target) == Ordering::Equal)
+        }
+        (false, true) => glob(&source_pattern, &unescape(target).chars().collect::<Vec<_>>()),
+        (true, false) => glob(&target_pattern, &unescape(source).chars().collect::<Vec<_>>()),
+        (false, false) => source == target,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// An unescaped `*`, matching any number of characters
+    Any,
+    /// An unescaped `?`, matching a single character
+    One,
+}
+
+fn pattern(value: &str) -> Vec<Token> {
+    let mut result = vec![];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        result.push(match c {
+            '\\' => match chars.next() {
+                Some(c) => Token::Char(c),
+                None => break,
+            },
+            '*' => Token::Any,
+            '?' => Token::One,
+            c => Token::Char(c),
+        });
+    }
+    result
+}
+
+fn is_literal(pattern: &[Token]) -> bool {
+    pattern.iter().all(|token| matches!(token, Token::Char(_)))
+}
+
+fn glob(pattern: &[Token], value: &[char]) -> bool {
+    match pattern.split_first() {
+        None => value.is_empty(),
+        Some((Token::Any, rest)) => (0..=value.len()).any(|n| glob(rest, &value[n..])),
+        Some((Token::One, rest)) => !value.is_empty() && glob(rest, &value[1..]),
+        Some((Token::Char(c), rest)) => value.first() == Some(c) && glob(rest, &value[1..]),
+    }
+}
+
+/// Split the attributes of a formatted string binding at unescaped colons, keeping escapes.
+fn split_formatted(value: &str) -> Vec<String> {
+    let mut result = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match (c, escaped) {
+            (':', false) => result.push(String::new()),
+            (c, _) => {
+                escaped = c == '\\' && !escaped;
+                if let Some(last) = result.last_mut() {
+                    last.push(c);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Decode a percent encoded attribute of a URI binding, into the form of a formatted string binding.
+fn decode_uri(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some((c, tail)) = rest.chars().next().map(|c| (c, &rest[c.len_utf8()..])) {
+        rest = tail;
+        let c = match c {
+            '%' if tail.len() >= 2 => match u8::from_str_radix(&tail[..2], 16) {
+                Ok(byte) => {
+                    rest = &tail[2..];
+                    byte as char
+                }
+                Err(_) => c,
+            },
+            c => c,
+        };
+        result.push_str(&escape(&c.to_string()));
+    }
+    result
+}
+
+/// Escape the special characters of a value, for a formatted string binding.
+fn escape(value: &str) -> String {
+    let mut result = String::new();
+    for c in value.chars() {
+        if !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-')) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn cpe(value: &str) -> Cpe {
+        Cpe::parse(value).unwrap()
+    }
+
+    fn expression(criteria: &str) -> CpeMatch {
+        CpeMatch {
+            criteria: criteria.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let quarkus = cpe("cpe:2.3:a:RedHat:quarkus:2.13");
+        assert_eq!(quarkus.product_key(), "a:redhat:quarkus");
+        assert_eq!(quarkus.version(), "2.13");
+        assert_eq!(quarkus.to_string(), "cpe:2.3:a:redhat:quarkus:2.13:*:*:*:*:*:*:*");
+
+        assert_eq!(cpe("cpe:/a:redhat:quarkus:2.13"), quarkus);
+        assert_eq!(cpe(r"cpe:2.3:a:vendor:a\:b:1.0").product(), r"a\:b");
+        assert_eq!(cpe("cpe:/a:vendor:a%3ab:1.0").product(), r"a\:b");
+        assert_eq!(
+            cpe("cpe:/o:redhat:enterprise_linux:9").product_key(),
+            "o:redhat:enterprise_linux"
+        );
+
+        assert_eq!(
+            cpe("cpe:2.3:a:redhat:quarkus").product_keys(),
+            vec![
+                "a:redhat:quarkus",
+                "*:redhat:quarkus",
+                "a:*:quarkus",
+                "*:*:quarkus",
+                "a:redhat:*",
+                "*:redhat:*",
+                "a:*:*",
+                "*:*:*"
+            ]
+        );
+        assert_eq!(quarkus.product_key_pattern(), None);
+        assert_eq!(
+            cpe("cpe:2.3:a:*:quark?s").product_key_pattern().as_deref(),
+            Some(r"(?:\*|a):(?:\*|(?:\\.|[^:\\])*):(?:\*|quark(?:\\.|[^:\\])s)")
+        );
+        assert_eq!(
+            cpe(r"cpe:2.3:a:vendor:a\:b*").product_key_pattern().as_deref(),
+            Some(r"(?:\*|a):(?:\*|vendor):(?:\*|a\\:b(?:\\.|[^:\\])*)")
+        );
+
+        assert!(Cpe::parse("pkg:maven/io.quarkus/quarkus-core").is_err());
+        assert!(Cpe::parse("cpe:2.3:x:redhat:quarkus").is_err());
+        assert!(Cpe::parse("cpe:2.3:a:a:b:c:d:e:f:g:h:i:j:k").is_err());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2.13", "2.9"), Ordering::Greater);
+        assert_eq!(compare_versions("2.13", "2.13.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0", "1.0-beta1"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0rc1", "2.0rc2"), Ordering::Less);
+        assert_eq!(compare_versions("2.16.4.Final", "2.16.10.Final"), Ordering::Less);
+        assert_eq!(compare_versions("010", "9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_matches() {
+        let quarkus = cpe("cpe:2.3:a:redhat:quarkus:2.13");
+
+        assert!(expression("cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*").matches(&quarkus));
+        assert!(expression("cpe:2.3:a:redhat:quarkus:2.13.0:*:*:*:*:*:*:*").matches(&quarkus));
+        assert!(expression("cpe:2.3:a:redhat:quark*:2.1?:*:*:*:*:*:*:*").matches(&quarkus));
+        assert!(!expression("cpe:2.3:a:redhat:quarkus:2.14:*:*:*:*:*:*:*").matches(&quarkus));
+        assert!(!expression("cpe:2.3:a:redhat:quarkus_core:*:*:*:*:*:*:*:*").matches(&quarkus));
+        assert!(!expression("cpe:2.3:a:redhat:quarkus:-:*:*:*:*:*:*:*").matches(&quarkus));
+        // names searched for may contain wildcards as well
+        assert!(expression("cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*").matches(&cpe("cpe:2.3:a:redhat:quark*")));
+        assert!(expression("cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*").matches(&cpe("cpe:2.3:a:*:quarkus")));
+        assert!(!expression("cpe:2.3:a:redhat:quarkus_core:*:*:*:*:*:*:*:*").matches(&cpe("cpe:2.3:a:redhat:quark?s")));
+        // a string comparison would take `2.13` as part of the range
+        let range = CpeMatch {
+            criteria: "cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*".to_string(),
+            version_start_including: Some("2.2".to_string()),
+            version_end_excluding: Some("2.9".to_string()),
+            ..Default::default()
+        };
+        assert!(!range.matches(&quarkus));
+        assert!(range.matches(&cpe("cpe:2.3:a:redhat:quarkus:2.8.4")));
+        assert!(range.matches(&cpe("cpe:2.3:a:redhat:quarkus")));
+
+        let range = CpeMatch {
+            criteria: "cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*".to_string(),
+            version_start_excluding: Some("2.9".to_string()),
+            version_end_including: Some("2.13".to_string()),
+            ..Default::default()
+        };
+        assert!(range.matches(&quarkus));
+        assert!(!range.matches(&cpe("cpe:2.3:a:redhat:quarkus:2.9")));
+        assert!(!range.matches(&cpe("cpe:2.3:a:redhat:quarkus:2.13.1")));
+    }
+
+    #[test]
+    fn test_matches_on() {
+        let http2 = CpeMatch {
+            criteria: "cpe:2.3:a:golang:http2:*:*:*:*:*:go:*:*".to_string(),
+            version_end_excluding: Some("0.17.0".to_string()),
+            platforms: vec![vec![
+                expression("cpe:2.3:o:redhat:enterprise_linux:8.0:*:*:*:*:*:*:*"),
+                expression("cpe:2.3:o:redhat:enterprise_linux:9.0:*:*:*:*:*:*:*"),
+            ]],
+            ..Default::default()
+        };
+        let cpe_http2 = cpe("cpe:2.3:a:golang:http2:0.16.0");
+
+        assert!(!http2.matches(&cpe_http2));
+        assert!(http2.matches_on(&cpe_http2, &[cpe("cpe:2.3:o:redhat:enterprise_linux:9")]));
+        assert!(!http2.matches_on(&cpe_http2, &[cpe("cpe:2.3:o:redhat:enterprise_linux:7")]));
+        assert!(!http2.matches_on(
+            &cpe("cpe:2.3:a:golang:http2:0.17.0"),
+            &[cpe("cpe:2.3:o:redhat:enterprise_linux:9")]
+        ));
+
+        let http2 = CpeMatch {
+            platforms: vec![
+                vec![expression("cpe:2.3:o:redhat:enterprise_linux:9.0:*:*:*:*:*:*:*")],
+                vec![expression("cpe:2.3:h:intel:*:*:*:*:*:*:*:*:*")],
+            ],
+            ..http2
+        };
+        let rhel = cpe("cpe:2.3:o:redhat:enterprise_linux:9");
+        assert!(!http2.matches_on(&cpe_http2, &[rhel.clone()]));
+        assert!(http2.matches_on(&cpe_http2, &[rhel, cpe("cpe:2.3:h:intel:x86")]));
+    }
+
+    #[test]
+    fn test_from_affected() {
+        let affected = json!({
+            "vendor": "Red Hat",
+            "product": "Quarkus",
+            "cpes": ["cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*"],
+            "versions": [
+                { "version": "0", "lessThan": "2.13.9", "status": "affected", "versionType": "semver" },
+                { "version": "3.0.0", "lessThanOrEqual": "3.2.5", "status": "affected", "versionType": "semver" },
+                { "version": "3.2.6", "status": "unaffected" },
+            ],
+        });
+        let expressions = CpeMatch::from_affected(&affected);
+        assert_eq!(
+            expressions,
+            vec![
+                CpeMatch {
+                    criteria: "cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*".to_string(),
+                    version_end_excluding: Some("2.13.9".to_string()),
+                    ..Default::default()
+                },
+                CpeMatch {
+                    criteria: "cpe:2.3:a:redhat:quarkus:*:*:*:*:*:*:*:*".to_string(),
+                    version_start_including: Some("3.0.0".to_string()),
+                    version_end_including: Some("3.2.5".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(expressions
+            .iter()
+            .any(|expression| expression.matches(&cpe("cpe:2.3:a:redhat:quarkus:2.13"))));
+        assert!(!expressions
+            .iter()
+            .any(|expression| expression.matches(&cpe("cpe:2.3:a:redhat:quarkus:3.2.6"))));
+
+        let affected = json!({
+            "cpes": ["cpe:2.3:a:redhat:quarkus:2.13.8:*:*:*:*:*:*:*", "cpe:2.3:a:redhat:quarkus_core:*:*:*:*:*:*:*:*"],
+            "defaultStatus": "unaffected",
+        });
+        assert_eq!(
+            CpeMatch::from_affected(&affected),
+            vec![expression("cpe:2.3:a:redhat:quarkus:2.13.8:*:*:*:*:*:*:*")]
+        );
+        assert!(CpeMatch::from_affected(&json!({ "product": "Quarkus" })).is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Compatibility of CVE records across the minor versions of the [CVE JSON 5 format](https://github.com/CVEProject/cve-schema).
+//!
+//! The CVE record model understands records of version 5.0. Later minor versions, like 5.1, are backward compatible,
+//! apart from their version and the containers of the authorized data publishers (ADP), which got richer. So records
+//! are converted into the form of the model, keeping the ADP containers apart, to be looked up by their JSON names.
+//!
+//! The records of vulnerabilities without a CVE are stored as OSV records, which are converted into CVE records.
+
+use crate::osv::Osv;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+/// The version of the format understood by the CVE record model
+pub const MODEL_VERSION: &str = "5.0";
+
+/// The major version of the format, all minor versions of it are supported
+const MAJOR_VERSION: &str = "5";
+
+/// Get the version of the format of a CVE record, like `5.1`.
+pub fn data_version(record: &Value) -> Option<&str> {
+    record["dataVersion"].as_str()
+}
+
+/// Check if the version of a CVE record is supported, which is any minor version of CVE JSON 5.
+pub fn is_supported(record: &Value) -> bool {
+    data_version(record)
+        .and_then(|version| version.split_once('.'))
+        .map(|(major, minor)| major == MAJOR_VERSION && minor.parse::<u32>().is_ok())
+        .unwrap_or(false)
+}
+
+/// Convert a CVE record into the form of the model, returning the ADP containers removed from it.
+///
+/// Records of unsupported versions are left as they are, for the model to reject them.
+pub fn to_model(record: &mut Value) -> Vec<Value> {
+    if !is_supported(record) {
+        return vec![];
+    }
+    record["dataVersion"] = Value::String(MODEL_VERSION.to_string());
+    let adp = record
+        .get_mut("containers")
+        .and_then(Value::as_object_mut)
+        .and_then(|containers| containers.remove("adp"));
+    match adp {
+        Some(Value::Array(adp)) => adp,
+        _ => vec![],
+    }
+}
+
+/// Parse a CVE record into the model, like `cve::Cve`, converting it first. The ADP containers are dropped.
+///
+/// Stored OSV records, which carry no `dataType`, are converted into CVE records, see [`Osv::to_cve_record`].
+pub fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, serde_json::Error> {
+    let mut record: Value = serde_json::from_slice(data)?;
+    from_value(&mut record)
+}
+
+/// Parse a CVE record in its JSON form into the model, like [`from_slice`].
+///
+/// The record is converted in place, so that it can be used to look up the parts of the CNA container not covered by
+/// the model, like the CVSS 4.0 metrics.
+pub fn from_value<T: DeserializeOwned>(record: &mut Value) -> Result<T, serde_json::Error> {
+    if record.get("dataType").is_none() {
+        *record = serde_json::from_value::<Osv>(record.take())?.to_cve_record();
+    }
+    to_model(record);
+    T::deserialize(&*record)
+}
+
+/// Parse a CVE record into the model, like [`from_slice`].
+pub fn from_str<T: DeserializeOwned>(data: &str) -> Result<T, serde_json::Error> {
+    from_slice(data.as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_model() {
+        let mut record = json!({
+            "dataType": "CVE_RECORD",
+            "dataVersion": "5.1",
+            "containers": {
+                "cna": {"title": "Example"},
+                "adp": [{"title": "CISA ADP Vulnrichment"}]
+            }
+        });
+        assert!(is_supported(&record));
+        let adp = to_model(&mut record);
+        assert_eq!(adp, vec![json!({"title": "CISA ADP Vulnrichment"})]);
+        assert_eq!(data_version(&record), Some(MODEL_VERSION));
+        assert_eq!(record["containers"], json!({"cna": {"title": "Example"}}));
+
+        let mut record = json!({"dataType": "CVE_RECORD", "dataVersion": "4.0"});
+        assert!(!is_supported(&record));
+        assert!(to_model(&mut record).is_empty());
+        assert_eq!(data_version(&record), Some("4.0"));
+    }
+
+    #[test]
+    fn test_from_osv() {
+        let record: Value = from_slice(include_bytes!("../../testdata/PYSEC-2023-228.json")).unwrap();
+        assert_eq!(record["dataType"], "CVE_RECORD");
+        assert_eq!(record["cveMetadata"]["cveId"], "PYSEC-2023-228");
+        assert_eq!(
+            record["containers"]["cna"]["affected"][0]["packageURL"],
+            "pkg:pypi/django"
+        );
+    }
+}
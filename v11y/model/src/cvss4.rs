@@ -0,0 +1,725 @@
+//! Parsing and scoring of CVSS 4.0 vectors.
+//!
+//! The score is calculated the way the FIRST reference implementation does: the vector is assigned to a
+//! "macro vector" of equivalent vectors, with a score taken from a lookup table, which then gets lowered by the
+//! distance of the vector to the most severe vector of the macro vector.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The prefix of a CVSS 4.0 vector
+const PREFIX: &str = "CVSS:4.0";
+
+/// The number of base metrics, which are mandatory and come first in [`METRICS`]
+const BASE_METRICS: usize = 11;
+
+/// All metrics, with their allowed values
+const METRICS: &[(&str, &[&str])] = &[
+    // base
+    ("AV", &["N", "A", "L", "P"]),
+    ("AC", &["L", "H"]),
+    ("AT", &["N", "P"]),
+    ("PR", &["N", "L", "H"]),
+    ("UI", &["N", "P", "A"]),
+    ("VC", &["H", "L", "N"]),
+    ("VI", &["H", "L", "N"]),
+    ("VA", &["H", "L", "N"]),
+    ("SC", &["H", "L", "N"]),
+    ("SI", &["H", "L", "N"]),
+    ("SA", &["H", "L", "N"]),
+    // threat
+    ("E", &["X", "A", "P", "U"]),
+    // environmental
+    ("CR", &["X", "H", "M", "L"]),
+    ("IR", &["X", "H", "M", "L"]),
+    ("AR", &["X", "H", "M", "L"]),
+    ("MAV", &["X", "N", "A", "L", "P"]),
+    ("MAC", &["X", "L", "H"]),
+    ("MAT", &["X", "N", "P"]),
+    ("MPR", &["X", "N", "L", "H"]),
+    ("MUI", &["X", "N", "P", "A"]),
+    ("MVC", &["X", "H", "L", "N"]),
+    ("MVI", &["X", "H", "L", "N"]),
+    ("MVA", &["X", "H", "L", "N"]),
+    ("MSC", &["X", "H", "L", "N"]),
+    ("MSI", &["X", "S", "H", "L", "N"]),
+    ("MSA", &["X", "S", "H", "L", "N"]),
+    // supplemental, not affecting the score
+    ("S", &["X", "N", "P"]),
+    ("AU", &["X", "N", "Y"]),
+    ("R", &["X", "A", "U", "I"]),
+    ("V", &["X", "D", "C"]),
+    ("RE", &["X", "L", "M", "H"]),
+    ("U", &["X", "Clear", "Green", "Amber", "Red"]),
+];
+
+/// An error parsing a CVSS 4.0 vector
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("missing the CVSS:4.0 prefix")]
+    Prefix,
+    #[error("invalid metric: {0}")]
+    InvalidMetric(String),
+    #[error("unknown metric: {0}")]
+    UnknownMetric(String),
+    #[error("invalid value of metric {0}: {1}")]
+    InvalidValue(&'static str, String),
+    #[error("duplicate metric: {0}")]
+    DuplicateMetric(&'static str),
+    #[error("missing base metric: {0}")]
+    MissingMetric(&'static str),
+}
+
+/// A parsed CVSS 4.0 vector, like `CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cvss4 {
+    /// The metrics, in the order of the vector
+    metrics: Vec<(&'static str, &'static str)>,
+}
+
+impl FromStr for Cvss4 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some(PREFIX) {
+            return Err(Error::Prefix);
+        }
+
+        let mut metrics = Vec::new();
+        for part in parts {
+            let (name, value) = part
+                .split_once(':')
+                .ok_or_else(|| Error::InvalidMetric(part.to_string()))?;
+            let &(name, values) = METRICS
+                .iter()
+                .find(|(metric, _)| *metric == name)
+                .ok_or_else(|| Error::UnknownMetric(name.to_string()))?;
+            let value = values
+                .iter()
+                .find(|v| **v == value)
+                .ok_or_else(|| Error::InvalidValue(name, value.to_string()))?;
+            if metrics.iter().any(|(metric, _)| *metric == name) {
+                return Err(Error::DuplicateMetric(name));
+            }
+            metrics.push((name, *value));
+        }
+
+        if let Some(&(name, _)) = METRICS[..BASE_METRICS]
+            .iter()
+            .find(|(name, _)| !metrics.iter().any(|(metric, _)| metric == name))
+        {
+            return Err(Error::MissingMetric(name));
+        }
+
+        Ok(Self { metrics })
+    }
+}
+
+impl Display for Cvss4 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(PREFIX)?;
+        for (name, value) in &self.metrics {
+            write!(f, "/{name}:{value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Cvss4 {
+    /// Get the CVSS 4.0 vector of a metric of a CVE record, if it has one.
+    pub fn from_cve_metric(metric: &serde_json::Value) -> Option<Result<Self, Error>> {
+        metric["cvssV4_0"]["vectorString"].as_str().map(Self::from_str)
+    }
+
+    /// The value of a metric, `X` (not defined) if it is missing.
+    pub fn get(&self, metric: &str) -> &'static str {
+        self.metrics
+            .iter()
+            .find(|(name, _)| *name == metric)
+            .map(|(_, value)| *value)
+            .unwrap_or("X")
+    }
+
+    /// The value of a metric used for scoring, applying modified metrics and the defaults of undefined metrics.
+    fn effective(&self, metric: &str) -> &'static str {
+        match (metric, self.get(metric)) {
+            // undefined threat and requirements default to the worst case
+            ("E", "X") => "A",
+            ("CR" | "IR" | "AR", "X") => "H",
+            (_, value) => match self.get(&format!("M{metric}")) {
+                "X" => value,
+                modified => modified,
+            },
+        }
+    }
+
+    /// The score, from 0.0 to 10.0.
+    pub fn score(&self) -> f64 {
+        let m = |metric: &str| self.effective(metric);
+
+        if ["VC", "VI", "VA", "SC", "SI", "SA"]
+            .into_iter()
+            .all(|metric| m(metric) == "N")
+        {
+            return 0.0;
+        }
+
+        let eq = self.macro_vector();
+        let Some(value) = lookup(&eq) else {
+            return 0.0;
+        };
+
+        // score of the next lower macro vector, raising one of the equivalence sets
+        let lower = |index: usize| {
+            let mut eq = eq;
+            eq[index] += 1;
+            lookup(&eq)
+        };
+        let lower_eq3eq6 = match (eq[2], eq[5]) {
+            (0, 0) => match (lower(2), lower(5)) {
+                (Some(eq3), Some(eq6)) => Some(eq3.max(eq6)),
+                (eq3, eq6) => eq3.or(eq6),
+            },
+            (1, 0) => lower(5),
+            _ => lower(2),
+        };
+
+        // distances of the vector to the most severe vector of the macro vector, in steps of 0.1
+        let Some(distance) = max_vectors(&eq).into_iter().find_map(|max| {
+            let distance = |metrics: &[&str]| {
+                metrics.iter().try_fold(0, |sum, &metric| {
+                    let distance = level(metric, m(metric)) - level(metric, max_value(&max, metric));
+                    (distance >= 0).then_some(sum + distance)
+                })
+            };
+            Some([
+                distance(&["AV", "PR", "UI"])?,
+                distance(&["AC", "AT"])?,
+                distance(&["VC", "VI", "VA", "CR", "IR", "AR"])?,
+                distance(&["SC", "SI", "SA"])?,
+            ])
+        }) else {
+            return value;
+        };
+
+        let max_severity = [
+            [1, 4, 5][eq[0] as usize],
+            [1, 2][eq[1] as usize],
+            match (eq[2], eq[5]) {
+                (0, 0) => 7,
+                (0, _) => 6,
+                (1, _) => 8,
+                _ => 10,
+            },
+            [6, 5, 4][eq[3] as usize],
+        ];
+
+        let mut lowered = 0;
+        let mut total = 0.0;
+        for ((next, distance), max_severity) in [lower(0), lower(1), lower_eq3eq6, lower(3)]
+            .into_iter()
+            .zip(distance)
+            .zip(max_severity)
+        {
+            if let Some(next) = next {
+                lowered += 1;
+                total += (value - next) * (distance as f64 / max_severity as f64);
+            }
+        }
+        // the threat equivalence set has no distance, but still counts when there is a lower macro vector
+        if lower(4).is_some() {
+            lowered += 1;
+        }
+
+        let mean = if lowered == 0 { 0.0 } else { total / lowered as f64 };
+        ((value - mean).clamp(0.0, 10.0) * 10.0).round() / 10.0
+    }
+
+    /// The qualitative severity of the score.
+    pub fn severity(&self) -> cvss::Severity {
+        cvss::v3::Score::from(self.score()).severity()
+    }
+
+    /// The levels of the six equivalence sets, defining the macro vector.
+    fn macro_vector(&self) -> [u8; 6] {
+        let m = |metric: &str| self.effective(metric);
+
+        let eq1 = match (m("AV"), m("PR"), m("UI")) {
+            ("N", "N", "N") => 0,
+            ("P", _, _) => 2,
+            ("N", _, _) | (_, "N", _) | (_, _, "N") => 1,
+            _ => 2,
+        };
+        let eq2 = match (m("AC"), m("AT")) {
+            ("L", "N") => 0,
+            _ => 1,
+        };
+        let eq3 = match (m("VC"), m("VI"), m("VA")) {
+            ("H", "H", _) => 0,
+            ("H", _, _) | (_, "H", _) | (_, _, "H") => 1,
+            _ => 2,
+        };
+        let eq4 = if self.get("MSI") == "S" || self.get("MSA") == "S" {
+            0
+        } else if m("SC") == "H" || m("SI") == "H" || m("SA") == "H" {
+            1
+        } else {
+            2
+        };
+        let eq5 = match m("E") {
+            "A" => 0,
+            "P" => 1,
+            _ => 2,
+        };
+        let eq6 = if (m("CR") == "H" && m("VC") == "H")
+            || (m("IR") == "H" && m("VI") == "H")
+            || (m("AR") == "H" && m("VA") == "H")
+        {
+            0
+        } else {
+            1
+        };
+
+        [eq1, eq2, eq3, eq4, eq5, eq6]
+    }
+}
+
+/// The severity level of a metric value, in steps of 0.1, lower being more severe.
+fn level(metric: &str, value: &str) -> i32 {
+    match (metric, value) {
+        ("AV", "N") | ("PR", "N") | ("UI", "N") | ("AC", "L") | ("AT", "N") => 0,
+        ("AV", "A") | ("PR", "L") | ("UI", "P") | ("AC", "H") | ("AT", "P") => 1,
+        ("AV", "L") | ("PR", "H") | ("UI", "A") => 2,
+        ("AV", "P") => 3,
+        ("VC" | "VI" | "VA", "H") | ("CR" | "IR" | "AR", "H") => 0,
+        ("VC" | "VI" | "VA", "L") | ("CR" | "IR" | "AR", "M") => 1,
+        ("VC" | "VI" | "VA", "N") | ("CR" | "IR" | "AR", "L") => 2,
+        ("SI" | "SA", "S") => 0,
+        ("SC" | "SI" | "SA", "H") => 1,
+        ("SC" | "SI" | "SA", "L") => 2,
+        ("SC" | "SI" | "SA", "N") => 3,
+        _ => 0,
+    }
+}
+
+/// The most severe vectors of each level of the equivalence sets, the sixth one being combined with the third.
+const MAX_COMPOSED_EQ1: [&[&str]; 3] = [
+    &["AV:N/PR:N/UI:N"],
+    &["AV:A/PR:N/UI:N", "AV:N/PR:L/UI:N", "AV:N/PR:N/UI:P"],
+    &["AV:P/PR:N/UI:N", "AV:A/PR:L/UI:P"],
+];
+const MAX_COMPOSED_EQ2: [&[&str]; 2] = [&["AC:L/AT:N"], &["AC:H/AT:N", "AC:L/AT:P"]];
+const MAX_COMPOSED_EQ3EQ6: [[&[&str]; 2]; 3] = [
+    [
+        &["VC:H/VI:H/VA:H/CR:H/IR:H/AR:H"],
+        &["VC:H/VI:H/VA:L/CR:M/IR:M/AR:H", "VC:H/VI:H/VA:H/CR:M/IR:M/AR:M"],
+    ],
+    [
+        &["VC:L/VI:H/VA:H/CR:H/IR:H/AR:H", "VC:H/VI:L/VA:H/CR:H/IR:H/AR:H"],
+        &[
+            "VC:L/VI:H/VA:L/CR:H/IR:M/AR:H",
+            "VC:L/VI:H/VA:H/CR:H/IR:M/AR:M",
+            "VC:H/VI:L/VA:H/CR:M/IR:H/AR:M",
+            "VC:H/VI:L/VA:L/CR:M/IR:H/AR:H",
+            "VC:L/VI:L/VA:H/CR:H/IR:H/AR:M",
+        ],
+    ],
+    [&[], &["VC:L/VI:L/VA:L/CR:H/IR:H/AR:H"]],
+];
+const MAX_COMPOSED_EQ4: [&[&str]; 3] = [&["SC:H/SI:S/SA:S"], &["SC:H/SI:H/SA:H"], &["SC:L/SI:L/SA:L"]];
+
+/// All combinations of the most severe vectors of a macro vector.
+fn max_vectors(eq: &[u8; 6]) -> Vec<Vec<&'static str>> {
+    let mut result = vec![];
+    for eq1 in MAX_COMPOSED_EQ1[eq[0] as usize] {
+        for eq2 in MAX_COMPOSED_EQ2[eq[1] as usize] {
+            for eq3eq6 in MAX_COMPOSED_EQ3EQ6[eq[2] as usize][eq[5] as usize] {
+                for eq4 in MAX_COMPOSED_EQ4[eq[3] as usize] {
+                    result.push(vec![*eq1, *eq2, *eq3eq6, *eq4]);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// The value of a metric in a most severe vector.
+fn max_value(max: &[&'static str], metric: &str) -> &'static str {
+    max.iter()
+        .flat_map(|vector| vector.split('/'))
+        .find_map(|part| part.strip_prefix(metric)?.strip_prefix(':'))
+        .unwrap_or("X")
+}
+
+/// The score of a macro vector.
+fn lookup(eq: &[u8; 6]) -> Option<f64> {
+    let key: String = eq.iter().map(|level| char::from(b'0' + level)).collect();
+    LOOKUP
+        .binary_search_by_key(&key.as_str(), |(key, _)| *key)
+        .ok()
+        .map(|index| LOOKUP[index].1)
+}
+
+/// The scores of all macro vectors, as published by FIRST, ordered by the macro vector.
+const LOOKUP: &[(&str, f64)] = &[
+    ("000000", 10.0),
+    ("000001", 9.9),
+    ("000010", 9.8),
+    ("000011", 9.5),
+    ("000020", 9.5),
+    ("000021", 9.2),
+    ("000100", 10.0),
+    ("000101", 9.6),
+    ("000110", 9.3),
+    ("000111", 8.7),
+    ("000120", 9.1),
+    ("000121", 8.1),
+    ("000200", 9.3),
+    ("000201", 9.0),
+    ("000210", 8.9),
+    ("000211", 8.0),
+    ("000220", 8.1),
+    ("000221", 6.8),
+    ("001000", 9.8),
+    ("001001", 9.5),
+    ("001010", 9.5),
+    ("001011", 9.2),
+    ("001020", 9.0),
+    ("001021", 8.4),
+    ("001100", 9.3),
+    ("001101", 9.2),
+    ("001110", 8.9),
+    ("001111", 8.1),
+    ("001120", 8.1),
+    ("001121", 6.5),
+    ("001200", 8.8),
+    ("001201", 8.0),
+    ("001210", 7.8),
+    ("001211", 7.0),
+    ("001220", 6.9),
+    ("001221", 4.8),
+    ("002001", 9.2),
+    ("002011", 8.2),
+    ("002021", 7.2),
+    ("002101", 7.9),
+    ("002111", 6.9),
+    ("002121", 5.0),
+    ("002201", 6.9),
+    ("002211", 5.5),
+    ("002221", 2.7),
+    ("010000", 9.9),
+    ("010001", 9.7),
+    ("010010", 9.5),
+    ("010011", 9.2),
+    ("010020", 9.2),
+    ("010021", 8.5),
+    ("010100", 9.5),
+    ("010101", 9.1),
+    ("010110", 9.0),
+    ("010111", 8.3),
+    ("010120", 8.4),
+    ("010121", 7.1),
+    ("010200", 9.2),
+    ("010201", 8.1),
+    ("010210", 8.2),
+    ("010211", 7.1),
+    ("010220", 7.2),
+    ("010221", 5.3),
+    ("011000", 9.5),
+    ("011001", 9.3),
+    ("011010", 9.2),
+    ("011011", 8.5),
+    ("011020", 8.5),
+    ("011021", 7.3),
+    ("011100", 9.2),
+    ("011101", 8.2),
+    ("011110", 8.0),
+    ("011111", 7.2),
+    ("011120", 7.0),
+    ("011121", 5.9),
+    ("011200", 8.4),
+    ("011201", 7.0),
+    ("011210", 7.1),
+    ("011211", 5.2),
+    ("011220", 5.0),
+    ("011221", 3.0),
+    ("012001", 8.6),
+    ("012011", 7.5),
+    ("012021", 5.2),
+    ("012101", 7.1),
+    ("012111", 5.2),
+    ("012121", 2.9),
+    ("012201", 6.3),
+    ("012211", 2.9),
+    ("012221", 1.7),
+    ("100000", 9.8),
+    ("100001", 9.5),
+    ("100010", 9.4),
+    ("100011", 8.7),
+    ("100020", 9.1),
+    ("100021", 8.1),
+    ("100100", 9.4),
+    ("100101", 8.9),
+    ("100110", 8.6),
+    ("100111", 7.4),
+    ("100120", 7.7),
+    ("100121", 6.4),
+    ("100200", 8.7),
+    ("100201", 7.5),
+    ("100210", 7.4),
+    ("100211", 6.3),
+    ("100220", 6.3),
+    ("100221", 4.9),
+    ("101000", 9.4),
+    ("101001", 8.9),
+    ("101010", 8.8),
+    ("101011", 7.7),
+    ("101020", 7.6),
+    ("101021", 6.7),
+    ("101100", 8.6),
+    ("101101", 7.6),
+    ("101110", 7.4),
+    ("101111", 5.8),
+    ("101120", 5.9),
+    ("101121", 5.0),
+    ("101200", 7.2),
+    ("101201", 5.7),
+    ("101210", 5.7),
+    ("101211", 5.2),
+    ("101220", 5.2),
+    ("101221", 2.5),
+    ("102001", 8.3),
+    ("102011", 7.0),
+    ("102021", 5.4),
+    ("102101", 6.5),
+    ("102111", 5.8),
+    ("102121", 2.6),
+    ("102201", 5.3),
+    ("102211", 2.1),
+    ("102221", 1.3),
+    ("110000", 9.5),
+    ("110001", 9.0),
+    ("110010", 8.8),
+    ("110011", 7.6),
+    ("110020", 7.6),
+    ("110021", 7.0),
+    ("110100", 9.0),
+    ("110101", 7.7),
+    ("110110", 7.5),
+    ("110111", 6.2),
+    ("110120", 6.1),
+    ("110121", 5.3),
+    ("110200", 7.7),
+    ("110201", 6.6),
+    ("110210", 6.8),
+    ("110211", 5.9),
+    ("110220", 5.2),
+    ("110221", 3.0),
+    ("111000", 8.9),
+    ("111001", 7.8),
+    ("111010", 7.6),
+    ("111011", 6.7),
+    ("111020", 6.2),
+    ("111021", 5.8),
+    ("111100", 7.4),
+    ("111101", 5.9),
+    ("111110", 5.7),
+    ("111111", 5.7),
+    ("111120", 4.7),
+    ("111121", 2.3),
+    ("111200", 6.1),
+    ("111201", 5.2),
+    ("111210", 5.7),
+    ("111211", 2.9),
+    ("111220", 2.4),
+    ("111221", 1.6),
+    ("112001", 7.1),
+    ("112011", 5.9),
+    ("112021", 3.0),
+    ("112101", 5.8),
+    ("112111", 2.6),
+    ("112121", 1.5),
+    ("112201", 2.3),
+    ("112211", 1.3),
+    ("112221", 0.6),
+    ("200000", 9.3),
+    ("200001", 8.7),
+    ("200010", 8.6),
+    ("200011", 7.2),
+    ("200020", 7.5),
+    ("200021", 5.8),
+    ("200100", 8.6),
+    ("200101", 7.4),
+    ("200110", 7.4),
+    ("200111", 6.1),
+    ("200120", 5.6),
+    ("200121", 3.4),
+    ("200200", 7.0),
+    ("200201", 5.4),
+    ("200210", 5.2),
+    ("200211", 4.0),
+    ("200220", 4.0),
+    ("200221", 2.2),
+    ("201000", 8.5),
+    ("201001", 7.5),
+    ("201010", 7.4),
+    ("201011", 5.5),
+    ("201020", 6.2),
+    ("201021", 5.1),
+    ("201100", 7.2),
+    ("201101", 5.7),
+    ("201110", 5.5),
+    ("201111", 4.1),
+    ("201120", 4.6),
+    ("201121", 1.9),
+    ("201200", 5.3),
+    ("201201", 3.6),
+    ("201210", 3.4),
+    ("201211", 1.9),
+    ("201220", 1.9),
+    ("201221", 0.8),
+    ("202001", 6.4),
+    ("202011", 5.1),
+    ("202021", 2.0),
+    ("202101", 4.7),
+    ("202111", 2.1),
+    ("202121", 1.1),
+    ("202201", 2.4),
+    ("202211", 0.9),
+    ("202221", 0.4),
+    ("210000", 8.8),
+    ("210001", 7.5),
+    ("210010", 7.3),
+    ("210011", 5.3),
+    ("210020", 6.0),
+    ("210021", 5.0),
+    ("210100", 7.3),
+    ("210101", 5.5),
+    ("210110", 5.9),
+    ("210111", 4.0),
+    ("210120", 4.1),
+    ("210121", 2.0),
+    ("210200", 5.4),
+    ("210201", 4.3),
+    ("210210", 4.5),
+    ("210211", 2.2),
+    ("210220", 2.0),
+    ("210221", 1.1),
+    ("211000", 7.5),
+    ("211001", 5.5),
+    ("211010", 5.8),
+    ("211011", 4.5),
+    ("211020", 4.0),
+    ("211021", 2.1),
+    ("211100", 6.1),
+    ("211101", 5.1),
+    ("211110", 4.8),
+    ("211111", 1.8),
+    ("211120", 2.0),
+    ("211121", 0.9),
+    ("211200", 4.6),
+    ("211201", 1.8),
+    ("211210", 1.7),
+    ("211211", 0.7),
+    ("211220", 0.8),
+    ("211221", 0.2),
+    ("212001", 5.3),
+    ("212011", 2.4),
+    ("212021", 1.4),
+    ("212101", 2.4),
+    ("212111", 1.2),
+    ("212121", 0.5),
+    ("212201", 1.0),
+    ("212211", 0.3),
+    ("212221", 0.1),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(vector: &str) -> f64 {
+        Cvss4::from_str(vector).unwrap().score()
+    }
+
+    #[test]
+    fn test_score() {
+        for (vector, expected) in [
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:H/SI:H/SA:H", 10.0),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N", 9.3),
+            ("CVSS:4.0/AV:N/AC:H/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N", 9.2),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:L/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N", 8.7),
+            ("CVSS:4.0/AV:L/AC:L/AT:N/PR:L/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N", 8.5),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:L/VI:N/VA:N/SC:N/SI:N/SA:N", 6.9),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:L/UI:N/VC:L/VI:N/VA:N/SC:N/SI:N/SA:N", 5.3),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:L/UI:P/VC:N/VI:N/VA:N/SC:L/SI:L/SA:N", 5.1),
+            ("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:N/VI:N/VA:N/SC:N/SI:N/SA:N", 0.0),
+        ] {
+            assert_eq!(score(vector), expected, "{vector}");
+        }
+    }
+
+    #[test]
+    fn test_threat_and_environment() {
+        let base = "CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N";
+        assert!(score(&format!("{base}/E:U")) < score(base));
+        // modified metrics replace the base metrics
+        assert_eq!(
+            score(&format!("{base}/MAV:L/MPR:L")),
+            score("CVSS:4.0/AV:L/AC:L/AT:N/PR:L/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N")
+        );
+        // undefined metrics are the same as not having them
+        assert_eq!(score(&format!("{base}/E:X/CR:X/MAV:X/U:Red")), score(base));
+    }
+
+    #[test]
+    fn test_severity() {
+        let cvss = Cvss4::from_str("CVSS:4.0/AV:N/AC:L/AT:N/PR:L/UI:N/VC:L/VI:N/VA:N/SC:N/SI:N/SA:N").unwrap();
+        assert_eq!(cvss.severity(), cvss::Severity::Medium);
+    }
+
+    #[test]
+    fn test_parse() {
+        let vector = "CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N/E:P";
+        let cvss = Cvss4::from_str(vector).unwrap();
+        assert_eq!(cvss.get("E"), "P");
+        assert_eq!(cvss.get("CR"), "X");
+        assert_eq!(cvss.to_string(), vector);
+
+        for (vector, error) in [
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", Error::Prefix),
+            (
+                "CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N",
+                Error::MissingMetric("SA"),
+            ),
+            (
+                "CVSS:4.0/AV:X/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N",
+                Error::InvalidValue("AV", "X".to_string()),
+            ),
+            (
+                "CVSS:4.0/AV:N/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N",
+                Error::DuplicateMetric("AV"),
+            ),
+            (
+                "CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N/C:H",
+                Error::UnknownMetric("C".to_string()),
+            ),
+        ] {
+            assert_eq!(Cvss4::from_str(vector), Err(error), "{vector}");
+        }
+    }
+
+    #[test]
+    fn test_cve_metric() {
+        let metric = serde_json::json!({
+            "cvssV4_0": {
+                "version": "4.0",
+                "vectorString": "CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N",
+                "baseScore": 9.3,
+            }
+        });
+        assert_eq!(Cvss4::from_cve_metric(&metric).unwrap().unwrap().score(), 9.3);
+        assert!(Cvss4::from_cve_metric(&serde_json::json!({"cvssV3_1": {}})).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+//! Weaknesses of CVEs, as CWE identifiers of the problem types of a CVE record.
+
+use serde_json::Value;
+
+/// A weakness, from the Common Weakness Enumeration.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Cwe {
+    /// The identifier, like `CWE-502`
+    pub id: String,
+    /// The name, like `Deserialization of Untrusted Data`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Cwe {
+    /// Get the CWEs of the problem types of a CVE container, like the CNA container, in its JSON form.
+    ///
+    /// Problem types which don't reference a CWE, like `NVD-CWE-noinfo`, are skipped, as are duplicates.
+    pub fn from_container(container: &Value) -> Vec<Self> {
+        let mut result: Vec<Self> = vec![];
+
+        let descriptions = container["problemTypes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|problem_type| problem_type["descriptions"].as_array())
+            .flatten();
+        for description in descriptions {
+            let Some(cwe) = Self::from_description(
+                description["cweId"].as_str(),
+                description["description"].as_str().unwrap_or_default(),
+            ) else {
+                continue;
+            };
+            if !result.iter().any(|existing| existing.id == cwe.id) {
+                result.push(cwe);
+            }
+        }
+
+        result
+    }
+
+    /// Get the CWE of a problem type description, like `CWE-502 Deserialization of Untrusted Data`.
+    ///
+    /// Older records lack the `cweId`, carrying the identifier in the description only.
+    fn from_description(cwe_id: Option<&str>, description: &str) -> Option<Self> {
+        let description = description.trim();
+        let id = match cwe_id {
+            Some(id) => normalize(id)?,
+            None => normalize(description.split_whitespace().next()?.trim_end_matches(':'))?,
+        };
+
+        let name = description
+            .get(..id.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(&id))
+            .map_or(description, |_| &description[id.len()..])
+            .trim_start_matches(|c: char| c == ':' || c == '-' || c.is_whitespace())
+            .trim();
+
+        Some(Self {
+            id,
+            name: (!name.is_empty()).then(|| name.to_string()),
+        })
+    }
+}
+
+/// Normalize a CWE identifier, like `cwe-502` or `502`, to `CWE-502`.
+///
+/// Returns `None` if the value is not a CWE identifier.
+pub fn normalize(value: &str) -> Option<String> {
+    let value = value.trim();
+    let number = match value.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("CWE-") => &value[4..],
+        _ => value,
+    };
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then(|| format!("CWE-{number}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_container() {
+        let container = serde_json::json!({
+            "problemTypes": [
+                {
+                    "descriptions": [
+                        {"lang": "en", "type": "CWE", "cweId": "CWE-502", "description": "CWE-502 Deserialization of Untrusted Data"},
+                        {"lang": "en", "type": "text", "description": "NVD-CWE-noinfo"}
+                    ]
+                },
+                {
+                    "descriptions": [
+                        {"lang": "en", "description": "CWE-400: Uncontrolled Resource Consumption"},
+                        {"lang": "en", "type": "CWE", "cweId": "CWE-502", "description": "CWE-502 Deserialization of Untrusted Data"},
+                        {"lang": "en", "type": "CWE", "cweId": "CWE-20", "description": "CWE-20"}
+                    ]
+                }
+            ]
+        });
+
+        assert_eq!(
+            Cwe::from_container(&container),
+            vec![
+                Cwe {
+                    id: "CWE-502".to_string(),
+                    name: Some("Deserialization of Untrusted Data".to_string()),
+                },
+                Cwe {
+                    id: "CWE-400".to_string(),
+                    name: Some("Uncontrolled Resource Consumption".to_string()),
+                },
+                Cwe {
+                    id: "CWE-20".to_string(),
+                    name: None,
+                },
+            ]
+        );
+        assert!(Cwe::from_container(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("cwe-502").as_deref(), Some("CWE-502"));
+        assert_eq!(normalize("502").as_deref(), Some("CWE-502"));
+        assert_eq!(normalize("NVD-CWE-Other"), None);
+        assert_eq!(normalize("CWE-"), None);
+    }
+}
@@ -0,0 +1,74 @@
+//! Exploit Prediction Scoring System (EPSS) scores, as published by FIRST.
+//!
+//! Scores are stored as labels of the CVE documents, so that they get indexed along with the CVE.
+
+use std::collections::BTreeMap;
+
+/// The label holding the EPSS score
+pub const SCORE_LABEL: &str = "epss";
+/// The label holding the percentile of the EPSS score
+pub const PERCENTILE_LABEL: &str = "epss_percentile";
+/// The label holding the date of the EPSS score
+pub const DATE_LABEL: &str = "epss_date";
+
+/// The EPSS score of a CVE.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Epss {
+    /// Probability of exploitation activity in the next 30 days, from 0 to 1
+    pub score: f64,
+    /// Percentile of the score, among all scored CVEs
+    pub percentile: f64,
+    /// Date the score was published, as `YYYY-MM-DD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl Epss {
+    /// Get the score from the labels of a CVE document, if it was scored.
+    pub fn from_labels(labels: &BTreeMap<String, String>) -> Option<Self> {
+        Some(Self {
+            score: labels.get(SCORE_LABEL)?.parse().ok()?,
+            percentile: labels
+                .get(PERCENTILE_LABEL)
+                .and_then(|percentile| percentile.parse().ok())
+                .unwrap_or_default(),
+            date: labels.get(DATE_LABEL).cloned(),
+        })
+    }
+
+    /// Store the score in the labels of a CVE document, keeping all other labels.
+    pub fn apply(&self, labels: &mut BTreeMap<String, String>) {
+        labels.insert(SCORE_LABEL.to_string(), self.score.to_string());
+        labels.insert(PERCENTILE_LABEL.to_string(), self.percentile.to_string());
+        match &self.date {
+            Some(date) => labels.insert(DATE_LABEL.to_string(), date.clone()),
+            None => labels.remove(DATE_LABEL),
+        };
+    }
+
+    /// Check if the score differs from another one, regardless of their dates.
+    pub fn changed(&self, other: &Self) -> bool {
+        self.score != other.score || self.percentile != other.percentile
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let epss = Epss {
+            score: 0.01055,
+            percentile: 0.83577,
+            date: Some("2023-10-14".to_string()),
+        };
+        let mut labels = BTreeMap::from([("team".to_string(), "security".to_string())]);
+        epss.apply(&mut labels);
+
+        assert_eq!(labels["epss"], "0.01055");
+        assert_eq!(labels["team"], "security");
+        assert_eq!(Epss::from_labels(&labels), Some(epss));
+        assert_eq!(Epss::from_labels(&BTreeMap::new()), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Availability of exploits of vulnerabilities, as found in the references of their records.
+
+/// Where an exploit is published.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ExploitSource {
+    /// The Exploit Database, `exploit-db.com`
+    ExploitDb,
+    /// A module of the Metasploit framework
+    Metasploit,
+}
+
+/// A published exploit of a vulnerability.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct Exploit {
+    pub source: ExploitSource,
+    /// The URL of the reference to the exploit
+    pub url: String,
+}
+
+impl Exploit {
+    /// Get the exploits among the URLs of the references of a record, skipping duplicates.
+    pub fn from_references<'a>(urls: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        let mut result: Vec<Self> = vec![];
+        for exploit in urls.into_iter().filter_map(Self::from_reference) {
+            if !result.iter().any(|existing| existing.url == exploit.url) {
+                result.push(exploit);
+            }
+        }
+        result
+    }
+
+    /// Get the exploit a reference points to, like `https://www.exploit-db.com/exploits/51107`.
+    pub fn from_reference(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let location = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))?
+            .to_lowercase();
+        let (host, path) = location.split_once('/').unwrap_or((&location, ""));
+        let host = host.strip_prefix("www.").unwrap_or(host);
+
+        let source = match host {
+            "exploit-db.com" if path.starts_with("exploits/") || path.starts_with("download/") => {
+                ExploitSource::ExploitDb
+            }
+            "github.com" if path.starts_with("rapid7/metasploit-framework/") && path.contains("/modules/") => {
+                ExploitSource::Metasploit
+            }
+            "rapid7.com" if path.starts_with("db/modules/") => ExploitSource::Metasploit,
+            _ => return None,
+        };
+
+        Some(Self {
+            source,
+            url: url.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_reference() {
+        assert_eq!(
+            Exploit::from_reference("https://www.exploit-db.com/exploits/51107"),
+            Some(Exploit {
+                source: ExploitSource::ExploitDb,
+                url: "https://www.exploit-db.com/exploits/51107".to_string(),
+            })
+        );
+        assert_eq!(
+            Exploit::from_reference(
+                "https://github.com/rapid7/metasploit-framework/blob/master/modules/exploits/linux/http/example.rb"
+            )
+            .map(|exploit| exploit.source),
+            Some(ExploitSource::Metasploit)
+        );
+        assert_eq!(
+            Exploit::from_reference("http://www.rapid7.com/db/modules/exploit/multi/http/struts2_content_type_ognl")
+                .map(|exploit| exploit.source),
+            Some(ExploitSource::Metasploit)
+        );
+
+        assert_eq!(Exploit::from_reference("https://www.exploit-db.com/"), None);
+        assert_eq!(
+            Exploit::from_reference("https://github.com/rapid7/metasploit-framework/pull/18445"),
+            None
+        );
+        assert_eq!(Exploit::from_reference("https://docs.djangoproject.com/en/4.2/"), None);
+    }
+
+    #[test]
+    fn test_from_references() {
+        let exploits = Exploit::from_references([
+            "https://www.exploit-db.com/exploits/51107",
+            "https://docs.djangoproject.com/en/4.2/",
+            "https://www.exploit-db.com/exploits/51107",
+        ]);
+        assert_eq!(exploits.len(), 1);
+    }
+}
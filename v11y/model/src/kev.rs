@@ -0,0 +1,112 @@
+//! Known exploited vulnerabilities, as listed in the CISA KEV catalog.
+//!
+//! Like EPSS scores, catalog entries are stored as labels of the CVE documents.
+
+use std::collections::BTreeMap;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// The label holding the date the CVE was added to the catalog
+pub const DATE_ADDED_LABEL: &str = "kev_date_added";
+/// The label holding the due date of the remediation
+pub const DUE_DATE_LABEL: &str = "kev_due_date";
+/// The label holding the action required to remediate the vulnerability
+pub const REQUIRED_ACTION_LABEL: &str = "kev_required_action";
+/// The label flagging vulnerabilities used in ransomware campaigns
+pub const RANSOMWARE_LABEL: &str = "kev_ransomware";
+
+const LABELS: [&str; 4] = [
+    DATE_ADDED_LABEL,
+    DUE_DATE_LABEL,
+    REQUIRED_ACTION_LABEL,
+    RANSOMWARE_LABEL,
+];
+
+/// A CVE known to be exploited.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct KnownExploited {
+    /// Date the CVE was added to the catalog, as `YYYY-MM-DD`
+    pub date_added: String,
+    /// Date by which the vulnerability must be remediated, as `YYYY-MM-DD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    /// The action required to remediate the vulnerability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_action: Option<String>,
+    /// The vulnerability is known to be used in ransomware campaigns
+    #[serde(default)]
+    pub ransomware: bool,
+}
+
+impl KnownExploited {
+    /// Get the catalog entry from the labels of a CVE document, if the CVE is known to be exploited.
+    pub fn from_labels(labels: &BTreeMap<String, String>) -> Option<Self> {
+        Some(Self {
+            date_added: labels.get(DATE_ADDED_LABEL)?.clone(),
+            due_date: labels.get(DUE_DATE_LABEL).cloned(),
+            required_action: labels.get(REQUIRED_ACTION_LABEL).cloned(),
+            ransomware: labels.get(RANSOMWARE_LABEL).is_some_and(|value| value == "true"),
+        })
+    }
+
+    /// Store the catalog entry in the labels of a CVE document, keeping all other labels.
+    pub fn apply(&self, labels: &mut BTreeMap<String, String>) {
+        Self::remove(labels);
+        labels.insert(DATE_ADDED_LABEL.to_string(), self.date_added.clone());
+        if let Some(due_date) = &self.due_date {
+            labels.insert(DUE_DATE_LABEL.to_string(), due_date.clone());
+        }
+        if let Some(required_action) = &self.required_action {
+            labels.insert(REQUIRED_ACTION_LABEL.to_string(), required_action.clone());
+        }
+        if self.ransomware {
+            labels.insert(RANSOMWARE_LABEL.to_string(), true.to_string());
+        }
+    }
+
+    /// Remove a catalog entry from the labels of a CVE document.
+    pub fn remove(labels: &mut BTreeMap<String, String>) {
+        for label in LABELS {
+            labels.remove(label);
+        }
+    }
+
+    /// The date the CVE was added to the catalog.
+    pub fn added(&self) -> Option<OffsetDateTime> {
+        parse_date(&self.date_added)
+    }
+
+    /// The due date of the remediation.
+    pub fn due(&self) -> Option<OffsetDateTime> {
+        self.due_date.as_deref().and_then(parse_date)
+    }
+}
+
+/// Parse a date of the catalog, as the start of the day in UTC.
+fn parse_date(date: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(&format!("{date}T00:00:00Z"), &Rfc3339).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let kev = KnownExploited {
+            date_added: "2023-10-10".to_string(),
+            due_date: Some("2023-10-31".to_string()),
+            required_action: Some("Apply mitigations per vendor instructions.".to_string()),
+            ransomware: false,
+        };
+        let mut labels = BTreeMap::from([("epss".to_string(), "0.61027".to_string())]);
+        kev.apply(&mut labels);
+
+        assert_eq!(labels.len(), 4);
+        assert_eq!(KnownExploited::from_labels(&labels), Some(kev.clone()));
+        assert_eq!(kev.due().map(|due| due.unix_timestamp()), Some(1698710400));
+
+        KnownExploited::remove(&mut labels);
+        assert_eq!(labels.len(), 1);
+        assert_eq!(KnownExploited::from_labels(&labels), None);
+    }
+}
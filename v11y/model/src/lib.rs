@@ -1,4 +1,17 @@
+pub mod batch;
+pub mod cpe;
+pub mod cve_record;
+pub mod cvss4;
+pub mod cwe;
+pub mod epss;
+pub mod exploit;
+pub mod kev;
+pub mod metrics;
+pub mod nvd;
+pub mod osv;
+pub mod related;
 pub mod search;
+pub mod status;
 
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
@@ -0,0 +1,14 @@
+//! Aggregated metrics of the indexed vulnerability records.
+
+use std::collections::BTreeMap;
+
+/// The number of records published in a year.
+///
+/// Records scored by several sources may count for more than one severity.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct YearCount {
+    pub year: i32,
+    pub count: u64,
+    /// Number of records, by severity. Records which are not scored aren't counted.
+    pub severities: BTreeMap<String, u64>,
+}
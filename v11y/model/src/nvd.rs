@@ -0,0 +1,84 @@
+//! The analysis of CVEs by the NVD, as synced from the NVD API.
+//!
+//! Like EPSS scores, the analysis is stored as labels of the CVE documents.
+
+use crate::cpe::CpeMatch;
+use std::collections::BTreeMap;
+
+/// The label holding the status of the analysis, like `Analyzed` or `Awaiting Analysis`
+pub const STATUS_LABEL: &str = "nvd_status";
+/// The label holding the CVSS 3.x vector assigned by the NVD
+pub const CVSS3_LABEL: &str = "nvd_cvss3";
+/// The label holding the CPE match expressions of the vulnerable products, as a JSON array
+pub const CPE_MATCHES_LABEL: &str = "nvd_cpe_matches";
+
+/// The analysis of a CVE by the NVD.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct NvdAnalysis {
+    /// The status of the analysis
+    pub status: String,
+    /// The CVSS 3.x vector of the primary score, as assessed by the NVD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss3: Option<String>,
+    /// The expressions matching the vulnerable products, as configured by the NVD
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpe_matches: Vec<CpeMatch>,
+}
+
+impl NvdAnalysis {
+    /// Get the analysis from the labels of a CVE document, if the CVE was synced from the NVD.
+    pub fn from_labels(labels: &BTreeMap<String, String>) -> Option<Self> {
+        Some(Self {
+            status: labels.get(STATUS_LABEL)?.clone(),
+            cvss3: labels.get(CVSS3_LABEL).cloned(),
+            cpe_matches: labels
+                .get(CPE_MATCHES_LABEL)
+                .and_then(|value| serde_json::from_str(value).ok())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Store the analysis in the labels of a CVE document, keeping all other labels.
+    pub fn apply(&self, labels: &mut BTreeMap<String, String>) {
+        labels.insert(STATUS_LABEL.to_string(), self.status.clone());
+        match &self.cvss3 {
+            Some(cvss3) => labels.insert(CVSS3_LABEL.to_string(), cvss3.clone()),
+            None => labels.remove(CVSS3_LABEL),
+        };
+        match serde_json::to_string(&self.cpe_matches) {
+            Ok(value) if !self.cpe_matches.is_empty() => labels.insert(CPE_MATCHES_LABEL.to_string(), value),
+            _ => labels.remove(CPE_MATCHES_LABEL),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let analysis = NvdAnalysis {
+            status: "Analyzed".to_string(),
+            cvss3: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H".to_string()),
+            cpe_matches: vec![CpeMatch {
+                criteria: "cpe:2.3:a:golang:http2:*:*:*:*:*:go:*:*".to_string(),
+                version_end_excluding: Some("0.17.0".to_string()),
+                ..Default::default()
+            }],
+        };
+        let mut labels = BTreeMap::from([("epss".to_string(), "0.61027".to_string())]);
+        analysis.apply(&mut labels);
+        assert_eq!(labels.len(), 4);
+        assert_eq!(NvdAnalysis::from_labels(&labels), Some(analysis));
+
+        NvdAnalysis {
+            status: "Awaiting Analysis".to_string(),
+            cvss3: None,
+            cpe_matches: vec![],
+        }
+        .apply(&mut labels);
+        assert_eq!(labels.len(), 2);
+        assert_eq!(NvdAnalysis::from_labels(&BTreeMap::new()), None,);
+    }
+}
@@ -0,0 +1,622 @@
+//! Vulnerability records in the [OSV format](https://ossf.github.io/osv-schema/), as published by osv.dev.
+//!
+//! Only the parts of the format used by v11y are modeled, all other content is kept in the stored record.
+//!
+//! Records are only stored for vulnerabilities without a CVE. Records aliasing a CVE are merged into the CVE instead,
+//! as its aliases. Stored records are shown like CVEs, by converting them into CVE records, see [`Osv::to_cve_record`].
+
+use crate::cve_record::MODEL_VERSION;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use time::{OffsetDateTime, UtcOffset};
+
+/// The label of CVE documents holding the ids of the records aliasing the CVE, separated by commas
+pub const ALIASES_LABEL: &str = "aliases";
+
+/// The organization assigning converted records, as OSV records don't name the organization assigning them
+const ASSIGNER_ORG_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// An OSV record.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Osv {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
+    /// The identifier, like `PYSEC-2023-228` or `RUSTSEC-2023-0052`
+    pub id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub modified: OffsetDateTime,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub published: Option<OffsetDateTime>,
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub withdrawn: Option<OffsetDateTime>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub severity: Vec<Severity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affected: Vec<Affected>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Reference>,
+}
+
+/// A severity score, like a CVSS vector.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Severity {
+    /// The type of score, like `CVSS_V3` or `CVSS_V4`
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub score: String,
+}
+
+/// A package affected by the vulnerability.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Affected {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<Package>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub severity: Vec<Severity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ranges: Vec<Range>,
+    /// Affected versions, in the version syntax of the ecosystem
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+}
+
+/// The identification of a package in its ecosystem.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Package {
+    /// The ecosystem, like `PyPI` or `crates.io`, which may carry a release, like `Debian:11`
+    pub ecosystem: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+}
+
+/// A range of affected versions, as a timeline of events.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Range {
+    #[serde(rename = "type")]
+    pub r#type: RangeType,
+    /// The repository, for `GIT` ranges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    pub events: Vec<Event>,
+}
+
+/// The encoding of the versions of a range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RangeType {
+    /// Semantic versions
+    Semver,
+    /// Versions in the version syntax of the ecosystem, ordered by the ecosystem's rules
+    Ecosystem,
+    /// Commit hashes of the repository of the range
+    Git,
+    /// Any other, unknown encoding
+    #[serde(other)]
+    Unspecified,
+}
+
+/// An event of the timeline of a range.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// The version introducing the vulnerability, `0` meaning all versions
+    Introduced(String),
+    /// The version fixing the vulnerability
+    Fixed(String),
+    /// The last affected version
+    LastAffected(String),
+    /// An upper limit of the range, beyond which the range doesn't state anything
+    Limit(String),
+}
+
+/// A reference to further information.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Reference {
+    #[serde(rename = "type")]
+    pub r#type: String,
+    pub url: String,
+}
+
+/// An interval of affected versions, decoded from the events of a range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interval<'a> {
+    /// The first affected version, `None` for all versions
+    pub introduced: Option<&'a str>,
+    /// The end of the interval, `None` if all later versions are affected
+    pub end: Option<End<'a>>,
+}
+
+/// The end of an interval of affected versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum End<'a> {
+    /// The version fixing the vulnerability, which is not affected
+    Fixed(&'a str),
+    /// The last affected version
+    LastAffected(&'a str),
+    /// An upper limit of the range, which is not affected
+    Limit(&'a str),
+}
+
+impl Osv {
+    /// The key of the record in the storage, which is the uppercase id, like for CVE ids.
+    ///
+    /// Records identified by a CVE id have no key, as they would replace the record of the CVE list.
+    pub fn storage_key(&self) -> Option<String> {
+        let key = self.id.trim().to_uppercase();
+        (!key.is_empty() && !key.starts_with("CVE-")).then_some(key)
+    }
+
+    /// The CVEs aliased by the record, in uppercase like their storage keys.
+    ///
+    /// Records aliasing a CVE are not stored, but merged into the CVE, which gets the id of the record as an alias.
+    pub fn cve_aliases(&self) -> Vec<String> {
+        self.aliases
+            .iter()
+            .map(|alias| alias.trim().to_uppercase())
+            .filter(|alias| alias.starts_with("CVE-"))
+            .collect()
+    }
+
+    /// Convert the record into a CVE record, in the form of the CVE record model.
+    ///
+    /// The affected packages become affected products, identified by their package URL, and withdrawn records become
+    /// rejected CVE records. The assigner is taken from the prefix of the id, like `GHSA`.
+    pub fn to_cve_record(&self) -> Value {
+        let assigner = self.id.split_once('-').map_or(self.id.as_str(), |(prefix, _)| prefix);
+        let provider = json!({
+            "orgId": ASSIGNER_ORG_ID,
+            "shortName": assigner,
+            "dateUpdated": timestamp(self.modified),
+        });
+
+        let mut metadata = json!({
+            "cveId": self.id,
+            "assignerOrgId": ASSIGNER_ORG_ID,
+            "assignerShortName": assigner,
+            "dateUpdated": timestamp(self.modified),
+        });
+        if let Some(published) = self.published {
+            metadata["datePublished"] = json!(timestamp(published));
+        }
+
+        let cna = match self.withdrawn {
+            Some(withdrawn) => {
+                metadata["state"] = json!("REJECTED");
+                metadata["dateRejected"] = json!(timestamp(withdrawn));
+                json!({
+                    "providerMetadata": provider,
+                    "rejectedReasons": [{ "lang": "en", "value": "This record was withdrawn." }],
+                })
+            }
+            None => {
+                metadata["state"] = json!("PUBLISHED");
+                let description = self.details.as_ref().or(self.summary.as_ref()).unwrap_or(&self.id);
+                let mut cna = json!({
+                    "providerMetadata": provider,
+                    "descriptions": [{ "lang": "en", "value": description }],
+                    "affected": self.affected.iter().filter_map(Affected::to_cve_product).collect::<Vec<_>>(),
+                    "references": self
+                        .references
+                        .iter()
+                        .map(|reference| json!({ "url": reference.url }))
+                        .collect::<Vec<_>>(),
+                    "metrics": self.severity.iter().filter_map(Severity::to_cve_metric).collect::<Vec<_>>(),
+                });
+                if let Some(summary) = &self.summary {
+                    cna["title"] = json!(summary);
+                }
+                cna
+            }
+        };
+
+        json!({
+            "dataType": "CVE_RECORD",
+            "dataVersion": MODEL_VERSION,
+            "cveMetadata": metadata,
+            "containers": { "cna": cna },
+        })
+    }
+}
+
+impl Severity {
+    /// The score as a metric of a CVE record, if it is a CVSS vector.
+    fn to_cve_metric(&self) -> Option<Value> {
+        let (name, version) = match self.r#type.as_str() {
+            "CVSS_V3" if self.score.starts_with("CVSS:3.0/") => ("cvssV3_0", "3.0"),
+            "CVSS_V3" => ("cvssV3_1", "3.1"),
+            "CVSS_V4" => ("cvssV4_0", "4.0"),
+            _ => return None,
+        };
+        let mut metric = json!({ "format": "CVSS" });
+        metric[name] = json!({ "version": version, "vectorString": self.score });
+        Some(metric)
+    }
+}
+
+impl Affected {
+    /// The affected package as an affected product of a CVE record, if it names a package.
+    ///
+    /// Intervals become version ranges, and listed versions become single versions, if there are no intervals.
+    fn to_cve_product(&self) -> Option<Value> {
+        let package = self.package.as_ref()?;
+
+        let mut versions: Vec<Value> = self
+            .ranges
+            .iter()
+            .filter(|range| matches!(range.r#type, RangeType::Semver | RangeType::Ecosystem))
+            .flat_map(|range| {
+                let version_type = match range.r#type {
+                    RangeType::Semver => "semver",
+                    _ => package.ecosystem_name(),
+                };
+                range.intervals().into_iter().map(move |interval| {
+                    let mut version = json!({
+                        "version": interval.introduced.unwrap_or("0"),
+                        "versionType": version_type,
+                        "status": "affected",
+                    });
+                    match interval.end {
+                        Some(End::Fixed(end) | End::Limit(end)) => version["lessThan"] = json!(end),
+                        Some(End::LastAffected(end)) => version["lessThanOrEqual"] = json!(end),
+                        None => version["lessThan"] = json!("*"),
+                    }
+                    version
+                })
+            })
+            .collect();
+        if versions.is_empty() {
+            versions = self
+                .versions
+                .iter()
+                .map(|version| json!({ "version": version, "status": "affected" }))
+                .collect();
+        }
+
+        let mut product = json!({
+            "vendor": package.ecosystem,
+            "product": package.name,
+            "packageName": package.name,
+            "defaultStatus": "unaffected",
+            "versions": versions,
+        });
+        if let Some(purl) = &package.purl {
+            product["packageURL"] = json!(purl);
+        }
+        Some(product)
+    }
+}
+
+/// A timestamp in the form of CVE records, which is UTC without an offset.
+fn timestamp(value: OffsetDateTime) -> String {
+    let value = value.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        value.year(),
+        u8::from(value.month()),
+        value.day(),
+        value.hour(),
+        value.minute(),
+        value.second()
+    )
+}
+
+/// Get the ids of the records aliasing a CVE, from the labels of the CVE document.
+pub fn aliases_from_labels(labels: &BTreeMap<String, String>) -> Vec<String> {
+    labels
+        .get(ALIASES_LABEL)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Add the ids of records aliasing a CVE to the labels of the CVE document, keeping the ids already present.
+pub fn add_aliases(labels: &mut BTreeMap<String, String>, ids: &[String]) {
+    let mut aliases = aliases_from_labels(labels);
+    aliases.extend(ids.iter().map(|id| id.trim().to_uppercase()));
+    aliases.sort_unstable();
+    aliases.dedup();
+    if !aliases.is_empty() {
+        labels.insert(ALIASES_LABEL.to_string(), aliases.join(","));
+    }
+}
+
+/// Keep only the ids of records aliasing a CVE which match a predicate, removing the label if none are left.
+pub fn retain_aliases(labels: &mut BTreeMap<String, String>, f: impl Fn(&str) -> bool) {
+    let mut aliases = aliases_from_labels(labels);
+    aliases.retain(|id| f(id));
+    match aliases.is_empty() {
+        true => labels.remove(ALIASES_LABEL),
+        false => labels.insert(ALIASES_LABEL.to_string(), aliases.join(",")),
+    };
+}
+
+impl Range {
+    /// Decode the timeline of events into intervals of affected versions.
+    ///
+    /// Events are applied in the order of the record, which for `SEMVER` and `ECOSYSTEM` ranges is the order of the
+    /// versions. A `limit` event ends an open interval, but doesn't close one which already ended.
+    pub fn intervals(&self) -> Vec<Interval<'_>> {
+        let mut result = vec![];
+        let mut current: Option<Option<&str>> = None;
+
+        for event in &self.events {
+            match event {
+                Event::Introduced(version) => {
+                    if let Some(introduced) = current.take() {
+                        result.push(Interval { introduced, end: None });
+                    }
+                    current = Some(Some(version.as_str()).filter(|version| *version != "0"));
+                }
+                Event::Fixed(version) | Event::LastAffected(version) | Event::Limit(version) => {
+                    if let Some(introduced) = current.take() {
+                        let end = match event {
+                            Event::Fixed(_) => End::Fixed(version),
+                            Event::LastAffected(_) => End::LastAffected(version),
+                            _ => End::Limit(version),
+                        };
+                        result.push(Interval {
+                            introduced,
+                            end: Some(end),
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(introduced) = current {
+            result.push(Interval { introduced, end: None });
+        }
+
+        result
+    }
+}
+
+/// The interval as version constraints, like `>=1.0.0, <1.2.3`.
+impl Display for Interval<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.introduced, self.end) {
+            (None, None) => write!(f, "*"),
+            (Some(introduced), None) => write!(f, ">={introduced}"),
+            (None, Some(end)) => write!(f, "{end}"),
+            (Some(introduced), Some(end)) => write!(f, ">={introduced}, {end}"),
+        }
+    }
+}
+
+impl Display for End<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(version) | Self::Limit(version) => write!(f, "<{version}"),
+            Self::LastAffected(version) => write!(f, "<={version}"),
+        }
+    }
+}
+
+impl Affected {
+    /// Describe the affected versions of the package, like `PyPI/django >=4.2.0, <4.2.7`.
+    ///
+    /// Ranges are described by their intervals, each interval being separated by `|`. Git ranges, using commit
+    /// hashes, and ranges of unknown encodings are left out, as are the explicitly listed versions if there are ranges.
+    pub fn describe(&self) -> Option<String> {
+        let package = self.package.as_ref()?;
+
+        let mut intervals: Vec<String> = self
+            .ranges
+            .iter()
+            .filter(|range| matches!(range.r#type, RangeType::Semver | RangeType::Ecosystem))
+            .flat_map(Range::intervals)
+            .map(|interval| interval.to_string())
+            .collect();
+        if intervals.is_empty() {
+            intervals = self.versions.iter().map(|version| format!("={version}")).collect();
+        }
+
+        let mut result = format!("{}/{}", package.ecosystem, package.name);
+        if !intervals.is_empty() {
+            result.push(' ');
+            result.push_str(&intervals.join(" | "));
+        }
+        Some(result)
+    }
+}
+
+impl Package {
+    /// The ecosystem, without the release, like `Debian` for `Debian:11`.
+    pub fn ecosystem_name(&self) -> &str {
+        self.ecosystem
+            .split_once(':')
+            .map_or(self.ecosystem.as_str(), |(name, _)| name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn range(events: Vec<Event>) -> Range {
+        Range {
+            r#type: RangeType::Ecosystem,
+            repo: None,
+            events,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let osv: Osv = serde_json::from_str(
+            r#"{
+                "id": "PYSEC-2023-228",
+                "modified": "2023-11-08T04:13:12.653560Z",
+                "published": "2023-11-02T06:15:08Z",
+                "aliases": ["CVE-2023-46695", "GHSA-qmf9-6jqf-j8fq"],
+                "details": "An issue was discovered in Django 3.2 before 3.2.23, 4.1 before 4.1.13, and 4.2 before 4.2.7.",
+                "affected": [{
+                    "package": {"ecosystem": "PyPI", "name": "django", "purl": "pkg:pypi/django"},
+                    "ranges": [{
+                        "type": "ECOSYSTEM",
+                        "events": [
+                            {"introduced": "3.2"}, {"fixed": "3.2.23"},
+                            {"introduced": "4.2"}, {"fixed": "4.2.7"}
+                        ]
+                    }, {
+                        "type": "GIT",
+                        "repo": "https://github.com/django/django",
+                        "events": [{"introduced": "0"}, {"fixed": "f9a7fb8466a7ba4857eaf930099b5258f3eafb2b"}]
+                    }],
+                    "versions": ["3.2", "3.2.1", "4.2", "4.2.1"]
+                }],
+                "references": [{"type": "WEB", "url": "https://www.djangoproject.com/weblog/2023/nov/01/security-releases/"}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(osv.id, "PYSEC-2023-228");
+        assert_eq!(osv.storage_key().as_deref(), Some("PYSEC-2023-228"));
+        assert_eq!(osv.aliases.len(), 2);
+        assert_eq!(osv.withdrawn, None);
+        assert_eq!(
+            osv.affected[0].describe().as_deref(),
+            Some("PyPI/django >=3.2, <3.2.23 | >=4.2, <4.2.7")
+        );
+    }
+
+    #[test]
+    fn test_to_cve_record() {
+        let mut osv: Osv = serde_json::from_slice(include_bytes!("../../testdata/PYSEC-2023-228.json")).unwrap();
+        assert_eq!(osv.cve_aliases(), vec!["CVE-2023-46695"]);
+
+        let record = osv.to_cve_record();
+        assert_eq!(record["dataVersion"], MODEL_VERSION);
+        assert_eq!(record["cveMetadata"]["cveId"], "PYSEC-2023-228");
+        assert_eq!(record["cveMetadata"]["assignerShortName"], "PYSEC");
+        assert_eq!(record["cveMetadata"]["state"], "PUBLISHED");
+        assert_eq!(record["cveMetadata"]["datePublished"], "2023-11-02T06:15:08");
+
+        let cna = &record["containers"]["cna"];
+        assert_eq!(cna["affected"][0]["packageURL"], "pkg:pypi/django");
+        assert_eq!(
+            cna["affected"][0]["versions"][0],
+            json!({"version": "3.2", "versionType": "PyPI", "status": "affected", "lessThan": "3.2.23"})
+        );
+        assert_eq!(cna["affected"][0]["versions"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            cna["metrics"][0]["cvssV3_1"]["vectorString"],
+            "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"
+        );
+        assert_eq!(cna["references"].as_array().unwrap().len(), 2);
+
+        osv.withdrawn = Some(osv.modified);
+        let record = osv.to_cve_record();
+        assert_eq!(record["cveMetadata"]["state"], "REJECTED");
+        assert!(record["containers"]["cna"]["rejectedReasons"].is_array());
+    }
+
+    #[test]
+    fn test_intervals() {
+        let range = range(vec![
+            Event::Introduced("0".into()),
+            Event::LastAffected("1.0.1".into()),
+            Event::Introduced("1.1.0".into()),
+            Event::Limit("1.2.0".into()),
+            Event::Introduced("2.0.0".into()),
+        ]);
+        assert_eq!(
+            range.intervals(),
+            vec![
+                Interval {
+                    introduced: None,
+                    end: Some(End::LastAffected("1.0.1")),
+                },
+                Interval {
+                    introduced: Some("1.1.0"),
+                    end: Some(End::Limit("1.2.0")),
+                },
+                Interval {
+                    introduced: Some("2.0.0"),
+                    end: None,
+                },
+            ]
+        );
+
+        // a limit doesn't close an interval which already ended
+        let range = self::range(vec![
+            Event::Introduced("0".into()),
+            Event::Fixed("1.0.0".into()),
+            Event::Limit("2.0.0".into()),
+        ]);
+        assert_eq!(range.intervals().len(), 1);
+    }
+
+    #[test]
+    fn test_describe_versions() {
+        let affected = Affected {
+            package: Some(Package {
+                ecosystem: "crates.io".into(),
+                name: "openssl".into(),
+                purl: None,
+            }),
+            severity: vec![],
+            ranges: vec![],
+            versions: vec!["0.10.55".into()],
+        };
+        assert_eq!(affected.describe().as_deref(), Some("crates.io/openssl =0.10.55"));
+    }
+
+    #[test]
+    fn test_aliases_labels() {
+        let mut labels = BTreeMap::from([(ALIASES_LABEL.to_string(), "GHSA-QMF9-6JQF-J8FQ".to_string())]);
+        add_aliases(&mut labels, &["PYSEC-2023-228".into(), "ghsa-qmf9-6jqf-j8fq".into()]);
+        assert_eq!(
+            aliases_from_labels(&labels),
+            vec!["GHSA-QMF9-6JQF-J8FQ", "PYSEC-2023-228"]
+        );
+
+        let mut labels = BTreeMap::new();
+        add_aliases(&mut labels, &[]);
+        assert!(labels.is_empty());
+
+        let mut labels = BTreeMap::from([(
+            ALIASES_LABEL.to_string(),
+            "GHSA-QMF9-6JQF-J8FQ,PYSEC-2023-228".to_string(),
+        )]);
+        retain_aliases(&mut labels, |id| id != "PYSEC-2023-228");
+        assert_eq!(aliases_from_labels(&labels), vec!["GHSA-QMF9-6JQF-J8FQ"]);
+        retain_aliases(&mut labels, |_| false);
+        assert!(labels.is_empty());
+    }
+
+    #[test]
+    fn test_ecosystem_name() {
+        let package = Package {
+            ecosystem: "Debian:11".into(),
+            name: "openssl".into(),
+            purl: None,
+        };
+        assert_eq!(package.ecosystem_name(), "Debian");
+    }
+}
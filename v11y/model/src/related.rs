@@ -0,0 +1,26 @@
+//! Vulnerabilities related to each other.
+
+/// The way a vulnerability is related to another one.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Relation {
+    /// One of the vulnerabilities is an alias of the other, or both share an alias
+    Alias,
+    /// Both vulnerabilities share a reference
+    Reference,
+    /// Both vulnerabilities affect the same package
+    Package,
+}
+
+/// A vulnerability related to another one.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct RelatedCve {
+    /// The identifier, like a CVE id or the id of an OSV record
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The ways the vulnerability is related, ordered by their strength
+    pub relations: Vec<Relation>,
+}
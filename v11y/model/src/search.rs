@@ -3,7 +3,14 @@ use sikula::prelude::*;
 use std::fmt::Debug;
 use time::OffsetDateTime;
 
-#[derive(Clone, Debug, PartialEq, Search)]
+use crate::cpe::{Cpe, CpeMatch};
+use crate::cwe::Cwe;
+use crate::epss::Epss;
+use crate::exploit::Exploit;
+use crate::kev::KnownExploited;
+
+#[derive(Clone, Debug, PartialEq, Search, strum::VariantNames)]
+#[strum(serialize_all = "camelCase")]
 pub enum Cves<'a> {
     /// Search by CVE id
     #[search(default)]
@@ -15,8 +22,32 @@ pub enum Cves<'a> {
     #[search(default)]
     Description(Primary<'a>),
 
+    /// Search by an alias, like the CVE id of an OSV record
+    #[search(default)]
+    Alias(Primary<'a>),
+
+    /// Search by the ecosystem of an affected package, like `PyPI` or `crates.io`
+    Ecosystem(&'a str),
+    /// Search by the name of an affected package
+    Package(Primary<'a>),
+    /// Search by the package URL of an affected package
+    Purl(Primary<'a>),
+    /// Search by the URL of a reference
+    Reference(Primary<'a>),
+    /// Search by the CPE of an affected product, like `cpe:2.3:a:redhat:quarkus:2.13`
+    ///
+    /// Only the vendor and product are matched, versions are evaluated by [`SearchDocument::affects`].
+    Cpe(&'a str),
+
+    /// Search by any of the CVSS 4.0 or 3.x scores
     #[search(sort)]
     Score(PartialOrdered<f64>),
+    /// Search by the CVSS 4.0 score
+    #[search(sort)]
+    Cvss4(PartialOrdered<f64>),
+
+    /// Search by the CWE of a weakness, like `CWE-502` or just `502`
+    Cwe(&'a str),
 
     DateReserved(Ordered<OffsetDateTime>),
     #[search(sort)]
@@ -25,6 +56,23 @@ pub enum Cves<'a> {
     DateUpdated(Ordered<OffsetDateTime>),
     #[search(sort)]
     DateRejected(Ordered<OffsetDateTime>),
+    /// Search by the time the record was last indexed, after it got created or modified
+    #[search(sort)]
+    Indexed(Ordered<OffsetDateTime>),
+
+    /// Search by EPSS score, the probability of exploitation
+    #[search(sort)]
+    Epss(PartialOrdered<f64>),
+    /// Search by the percentile of the EPSS score
+    #[search(sort)]
+    EpssPercentile(PartialOrdered<f64>),
+
+    /// Search by the date the CVE was added to the known exploited vulnerabilities catalog
+    #[search(sort)]
+    KevAdded(Ordered<OffsetDateTime>),
+    /// Search by the due date of the remediation of a known exploited vulnerability
+    #[search(sort)]
+    KevDue(Ordered<OffsetDateTime>),
 
     Severity(&'a str),
     Low,
@@ -32,8 +80,16 @@ pub enum Cves<'a> {
     High,
     Critical,
 
+    /// Search by the state of the record, `published`, `rejected` or `reserved`
+    ///
+    /// Unless a query refers to the state, rejected and reserved records are not matched.
+    State(&'a str),
     Published,
     Rejected,
+    /// CVEs listed in the known exploited vulnerabilities catalog
+    KnownExploited,
+    /// Vulnerabilities referencing a published exploit, like an Exploit Database entry or a Metasploit module
+    ExploitAvailable,
 }
 
 /// A document returned from the search index for every match.
@@ -42,15 +98,115 @@ pub struct SearchDocument {
     /// CVE identifier
     pub id: String,
     pub published: bool,
+    /// The state of the record, missing for records indexed before it was tracked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<CveState>,
     pub title: Option<String>,
     pub descriptions: Vec<String>,
 
+    /// Other identifiers of the vulnerability
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// The affected packages and versions, like `PyPI/django >=4.2, <4.2.7`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub affected: Vec<String>,
+    /// The normalized package URLs of the affected packages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub purls: Vec<String>,
+
     pub cvss3x_score: Option<f64>,
+    /// The CVSS 4.0 score, if the CVE was scored using CVSS 4.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cvss4_score: Option<f64>,
+
+    /// The weaknesses of the problem types
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwes: Vec<Cwe>,
 
     #[serde(with = "time::serde::rfc3339::option")]
     pub date_published: Option<OffsetDateTime>,
     #[serde(with = "time::serde::rfc3339::option")]
     pub date_updated: Option<OffsetDateTime>,
+    /// The time the record was last indexed, after it got created or modified
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub indexed_timestamp: Option<OffsetDateTime>,
+
+    /// The EPSS score, if the CVE was scored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epss: Option<Epss>,
+    /// The entry of the known exploited vulnerabilities catalog, if the CVE is known to be exploited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub known_exploited: Option<KnownExploited>,
+    /// Whether an exploit is published, as referenced by the record
+    #[serde(default)]
+    pub exploit_available: bool,
+    /// The published exploits
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exploits: Vec<Exploit>,
+    /// The expressions matching the CPEs of the affected products
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpe_matches: Vec<CpeMatch>,
+}
+
+impl SearchDocument {
+    /// The preferred CVSS score, which is the CVSS 4.0 score if available.
+    pub fn score(&self) -> Option<f64> {
+        self.cvss4_score.or(self.cvss3x_score)
+    }
+
+    /// The expressions matching a CPE, which are empty if the vulnerability doesn't affect the CPE.
+    pub fn matching(&self, cpe: &Cpe) -> Vec<CpeMatch> {
+        self.matching_on(cpe, &[])
+    }
+
+    /// The expressions matching a CPE running on the given platforms, like an operating system.
+    pub fn matching_on(&self, cpe: &Cpe, platforms: &[Cpe]) -> Vec<CpeMatch> {
+        self.cpe_matches
+            .iter()
+            .filter(|expression| expression.matches_on(cpe, platforms))
+            .cloned()
+            .collect()
+    }
+
+    /// Check if the vulnerability affects a CPE, like `cpe:2.3:a:redhat:quarkus:2.13`.
+    pub fn affects(&self, cpe: &Cpe) -> bool {
+        self.cpe_matches.iter().any(|expression| expression.matches(cpe))
+    }
+}
+
+/// The state of a CVE record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CveState {
+    Published,
+    /// Rejected by the CNA, or withdrawn in case of an OSV record
+    Rejected,
+    /// Reserved, but not yet published
+    Reserved,
+}
+
+impl CveState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Published => "PUBLISHED",
+            Self::Rejected => "REJECTED",
+            Self::Reserved => "RESERVED",
+        }
+    }
+
+    /// Parse a state, ignoring its case.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "PUBLISHED" => Some(Self::Published),
+            "REJECTED" => Some(Self::Rejected),
+            "RESERVED" => Some(Self::Reserved),
+            _ => None,
+        }
+    }
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
@@ -0,0 +1,172 @@
+//! The status of the sources of vulnerability information, telling whether their mirrors are up to date.
+
+use std::fmt::{Display, Formatter};
+use time::OffsetDateTime;
+
+/// A source of vulnerability information, synced by a walker or uploaded manually.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The CVE list of the CVE program
+    Cve,
+    /// The analysis of the National Vulnerability Database
+    Nvd,
+    /// Records in the OSV format, like the dumps of osv.dev
+    Osv,
+    /// The GitHub security advisories
+    Ghsa,
+    /// The Exploit Prediction Scoring System
+    Epss,
+    /// The known exploited vulnerabilities catalog of CISA
+    Kev,
+    /// Records uploaded through the API
+    Manual,
+}
+
+impl Source {
+    pub const ALL: [Source; 7] = [
+        Self::Cve,
+        Self::Nvd,
+        Self::Osv,
+        Self::Ghsa,
+        Self::Epss,
+        Self::Kev,
+        Self::Manual,
+    ];
+
+    /// The name of the source, which is also the name its status is stored by
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cve => "cve",
+            Self::Nvd => "nvd",
+            Self::Osv => "osv",
+            Self::Ghsa => "ghsa",
+            Self::Epss => "epss",
+            Self::Kev => "kev",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The status of a source of vulnerability information.
+///
+/// A sync is a run of the walker of the source. Manual uploads have no runs, every upload counts as a sync of its
+/// own, and their counts are accumulated over all uploads.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct SourceStatus {
+    pub source: Source,
+    /// When the last successful sync finished, `None` if no sync succeeded yet
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_success: Option<OffsetDateTime>,
+    /// When the last sync finished, successful or not
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_attempt: Option<OffsetDateTime>,
+    /// The error the last sync failed with, `None` if it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// The number of documents stored or updated by the last sync
+    #[serde(default)]
+    pub documents: u64,
+    /// The number of documents which failed to be stored or updated by the last sync
+    #[serde(default)]
+    pub errors: u64,
+}
+
+impl SourceStatus {
+    /// The status of a source which was never synced.
+    pub fn new(source: Source) -> Self {
+        Self {
+            source,
+            last_success: None,
+            last_attempt: None,
+            last_error: None,
+            documents: 0,
+            errors: 0,
+        }
+    }
+
+    /// Record the outcome of a sync finished at a time, with the numbers of stored and failed documents if it
+    /// succeeded.
+    pub fn record(&mut self, at: OffsetDateTime, outcome: Result<(u64, u64), String>) {
+        self.last_attempt = Some(at);
+        match outcome {
+            Ok((documents, errors)) => {
+                self.last_success = Some(at);
+                self.last_error = None;
+                self.documents = documents;
+                self.errors = errors;
+            }
+            Err(err) => {
+                self.last_error = Some(err);
+                self.documents = 0;
+                self.errors = 0;
+            }
+        }
+    }
+
+    /// Record the outcome of a manual upload at a time, accumulating the counts.
+    pub fn record_upload(&mut self, at: OffsetDateTime, stored: bool) {
+        self.last_attempt = Some(at);
+        if stored {
+            self.last_success = Some(at);
+            self.last_error = None;
+            self.documents += 1;
+        } else {
+            self.errors += 1;
+        }
+    }
+
+    /// Check if the last successful sync finished before a time, or there was none.
+    pub fn is_stale(&self, since: OffsetDateTime) -> bool {
+        self.last_success.map(|last| last < since).unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(day: i64) -> OffsetDateTime {
+        // days of October 2023
+        OffsetDateTime::from_unix_timestamp(1696118400 + (day - 1) * 86400).unwrap()
+    }
+
+    #[test]
+    fn test_record() {
+        let mut status = SourceStatus::new(Source::Nvd);
+        assert!(status.is_stale(at(1)));
+
+        status.record(at(2), Ok((10, 1)));
+        status.record(at(3), Err("Service unavailable".to_string()));
+        assert_eq!(status.last_success, Some(at(2)));
+        assert_eq!(status.last_attempt, Some(at(3)));
+        assert_eq!(status.last_error.as_deref(), Some("Service unavailable"));
+        assert!(!status.is_stale(at(1)));
+        assert!(status.is_stale(at(3)));
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["source"], "nvd");
+        assert_eq!(json["last_success"], "2023-10-02T00:00:00Z");
+        assert_eq!(serde_json::from_value::<SourceStatus>(json).unwrap(), status);
+    }
+
+    #[test]
+    fn test_record_upload() {
+        let mut status = SourceStatus::new(Source::Manual);
+        status.record_upload(at(1), true);
+        status.record_upload(at(2), false);
+        status.record_upload(at(3), true);
+        assert_eq!(status.last_success, Some(at(3)));
+        assert_eq!((status.documents, status.errors), (2, 1));
+    }
+}
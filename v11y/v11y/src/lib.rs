@@ -5,6 +5,11 @@ pub enum Command {
     Api(v11y_api::Run),
     Indexer(v11y_indexer::Run),
     Walker(v11y_walker::Run),
+    Epss(v11y_walker::epss::Run),
+    Ghsa(v11y_walker::ghsa::Run),
+    Kev(v11y_walker::kev::Run),
+    Nvd(v11y_walker::nvd::Run),
+    Osv(v11y_walker::osv::Run),
 }
 
 impl Command {
@@ -13,6 +18,11 @@ impl Command {
             Self::Api(run) => run.run().await,
             Self::Indexer(run) => run.run().await,
             Self::Walker(run) => run.run().await,
+            Self::Epss(run) => run.run().await,
+            Self::Ghsa(run) => run.run().await,
+            Self::Kev(run) => run.run().await,
+            Self::Nvd(run) => run.run().await,
+            Self::Osv(run) => run.run().await,
         }
     }
 }
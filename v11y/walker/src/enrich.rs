@@ -0,0 +1,133 @@
+//! Enrichment of the stored CVEs, with information of other sources.
+//!
+//! The information is stored as labels of the CVE documents, which makes the indexer index it along with the CVE.
+//! Labels only get stored if they changed, so that unchanged CVEs don't get reindexed.
+
+use crate::status::Counts;
+use futures::{stream, StreamExt};
+use std::collections::HashSet;
+use trustification_storage::{Error as StorageError, Labels, S3Path, Storage};
+use url::Url;
+
+/// The outcome of updating the labels of a single CVE
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Updated,
+    Unchanged,
+    /// The CVE is not stored, so it can't be labeled
+    Unknown,
+    Failed,
+}
+
+/// Fetch the data of a source, which may be a local file using a `file:` URL.
+pub(crate) async fn fetch(source: &Url) -> anyhow::Result<Vec<u8>> {
+    if source.scheme() == "file" {
+        let path = source
+            .to_file_path()
+            .map_err(|()| anyhow::anyhow!("Invalid file URL: {source}"))?;
+        return Ok(tokio::fs::read(path).await?);
+    }
+
+    let response = reqwest::get(source.clone()).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Update the labels of all CVEs, logging the outcome. Returns the numbers of CVEs which were updated, and which failed
+/// to be updated.
+pub(crate) async fn update_all<T>(
+    storage: &Storage,
+    entries: &[(String, T)],
+    concurrency: usize,
+    f: impl Fn(&T, &mut Labels),
+) -> Counts {
+    let f = &f;
+    let outcomes: Vec<_> = stream::iter(entries.iter())
+        .map(|(cve, entry)| update(storage, cve, move |labels| f(entry, labels)))
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let count = |outcome: Outcome| outcomes.iter().filter(|o| **o == outcome).count();
+    log::info!(
+        "Processed {} CVEs: {} updated, {} unchanged, {} unknown, {} failed",
+        outcomes.len(),
+        count(Outcome::Updated),
+        count(Outcome::Unchanged),
+        count(Outcome::Unknown),
+        count(Outcome::Failed),
+    );
+    Counts {
+        documents: count(Outcome::Updated) as u64,
+        errors: count(Outcome::Failed) as u64,
+    }
+}
+
+/// Remove the labels of a source from all labeled CVEs which are not part of its current entries, as they got removed
+/// from the source. Returns the numbers of CVEs which were updated, and which failed to be updated.
+pub(crate) async fn remove_stale<T>(
+    storage: &Storage,
+    entries: &[(String, T)],
+    concurrency: usize,
+    remove: impl Fn(&mut Labels),
+) -> anyhow::Result<Counts> {
+    let current: HashSet<_> = entries.iter().map(|(cve, _)| cve.as_str()).collect();
+    let stale: Vec<_> = storage
+        .list_labeled_keys()
+        .await?
+        .into_iter()
+        .filter(|cve| !current.contains(cve.as_str()))
+        .map(|cve| (cve, ()))
+        .collect();
+    log::info!("Checking {} CVEs for stale labels", stale.len());
+    Ok(update_all(storage, &stale, concurrency, |_, labels| remove(labels)).await)
+}
+
+/// Update the labels of all labeled CVEs, using a function modifying the labels of a CVE, getting the CVE along with
+/// them. Returns the numbers of CVEs which were updated, and which failed to be updated.
+pub(crate) async fn update_labeled(
+    storage: &Storage,
+    concurrency: usize,
+    f: impl Fn(&str, &mut Labels),
+) -> anyhow::Result<Counts> {
+    let labeled: Vec<_> = storage
+        .list_labeled_keys()
+        .await?
+        .into_iter()
+        .map(|cve| (cve.clone(), cve))
+        .collect();
+    Ok(update_all(storage, &labeled, concurrency, |cve, labels| f(cve, labels)).await)
+}
+
+/// Update the labels of a CVE, using a function modifying them.
+async fn update(storage: &Storage, cve: &str, f: impl FnOnce(&mut Labels)) -> Outcome {
+    match try_update(storage, cve, f).await {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            log::warn!("Failed to update the labels of {cve}: {err}");
+            Outcome::Failed
+        }
+    }
+}
+
+async fn try_update(storage: &Storage, cve: &str, f: impl FnOnce(&mut Labels)) -> Result<Outcome, StorageError> {
+    let current = storage.get_labels(cve).await?;
+    let mut labels = current.clone();
+    f(&mut labels);
+    if labels == current {
+        return Ok(Outcome::Unchanged);
+    }
+
+    if current.is_empty() {
+        // labels can only be indexed along with their document
+        match storage.get_head(S3Path::from_key(cve)).await {
+            Ok(head) if head.status.as_u16() == 404 => return Ok(Outcome::Unknown),
+            Err(StorageError::NotFound) => return Ok(Outcome::Unknown),
+            Ok(_) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    storage.put_labels(cve, &labels).await?;
+    log::debug!("Updated the labels of {cve}");
+    Ok(Outcome::Updated)
+}
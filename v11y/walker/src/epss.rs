@@ -0,0 +1,182 @@
+//! Import of the daily EPSS scores published by FIRST.
+//!
+//! Scores are stored as labels of the stored CVE documents, see [`crate::enrich`].
+
+use crate::enrich;
+use crate::status::{self, Counts};
+use prometheus::Registry;
+use std::io::Read;
+use std::process::ExitCode;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use url::Url;
+use v11y_model::epss::Epss;
+use v11y_model::status::Source;
+
+/// The location of the current scores
+const DEFAULT_SOURCE: &str = "https://epss.cyentia.com/epss_scores-current.csv.gz";
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Import EPSS scores", args_conflicts_with_subcommands = true)]
+pub struct Run {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The URL of the scores, a CSV file which may be gzip compressed. Local files can be used with a `file:` URL.
+    #[arg(long, default_value = DEFAULT_SOURCE)]
+    pub source: Url,
+
+    /// The number of CVEs updated concurrently.
+    #[arg(long, default_value_t = 16)]
+    pub concurrency: usize,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+        Infrastructure::from(self.infra)
+            .run(
+                "v11y-epss",
+                |_context| async { Ok(()) },
+                status::recorded(&storage, Source::Epss, |storage| async move {
+                    log::info!("Fetching EPSS scores from {}", self.source);
+                    let data = enrich::fetch(&self.source).await?;
+                    let scores = parse(&decompress(data)?)?;
+                    log::info!("Processing {} scores", scores.len());
+
+                    let counts = enrich::update_all(&storage, &scores, self.concurrency, |epss, labels| {
+                        // the date alone changes every day, which doesn't justify reindexing the CVE
+                        if Epss::from_labels(labels).map_or(true, |current| current.changed(epss)) {
+                            epss.apply(labels);
+                        }
+                    })
+                    .await;
+                    Ok(counts)
+                }),
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Decompress the data, if it is gzip compressed.
+fn decompress(data: Vec<u8>) -> anyhow::Result<String> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        let mut result = String::new();
+        flate2::read::GzDecoder::new(data.as_slice()).read_to_string(&mut result)?;
+        Ok(result)
+    } else {
+        Ok(String::from_utf8(data)?)
+    }
+}
+
+/// Parse the CSV of EPSS scores.
+///
+/// Ahead of the header, the file has a comment carrying the date of the scores, like
+/// `#model_version:v2023.03.01,score_date:2023-10-14T00:00:00+0000`.
+fn parse(data: &str) -> anyhow::Result<Vec<(String, Epss)>> {
+    let mut date = None;
+    let mut columns = None;
+    let mut scores = vec![];
+
+    for (n, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            date = comment
+                .split(',')
+                .find_map(|entry| entry.strip_prefix("score_date:"))
+                .and_then(|value| value.get(..10))
+                .map(ToString::to_string)
+                .or(date);
+            continue;
+        }
+
+        let values: Vec<_> = line.split(',').map(str::trim).collect();
+        let Some((cve, score, percentile)) = columns else {
+            let column = |name: &str| values.iter().position(|value| *value == name);
+            match (column("cve"), column("epss"), column("percentile")) {
+                (Some(cve), Some(score), Some(percentile)) => columns = Some((cve, score, percentile)),
+                _ => anyhow::bail!("Missing the cve, epss and percentile columns: {line}"),
+            }
+            continue;
+        };
+
+        let value = |column: usize| values.get(column).and_then(|value| value.parse::<f64>().ok());
+        match (values.get(cve), value(score), value(percentile)) {
+            (Some(cve), Some(score), Some(percentile)) => scores.push((
+                cve.to_uppercase(),
+                Epss {
+                    score,
+                    percentile,
+                    date: date.clone(),
+                },
+            )),
+            _ => log::warn!("Skipping invalid line {}: {line}", n + 1),
+        }
+    }
+
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let scores = parse(
+            "#model_version:v2023.03.01,score_date:2023-10-14T00:00:00+0000\n\
+             cve,epss,percentile\n\
+             CVE-1999-0001,0.01055,0.83577\n\
+             CVE-2023-44487,0.61027,0.97652\n\
+             CVE-2023-0000,invalid,0.5\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scores,
+            vec![
+                (
+                    "CVE-1999-0001".to_string(),
+                    Epss {
+                        score: 0.01055,
+                        percentile: 0.83577,
+                        date: Some("2023-10-14".to_string()),
+                    }
+                ),
+                (
+                    "CVE-2023-44487".to_string(),
+                    Epss {
+                        score: 0.61027,
+                        percentile: 0.97652,
+                        date: Some("2023-10-14".to_string()),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_missing_columns() {
+        assert!(parse("cve,score\nCVE-1999-0001,0.01055\n").is_err());
+    }
+
+    #[test]
+    fn test_decompress() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, b"cve,epss,percentile\n").unwrap();
+        let data = encoder.finish().unwrap();
+
+        assert_eq!(decompress(data).unwrap(), "cve,epss,percentile\n");
+        assert_eq!(decompress(b"plain".to_vec()).unwrap(), "plain");
+    }
+}
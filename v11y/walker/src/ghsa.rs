@@ -0,0 +1,735 @@
+//! Sync of the GitHub Security Advisories (GHSA), using the GraphQL API of GitHub.
+//!
+//! Advisories are converted into OSV records, which are stored and indexed like the records imported by
+//! [`crate::osv`]. Advisories aliasing a CVE are merged into the CVE instead, which gets the advisory id as a label,
+//! see [`crate::enrich`], so that a vulnerability is only found once. The label of a CVE loses the ids of synced
+//! advisories which no longer alias it.
+
+use crate::enrich;
+use crate::status::{self, Counts};
+use packageurl::PackageUrl;
+use prometheus::Registry;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use url::Url;
+use v11y_model::osv::{self, Affected, Event, Osv, Package, Range, RangeType, Reference, Severity};
+use v11y_model::status::Source;
+
+/// The GraphQL endpoint of GitHub
+const DEFAULT_SOURCE: &str = "https://api.github.com/graphql";
+
+/// The number of advisories, and of vulnerabilities of an advisory, requested at once
+const PAGE_SIZE: usize = 100;
+
+const QUERY: &str = r#"
+query($first: Int!, $after: String, $updatedSince: DateTime) {
+  securityAdvisories(first: $first, after: $after, updatedSince: $updatedSince, orderBy: {field: UPDATED_AT, direction: ASC}) {
+    pageInfo { hasNextPage endCursor }
+    nodes {
+      ghsaId
+      summary
+      description
+      permalink
+      publishedAt
+      updatedAt
+      withdrawnAt
+      identifiers { type value }
+      references { url }
+      cvssSeverities { cvssV3 { vectorString } cvssV4 { vectorString } }
+      vulnerabilities(first: $first) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          package { ecosystem name }
+          vulnerableVersionRange
+          firstPatchedVersion { identifier }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// The query of the further pages of vulnerabilities of an advisory, with more vulnerabilities than a page holds
+const VULNERABILITIES_QUERY: &str = r#"
+query($ghsaId: String!, $first: Int!, $after: String) {
+  securityAdvisory(ghsaId: $ghsaId) {
+    vulnerabilities(first: $first, after: $after) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        package { ecosystem name }
+        vulnerableVersionRange
+        firstPatchedVersion { identifier }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Sync GitHub security advisories", args_conflicts_with_subcommands = true)]
+pub struct Run {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The URL of the GraphQL API.
+    #[arg(long, default_value = DEFAULT_SOURCE)]
+    pub source: Url,
+
+    /// The GitHub token used for the API.
+    #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub token: String,
+
+    /// Only sync advisories updated since this time, in RFC 3339 format.
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<OffsetDateTime>,
+
+    /// The ecosystems of the affected packages to sync. Advisories not affecting any of them are skipped.
+    #[arg(long = "ecosystem", value_enum, default_values_t = [Ecosystem::Npm, Ecosystem::Maven, Ecosystem::Pip])]
+    pub ecosystems: Vec<Ecosystem>,
+
+    /// The number of CVEs updated concurrently.
+    #[arg(long, default_value_t = 16)]
+    pub concurrency: usize,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+/// The ecosystems of GitHub advisories, which can be converted into OSV records.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Ecosystem {
+    Npm,
+    Maven,
+    Pip,
+}
+
+impl Ecosystem {
+    /// The name of the ecosystem in OSV records
+    fn osv_name(&self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Maven => "Maven",
+            Self::Pip => "PyPI",
+        }
+    }
+
+    /// The package URL of a package of the ecosystem
+    fn purl(&self, name: &str) -> Option<String> {
+        let (ty, namespace, name) = match self {
+            Self::Npm => match name.split_once('/') {
+                Some((scope, name)) if scope.starts_with('@') => ("npm", Some(scope), name.to_string()),
+                _ => ("npm", None, name.to_string()),
+            },
+            Self::Maven => {
+                let (group, artifact) = name.split_once(':')?;
+                ("maven", Some(group), artifact.to_string())
+            }
+            // names of python packages are normalized in package URLs
+            Self::Pip => ("pypi", None, name.to_lowercase().replace('_', "-")),
+        };
+
+        let mut purl = PackageUrl::new(ty, name).ok()?;
+        if let Some(namespace) = namespace {
+            purl.with_namespace(namespace);
+        }
+        Some(purl.to_string())
+    }
+}
+
+fn parse_since(value: &str) -> Result<OffsetDateTime, time::error::Parse> {
+    OffsetDateTime::parse(value, &Rfc3339)
+}
+
+/// The numbers of advisories by the outcome of syncing them
+#[derive(Clone, Copy, Debug, Default)]
+struct Stats {
+    stored: usize,
+    merged: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+        Infrastructure::from(self.infra)
+            .run(
+                "v11y-ghsa",
+                |_context| async { Ok(()) },
+                status::recorded(&storage, Source::Ghsa, |storage| async move {
+                    let client = reqwest::Client::builder().user_agent("trustification-v11y").build()?;
+
+                    let since = self.since.map(|since| since.format(&Rfc3339)).transpose()?;
+                    log::info!(
+                        "Syncing advisories updated since {}",
+                        since.as_deref().unwrap_or("ever")
+                    );
+
+                    let mut stats = Stats::default();
+                    let mut aliases = BTreeMap::<String, Vec<String>>::new();
+                    // the CVEs currently aliased by each synced advisory, none for advisories not merged into CVEs
+                    let mut synced = BTreeMap::<String, Vec<String>>::new();
+                    let mut cursor = None;
+
+                    loop {
+                        let page =
+                            fetch(&client, &self.source, &self.token, since.as_deref(), cursor.as_deref()).await?;
+
+                        for advisory in page.nodes {
+                            synced.insert(advisory.ghsa_id.trim().to_uppercase(), vec![]);
+                            let Some(osv) = convert(&advisory, &self.ecosystems) else {
+                                log::debug!("Skipping {}, not affecting any synced ecosystem", advisory.ghsa_id);
+                                stats.skipped += 1;
+                                continue;
+                            };
+                            let Some(key) = osv.storage_key() else {
+                                stats.skipped += 1;
+                                continue;
+                            };
+
+                            // withdrawn advisories are stored as such, instead of being merged into their CVEs
+                            let cves = match osv.withdrawn {
+                                Some(_) => vec![],
+                                None => osv.cve_aliases(),
+                            };
+                            if !cves.is_empty() {
+                                log::debug!("Merging {key} into {cves:?}");
+                                for cve in &cves {
+                                    aliases.entry(cve.clone()).or_default().push(key.clone());
+                                }
+                                synced.insert(key, cves);
+                                stats.merged += 1;
+                                continue;
+                            }
+
+                            match storage.put_json_slice(&key, &serde_json::to_vec(&osv)?).await {
+                                Ok(_) => {
+                                    log::debug!("Stored {key}");
+                                    stats.stored += 1;
+                                }
+                                Err(err) => {
+                                    log::warn!("Failed to store {key}: {err}");
+                                    stats.failed += 1;
+                                }
+                            }
+                        }
+
+                        match page.page_info {
+                            PageInfo {
+                                has_next_page: true,
+                                end_cursor: Some(end_cursor),
+                            } => cursor = Some(end_cursor),
+                            _ => break,
+                        }
+                    }
+
+                    log::info!(
+                        "Processed {} advisories: {} stored, {} merged into CVEs, {} skipped, {} failed",
+                        stats.stored + stats.merged + stats.skipped + stats.failed,
+                        stats.stored,
+                        stats.merged,
+                        stats.skipped,
+                        stats.failed
+                    );
+
+                    let aliases: Vec<_> = aliases.into_iter().collect();
+                    // failing to label a CVE loses the advisory merged into it, so it counts as an error
+                    let labels = enrich::update_all(&storage, &aliases, self.concurrency, |ids, labels| {
+                        osv::add_aliases(labels, ids)
+                    })
+                    .await;
+                    // synced advisories are removed from the aliases of the CVEs they no longer alias
+                    let stale = enrich::update_labeled(&storage, self.concurrency, |cve, labels| {
+                        osv::retain_aliases(labels, |id| {
+                            synced
+                                .get(id)
+                                .map_or(true, |cves| cves.iter().any(|aliased| aliased == cve))
+                        })
+                    })
+                    .await?;
+                    Ok(Counts {
+                        documents: stats.stored as u64,
+                        errors: stats.failed as u64 + labels.errors + stale.errors,
+                    })
+                }),
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Fetch a page of advisories, along with all of their vulnerabilities.
+async fn fetch(
+    client: &reqwest::Client,
+    source: &Url,
+    token: &str,
+    since: Option<&str>,
+    after: Option<&str>,
+) -> anyhow::Result<Connection<Advisory>> {
+    let variables = Variables {
+        first: PAGE_SIZE,
+        after,
+        updated_since: since,
+    };
+    let mut page = query::<_, Data>(client, source, token, QUERY, &variables)
+        .await?
+        .security_advisories;
+
+    for advisory in &mut page.nodes {
+        fetch_vulnerabilities(client, source, token, advisory).await?;
+    }
+    Ok(page)
+}
+
+/// Fetch the further pages of vulnerabilities of an advisory, adding them to the advisory.
+async fn fetch_vulnerabilities(
+    client: &reqwest::Client,
+    source: &Url,
+    token: &str,
+    advisory: &mut Advisory,
+) -> anyhow::Result<()> {
+    while let PageInfo {
+        has_next_page: true,
+        end_cursor: Some(after),
+    } = &advisory.vulnerabilities.page_info
+    {
+        log::debug!("Fetching further vulnerabilities of {}", advisory.ghsa_id);
+        let variables = VulnerabilitiesVariables {
+            ghsa_id: &advisory.ghsa_id,
+            first: PAGE_SIZE,
+            after,
+        };
+        let page = query::<_, AdvisoryData>(client, source, token, VULNERABILITIES_QUERY, &variables)
+            .await?
+            .security_advisory
+            .ok_or_else(|| anyhow::anyhow!("Missing advisory {} in the response", advisory.ghsa_id))?
+            .vulnerabilities;
+
+        advisory.vulnerabilities.nodes.extend(page.nodes);
+        advisory.vulnerabilities.page_info = page.page_info;
+    }
+    Ok(())
+}
+
+/// Run a query of the GraphQL API, failing on any error of the response.
+async fn query<V: Serialize, D: DeserializeOwned>(
+    client: &reqwest::Client,
+    source: &Url,
+    token: &str,
+    query: &str,
+    variables: &V,
+) -> anyhow::Result<D> {
+    let request = GraphQlRequest { query, variables };
+
+    let response: GraphQlResponse<D> = client
+        .post(source.clone())
+        .bearer_auth(token)
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if let Some(error) = response.errors.first() {
+        anyhow::bail!("Failed to query the advisories: {}", error.message);
+    }
+    response
+        .data
+        .ok_or_else(|| anyhow::anyhow!("Missing advisories in the response"))
+}
+
+#[derive(Debug, Serialize)]
+struct GraphQlRequest<'a, V> {
+    query: &'a str,
+    variables: &'a V,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Variables<'a> {
+    first: usize,
+    after: Option<&'a str>,
+    updated_since: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VulnerabilitiesVariables<'a> {
+    ghsa_id: &'a str,
+    first: usize,
+    after: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<D> {
+    data: Option<D>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Data {
+    security_advisories: Connection<Advisory>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AdvisoryData {
+    security_advisory: Option<AdvisoryVulnerabilities>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryVulnerabilities {
+    vulnerabilities: Connection<Vulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    #[serde(default)]
+    page_info: PageInfo,
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Advisory {
+    ghsa_id: String,
+    summary: Option<String>,
+    description: Option<String>,
+    permalink: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    published_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    updated_at: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    withdrawn_at: Option<OffsetDateTime>,
+    #[serde(default)]
+    identifiers: Vec<Identifier>,
+    #[serde(default)]
+    references: Vec<AdvisoryReference>,
+    #[serde(default)]
+    cvss_severities: CvssSeverities,
+    vulnerabilities: Connection<Vulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifier {
+    /// Either `GHSA` or `CVE`
+    r#type: String,
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryReference {
+    url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CvssSeverities {
+    cvss_v3: Option<Cvss>,
+    cvss_v4: Option<Cvss>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cvss {
+    vector_string: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vulnerability {
+    package: VulnerablePackage,
+    vulnerable_version_range: String,
+    first_patched_version: Option<PatchedVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VulnerablePackage {
+    /// The GraphQL name of the ecosystem, like `NPM`, which may be one not supported for the conversion
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchedVersion {
+    identifier: String,
+}
+
+/// Convert an advisory into an OSV record, keeping only the packages of the given ecosystems.
+///
+/// Returns `None` if the advisory doesn't affect any package of the ecosystems.
+fn convert(advisory: &Advisory, ecosystems: &[Ecosystem]) -> Option<Osv> {
+    let affected: Vec<_> = advisory
+        .vulnerabilities
+        .nodes
+        .iter()
+        .filter_map(|vulnerability| {
+            let ecosystem = ecosystems
+                .iter()
+                .find(|ecosystem| Some(**ecosystem) == parse_ecosystem(&vulnerability.package.ecosystem))?;
+            convert_vulnerability(*ecosystem, vulnerability)
+        })
+        .collect();
+    if affected.is_empty() {
+        return None;
+    }
+
+    let aliases = advisory
+        .identifiers
+        .iter()
+        .filter(|identifier| identifier.r#type != "GHSA")
+        .map(|identifier| identifier.value.trim().to_uppercase())
+        .collect();
+
+    let severity = [
+        ("CVSS_V4", &advisory.cvss_severities.cvss_v4),
+        ("CVSS_V3", &advisory.cvss_severities.cvss_v3),
+    ]
+    .into_iter()
+    .filter_map(|(r#type, cvss)| {
+        let score = cvss.as_ref()?.vector_string.clone()?;
+        Some(Severity {
+            r#type: r#type.to_string(),
+            score,
+        })
+    })
+    .collect();
+
+    let references = advisory
+        .permalink
+        .iter()
+        .map(|url| Reference {
+            r#type: "ADVISORY".to_string(),
+            url: url.clone(),
+        })
+        .chain(advisory.references.iter().map(|reference| Reference {
+            r#type: "WEB".to_string(),
+            url: reference.url.clone(),
+        }))
+        .collect();
+
+    Some(Osv {
+        schema_version: None,
+        id: advisory.ghsa_id.clone(),
+        modified: advisory.updated_at,
+        published: advisory.published_at,
+        withdrawn: advisory.withdrawn_at,
+        aliases,
+        related: vec![],
+        summary: advisory.summary.clone(),
+        details: advisory.description.clone(),
+        severity,
+        affected,
+        references,
+    })
+}
+
+fn parse_ecosystem(value: &str) -> Option<Ecosystem> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+}
+
+/// Convert a vulnerable package, and its version range like `>= 1.0.0, < 1.2.3`, into an affected package.
+///
+/// A lower bound excluding a version, like `> 1.0.0`, is converted into an interval introduced by that version, as
+/// OSV ranges can't express it.
+fn convert_vulnerability(ecosystem: Ecosystem, vulnerability: &Vulnerability) -> Option<Affected> {
+    let name = vulnerability.package.name.trim();
+    let package = Package {
+        ecosystem: ecosystem.osv_name().to_string(),
+        name: name.to_string(),
+        purl: ecosystem.purl(name),
+    };
+
+    let mut introduced = None;
+    let mut end = None;
+    let mut versions = vec![];
+
+    for constraint in vulnerability.vulnerable_version_range.split(',') {
+        let constraint = constraint.trim();
+        if let Some(version) = constraint.strip_prefix(">=").or_else(|| constraint.strip_prefix('>')) {
+            introduced = Some(version.trim().to_string());
+        } else if let Some(version) = constraint.strip_prefix("<=") {
+            end = Some(Event::LastAffected(version.trim().to_string()));
+        } else if let Some(version) = constraint.strip_prefix('<') {
+            end = Some(Event::Fixed(version.trim().to_string()));
+        } else if let Some(version) = constraint.strip_prefix('=') {
+            versions.push(version.trim().to_string());
+        } else if !constraint.is_empty() {
+            log::warn!(
+                "Skipping {name}, with an invalid version range: {}",
+                vulnerability.vulnerable_version_range
+            );
+            return None;
+        }
+    }
+
+    let ranges = if versions.is_empty() {
+        let end = end.or_else(|| {
+            vulnerability
+                .first_patched_version
+                .as_ref()
+                .map(|version| Event::Fixed(version.identifier.clone()))
+        });
+        vec![Range {
+            r#type: RangeType::Ecosystem,
+            repo: None,
+            events: [Event::Introduced(introduced.unwrap_or_else(|| "0".to_string()))]
+                .into_iter()
+                .chain(end)
+                .collect(),
+        }]
+    } else {
+        vec![]
+    };
+
+    Some(Affected {
+        package: Some(package),
+        severity: vec![],
+        ranges,
+        versions,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ADVISORY: &str = r#"{
+        "ghsaId": "GHSA-qmf9-6jqf-j8fq",
+        "summary": "Django Denial-of-service in django.contrib.auth.forms.UsernameField",
+        "description": "An issue was discovered in Django 3.2 before 3.2.23, 4.1 before 4.1.13, and 4.2 before 4.2.7.",
+        "permalink": "https://github.com/advisories/GHSA-qmf9-6jqf-j8fq",
+        "publishedAt": "2023-11-02T06:30:30Z",
+        "updatedAt": "2023-11-09T19:51:44Z",
+        "withdrawnAt": null,
+        "identifiers": [
+            {"type": "GHSA", "value": "GHSA-qmf9-6jqf-j8fq"},
+            {"type": "CVE", "value": "CVE-2023-46695"}
+        ],
+        "references": [{"url": "https://nvd.nist.gov/vuln/detail/CVE-2023-46695"}],
+        "cvssSeverities": {
+            "cvssV3": {"vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"},
+            "cvssV4": {"vectorString": null}
+        },
+        "vulnerabilities": {
+            "pageInfo": {"hasNextPage": false, "endCursor": "Y3Vyc29yOnYyOpK5MjAyMy0xMS0wOVQxOTo1MTo0NCswMDowMM4AAxB2"},
+            "nodes": [
+                {
+                    "package": {"ecosystem": "PIP", "name": "Django"},
+                    "vulnerableVersionRange": ">= 4.2, < 4.2.7",
+                    "firstPatchedVersion": {"identifier": "4.2.7"}
+                },
+                {
+                    "package": {"ecosystem": "PIP", "name": "Django"},
+                    "vulnerableVersionRange": "< 3.2.23",
+                    "firstPatchedVersion": {"identifier": "3.2.23"}
+                },
+                {
+                    "package": {"ecosystem": "RUST", "name": "django"},
+                    "vulnerableVersionRange": "<= 1.0.0",
+                    "firstPatchedVersion": null
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_convert() {
+        let advisory: Advisory = serde_json::from_str(ADVISORY).unwrap();
+        assert!(!advisory.vulnerabilities.page_info.has_next_page);
+        let osv = convert(&advisory, &[Ecosystem::Npm, Ecosystem::Maven, Ecosystem::Pip]).unwrap();
+
+        assert_eq!(osv.storage_key().as_deref(), Some("GHSA-QMF9-6JQF-J8FQ"));
+        assert_eq!(osv.aliases, vec!["CVE-2023-46695"]);
+        assert_eq!(osv.severity.len(), 1);
+        assert_eq!(osv.severity[0].r#type, "CVSS_V3");
+        assert_eq!(osv.references.len(), 2);
+
+        // packages of other ecosystems are left out
+        assert_eq!(osv.affected.len(), 2);
+        assert_eq!(
+            osv.affected[0]
+                .package
+                .as_ref()
+                .and_then(|package| package.purl.as_deref()),
+            Some("pkg:pypi/django")
+        );
+        assert_eq!(osv.affected[0].describe().as_deref(), Some("PyPI/Django >=4.2, <4.2.7"));
+        assert_eq!(osv.affected[1].describe().as_deref(), Some("PyPI/Django <3.2.23"));
+
+        // advisories not affecting any of the ecosystems are skipped
+        assert_eq!(convert(&advisory, &[Ecosystem::Npm]), None);
+    }
+
+    #[test]
+    fn test_convert_ranges() {
+        let vulnerability = |range: &str, patched: Option<&str>| Vulnerability {
+            package: VulnerablePackage {
+                ecosystem: "MAVEN".to_string(),
+                name: "org.apache.logging.log4j:log4j-core".to_string(),
+            },
+            vulnerable_version_range: range.to_string(),
+            first_patched_version: patched.map(|identifier| PatchedVersion {
+                identifier: identifier.to_string(),
+            }),
+        };
+        let describe = |range: &str, patched: Option<&str>| {
+            convert_vulnerability(Ecosystem::Maven, &vulnerability(range, patched)).and_then(|affected| {
+                assert_eq!(
+                    affected.package.as_ref().and_then(|package| package.purl.as_deref()),
+                    Some("pkg:maven/org.apache.logging.log4j/log4j-core")
+                );
+                affected.describe()
+            })
+        };
+
+        let name = "Maven/org.apache.logging.log4j:log4j-core";
+        assert_eq!(describe("<= 2.14.1", None), Some(format!("{name} <=2.14.1")));
+        assert_eq!(
+            describe(">= 2.0-beta9, <= 2.14.1", Some("2.15.0")),
+            Some(format!("{name} >=2.0-beta9, <=2.14.1"))
+        );
+        assert_eq!(
+            describe(">= 2.13.0", Some("2.15.0")),
+            Some(format!("{name} >=2.13.0, <2.15.0"))
+        );
+        assert_eq!(describe("= 2.15.0", None), Some(format!("{name} =2.15.0")));
+        assert_eq!(describe("~> 2.15.0", None), None);
+    }
+
+    #[test]
+    fn test_purl() {
+        assert_eq!(Ecosystem::Npm.purl("lodash").as_deref(), Some("pkg:npm/lodash"));
+        assert_eq!(
+            Ecosystem::Pip.purl("Typing_Extensions").as_deref(),
+            Some("pkg:pypi/typing-extensions")
+        );
+        assert_eq!(Ecosystem::Maven.purl("log4j"), None);
+    }
+}
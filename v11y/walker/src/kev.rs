@@ -0,0 +1,161 @@
+//! Import of the CISA Known Exploited Vulnerabilities (KEV) catalog.
+//!
+//! Catalog entries are stored as labels of the stored CVE documents, see [`crate::enrich`]. The labels of CVEs which
+//! got removed from the catalog are removed, by reconciling all labeled CVEs with the full catalog on each sync.
+
+use crate::enrich;
+use crate::status::{self, Counts};
+use prometheus::Registry;
+use serde::Deserialize;
+use std::process::ExitCode;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use url::Url;
+use v11y_model::kev::KnownExploited;
+use v11y_model::status::Source;
+
+/// The location of the current catalog
+const DEFAULT_SOURCE: &str = "https://www.cisa.gov/sites/default/files/feeds/known_exploited_vulnerabilities.json";
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Import the known exploited vulnerabilities catalog",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Run {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The URL of the catalog, in its JSON format. Local files can be used with a `file:` URL.
+    #[arg(long, default_value = DEFAULT_SOURCE)]
+    pub source: Url,
+
+    /// The number of CVEs updated concurrently.
+    #[arg(long, default_value_t = 16)]
+    pub concurrency: usize,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+        Infrastructure::from(self.infra)
+            .run(
+                "v11y-kev",
+                |_context| async { Ok(()) },
+                status::recorded(&storage, Source::Kev, |storage| async move {
+                    log::info!("Fetching the KEV catalog from {}", self.source);
+                    let data = enrich::fetch(&self.source).await?;
+                    let entries = parse(&data)?;
+                    log::info!("Processing {} known exploited vulnerabilities", entries.len());
+
+                    let mut counts =
+                        enrich::update_all(&storage, &entries, self.concurrency, |kev, labels| kev.apply(labels)).await;
+                    counts +=
+                        enrich::remove_stale(&storage, &entries, self.concurrency, KnownExploited::remove).await?;
+                    Ok(counts)
+                }),
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Catalog {
+    #[serde(default)]
+    catalog_version: Option<String>,
+    vulnerabilities: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(rename = "cveID")]
+    cve_id: String,
+    date_added: String,
+    #[serde(default)]
+    due_date: Option<String>,
+    #[serde(default)]
+    required_action: Option<String>,
+    /// Either `Known` or `Unknown`
+    #[serde(default)]
+    known_ransomware_campaign_use: Option<String>,
+}
+
+/// Parse the JSON catalog, into the entries of the CVEs.
+fn parse(data: &[u8]) -> anyhow::Result<Vec<(String, KnownExploited)>> {
+    let catalog: Catalog = serde_json::from_slice(data)?;
+    if let Some(version) = &catalog.catalog_version {
+        log::info!("Catalog version: {version}");
+    }
+
+    Ok(catalog
+        .vulnerabilities
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.cve_id.trim().to_uppercase(),
+                KnownExploited {
+                    date_added: entry.date_added,
+                    due_date: entry.due_date.filter(|date| !date.is_empty()),
+                    required_action: entry.required_action.filter(|action| !action.is_empty()),
+                    ransomware: entry
+                        .known_ransomware_campaign_use
+                        .is_some_and(|usage| usage.eq_ignore_ascii_case("known")),
+                },
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(
+            br#"{
+                "title": "CISA Catalog of Known Exploited Vulnerabilities",
+                "catalogVersion": "2023.10.13",
+                "dateReleased": "2023-10-13T18:12:57.4946Z",
+                "count": 1,
+                "vulnerabilities": [
+                    {
+                        "cveID": "CVE-2023-44487",
+                        "vendorProject": "IETF",
+                        "product": "HTTP/2",
+                        "vulnerabilityName": "HTTP/2 Rapid Reset Attack Vulnerability",
+                        "dateAdded": "2023-10-10",
+                        "shortDescription": "HTTP/2 contains a rapid reset vulnerability.",
+                        "requiredAction": "Apply mitigations per vendor instructions.",
+                        "dueDate": "2023-10-31",
+                        "knownRansomwareCampaignUse": "Unknown",
+                        "notes": ""
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries,
+            vec![(
+                "CVE-2023-44487".to_string(),
+                KnownExploited {
+                    date_added: "2023-10-10".to_string(),
+                    due_date: Some("2023-10-31".to_string()),
+                    required_action: Some("Apply mitigations per vendor instructions.".to_string()),
+                    ransomware: false,
+                }
+            )]
+        );
+    }
+}
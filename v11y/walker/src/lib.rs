@@ -1,3 +1,4 @@
+use crate::status::Counts;
 use prometheus::Registry;
 use std::collections::HashSet;
 use std::ffi::OsStr;
@@ -5,9 +6,17 @@ use std::path::PathBuf;
 use std::process::ExitCode;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
 use trustification_storage::{Storage, StorageConfig};
+use v11y_model::status::Source;
 use walkdir::WalkDir;
 
 mod delta;
+mod enrich;
+pub mod epss;
+pub mod ghsa;
+pub mod kev;
+pub mod nvd;
+pub mod osv;
+mod status;
 
 #[derive(clap::Args, Debug)]
 #[command(about = "Run the walker", args_conflicts_with_subcommands = true)]
@@ -38,13 +47,12 @@ pub struct Run {
 
 impl Run {
     pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
         Infrastructure::from(self.infra)
             .run(
                 "v11y-walker",
                 |_context| async { Ok(()) },
-                |_context| async move {
-                    let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
-
+                status::recorded(&storage, Source::Cve, |storage| async move {
                     let mut files = vec![];
                     let mut filter = HashSet::new();
 
@@ -142,8 +150,11 @@ impl Run {
                     if let Some(last_delta_file) = &self.delta_file {
                         std::fs::copy(delta_file, last_delta_file)?;
                     }
-                    Ok(())
-                },
+                    Ok(Counts {
+                        documents: files.len() as u64,
+                        errors: 0,
+                    })
+                }),
             )
             .await?;
         Ok(ExitCode::SUCCESS)
@@ -0,0 +1,552 @@
+//! Incremental sync of the analysis of CVEs by the NVD, using the CVE API 2.0 of the NVD.
+//!
+//! Only CVEs modified since the last sync are requested, using the range of their last modification. The end of the
+//! synced range is stored as cursor of the next sync, next to the status of the sources. The analysis is stored as labels of the stored CVE documents,
+//! see [`crate::enrich`], so CVEs which are not stored yet are skipped.
+
+use crate::enrich;
+use crate::status::{self, Counts};
+use prometheus::Registry;
+use serde::{Deserialize, Serialize};
+use std::process::ExitCode;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::time::{sleep, Instant};
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use url::Url;
+use v11y_model::cpe::CpeMatch;
+use v11y_model::nvd::NvdAnalysis;
+use v11y_model::status::Source;
+
+/// The CVE API of the NVD
+const DEFAULT_SOURCE: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+
+/// The name the cursor is stored with, among the statuses of the storage
+const CURSOR: &str = "nvd-cursor";
+
+/// The maximum number of CVEs of a page, as allowed by the API
+const PAGE_SIZE: usize = 2_000;
+/// The maximum range of last modification dates of a single request, as allowed by the API
+const MAX_RANGE: time::Duration = time::Duration::days(120);
+
+/// The delay between requests without API key, as the API allows 5 requests in 30 seconds
+const PUBLIC_DELAY: Duration = Duration::from_secs(6);
+/// The delay between requests with an API key, as the API allows 50 requests in 30 seconds
+const API_KEY_DELAY: Duration = Duration::from_millis(600);
+/// The number of attempts of a request being rate limited, or failing temporarily
+const MAX_ATTEMPTS: u32 = 5;
+/// The time to wait after a request was rejected, multiplied by the attempt
+const BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(clap::Args, Debug)]
+#[command(
+    about = "Sync the analysis of CVEs by the NVD",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Run {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// The URL of the CVE API.
+    #[arg(long, default_value = DEFAULT_SOURCE)]
+    pub source: Url,
+
+    /// The API key of the NVD, raising the rate limit of the API.
+    #[arg(long, env = "NVD_API_KEY", hide_env_values = true)]
+    pub api_key: Option<String>,
+
+    /// Sync CVEs modified since this time, in RFC 3339 format, instead of since the stored cursor. Without both, the
+    /// CVEs modified in the last 120 days are synced.
+    #[arg(long, value_parser = parse_since)]
+    pub since: Option<OffsetDateTime>,
+
+    /// The number of CVEs updated concurrently.
+    #[arg(long, default_value_t = 16)]
+    pub concurrency: usize,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+fn parse_since(value: &str) -> Result<OffsetDateTime, time::error::Parse> {
+    OffsetDateTime::parse(value, &Rfc3339)
+}
+
+/// The persisted state of the sync.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cursor {
+    /// CVEs modified up to this time were synced
+    #[serde(with = "time::serde::rfc3339")]
+    last_modified: OffsetDateTime,
+}
+
+impl Cursor {
+    async fn read(storage: &Storage) -> anyhow::Result<Option<Self>> {
+        Ok(storage
+            .get_status(CURSOR)
+            .await?
+            .map(serde_json::from_value)
+            .transpose()?)
+    }
+
+    async fn write(&self, storage: &Storage) -> anyhow::Result<()> {
+        storage.put_status(CURSOR, &serde_json::to_value(self)?).await?;
+        Ok(())
+    }
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+        Infrastructure::from(self.infra)
+            .run(
+                "v11y-nvd",
+                |_context| async { Ok(()) },
+                status::recorded(&storage, Source::Nvd, |storage| async move {
+                    let end = OffsetDateTime::now_utc();
+                    let cursor = Cursor::read(&storage).await?;
+                    let start = self
+                        .since
+                        .or(cursor.map(|cursor| cursor.last_modified))
+                        .unwrap_or(end - MAX_RANGE);
+                    log::info!("Syncing CVEs modified from {start} to {end}");
+
+                    let mut client = Client::new(self.source, self.api_key)?;
+                    let mut counts = Counts::default();
+                    for (from, to) in ranges(start, end) {
+                        let mut entries = vec![];
+                        let mut index = 0;
+                        loop {
+                            let page = client.fetch(from, to, index).await?;
+                            let received = page.vulnerabilities.len();
+                            entries.extend(
+                                page.vulnerabilities
+                                    .into_iter()
+                                    .map(|vulnerability| vulnerability.cve.analysis()),
+                            );
+                            index += received;
+                            if received == 0 || index >= page.total_results {
+                                break;
+                            }
+                        }
+
+                        log::info!("Processing {} CVEs modified from {from} to {to}", entries.len());
+                        counts += enrich::update_all(&storage, &entries, self.concurrency, |analysis, labels| {
+                            analysis.apply(labels)
+                        })
+                        .await;
+                    }
+
+                    match counts.errors {
+                        0 => Cursor { last_modified: end }.write(&storage).await?,
+                        failed => log::warn!("Keeping the cursor, as {failed} CVEs failed to be updated"),
+                    }
+                    Ok(counts)
+                }),
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Split the time from start to end into ranges the API accepts.
+fn ranges(start: OffsetDateTime, end: OffsetDateTime) -> Vec<(OffsetDateTime, OffsetDateTime)> {
+    let mut ranges = vec![];
+    let mut from = start;
+    while from < end {
+        let to = (from + MAX_RANGE).min(end);
+        ranges.push((from, to));
+        from = to;
+    }
+    ranges
+}
+
+/// Format a point in time as expected by the API, which doesn't accept all RFC 3339 timestamps.
+fn format_date(date: OffsetDateTime) -> String {
+    let date = date.to_offset(time::UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}+00:00",
+        date.year(),
+        date.month() as u8,
+        date.day(),
+        date.hour(),
+        date.minute(),
+        date.second(),
+        date.millisecond()
+    )
+}
+
+/// A client of the API, respecting its rate limit.
+struct Client {
+    client: reqwest::Client,
+    source: Url,
+    api_key: Option<String>,
+    delay: Duration,
+    last_request: Option<Instant>,
+}
+
+impl Client {
+    fn new(source: Url, api_key: Option<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().user_agent("trustification-v11y").build()?,
+            source,
+            delay: match api_key {
+                Some(_) => API_KEY_DELAY,
+                None => PUBLIC_DELAY,
+            },
+            api_key,
+            last_request: None,
+        })
+    }
+
+    /// Fetch a page of the CVEs modified in a range, retrying requests which got rate limited.
+    async fn fetch(&mut self, from: OffsetDateTime, to: OffsetDateTime, index: usize) -> anyhow::Result<Page> {
+        let mut attempt = 1;
+        loop {
+            if let Some(last_request) = self.last_request {
+                sleep(self.delay.saturating_sub(last_request.elapsed())).await;
+            }
+            self.last_request = Some(Instant::now());
+
+            let mut request = self
+                .client
+                .get(self.source.clone())
+                .query(&[
+                    ("lastModStartDate", format_date(from)),
+                    ("lastModEndDate", format_date(to)),
+                ])
+                .query(&[("startIndex", index), ("resultsPerPage", PAGE_SIZE)]);
+            if let Some(api_key) = &self.api_key {
+                request = request.header("apiKey", api_key);
+            }
+
+            let response = request.send().await?;
+            let status = response.status().as_u16();
+            // the API rejects requests exceeding the rate limit with a 403
+            if matches!(status, 403 | 429 | 503) && attempt < MAX_ATTEMPTS {
+                log::warn!("Request got rejected with status {status} (attempt {attempt}/{MAX_ATTEMPTS}), backing off");
+                sleep(BACKOFF * attempt).await;
+                attempt += 1;
+                continue;
+            }
+
+            return Ok(response.error_for_status()?.json().await?);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    total_results: usize,
+    #[serde(default)]
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vulnerability {
+    cve: NvdCve,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdCve {
+    id: String,
+    vuln_status: String,
+    #[serde(default)]
+    metrics: Metrics,
+    #[serde(default)]
+    configurations: Vec<Configuration>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Configuration {
+    #[serde(default)]
+    operator: Operator,
+    #[serde(default)]
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Node {
+    #[serde(default)]
+    operator: Operator,
+    #[serde(default)]
+    negate: bool,
+    #[serde(default)]
+    cpe_match: Vec<NvdCpeMatch>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum Operator {
+    And,
+    #[default]
+    Or,
+}
+
+impl Node {
+    /// The groups of expressions of the node, of which one expression each must match.
+    fn groups(&self) -> Vec<Vec<CpeMatch>> {
+        let expressions = self.cpe_match.iter().map(|cpe_match| cpe_match.expression.clone());
+        match self.operator {
+            Operator::And => expressions.map(|expression| vec![expression]).collect(),
+            Operator::Or => vec![expressions.collect()],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdCpeMatch {
+    vulnerable: bool,
+    #[serde(flatten)]
+    expression: CpeMatch,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Metrics {
+    #[serde(default)]
+    cvss_metric_v31: Vec<CvssMetric>,
+    #[serde(default)]
+    cvss_metric_v30: Vec<CvssMetric>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CvssMetric {
+    /// Either `Primary`, the assessment of the NVD, or `Secondary`, the one of the CNA
+    #[serde(rename = "type")]
+    ty: String,
+    cvss_data: CvssData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CvssData {
+    vector_string: String,
+}
+
+impl NvdCve {
+    fn analysis(self) -> (String, NvdAnalysis) {
+        let cvss3 = self
+            .metrics
+            .cvss_metric_v31
+            .iter()
+            .chain(&self.metrics.cvss_metric_v30)
+            .find(|metric| metric.ty == "Primary")
+            .map(|metric| metric.cvss_data.vector_string.clone());
+        // the platforms of vulnerable products, like the operating system they run on, are not vulnerable themselves,
+        // but are required by the expressions of configurations combining them with AND
+        let mut cpe_matches: Vec<CpeMatch> = vec![];
+        for configuration in &self.configurations {
+            // negated nodes, like "not running on", can't be evaluated and are skipped
+            let nodes: Vec<&Node> = configuration.nodes.iter().filter(|node| !node.negate).collect();
+            for (n, node) in nodes.iter().enumerate() {
+                for (m, cpe_match) in node.cpe_match.iter().enumerate() {
+                    if !cpe_match.vulnerable {
+                        continue;
+                    }
+                    let mut expression = cpe_match.expression.clone();
+                    if node.operator == Operator::And {
+                        let others = node.cpe_match.iter().enumerate().filter(|(other, _)| *other != m);
+                        expression
+                            .platforms
+                            .extend(others.map(|(_, other)| vec![other.expression.clone()]));
+                    }
+                    if configuration.operator == Operator::And {
+                        let others = nodes.iter().enumerate().filter(|(other, _)| *other != n);
+                        expression
+                            .platforms
+                            .extend(others.flat_map(|(_, other)| other.groups()));
+                    }
+                    if !cpe_matches.contains(&expression) {
+                        cpe_matches.push(expression);
+                    }
+                }
+            }
+        }
+        (
+            self.id.trim().to_uppercase(),
+            NvdAnalysis {
+                status: self.vuln_status,
+                cvss3,
+                cpe_matches,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let page: Page = serde_json::from_str(
+            r#"{
+                "resultsPerPage": 2,
+                "startIndex": 0,
+                "totalResults": 2,
+                "format": "NVD_CVE",
+                "version": "2.0",
+                "timestamp": "2023-11-21T09:55:03.123",
+                "vulnerabilities": [
+                    {
+                        "cve": {
+                            "id": "CVE-2023-44487",
+                            "sourceIdentifier": "cve@mitre.org",
+                            "published": "2023-10-10T14:15:10.883",
+                            "lastModified": "2023-11-07T04:21:03.403",
+                            "vulnStatus": "Modified",
+                            "metrics": {
+                                "cvssMetricV31": [
+                                    {
+                                        "source": "nvd@nist.gov",
+                                        "type": "Primary",
+                                        "cvssData": {
+                                            "version": "3.1",
+                                            "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H",
+                                            "baseScore": 7.5,
+                                            "baseSeverity": "HIGH"
+                                        }
+                                    }
+                                ]
+                            },
+                            "configurations": [
+                                {
+                                    "operator": "AND",
+                                    "nodes": [
+                                        {
+                                            "operator": "OR",
+                                            "negate": false,
+                                            "cpeMatch": [
+                                                {
+                                                    "vulnerable": true,
+                                                    "criteria": "cpe:2.3:a:golang:http2:*:*:*:*:*:go:*:*",
+                                                    "versionEndExcluding": "0.17.0",
+                                                    "matchCriteriaId": "E6892A80-1A9D-4B5A-8B32-6FC34FA2A99B"
+                                                }
+                                            ]
+                                        },
+                                        {
+                                            "operator": "OR",
+                                            "negate": false,
+                                            "cpeMatch": [
+                                                {
+                                                    "vulnerable": false,
+                                                    "criteria": "cpe:2.3:o:redhat:enterprise_linux:9.0:*:*:*:*:*:*:*",
+                                                    "matchCriteriaId": "1B2ADC9B-8C0E-4B49-AB1F-C3A9A9A3C1B5"
+                                                }
+                                            ]
+                                        }
+                                    ]
+                                },
+                                {
+                                    "nodes": [
+                                        {
+                                            "operator": "OR",
+                                            "negate": false,
+                                            "cpeMatch": [
+                                                {
+                                                    "vulnerable": true,
+                                                    "criteria": "cpe:2.3:a:nghttp2:nghttp2:*:*:*:*:*:*:*:*",
+                                                    "versionEndExcluding": "1.57.0"
+                                                }
+                                            ]
+                                        },
+                                        {
+                                            "operator": "OR",
+                                            "negate": true,
+                                            "cpeMatch": [
+                                                {
+                                                    "vulnerable": true,
+                                                    "criteria": "cpe:2.3:a:nghttp2:nghttp2:1.56.0:*:*:*:*:*:*:*"
+                                                }
+                                            ]
+                                        }
+                                    ]
+                                }
+                            ]
+                        }
+                    },
+                    {
+                        "cve": {
+                            "id": "CVE-2023-46695",
+                            "vulnStatus": "Awaiting Analysis",
+                            "metrics": {}
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(page.total_results, 2);
+        let entries: Vec<_> = page
+            .vulnerabilities
+            .into_iter()
+            .map(|vulnerability| vulnerability.cve.analysis())
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (
+                    "CVE-2023-44487".to_string(),
+                    NvdAnalysis {
+                        status: "Modified".to_string(),
+                        cvss3: Some("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H".to_string()),
+                        cpe_matches: vec![
+                            CpeMatch {
+                                criteria: "cpe:2.3:a:golang:http2:*:*:*:*:*:go:*:*".to_string(),
+                                version_end_excluding: Some("0.17.0".to_string()),
+                                platforms: vec![vec![CpeMatch {
+                                    criteria: "cpe:2.3:o:redhat:enterprise_linux:9.0:*:*:*:*:*:*:*".to_string(),
+                                    ..Default::default()
+                                }]],
+                                ..Default::default()
+                            },
+                            CpeMatch {
+                                criteria: "cpe:2.3:a:nghttp2:nghttp2:*:*:*:*:*:*:*:*".to_string(),
+                                version_end_excluding: Some("1.57.0".to_string()),
+                                ..Default::default()
+                            },
+                        ],
+                    }
+                ),
+                (
+                    "CVE-2023-46695".to_string(),
+                    NvdAnalysis {
+                        status: "Awaiting Analysis".to_string(),
+                        cvss3: None,
+                        cpe_matches: vec![],
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let start = OffsetDateTime::from_unix_timestamp(1690000000).unwrap();
+        assert!(ranges(start, start).is_empty());
+
+        let ranges = ranges(start, start + time::Duration::days(200));
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0], (start, start + MAX_RANGE));
+        assert_eq!(ranges[1], (start + MAX_RANGE, start + time::Duration::days(200)));
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = OffsetDateTime::from_unix_timestamp_nanos(1700560503_123_000_000).unwrap();
+        assert_eq!(format_date(date), "2023-11-21T09:55:03.123+00:00");
+    }
+}
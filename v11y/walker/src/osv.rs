@@ -0,0 +1,161 @@
+//! Import of vulnerability records in the OSV format, like the dumps of osv.dev.
+//!
+//! The records are stored next to the CVE records, using their uppercase id as key. Records identified by a CVE id
+//! are skipped, as CVEs are taken from the CVE list. Records aliasing a CVE are merged into the CVE instead, which gets
+//! their id as an alias label, see [`crate::enrich`]. So a vulnerability is only stored once, as its CVE if it has one.
+
+use crate::enrich;
+use crate::status::{self, Counts};
+use prometheus::Registry;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
+use trustification_storage::{Storage, StorageConfig};
+use v11y_model::osv::{self, Osv};
+use v11y_model::status::Source;
+use walkdir::WalkDir;
+
+#[derive(clap::Args, Debug)]
+#[command(about = "Import OSV records", args_conflicts_with_subcommands = true)]
+pub struct Run {
+    #[arg(long = "devmode", default_value_t = false)]
+    pub devmode: bool,
+
+    /// A directory of OSV records, a single record, or a zip archive of records like the `all.zip` dumps of osv.dev.
+    #[arg(long)]
+    pub source: PathBuf,
+
+    /// The number of CVEs updated concurrently with the aliases of the records.
+    #[arg(long, default_value_t = 16)]
+    pub concurrency: usize,
+
+    #[command(flatten)]
+    pub storage: StorageConfig,
+
+    #[command(flatten)]
+    pub infra: InfrastructureConfig,
+}
+
+/// The numbers of records by the outcome of importing them
+#[derive(Clone, Copy, Debug, Default)]
+struct Stats {
+    stored: usize,
+    merged: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+impl Run {
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
+        let storage = Storage::new(self.storage.process("v11y", self.devmode), &Registry::new())?;
+        Infrastructure::from(self.infra)
+            .run(
+                "v11y-osv",
+                |_context| async { Ok(()) },
+                status::recorded(&storage, Source::Osv, |storage| async move {
+                    let mut stats = Stats::default();
+                    let mut aliases = Aliases::new();
+
+                    if self.source.is_dir() {
+                        for entry in WalkDir::new(&self.source).follow_links(true) {
+                            let entry = entry?;
+                            if entry.file_type().is_file() && is_json(entry.path()) {
+                                let data = tokio::fs::read(entry.path()).await?;
+                                let name = entry.path().display().to_string();
+                                import(&storage, &name, &data, &mut stats, &mut aliases).await;
+                            }
+                        }
+                    } else if self.source.extension().and_then(OsStr::to_str) == Some("zip") {
+                        let mut archive = zip::ZipArchive::new(std::fs::File::open(&self.source)?)?;
+                        for i in 0..archive.len() {
+                            let (name, data) = {
+                                let mut file = archive.by_index(i)?;
+                                if !file.is_file() || !is_json(Path::new(file.name())) {
+                                    continue;
+                                }
+                                let mut data = Vec::with_capacity(file.size() as usize);
+                                file.read_to_end(&mut data)?;
+                                (file.name().to_string(), data)
+                            };
+                            import(&storage, &name, &data, &mut stats, &mut aliases).await;
+                        }
+                    } else {
+                        let data = tokio::fs::read(&self.source).await?;
+                        let name = self.source.display().to_string();
+                        import(&storage, &name, &data, &mut stats, &mut aliases).await;
+                    }
+
+                    log::info!(
+                        "Processed {} records: {} stored, {} merged into CVEs, {} skipped, {} failed",
+                        stats.stored + stats.merged + stats.skipped + stats.failed,
+                        stats.stored,
+                        stats.merged,
+                        stats.skipped,
+                        stats.failed
+                    );
+
+                    let aliases: Vec<_> = aliases.into_iter().collect();
+                    // failing to label a CVE loses the record, so it counts as an error
+                    let labels = enrich::update_all(&storage, &aliases, self.concurrency, |ids, labels| {
+                        osv::add_aliases(labels, ids)
+                    })
+                    .await;
+                    Ok(Counts {
+                        documents: stats.stored as u64,
+                        errors: stats.failed as u64 + labels.errors,
+                    })
+                }),
+            )
+            .await?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// The ids of the records aliasing a CVE, by the CVE
+type Aliases = BTreeMap<String, Vec<String>>;
+
+fn is_json(path: &Path) -> bool {
+    path.extension().and_then(OsStr::to_str) == Some("json")
+}
+
+/// Parse and store a single record, or record it as an alias of the CVEs it aliases.
+async fn import(storage: &Storage, name: &str, data: &[u8], stats: &mut Stats, aliases: &mut Aliases) {
+    let osv: Osv = match serde_json::from_slice(data) {
+        Ok(osv) => osv,
+        Err(err) => {
+            log::warn!("Failed to parse {name}: {err}");
+            stats.failed += 1;
+            return;
+        }
+    };
+
+    let Some(key) = osv.storage_key() else {
+        log::debug!("Skipping {}, identified by a CVE id", osv.id);
+        stats.skipped += 1;
+        return;
+    };
+
+    let cves = osv.cve_aliases();
+    if !cves.is_empty() {
+        log::debug!("Merging {key} into {cves:?}");
+        for cve in cves {
+            aliases.entry(cve).or_default().push(key.clone());
+        }
+        stats.merged += 1;
+        return;
+    }
+
+    match storage.put_json_slice(&key, data).await {
+        Ok(_) => {
+            log::debug!("Stored {key}");
+            stats.stored += 1;
+        }
+        Err(err) => {
+            log::warn!("Failed to store {key}: {err}");
+            stats.failed += 1;
+        }
+    }
+}
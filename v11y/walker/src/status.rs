@@ -0,0 +1,69 @@
+//! Recording of the status of the synced sources, reported by the API so that stale mirrors can be spotted.
+
+use futures::future::LocalBoxFuture;
+use std::future::Future;
+use std::ops::AddAssign;
+use time::OffsetDateTime;
+use trustification_infrastructure::MainContext;
+use trustification_storage::Storage;
+use v11y_model::status::{Source, SourceStatus};
+
+/// The numbers of documents by the outcome of a sync
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Counts {
+    /// The documents which were stored or updated
+    pub(crate) documents: u64,
+    /// The documents which failed to be stored or updated
+    pub(crate) errors: u64,
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.documents += other.documents;
+        self.errors += other.errors;
+    }
+}
+
+/// Wrap the sync of a source as the main function of the infrastructure, recording the outcome of the sync.
+pub(crate) fn recorded<'a, F, Fut>(
+    storage: &'a Storage,
+    source: Source,
+    sync: F,
+) -> impl FnOnce(MainContext<()>) -> LocalBoxFuture<'a, anyhow::Result<()>>
+where
+    F: FnOnce(&'a Storage) -> Fut + 'a,
+    Fut: Future<Output = anyhow::Result<Counts>> + 'a,
+{
+    move |_context| {
+        Box::pin(async move {
+            let outcome = sync(storage).await;
+            record(storage, source, &outcome).await;
+            outcome.map(|_| ())
+        })
+    }
+}
+
+/// Record the outcome of a sync of a source. Failing to record it doesn't fail the sync, so it only gets logged.
+async fn record(storage: &Storage, source: Source, outcome: &anyhow::Result<Counts>) {
+    if let Err(err) = try_record(storage, source, outcome).await {
+        log::warn!("Failed to record the status of {source}: {err}");
+    }
+}
+
+async fn try_record(storage: &Storage, source: Source, outcome: &anyhow::Result<Counts>) -> anyhow::Result<()> {
+    let mut status = match storage.get_status(source.name()).await? {
+        Some(status) => serde_json::from_value(status)?,
+        None => SourceStatus::new(source),
+    };
+    status.record(
+        OffsetDateTime::now_utc(),
+        match outcome {
+            Ok(counts) => Ok((counts.documents, counts.errors)),
+            Err(err) => Err(err.to_string()),
+        },
+    );
+    storage
+        .put_status(source.name(), &serde_json::to_value(&status)?)
+        .await?;
+    Ok(())
+}
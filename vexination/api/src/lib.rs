@@ -9,7 +9,7 @@ use trustification_auth::{
     authorizer::Authorizer,
     swagger_ui::{SwaggerUiOidc, SwaggerUiOidcConfig},
 };
-use trustification_index::{IndexConfig, IndexStore};
+use trustification_index::{require_unsharded, IndexConfig, IndexStore};
 use trustification_infrastructure::{
     app::http::{BinaryByteSize, HttpServerBuilder, HttpServerConfig},
     endpoint::Vexination,
@@ -110,8 +110,15 @@ impl Run {
         registry: &Registry,
         devmode: bool,
     ) -> anyhow::Result<Arc<AppState>> {
-        let index =
-            block_in_place(|| IndexStore::new(&storage, &index_config, vexination_index::Index::new(), registry))?;
+        require_unsharded(&index_config, "vexination")?;
+        let index = block_in_place(|| {
+            IndexStore::new(
+                &storage,
+                &index_config,
+                vexination_index::Index::with_tokenizers(&index_config.tokenizers),
+                registry,
+            )
+        })?;
         let storage = Storage::new(storage.process("vexination", devmode), registry)?;
 
         let state = Arc::new(AppState { storage, index });
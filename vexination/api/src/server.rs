@@ -7,7 +7,7 @@ use actix_web::{
 use derive_more::{Display, Error, From};
 use serde::Deserialize;
 use std::sync::Arc;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{HistogramBucket, HistogramParams, SearchField, SearchOptions};
 use trustification_auth::{
     authenticator::{user::UserInformation, Authenticator},
     authorizer::Authorizer,
@@ -18,14 +18,34 @@ use trustification_index::Error as IndexError;
 use trustification_infrastructure::new_auth;
 use trustification_storage::{Error as StorageError, S3Path, Storage};
 use utoipa::OpenApi;
+use vexination_index::document::VexDocument;
 use vexination_model::prelude::*;
 
 use crate::SharedState;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(fetch_vex, publish_vex, search_vex),
-    components(schemas(SearchDocument, SearchResult),)
+    paths(
+        fetch_vex,
+        fetch_vex_revisions,
+        fetch_vex_revision,
+        publish_vex,
+        validate_vex,
+        search_vex,
+        search_vex_fields,
+        vex_histogram
+    ),
+    components(schemas(
+        SearchDocument,
+        SearchResult,
+        AdvisoryValidation,
+        ValidationFinding,
+        FindingSeverity,
+        trustification_api::search::FacetCount,
+        trustification_api::search::HistogramBucket,
+        trustification_api::search::SearchField,
+        trustification_api::search::SearchFieldType
+    ),)
 )]
 pub struct ApiDoc;
 
@@ -39,13 +59,22 @@ pub fn config(
         web::scope("/api/v1")
             .wrap(new_auth!(auth))
             .service(fetch_vex)
+            .service(fetch_vex_revisions)
+            .service(fetch_vex_revision)
             .service(
                 web::resource("/vex")
                     .app_data(web::PayloadConfig::new(publish_limit))
                     .guard(guard::Any(guard::Method(Method::PUT)).or(guard::Method(Method::POST)))
                     .to(publish_vex),
             )
+            .service(
+                web::resource("/vex/validate")
+                    .app_data(web::PayloadConfig::new(publish_limit))
+                    .route(web::post().to(validate_vex)),
+            )
             .service(search_vex)
+            .service(search_vex_fields)
+            .service(vex_histogram)
             .service(delete_vex)
             .service(delete_vexes),
     )
@@ -84,6 +113,15 @@ impl actix_web::error::ResponseError for Error {
         match self {
             Self::Storage(StorageError::NotFound) => StatusCode::NOT_FOUND,
             Self::Index(IndexError::QueryParser(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotFacetable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotHistogrammable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidInterval(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NoStatistics(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotDeduplicable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::NotSortable(_)) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::InvalidCursor) => StatusCode::BAD_REQUEST,
+            Self::Index(IndexError::CursorExpired) => StatusCode::GONE,
+            Self::Index(IndexError::Timeout(_)) => StatusCode::GATEWAY_TIMEOUT,
             e => {
                 log::error!("{e:?}");
                 StatusCode::INTERNAL_SERVER_ERROR
@@ -125,6 +163,96 @@ async fn fetch_vex(
     Ok(fetch_object(&state.storage, &params.advisory).await)
 }
 
+/// List the stored revisions of a VEX, ordered from the oldest to the newest.
+///
+/// Revisions are named after the version of the document, and get stored whenever a document is published.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/revisions",
+    responses(
+        (status = 200, description = "Revisions of the VEX", body = Vec<String>),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+    )
+)]
+#[get("/vex/revisions")]
+async fn fetch_vex_revisions(
+    state: web::Data<SharedState>,
+    params: web::Query<QueryParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let mut revisions = state
+        .storage
+        .list_revisions(&params.advisory)
+        .await
+        .map_err(Error::Storage)?;
+    revisions.sort_by(|a, b| compare_versions(a, b));
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+/// Parameters passed when fetching a revision of an advisory.
+#[derive(Debug, Deserialize)]
+struct RevisionParams {
+    /// Identifier of the advisory to get
+    advisory: String,
+    /// Name of the revision
+    rev: String,
+}
+
+/// Retrieve a stored revision of a VEX.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/revision",
+    responses(
+        (status = 200, description = "VEX revision found"),
+        (status = NOT_FOUND, description = "VEX revision not found in archive"),
+    ),
+    params(
+        ("advisory" = String, Query, description = "Identifier of the VEX"),
+        ("rev" = String, Query, description = "Name of the revision"),
+    )
+)]
+#[get("/vex/revision")]
+async fn fetch_vex_revision(
+    state: web::Data<SharedState>,
+    params: web::Query<RevisionParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let data = state
+        .storage
+        .get_revision(&params.advisory, &revision_name(&params.rev))
+        .await
+        .map_err(Error::Storage)?;
+
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).body(data))
+}
+
+/// The name of the revision of a document version, usable as part of a storage key.
+fn revision_name(version: &str) -> String {
+    version.replace('/', "_")
+}
+
+/// Compare versions by their dot separated parts, numerically if possible.
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |version: &str| {
+        version
+            .split('.')
+            .map(|part| (part.parse::<u64>().ok(), part.to_string()))
+            .collect::<Vec<_>>()
+    };
+    parts(a).cmp(&parts(b))
+}
+
 /// Parameters passed when publishing advisory.
 #[derive(Debug, Deserialize)]
 struct PublishParams {
@@ -134,7 +262,7 @@ struct PublishParams {
 
 /// Upload a VEX document.
 ///
-/// The document must be in the CSAF v2.0 format.
+/// The document must be in the CSAF v2.0 format, or an OpenVEX or CycloneDX VEX document, which is converted into CSAF.
 #[utoipa::path(
     put,
     tag = "vexination",
@@ -157,8 +285,21 @@ async fn publish_vex(
 ) -> actix_web::Result<HttpResponse> {
     authorizer.require(&user, Permission::CreateVex)?;
 
-    let vex = match serde_json::from_slice::<csaf::Csaf>(&data) {
-        Ok(data) => data,
+    let (vex, data) = match VexDocument::parse(&data) {
+        Ok(VexDocument::Csaf(advisory)) => (advisory.csaf, data),
+        Ok(vex) => {
+            // OpenVEX and CycloneDX documents are stored as CSAF, so that they can be consumed like any other advisory
+            match vex
+                .into_advisory()
+                .and_then(|advisory| serde_json::to_vec(&advisory.csaf).map(|data| (advisory.csaf, Bytes::from(data))))
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    log::warn!("Unable to convert VEX document: {:?}", e);
+                    return Ok(HttpResponse::BadRequest().into());
+                }
+            }
+        }
         Err(e) => {
             log::warn!("Unknown input format: {:?}", e);
             return Ok(HttpResponse::BadRequest().into());
@@ -177,11 +318,40 @@ async fn publish_vex(
         .put_json_slice(&advisory, &data)
         .await
         .map_err(Error::Storage)?;
+    // keep the history of the revisions, as every revision replaces the current document
+    state
+        .storage
+        .put_revision(&advisory, &revision_name(&vex.document.tracking.version), &data)
+        .await
+        .map_err(Error::Storage)?;
     let msg = format!("VEX of size {} stored successfully", &data[..].len());
     log::trace!("{}", msg);
     Ok(HttpResponse::Created().body(msg))
 }
 
+/// Validate a CSAF document, without storing it.
+///
+/// Next to the schema, the document is checked for mandatory fields, references to undefined products,
+/// contradicting product status and CVSS vectors not matching their scores.
+#[utoipa::path(
+    post,
+    tag = "vexination",
+    path = "/api/v1/vex/validate",
+    request_body(content = Value, description = "The VEX doc to be validated", content_type = "application/json"),
+    responses(
+        (status = 200, description = "VEX was validated", body = AdvisoryValidation),
+    )
+)]
+async fn validate_vex(
+    data: Bytes,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    Ok(HttpResponse::Ok().json(vexination_index::validation::validate(&data)))
+}
+
 /// Parameters for search query.
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
@@ -202,6 +372,18 @@ pub struct SearchParams {
     /// Enable fetching document summaries
     #[serde(default = "default_summaries")]
     pub summaries: bool,
+    /// Sort order of the results, in the form of `<field>:<asc|desc>`
+    #[serde(default)]
+    pub sort: Option<String>,
+    /// Cursor to iterate the results with, instead of using an offset
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Collapse results sharing the value of a field to the highest ranking one
+    #[serde(default)]
+    pub dedup_by: Option<String>,
+    /// Comma separated list of facets to compute bucket counts for
+    #[serde(default, with = "trustification_api::search::comma_separated")]
+    pub facets: Vec<String>,
 }
 
 const fn default_offset() -> usize {
@@ -230,6 +412,11 @@ impl From<&SearchParams> for SearchOptions {
             explain: value.explain,
             metadata: value.metadata,
             summaries: value.summaries,
+            facets: value.facets.clone(),
+            sort: value.sort.clone(),
+            highlight: false,
+            cursor: value.cursor.clone(),
+            dedup_by: value.dedup_by.clone(),
         }
     }
 }
@@ -247,6 +434,10 @@ impl From<&SearchParams> for SearchOptions {
     ),
     params(
         ("q" = String, Query, description = "Search query"),
+        ("sort" = Option<String>, Query, description = "Sort order, e.g. indexed:desc"),
+        ("cursor" = Option<String>, Query, description = "Iterate the results using a cursor: empty for the first page, or the cursor returned by the previous page"),
+        ("facets" = Option<String>, Query, description = "Comma separated list of facets: severity, publisher, year"),
+        ("dedup_by" = Option<String>, Query, description = "Collapse results sharing the value of a field to the highest ranking one: id, publisher"),
     )
 )]
 #[get("/vex/search")]
@@ -262,14 +453,92 @@ async fn search_vex(
 
     log::info!("Querying VEX using {}", params.q);
 
-    let (result, total) = web::block(move || {
-        state
-            .index
-            .search(&params.q, params.offset, params.limit, (&params).into())
+    let (result, total, facets, cursor) = web::block(move || {
+        let (result, total, cursor) = match &params.cursor {
+            Some(cursor) => state
+                .index
+                .search_cursor(&params.q, cursor, params.limit, (&params).into())?,
+            None => {
+                let (result, total) = state
+                    .index
+                    .search(&params.q, params.offset, params.limit, (&params).into())?;
+                (result, total, None)
+            }
+        };
+        let facets = state.index.facets(&params.q, &params.facets)?;
+        Ok::<_, IndexError>((result, total, facets, cursor))
     })
     .await?
     .map_err(Error::Index)?;
-    Ok(HttpResponse::Ok().json(SearchResult { total, result }))
+
+    Ok(HttpResponse::Ok().json(SearchResult {
+        total,
+        result,
+        facets,
+        cursor,
+    }))
+}
+
+/// List the fields VEX search queries can be qualified with.
+///
+/// Each field comes with its type and an example query, for building queries programmatically.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/search/fields",
+    responses(
+        (status = 200, description = "Search fields", body = Vec<SearchField>),
+    ),
+)]
+#[get("/vex/search/fields")]
+async fn search_vex_fields(
+    state: web::Data<SharedState>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    Ok(HttpResponse::Ok().json(state.index.search_fields()))
+}
+
+/// Count the advisories matching a search query for each interval of time.
+///
+/// Queries can select ranges of dates, like `release>2024-01-01` or `cveRelease:[2024-01-01 TO 2024-06-30]`.
+#[utoipa::path(
+    get,
+    tag = "vexination",
+    path = "/api/v1/vex/histogram",
+    responses(
+        (status = 200, description = "Histogram computed", body = Vec<HistogramBucket>),
+        (status = BAD_REQUEST, description = "Bad query, field, interval or facet"),
+        (status = 401, description = "User is not authenticated"),
+    ),
+    params(
+        ("q" = Option<String>, Query, description = "Search query"),
+        ("field" = Option<String>, Query, description = "Date to bucket by: release (default), initial, indexed, cveRelease or cveDiscovery"),
+        ("interval" = Option<String>, Query, description = "Length of the intervals, defaults to 7d"),
+        ("offset" = Option<String>, Query, description = "Shift of the intervals from the epoch"),
+        ("facet" = Option<String>, Query, description = "Facet to split the intervals by, like severity or publisher"),
+    )
+)]
+#[get("/vex/histogram")]
+async fn vex_histogram(
+    state: web::Data<SharedState>,
+    params: web::Query<HistogramParams>,
+    authorizer: web::Data<Authorizer>,
+    user: UserInformation,
+) -> actix_web::Result<HttpResponse> {
+    authorizer.require(&user, Permission::ReadVex)?;
+
+    let params = params.into_inner();
+
+    log::debug!("Computing VEX histogram: '{}'", params.q);
+
+    let buckets = web::block(move || state.index.histogram_for(&params, "release"))
+        .await?
+        .map_err(Error::Index)?;
+
+    Ok(HttpResponse::Ok().json(buckets))
 }
 
 /// Delete a VEX doc using its identifier.
@@ -0,0 +1,474 @@
+//! Support for [CycloneDX](https://cyclonedx.org/capabilities/vex/) VEX documents.
+//!
+//! A CycloneDX VEX document is a BOM carrying vulnerabilities, with the analysis of their impact on the components
+//! the vulnerabilities affect. Like OpenVEX documents, they are converted into CSAF VEX documents: each affected
+//! component gets a branch of the product tree, identified by its reference, and the state and justification of the
+//! analysis are mapped to the product status and flags of the CSAF vulnerability.
+
+use csaf::Csaf;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// A CycloneDX document, carrying vulnerabilities.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycloneDxVex {
+    pub bom_format: String,
+    #[serde(default)]
+    pub spec_version: Option<String>,
+    #[serde(default)]
+    pub serial_number: Option<String>,
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub metadata: Option<Metadata>,
+    #[serde(default)]
+    pub components: Vec<Component>,
+    #[serde(default)]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub component: Option<Component>,
+    #[serde(default)]
+    pub supplier: Option<Organization>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Organization {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub url: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Component {
+    #[serde(rename = "bom-ref", default)]
+    pub bom_ref: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub purl: Option<String>,
+    #[serde(default)]
+    pub cpe: Option<String>,
+    #[serde(default)]
+    pub components: Vec<Component>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Vulnerability {
+    pub id: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+    #[serde(default)]
+    pub references: Vec<Reference>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub detail: Option<String>,
+    #[serde(default)]
+    pub recommendation: Option<String>,
+    #[serde(default)]
+    pub published: Option<String>,
+    #[serde(default)]
+    pub updated: Option<String>,
+    #[serde(default)]
+    pub analysis: Option<Analysis>,
+    #[serde(default)]
+    pub affects: Vec<Affects>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Source {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// The same vulnerability, in another source.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Reference {
+    pub id: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Analysis {
+    #[serde(default)]
+    pub state: Option<State>,
+    #[serde(default)]
+    pub justification: Option<Justification>,
+    #[serde(default)]
+    pub response: Vec<Response>,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Resolved,
+    ResolvedWithPedigree,
+    Exploitable,
+    InTriage,
+    FalsePositive,
+    NotAffected,
+}
+
+impl State {
+    /// The matching category of the product status of a CSAF vulnerability.
+    fn product_status(state: Option<Self>) -> &'static str {
+        match state {
+            Some(Self::Resolved | Self::ResolvedWithPedigree) => "fixed",
+            // a vulnerability without analysis is reported as affecting the components
+            Some(Self::Exploitable) | None => "known_affected",
+            Some(Self::InTriage) => "under_investigation",
+            Some(Self::FalsePositive | Self::NotAffected) => "known_not_affected",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Justification {
+    CodeNotPresent,
+    CodeNotReachable,
+    RequiresConfiguration,
+    RequiresDependency,
+    RequiresEnvironment,
+    ProtectedByCompiler,
+    ProtectedAtRuntime,
+    ProtectedAtPerimeter,
+    ProtectedByMitigatingControl,
+}
+
+impl Justification {
+    /// The label of the matching CSAF flag.
+    fn label(&self) -> &'static str {
+        match self {
+            Self::CodeNotPresent => "vulnerable_code_not_present",
+            Self::CodeNotReachable => "vulnerable_code_not_in_execute_path",
+            Self::RequiresConfiguration | Self::RequiresEnvironment => {
+                "vulnerable_code_cannot_be_controlled_by_adversary"
+            }
+            Self::RequiresDependency => "component_not_present",
+            Self::ProtectedByCompiler
+            | Self::ProtectedAtRuntime
+            | Self::ProtectedAtPerimeter
+            | Self::ProtectedByMitigatingControl => "inline_mitigations_already_exist",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    CanNotFix,
+    WillNotFix,
+    Update,
+    Rollback,
+    WorkaroundAvailable,
+}
+
+impl Response {
+    /// The matching category of a CSAF remediation, and a description of it.
+    fn remediation(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::CanNotFix => ("no_fix_planned", "The vulnerability can not be fixed"),
+            Self::WillNotFix => ("no_fix_planned", "The vulnerability will not be fixed"),
+            Self::Update => ("vendor_fix", "Update to a fixed version"),
+            Self::Rollback => ("vendor_fix", "Roll back to a previous version"),
+            Self::WorkaroundAvailable => ("workaround", "A workaround is available"),
+        }
+    }
+}
+
+/// A component a vulnerability affects, by its reference or a BOM-Link to it.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Affects {
+    #[serde(rename = "ref")]
+    pub reference: String,
+}
+
+impl CycloneDxVex {
+    /// Check if the document is a CycloneDX document which carries vulnerabilities.
+    pub fn is_vex(&self) -> bool {
+        self.bom_format == "CycloneDX" && !self.vulnerabilities.is_empty()
+    }
+
+    /// The identifier of the advisory: the serial number, without its URN prefix.
+    ///
+    /// Falls back to the reference, or the name, of the component the document describes.
+    pub fn advisory_id(&self) -> Option<&str> {
+        let component = self.metadata.as_ref().and_then(|metadata| metadata.component.as_ref());
+        self.serial_number
+            .as_deref()
+            .map(|serial| serial.trim_start_matches("urn:uuid:"))
+            .or_else(|| component.map(|c| c.bom_ref.as_deref().unwrap_or(&c.name)))
+    }
+
+    /// All components of the document, by their reference.
+    fn components(&self) -> BTreeMap<&str, &Component> {
+        fn collect<'a>(components: &'a [Component], result: &mut BTreeMap<&'a str, &'a Component>) {
+            for component in components {
+                if let Some(bom_ref) = &component.bom_ref {
+                    result.insert(bom_ref, component);
+                }
+                collect(&component.components, result);
+            }
+        }
+
+        let mut result = BTreeMap::new();
+        if let Some(component) = self.metadata.as_ref().and_then(|metadata| metadata.component.as_ref()) {
+            collect(std::slice::from_ref(component), &mut result);
+        }
+        collect(&self.components, &mut result);
+        result
+    }
+
+    /// Convert the document into a CSAF VEX document.
+    ///
+    /// Fails if the document has no identifier or timestamp, which the tracking of the advisory requires.
+    pub fn to_csaf(&self) -> Result<Csaf, serde_json::Error> {
+        use serde::de::Error;
+
+        let id = self
+            .advisory_id()
+            .ok_or_else(|| serde_json::Error::custom("missing serial number and component"))?;
+        let metadata = self.metadata.clone().unwrap_or_default();
+        let timestamp = metadata
+            .timestamp
+            .as_ref()
+            .ok_or_else(|| serde_json::Error::custom("missing timestamp"))?;
+
+        let components = self.components();
+        let mut branches = BTreeMap::new();
+        let vulnerabilities: Vec<_> = self
+            .vulnerabilities
+            .iter()
+            .map(|vulnerability| {
+                for affects in &vulnerability.affects {
+                    let reference = affects.reference.as_str();
+                    // a BOM-Link refers to the component of another BOM by the fragment
+                    let local = reference.rsplit_once('#').map(|(_, r)| r).unwrap_or(reference);
+                    branches
+                        .entry(reference)
+                        .or_insert_with(|| branch(reference, components.get(local).copied()));
+                }
+                self.vulnerability(vulnerability)
+            })
+            .collect();
+
+        let version = self.version.unwrap_or(1).to_string();
+        let publisher = metadata.supplier.as_ref();
+        let publisher_name = publisher.and_then(|p| p.name.as_deref()).unwrap_or("unknown");
+        let namespace = publisher
+            .and_then(|p| p.url.first().map(String::as_str))
+            .unwrap_or("https://cyclonedx.org");
+        let csaf = json!({
+            "document": {
+                "category": "csaf_vex",
+                "csaf_version": "2.0",
+                "title": format!("CycloneDX VEX document {id}"),
+                "publisher": {
+                    "category": "other",
+                    "name": publisher_name,
+                    "namespace": namespace,
+                },
+                "tracking": {
+                    "id": id,
+                    "status": "final",
+                    "version": version,
+                    "initial_release_date": timestamp,
+                    "current_release_date": timestamp,
+                    "revision_history": [{
+                        "date": timestamp,
+                        "number": version,
+                        "summary": "Converted from CycloneDX",
+                    }],
+                },
+            },
+            "product_tree": {
+                "branches": branches.into_values().collect::<Vec<_>>(),
+            },
+            "vulnerabilities": vulnerabilities,
+        });
+
+        serde_json::from_value(csaf)
+    }
+
+    fn vulnerability(&self, vulnerability: &Vulnerability) -> Value {
+        let analysis = vulnerability.analysis.clone().unwrap_or_default();
+        let product_ids: Vec<_> = vulnerability.affects.iter().map(|a| a.reference.as_str()).collect();
+
+        let mut result = Map::new();
+        result.insert("title".to_string(), vulnerability.id.as_str().into());
+
+        let aliases = std::iter::once((vulnerability.id.as_str(), vulnerability.source.as_ref())).chain(
+            vulnerability
+                .references
+                .iter()
+                .map(|reference| (reference.id.as_str(), reference.source.as_ref())),
+        );
+        let mut ids = Vec::new();
+        for (id, source) in aliases {
+            if is_cve(id) {
+                result.entry("cve").or_insert_with(|| id.into());
+            } else {
+                let system_name = source.and_then(|s| s.name.as_deref()).unwrap_or("CycloneDX");
+                ids.push(json!({ "system_name": system_name, "text": id }));
+            }
+        }
+        if !ids.is_empty() {
+            result.insert("ids".to_string(), ids.into());
+        }
+
+        let mut notes = Vec::new();
+        if let Some(description) = &vulnerability.description {
+            notes.push(json!({ "category": "description", "text": description }));
+        }
+        if let Some(detail) = &vulnerability.detail {
+            notes.push(json!({ "category": "details", "text": detail }));
+        }
+        if !notes.is_empty() {
+            result.insert("notes".to_string(), notes.into());
+        }
+        if let Some(published) = &vulnerability.published {
+            result.insert("release_date".to_string(), published.as_str().into());
+        }
+
+        let status = State::product_status(analysis.state);
+        result.insert("product_status".to_string(), json!({ (status): product_ids }));
+
+        let not_affected = matches!(analysis.state, Some(State::NotAffected | State::FalsePositive));
+        if let (true, Some(justification)) = (not_affected, analysis.justification) {
+            result.insert(
+                "flags".to_string(),
+                json!([{ "label": justification.label(), "product_ids": product_ids }]),
+            );
+        }
+        if let Some(detail) = &analysis.detail {
+            result.insert(
+                "threats".to_string(),
+                json!([{ "category": "impact", "details": detail, "product_ids": product_ids }]),
+            );
+        }
+
+        let remediations: Vec<_> = analysis
+            .response
+            .iter()
+            .map(|response| {
+                let (category, details) = response.remediation();
+                let details = match (category, &vulnerability.recommendation) {
+                    ("vendor_fix", Some(recommendation)) => recommendation.as_str(),
+                    _ => details,
+                };
+                json!({ "category": category, "details": details, "product_ids": product_ids })
+            })
+            .collect();
+        if !remediations.is_empty() {
+            result.insert("remediations".to_string(), remediations.into());
+        }
+
+        Value::Object(result)
+    }
+}
+
+fn is_cve(id: &str) -> bool {
+    id.starts_with("CVE-")
+}
+
+fn branch(reference: &str, component: Option<&Component>) -> Value {
+    let name = match component {
+        Some(Component {
+            name,
+            version: Some(version),
+            ..
+        }) => format!("{name} {version}"),
+        Some(component) => component.name.clone(),
+        None => reference.to_string(),
+    };
+
+    let mut helper = Map::new();
+    let purl = component
+        .and_then(|component| component.purl.as_deref())
+        .or_else(|| Some(reference).filter(|r| r.starts_with("pkg:")));
+    if let Some(purl) = purl {
+        helper.insert("purl".to_string(), purl.into());
+    }
+    if let Some(cpe) = component.and_then(|component| component.cpe.as_deref()) {
+        helper.insert("cpe".to_string(), cpe.into());
+    }
+
+    let mut full_product_name = json!({
+        "name": name,
+        "product_id": reference,
+    });
+    if !helper.is_empty() {
+        full_product_name["product_identification_helper"] = Value::Object(helper);
+    }
+    json!({
+        "category": "product_version",
+        "name": name,
+        "product": full_product_name,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let data = std::fs::read("../testdata/cyclonedx-vex.json").unwrap();
+        let vex: CycloneDxVex = serde_json::from_slice(&data).unwrap();
+        assert!(vex.is_vex());
+        assert_eq!(vex.advisory_id(), Some("3e671687-395b-41f5-a30f-a58921a69b79"));
+
+        let csaf = vex.to_csaf().unwrap();
+        assert_eq!(csaf.document.tracking.id, "3e671687-395b-41f5-a30f-a58921a69b79");
+        assert_eq!(csaf.document.tracking.version, "2");
+        assert_eq!(csaf.document.publisher.name, "Acme Inc");
+
+        let vulns = csaf.vulnerabilities.unwrap();
+        assert_eq!(vulns.len(), 2);
+        assert_eq!(vulns[0].cve.as_deref(), Some("CVE-2021-44228"));
+
+        let status = vulns[0].product_status.as_ref().unwrap();
+        assert_eq!(
+            status.known_not_affected.as_ref().unwrap()[0].0,
+            "urn:cdx:3e671687-395b-41f5-a30f-a58921a69b79/1#pkg:maven/org.apache.logging.log4j/log4j-core@2.14.1"
+        );
+        assert_eq!(vulns[0].flags.as_ref().unwrap().len(), 1);
+        assert_eq!(vulns[0].threats.as_ref().unwrap().len(), 1);
+
+        let status = vulns[1].product_status.as_ref().unwrap();
+        assert_eq!(status.known_affected.as_ref().unwrap().len(), 1);
+        assert_eq!(vulns[1].ids.as_ref().unwrap()[0].system_name, "GitHub Advisories");
+        assert_eq!(vulns[1].remediations.as_ref().unwrap().len(), 1);
+
+        let tree = csaf.product_tree.unwrap();
+        assert_eq!(tree.branches.unwrap().0.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_identifier() {
+        let vex: CycloneDxVex = serde_json::from_value(json!({
+            "bomFormat": "CycloneDX",
+            "metadata": { "timestamp": "2023-01-01T00:00:00Z" },
+            "vulnerabilities": [{ "id": "CVE-2023-12345" }]
+        }))
+        .unwrap();
+        assert!(vex.is_vex());
+        assert!(vex.to_csaf().is_err());
+    }
+}
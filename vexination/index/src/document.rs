@@ -0,0 +1,148 @@
+//! Parsing of VEX documents, detecting their format and version.
+//!
+//! Documents are parsed into the CSAF 2.0 model, so that all versions share the same index schema. CSAF 2.1
+//! documents are downgraded to 2.0 for that, see [`vexination_model::convert`].
+
+use crate::cyclonedx::CycloneDxVex;
+use crate::openvex::OpenVex;
+use csaf::Csaf;
+use serde::Deserialize;
+use serde_json::Value;
+use vexination_model::convert::downgrade;
+pub use vexination_model::convert::{CsafVersion, CvssV4, Features, VulnerabilityFeatures};
+
+/// A VEX document, in one of the supported formats.
+#[derive(Clone, Debug)]
+pub enum VexDocument {
+    Csaf(Box<Advisory>),
+    OpenVex(Box<OpenVex>),
+    CycloneDx(Box<CycloneDxVex>),
+}
+
+impl VexDocument {
+    /// Parse a document, detecting its format.
+    ///
+    /// If the document is neither CSAF, OpenVEX nor CycloneDX carrying vulnerabilities, the error of parsing it as CSAF is returned.
+    pub fn parse(data: &[u8]) -> Result<Self, serde_json::Error> {
+        let value: Value = serde_json::from_slice(data)?;
+        if CsafVersion::detect(&value) == CsafVersion::V2_1 {
+            return Ok(Self::Csaf(Box::new(Advisory::from_value(value)?)));
+        }
+
+        match Csaf::deserialize(&value) {
+            Ok(csaf) => Ok(Self::Csaf(Box::new(Advisory::from(csaf)))),
+            Err(err) => {
+                if let Ok(openvex) = OpenVex::deserialize(&value) {
+                    if openvex.is_openvex() {
+                        return Ok(Self::OpenVex(Box::new(openvex)));
+                    }
+                }
+                match serde_json::from_value::<CycloneDxVex>(value) {
+                    Ok(cyclonedx) if cyclonedx.is_vex() => Ok(Self::CycloneDx(Box::new(cyclonedx))),
+                    _ => Err(err),
+                }
+            }
+        }
+    }
+
+    /// Get the document as an advisory, converting it if necessary.
+    pub fn into_advisory(self) -> Result<Advisory, serde_json::Error> {
+        match self {
+            Self::Csaf(advisory) => Ok(*advisory),
+            Self::OpenVex(openvex) => openvex.to_csaf().map(Advisory::from),
+            Self::CycloneDx(cyclonedx) => cyclonedx.to_csaf().map(Advisory::from),
+        }
+    }
+}
+
+/// A CSAF document, in the CSAF 2.0 model.
+#[derive(Clone, Debug)]
+pub struct Advisory {
+    pub csaf: Csaf,
+    /// The version of the original document
+    pub version: CsafVersion,
+    pub features: Features,
+}
+
+impl From<Csaf> for Advisory {
+    fn from(csaf: Csaf) -> Self {
+        Self {
+            csaf,
+            version: CsafVersion::V2_0,
+            features: Features::default(),
+        }
+    }
+}
+
+impl Advisory {
+    /// Parse a CSAF document of any version, by downgrading it to 2.0.
+    fn from_value(mut value: Value) -> Result<Self, serde_json::Error> {
+        let (version, features) = downgrade(&mut value);
+        Ok(Self {
+            csaf: serde_json::from_value(value)?,
+            version,
+            features,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(name: &str) -> VexDocument {
+        let data = std::fs::read(format!("../testdata/{name}.json")).unwrap();
+        VexDocument::parse(&data).unwrap()
+    }
+
+    #[test]
+    fn test_parse_csaf_2_0() {
+        let VexDocument::Csaf(advisory) = parse("rhsa-2023_1441") else {
+            panic!("must be detected as CSAF");
+        };
+        assert_eq!(advisory.version, CsafVersion::V2_0);
+        assert_eq!(advisory.features, Features::default());
+    }
+
+    #[test]
+    fn test_parse_csaf_2_1() {
+        let VexDocument::Csaf(advisory) = parse("csaf-2.1") else {
+            panic!("must be detected as CSAF");
+        };
+        assert_eq!(advisory.version, CsafVersion::V2_1);
+        assert_eq!(advisory.csaf.document.tracking.id, "EXAMPLE-2024-0001");
+        assert_eq!(
+            advisory.features.vulnerabilities,
+            vec![VulnerabilityFeatures {
+                cvss_v4: vec![CvssV4 {
+                    score: 9.3,
+                    severity: "critical".to_string(),
+                }],
+                cwes: vec!["CWE-787".to_string(), "CWE-20".to_string()],
+            }]
+        );
+
+        let vuln = &advisory.csaf.vulnerabilities.unwrap()[0];
+        assert_eq!(vuln.cwe.as_ref().unwrap().id, "CWE-787");
+        assert!(vuln.release_date.is_some());
+        assert_eq!(vuln.scores.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_openvex() {
+        assert!(matches!(parse("openvex"), VexDocument::OpenVex(_)));
+    }
+
+    #[test]
+    fn test_parse_cyclonedx() {
+        assert!(matches!(parse("cyclonedx-vex"), VexDocument::CycloneDx(_)));
+        // an SBOM without vulnerabilities isn't a VEX document
+        assert!(VexDocument::parse(br#"{"bomFormat": "CycloneDX", "specVersion": "1.5"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(VexDocument::parse(br#"{"@context": "https://example.com"}"#).is_err());
+        assert!(VexDocument::parse(b"not json").is_err());
+    }
+}
@@ -1,38 +1,51 @@
 use csaf::{
     definitions::{BranchesT, NoteCategory, ProductIdT, ProductIdentificationHelper},
     product_tree::ProductTree,
+    vulnerability::{Remediation, RemediationCategory},
     Csaf,
 };
 use log::{debug, warn};
-use serde_json::{Map, Value};
+use serde_json::{json, Map, Value};
 use sikula::prelude::*;
-use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
-    time::Duration,
-};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet};
+use strum::VariantNames;
 use time::OffsetDateTime;
-use trustification_api::search::SearchOptions;
+use trustification_api::search::{SearchField, SearchOptions};
 use trustification_index::{
-    boost, create_date_query, create_float_query, create_string_query, create_string_query_case, create_text_query,
-    field2date, field2float, field2str, field2str_opt, field2strvec,
+    boosts::boost_query,
+    create_date_query, create_float_query, create_string_query, create_string_query_case, field2date, field2date_opt,
+    field2float, field2str, field2str_opt, field2strvec,
     metadata::doc2metadata,
-    sort_by,
+    patterns::pattern_value,
+    search_field, sort_by,
     tantivy::{
         self,
         collector::TopDocs,
         doc,
         query::{AllQuery, BooleanQuery, Query, TermSetQuery},
-        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING, TEXT},
+        schema::{Field, Schema, Term, FAST, INDEXED, STORED, STRING},
         store::ZstdCompressor,
         DateTime, DocAddress, DocId, IndexSettings, Score, Searcher, SegmentReader, SnippetGenerator,
     },
-    term2query, Case, Document, Error as SearchError, SearchQuery,
+    term2query,
+    tokenizer::{FieldTokenizer, Tokenizer, Tokenizers},
+    Case, Document, Error as SearchError, QueryContext, SearchQuery,
 };
 use vexination_model::prelude::*;
 
+pub mod cyclonedx;
+pub mod document;
+pub mod openvex;
+pub mod range;
+pub mod validation;
+
+use document::Advisory;
+use range::{AffectsQuery, VersionRange};
+
 pub struct Index {
     schema: Schema,
     fields: Fields,
+    tokenizers: Tokenizers,
 }
 
 struct Fields {
@@ -46,30 +59,49 @@ struct Fields {
     advisory_status: Field,
     advisory_title: Field,
     advisory_description: Field,
+    /// the aggregate severity, as reported by the publisher
     advisory_severity: Field,
+    /// the aggregate severity, normalized to a [`Severity`]
+    advisory_severity_normalized: Field,
     advisory_revision: Field,
     advisory_initial: Field,
     advisory_current: Field,
+    advisory_publisher: Field,
+    /// the category of the document, which is the CSAF profile
+    advisory_category: Field,
+    /// the CSAF version of the original document
+    advisory_csaf_version: Field,
+    /// the year of the current release, as text to be usable as facet
+    advisory_year: Field,
 
     advisory_severity_score: Field,
 
     cve_severity_count: Field,
+    /// the public release dates of the CVEs, as unix timestamps by CVE
+    cve_release_dates: Field,
+    /// the remediations of all vulnerabilities, as JSON serialized [`AdvisoryRemediation`]
+    cve_remediations: Field,
 
     cve_id: Field,
     cve_title: Field,
     cve_description: Field,
     cve_release: Field,
     cve_discovery: Field,
+    /// the severities of the vulnerabilities, normalized to a [`Severity`]
     cve_severity: Field,
     cve_cvss: Field,
     cve_fixed: Field,
     cve_affected: Field,
     cve_not_affected: Field,
+    /// the packages having affected ranges, without version
+    cve_affected_package: Field,
+    /// the affected ranges, encoded with their package
+    cve_affected_range: Field,
     cve_cwe: Field,
     cve_cvss_max: Field,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 struct ProductPackage {
     cpe: Option<String>,
     purl: Option<String>,
@@ -78,7 +110,7 @@ struct ProductPackage {
 impl trustification_index::Index for Index {
     type MatchedDocument = SearchHit;
 
-    fn prepare_query(&self, q: &str) -> Result<SearchQuery, SearchError> {
+    fn prepare_query(&self, q: &str, context: &QueryContext) -> Result<SearchQuery, SearchError> {
         let mut query = Vulnerabilities::parse(q).map_err(|err| SearchError::QueryParser(err.to_string()))?;
 
         query.term = query.term.compact();
@@ -93,7 +125,7 @@ impl trustification_index::Index for Index {
         let query = if query.term.is_empty() {
             Box::new(AllQuery)
         } else {
-            term2query(&query.term, &|resource| self.resource2query(resource))
+            term2query(&query.term, &|resource| self.resource2query(resource, context))
         };
 
         log::trace!("Processed query: {:?}", query);
@@ -106,6 +138,7 @@ impl trustification_index::Index for Index {
         query: &dyn Query,
         offset: usize,
         limit: usize,
+        context: &QueryContext,
     ) -> Result<(Vec<(f32, DocAddress)>, usize), SearchError> {
         let severity_field = self
             .schema
@@ -113,6 +146,7 @@ impl trustification_index::Index for Index {
             .to_string();
         let date_field = self.schema.get_field_name(self.fields.advisory_current).to_string();
         let now = tantivy::DateTime::from_utc(OffsetDateTime::now_utc());
+        let recency = context.recency();
         Ok(searcher.search(
             query,
             &(
@@ -136,9 +170,9 @@ impl trustification_index::Index for Index {
                                     if date < now {
                                         let mut normalized = 2.0
                                             * (date.into_timestamp_secs() as f64 / now.into_timestamp_secs() as f64);
-                                        // If it's the past month, boost it more.
-                                        if (now.into_utc() - date.into_utc()) < Duration::from_secs(30 * 24 * 3600) {
-                                            normalized *= 4.0;
+                                        // If it's recent, boost it more.
+                                        if (now.into_utc() - date.into_utc()) < recency.window {
+                                            normalized *= recency.boost as f64;
                                         }
                                         log::trace!(
                                             "DATE score impact {} -> {}",
@@ -175,6 +209,7 @@ impl trustification_index::Index for Index {
         let advisory_title = field2str(&self.schema, &doc, self.fields.advisory_title)?;
         let advisory_severity = field2str_opt(&doc, self.fields.advisory_severity);
         let advisory_date = field2date(&self.schema, &doc, self.fields.advisory_current)?;
+        let advisory_initial_date = field2date_opt(&doc, self.fields.advisory_initial);
         let advisory_desc = field2str(&self.schema, &doc, self.fields.advisory_description).unwrap_or("");
 
         let cves = field2strvec(&doc, self.fields.cve_id)?
@@ -193,16 +228,35 @@ impl trustification_index::Index for Index {
             }
         }
 
+        let mut cve_release_dates = HashMap::new();
+        if let Some(Some(data)) = doc.get_first(self.fields.cve_release_dates).map(|d| d.as_json()) {
+            for (cve, value) in data.iter() {
+                if let Some(date) = value.as_i64().and_then(|v| OffsetDateTime::from_unix_timestamp(v).ok()) {
+                    cve_release_dates.insert(cve.clone(), date);
+                }
+            }
+        }
+
+        let remediations = doc
+            .get_first(self.fields.cve_remediations)
+            .and_then(|d| d.as_json())
+            .and_then(|data| data.get("remediations"))
+            .and_then(|remediations| serde_json::from_value(remediations.clone()).ok())
+            .unwrap_or_default();
+
         let document = SearchDocument {
             advisory_id: advisory_id.to_string(),
             advisory_title: advisory_title.to_string(),
             advisory_date,
+            advisory_initial_date,
             advisory_snippet,
             advisory_severity: advisory_severity.map(ToString::to_string),
             advisory_desc: advisory_desc.to_string(),
             cves,
             cvss_max,
             cve_severity_count,
+            cve_release_dates,
+            remediations,
         };
 
         let explanation = if options.explain {
@@ -226,10 +280,91 @@ impl trustification_index::Index for Index {
             metadata,
         })
     }
+
+    fn sort_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "indexed" => Some(self.fields.indexed_timestamp),
+            "release" => Some(self.fields.advisory_current),
+            "initial" => Some(self.fields.advisory_initial),
+            _ => None,
+        }
+    }
+
+    fn facet_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "severity" => Some(self.fields.advisory_severity),
+            "normalizedSeverity" => Some(self.fields.advisory_severity_normalized),
+            "publisher" => Some(self.fields.advisory_publisher),
+            "year" => Some(self.fields.advisory_year),
+            "category" => Some(self.fields.advisory_category),
+            "csafVersion" => Some(self.fields.advisory_csaf_version),
+            _ => None,
+        }
+    }
+
+    fn histogram_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "indexed" => Some(self.fields.indexed_timestamp),
+            "release" => Some(self.fields.advisory_current),
+            "initial" => Some(self.fields.advisory_initial),
+            "cveRelease" => Some(self.fields.cve_release),
+            "cveDiscovery" => Some(self.fields.cve_discovery),
+            _ => None,
+        }
+    }
+
+    fn stats_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "score" => Some(self.fields.advisory_severity_score),
+            "cvssMax" => Some(self.fields.cve_cvss_max),
+            _ => None,
+        }
+    }
+
+    fn dedup_field(&self, name: &str) -> Option<Field> {
+        match name {
+            "id" => Some(self.fields.advisory_id),
+            "publisher" => Some(self.fields.advisory_publisher),
+            _ => None,
+        }
+    }
+
+    fn qualifiers(&self) -> &[&'static str] {
+        Vulnerabilities::VARIANTS
+    }
+
+    fn qualifier_field(&self, name: &str) -> Option<SearchField> {
+        let field = |f, example| search_field(&self.schema, name, f, example);
+        Some(match name {
+            "id" => field(self.fields.advisory_id, "id:\"RHSA-2023:1441\"").with_default(),
+            "cve" => field(self.fields.cve_id, "cve:CVE-2023-0286").with_default(),
+            "title" => field(self.fields.advisory_title, "title:openssl").with_default(),
+            "description" => field(self.fields.advisory_description, "description:\"memory leak\"").with_default(),
+            "status" => field(self.fields.advisory_status, "status:final"),
+            "publisher" => field(self.fields.advisory_publisher, "publisher:\"Red Hat Product Security\""),
+            "category" => field(self.fields.advisory_category, "category:csaf_vex"),
+            "csafVersion" => field(self.fields.advisory_csaf_version, "csafVersion:2.0"),
+            "severity" => field(self.fields.advisory_severity_normalized, "severity:important").with_sortable(),
+            "cvss" => field(self.fields.cve_cvss, "cvss:>7.5"),
+            "package" => field(self.fields.cve_affected, "package:openssl"),
+            "fixed" => field(self.fields.cve_fixed, "fixed:\"pkg:rpm/redhat/openssl\""),
+            "affected" => field(self.fields.cve_affected, "affected:\"pkg:rpm/redhat/openssl\""),
+            "notAffected" => field(self.fields.cve_not_affected, "notAffected:\"pkg:rpm/redhat/openssl\""),
+            "affects" => field(
+                self.fields.cve_affected_package,
+                "affects:\"pkg:rpm/redhat/openssl@1.1.1k\"",
+            ),
+            "initial" => field(self.fields.advisory_initial, "initial:>2023-01-01"),
+            "release" => field(self.fields.advisory_current, "release:2023-01-01..2023-12-31").with_sortable(),
+            "cveRelease" => field(self.fields.cve_release, "cveRelease:>2023-01-01"),
+            "cveDiscovery" => field(self.fields.cve_discovery, "cveDiscovery:<2023-01-01"),
+            _ => return None,
+        })
+    }
 }
 
 impl trustification_index::WriteIndex for Index {
-    type Document = Csaf;
+    type Document = Advisory;
 
     fn name(&self) -> &str {
         "vex"
@@ -242,11 +377,14 @@ impl trustification_index::WriteIndex for Index {
         }
     }
 
-    fn parse_doc(&self, data: &[u8]) -> Result<Csaf, SearchError> {
-        serde_json::from_slice::<Csaf>(data).map_err(|e| SearchError::DocParser(e.to_string()))
+    fn parse_doc(&self, data: &[u8]) -> Result<Advisory, SearchError> {
+        document::VexDocument::parse(data)
+            .and_then(|vex| vex.into_advisory())
+            .map_err(|e| SearchError::DocParser(e.to_string()))
     }
 
-    fn index_doc(&self, id: &str, csaf: &Csaf) -> Result<Vec<(String, Document)>, SearchError> {
+    fn index_doc(&self, id: &str, advisory: &Advisory) -> Result<Vec<(String, Document)>, SearchError> {
+        let csaf = &advisory.csaf;
         let document_status = match &csaf.document.tracking.status {
             csaf::document::Status::Draft => "draft",
             csaf::document::Status::Interim => "interim",
@@ -279,15 +417,13 @@ impl trustification_index::WriteIndex for Index {
         }
 
         if let Some(severity) = &csaf.document.aggregate_severity {
-            let severity = severity.text.to_lowercase();
-            document.add_text(self.fields.advisory_severity, &severity);
-            let score = match severity.as_ref() {
-                "critical" => 1.0,
-                "important" => 0.75,
-                "moderate" => 0.5,
-                "low" => 0.25,
-                _ => 0.25,
-            };
+            document.add_text(self.fields.advisory_severity, severity.text.to_lowercase());
+            let normalized = Severity::normalize(&severity.text);
+            if let Some(normalized) = normalized {
+                document.add_text(self.fields.advisory_severity_normalized, normalized.as_str());
+            }
+            // unknown labels are ranked like the lowest severity
+            let score = normalized.map(|severity| severity.rank()).unwrap_or(0.25);
             document.add_f64(self.fields.advisory_severity_score, score);
         }
 
@@ -304,15 +440,30 @@ impl trustification_index::WriteIndex for Index {
             self.fields.advisory_current,
             DateTime::from_timestamp_millis(csaf.document.tracking.current_release_date.timestamp_millis()),
         );
+        if let Ok(current) =
+            OffsetDateTime::from_unix_timestamp(csaf.document.tracking.current_release_date.timestamp())
+        {
+            document.add_text(self.fields.advisory_year, current.year().to_string());
+        }
+        document.add_text(self.fields.advisory_publisher, &csaf.document.publisher.name);
+        if let Ok(Value::String(category)) = serde_json::to_value(&csaf.document.category) {
+            document.add_text(self.fields.advisory_category, category);
+        }
+        document.add_text(self.fields.advisory_csaf_version, advisory.version.as_str());
 
-        let mut cve_severities: HashMap<&str, usize> = HashMap::new();
+        let mut cve_severities: HashMap<Severity, usize> = HashMap::new();
         let mut cvss_max: Option<f64> = None;
         let mut fixed: HashSet<String> = HashSet::new();
         let mut affected: HashSet<String> = HashSet::new();
         let mut no_affected: HashSet<String> = HashSet::new();
+        let mut affected_ranges: HashSet<(String, String)> = HashSet::new();
+        let mut release_dates: Map<String, Value> = Map::new();
+        let mut remediations: Vec<AdvisoryRemediation> = Vec::new();
 
         if let Some(vulns) = &csaf.vulnerabilities {
-            for vuln in vulns {
+            for (n, vuln) in vulns.iter().enumerate() {
+                let features = advisory.features.vulnerabilities.get(n);
+
                 if let Some(title) = &vuln.title {
                     document.add_text(self.fields.cve_title, title);
                 }
@@ -337,8 +488,9 @@ impl trustification_index::WriteIndex for Index {
                                 }
                             }
 
-                            document.add_text(self.fields.cve_severity, cvss3.severity().as_str());
-                            match cve_severities.entry(cvss3.severity().as_str()) {
+                            let severity = Severity::from_score(cvss3.score().value());
+                            document.add_text(self.fields.cve_severity, severity.as_str());
+                            match cve_severities.entry(severity) {
                                 Entry::Occupied(o) => {
                                     *o.into_mut() += 1;
                                 }
@@ -350,9 +502,25 @@ impl trustification_index::WriteIndex for Index {
                     }
                 }
 
+                // scores of CVSS v4, which only CSAF 2.1 documents have
+                for cvss4 in features.iter().flat_map(|features| &features.cvss_v4) {
+                    document.add_f64(self.fields.cve_cvss, cvss4.score);
+                    if cvss_max.map(|current| cvss4.score > current).unwrap_or(true) {
+                        cvss_max.replace(cvss4.score);
+                    }
+                    let severity = Severity::normalize(&cvss4.severity).unwrap_or(Severity::from_score(cvss4.score));
+                    document.add_text(self.fields.cve_severity, severity.as_str());
+                    *cve_severities.entry(severity).or_default() += 1;
+                }
+
                 if let Some(cwe) = &vuln.cwe {
                     document.add_text(self.fields.cve_cwe, &cwe.id);
                 }
+                for cwe in features.iter().flat_map(|features| &features.cwes) {
+                    if vuln.cwe.as_ref().map(|first| &first.id != cwe).unwrap_or(true) {
+                        document.add_text(self.fields.cve_cwe, cwe);
+                    }
+                }
 
                 if let Some(notes) = &vuln.notes {
                     for note in notes {
@@ -386,6 +554,20 @@ impl trustification_index::WriteIndex for Index {
                         }
                     }
 
+                    for (products, single) in [
+                        (&status.known_affected, VersionRange::exact as fn(&str) -> VersionRange),
+                        (&status.first_affected, VersionRange::starting),
+                        (&status.last_affected, VersionRange::ending),
+                    ] {
+                        for product in products.iter().flatten() {
+                            for (package, range) in range::product_ranges(csaf, product, single) {
+                                let package = trustification_purl::normalize(&package).into_owned();
+                                let encoded = range::encode(&package, &range);
+                                affected_ranges.insert((package, encoded));
+                            }
+                        }
+                    }
+
                     if let Some(products) = &status.fixed {
                         for product in products {
                             let (pp, related_pp) = find_product_package(csaf, product);
@@ -433,6 +615,10 @@ impl trustification_index::WriteIndex for Index {
                     }
                 }
 
+                for remediation in vuln.remediations.iter().flatten() {
+                    remediations.push(extract_remediation(csaf, vuln.cve.as_deref(), remediation));
+                }
+
                 if let Some(discovery_date) = &vuln.discovery_date {
                     document.add_date(
                         self.fields.cve_discovery,
@@ -445,19 +631,31 @@ impl trustification_index::WriteIndex for Index {
                         self.fields.cve_release,
                         DateTime::from_timestamp_millis(release_date.timestamp_millis()),
                     );
+                    if let Some(cve) = &vuln.cve {
+                        release_dates.insert(cve.to_uppercase(), release_date.timestamp().into());
+                    }
                 }
             }
 
+            // package URLs are normalized like in the other indexes, CPEs are kept as they are
             for affected in affected {
-                document.add_text(self.fields.cve_affected, affected);
+                document.add_text(self.fields.cve_affected, trustification_purl::normalize(&affected));
+            }
+
+            for (package, range) in affected_ranges {
+                document.add_text(self.fields.cve_affected_package, package);
+                document.add_text(self.fields.cve_affected_range, range);
             }
 
             for fixed in fixed {
-                document.add_text(self.fields.cve_fixed, fixed);
+                document.add_text(self.fields.cve_fixed, trustification_purl::normalize(&fixed));
             }
 
             for no_affected in no_affected {
-                document.add_text(self.fields.cve_not_affected, no_affected);
+                document.add_text(
+                    self.fields.cve_not_affected,
+                    trustification_purl::normalize(&no_affected),
+                );
             }
 
             let mut json_severities: Map<String, Value> = Map::new();
@@ -465,6 +663,11 @@ impl trustification_index::WriteIndex for Index {
                 json_severities.insert(key.to_string(), Value::Number((*value).into()));
             }
             document.add_json_object(self.fields.cve_severity_count, json_severities);
+            document.add_json_object(self.fields.cve_release_dates, release_dates);
+            // JSON fields must be objects
+            let mut json_remediations = Map::new();
+            json_remediations.insert("remediations".to_string(), json!(remediations));
+            document.add_json_object(self.fields.cve_remediations, json_remediations);
 
             if let Some(cvss_max) = cvss_max {
                 document.add_f64(self.fields.cve_cvss_max, cvss_max);
@@ -494,36 +697,54 @@ impl Default for Index {
 }
 
 impl Index {
-    // TODO use CONST for field names
     pub fn new() -> Self {
+        Self::with_tokenizers(&[])
+    }
+
+    /// Create the index, with the tokenizers configured for its fields.
+    ///
+    /// The titles and descriptions of advisories and vulnerabilities are simple by default.
+    // TODO use CONST for field names
+    pub fn with_tokenizers(configured: &[FieldTokenizer]) -> Self {
+        let mut tokenizers = Tokenizers::new(configured);
         let mut schema = Schema::builder();
-        let indexed_timestamp = schema.add_date_field("indexed_timestamp", STORED);
+        let indexed_timestamp = schema.add_date_field("indexed_timestamp", STORED | FAST);
 
         let advisory_id = schema.add_text_field("advisory_id", STRING | FAST);
         let advisory_id_raw = schema.add_text_field("advisory_id_raw", STRING | STORED);
         let advisory_status = schema.add_text_field("advisory_status", STRING);
-        let advisory_title = schema.add_text_field("advisory_title", TEXT | STORED);
-        let advisory_description = schema.add_text_field("advisory_description", TEXT | STORED);
+        let advisory_title = tokenizers.add_text_field(&mut schema, "advisory_title", Tokenizer::Simple, STORED);
+        let advisory_description =
+            tokenizers.add_text_field(&mut schema, "advisory_description", Tokenizer::Simple, STORED);
         let advisory_revision = schema.add_text_field("advisory_revision", STRING | STORED);
-        let advisory_severity = schema.add_text_field("advisory_severity", STRING | STORED);
-        let advisory_initial = schema.add_date_field("advisory_initial_date", INDEXED);
+        let advisory_severity = schema.add_text_field("advisory_severity", STRING | FAST | STORED);
+        let advisory_severity_normalized = schema.add_text_field("advisory_severity_normalized", STRING | FAST);
+        let advisory_initial = schema.add_date_field("advisory_initial_date", INDEXED | FAST | STORED);
         let advisory_current = schema.add_date_field("advisory_current_date", INDEXED | FAST | STORED);
+        let advisory_publisher = schema.add_text_field("advisory_publisher", STRING | FAST);
+        let advisory_year = schema.add_text_field("advisory_year", STRING | FAST);
+        let advisory_category = schema.add_text_field("advisory_category", STRING | FAST);
+        let advisory_csaf_version = schema.add_text_field("advisory_csaf_version", STRING | FAST);
         let advisory_severity_score = schema.add_f64_field("advisory_severity_score", FAST);
 
         let cve_id = schema.add_text_field("cve_id", STRING | FAST | STORED);
-        let cve_title = schema.add_text_field("cve_title", TEXT | STORED);
-        let cve_description = schema.add_text_field("cve_description", TEXT | STORED);
-        let cve_discovery = schema.add_date_field("cve_discovery_date", INDEXED);
-        let cve_release = schema.add_date_field("cve_release_date", INDEXED | STORED);
+        let cve_title = tokenizers.add_text_field(&mut schema, "cve_title", Tokenizer::Simple, STORED);
+        let cve_description = tokenizers.add_text_field(&mut schema, "cve_description", Tokenizer::Simple, STORED);
+        let cve_discovery = schema.add_date_field("cve_discovery_date", INDEXED | FAST);
+        let cve_release = schema.add_date_field("cve_release_date", INDEXED | FAST | STORED);
         let cve_severity = schema.add_text_field("cve_severity", STRING | FAST);
         let cve_affected = schema.add_text_field("cve_affected", STORED | STRING);
         let cve_not_affected = schema.add_text_field("cve_not_affected", STORED | STRING);
         let cve_fixed = schema.add_text_field("cve_fixed", STORED | STRING);
         let cve_cvss = schema.add_f64_field("cve_cvss", FAST | INDEXED | STORED);
         let cve_cvss_max = schema.add_f64_field("cve_cvss_max", FAST | STORED);
+        let cve_affected_package = schema.add_text_field("cve_affected_package", STRING);
+        let cve_affected_range = schema.add_text_field("cve_affected_range", STRING | FAST);
         let cve_cwe = schema.add_text_field("cve_cwe", STRING | STORED);
 
         let cve_severity_count = schema.add_json_field("cve_severity_count", STORED);
+        let cve_release_dates = schema.add_json_field("cve_release_dates", STORED);
+        let cve_remediations = schema.add_json_field("cve_remediations", STORED);
 
         Self {
             schema: schema.build(),
@@ -537,8 +758,13 @@ impl Index {
                 advisory_description,
                 advisory_revision,
                 advisory_severity,
+                advisory_severity_normalized,
                 advisory_initial,
                 advisory_current,
+                advisory_publisher,
+                advisory_year,
+                advisory_category,
+                advisory_csaf_version,
                 advisory_severity_score,
 
                 cve_id,
@@ -553,79 +779,133 @@ impl Index {
                 cve_cvss_max,
                 cve_cwe,
                 cve_severity_count,
+                cve_release_dates,
+                cve_remediations,
                 cve_not_affected,
+                cve_affected_package,
+                cve_affected_range,
             },
+            tokenizers,
         }
     }
 
-    fn resource2query(&self, resource: &Vulnerabilities) -> Box<dyn Query> {
+    /// Match advisories having the severity, either as aggregate severity or by any of their vulnerabilities.
+    fn severity_query(&self, severity: Severity) -> Box<dyn Query> {
+        Box::new(TermSetQuery::new(vec![
+            Term::from_field_text(self.fields.cve_severity, severity.as_str()),
+            Term::from_field_text(self.fields.advisory_severity_normalized, severity.as_str()),
+        ]))
+    }
+
+    fn resource2query(&self, resource: &Vulnerabilities, context: &QueryContext) -> Box<dyn Query> {
         const ID_WEIGHT: f32 = 1.5;
         const CVE_ID_WEIGHT: f32 = 1.4;
-        const ADV_TITLE_WEIGHT: f32 = 1.3;
-        const CVE_TITLE_WEIGHT: f32 = 1.3;
+        const TITLE_WEIGHT: f32 = 1.3;
         match resource {
-            Vulnerabilities::Id(primary) => boost(
-                create_string_query_case(self.fields.advisory_id, primary, Case::Uppercase),
+            Vulnerabilities::Id(primary) => boost_query(
+                "id",
                 ID_WEIGHT,
+                create_string_query_case(self.fields.advisory_id, primary, Case::Uppercase, context),
+                context,
             ),
-            Vulnerabilities::Cve(primary) => boost(
-                create_string_query_case(self.fields.cve_id, primary, Case::Uppercase),
+            Vulnerabilities::Cve(primary) => boost_query(
+                "cve",
                 CVE_ID_WEIGHT,
+                create_string_query_case(self.fields.cve_id, primary, Case::Uppercase, context),
+                context,
             ),
 
             Vulnerabilities::Description(primary) => {
-                let q1 = create_text_query(self.fields.advisory_description, primary);
-                let q2 = create_text_query(self.fields.cve_description, primary);
-                Box::new(BooleanQuery::union(vec![q1, q2]))
+                let q1 = self
+                    .tokenizers
+                    .query(self.fields.advisory_description, primary, context);
+                let q2 = self.tokenizers.query(self.fields.cve_description, primary, context);
+                boost_query("description", 1.0, Box::new(BooleanQuery::union(vec![q1, q2])), context)
             }
 
+            // the titles of the advisory and of its CVEs are weighted the same
             Vulnerabilities::Title(primary) => {
-                let q1 = boost(create_text_query(self.fields.advisory_title, primary), ADV_TITLE_WEIGHT);
-                let q2 = boost(create_text_query(self.fields.cve_title, primary), CVE_TITLE_WEIGHT);
-                Box::new(BooleanQuery::union(vec![q1, q2]))
+                let q1 = self.tokenizers.query(self.fields.advisory_title, primary, context);
+                let q2 = self.tokenizers.query(self.fields.cve_title, primary, context);
+                boost_query(
+                    "title",
+                    TITLE_WEIGHT,
+                    Box::new(BooleanQuery::union(vec![q1, q2])),
+                    context,
+                )
             }
 
             Vulnerabilities::Package(primary) => {
-                let q1 = create_rewrite_string_query(self.fields.cve_affected, primary);
-                let q2 = create_rewrite_string_query(self.fields.cve_fixed, primary);
-                let q3 = create_rewrite_string_query(self.fields.cve_not_affected, primary);
+                let q1 = create_rewrite_string_query(self.fields.cve_affected, primary, context);
+                let q2 = create_rewrite_string_query(self.fields.cve_fixed, primary, context);
+                let q3 = create_rewrite_string_query(self.fields.cve_not_affected, primary, context);
 
                 Box::new(BooleanQuery::union(vec![q1, q2, q3]))
             }
 
-            Vulnerabilities::Fixed(primary) => create_rewrite_string_query(self.fields.cve_fixed, primary),
+            Vulnerabilities::Fixed(primary) => create_rewrite_string_query(self.fields.cve_fixed, primary, context),
 
-            Vulnerabilities::Affected(primary) => create_rewrite_string_query(self.fields.cve_affected, primary),
+            Vulnerabilities::Affected(primary) => {
+                create_rewrite_string_query(self.fields.cve_affected, primary, context)
+            }
 
-            Vulnerabilities::NotAffected(primary) => create_rewrite_string_query(self.fields.cve_not_affected, primary),
+            Vulnerabilities::NotAffected(primary) => {
+                create_rewrite_string_query(self.fields.cve_not_affected, primary, context)
+            }
 
-            Vulnerabilities::Severity(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
-                self.fields.advisory_severity,
-                &value.to_ascii_lowercase(),
-            )])),
+            Vulnerabilities::Affects(purl) => match range::split_purl(&trustification_purl::normalize(purl)) {
+                (package, Some(version)) => Box::new(AffectsQuery {
+                    package_field: self.fields.cve_affected_package,
+                    range_field: self.schema.get_field_name(self.fields.cve_affected_range).to_string(),
+                    package: package.to_string(),
+                    version: version.to_string(),
+                }),
+                // without a version, any affected range of the package matches
+                (package, None) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                    self.fields.cve_affected_package,
+                    package,
+                )])),
+            },
+
+            Vulnerabilities::Severity(value) => match Severity::normalize(value) {
+                Some(severity) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                    self.fields.advisory_severity_normalized,
+                    severity.as_str(),
+                )])),
+                // labels which can't be normalized can only match as reported
+                None => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                    self.fields.advisory_severity,
+                    &value.to_ascii_lowercase(),
+                )])),
+            },
 
             Vulnerabilities::Status(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
                 self.fields.advisory_status,
                 value,
             )])),
 
-            Vulnerabilities::Final => create_string_query(self.fields.advisory_status, &Primary::Equal("final")),
-            Vulnerabilities::Critical => Box::new(TermSetQuery::new(vec![
-                Term::from_field_text(self.fields.cve_severity, "critical"),
-                Term::from_field_text(self.fields.advisory_severity, "critical"),
-            ])),
-            Vulnerabilities::High => Box::new(TermSetQuery::new(vec![
-                Term::from_field_text(self.fields.cve_severity, "high"),
-                Term::from_field_text(self.fields.advisory_severity, "important"),
-            ])),
-            Vulnerabilities::Medium => Box::new(TermSetQuery::new(vec![
-                Term::from_field_text(self.fields.cve_severity, "medium"),
-                Term::from_field_text(self.fields.advisory_severity, "moderate"),
-            ])),
-            Vulnerabilities::Low => Box::new(TermSetQuery::new(vec![
-                Term::from_field_text(self.fields.cve_severity, "low"),
-                Term::from_field_text(self.fields.advisory_severity, "low"),
-            ])),
+            Vulnerabilities::Publisher(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_publisher,
+                value,
+            )])),
+
+            Vulnerabilities::Category(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_category,
+                value,
+            )])),
+
+            Vulnerabilities::CsafVersion(value) => Box::new(TermSetQuery::new(vec![Term::from_field_text(
+                self.fields.advisory_csaf_version,
+                value,
+            )])),
+
+            Vulnerabilities::Final => {
+                create_string_query(self.fields.advisory_status, &Primary::Equal("final"), context)
+            }
+            Vulnerabilities::Critical => self.severity_query(Severity::Critical),
+            Vulnerabilities::High => self.severity_query(Severity::High),
+            Vulnerabilities::Medium => self.severity_query(Severity::Medium),
+            Vulnerabilities::Low => self.severity_query(Severity::Low),
             Vulnerabilities::Cvss(ordered) => create_float_query(&self.schema, [self.fields.cve_cvss], ordered),
             Vulnerabilities::Initial(ordered) => create_date_query(&self.schema, self.fields.advisory_initial, ordered),
             Vulnerabilities::Release(ordered) => create_date_query(&self.schema, self.fields.advisory_current, ordered),
@@ -673,6 +953,52 @@ fn find_product_ref<'m>(tree: &'m ProductTree, product_id: &ProductIdT) -> Optio
     None
 }
 
+/// Extract a remediation, identifying its products by their package URL or CPE.
+fn extract_remediation(csaf: &Csaf, cve: Option<&str>, remediation: &Remediation) -> AdvisoryRemediation {
+    let category = match serde_json::to_value(&remediation.category) {
+        Ok(Value::String(category)) => category,
+        _ => "unknown".to_string(),
+    };
+
+    let mut products = BTreeSet::new();
+    let mut fixed_versions = BTreeSet::new();
+    for product in remediation.product_ids.iter().flatten() {
+        let (pp, related_pp) = find_product_package(csaf, product);
+        let package = pp.as_ref().and_then(|p| p.purl.clone().or(p.cpe.clone()));
+        let related = related_pp.and_then(|p| p.cpe.or(p.purl));
+        products.insert(package.or(related).unwrap_or_else(|| product.0.clone()));
+
+        if let RemediationCategory::VendorFix = remediation.category {
+            match pp.and_then(|p| p.purl) {
+                Some(purl) => {
+                    if let (_, Some(version)) = range::split_purl(&purl) {
+                        fixed_versions.insert(version.to_string());
+                    }
+                }
+                // without a package URL, the package of a relationship, like a NEVRA, is the best we have
+                None => {
+                    let package = csaf
+                        .product_tree
+                        .as_ref()
+                        .and_then(|tree| find_product_ref(tree, product));
+                    if let Some((package, _)) = package {
+                        fixed_versions.insert(package.0.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    AdvisoryRemediation {
+        cve: cve.map(str::to_uppercase),
+        category,
+        details: remediation.details.clone(),
+        url: remediation.url.as_ref().map(ToString::to_string),
+        products: products.into_iter().collect(),
+        fixed_versions: fixed_versions.into_iter().collect(),
+    }
+}
+
 fn find_product_package(csaf: &Csaf, product_id: &ProductIdT) -> (Option<ProductPackage>, Option<ProductPackage>) {
     if let Some(tree) = &csaf.product_tree {
         if let Some((p_ref, p_ref_related)) = find_product_ref(tree, product_id) {
@@ -694,20 +1020,67 @@ fn find_product_package(csaf: &Csaf, product_id: &ProductIdT) -> (Option<Product
 
                 return (pp, related_pp);
             }
+        } else if let Some(branches) = &tree.branches {
+            // products without a relationship are referenced directly
+            return (find_direct_product_package(branches, product_id), None);
         }
     }
     (None, None)
 }
 
-fn create_rewrite_string_query(field: Field, primary: &Primary<'_>) -> Box<dyn Query> {
+/// Find the package of a product referenced directly, which must be identified by a package URL or CPE.
+///
+/// A product ID identifying different packages doesn't tell which one is meant, so none of them is returned.
+fn find_direct_product_package(branches: &BranchesT, product_id: &ProductIdT) -> Option<ProductPackage> {
+    let mut packages = Vec::new();
+    collect_product_packages(branches, product_id, &mut packages);
+    packages.dedup();
+    match packages.len() {
+        1 => packages.pop(),
+        0 => None,
+        _ => {
+            debug!(
+                "Product {} identifies {} packages, ignoring it",
+                product_id.0,
+                packages.len()
+            );
+            None
+        }
+    }
+}
+
+fn collect_product_packages(branches: &BranchesT, product_id: &ProductIdT, packages: &mut Vec<ProductPackage>) {
+    for branch in branches.0.iter() {
+        if let Some(product) = &branch.product {
+            if product.product_id.0 == product_id.0 {
+                if let Some(helper) = &product.product_identification_helper {
+                    let package = ProductPackage {
+                        purl: helper.purl.as_ref().map(|p| p.to_string()),
+                        cpe: helper.cpe.as_ref().map(|p| p.to_string()),
+                    };
+                    if package.purl.is_some() || package.cpe.is_some() {
+                        packages.push(package);
+                    }
+                }
+            }
+        }
+        if let Some(branches) = &branch.branches {
+            collect_product_packages(branches, product_id, packages);
+        }
+    }
+}
+
+fn create_rewrite_string_query(field: Field, primary: &Primary<'_>, context: &QueryContext) -> Box<dyn Query> {
     match primary {
-        Primary::Equal(value) => {
-            let rewrite = rewrite_cpe(value);
-            create_string_query(field, &Primary::Equal(&rewrite))
+        Primary::Equal(value) if pattern_value(value).is_none() => {
+            let rewrite = trustification_purl::normalize(value);
+            let rewrite = rewrite_cpe(&rewrite);
+            create_string_query(field, &Primary::Equal(&rewrite), context)
         }
+        Primary::Equal(value) => create_string_query(field, &Primary::Equal(value), context),
         Primary::Partial(value) => {
             let rewrite = rewrite_cpe_partial(value);
-            create_string_query(field, &Primary::Partial(&rewrite))
+            create_string_query(field, &Primary::Partial(&rewrite), context)
         }
     }
 }
@@ -736,6 +1109,7 @@ fn rewrite_cpe_partial(value: &str) -> String {
 mod tests {
     use std::fmt::Display;
     use time::format_description;
+    use trustification_api::search::{FacetCount, SearchFieldType};
     use trustification_index::IndexStore;
 
     use super::*;
@@ -764,7 +1138,10 @@ mod tests {
         let mut writer = store.writer().unwrap();
         for advisory in advisories {
             let data = std::fs::read_to_string(format!("../testdata/{}.json", advisory)).unwrap();
-            let csaf: Csaf = serde_json::from_str(&data).unwrap();
+            let csaf = document::VexDocument::parse(data.as_bytes())
+                .and_then(|vex| vex.into_advisory())
+                .unwrap()
+                .csaf;
 
             writer
                 .add_document(store.index_as_mut(), &csaf.document.tracking.id, data.as_bytes())
@@ -921,6 +1298,42 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_free_form_alternative_ranges() {
+        assert_search(|index| {
+            let result = search(&index, "release>2023-02-08");
+            assert_eq!(result.0.len(), 3);
+
+            let result = search(&index, "release:[2022-01-01 TO 2024-01-01]");
+            assert_eq!(result.0.len(), 3);
+
+            let result = search(&index, "release:[2022-01-01 TO 2023-01-01}");
+            assert_eq!(result.0.len(), 0);
+
+            let result = search(&index, "cveDiscovery:[2022-01-01 TO *]");
+            assert_eq!(result.0.len(), 3);
+
+            let result = search(&index, "cvss>5");
+            assert_eq!(result.0.len(), 4);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_cve_release_histogram() {
+        assert_search(|index| {
+            let buckets = index
+                .histogram(
+                    "",
+                    "cveRelease",
+                    std::time::Duration::from_secs(365 * 24 * 3600),
+                    Default::default(),
+                    None,
+                )
+                .unwrap();
+            assert!(buckets.iter().map(|bucket| bucket.count).sum::<u64>() > 0);
+        });
+    }
+
     #[tokio::test]
     async fn test_title_case_insensitive() {
         assert_search(|index| {
@@ -961,6 +1374,15 @@ mod tests {
             let result = search(&index, "severity:Important");
             assert_eq!(result.0.len(), 3);
 
+            // normalized to the same severity
+            let result = search(&index, "severity:high");
+            assert_eq!(result.0.len(), 3);
+
+            let result = search(&index, "severity:Moderate");
+            assert_eq!(result.0.len(), 1);
+            let result = search(&index, "severity:medium");
+            assert_eq!(result.0.len(), 1);
+
             let result = search(&index, "is:high");
             assert_eq!(result.0.len(), 4);
 
@@ -975,6 +1397,33 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_facets() {
+        assert_search(|index| {
+            let facets = index
+                .facets(
+                    "",
+                    &["severity".to_string(), "publisher".to_string(), "year".to_string()],
+                )
+                .unwrap();
+            let count = |value: &str, count| FacetCount {
+                value: value.to_string(),
+                count,
+            };
+            assert_eq!(facets["severity"], vec![count("important", 3), count("moderate", 1)]);
+
+            let facets = index.facets("", &["normalizedSeverity".to_string()]).unwrap();
+            assert_eq!(facets["normalizedSeverity"], vec![count("high", 3), count("medium", 1)]);
+            assert_eq!(facets["publisher"], vec![count("Red Hat Product Security", 4)]);
+            assert_eq!(facets["year"], vec![count("2023", 3), count("2021", 1)]);
+
+            let result = search(&index, r#"publisher:"Red Hat Product Security""#);
+            assert_eq!(result.0.len(), 4);
+
+            assert!(index.facets("", &["title".to_string()]).is_err());
+        });
+    }
+
     #[tokio::test]
     async fn test_packages() {
         assert_search(|index| {
@@ -1025,6 +1474,147 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_csaf_versions() {
+        assert_search_with(["rhsa-2023_1441", "csaf-2.1"], |index| {
+            let result = search(&index, r#"csafVersion:"2.1""#);
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.advisory_id, "EXAMPLE-2024-0001");
+
+            let result = search(&index, r#"csafVersion:"2.0""#);
+            assert_eq!(result.0.len(), 1);
+
+            let result = search(&index, "category:csaf_vex");
+            assert_eq!(result.0.len(), 2);
+
+            // the CVSS v4 score
+            let result = search(&index, "cvss:>9");
+            assert_eq!(result.0.len(), 1);
+            assert_eq!(result.0[0].document.cvss_max, Some(9.3));
+
+            let result = search(&index, "fixed:\"pkg:generic/example/libexample@1.2.4\"");
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_affects() {
+        assert_search_with(["rhsa-2023_1441", "quarkus-ranges"], |index| {
+            let affects = |purl: &str| {
+                search(&index, &format!(r#"affects:"{purl}""#))
+                    .0
+                    .into_iter()
+                    .map(|hit| hit.document.advisory_id)
+                    .collect::<Vec<_>>()
+            };
+
+            // in range
+            assert_eq!(
+                affects("pkg:maven/io.quarkus/quarkus-core@2.13.5"),
+                vec!["EXAMPLE-2024-0002".to_string()]
+            );
+            assert_eq!(affects("pkg:maven/io.quarkus/quarkus-core@2.13.0.Final").len(), 1);
+            // fixed
+            assert!(affects("pkg:maven/io.quarkus/quarkus-core@2.13.6.Final").is_empty());
+            assert!(affects("pkg:maven/io.quarkus/quarkus-core@2.12.0").is_empty());
+            // exact version, ignoring qualifiers
+            assert_eq!(affects("pkg:maven/io.quarkus/quarkus-core@2.16.1.Final").len(), 1);
+            assert_eq!(affects("pkg:maven/io.quarkus/quarkus-core@2.16.1").len(), 1);
+            // any version
+            assert_eq!(affects("pkg:maven/io.quarkus/quarkus-core").len(), 1);
+            assert!(affects("pkg:maven/io.quarkus/quarkus-vertx@2.13.5").is_empty());
+
+            assert_eq!(
+                affects("pkg:rpm/redhat/openssl@1.1.1k-7.el8_6?arch=x86_64"),
+                vec!["RHSA-2023:1441".to_string()]
+            );
+            assert!(affects("pkg:rpm/redhat/openssl@1.1.1k-8.el8_6").is_empty());
+
+            // combined with other terms
+            let result = search(&index, r#"affects:"pkg:maven/io.quarkus/quarkus-core@2.13.5" quarkus"#);
+            assert_eq!(result.0.len(), 1);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_openvex() {
+        let _ = env_logger::try_init();
+
+        let mut store = IndexStore::new_in_memory(Index::new()).unwrap();
+        let data = std::fs::read("../testdata/openvex.json").unwrap();
+        let mut writer = store.writer().unwrap();
+        writer
+            .add_document(store.index_as_mut(), "vex-9fb3463de1b57", &data)
+            .unwrap();
+        writer.commit().unwrap();
+
+        let result = search(&store, "cve:CVE-2023-12345");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "fixed:\"pkg:apk/wolfi/git@2.39.0-r1?arch=x86_64\"");
+        assert_eq!(result.0.len(), 1);
+
+        // package URLs are normalized
+        let result = search(&store, "fixed:\"PKG:APK/wolfi/git@2.39.0-r1?ARCH=x86_64&distro=\"");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "notAffected:\"pkg:apk/wolfi/git@2.38.1-r0?arch=x86_64\"");
+        assert_eq!(result.0.len(), 1);
+
+        let result = search(&store, "affected:\"pkg:apk/wolfi/curl@8.1.2-r0?arch=x86_64\"");
+        assert_eq!(result.0.len(), 1);
+    }
+
+    #[test]
+    fn test_direct_product_package() {
+        let branches: BranchesT = serde_json::from_value(json!([
+            {
+                "category": "product_version",
+                "name": "a",
+                "product": {
+                    "name": "a",
+                    "product_id": "a",
+                    "product_identification_helper": { "purl": "pkg:apk/wolfi/a@1" }
+                }
+            },
+            {
+                "category": "product_version",
+                "name": "b",
+                "product": {
+                    "name": "b",
+                    "product_id": "b",
+                    "product_identification_helper": { "purl": "pkg:apk/wolfi/b@1" }
+                }
+            },
+            {
+                "category": "product_version",
+                "name": "b",
+                "product": {
+                    "name": "b",
+                    "product_id": "b",
+                    "product_identification_helper": { "purl": "pkg:apk/wolfi/b@2" }
+                }
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            find_direct_product_package(&branches, &ProductIdT("a".to_string())),
+            Some(ProductPackage {
+                cpe: None,
+                purl: Some("pkg:apk/wolfi/a@1".to_string()),
+            })
+        );
+        assert_eq!(
+            find_direct_product_package(&branches, &ProductIdT("b".to_string())),
+            None
+        );
+        assert_eq!(
+            find_direct_product_package(&branches, &ProductIdT("c".to_string())),
+            None
+        );
+    }
+
     #[tokio::test]
     async fn test_delete_document() {
         assert_search(|mut index| {
@@ -1062,6 +1652,42 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_remediations() {
+        assert_search(|index| {
+            let result = search(&index, "id:\"RHSA-2023:1441\"");
+            assert_eq!(result.0.len(), 1);
+            let remediations = &result.0[0].document.remediations;
+            assert_eq!(remediations.len(), 1);
+            assert_eq!(remediations[0].cve.as_deref(), Some("CVE-2023-0286"));
+            assert_eq!(remediations[0].category, "vendor_fix");
+            assert_eq!(
+                remediations[0].products,
+                vec!["cpe:/o:redhat:rhel_eus:8.6::baseos".to_string()]
+            );
+            assert!(remediations[0]
+                .fixed_versions
+                .contains(&"openssl-1:1.1.1k-8.el8_6.x86_64".to_string()));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_release_dates() {
+        assert_search(|index| {
+            let result = search(&index, "id:\"RHSA-2023:1441\"");
+            assert_eq!(result.0.len(), 1);
+            let document = &result.0[0].document;
+            assert_eq!(
+                document.advisory_initial_date,
+                Some(OffsetDateTime::from_unix_timestamp(1679570040).unwrap())
+            );
+            assert_eq!(
+                document.cve_release_dates["CVE-2023-0286"],
+                OffsetDateTime::from_unix_timestamp(1675728000).unwrap()
+            );
+        });
+    }
+
     #[tokio::test]
     async fn test_sorting() {
         assert_search(|index| {
@@ -1079,6 +1705,50 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_sort_option() {
+        assert_search(|index| {
+            let sorted = |sort: &str| {
+                index
+                    .search(
+                        "",
+                        0,
+                        10000,
+                        SearchOptions {
+                            sort: Some(sort.to_string()),
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap()
+                    .0
+            };
+
+            let result = sorted("release:desc");
+            assert_eq!(result.len(), 4);
+            assert!(result
+                .windows(2)
+                .all(|w| w[0].document.advisory_date >= w[1].document.advisory_date));
+
+            let result = sorted("initial:asc");
+            assert_eq!(result[0].document.advisory_id, "RHSA-2021:3029");
+            assert!(result
+                .windows(2)
+                .all(|w| w[0].document.advisory_initial_date <= w[1].document.advisory_initial_date));
+
+            assert!(index
+                .search(
+                    "",
+                    0,
+                    10,
+                    SearchOptions {
+                        sort: Some("title:asc".to_string()),
+                        ..Default::default()
+                    }
+                )
+                .is_err());
+        });
+    }
+
     #[tokio::test]
     async fn test_sorting_noterms() {
         assert_search(|index| {
@@ -1113,6 +1783,7 @@ mod tests {
                         explain: false,
                         metadata: true,
                         summaries: true,
+                        ..Default::default()
                     },
                 )
                 .unwrap();
@@ -1126,4 +1797,25 @@ mod tests {
             }
         });
     }
+
+    #[tokio::test]
+    async fn test_search_fields() {
+        assert_search(|index| {
+            let fields = index.search_fields();
+            assert!(fields
+                .iter()
+                .any(|f| f.name == "release" && f.r#type == SearchFieldType::Date && f.sortable));
+            assert!(fields
+                .iter()
+                .any(|f| f.name == "cvss" && f.r#type == SearchFieldType::Number));
+            // all examples are valid queries, including the ones of qualifiers lacking a field, described as predicates
+            for field in fields {
+                assert!(
+                    index.search(&field.example, 0, 10, Default::default()).is_ok(),
+                    "{}",
+                    field.example
+                );
+            }
+        });
+    }
 }
@@ -0,0 +1,448 @@
+//! Support for [OpenVEX](https://github.com/openvex/spec) documents.
+//!
+//! OpenVEX documents are converted into CSAF VEX documents, so that they can be stored, indexed and consumed like any
+//! other advisory. Each product gets a branch of the product tree, identified by its `@id`, and each subcomponent a
+//! relationship to its product. Statements are grouped by vulnerability, and mapped to the product status, flags,
+//! threats and remediations of the CSAF vulnerability.
+
+use csaf::Csaf;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Justifications of OpenVEX, which match the labels of CSAF flags
+const JUSTIFICATIONS: [&str; 5] = [
+    "component_not_present",
+    "vulnerable_code_not_present",
+    "vulnerable_code_not_in_execute_path",
+    "vulnerable_code_cannot_be_controlled_by_adversary",
+    "inline_mitigations_already_exist",
+];
+
+/// An OpenVEX document.
+#[derive(Clone, Debug, Deserialize)]
+pub struct OpenVex {
+    #[serde(rename = "@context")]
+    pub context: String,
+    #[serde(rename = "@id")]
+    pub id: String,
+    pub author: String,
+    #[serde(default)]
+    pub role: Option<String>,
+    pub timestamp: String,
+    #[serde(default)]
+    pub last_updated: Option<String>,
+    /// The version, a number since v0.2.0 of the specification
+    #[serde(default)]
+    pub version: Value,
+    #[serde(default)]
+    pub statements: Vec<Statement>,
+}
+
+/// A statement about the status of products regarding a vulnerability.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Statement {
+    pub vulnerability: VulnerabilityRef,
+    #[serde(default)]
+    pub products: Vec<ProductRef>,
+    /// Subcomponents of all products, as used before v0.2.0 of the specification
+    #[serde(default)]
+    pub subcomponents: Vec<ProductRef>,
+    pub status: Status,
+    #[serde(default)]
+    pub justification: Option<String>,
+    #[serde(default)]
+    pub impact_statement: Option<String>,
+    #[serde(default)]
+    pub action_statement: Option<String>,
+    #[serde(default)]
+    pub status_notes: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    NotAffected,
+    Affected,
+    Fixed,
+    UnderInvestigation,
+}
+
+impl Status {
+    /// The matching category of the product status of a CSAF vulnerability.
+    fn product_status(&self) -> &'static str {
+        match self {
+            Self::NotAffected => "known_not_affected",
+            Self::Affected => "known_affected",
+            Self::Fixed => "fixed",
+            Self::UnderInvestigation => "under_investigation",
+        }
+    }
+}
+
+/// A vulnerability, either by its name or as a structure since v0.2.0 of the specification.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum VulnerabilityRef {
+    Name(String),
+    Vulnerability {
+        name: String,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        aliases: Vec<String>,
+    },
+}
+
+impl VulnerabilityRef {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(name) | Self::Vulnerability { name, .. } => name,
+        }
+    }
+}
+
+/// A product, either by its identifier or as a structure since v0.2.0 of the specification.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ProductRef {
+    Id(String),
+    Product {
+        #[serde(rename = "@id")]
+        id: String,
+        #[serde(default)]
+        identifiers: BTreeMap<String, String>,
+        #[serde(default)]
+        subcomponents: Vec<ProductRef>,
+    },
+}
+
+impl ProductRef {
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Id(id) | Self::Product { id, .. } => id,
+        }
+    }
+
+    fn subcomponents(&self) -> &[ProductRef] {
+        match self {
+            Self::Id(_) => &[],
+            Self::Product { subcomponents, .. } => subcomponents,
+        }
+    }
+
+    /// The product identification helper of the CSAF product.
+    fn helper(&self) -> Option<Value> {
+        let mut helper = Map::new();
+        let purl = match self {
+            Self::Product { identifiers, .. } => identifiers.get("purl").map(String::as_str),
+            Self::Id(_) => None,
+        }
+        .or_else(|| Some(self.id()).filter(|id| id.starts_with("pkg:")));
+        if let Some(purl) = purl {
+            helper.insert("purl".to_string(), purl.into());
+        }
+        if let Self::Product { identifiers, .. } = self {
+            if let Some(cpe) = identifiers.get("cpe23").or_else(|| identifiers.get("cpe22")) {
+                helper.insert("cpe".to_string(), cpe.as_str().into());
+            }
+        }
+        (!helper.is_empty()).then_some(Value::Object(helper))
+    }
+}
+
+/// The vulnerability of a CSAF document being assembled.
+#[derive(Default)]
+struct Vulnerability {
+    description: Option<String>,
+    aliases: Vec<String>,
+    product_status: BTreeMap<&'static str, Vec<String>>,
+    flags: BTreeMap<String, Vec<String>>,
+    threats: Vec<Value>,
+    remediations: Vec<Value>,
+    notes: Vec<Value>,
+}
+
+impl OpenVex {
+    /// Check if the context refers to the OpenVEX namespace.
+    pub fn is_openvex(&self) -> bool {
+        self.context.starts_with("https://openvex.dev/ns")
+    }
+
+    /// The identifier of the advisory: the last segment of the `@id` of the document.
+    pub fn advisory_id(&self) -> &str {
+        self.id
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|id| !id.is_empty())
+            .unwrap_or(&self.id)
+    }
+
+    fn version(&self) -> String {
+        match &self.version {
+            Value::String(version) => version.clone(),
+            Value::Null => "1".to_string(),
+            version => version.to_string(),
+        }
+    }
+
+    /// Convert the document into a CSAF VEX document.
+    pub fn to_csaf(&self) -> Result<Csaf, serde_json::Error> {
+        let mut branches = BTreeMap::new();
+        let mut relationships = BTreeMap::new();
+        let mut vulnerabilities: BTreeMap<&str, Vulnerability> = BTreeMap::new();
+
+        for statement in &self.statements {
+            let vulnerability = vulnerabilities.entry(statement.vulnerability.name()).or_default();
+            if let VulnerabilityRef::Vulnerability {
+                description, aliases, ..
+            } = &statement.vulnerability
+            {
+                if vulnerability.description.is_none() {
+                    vulnerability.description = description.clone();
+                }
+                vulnerability.aliases.extend(aliases.iter().cloned());
+            }
+
+            let mut product_ids = Vec::new();
+            for product in &statement.products {
+                branches.entry(product.id()).or_insert_with(|| branch(product));
+
+                let subcomponents = product.subcomponents().iter().chain(&statement.subcomponents);
+                let mut has_subcomponents = false;
+                for subcomponent in subcomponents {
+                    has_subcomponents = true;
+                    branches
+                        .entry(subcomponent.id())
+                        .or_insert_with(|| branch(subcomponent));
+                    let id = format!("{}:{}", product.id(), subcomponent.id());
+                    relationships
+                        .entry(id.clone())
+                        .or_insert_with(|| relationship(&id, product.id(), subcomponent.id()));
+                    product_ids.push(id);
+                }
+                if !has_subcomponents {
+                    product_ids.push(product.id().to_string());
+                }
+            }
+
+            vulnerability
+                .product_status
+                .entry(statement.status.product_status())
+                .or_default()
+                .extend(product_ids.iter().cloned());
+
+            let justification = statement
+                .justification
+                .as_ref()
+                .filter(|justification| JUSTIFICATIONS.contains(&justification.as_str()));
+            if let (Status::NotAffected, Some(justification)) = (statement.status, justification) {
+                vulnerability
+                    .flags
+                    .entry(justification.clone())
+                    .or_default()
+                    .extend(product_ids.iter().cloned());
+            }
+            if let Some(impact) = &statement.impact_statement {
+                vulnerability.threats.push(json!({
+                    "category": "impact",
+                    "details": impact,
+                    "product_ids": product_ids,
+                }));
+            }
+            if let Some(action) = &statement.action_statement {
+                vulnerability.remediations.push(json!({
+                    "category": "workaround",
+                    "details": action,
+                    "product_ids": product_ids,
+                }));
+            }
+            if let Some(notes) = &statement.status_notes {
+                vulnerability.notes.push(json!({
+                    "category": "other",
+                    "title": "Status notes",
+                    "text": notes,
+                }));
+            }
+        }
+
+        let vulnerabilities: Vec<_> = vulnerabilities
+            .into_iter()
+            .map(|(name, vulnerability)| self.vulnerability(name, vulnerability))
+            .collect();
+
+        let version = self.version();
+        let current = self.last_updated.as_ref().unwrap_or(&self.timestamp);
+        let mut csaf = json!({
+            "document": {
+                "category": "csaf_vex",
+                "csaf_version": "2.0",
+                "title": format!("OpenVEX document {}", self.id),
+                "publisher": {
+                    "category": "other",
+                    "name": self.author,
+                    "namespace": self.namespace(),
+                },
+                "tracking": {
+                    "id": self.advisory_id(),
+                    "status": "final",
+                    "version": version,
+                    "initial_release_date": self.timestamp,
+                    "current_release_date": current,
+                    "revision_history": [{
+                        "date": current,
+                        "number": version,
+                        "summary": "Converted from OpenVEX",
+                    }],
+                },
+            },
+            "product_tree": {
+                "branches": branches.into_values().collect::<Vec<_>>(),
+                "relationships": relationships.into_values().collect::<Vec<_>>(),
+            },
+            "vulnerabilities": vulnerabilities,
+        });
+
+        if self.namespace_origin().is_some() {
+            csaf["document"]["references"] = json!([{
+                "category": "self",
+                "summary": "The original OpenVEX document",
+                "url": self.id,
+            }]);
+        }
+
+        serde_json::from_value(csaf)
+    }
+
+    /// The origin of the `@id`, if it is a URL.
+    fn namespace_origin(&self) -> Option<String> {
+        match self.id.split_once("://") {
+            Some((scheme, rest)) if scheme == "https" || scheme == "http" => {
+                let host = rest.split('/').next().unwrap_or_default();
+                (!host.is_empty()).then(|| format!("{scheme}://{host}"))
+            }
+            _ => None,
+        }
+    }
+
+    /// The namespace of the publisher, falling back to the one of OpenVEX if the `@id` isn't a URL.
+    fn namespace(&self) -> String {
+        self.namespace_origin()
+            .unwrap_or_else(|| "https://openvex.dev".to_string())
+    }
+
+    fn vulnerability(&self, name: &str, vulnerability: Vulnerability) -> Value {
+        let mut result = Map::new();
+        result.insert("title".to_string(), name.into());
+        if is_cve(name) {
+            result.insert("cve".to_string(), name.into());
+        } else if let Some(cve) = vulnerability.aliases.iter().find(|alias| is_cve(alias)) {
+            result.insert("cve".to_string(), cve.as_str().into());
+        }
+
+        let ids: Vec<_> = std::iter::once(name)
+            .chain(vulnerability.aliases.iter().map(String::as_str))
+            .filter(|id| !is_cve(id))
+            .map(|id| json!({ "system_name": "OpenVEX", "text": id }))
+            .collect();
+        if !ids.is_empty() {
+            result.insert("ids".to_string(), ids.into());
+        }
+
+        let mut notes = vulnerability.notes;
+        if let Some(description) = vulnerability.description {
+            notes.insert(0, json!({ "category": "description", "text": description }));
+        }
+        if !notes.is_empty() {
+            result.insert("notes".to_string(), notes.into());
+        }
+
+        result.insert("product_status".to_string(), json!(vulnerability.product_status));
+        if !vulnerability.flags.is_empty() {
+            let flags: Vec<_> = vulnerability
+                .flags
+                .into_iter()
+                .map(|(label, product_ids)| json!({ "label": label, "product_ids": product_ids }))
+                .collect();
+            result.insert("flags".to_string(), flags.into());
+        }
+        if !vulnerability.threats.is_empty() {
+            result.insert("threats".to_string(), vulnerability.threats.into());
+        }
+        if !vulnerability.remediations.is_empty() {
+            result.insert("remediations".to_string(), vulnerability.remediations.into());
+        }
+
+        Value::Object(result)
+    }
+}
+
+fn is_cve(id: &str) -> bool {
+    id.starts_with("CVE-")
+}
+
+fn branch(product: &ProductRef) -> Value {
+    let mut full_product_name = json!({
+        "name": product.id(),
+        "product_id": product.id(),
+    });
+    if let Some(helper) = product.helper() {
+        full_product_name["product_identification_helper"] = helper;
+    }
+    json!({
+        "category": "product_version",
+        "name": product.id(),
+        "product": full_product_name,
+    })
+}
+
+fn relationship(id: &str, product: &str, subcomponent: &str) -> Value {
+    json!({
+        "category": "default_component_of",
+        "full_product_name": {
+            "name": format!("{subcomponent} as a component of {product}"),
+            "product_id": id,
+        },
+        "product_reference": subcomponent,
+        "relates_to_product_reference": product,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let data = std::fs::read("../testdata/openvex.json").unwrap();
+        let openvex: OpenVex = serde_json::from_slice(&data).unwrap();
+        assert!(openvex.is_openvex());
+        assert_eq!(openvex.advisory_id(), "vex-9fb3463de1b57");
+
+        let csaf = openvex.to_csaf().unwrap();
+        assert_eq!(csaf.document.tracking.id, "vex-9fb3463de1b57");
+        assert_eq!(csaf.document.tracking.version, "2");
+        assert_eq!(csaf.document.publisher.name, "Wolfi J Inkinson");
+
+        let vulns = csaf.vulnerabilities.unwrap();
+        assert_eq!(vulns.len(), 2);
+        assert_eq!(vulns[0].cve.as_deref(), Some("CVE-2023-12345"));
+
+        let status = vulns[0].product_status.as_ref().unwrap();
+        assert_eq!(status.fixed.as_ref().unwrap().len(), 1);
+        assert_eq!(
+            status.known_not_affected.as_ref().unwrap()[0].0,
+            "pkg:oci/git@sha256:23a264e6e429852221a963e9f17338ba3f5796dc7086e46439a6f4482cf6e0cb:pkg:apk/wolfi/git@2.38.1-r0?arch=x86_64"
+        );
+        assert_eq!(vulns[0].flags.as_ref().unwrap().len(), 1);
+
+        let status = vulns[1].product_status.as_ref().unwrap();
+        assert_eq!(status.known_affected.as_ref().unwrap().len(), 1);
+        assert_eq!(vulns[1].remediations.as_ref().unwrap().len(), 1);
+
+        let tree = csaf.product_tree.unwrap();
+        assert_eq!(tree.branches.unwrap().0.len(), 4);
+        assert_eq!(tree.relationships.unwrap().len(), 1);
+    }
+}
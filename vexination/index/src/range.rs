@@ -0,0 +1,523 @@
+//! Affected version ranges.
+//!
+//! Advisories declare affected versions either as ranges, using branches of the category `product_version_range`, or
+//! by the version of package URLs. Both are indexed as ranges of the package, so that queries like
+//! `affects:"pkg:maven/io.quarkus/quarkus-core@2.13.5"` can evaluate whether a version is affected, without requiring
+//! an exact match.
+//!
+//! Ranges use the constraints of the [vers](https://github.com/package-url/purl-spec/blob/master/VERSION-RANGE-SPEC.rst)
+//! specification. Versions are compared segment by segment, which works for semantic versions as well as Maven
+//! versions.
+
+use csaf::definitions::{Branch, BranchCategory, BranchesT, ProductIdT};
+use csaf::Csaf;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use trustification_index::tantivy::{
+    self,
+    query::{EnableScoring, Explanation, Query, Scorer, TermQuery, Weight},
+    schema::{Field, IndexRecordOption, Term},
+    DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED,
+};
+
+/// Split a package URL into the package, without qualifiers, and its version.
+pub fn split_purl(purl: &str) -> (&str, Option<&str>) {
+    let purl = purl.split(['?', '#']).next().unwrap_or(purl);
+    match purl.rsplit_once('@') {
+        Some((package, version)) if !version.is_empty() => (package, Some(version)),
+        Some((package, _)) => (package, None),
+        None => (purl, None),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparator {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Constraint {
+    comparator: Comparator,
+    version: String,
+}
+
+/// A range of versions, as a list of constraints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionRange {
+    /// Constraints, sorted by version. Empty if all versions are in range.
+    constraints: Vec<Constraint>,
+}
+
+/// The comparators of constraints, by their prefix, longer prefixes first
+const COMPARATORS: [(&str, Comparator); 6] = [
+    ("!=", Comparator::NotEqual),
+    ("<=", Comparator::LessOrEqual),
+    (">=", Comparator::GreaterOrEqual),
+    ("<", Comparator::Less),
+    (">", Comparator::Greater),
+    ("=", Comparator::Equal),
+];
+
+impl VersionRange {
+    /// Parse a range, either as `vers` string, or as its constraints only.
+    ///
+    /// Constraints are separated by `|`, `,` or whitespace.
+    pub fn parse(range: &str) -> Option<Self> {
+        let constraints = match range.strip_prefix("vers:") {
+            Some(range) => range.split_once('/')?.1,
+            None => range,
+        };
+        if constraints.trim() == "*" {
+            return Some(Self { constraints: vec![] });
+        }
+
+        let mut result = Vec::new();
+        let mut tokens = constraints.split(['|', ',']).flat_map(str::split_whitespace).peekable();
+        while let Some(token) = tokens.next() {
+            let (comparator, version) = COMPARATORS
+                .into_iter()
+                .find_map(|(prefix, comparator)| token.strip_prefix(prefix).map(|version| (comparator, version)))
+                .unwrap_or((Comparator::Equal, token));
+            // a comparator may be separated from its version by whitespace, like ">= 2.13.0"
+            let version = match version.is_empty() {
+                true => tokens.next_if(|next| !next.starts_with(['!', '<', '>', '=']))?,
+                false => version,
+            };
+            result.push(Constraint {
+                comparator,
+                version: version.to_string(),
+            });
+        }
+        if result.is_empty() {
+            return None;
+        }
+
+        result.sort_by(|a, b| compare_versions(&a.version, &b.version));
+        Some(Self { constraints: result })
+    }
+
+    /// A range containing a single version.
+    pub fn exact(version: &str) -> Self {
+        Self::single(Comparator::Equal, version)
+    }
+
+    /// A range of all versions starting with a version.
+    pub fn starting(version: &str) -> Self {
+        Self::single(Comparator::GreaterOrEqual, version)
+    }
+
+    /// A range of all versions up to a version.
+    pub fn ending(version: &str) -> Self {
+        Self::single(Comparator::LessOrEqual, version)
+    }
+
+    fn single(comparator: Comparator, version: &str) -> Self {
+        Self {
+            constraints: vec![Constraint {
+                comparator,
+                version: version.to_string(),
+            }],
+        }
+    }
+
+    /// Check if a version is in range.
+    pub fn contains(&self, version: &str) -> bool {
+        if self.constraints.is_empty() {
+            return true;
+        }
+
+        let mut ranges = Vec::new();
+        for constraint in &self.constraints {
+            let ordering = compare_versions(version, &constraint.version);
+            match constraint.comparator {
+                Comparator::Equal if ordering == Ordering::Equal => return true,
+                Comparator::NotEqual if ordering == Ordering::Equal => return false,
+                Comparator::Equal | Comparator::NotEqual => {}
+                _ => ranges.push((constraint.comparator, ordering)),
+            }
+        }
+
+        // pair up lower and upper bounds into intervals, a missing bound is unbounded
+        let mut lower = None;
+        for (comparator, ordering) in ranges {
+            match comparator {
+                Comparator::Greater | Comparator::GreaterOrEqual => {
+                    lower = Some((comparator, ordering));
+                }
+                _ => {
+                    let above_lower = match lower.take() {
+                        None => true,
+                        Some((Comparator::Greater, ordering)) => ordering == Ordering::Greater,
+                        Some((_, ordering)) => ordering != Ordering::Less,
+                    };
+                    let below_upper = match comparator {
+                        Comparator::Less => ordering == Ordering::Less,
+                        _ => ordering != Ordering::Greater,
+                    };
+                    if above_lower && below_upper {
+                        return true;
+                    }
+                }
+            }
+        }
+        match lower {
+            Some((Comparator::Greater, ordering)) => ordering == Ordering::Greater,
+            Some((_, ordering)) => ordering != Ordering::Less,
+            None => false,
+        }
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.constraints.is_empty() {
+            return f.write_str("*");
+        }
+        for (n, constraint) in self.constraints.iter().enumerate() {
+            if n > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "{}{}", constraint.comparator.as_str(), constraint.version)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment<'a> {
+    /// Qualifiers of pre-releases, like `alpha` or `SNAPSHOT`
+    PreRelease(&'a str),
+    /// The end of a version, or a qualifier of a release, like `Final`
+    Release,
+    Number(u64),
+    /// Any other qualifier, following a release
+    Qualifier(&'a str),
+}
+
+impl<'a> Segment<'a> {
+    fn new(segment: &'a str) -> Self {
+        if let Ok(number) = segment.parse() {
+            return Self::Number(number);
+        }
+        // qualifiers may be numbered, like `CR1`
+        let qualifier = segment.to_ascii_lowercase();
+        match qualifier.trim_end_matches(|c: char| c.is_ascii_digit()) {
+            "final" | "ga" | "release" => Self::Release,
+            "alpha" | "a" | "beta" | "b" | "milestone" | "m" | "rc" | "cr" | "snapshot" | "dev" | "pre" => {
+                Self::PreRelease(segment)
+            }
+            _ => Self::Qualifier(segment),
+        }
+    }
+
+    /// The segment a shorter version is padded with, when compared to this segment.
+    fn padding(&self) -> Self {
+        match self {
+            Self::Number(_) => Self::Number(0),
+            _ => Self::Release,
+        }
+    }
+}
+
+/// Compare two versions, segment by segment.
+///
+/// Numeric segments are compared as numbers, pre-release qualifiers order before the release, and other qualifiers
+/// after it.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let segments = |version: &'_ str| {
+        let version = version.trim_start_matches(['v', 'V']);
+        // build metadata of semantic versions doesn't take part in comparing
+        let version = version.split('+').next().unwrap_or(version);
+        version
+            .split(['.', '-', '_'])
+            .filter(|segment| !segment.is_empty())
+            .map(Segment::new)
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (segments(a), segments(b));
+
+    for n in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(n), b.get(n)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(a), None) => a.cmp(&a.padding()),
+            (None, Some(b)) => b.padding().cmp(b),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Collect the affected ranges of a product, as pairs of package and range.
+///
+/// The comparator is used for products having a versioned package URL, rather than a range.
+pub(crate) fn product_ranges(
+    csaf: &Csaf,
+    product_id: &ProductIdT,
+    single: fn(&str) -> VersionRange,
+) -> Vec<(String, VersionRange)> {
+    let Some(tree) = &csaf.product_tree else {
+        return vec![];
+    };
+    let Some(branches) = &tree.branches else {
+        return vec![];
+    };
+
+    // the component of a relationship declares the package
+    let mut ids = vec![product_id];
+    for relationship in tree.relationships.iter().flatten() {
+        if relationship.full_product_name.product_id.0 == product_id.0 {
+            ids.push(&relationship.product_reference);
+        }
+    }
+
+    let mut result = Vec::new();
+    for id in ids {
+        let mut path = Vec::new();
+        if !trace(branches, &id.0, &mut path) {
+            continue;
+        }
+        let purl = path
+            .last()
+            .and_then(|branch| branch.product.as_ref())
+            .and_then(|product| product.product_identification_helper.as_ref())
+            .and_then(|helper| helper.purl.as_ref())
+            .map(|purl| purl.to_string());
+        let Some(purl) = purl else {
+            continue;
+        };
+
+        let (package, version) = split_purl(&purl);
+        let range = path
+            .iter()
+            .rev()
+            .find(|branch| matches!(branch.category, BranchCategory::ProductVersionRange))
+            .and_then(|branch| VersionRange::parse(&branch.name))
+            .or_else(|| version.map(single));
+        if let Some(range) = range {
+            result.push((package.to_string(), range));
+        }
+    }
+    result
+}
+
+/// Find the path of branches leading to a product.
+fn trace<'a>(branches: &'a BranchesT, product_id: &str, path: &mut Vec<&'a Branch>) -> bool {
+    for branch in &branches.0 {
+        path.push(branch);
+        if branch
+            .product
+            .as_ref()
+            .map(|p| p.product_id.0 == product_id)
+            .unwrap_or(false)
+        {
+            return true;
+        }
+        if let Some(branches) = &branch.branches {
+            if trace(branches, product_id, path) {
+                return true;
+            }
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Encode a range of a package as a value of the index.
+pub(crate) fn encode(package: &str, range: &VersionRange) -> String {
+    format!("{package} {range}")
+}
+
+/// Matches documents having a range of the package, which contains the version.
+///
+/// Candidates are selected by the package, the ranges are then evaluated against the fast field of encoded ranges.
+#[derive(Clone, Debug)]
+pub(crate) struct AffectsQuery {
+    pub package_field: Field,
+    /// The name of the field of encoded ranges
+    pub range_field: String,
+    pub package: String,
+    pub version: String,
+}
+
+impl Query for AffectsQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let term = Term::from_field_text(self.package_field, &self.package);
+        Ok(Box::new(AffectsWeight {
+            inner: TermQuery::new(term, IndexRecordOption::Basic).weight(enable_scoring)?,
+            query: self.clone(),
+        }))
+    }
+}
+
+struct AffectsWeight {
+    inner: Box<dyn Weight>,
+    query: AffectsQuery,
+}
+
+impl AffectsWeight {
+    fn affects(&self, ranges: &[String]) -> bool {
+        ranges.iter().any(|value| match value.split_once(' ') {
+            Some((package, range)) if package == self.query.package => VersionRange::parse(range)
+                .map(|range| range.contains(&self.query.version))
+                .unwrap_or(false),
+            _ => false,
+        })
+    }
+}
+
+impl Weight for AffectsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let mut docs = Vec::new();
+        if let Some(column) = reader.fast_fields().str(&self.query.range_field)? {
+            let mut scorer = self.inner.scorer(reader, boost)?;
+            let mut value = String::new();
+            let mut doc = scorer.doc();
+            while doc != TERMINATED {
+                let mut ranges = Vec::new();
+                for ord in column.term_ords(doc) {
+                    value.clear();
+                    if column.ord_to_str(ord, &mut value)? {
+                        ranges.push(value.clone());
+                    }
+                }
+                if self.affects(&ranges) {
+                    docs.push((doc, scorer.score()));
+                }
+                doc = scorer.advance();
+            }
+        }
+        Ok(Box::new(MatchingDocs { docs, cursor: 0 }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new(
+            format!("{}@{} is in an affected range", self.query.package, self.query.version),
+            scorer.score(),
+        ))
+    }
+}
+
+/// The documents matched by a query, in ascending order.
+struct MatchingDocs {
+    docs: Vec<(DocId, Score)>,
+    cursor: usize,
+}
+
+impl DocSet for MatchingDocs {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.docs.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.docs.get(self.cursor).map(|(doc, _)| *doc).unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.docs.len() as u32
+    }
+}
+
+impl Scorer for MatchingDocs {
+    fn score(&mut self) -> Score {
+        self.docs.get(self.cursor).map(|(_, score)| *score).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("2.13.5", "2.13.5"), Ordering::Equal);
+        assert_eq!(compare_versions("2.13.5", "2.13.10"), Ordering::Less);
+        assert_eq!(compare_versions("2.13.5.Final", "2.13.5"), Ordering::Equal);
+        assert_eq!(compare_versions("2.13.0.CR1", "2.13.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0-alpha.1", "1.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0-alpha.1", "1.0.0-beta"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0+build.5", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-redhat-00001", "1.0.0"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.2", "1.1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0", "1.0.0-alpha"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_split_purl() {
+        assert_eq!(
+            split_purl("pkg:maven/io.quarkus/quarkus-core@2.13.5.Final?type=jar"),
+            ("pkg:maven/io.quarkus/quarkus-core", Some("2.13.5.Final"))
+        );
+        assert_eq!(
+            split_purl("pkg:maven/io.quarkus/quarkus-core"),
+            ("pkg:maven/io.quarkus/quarkus-core", None)
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        let range = VersionRange::parse("vers:maven/>=2.13.0|<2.13.6").unwrap();
+        assert_eq!(range.to_string(), ">=2.13.0|<2.13.6");
+        assert!(range.contains("2.13.0"));
+        assert!(range.contains("2.13.5.Final"));
+        assert!(!range.contains("2.13.6"));
+        assert!(!range.contains("2.12.9"));
+
+        let range = VersionRange::parse("vers:npm/<1.0.0|>=2.0.0|<2.1.0|!=2.0.5|=3.0.0").unwrap();
+        assert!(range.contains("0.9.0"));
+        assert!(!range.contains("1.5.0"));
+        assert!(range.contains("2.0.4"));
+        assert!(!range.contains("2.0.5"));
+        assert!(range.contains("3.0.0"));
+        assert!(!range.contains("3.0.1"));
+
+        let range = VersionRange::parse(">=1.5").unwrap();
+        assert!(range.contains("10.0"));
+        assert!(!range.contains("1.4"));
+
+        assert!(VersionRange::parse("vers:maven/*").unwrap().contains("1.0"));
+        assert!(VersionRange::parse("vers:maven/").is_none());
+        assert!(VersionRange::parse("vers:maven/>=").is_none());
+
+        let range = VersionRange::parse(">= 2.13.0, < 2.13.6").unwrap();
+        assert_eq!(range.to_string(), ">=2.13.0|<2.13.6");
+        assert!(range.contains("2.13.5"));
+        assert!(!range.contains("2.13.6"));
+        assert_eq!(VersionRange::parse(">=1.0 <2.0").unwrap().to_string(), ">=1.0|<2.0");
+        assert!(VersionRange::parse(">= <2.0").is_none());
+
+        assert!(VersionRange::exact("1.0").contains("1.0.0"));
+        assert!(VersionRange::ending("1.0").contains("0.9"));
+        assert!(!VersionRange::starting("1.0").contains("0.9"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Validation of CSAF documents, without indexing or storing them.
+//!
+//! Next to the schema, which is checked by parsing the document, a subset of the mandatory tests of the CSAF
+//! specification is run on the JSON document. That way findings can point to the offending value.
+
+use crate::document::VexDocument;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use vexination_model::prelude::{AdvisoryValidation, Severity, ValidationFinding};
+
+/// Fields which must be present, and not empty
+const MANDATORY_FIELDS: [&str; 11] = [
+    "/document/category",
+    "/document/csaf_version",
+    "/document/title",
+    "/document/publisher/category",
+    "/document/publisher/name",
+    "/document/publisher/namespace",
+    "/document/tracking/id",
+    "/document/tracking/version",
+    "/document/tracking/status",
+    "/document/tracking/initial_release_date",
+    "/document/tracking/current_release_date",
+];
+
+/// Groups of product status, products must only be in one of them
+const STATUS_GROUPS: [(&str, &[&str]); 4] = [
+    ("affected", &["first_affected", "known_affected", "last_affected"]),
+    ("not affected", &["known_not_affected"]),
+    ("fixed", &["first_fixed", "fixed"]),
+    ("under investigation", &["under_investigation"]),
+];
+
+/// Tolerance when comparing a stated CVSS score with the computed one
+const SCORE_TOLERANCE: f64 = 0.05;
+
+/// Validate a CSAF document.
+pub fn validate(data: &[u8]) -> AdvisoryValidation {
+    let value: Value = match serde_json::from_slice(data) {
+        Ok(value) => value,
+        Err(err) => {
+            return AdvisoryValidation::new(vec![ValidationFinding::error("json", format!("Malformed JSON: {err}"))])
+        }
+    };
+
+    let mut findings = vec![];
+    match VexDocument::parse(data) {
+        Ok(VexDocument::Csaf(_)) => {}
+        Ok(VexDocument::OpenVex(_)) => {
+            return AdvisoryValidation::new(vec![ValidationFinding::error(
+                "format",
+                "The document is an OpenVEX document, only CSAF documents can be validated",
+            )])
+        }
+        Ok(VexDocument::CycloneDx(_)) => {
+            return AdvisoryValidation::new(vec![ValidationFinding::error(
+                "format",
+                "The document is a CycloneDX document, only CSAF documents can be validated",
+            )])
+        }
+        // the semantic checks are still run, they point to the offending values more precisely
+        Err(err) => findings.push(ValidationFinding::error(
+            "schema",
+            format!("The document doesn't match the CSAF schema: {err}"),
+        )),
+    }
+
+    mandatory_fields(&value, &mut findings);
+    tracking(&value, &mut findings);
+    product_references(&value, &mut findings);
+    product_status(&value, &mut findings);
+    cves(&value, &mut findings);
+    cvss(&value, &mut findings);
+
+    AdvisoryValidation::new(findings)
+}
+
+/// Iterate over the entries of an array, along with their path.
+fn entries<'a>(value: &'a Value, path: &str) -> impl Iterator<Item = (String, &'a Value)> + 'a {
+    let path = path.to_string();
+    value
+        .pointer(&path)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(move |(i, entry)| (format!("{path}/{i}"), entry))
+}
+
+fn mandatory_fields(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    for path in MANDATORY_FIELDS {
+        match value.pointer(path) {
+            None | Some(Value::Null) => {
+                findings.push(ValidationFinding::error("mandatory-field", "Missing mandatory field").at(path))
+            }
+            Some(Value::String(s)) if !s.trim().is_empty() => {}
+            Some(_) => {
+                findings.push(ValidationFinding::error("mandatory-field", "Must be a non-empty string").at(path))
+            }
+        }
+    }
+
+    let path = "/document/tracking/revision_history";
+    if !matches!(value.pointer(path), Some(Value::Array(history)) if !history.is_empty()) {
+        findings.push(ValidationFinding::error("mandatory-field", "The revision history must not be empty").at(path));
+    }
+}
+
+fn tracking(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    let date = |path: &str, findings: &mut Vec<ValidationFinding>| {
+        let date = value.pointer(path).and_then(Value::as_str)?;
+        match OffsetDateTime::parse(date, &Rfc3339) {
+            Ok(date) => Some(date),
+            Err(err) => {
+                findings.push(ValidationFinding::error("date", format!("Invalid date '{date}': {err}")).at(path));
+                None
+            }
+        }
+    };
+
+    let initial = date("/document/tracking/initial_release_date", findings);
+    let current = date("/document/tracking/current_release_date", findings);
+    if let (Some(initial), Some(current)) = (initial, current) {
+        if current < initial {
+            findings.push(
+                ValidationFinding::warning("date", "The current release date is before the initial release date")
+                    .at("/document/tracking/current_release_date"),
+            );
+        }
+    }
+
+    // the document version must be the number of the latest revision
+    let mut latest: Option<(OffsetDateTime, &str)> = None;
+    for (path, revision) in entries(value, "/document/tracking/revision_history") {
+        let Some(date) = date(&format!("{path}/date"), findings) else {
+            continue;
+        };
+        let Some(number) = revision["number"].as_str() else {
+            continue;
+        };
+        if latest.map(|(latest, _)| date >= latest).unwrap_or(true) {
+            latest = Some((date, number));
+        }
+    }
+    if let (Some((_, number)), Some(version)) = (latest, value.pointer("/document/tracking/version")) {
+        if version.as_str().is_some_and(|version| version != number) {
+            findings.push(
+                ValidationFinding::error(
+                    "tracking-version",
+                    format!("The version doesn't match the number of the latest revision ({number})"),
+                )
+                .at("/document/tracking/version"),
+            );
+        }
+    }
+}
+
+fn product_references(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    let mut products = HashMap::<String, String>::new();
+    let mut define = |id: Option<&Value>, path: String, findings: &mut Vec<ValidationFinding>| {
+        let Some(id) = id.and_then(Value::as_str) else {
+            return;
+        };
+        match products.get(id) {
+            Some(first) => findings.push(
+                ValidationFinding::error(
+                    "product-reference",
+                    format!("Product ID '{id}' is already defined at {first}"),
+                )
+                .at(path),
+            ),
+            None => {
+                products.insert(id.to_string(), path);
+            }
+        }
+    };
+
+    let mut branches: Vec<_> = entries(value, "/product_tree/branches").collect();
+    while let Some((path, branch)) = branches.pop() {
+        define(
+            branch.pointer("/product/product_id"),
+            format!("{path}/product/product_id"),
+            findings,
+        );
+        branches.extend(entries(branch, "/branches").map(|(sub, branch)| (format!("{path}{sub}"), branch)));
+    }
+    for (path, product) in entries(value, "/product_tree/full_product_names") {
+        define(product.get("product_id"), format!("{path}/product_id"), findings);
+    }
+    for (path, relationship) in entries(value, "/product_tree/relationships") {
+        define(
+            relationship.pointer("/full_product_name/product_id"),
+            format!("{path}/full_product_name/product_id"),
+            findings,
+        );
+    }
+
+    let mut groups = HashSet::new();
+    for (_, group) in entries(value, "/product_tree/product_groups") {
+        groups.extend(group["group_id"].as_str());
+    }
+
+    let mut check = |path: &str, kind: &str, defined: &dyn Fn(&str) -> bool| {
+        for (path, id) in entries(value, path) {
+            if let Some(id) = id.as_str() {
+                if !defined(id) {
+                    findings.push(
+                        ValidationFinding::error("product-reference", format!("{kind} ID '{id}' is not defined"))
+                            .at(path),
+                    );
+                }
+            }
+        }
+    };
+    let product = |id: &str| products.contains_key(id);
+    let group = |id: &str| groups.contains(id);
+
+    for (path, _) in entries(value, "/product_tree/product_groups") {
+        check(&format!("{path}/product_ids"), "Product", &product);
+    }
+    for (path, _) in entries(value, "/product_tree/relationships") {
+        check(&format!("{path}/product_reference"), "Product", &product);
+        check(&format!("{path}/relates_to_product_reference"), "Product", &product);
+    }
+    for (path, vulnerability) in entries(value, "/vulnerabilities") {
+        if let Some(status) = vulnerability.get("product_status").and_then(Value::as_object) {
+            for category in status.keys() {
+                check(&format!("{path}/product_status/{category}"), "Product", &product);
+            }
+        }
+        for (path, _) in entries(value, &format!("{path}/scores")) {
+            check(&format!("{path}/products"), "Product", &product);
+        }
+        for list in ["remediations", "threats", "flags"] {
+            for (path, _) in entries(value, &format!("{path}/{list}")) {
+                check(&format!("{path}/product_ids"), "Product", &product);
+                check(&format!("{path}/group_ids"), "Product group", &group);
+            }
+        }
+    }
+}
+
+fn product_status(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    for (path, _) in entries(value, "/vulnerabilities") {
+        let mut statuses = HashMap::<&str, &str>::new();
+        for (group, categories) in STATUS_GROUPS {
+            for category in categories {
+                let category = format!("{path}/product_status/{category}");
+                for (path, id) in entries(value, &category) {
+                    let Some(id) = id.as_str() else {
+                        continue;
+                    };
+                    match statuses.insert(id, group) {
+                        Some(other) if other != group => findings.push(
+                            ValidationFinding::error(
+                                "product-status",
+                                format!("Product '{id}' is both {other} and {group}"),
+                            )
+                            .at(path),
+                        ),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn cves(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    let mut seen = HashSet::new();
+    for (path, vulnerability) in entries(value, "/vulnerabilities") {
+        let Some(cve) = vulnerability.get("cve").and_then(Value::as_str) else {
+            continue;
+        };
+        let path = format!("{path}/cve");
+        if !is_cve(cve) {
+            findings.push(ValidationFinding::error("cve", format!("Invalid CVE ID '{cve}'")).at(&path));
+        }
+        if !seen.insert(cve) {
+            findings.push(ValidationFinding::error("cve", format!("CVE '{cve}' is listed multiple times")).at(&path));
+        }
+    }
+}
+
+/// Check if the value has the format of a CVE ID, like `CVE-2023-0286`.
+fn is_cve(value: &str) -> bool {
+    let mut parts = value.splitn(3, '-');
+    let (Some("CVE"), Some(year), Some(number)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    year.len() == 4
+        && number.len() >= 4
+        && year.chars().all(|c| c.is_ascii_digit())
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+fn cvss(value: &Value, findings: &mut Vec<ValidationFinding>) {
+    for (path, _) in entries(value, "/vulnerabilities") {
+        for (path, score) in entries(value, &format!("{path}/scores")) {
+            let Some(metric) = score.get("cvss_v3") else {
+                continue;
+            };
+            let path = format!("{path}/cvss_v3");
+            let Some(vector) = metric["vectorString"].as_str() else {
+                continue;
+            };
+
+            let base = match cvss::v3::Base::from_str(vector) {
+                Ok(base) => base,
+                Err(err) => {
+                    findings.push(
+                        ValidationFinding::error("cvss", format!("Invalid CVSS v3 vector '{vector}': {err}"))
+                            .at(format!("{path}/vectorString")),
+                    );
+                    continue;
+                }
+            };
+
+            let version = format!("3.{}", base.minor_version);
+            if metric["version"].as_str().is_some_and(|stated| stated != version) {
+                findings.push(
+                    ValidationFinding::error("cvss", format!("The version doesn't match the vector ({version})"))
+                        .at(format!("{path}/version")),
+                );
+            }
+
+            let computed = base.score();
+            if let Some(stated) = metric["baseScore"].as_f64() {
+                if (stated - computed.value()).abs() > SCORE_TOLERANCE {
+                    findings.push(
+                        ValidationFinding::error(
+                            "cvss",
+                            format!(
+                                "The base score {stated} doesn't match the score of the vector ({})",
+                                computed.value()
+                            ),
+                        )
+                        .at(format!("{path}/baseScore")),
+                    );
+                }
+            }
+            let severity = Severity::from(computed.severity());
+            if let Some(stated) = metric["baseSeverity"].as_str() {
+                if !stated.eq_ignore_ascii_case(severity.as_str()) {
+                    findings.push(
+                        ValidationFinding::warning(
+                            "cvss",
+                            format!("The base severity {stated} doesn't match the severity of the vector ({severity})"),
+                        )
+                        .at(format!("{path}/baseSeverity")),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use vexination_model::prelude::FindingSeverity;
+
+    fn load(name: &str) -> Value {
+        serde_json::from_slice(&std::fs::read(format!("../testdata/{name}.json")).unwrap()).unwrap()
+    }
+
+    fn run(value: &Value) -> AdvisoryValidation {
+        validate(&serde_json::to_vec(value).unwrap())
+    }
+
+    fn rules(validation: &AdvisoryValidation) -> Vec<(&str, Option<&str>)> {
+        validation
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.as_str(), finding.path.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let validation = run(&load("rhsa-2023_3408"));
+        assert_eq!(validation.findings, vec![]);
+        assert!(validation.valid);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let validation = validate(b"{\"document\": ");
+        assert!(!validation.valid);
+        assert_eq!(rules(&validation), vec![("json", None)]);
+
+        let validation = validate(&std::fs::read("../testdata/openvex.json").unwrap());
+        assert_eq!(rules(&validation), vec![("format", None)]);
+    }
+
+    #[test]
+    fn test_mandatory_fields() {
+        let mut value = load("rhsa-2023_3408");
+        value["document"]["title"] = json!("");
+        value["document"]["publisher"]
+            .as_object_mut()
+            .unwrap()
+            .remove("namespace");
+        value["document"]["tracking"]["version"] = json!("2");
+
+        let validation = run(&value);
+        assert!(!validation.valid);
+        let rules = rules(&validation);
+        assert!(rules.contains(&("schema", None)));
+        assert!(rules.contains(&("mandatory-field", Some("/document/title"))));
+        assert!(rules.contains(&("mandatory-field", Some("/document/publisher/namespace"))));
+        assert!(rules.contains(&("tracking-version", Some("/document/tracking/version"))));
+    }
+
+    #[test]
+    fn test_product_references() {
+        let mut value = load("rhsa-2023_3408");
+        value["vulnerabilities"][0]["product_status"]["fixed"][0] = json!("unknown-product");
+        let relationships = value["product_tree"]["relationships"].as_array_mut().unwrap();
+        let duplicate = relationships[0].clone();
+        relationships.push(duplicate);
+        let duplicate = format!(
+            "/product_tree/relationships/{}/full_product_name/product_id",
+            relationships.len() - 1
+        );
+
+        let validation = run(&value);
+        assert!(!validation.valid);
+        assert_eq!(
+            rules(&validation),
+            vec![
+                ("product-reference", Some(duplicate.as_str())),
+                ("product-reference", Some("/vulnerabilities/0/product_status/fixed/0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_product_status() {
+        // the product is listed as both fixed and not affected
+        let validation = run(&load("rhsa-2023_1441"));
+        assert!(!validation.valid);
+        assert_eq!(
+            validation
+                .findings
+                .iter()
+                .map(|finding| finding.rule.as_str())
+                .collect::<HashSet<_>>(),
+            HashSet::from(["product-status"])
+        );
+    }
+
+    #[test]
+    fn test_cvss() {
+        let mut value = load("rhsa-2023_3408");
+        value["vulnerabilities"][0]["cve"] = json!("CVE-23-1");
+        let metric = &mut value["vulnerabilities"][0]["scores"][0]["cvss_v3"];
+        metric["baseScore"] = json!(9.8);
+        metric["baseSeverity"] = json!("CRITICAL");
+        value["vulnerabilities"][1]["scores"][0]["cvss_v3"]["vectorString"] = json!("CVSS:3.1/AV:X");
+
+        let validation = run(&value);
+        assert!(!validation.valid);
+        let rules = rules(&validation);
+        assert!(rules.contains(&("cve", Some("/vulnerabilities/0/cve"))));
+        assert!(rules.contains(&("cvss", Some("/vulnerabilities/0/scores/0/cvss_v3/baseScore"))));
+        assert!(rules.contains(&("cvss", Some("/vulnerabilities/0/scores/0/cvss_v3/baseSeverity"))));
+        assert!(rules.contains(&("cvss", Some("/vulnerabilities/1/scores/0/cvss_v3/vectorString"))));
+
+        let severity = validation
+            .findings
+            .iter()
+            .find(|finding| finding.path.as_deref() == Some("/vulnerabilities/0/scores/0/cvss_v3/baseSeverity"))
+            .unwrap();
+        assert_eq!(severity.severity, FindingSeverity::Warning);
+    }
+
+    #[test]
+    fn test_is_cve() {
+        assert!(is_cve("CVE-2023-0286"));
+        assert!(is_cve("CVE-2023-123456"));
+        assert!(!is_cve("CVE-2023-12"));
+        assert!(!is_cve("GHSA-2023-0286"));
+        assert!(!is_cve("CVE-2023-02a6"));
+    }
+}
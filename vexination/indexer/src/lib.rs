@@ -5,7 +5,7 @@ use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::block_in_place;
 use trustification_event_bus::EventBusConfig;
-use trustification_index::{IndexConfig, IndexStore, WriteIndex};
+use trustification_index::{require_unsharded, IndexConfig, IndexStore, WriteIndex};
 use trustification_indexer::{actix::configure, Indexer, IndexerStatus, ReindexMode};
 use trustification_infrastructure::health::checks::FailureRate;
 use trustification_infrastructure::{Infrastructure, InfrastructureConfig};
@@ -24,6 +24,9 @@ pub struct Run {
     #[arg(long = "failed-topic", default_value = "vex-failed")]
     pub failed_topic: String,
 
+    #[arg(long = "deleted-topic", default_value = "vex-deleted")]
+    pub deleted_topic: String,
+
     #[arg(long = "devmode", default_value_t = false)]
     pub devmode: bool,
 
@@ -55,7 +58,9 @@ impl Run {
                 "vexination-indexer",
                 |_context| async { Ok(()) },
                 |context| async move {
-                    let index: Box<dyn WriteIndex<Document = csaf::Csaf>> = Box::new(Index::new());
+                    require_unsharded(&self.index, "vexination")?;
+                    let index: Box<dyn WriteIndex<Document = csaf::Csaf>> =
+                        Box::new(Index::with_tokenizers(&self.index.tokenizers));
                     let index = block_in_place(|| {
                         IndexStore::new(&self.storage, &self.index, index, context.metrics.registry())
                     })?;
@@ -77,6 +82,7 @@ impl Run {
                         stored_topic: self.stored_topic.as_str(),
                         indexed_topic: self.indexed_topic.as_str(),
                         failed_topic: self.failed_topic.as_str(),
+                        deleted_topic: self.deleted_topic.as_str(),
                         sync_interval: self.index.sync_interval.into(),
                         status: s.clone(),
                         commands: command_receiver,
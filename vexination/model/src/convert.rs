@@ -0,0 +1,189 @@
+//! Conversion of CSAF documents of other versions to the CSAF 2.0 model.
+//!
+//! CSAF 2.1 documents are downgraded to 2.0: renamed fields are mapped to their 2.0 counterparts, and new values of
+//! enumerations to the closest 2.0 value. Information which has no 2.0 counterpart, like CVSS v4 metrics, is kept
+//! aside as [`Features`].
+
+use csaf::Csaf;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsafVersion {
+    V2_0,
+    V2_1,
+}
+
+impl CsafVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V2_0 => "2.0",
+            Self::V2_1 => "2.1",
+        }
+    }
+}
+
+impl CsafVersion {
+    /// Detect the version of a CSAF document, assuming 2.0 unless it states otherwise.
+    pub fn detect(value: &Value) -> Self {
+        match value["document"]["csaf_version"] == Self::V2_1.as_str() {
+            true => Self::V2_1,
+            false => Self::V2_0,
+        }
+    }
+}
+
+/// Information of a CSAF 2.1 document which can't be represented by the CSAF 2.0 model.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Features {
+    /// Features of each vulnerability, in the order of the vulnerabilities of the document
+    pub vulnerabilities: Vec<VulnerabilityFeatures>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VulnerabilityFeatures {
+    pub cvss_v4: Vec<CvssV4>,
+    /// All CWEs, of which the 2.0 model only keeps the first one
+    pub cwes: Vec<String>,
+}
+
+/// The base score of CVSS v4 metrics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CvssV4 {
+    pub score: f64,
+    /// The severity, in lowercase
+    pub severity: String,
+}
+
+/// Downgrade a CSAF document to 2.0 in place, returning its original version and the information which can't be
+/// downgraded.
+pub fn downgrade(value: &mut Value) -> (CsafVersion, Features) {
+    let mut features = Features::default();
+    let version = CsafVersion::detect(value);
+    if version == CsafVersion::V2_0 {
+        return (version, features);
+    }
+
+    value["document"]["csaf_version"] = CsafVersion::V2_0.as_str().into();
+    map_value(
+        value.pointer_mut("/document/publisher/category"),
+        |category| match category {
+            "multiplier" => Some("other"),
+            _ => None,
+        },
+    );
+    map_value(
+        value.pointer_mut("/document/distribution/tlp/label"),
+        |label| match label {
+            "CLEAR" => Some("WHITE"),
+            "AMBER+STRICT" => Some("AMBER"),
+            _ => None,
+        },
+    );
+
+    for vuln in value["vulnerabilities"].as_array_mut().into_iter().flatten() {
+        let Some(vuln) = vuln.as_object_mut() else {
+            continue;
+        };
+        features.vulnerabilities.push(downgrade_vulnerability(vuln));
+    }
+
+    (version, features)
+}
+
+/// Parse a CSAF document of any supported version into the CSAF 2.0 model.
+///
+/// Documents are stored as they were published, so all consumers of stored documents must parse them this way.
+pub fn parse_csaf(data: &[u8]) -> Result<Csaf, serde_json::Error> {
+    let mut value: Value = serde_json::from_slice(data)?;
+    downgrade(&mut value);
+    serde_json::from_value(value)
+}
+
+/// Downgrade a CSAF 2.1 vulnerability to 2.0, returning the information which can't be downgraded.
+fn downgrade_vulnerability(vuln: &mut Map<String, Value>) -> VulnerabilityFeatures {
+    let mut features = VulnerabilityFeatures::default();
+
+    if let Some(Value::Array(cwes)) = vuln.remove("cwes") {
+        features.cwes = cwes
+            .iter()
+            .filter_map(|cwe| cwe["id"].as_str().map(|id| id.to_string()))
+            .collect();
+        if let Some(cwe) = cwes.into_iter().next() {
+            vuln.insert("cwe".to_string(), cwe);
+        }
+    }
+
+    if let Some(date) = vuln.remove("disclosure_date") {
+        vuln.insert("release_date".to_string(), date);
+    }
+
+    if let Some(Value::Array(metrics)) = vuln.remove("metrics") {
+        let mut scores = Vec::new();
+        for metric in metrics {
+            let content = &metric["content"];
+            if let (Some(score), Some(severity)) = (
+                content["cvss_v4"]["baseScore"].as_f64(),
+                content["cvss_v4"]["baseSeverity"].as_str(),
+            ) {
+                features.cvss_v4.push(CvssV4 {
+                    score,
+                    severity: severity.to_lowercase(),
+                });
+            }
+
+            let mut score = Map::new();
+            score.insert("products".to_string(), metric["products"].clone());
+            for version in ["cvss_v2", "cvss_v3"] {
+                if !content[version].is_null() {
+                    score.insert(version.to_string(), content[version].clone());
+                }
+            }
+            // a score of the 2.0 model requires at least one CVSS version
+            if score.len() > 1 {
+                scores.push(Value::Object(score));
+            }
+        }
+        if !scores.is_empty() {
+            vuln.insert("scores".to_string(), scores.into());
+        }
+    }
+
+    for remediation in vuln
+        .get_mut("remediations")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        map_value(remediation.get_mut("category"), |category| match category {
+            "optional_patch" => Some("vendor_fix"),
+            "fix_planned" => Some("none_available"),
+            _ => None,
+        });
+    }
+
+    features
+}
+
+/// Replace a string value, if the mapping provides a replacement.
+fn map_value(value: Option<&mut Value>, f: impl FnOnce(&str) -> Option<&'static str>) {
+    if let Some(value) = value {
+        if let Some(replacement) = value.as_str().and_then(f) {
+            *value = replacement.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_csaf() {
+        let csaf = parse_csaf(include_bytes!("../../testdata/csaf-2.1.json")).unwrap();
+        assert_eq!(csaf.document.tracking.id, "EXAMPLE-2024-0001");
+        assert_eq!(csaf.vulnerabilities.unwrap()[0].cwe.as_ref().unwrap().id, "CWE-787");
+
+        let csaf = parse_csaf(include_bytes!("../../testdata/rhsa-2023_1441.json")).unwrap();
+        assert_eq!(csaf.document.tracking.id, "RHSA-2023:1441");
+    }
+}
@@ -1,5 +1,10 @@
+pub mod convert;
 pub mod search;
+pub mod severity;
+pub mod validation;
 
 pub mod prelude {
     pub use crate::search::*;
+    pub use crate::severity::*;
+    pub use crate::validation::*;
 }
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde_json::Value;
 use sikula::prelude::*;
+use trustification_api::search::FacetCount;
 use utoipa::ToSchema;
 
-#[derive(Clone, Debug, PartialEq, Search)]
+#[derive(Clone, Debug, PartialEq, Search, strum::VariantNames)]
+#[strum(serialize_all = "camelCase")]
 pub enum Vulnerabilities<'a> {
     #[search(default)]
     Id(Primary<'a>),
@@ -15,6 +17,9 @@ pub enum Vulnerabilities<'a> {
     #[search(default)]
     Description(Primary<'a>),
     Status(&'a str),
+    Publisher(&'a str),
+    Category(&'a str),
+    CsafVersion(&'a str),
     #[search(sort)]
     Severity(&'a str),
     Cvss(PartialOrdered<f64>),
@@ -26,6 +31,7 @@ pub enum Vulnerabilities<'a> {
     Affected(Primary<'a>),
     #[search(scope)]
     NotAffected(Primary<'a>),
+    Affects(&'a str),
     #[search]
     Initial(Ordered<time::OffsetDateTime>),
     #[search(sort)]
@@ -42,7 +48,7 @@ pub enum Vulnerabilities<'a> {
 }
 
 /// A document returned from the search index for every match.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, ToSchema)]
 pub struct SearchDocument {
     /// Advisory identifier
     pub advisory_id: String,
@@ -51,6 +57,10 @@ pub struct SearchDocument {
     /// Advisory release date in RFC3339 format
     #[schema(value_type = String)]
     pub advisory_date: time::OffsetDateTime,
+    /// Advisory initial release date
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub advisory_initial_date: Option<time::OffsetDateTime>,
     /// Snippet highlighting part of description that matched
     pub advisory_snippet: String,
     /// Advisory description
@@ -63,10 +73,39 @@ pub struct SearchDocument {
     pub cvss_max: Option<f64>,
     /// Number of severities by level
     pub cve_severity_count: HashMap<String, u64>,
+    /// Public release dates of the vulnerabilities, by CVE identifier
+    #[serde(default)]
+    #[schema(value_type = HashMap<String, String>)]
+    pub cve_release_dates: HashMap<String, time::OffsetDateTime>,
+    /// Remediations of the vulnerabilities
+    #[serde(default)]
+    pub remediations: Vec<AdvisoryRemediation>,
+}
+
+/// A remediation of a vulnerability, as stated by an advisory.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize, ToSchema)]
+pub struct AdvisoryRemediation {
+    /// The CVE identifier of the vulnerability, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cve: Option<String>,
+    /// The category of the remediation, like `vendor_fix` or `workaround`
+    pub category: String,
+    /// How to remediate
+    pub details: String,
+    /// A URL providing more information, like the download of a fix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Package URLs or CPEs of the products the remediation applies to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub products: Vec<String>,
+    /// Versions of the packages fixing the vulnerability, for vendor fixes: the version of their package URL, or the
+    /// package itself if it has none
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixed_versions: Vec<String>,
 }
 
 /// The hit describes the document, its score and optionally an explanation of why that score was given.
-#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct SearchHit {
     /// The document that was matched.
     pub document: SearchDocument,
@@ -87,4 +126,10 @@ pub struct SearchResult {
     pub total: usize,
     /// Documents matched up to max requested
     pub result: Vec<SearchHit>,
+    /// Bucket counts of the requested facets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub facets: BTreeMap<String, Vec<FacetCount>>,
+    /// Cursor for retrieving the next page, if results were requested using a cursor and more are available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
 }
@@ -0,0 +1,132 @@
+//! Normalization of severities.
+//!
+//! Publishers report severities in different ways: as CVSS vectors or scores, as the qualitative CVSS ratings, or as
+//! labels of their own, like the "important" and "moderate" of Red Hat. All of them are mapped to a canonical
+//! [`Severity`] when indexing, so that filtering by severity behaves the same for all advisories.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// The canonical severity, following the qualitative ratings of CVSS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Normalize a reported severity.
+    ///
+    /// The value can be a CVSS v3 vector, a score, or a label. Returns `None` if the value isn't understood.
+    pub fn normalize(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.starts_with("CVSS:3") {
+            return cvss::v3::Base::from_str(value)
+                .ok()
+                .map(|base| Self::from_score(base.score().value()));
+        }
+        if let Ok(score) = value.parse::<f64>() {
+            return (0.0..=10.0).contains(&score).then(|| Self::from_score(score));
+        }
+
+        match value.to_ascii_lowercase().as_str() {
+            "none" | "informational" | "info" | "negligible" => Some(Self::None),
+            "low" | "minor" => Some(Self::Low),
+            "medium" | "moderate" => Some(Self::Medium),
+            "high" | "important" | "severe" | "major" => Some(Self::High),
+            "critical" | "urgent" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    /// The severity of a CVSS score, as defined by the CVSS v3 and v4 specifications.
+    pub fn from_score(score: f64) -> Self {
+        if score >= 9.0 {
+            Self::Critical
+        } else if score >= 7.0 {
+            Self::High
+        } else if score >= 4.0 {
+            Self::Medium
+        } else if score > 0.0 {
+            Self::Low
+        } else {
+            Self::None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+
+    /// A score in the range of `0.0` to `1.0`, for sorting by severity.
+    pub fn rank(&self) -> f64 {
+        match self {
+            Self::None => 0.0,
+            Self::Low => 0.25,
+            Self::Medium => 0.5,
+            Self::High => 0.75,
+            Self::Critical => 1.0,
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<cvss::Severity> for Severity {
+    fn from(value: cvss::Severity) -> Self {
+        match value {
+            cvss::Severity::None => Self::None,
+            cvss::Severity::Low => Self::Low,
+            cvss::Severity::Medium => Self::Medium,
+            cvss::Severity::High => Self::High,
+            cvss::Severity::Critical => Self::Critical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(Severity::normalize("Important"), Some(Severity::High));
+        assert_eq!(Severity::normalize("moderate"), Some(Severity::Medium));
+        assert_eq!(Severity::normalize("CRITICAL"), Some(Severity::Critical));
+        assert_eq!(Severity::normalize("7.5"), Some(Severity::High));
+        assert_eq!(Severity::normalize("0.0"), Some(Severity::None));
+        assert_eq!(
+            Severity::normalize("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(Severity::Critical)
+        );
+        assert_eq!(Severity::normalize("11"), None);
+        assert_eq!(Severity::normalize("CVSS:3.1/AV:N"), None);
+        assert_eq!(Severity::normalize("whatever"), None);
+    }
+
+    #[test]
+    fn test_from_score() {
+        assert_eq!(Severity::from_score(10.0), Severity::Critical);
+        assert_eq!(Severity::from_score(9.0), Severity::Critical);
+        assert_eq!(Severity::from_score(8.9), Severity::High);
+        assert_eq!(Severity::from_score(4.0), Severity::Medium);
+        assert_eq!(Severity::from_score(0.1), Severity::Low);
+        assert_eq!(Severity::from_score(0.0), Severity::None);
+    }
+}
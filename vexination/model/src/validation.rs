@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The result of validating an advisory, without storing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct AdvisoryValidation {
+    /// The document has no errors
+    pub valid: bool,
+    /// Problems found in the document
+    pub findings: Vec<ValidationFinding>,
+}
+
+impl AdvisoryValidation {
+    /// Create the validation result of the findings.
+    pub fn new(findings: Vec<ValidationFinding>) -> Self {
+        Self {
+            valid: !findings
+                .iter()
+                .any(|finding| finding.severity == FindingSeverity::Error),
+            findings,
+        }
+    }
+}
+
+/// A problem found in an advisory.
+#[derive(Clone, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+pub struct ValidationFinding {
+    pub severity: FindingSeverity,
+    /// The rule being violated, like `schema`, `mandatory-field` or `cvss`
+    pub rule: String,
+    pub message: String,
+    /// The JSON pointer to the offending value, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl ValidationFinding {
+    pub fn error(rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: FindingSeverity::Error,
+            rule: rule.to_string(),
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    pub fn warning(rule: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: FindingSeverity::Warning,
+            ..Self::error(rule, message)
+        }
+    }
+
+    /// Set the JSON pointer to the offending value.
+    pub fn at(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ToSchema, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    /// The document violates the CSAF specification
+    Error,
+    /// The document is valid, but may not be processed as expected
+    Warning,
+}